@@ -1,67 +1,72 @@
 use cosmwasm_std::{
-    entry_point, from_json, to_json_binary, to_json_vec, BankMsg, Binary, Coin, Deps, DepsMut,
-    Env, MessageInfo, Response, StdError, StdResult, Uint128, QueryRequest, GrpcQuery,
-    ContractResult, SystemResult, WasmMsg, WasmQuery,
+    entry_point, from_json, instantiate2_address, to_json_binary, to_json_vec, BankMsg, Binary,
+    Coin, CosmosMsg, Deps, DepsMut, Env, Event, MessageInfo, Order, Response, StdError, StdResult,
+    Uint128, QueryRequest, GrpcQuery, ContractResult, SystemResult, Storage, WasmMsg, WasmQuery, Addr, to_json_string,
+    Timestamp, Int128, Reply, SubMsg, AnyMsg,
 };
-use prost::Message;
+use std::collections::BTreeMap;
 use cw2::{get_contract_version, set_contract_version};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use external_address::ExternalAddress;
+use gonka_cw_controllers::ownership::ensure_owner;
+use gonka_cw_controllers::pause::{ensure_not_paused, set_paused};
 
 use crate::error::ContractError;
+use crate::export::export_state;
+use crate::pagination::{next_start_after, page_limit, start_bound};
+use crate::proto::{
+    self, MsgExecuteContract, QueryCommunitySaleParamsRequest,
+    QueryCommunitySaleParamsResponse, QueryModuleVersionsRequest, QueryModuleVersionsResponse,
+    QueryParticipantAttestationRequest, QueryParticipantAttestationResponse,
+    QueryGetCurrentEpochRequest, QueryGetCurrentEpochResponse, QueryRandomSeedRequest, QueryRandomSeedResponse, QueryTotalSupplyRequest, QueryTotalSupplyResponse,
+    QueryValidateWrappedTokenForTradeRequest,
+    QueryValidateWrappedTokenForTradeResponse,
+};
 use crate::msg::{
-    ConfigResponse, Cw20ReceiveMsg, ExecuteMsg, InstantiateMsg,
-    NativeBalanceResponse, PurchaseTokenMsg, QueryMsg, TestBridgeValidationResponse,
-    TokenCalculationResponse, BlockHeightResponse, ApprovedTokensForTradeJson, ApprovedTokenJson,
+    CircuitBreakerResponse, ConfigResponse, Cw20ReceiveMsg, ExecuteMsg, InstantiateMsg, SudoMsg,
+    NativeBalanceResponse, PurchaseTokenMsg, PURCHASE_MSG_VERSION, QueryMsg, ContractInfoResponse, CurrentPriceResponse,
+    LotteryResponse, LotteryEntryResponse, MigrationStatusResponse, PurchaseNonceResponse, RaiseCapacityResponse,
+    RoundInfo, RoundsResponse, EpochSaleInfo, EpochSaleParams, EpochScheduleResponse, SubscriptionsResponse, ReserveEntry, ReservesResponse, DepositEntry, DepositsResponse, SaleParams, SaleResponse, SalesResponse, SignedPurchaseIntent,
+    TokenCalculationResponse, UsdCalculationResponse, PurchaseExactOut,
+    PayoutDenomInfo, PayoutDenomsResponse, VestingContractsResponse, VestingExecuteMsg, VestingInstantiateMsg,
+    PurchaseLimitsResponse, WhitelistEntryResponse, WhitelistResponse, QueuedActionsResponse,
+    StatusResponse, TokenPriceResponse, TokenPricesResponse, BuyerPriceResponse, BuyerPricesResponse, ModuleVersionResponse,
+    ReceiptMetadata, ReceiptNftExecuteMsg, AuditLogResponse, PriceHistoryResponse, DebugGrpcResponse, DecodeAttempt, ExportStateResponse, StateSection,
+    UnbridgedProceedsEntry, UnbridgedProceedsResponse, StreamedProceedsEntry, StreamedProceedsResponse, CanPurchaseResponse, PoolResponse, PoolsResponse, BuyerPoolsResponse,
 };
-use crate::state::{calculate_tokens_for_usd, Config, CONFIG};
-
-#[derive(Clone, PartialEq, Message)]
-pub struct QueryValidateWrappedTokenForTradeRequest {
-    #[prost(string, tag = "1")]
-    pub contract_address: String,
-}
-
-#[derive(Clone, PartialEq, Message)]
-pub struct QueryValidateWrappedTokenForTradeResponse {
-    #[prost(bool, tag = "1")]
-    pub is_valid: bool,
-}
-
-#[derive(Clone, PartialEq, Message, serde::Serialize)]
-pub struct BridgeTradeApprovedToken {
-    #[prost(string, tag = "1")]
-    pub chain_id: String,
-    #[prost(string, tag = "2")]
-    pub contract_address: String,
-}
-
-#[derive(Clone, PartialEq, Message, serde::Serialize)]
-pub struct QueryApprovedTokensForTradeResponseProto {
-    #[prost(message, repeated, tag = "1")]
-    pub approved_tokens: ::prost::alloc::vec::Vec<BridgeTradeApprovedToken>,
-}
-
-#[derive(Clone, PartialEq, Message)]
-pub struct EmptyRequest {}
-
-#[derive(Clone, PartialEq, Message)]
-pub struct QueryTotalSupplyRequest {}
-
-#[derive(Clone, PartialEq, Message)]
-pub struct QueryTotalSupplyResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub supply: ::prost::alloc::vec::Vec<CoinProto>,
-}
-
-#[derive(Clone, PartialEq, Message)]
-pub struct CoinProto {
-    #[prost(string, tag = "1")]
-    pub denom: String,
-    #[prost(string, tag = "2")]
-    pub amount: String,
-}
+#[cfg(feature = "debug")]
+use crate::msg::{ApprovedTokenJson, ApprovedTokensForTradeJson, BlockHeightResponse, TestBridgeValidationResponse};
+use crate::state::{
+    average_price_usd, calculate_tokens_for_usd, calculate_usd_for_tokens, split_usd_payment, CircuitBreaker, Config, PurchaseLimits, PurchaseLimitsSource, VestingConfig,
+    WhitelistEntry, CONFIG, MAX_WHITELIST_BATCH, TOKENS_SOLD_BY_DENOM,
+    RAISED_BY_TOKEN, PURCHASERS, UNIQUE_BUYERS, REFUNDED_USD, FinalReport, TokenRaised, FINAL_REPORT,
+    VESTING_CONTRACTS, VESTING_UNLOCKS, WHITELIST, WHITELIST_IMPORT, QueuedAction, TimelockedAction, MIN_TIMELOCK_DELAY_BLOCKS,
+    NEXT_TIMELOCK_ID, TIMELOCK_ETA, TIMELOCK_QUEUE, MAX_CORRECTION_REASON_LEN, LAST_PURCHASE_HEIGHT, TOKEN_PRICES, BUYER_PRICES,
+    purchase_commitment_hash, PurchaseCommitment, PURCHASE_COMMITMENTS, NEXT_RECEIPT_ID,
+    AuditEntry, AUDIT_LOG, NEXT_AUDIT_ID, PriceChange, PRICE_HISTORY, NEXT_PRICE_CHANGE_ID, ValidatorKickback, MAX_BPS, Lottery, LotteryEntry, LOTTERY,
+    LOTTERY_ENTRIES, MAX_LOTTERY_ENTRIES, LOTTERY_CLAIM_BLOCKS, DEFAULT_PAYOUT_DECIMALS, MAX_PAYOUT_DECIMALS, purchase_intent_hash,
+    PurchaseIntentSignDoc, PURCHASE_NONCES, Sale, NEXT_SALE_ID, SALES, SALE_BUYERS, Reservation, ReservationPayment,
+    InventoryPool, POOLS, POOL_BUYERS, POOL_CURSOR, MAX_POOLS, MAX_POOL_LABEL_LEN, NameBindings, NAMES_RESOLVED_AT,
+    CoApproval, PurchaseApproval, PURCHASE_APPROVALS, EpochSale, EPOCH_SCHEDULE, EPOCH_RAISED, MAX_EPOCH_SCHEDULE_BATCH,
+    Subscription, SUBSCRIPTIONS, NEXT_SUBSCRIPTION_ID, SUBSCRIPTION_DUE, MIN_SUBSCRIPTION_INTERVAL_BLOCKS,
+    MAX_SUBSCRIPTIONS_PER_BUYER, MAX_SUBSCRIPTION_SKIPS, BUYER_SUBSCRIPTIONS,
+    Quote, QUOTES, NEXT_QUOTE_ID, QUOTE_EXPIRIES, QUOTE_VALIDITY_BLOCKS, MAX_TRAILING_WINDOWS, WINDOW_VOLUME,
+    ProceedsBridge, UNBRIDGED_PROCEEDS, ProceedsStream, STREAMED_PROCEEDS, MAX_RESERVATION_BLOCKS, NEXT_RESERVATION_ID, RESERVATIONS, RESERVATION_EXPIRIES, RESERVES, RESERVE_LOTTERY, RESERVE_RESERVATIONS, RESERVE_COMMITMENTS, sale_reserve, PURCHASE_COMMITMENT_DEPOSIT, PURCHASE_COMMITMENT_EXPIRIES, DEPOSITS, SaleRound, MAX_ROUNDS, NEXT_ROUND_ID, ROUNDS, ConfigV1, MigrationProgress, MigrationStage, MIGRATION,
+};
+use cw_storage_plus::Bound;
 
 const CONTRACT_NAME: &str = "community-sale";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Version of the public message/response JSON shapes reported by `ContractInfo`
+const SCHEMA_VERSION: u32 = 1;
+
+const DEFAULT_CRANK_LIMIT: u32 = 10;
+const MAX_CRANK_LIMIT: u32 = 50;
+
+/// Reply to a failed proceeds bridge hop
+const PROCEEDS_BRIDGE_REPLY_ID: u64 = 1;
+const VESTING_RELEASE_REPLY_ID: u64 = 2;
 
 fn validate_wrapped_token_for_trade(deps: Deps, token_identifier: &str) -> Result<bool, ContractError> {
     let contract_address = token_identifier
@@ -73,7 +78,7 @@ fn validate_wrapped_token_for_trade(deps: Deps, token_identifier: &str) -> Resul
     };
     let response: QueryValidateWrappedTokenForTradeResponse = query_proto(
         deps,
-        "/inference.inference.Query/ValidateWrappedTokenForTrade",
+        proto::VALIDATE_WRAPPED_TOKEN_FOR_TRADE_PATH,
         &request,
     )
     .map_err(ContractError::Std)?;
@@ -81,11 +86,85 @@ fn validate_wrapped_token_for_trade(deps: Deps, token_identifier: &str) -> Resul
     Ok(response.is_valid)
 }
 
+/// Rejects buyers without a valid participant attestation; fails closed if the query errors
+fn ensure_attested(deps: Deps, buyer: &str) -> Result<(), ContractError> {
+    let response: QueryParticipantAttestationResponse = query_proto(
+        deps,
+        proto::PARTICIPANT_ATTESTATION_PATH,
+        &QueryParticipantAttestationRequest {
+            address: buyer.to_string(),
+        },
+    )
+    .map_err(ContractError::Std)?;
+    if !response.valid {
+        return Err(ContractError::AttestationMissing {
+            buyer: buyer.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn query_current_epoch(deps: Deps) -> StdResult<u64> {
+    let response: QueryGetCurrentEpochResponse =
+        query_proto(deps, proto::CURRENT_EPOCH_PATH, &QueryGetCurrentEpochRequest {})?;
+    Ok(response.epoch)
+}
+
+/// The current inference epoch and its scheduled terms, if it has any. The chain is not queried
+/// while nothing is scheduled; once something is, a failed query fails the purchase, since the
+/// epoch's cap cannot be enforced without it.
+fn current_epoch_sale(deps: Deps) -> StdResult<Option<(u64, EpochSale)>> {
+    if EPOCH_SCHEDULE.is_empty(deps.storage) {
+        return Ok(None);
+    }
+    let epoch = query_current_epoch(deps)?;
+    Ok(EPOCH_SCHEDULE.may_load(deps.storage, epoch)?.map(|sale| (epoch, sale)))
+}
+
+/// Checks a purchase against the current epoch's cap. Returns the epoch and its raised total
+/// including the purchase, for the caller to save once the purchase goes through.
+fn check_epoch_cap(deps: Deps, usd_amount: Uint128) -> Result<Option<(u64, Uint128)>, ContractError> {
+    let Some((epoch, sale)) = current_epoch_sale(deps)? else {
+        return Ok(None);
+    };
+    let raised = EPOCH_RAISED.may_load(deps.storage, epoch)?.unwrap_or_default();
+    if let Some(cap) = sale.cap_usd {
+        let remaining = cap.saturating_sub(raised);
+        if usd_amount > remaining {
+            return Err(ContractError::EpochCapExceeded {
+                epoch,
+                remaining_usd: remaining.u128(),
+                requested_usd: usd_amount.u128(),
+            });
+        }
+    }
+    let raised = raised
+        .checked_add(usd_amount)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    Ok(Some((epoch, raised)))
+}
+
+/// Consensus version of the inference module as reported by x/upgrade
+fn query_module_version(deps: Deps) -> StdResult<Option<u64>> {
+    let response: QueryModuleVersionsResponse = query_proto(
+        deps,
+        proto::MODULE_VERSIONS_PATH,
+        &QueryModuleVersionsRequest {
+            module_name: proto::INFERENCE_MODULE_NAME.to_string(),
+        },
+    )?;
+    Ok(response
+        .module_versions
+        .into_iter()
+        .find(|m| m.name == proto::INFERENCE_MODULE_NAME)
+        .map(|m| m.version))
+}
+
 fn get_native_denom(deps: Deps) -> Result<String, ContractError> {
     let request = QueryTotalSupplyRequest {};
     match query_proto::<QueryTotalSupplyRequest, QueryTotalSupplyResponse>(
         deps,
-        "/cosmos.bank.v1beta1.Query/TotalSupply",
+        proto::TOTAL_SUPPLY_PATH,
         &request,
     ) {
         Ok(response) => {
@@ -100,6 +179,66 @@ fn get_native_denom(deps: Deps) -> Result<String, ContractError> {
     }
 }
 
+fn parse_param_amount(value: &str) -> Result<Uint128, ContractError> {
+    if value.is_empty() {
+        return Ok(Uint128::zero());
+    }
+    value
+        .parse::<u128>()
+        .map(Uint128::from)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("parse param {}: {}", value, e))))
+}
+
+/// Read sale limits from inference module params so governance can tune them directly
+fn query_chain_purchase_limits(deps: Deps) -> Result<PurchaseLimits, ContractError> {
+    let response: QueryCommunitySaleParamsResponse = query_proto(
+        deps,
+        proto::COMMUNITY_SALE_PARAMS_PATH,
+        &QueryCommunitySaleParamsRequest {},
+    )
+    .map_err(ContractError::Std)?;
+
+    Ok(PurchaseLimits {
+        min_purchase_usd: parse_param_amount(&response.min_purchase_usd)?,
+        max_purchase_usd: parse_param_amount(&response.max_purchase_usd)?,
+        min_price_usd: parse_param_amount(&response.min_price_usd)?,
+        max_price_usd: parse_param_amount(&response.max_price_usd)?,
+    })
+}
+
+fn resolve_purchase_limits(deps: Deps, config: &Config) -> Result<Option<PurchaseLimits>, ContractError> {
+    match &config.purchase_limits {
+        None => Ok(None),
+        Some(PurchaseLimitsSource::Config(limits)) => Ok(Some(limits.clone())),
+        Some(PurchaseLimitsSource::ChainParams {}) => query_chain_purchase_limits(deps).map(Some),
+    }
+}
+
+fn check_purchase_limits(
+    limits: &PurchaseLimits,
+    usd_amount: Uint128,
+    price_usd: Uint128,
+) -> Result<(), ContractError> {
+    let below = |bound: Uint128, value: Uint128| !bound.is_zero() && value < bound;
+    let above = |bound: Uint128, value: Uint128| !bound.is_zero() && value > bound;
+
+    if below(limits.min_purchase_usd, usd_amount) || above(limits.max_purchase_usd, usd_amount) {
+        return Err(ContractError::PurchaseOutOfLimits {
+            amount: usd_amount.u128(),
+            min: limits.min_purchase_usd.u128(),
+            max: limits.max_purchase_usd.u128(),
+        });
+    }
+    if below(limits.min_price_usd, price_usd) || above(limits.max_price_usd, price_usd) {
+        return Err(ContractError::PriceOutOfBounds {
+            price: price_usd.u128(),
+            min: limits.min_price_usd.u128(),
+            max: limits.max_price_usd.u128(),
+        });
+    }
+    Ok(())
+}
+
 fn create_cw20_transfer_msg(
     cw20_contract: String,
     recipient: String,
@@ -116,6 +255,222 @@ fn create_cw20_transfer_msg(
     })
 }
 
+fn create_cw20_transfer_from_msg(
+    cw20_contract: String,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<WasmMsg, ContractError> {
+    let transfer_from_msg_str = format!(
+        r#"{{"transfer_from":{{"owner":"{}","recipient":"{}","amount":"{}"}}}}"#,
+        owner, recipient, amount
+    );
+    Ok(WasmMsg::Execute {
+        contract_addr: cw20_contract,
+        msg: Binary::from(transfer_from_msg_str.as_bytes()),
+        funds: vec![],
+    })
+}
+
+/// Sends the validator kickback's share of a payment in `cw20_contract` to its recipient as an
+/// Any-encoded MsgExecuteContract: a transfer from our balance, or with `owner`, a transfer_from
+/// pulling straight from the buyer's allowance
+fn create_kickback_msg(
+    env: &Env,
+    kickback: &ValidatorKickback,
+    cw20_contract: String,
+    owner: Option<String>,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    let transfer = match owner {
+        Some(owner) => create_cw20_transfer_from_msg(cw20_contract, owner, kickback.recipient.clone(), amount)?,
+        None => create_cw20_transfer_msg(cw20_contract, kickback.recipient.clone(), amount)?,
+    };
+    let WasmMsg::Execute { contract_addr, msg, .. } = transfer else {
+        unreachable!("cw20 transfers are executes")
+    };
+    let value = MsgExecuteContract {
+        sender: env.contract.address.to_string(),
+        contract: contract_addr,
+        msg: msg.to_vec(),
+        funds: vec![],
+    };
+    Ok(CosmosMsg::Any(AnyMsg {
+        type_url: proto::MSG_EXECUTE_CONTRACT_TYPE_URL.to_string(),
+        value: Binary::from(prost::Message::encode_to_vec(&value)),
+    }))
+}
+
+/// Payload of a proceeds bridge hop, read back by `reply` if the hop fails
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BridgedProceeds {
+    cw20_contract: String,
+    amount: Uint128,
+}
+
+/// Sends proceeds to `recipient`, or with proceeds bridging on, withdraws them through the
+/// payment token to the external treasury. A failed hop is caught by `reply` and the CW20 stays
+/// here as unbridged proceeds, so it never fails the purchase that collected them.
+fn forward_proceeds(
+    config: &Config,
+    cw20_contract: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<SubMsg, ContractError> {
+    let Some(bridge) = &config.proceeds_bridge else {
+        return Ok(SubMsg::new(create_cw20_transfer_msg(cw20_contract, recipient, amount)?));
+    };
+    let withdraw_msg_str = format!(
+        r#"{{"withdraw":{{"amount":"{}","destination_address":"{}"}}}}"#,
+        amount, bridge.destination
+    );
+    let payload = to_json_binary(&BridgedProceeds { cw20_contract: cw20_contract.clone(), amount })?;
+    let withdraw = WasmMsg::Execute {
+        contract_addr: cw20_contract,
+        msg: Binary::from(withdraw_msg_str.as_bytes()),
+        funds: vec![],
+    };
+    Ok(SubMsg::reply_on_error(withdraw, PROCEEDS_BRIDGE_REPLY_ID).with_payload(payload))
+}
+
+/// Forwards proceeds to `recipient` now, or while proceeds streaming is on, holds them here for
+/// the crank's next scheduled release
+fn route_proceeds(
+    storage: &mut dyn Storage,
+    config: &Config,
+    response: Response,
+    cw20_contract: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if config.proceeds_stream.is_none() {
+        return Ok(response.add_submessage(forward_proceeds(config, cw20_contract, recipient, amount)?));
+    }
+    STREAMED_PROCEEDS.update(storage, (&cw20_contract, &recipient), |held| -> Result<_, ContractError> {
+        held.unwrap_or_default()
+            .checked_add(amount)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+    })?;
+    Ok(response.add_attribute("streamed_proceeds", amount))
+}
+
+/// CW20 balance of `owner` and the allowance it granted this contract (zero once expired)
+fn query_cw20_funding(deps: Deps, env: &Env, cw20_contract: &str, owner: &str) -> Result<(Uint128, Uint128), ContractError> {
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Cw20QueryMsg {
+        Balance { address: String },
+        Allowance { owner: String, spender: String },
+    }
+    #[derive(serde::Deserialize)]
+    struct BalanceResponse {
+        balance: Uint128,
+    }
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum Expiration {
+        AtHeight(u64),
+        AtTime(Timestamp),
+        Never {},
+    }
+    #[derive(serde::Deserialize)]
+    struct AllowanceResponse {
+        allowance: Uint128,
+        expires: Expiration,
+    }
+
+    let query = |msg: &Cw20QueryMsg| -> Result<QueryRequest, ContractError> {
+        Ok(QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: cw20_contract.to_string(),
+            msg: to_json_binary(msg).map_err(|e| ContractError::Std(StdError::msg(format!("serialize: {}", e))))?,
+        }))
+    };
+    let balance: BalanceResponse = deps
+        .querier
+        .query(&query(&Cw20QueryMsg::Balance { address: owner.to_string() })?)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("query cw20 balance: {}", e))))?;
+    let allowance: AllowanceResponse = deps
+        .querier
+        .query(&query(&Cw20QueryMsg::Allowance {
+            owner: owner.to_string(),
+            spender: env.contract.address.to_string(),
+        })?)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("query cw20 allowance: {}", e))))?;
+    let expired = match allowance.expires {
+        Expiration::AtHeight(height) => env.block.height >= height,
+        Expiration::AtTime(time) => env.block.time >= time,
+        Expiration::Never {} => false,
+    };
+    let allowance = if expired { Uint128::zero() } else { allowance.allowance };
+    Ok((balance.balance, allowance))
+}
+
+/// Address `name` points at in the name registry
+fn resolve_registry_name(deps: Deps, registry: &str, name: &str) -> Result<String, ContractError> {
+    #[derive(serde::Serialize)]
+    #[serde(rename_all = "snake_case")]
+    enum NameRegistryQueryMsg {
+        Resolve { name: String },
+    }
+    #[derive(serde::Deserialize)]
+    struct ResolveResponse {
+        address: String,
+    }
+
+    let request = QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: registry.to_string(),
+        msg: to_json_binary(&NameRegistryQueryMsg::Resolve { name: name.to_string() })?,
+    });
+    let response: ResolveResponse = deps
+        .querier
+        .query(&request)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("resolve {}: {}", name, e))))?;
+    Ok(response.address)
+}
+
+/// Resolves the bound names into `config`. Returns false if any name failed to resolve, in
+/// which case it keeps its previous address.
+fn resolve_bound_names(deps: Deps, config: &mut Config, bindings: &NameBindings) -> bool {
+    let mut resolved = true;
+    if let Some(name) = &bindings.treasury {
+        match resolve_registry_name(deps, &bindings.registry, name) {
+            Ok(address) => config.treasury = Some(address),
+            Err(_) => resolved = false,
+        }
+    }
+    if let Some(name) = &bindings.buyer {
+        match resolve_registry_name(deps, &bindings.registry, name) {
+            Ok(address) => config.buyer = address,
+            Err(_) => resolved = false,
+        }
+    }
+    resolved
+}
+
+/// Refreshes the treasury and designated buyer from the name registry once the cached
+/// resolution is `cache_blocks` old. A name the registry cannot resolve keeps its last address
+/// and is retried on the next execution.
+fn sync_bound_names(deps: DepsMut, env: &Env) -> Result<(), ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let Some(bindings) = config.name_bindings.clone() else {
+        return Ok(());
+    };
+    if let Some(resolved_at) = NAMES_RESOLVED_AT.may_load(deps.storage)? {
+        if env.block.height < resolved_at.saturating_add(bindings.cache_blocks) {
+            return Ok(());
+        }
+    }
+    let (old_treasury, old_buyer) = (config.treasury.clone(), config.buyer.clone());
+    let resolved = resolve_bound_names(deps.as_ref(), &mut config, &bindings);
+    if config.treasury != old_treasury || config.buyer != old_buyer {
+        CONFIG.save(deps.storage, &config)?;
+    }
+    if resolved {
+        NAMES_RESOLVED_AT.save(deps.storage, &env.block.height)?;
+    }
+    Ok(())
+}
+
 /// Query message for wrapped token's BridgeInfo
 #[derive(serde::Serialize)]
 struct BridgeInfoQuery {}
@@ -128,7 +483,7 @@ struct BridgeInfoResponse {
 }
 
 /// Query CW20 wrapped token for its underlying bridge info (chain_id, eth_contract)
-fn query_bridge_info(deps: Deps, cw20_addr: &str) -> Result<(String, String), ContractError> {
+fn query_bridge_info(deps: Deps, cw20_addr: &str) -> Result<(String, ExternalAddress), ContractError> {
     #[derive(serde::Serialize)]
     struct QueryMsg {
         bridge_info: BridgeInfoQuery,
@@ -141,14 +496,13 @@ fn query_bridge_info(deps: Deps, cw20_addr: &str) -> Result<(String, String), Co
             .map_err(|e| ContractError::Std(StdError::msg(format!("serialize: {}", e))))?,
     })).map_err(|e| ContractError::Std(StdError::msg(format!("query bridge_info: {}", e))))?;
     
-    Ok((response.chain_id, response.contract_address.to_lowercase()))
+    Ok((response.chain_id, ExternalAddress::from(response.contract_address)))
 }
 
-#[entry_point]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
@@ -164,450 +518,7919 @@ pub fn instantiate(
     if msg.accepted_chain_id.is_empty() || msg.accepted_eth_contract.is_empty() {
         return Err(ContractError::Std(StdError::msg("accepted_chain_id and accepted_eth_contract required")));
     }
+    let accepted_eth_contract = ExternalAddress::parse(&msg.accepted_eth_contract)?;
+
+    if let Some(expected) = msg.expected_module_version {
+        let actual = query_module_version(deps.as_ref()).ok().flatten();
+        if actual != Some(expected) {
+            return Err(ContractError::ModuleVersionMismatch {
+                module: proto::INFERENCE_MODULE_NAME.to_string(),
+                expected,
+                actual,
+            });
+        }
+    }
+
+    let receipt_nft_contract = msg
+        .receipt_nft_contract
+        .map(|addr| deps.api.addr_validate(&addr).map(|a| a.to_string()))
+        .transpose()?;
 
     let native_denom = get_native_denom(deps.as_ref())?;
+    let payout_decimals = msg.payout_decimals.unwrap_or(DEFAULT_PAYOUT_DECIMALS);
+    if payout_decimals > MAX_PAYOUT_DECIMALS {
+        return Err(ContractError::InvalidPayoutDecimals {
+            decimals: payout_decimals,
+            max: MAX_PAYOUT_DECIMALS,
+        });
+    }
+    let alternative_payout_denoms =
+        validate_payout_denoms(msg.alternative_payout_denoms.unwrap_or_default(), &native_denom)?;
+    if let Some(vesting) = &msg.vesting {
+        validate_vesting_config(vesting)?;
+    }
 
     let config = Config {
         admin: admin.clone(),
         buyer: buyer.clone(),
         accepted_chain_id: msg.accepted_chain_id.clone(),
-        accepted_eth_contract: msg.accepted_eth_contract.to_lowercase(),
+        accepted_eth_contract: accepted_eth_contract.clone(),
         price_usd: msg.price_usd,
         native_denom: native_denom.clone(),
+        payout_decimals,
+        alternative_payout_denoms,
+        vesting: msg.vesting,
+        purchase_limits: msg.purchase_limits,
+        timelock_delay_blocks: 0,
+        crank_reward: Uint128::zero(),
+        commit_reveal_window_blocks: 0,
+        expected_module_version: msg.expected_module_version,
+        receipt_nft_contract,
+        require_attestation: false,
+        debug_grpc_enabled: false,
+        state_export_enabled: false,
+        validator_kickback: None,
+        price_floor_usd: None,
+        max_raise_usd: msg.max_raise_usd,
+        total_raised_usd: Uint128::zero(),
+        circuit_breaker: None,
+        proceeds_bridge: None,
+        proceeds_stream: None,
+        treasury: None,
+        name_bindings: None,
+        co_approval: None,
+        trusted_payment_token: None,
         is_paused: false,
         total_tokens_sold: Uint128::zero(),
     };
     CONFIG.save(deps.storage, &config)?;
+    record_price_change(deps.storage, &env, &info.sender, None, None, Some(msg.price_usd))?;
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("admin", admin)
         .add_attribute("buyer", buyer)
         .add_attribute("accepted_chain_id", msg.accepted_chain_id)
-        .add_attribute("accepted_eth_contract", msg.accepted_eth_contract)
+        .add_attribute("accepted_eth_contract", accepted_eth_contract)
         .add_attribute("price_usd", msg.price_usd)
         .add_attribute("native_denom", native_denom))
 }
 
-#[entry_point]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    sync_bound_names(deps.branch(), &env)?;
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
-        ExecuteMsg::Pause {} => pause_contract(deps, info),
-        ExecuteMsg::Resume {} => resume_contract(deps, info),
-        ExecuteMsg::UpdateBuyer { buyer } => update_buyer(deps, info, buyer),
-        ExecuteMsg::UpdatePrice { price_usd } => update_price(deps, info, price_usd),
-        ExecuteMsg::WithdrawNativeTokens { amount, recipient } => withdraw_native_tokens(deps, info, amount, recipient),
+        ExecuteMsg::PurchaseViaAllowance { token, usd_amount } => purchase_via_allowance(deps, env, info, token, usd_amount),
+        ExecuteMsg::Pause {} => pause_contract(deps, env, info),
+        ExecuteMsg::Resume {} => resume_contract(deps, env, info),
+        ExecuteMsg::ContinueMigration {} => continue_migration(deps, env, info),
+        ExecuteMsg::UpdateBuyer { buyer } => update_buyer(deps, env, info, buyer),
+        ExecuteMsg::ImportWhitelist { entries, batch } => import_whitelist(deps, env, info, entries, batch),
+        ExecuteMsg::UpdatePrice { price_usd } => update_price(deps, env, info, price_usd),
+        ExecuteMsg::UpdateTokenPrice { cw20_contract, price } => update_token_price(deps, env, info, cw20_contract, price),
+        ExecuteMsg::UpdateBuyerPrice { buyer, price } => update_buyer_price(deps, env, info, buyer, price),
+        ExecuteMsg::UpdateVestingConfig { vesting } => update_vesting_config(deps, env, info, vesting),
+        ExecuteMsg::UpdatePurchaseLimits { purchase_limits } => update_purchase_limits(deps, env, info, purchase_limits),
+        ExecuteMsg::UpdatePayoutDenoms { denoms } => update_payout_denoms(deps, env, info, denoms),
+        ExecuteMsg::WithdrawNativeTokens { amount, recipient, denom } => withdraw_native_tokens(deps, env, info, amount, recipient, denom),
         ExecuteMsg::EmergencyWithdraw { recipient } => emergency_withdraw(deps, env, info, recipient),
+        ExecuteMsg::FinalizeSale { recipient } => finalize_sale(deps, env, info, recipient),
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::AddRound { name, start_height, end_height, price_usd, treasury } => {
+            let round = SaleRound {
+                name,
+                start_height,
+                end_height,
+                price_usd,
+                treasury,
+                raised_usd: Uint128::zero(),
+                tokens_sold: Uint128::zero(),
+                finished: false,
+            };
+            add_round(deps, env, info, round)
+        }
+        ExecuteMsg::RemoveRound { id } => remove_round(deps, env, info, id),
+        ExecuteMsg::SetEpochSchedule { epochs } => set_epoch_schedule(deps, env, info, epochs),
+        ExecuteMsg::UpdateMaxRaise { max_raise_usd } => update_max_raise(deps, env, info, max_raise_usd),
+        ExecuteMsg::UpdateProceedsBridge { destination } => update_proceeds_bridge(deps, env, info, destination),
+        ExecuteMsg::UpdateTrustedPaymentToken { cw20_contract } => update_trusted_payment_token(deps, env, info, cw20_contract),
+        ExecuteMsg::UpdateProceedsStream { interval_blocks } => update_proceeds_stream(deps, env, info, interval_blocks),
+        ExecuteMsg::UpdateNameBindings { bindings } => update_name_bindings(deps, env, info, bindings),
+        ExecuteMsg::UpdateCoApproval { co_approval } => update_co_approval(deps, env, info, co_approval),
+        ExecuteMsg::ApprovePurchase { buyer, max_usd, expiry } => approve_purchase(deps, env, info, buyer, max_usd, expiry),
+        ExecuteMsg::FlushUnbridgedProceeds { cw20_contract } => flush_unbridged_proceeds(deps, env, info, cw20_contract),
+        ExecuteMsg::UpdateCrankConfig { crank_reward, timelock_delay_blocks } => update_crank_config(deps, env, info, crank_reward, timelock_delay_blocks),
+        ExecuteMsg::QueueAction { action } => queue_action(deps, env, info, action),
+        ExecuteMsg::CancelAction { id } => cancel_action(deps, env, info, id),
+        ExecuteMsg::Crank { limit } => crank(deps, env, info, limit),
+        ExecuteMsg::Subscribe { cw20_contract, usd_amount, interval_blocks } => {
+            subscribe(deps, env, info, cw20_contract, usd_amount, interval_blocks)
+        }
+        ExecuteMsg::PauseSubscription { id } => pause_subscription(deps, info, id),
+        ExecuteMsg::ResumeSubscription { id } => resume_subscription(deps, env, info, id),
+        ExecuteMsg::CancelSubscription { id } => cancel_subscription(deps, info, id),
+        ExecuteMsg::ProcessSubscriptions { limit } => process_subscriptions(deps, env, info, limit),
+        ExecuteMsg::UpdateCommitRevealWindow { blocks } => update_commit_reveal_window(deps, env, info, blocks),
+        ExecuteMsg::UpdateReceiptNft { cw721_contract } => update_receipt_nft(deps, env, info, cw721_contract),
+        ExecuteMsg::SetAttestationRequired { required } => set_attestation_required(deps, env, info, required),
+        ExecuteMsg::SetDebugGrpc { enabled } => set_debug_grpc(deps, env, info, enabled),
+        ExecuteMsg::SetStateExport { enabled } => set_state_export(deps, env, info, enabled),
+        ExecuteMsg::CommitPurchase { commitment, cw20_contract } => commit_purchase(deps, env, info, commitment, cw20_contract),
+        ExecuteMsg::RequestQuote { cw20_contract } => request_quote(deps, env, info, cw20_contract),
+        ExecuteMsg::StartLottery { commit_end_height, capacity_usd } => {
+            start_lottery(deps, env, info, commit_end_height, capacity_usd)
+        }
+        ExecuteMsg::FinalizeLottery {} => {
+            let lottery = LOTTERY.may_load(deps.storage)?.ok_or(ContractError::LotteryNotActive {})?;
+            let response: QueryRandomSeedResponse = query_proto(
+                deps.as_ref(),
+                proto::RANDOM_SEED_PATH,
+                &QueryRandomSeedRequest { block_height: lottery.commit_end_height },
+            )?;
+            finalize_lottery(deps, &env, response.seed)
+        }
+        ExecuteMsg::ClaimLottery {} => claim_lottery(deps, env, info),
+        ExecuteMsg::CreateSale { params } => create_sale(deps, env, info, params),
+        ExecuteMsg::UpdateSale { id, add_buyers, remove_buyers, closed } => {
+            update_sale(deps, env, info, id, add_buyers, remove_buyers, closed)
+        }
+        ExecuteMsg::SetPool { label, cap, add_buyers, remove_buyers } => {
+            set_pool(deps, env, info, label, cap, add_buyers, remove_buyers)
+        }
+        ExecuteMsg::RemovePool { label } => remove_pool(deps, env, info, label),
+        ExecuteMsg::ReservePurchase { usd_amount, deadline_height } => {
+            reserve_purchase(deps, env, info, usd_amount, deadline_height)
+        }
+        ExecuteMsg::CancelReservation { id } => cancel_reservation(deps, env, info, id),
     }
 }
 
-fn receive_cw20(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    cw20_msg: Cw20ReceiveMsg,
-) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-
-    if config.is_paused {
-        return Err(ContractError::ContractPaused {});
-    }
-
-    let cw20_contract = info.sender.to_string();
-
-    // Check 1: Only designated buyer can purchase
-    if cw20_msg.sender != config.buyer {
-        return Err(ContractError::BuyerNotAllowed {
-            buyer: cw20_msg.sender.clone(),
-        });
+fn validate_payment_token(deps: Deps, config: &Config, cw20_contract: &str) -> Result<(), ContractError> {
+    // The pinned token passed these checks when it was pinned
+    if config.trusted_payment_token.as_deref() == Some(cw20_contract) {
+        return Ok(());
     }
-
-    // Check 2: Validate it's a legit bridge token via chain
-    if !validate_wrapped_token_for_trade(deps.as_ref(), &cw20_contract)? {
+    // Validate it's a legit bridge token via chain
+    if !validate_wrapped_token_for_trade(deps, cw20_contract)? {
         return Err(ContractError::TokenNotAccepted {
             token: format!("CW20 {} not approved for trading", cw20_contract),
         });
     }
 
-    // Check 3: Query underlying Ethereum address and compare to expected
-    let (chain_id, eth_contract) = query_bridge_info(deps.as_ref(), &cw20_contract)?;
-    if chain_id != config.accepted_chain_id || eth_contract != config.accepted_eth_contract {
+    // Query underlying Ethereum address and compare to expected. Tokens the admin has priced with
+    // UpdateTokenPrice are accepted alongside the configured one, if bridged from the same chain
+    let (chain_id, eth_contract) = query_bridge_info(deps, cw20_contract)?;
+    let priced = TOKEN_PRICES.has(deps.storage, cw20_contract);
+    if chain_id != config.accepted_chain_id || (!priced && eth_contract != config.accepted_eth_contract) {
         return Err(ContractError::WrongToken {
             expected_chain: config.accepted_chain_id.clone(),
-            expected_contract: config.accepted_eth_contract.clone(),
+            expected_contract: config.accepted_eth_contract.to_string(),
             got_chain: chain_id,
-            got_contract: eth_contract,
+            got_contract: eth_contract.into(),
         });
     }
+    Ok(())
+}
 
-    let _purchase_msg: PurchaseTokenMsg = from_json(&cw20_msg.msg)?;
-    let buyer = cw20_msg.sender;
-    let usd_amount = cw20_msg.amount;
-
+/// Purchase from a sale created with CreateSale. Sales share the contract's payment token, payout denom and
+/// pause switch; buyers, price and inventory are per sale.
+fn purchase_from_sale(
+    deps: DepsMut,
+    env: &Env,
+    mut config: Config,
+    sale_id: u64,
+    buyer: String,
+    cw20_contract: String,
+    usd_amount: Uint128,
+) -> Result<Response, ContractError> {
     if usd_amount.is_zero() {
         return Err(ContractError::ZeroAmount {});
     }
-
-    // Fixed price calculation
-    let tokens_to_buy = calculate_tokens_for_usd(usd_amount, config.price_usd);
-    if tokens_to_buy.is_zero() {
+    let mut sale = SALES.may_load(deps.storage, sale_id)?.ok_or(ContractError::SaleNotFound { id: sale_id })?;
+    if sale.closed {
+        return Err(ContractError::SaleClosed { id: sale_id });
+    }
+    if !SALE_BUYERS.has(deps.storage, (sale_id, &buyer)) {
+        return Err(ContractError::BuyerNotAllowed { buyer });
+    }
+    if let Some(floor) = config.price_floor_usd {
+        if sale.price_usd < floor {
+            return Err(ContractError::PriceBelowFloor { price: sale.price_usd.u128(), floor: floor.u128() });
+        }
+    }
+    let tokens = calculate_tokens_for_usd(usd_amount, sale.price_usd, config.payout_decimals);
+    if tokens.is_zero() {
         return Err(ContractError::ZeroAmount {});
     }
-
-    // Check contract balance
-    let contract_balance = deps
-        .querier
-        .query_balance(env.contract.address.to_string(), &config.native_denom)?;
-
-    let balance_u128: Uint128 = contract_balance
-        .amount
-        .try_into()
-        .map_err(|_| ContractError::Std(StdError::msg("balance exceeds Uint128")))?;
-
-    if tokens_to_buy > balance_u128 {
+    let remaining = sale.inventory.saturating_sub(sale.sold);
+    if tokens > remaining {
+        return Err(ContractError::SaleInventoryExceeded {
+            remaining: remaining.u128(),
+            requested: tokens.u128(),
+        });
+    }
+    // The chain queries go last, once the sale's own checks have passed
+    if config.require_attestation {
+        ensure_attested(deps.as_ref(), &buyer)?;
+    }
+    validate_payment_token(deps.as_ref(), &config, &cw20_contract)?;
+    consume_purchase_approval(deps.storage, env, &config, &buyer, usd_amount)?;
+    // The sale sells from its own reserve, never from what other obligations hold back
+    let reserve = sale_reserve(sale_id);
+    let sale_reserved = RESERVES.may_load(deps.storage, &reserve)?.unwrap_or_default();
+    let available = free_native(deps.as_ref(), env, &config)?
+        .checked_add(sale_reserved)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    if tokens > available {
         return Err(ContractError::InsufficientBalance {
-            available: balance_u128.u128(),
-            needed: tokens_to_buy.u128(),
+            available: available.u128(),
+            needed: tokens.u128(),
         });
     }
 
-    // Update total sold
-    let mut updated_config = config.clone();
-    updated_config.total_tokens_sold = updated_config
+    sale.sold = sale
+        .sold
+        .checked_add(tokens)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    sale.raised_usd = sale
+        .raised_usd
+        .checked_add(usd_amount)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    SALES.save(deps.storage, sale_id, &sale)?;
+    update_reserve(deps.storage, &reserve, tokens, false)?;
+    record_purchase_stats(deps.storage, &buyer, &cw20_contract, usd_amount)?;
+    config.total_raised_usd = check_raise_cap(&config, usd_amount)?;
+    config.total_tokens_sold = config
         .total_tokens_sold
-        .checked_add(tokens_to_buy)
+        .checked_add(tokens)
         .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
-    CONFIG.save(deps.storage, &updated_config)?;
+    let breaker_event = record_sale_volume(deps.storage, env, &mut config, usd_amount)?;
+    CONFIG.save(deps.storage, &config)?;
+    TOKENS_SOLD_BY_DENOM.update(deps.storage, &config.native_denom, |sold| -> Result<_, ContractError> {
+        sold.unwrap_or_default()
+            .checked_add(tokens)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+    })?;
+    LAST_PURCHASE_HEIGHT.save(deps.storage, &env.block.height)?;
 
-    // Send GNK to buyer
-    let send_native_msg = BankMsg::Send {
+    let mut response = Response::new().add_message(BankMsg::Send {
         to_address: buyer.clone(),
         amount: vec![Coin {
             denom: config.native_denom.clone(),
-            amount: tokens_to_buy.into(),
+            amount: tokens.into(),
         }],
-    };
-
-    // Forward W(USDT) to admin
-    let mut response = Response::new().add_message(send_native_msg);
-    if !config.admin.is_empty() {
-        let transfer_cw20_msg = create_cw20_transfer_msg(
-            cw20_contract.clone(),
-            config.admin.clone(),
-            usd_amount,
-        )?;
-        response = response.add_message(transfer_cw20_msg);
+    });
+    let treasury = config.proceeds_recipient();
+    if !treasury.is_empty() {
+        response = route_proceeds(deps.storage, &config, response, cw20_contract, treasury, usd_amount)?;
+    }
+    if let Some(event) = breaker_event {
+        response = response.add_event(event);
     }
-
     Ok(response
         .add_attribute("method", "purchase")
+        .add_attribute("sale_id", sale_id.to_string())
         .add_attribute("buyer", buyer)
         .add_attribute("usdt_amount", usd_amount)
-        .add_attribute("gnk_purchased", tokens_to_buy)
-        .add_attribute("price_usd", config.price_usd))
+        .add_attribute("gnk_purchased", tokens)
+        .add_attribute("price_usd", sale.price_usd))
 }
 
-fn pause_contract(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    if info.sender.as_str() != config.admin {
-        return Err(ContractError::Unauthorized {});
+fn reserve_purchase(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    usd_amount: Uint128,
+    deadline_height: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(config.is_paused)?;
+    let buyer = info.sender.to_string();
+    if buyer != config.buyer && !WHITELIST.has(deps.storage, &buyer) {
+        return Err(ContractError::BuyerNotAllowed { buyer });
     }
-    config.is_paused = true;
-    CONFIG.save(deps.storage, &config)?;
-    Ok(Response::new().add_attribute("method", "pause"))
-}
-
-fn resume_contract(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    if info.sender.as_str() != config.admin {
-        return Err(ContractError::Unauthorized {});
+    if usd_amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
     }
-    config.is_paused = false;
-    CONFIG.save(deps.storage, &config)?;
-    Ok(Response::new().add_attribute("method", "resume"))
-}
-
-fn update_buyer(deps: DepsMut, info: MessageInfo, buyer: String) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    if info.sender.as_str() != config.admin {
-        return Err(ContractError::Unauthorized {});
+    if deadline_height <= env.block.height || deadline_height > env.block.height + MAX_RESERVATION_BLOCKS {
+        return Err(ContractError::InvalidReservation {
+            reason: format!("deadline must be within {} blocks", MAX_RESERVATION_BLOCKS),
+        });
     }
-    let validated_buyer = deps.api.addr_validate(&buyer)?.to_string();
-    config.buyer = validated_buyer.clone();
-    CONFIG.save(deps.storage, &config)?;
+    // The approval covers the whole reservation, however many payments fund it
+    consume_purchase_approval(deps.storage, &env, &config, &buyer, usd_amount)?;
+    let id = NEXT_RESERVATION_ID.may_load(deps.storage)?.unwrap_or(1);
+    NEXT_RESERVATION_ID.save(deps.storage, &(id + 1))?;
+    // Held back at the default price, the likely settlement price
+    let reserved_tokens = calculate_tokens_for_usd(usd_amount, config.price_usd, config.payout_decimals);
+    update_reserve(deps.storage, RESERVE_RESERVATIONS, reserved_tokens, true)?;
+    RESERVATIONS.save(
+        deps.storage,
+        id,
+        &Reservation {
+            buyer: buyer.clone(),
+            usd_amount,
+            funded_usd: Uint128::zero(),
+            deadline_height,
+            payments: vec![],
+            reserved_tokens,
+        },
+    )?;
+    RESERVATION_EXPIRIES.save(deps.storage, (deadline_height, id), &())?;
     Ok(Response::new()
-        .add_attribute("method", "update_buyer")
-        .add_attribute("buyer", validated_buyer))
+        .add_attribute("method", "reserve_purchase")
+        .add_attribute("reservation_id", id.to_string())
+        .add_attribute("buyer", buyer)
+        .add_attribute("usd_amount", usd_amount)
+        .add_attribute("deadline_height", deadline_height.to_string()))
 }
 
-fn update_price(deps: DepsMut, info: MessageInfo, price_usd: Uint128) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
-    if info.sender.as_str() != config.admin {
-        return Err(ContractError::Unauthorized {});
+/// Adds a payment to a reservation, refunding anything beyond the outstanding amount, and
+/// settles the reservation once it is fully funded
+fn fund_reservation(
+    deps: DepsMut,
+    env: &Env,
+    config: Config,
+    id: u64,
+    buyer: String,
+    cw20_contract: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut reservation = RESERVATIONS.may_load(deps.storage, id)?.ok_or(ContractError::ReservationNotFound { id })?;
+    if reservation.buyer != buyer {
+        return Err(ContractError::BuyerNotAllowed { buyer });
     }
-    if price_usd.is_zero() {
+    if env.block.height > reservation.deadline_height {
+        return Err(ContractError::ReservationExpired {
+            deadline_height: reservation.deadline_height,
+        });
+    }
+    if amount.is_zero() {
         return Err(ContractError::ZeroAmount {});
     }
-    config.price_usd = price_usd;
-    CONFIG.save(deps.storage, &config)?;
-    Ok(Response::new()
-        .add_attribute("method", "update_price")
-        .add_attribute("price_usd", price_usd))
+    // A token with its own price is credited at what it buys, valued at the default price;
+    // under a round or epoch price every token buys the same
+    let price = match price_with_source(deps.as_ref(), env, &config, &cw20_contract)? {
+        (price, "token") => price,
+        _ => config.price_usd,
+    };
+    let outstanding = reservation.usd_amount - reservation.funded_usd;
+    let credit = amount.multiply_ratio(config.price_usd, price);
+    let (accepted, credited) = if credit <= outstanding {
+        (amount, credit)
+    } else {
+        // Only the part covering the outstanding amount is kept, rounded in the contract's favour
+        let needed = outstanding.multiply_ratio(price, config.price_usd);
+        let needed = if needed.multiply_ratio(config.price_usd, price) < outstanding { needed + Uint128::one() } else { needed };
+        (needed.min(amount), outstanding)
+    };
+    let excess = amount - accepted;
+    reservation.funded_usd += credited;
+    match reservation.payments.iter_mut().find(|p| p.cw20_contract == cw20_contract) {
+        Some(payment) => payment.amount += accepted,
+        None => reservation.payments.push(ReservationPayment {
+            cw20_contract: cw20_contract.clone(),
+            amount: accepted,
+        }),
+    }
+
+    if !excess.is_zero() {
+        record_refund(deps.storage, excess)?;
+    }
+    let mut response = if reservation.funded_usd == reservation.usd_amount {
+        RESERVATIONS.remove(deps.storage, id);
+        RESERVATION_EXPIRIES.remove(deps.storage, (reservation.deadline_height, id));
+        update_reserve(deps.storage, RESERVE_RESERVATIONS, reservation.reserved_tokens, false)?;
+        settle_reservation(deps, env, config, reservation)?
+    } else {
+        RESERVATIONS.save(deps.storage, id, &reservation)?;
+        Response::new()
+            .add_attribute("method", "fund_reservation")
+            .add_attribute("funded_usd", reservation.funded_usd)
+            .add_attribute("remaining_usd", reservation.usd_amount - reservation.funded_usd)
+    };
+    if !excess.is_zero() {
+        response = response
+            .add_message(create_cw20_transfer_msg(cw20_contract, buyer, excess)?)
+            .add_attribute("refund_usd", excess);
+    }
+    Ok(response.add_attribute("reservation_id", id.to_string()))
 }
 
-fn withdraw_native_tokens(
+/// Pays out a fully funded reservation, each payment at its token's effective price, and
+/// forwards every payment to the treasury
+fn settle_reservation(
     deps: DepsMut,
-    info: MessageInfo,
-    amount: Uint128,
-    recipient: String,
+    env: &Env,
+    mut config: Config,
+    reservation: Reservation,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender.as_str() != config.admin {
-        return Err(ContractError::Unauthorized {});
+    let round = active_round(deps.storage, env.block.height)?;
+    if round.is_none() && !ROUNDS.is_empty(deps.storage) {
+        return Err(ContractError::NoActiveRound {});
     }
-    let recipient_addr = deps.api.addr_validate(&recipient)?;
-    if amount.is_zero() {
+    let mut tokens = Uint128::zero();
+    for payment in &reservation.payments {
+        let price = effective_price(deps.as_ref(), env, &config, &payment.cw20_contract)?;
+        if let Some(floor) = config.price_floor_usd {
+            if price < floor {
+                return Err(ContractError::PriceBelowFloor { price: price.u128(), floor: floor.u128() });
+            }
+        }
+        tokens = tokens
+            .checked_add(calculate_tokens_for_usd(payment.amount, price, config.payout_decimals))
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    }
+    let usd_amount = reservation.usd_amount;
+    config.total_raised_usd = check_raise_cap(&config, usd_amount)?;
+    if let Some((epoch, raised)) = check_epoch_cap(deps.as_ref(), usd_amount)? {
+        EPOCH_RAISED.save(deps.storage, epoch, &raised)?;
+    }
+
+    // Whitelisted buyers are bounded by their allocation; the designated buyer is not
+    let buyer = reservation.buyer;
+    if buyer != config.buyer {
+        if let Some(mut entry) = WHITELIST.may_load(deps.storage, &buyer)? {
+            let remaining = entry.allocation_usd.saturating_sub(entry.purchased_usd);
+            if usd_amount > remaining {
+                return Err(ContractError::AllocationExceeded {
+                    buyer,
+                    remaining: remaining.u128(),
+                });
+            }
+            entry.purchased_usd += usd_amount;
+            WHITELIST.save(deps.storage, &buyer, &entry)?;
+        }
+    }
+
+    if tokens.is_zero() {
         return Err(ContractError::ZeroAmount {});
     }
-    let send_msg = BankMsg::Send {
-        to_address: recipient_addr.to_string(),
+    // The reservation's own hold was released before settling, so it is part of what is free
+    let balance = free_native(deps.as_ref(), env, &config)?;
+    if tokens > balance {
+        return Err(ContractError::InsufficientBalance {
+            available: balance.u128(),
+            needed: tokens.u128(),
+        });
+    }
+    let pool = select_pool(deps.storage, &buyer, tokens)?;
+    config.total_tokens_sold = config
+        .total_tokens_sold
+        .checked_add(tokens)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    let breaker_event = record_sale_volume(deps.storage, env, &mut config, usd_amount)?;
+    CONFIG.save(deps.storage, &config)?;
+    TOKENS_SOLD_BY_DENOM.update(deps.storage, &config.native_denom, |sold| -> Result<_, ContractError> {
+        sold.unwrap_or_default()
+            .checked_add(tokens)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+    })?;
+    LAST_PURCHASE_HEIGHT.save(deps.storage, &env.block.height)?;
+    for payment in &reservation.payments {
+        record_purchase_stats(deps.storage, &buyer, &payment.cw20_contract, payment.amount)?;
+    }
+    if let Some(label) = &pool {
+        record_pool_draw(deps.storage, label, tokens, usd_amount)?;
+    }
+    if let Some((round_id, mut round)) = round.clone() {
+        round.raised_usd += usd_amount;
+        round.tokens_sold += tokens;
+        ROUNDS.save(deps.storage, round_id, &round)?;
+    }
+
+    let mut response = Response::new().add_message(BankMsg::Send {
+        to_address: buyer.clone(),
         amount: vec![Coin {
-            denom: config.native_denom,
-            amount: amount.into(),
+            denom: config.native_denom.clone(),
+            amount: tokens.into(),
         }],
+    });
+    let treasury = round
+        .and_then(|(_, round)| round.treasury)
+        .unwrap_or_else(|| config.proceeds_recipient());
+    if !treasury.is_empty() {
+        for payment in reservation.payments.into_iter().filter(|p| !p.amount.is_zero()) {
+            response = route_proceeds(deps.storage, &config, response, payment.cw20_contract, treasury.clone(), payment.amount)?;
+        }
+    }
+    if let Some(event) = breaker_event {
+        response = response.add_event(event);
+    }
+    if let Some(label) = pool {
+        response = response.add_attribute("pool", label);
+    }
+    Ok(response
+        .add_attribute("method", "purchase")
+        .add_attribute("buyer", buyer)
+        .add_attribute("usdt_amount", usd_amount)
+        .add_attribute("gnk_purchased", tokens))
+}
+
+fn update_reserve(storage: &mut dyn Storage, obligation: &str, amount: Uint128, increase: bool) -> StdResult<()> {
+    let reserved = RESERVES.may_load(storage, obligation)?.unwrap_or_default();
+    let reserved = if increase { reserved.checked_add(amount)? } else { reserved.saturating_sub(amount) };
+    RESERVES.save(storage, obligation, &reserved)
+}
+
+/// Native tokens owed to buyers but not yet paid out, over every obligation
+fn reserved_native(storage: &dyn Storage) -> StdResult<Uint128> {
+    let mut reserved = Uint128::zero();
+    for item in RESERVES.range(storage, None, None, Order::Ascending) {
+        reserved = reserved.checked_add(item?.1)?;
+    }
+    Ok(reserved)
+}
+
+/// Holds back the unsold inventory of open sales created before sales had reserves
+fn seed_sale_reserves(storage: &mut dyn Storage) -> StdResult<()> {
+    let sales = SALES
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, sale) in sales.into_iter().filter(|(_, sale)| !sale.closed) {
+        if !RESERVES.has(storage, &sale_reserve(id)) {
+            RESERVES.save(storage, &sale_reserve(id), &sale.inventory.saturating_sub(sale.sold))?;
+        }
+    }
+    Ok(())
+}
+
+/// Indexes the deadlines of open reservations made before the crank expired them
+fn seed_reservation_expiries(storage: &mut dyn Storage) -> StdResult<()> {
+    if !RESERVATION_EXPIRIES.is_empty(storage) {
+        return Ok(());
+    }
+    let reservations = RESERVATIONS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, reservation) in reservations {
+        RESERVATION_EXPIRIES.save(storage, (reservation.deadline_height, id), &())?;
+    }
+    Ok(())
+}
+
+/// Counts the subscriptions of deployments that predate the per-buyer limit
+fn seed_buyer_subscriptions(storage: &mut dyn Storage) -> StdResult<()> {
+    if !BUYER_SUBSCRIPTIONS.is_empty(storage) {
+        return Ok(());
+    }
+    let mut held: BTreeMap<String, u32> = BTreeMap::new();
+    for item in SUBSCRIPTIONS.range(storage, None, None, Order::Ascending) {
+        *held.entry(item?.1.buyer).or_default() += 1;
+    }
+    for (buyer, count) in held {
+        BUYER_SUBSCRIPTIONS.save(storage, &buyer, &count)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the reserve counters for a contract upgraded from before they existed: finalized
+/// lottery wins not yet claimed and open reservations. Runs once, while `RESERVES` is empty.
+fn seed_reserves(storage: &mut dyn Storage) -> StdResult<()> {
+    if !RESERVES.is_empty(storage) {
+        return Ok(());
+    }
+    let config = CONFIG.load(storage)?;
+    let mut lottery_tokens = Uint128::zero();
+    if let Some(lottery) = LOTTERY.may_load(storage)? {
+        if lottery.seed.is_some() {
+            for item in LOTTERY_ENTRIES.range(storage, None, None, Order::Ascending) {
+                let won_usd = item?.1.won_usd;
+                lottery_tokens += calculate_tokens_for_usd(won_usd, lottery.price_usd, config.payout_decimals);
+            }
+        }
+    }
+    let reservations = RESERVATIONS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut reservation_tokens = Uint128::zero();
+    for (id, mut reservation) in reservations {
+        reservation.reserved_tokens = calculate_tokens_for_usd(reservation.usd_amount, config.price_usd, config.payout_decimals);
+        reservation_tokens += reservation.reserved_tokens;
+        RESERVATIONS.save(storage, id, &reservation)?;
+    }
+    RESERVES.save(storage, RESERVE_LOTTERY, &lottery_tokens)?;
+    RESERVES.save(storage, RESERVE_RESERVATIONS, &reservation_tokens)
+}
+
+/// Native balance WithdrawNativeTokens may take, after `reserved_native`
+fn free_native(deps: Deps, env: &Env, config: &Config) -> Result<Uint128, ContractError> {
+    let balance: Uint128 = deps
+        .querier
+        .query_balance(env.contract.address.to_string(), &config.native_denom)?
+        .amount
+        .try_into()
+        .map_err(|_| ContractError::Std(StdError::msg("balance exceeds Uint128")))?;
+    Ok(balance.saturating_sub(reserved_native(deps.storage)?))
+}
+
+/// What a purchase paying out in `denom` can draw on: the free native balance, or the whole
+/// balance of an alternative payout denom, which nothing is held back in
+fn available_payout(deps: Deps, env: &Env, config: &Config, denom: &str) -> Result<Uint128, ContractError> {
+    if denom == config.native_denom {
+        return free_native(deps, env, config);
+    }
+    deps.querier
+        .query_balance(env.contract.address.to_string(), denom)?
+        .amount
+        .try_into()
+        .map_err(|_| ContractError::Std(StdError::msg("balance exceeds Uint128")))
+}
+
+fn cancel_reservation(deps: DepsMut, env: Env, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let reservation = RESERVATIONS.may_load(deps.storage, id)?.ok_or(ContractError::ReservationNotFound { id })?;
+    if info.sender.as_str() != reservation.buyer && env.block.height <= reservation.deadline_height {
+        return Err(ContractError::Unauthorized {});
+    }
+    let refund_usd = reservation.funded_usd;
+    Ok(Response::new()
+        .add_messages(close_reservation(deps.storage, id, reservation)?)
+        .add_attribute("method", "cancel_reservation")
+        .add_attribute("reservation_id", id.to_string())
+        .add_attribute("refund_usd", refund_usd))
+}
+
+/// Removes an unsettled reservation, releasing its reserve, and returns the refunds of its payments
+fn close_reservation(storage: &mut dyn Storage, id: u64, reservation: Reservation) -> Result<Vec<WasmMsg>, ContractError> {
+    RESERVATIONS.remove(storage, id);
+    RESERVATION_EXPIRIES.remove(storage, (reservation.deadline_height, id));
+    update_reserve(storage, RESERVE_RESERVATIONS, reservation.reserved_tokens, false)?;
+    record_refund(storage, reservation.funded_usd)?;
+    reservation
+        .payments
+        .into_iter()
+        .filter(|p| !p.amount.is_zero())
+        .map(|payment| create_cw20_transfer_msg(payment.cw20_contract, reservation.buyer.clone(), payment.amount))
+        .collect()
+}
+
+fn create_sale(deps: DepsMut, env: Env, info: MessageInfo, params: SaleParams) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    if params.price_usd.is_zero() || params.inventory.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    if params.buyers.len() > MAX_WHITELIST_BATCH {
+        return Err(ContractError::WhitelistBatchTooLarge {
+            size: params.buyers.len(),
+            max: MAX_WHITELIST_BATCH,
+        });
+    }
+    let id = NEXT_SALE_ID.may_load(deps.storage)?.unwrap_or(1);
+    NEXT_SALE_ID.save(deps.storage, &(id + 1))?;
+    for buyer in &params.buyers {
+        let buyer = deps.api.addr_validate(buyer)?;
+        SALE_BUYERS.save(deps.storage, (id, buyer.as_str()), &())?;
+    }
+    let sale = Sale {
+        name: params.name,
+        price_usd: params.price_usd,
+        inventory: params.inventory,
+        sold: Uint128::zero(),
+        raised_usd: Uint128::zero(),
+        closed: false,
+        created_at_height: env.block.height,
     };
+    SALES.save(deps.storage, id, &sale)?;
+    RESERVES.save(deps.storage, &sale_reserve(id), &sale.inventory)?;
+    record_audit(deps.storage, &env, &info.sender, "create_sale", None, Some(to_json_string(&sale)?))?;
     Ok(Response::new()
-        .add_message(send_msg)
-        .add_attribute("method", "withdraw")
-        .add_attribute("amount", amount)
-        .add_attribute("recipient", recipient))
+        .add_attribute("method", "create_sale")
+        .add_attribute("sale_id", id.to_string())
+        .add_attribute("buyers", params.buyers.len().to_string()))
 }
 
-fn emergency_withdraw(
+fn update_sale(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    recipient: String,
+    id: u64,
+    add_buyers: Vec<String>,
+    remove_buyers: Vec<String>,
+    closed: Option<bool>,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    if info.sender.as_str() != config.admin {
-        return Err(ContractError::Unauthorized {});
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let mut sale = SALES.may_load(deps.storage, id)?.ok_or(ContractError::SaleNotFound { id })?;
+    for buyer in &add_buyers {
+        let buyer = deps.api.addr_validate(buyer)?;
+        SALE_BUYERS.save(deps.storage, (id, buyer.as_str()), &())?;
     }
-    let recipient_addr = deps.api.addr_validate(&recipient)?;
-    let balance = deps
-        .querier
-        .query_balance(env.contract.address.to_string(), &config.native_denom)?;
+    for buyer in &remove_buyers {
+        SALE_BUYERS.remove(deps.storage, (id, buyer.as_str()));
+    }
+    let old_closed = sale.closed;
+    if let Some(closed) = closed {
+        sale.closed = closed;
+        SALES.save(deps.storage, id, &sale)?;
+        // A closed sale's unsold inventory is free again until it reopens
+        if closed {
+            RESERVES.remove(deps.storage, &sale_reserve(id));
+        } else {
+            RESERVES.save(deps.storage, &sale_reserve(id), &sale.inventory.saturating_sub(sale.sold))?;
+        }
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_sale",
+        Some(format!("sale {} closed {}", id, old_closed)),
+        Some(format!(
+            "closed {}, +{} buyers, -{} buyers",
+            sale.closed,
+            add_buyers.len(),
+            remove_buyers.len()
+        )),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_sale")
+        .add_attribute("sale_id", id.to_string())
+        .add_attribute("closed", sale.closed.to_string()))
+}
 
-    if balance.amount.is_zero() {
-        return Ok(Response::new()
-            .add_attribute("method", "emergency_withdraw")
-            .add_attribute("message", "no_funds"));
+fn set_pool(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    label: String,
+    cap: Uint128,
+    add_buyers: Vec<String>,
+    remove_buyers: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    if label.is_empty()
+        || label.len() > MAX_POOL_LABEL_LEN
+        || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(ContractError::InvalidPool {
+            reason: format!("label must be 1-{} letters, digits, '-' or '_'", MAX_POOL_LABEL_LEN),
+        });
+    }
+    if cap.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let batch = add_buyers.len() + remove_buyers.len();
+    if batch > MAX_WHITELIST_BATCH {
+        return Err(ContractError::WhitelistBatchTooLarge { size: batch, max: MAX_WHITELIST_BATCH });
+    }
+    let old = POOLS.may_load(deps.storage, &label)?;
+    if old.is_none() && POOLS.keys(deps.storage, None, None, Order::Ascending).count() >= MAX_POOLS {
+        return Err(ContractError::InvalidPool { reason: format!("at most {} pools", MAX_POOLS) });
+    }
+    let mut pool = old.clone().unwrap_or(InventoryPool {
+        cap,
+        sold: Uint128::zero(),
+        raised_usd: Uint128::zero(),
+        purchases: 0,
+    });
+    if cap < pool.sold {
+        return Err(ContractError::InvalidPool {
+            reason: format!("cap {} is below the {} already sold", cap, pool.sold),
+        });
+    }
+    pool.cap = cap;
+    POOLS.save(deps.storage, &label, &pool)?;
+    for buyer in &add_buyers {
+        let buyer = deps.api.addr_validate(buyer)?;
+        POOL_BUYERS.save(deps.storage, (label.as_str(), buyer.as_str()), &())?;
+    }
+    for buyer in &remove_buyers {
+        POOL_BUYERS.remove(deps.storage, (label.as_str(), buyer.as_str()));
     }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_pool",
+        old.map(|old| format!("{}: cap {}", label, old.cap)),
+        Some(format!(
+            "{}: cap {}, +{} buyers, -{} buyers",
+            label,
+            cap,
+            add_buyers.len(),
+            remove_buyers.len()
+        )),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_pool")
+        .add_attribute("pool", label)
+        .add_attribute("cap", cap))
+}
 
-    let send_msg = BankMsg::Send {
-        to_address: recipient_addr.to_string(),
-        amount: vec![balance.clone()],
-    };
+fn remove_pool(deps: DepsMut, env: Env, info: MessageInfo, label: String) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let pool = POOLS
+        .may_load(deps.storage, &label)?
+        .ok_or_else(|| ContractError::PoolNotFound { label: label.clone() })?;
+    // Pools that sold stay for their accounting
+    if !pool.sold.is_zero() {
+        return Err(ContractError::InvalidPool { reason: format!("pool {} has sales", label) });
+    }
+    let buyers = POOL_BUYERS
+        .prefix(label.as_str())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for buyer in &buyers {
+        POOL_BUYERS.remove(deps.storage, (label.as_str(), buyer.as_str()));
+    }
+    POOLS.remove(deps.storage, &label);
+    record_audit(deps.storage, &env, &info.sender, "remove_pool", Some(to_json_string(&pool)?), None)?;
     Ok(Response::new()
-        .add_message(send_msg)
-        .add_attribute("method", "emergency_withdraw")
-        .add_attribute("amount", balance.amount)
-        .add_attribute("recipient", recipient))
+        .add_attribute("method", "remove_pool")
+        .add_attribute("pool", label))
 }
 
-#[entry_point]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
-        QueryMsg::NativeBalance {} => to_json_binary(&query_native_balance(deps, env)?),
-        QueryMsg::CalculateTokens { usd_amount } => to_json_binary(&query_calculate_tokens(deps, usd_amount)?),
-        QueryMsg::TestBridgeValidation { cw20_contract } => to_json_binary(&query_test_bridge_validation(deps, cw20_contract)?),
-        QueryMsg::BlockHeight {} => to_json_binary(&query_block_height(env)?),
-        QueryMsg::TestApprovedTokens {} => to_json_binary(&query_test_approved_tokens(deps)?),
+/// Pool a main-sale purchase of `tokens` by `buyer` draws from: the first of the buyer's pools
+/// with room, trying pools in label order after the one the previous purchase drew from.
+/// `None` while no pools exist.
+fn select_pool(storage: &dyn Storage, buyer: &str, tokens: Uint128) -> Result<Option<String>, ContractError> {
+    let pools = POOLS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if pools.is_empty() {
+        return Ok(None);
+    }
+    let start = POOL_CURSOR
+        .may_load(storage)?
+        .and_then(|cursor| pools.iter().position(|(label, _)| *label > cursor))
+        .unwrap_or(0);
+    let mut eligible = false;
+    let mut most_remaining = Uint128::zero();
+    for i in 0..pools.len() {
+        let (label, pool) = &pools[(start + i) % pools.len()];
+        if !POOL_BUYERS.has(storage, (label.as_str(), buyer)) {
+            continue;
+        }
+        eligible = true;
+        let remaining = pool.cap.saturating_sub(pool.sold);
+        if tokens <= remaining {
+            return Ok(Some(label.clone()));
+        }
+        most_remaining = most_remaining.max(remaining);
     }
+    if !eligible {
+        return Err(ContractError::BuyerNotAllowed { buyer: buyer.to_string() });
+    }
+    Err(ContractError::PoolCapacityExceeded {
+        remaining: most_remaining.u128(),
+        requested: tokens.u128(),
+    })
 }
 
-#[entry_point]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: Binary) -> Result<Response, ContractError> {
-    let old = get_contract_version(deps.storage)
-        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
-        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
-    Ok(Response::new()
-        .add_attribute("action", "migrate")
-        .add_attribute("from_version", old.version)
-        .add_attribute("to_version", CONTRACT_VERSION))
+fn record_pool_draw(
+    storage: &mut dyn Storage,
+    label: &str,
+    tokens: Uint128,
+    usd_amount: Uint128,
+) -> Result<(), ContractError> {
+    let mut pool = POOLS
+        .may_load(storage, label)?
+        .ok_or_else(|| ContractError::PoolNotFound { label: label.to_string() })?;
+    pool.sold = pool
+        .sold
+        .checked_add(tokens)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    pool.raised_usd = pool
+        .raised_usd
+        .checked_add(usd_amount)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    pool.purchases += 1;
+    POOLS.save(storage, label, &pool)?;
+    POOL_CURSOR.save(storage, &label.to_string())?;
+    Ok(())
 }
 
-fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+/// Just the version of a purchase payload, read first so a payload from a newer integrator
+/// fails on its version rather than on fields this contract does not know
+#[derive(serde::Deserialize)]
+struct PurchaseMsgVersion {
+    #[serde(default)]
+    v: Option<u32>,
+}
+
+fn parse_purchase_msg(msg: &Binary) -> Result<PurchaseTokenMsg, ContractError> {
+    let version = from_json::<PurchaseMsgVersion>(msg)?.v.unwrap_or(1);
+    if version == 0 || version > PURCHASE_MSG_VERSION {
+        return Err(ContractError::UnsupportedPayloadVersion { version, max: PURCHASE_MSG_VERSION });
+    }
+    Ok(from_json(msg)?)
+}
+
+fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    Ok(ConfigResponse {
-        admin: config.admin,
-        buyer: config.buyer,
-        accepted_chain_id: config.accepted_chain_id,
-        accepted_eth_contract: config.accepted_eth_contract,
-        price_usd: config.price_usd,
-        native_denom: config.native_denom,
-        is_paused: config.is_paused,
-        total_tokens_sold: config.total_tokens_sold,
-    })
+
+    ensure_not_paused(config.is_paused)?;
+
+    let purchase_msg = parse_purchase_msg(&cw20_msg.msg)?;
+    execute_purchase(deps, env, config, info.sender.to_string(), cw20_msg.sender, cw20_msg.amount, purchase_msg)
 }
 
-fn query_native_balance(deps: Deps, env: Env) -> StdResult<NativeBalanceResponse> {
+/// Buys with `usd_amount` of `cw20_contract` pulled from the buyer's allowance, for wallets
+/// that cannot attach a payload to a CW20 Send. A plain purchase, as with an empty payload.
+fn purchase_via_allowance(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    usd_amount: Uint128,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let balance = deps
-        .querier
-        .query_balance(&env.contract.address, &config.native_denom)?;
-    Ok(NativeBalanceResponse { balance })
+    ensure_not_paused(config.is_paused)?;
+    let cw20_contract = deps.api.addr_validate(&token)?.to_string();
+    let buyer = info.sender.to_string();
+
+    let purchase_msg = PurchaseTokenMsg {
+        v: None,
+        payout_denom: None,
+        salt: None,
+        exact_out: None,
+        intent: None,
+        sale_id: None,
+        reservation_id: None,
+        quote_id: None,
+    };
+    let mut response = execute_purchase(
+        deps.branch(),
+        env.clone(),
+        config,
+        cw20_contract.clone(),
+        buyer.clone(),
+        usd_amount,
+        purchase_msg,
+    )?;
+
+    // The purchase runs its own checks first; a short allowance or balance would only fail
+    // the pull below, with a less helpful error
+    let (balance, allowance) = query_cw20_funding(deps.as_ref(), &env, &cw20_contract, &buyer)?;
+    if usd_amount > allowance {
+        return Err(ContractError::InsufficientAllowance {
+            allowance: allowance.u128(),
+            required: usd_amount.u128(),
+        });
+    }
+    if usd_amount > balance {
+        return Err(ContractError::InsufficientPayment {
+            sent: balance.u128(),
+            required: usd_amount.u128(),
+        });
+    }
+
+    // The payment must land before the messages that forward or refund it
+    let pull = create_cw20_transfer_from_msg(cw20_contract, buyer, env.contract.address.to_string(), usd_amount)?;
+    response.messages.insert(0, SubMsg::new(pull));
+    Ok(response.add_attribute("payment", "allowance"))
 }
 
-fn query_calculate_tokens(deps: Deps, usd_amount: Uint128) -> StdResult<TokenCalculationResponse> {
-    let config = CONFIG.load(deps.storage)?;
-    let tokens = calculate_tokens_for_usd(usd_amount, config.price_usd);
-    Ok(TokenCalculationResponse {
-        tokens,
-        price_usd: config.price_usd,
-    })
-}
+/// Runs a purchase paid with `sent_usd` of `cw20_contract` from `payer`, who buys unless the
+/// payload carries a signed intent
+fn execute_purchase(
+    mut deps: DepsMut,
+    env: Env,
+    config: Config,
+    cw20_contract: String,
+    payer: String,
+    sent_usd: Uint128,
+    purchase_msg: PurchaseTokenMsg,
+) -> Result<Response, ContractError> {
+    // A relayer may pay for a purchase the buyer signed off-chain; otherwise the payer buys
+    let buyer = match &purchase_msg.intent {
+        Some(intent) => verify_purchase_intent(deps.branch(), &env, &cw20_contract, intent, &purchase_msg, sent_usd)?,
+        None => payer.clone(),
+    };
+
+    // Local checks run first: the attestation and payment-token validations below are chain
+    // and cross-contract queries, far costlier than reading our own storage
+    if sent_usd.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    // Payments addressed to a CreateSale sale follow that sale's buyer list, price and inventory
+    if let Some(sale_id) = purchase_msg.sale_id {
+        return purchase_from_sale(deps, &env, config, sale_id, buyer, cw20_contract, sent_usd);
+    }
+
+    // Check 1: Only the designated buyer or whitelisted buyers can purchase
+    let whitelist_entry = WHITELIST.may_load(deps.storage, &buyer)?;
+    if buyer != config.buyer && whitelist_entry.is_none() {
+        return Err(ContractError::BuyerNotAllowed {
+            buyer: buyer.clone(),
+        });
+    }
+
+    // During a lottery's commit window payments are entries, not purchases
+    let lottery = LOTTERY.may_load(deps.storage)?.filter(|lottery| lottery.seed.is_none());
+    let round = if purchase_msg.reservation_id.is_none() && lottery.is_none() {
+        // With a round schedule, purchases only run inside a round, at its price and for its treasury
+        let round = active_round(deps.storage, env.block.height)?;
+        if round.is_none() && !ROUNDS.is_empty(deps.storage) {
+            return Err(ContractError::NoActiveRound {});
+        }
+        // A used-up raise cap or allocation fails any purchase, whatever it turns out to cost
+        if config.max_raise_usd.is_some_and(|max_raise_usd| config.total_raised_usd >= max_raise_usd) {
+            return Err(ContractError::RaiseCapExceeded { remaining_usd: 0, requested_usd: sent_usd.u128() });
+        }
+        if let Some(entry) = whitelist_entry.as_ref().filter(|_| buyer != config.buyer) {
+            if entry.purchased_usd >= entry.allocation_usd {
+                return Err(ContractError::AllocationExceeded { buyer: buyer.clone(), remaining: 0 });
+            }
+        }
+        round
+    } else {
+        None
+    };
+
+    if config.require_attestation {
+        ensure_attested(deps.as_ref(), &buyer)?;
+    }
+
+    // Checks 2 and 3: a chain-approved bridge token wrapping the accepted external token
+    validate_payment_token(deps.as_ref(), &config, &cw20_contract)?;
+
+    // Payments referencing a reservation fund it instead of buying directly
+    if let Some(id) = purchase_msg.reservation_id {
+        return fund_reservation(deps, &env, config, id, buyer, cw20_contract, sent_usd);
+    }
+    if let Some(lottery) = lottery {
+        return commit_lottery(deps, &env, lottery, buyer, cw20_contract, sent_usd);
+    }
+
+    let payout_denom = resolve_payout_denom(&config, purchase_msg.payout_denom)?;
+
+    // A negotiated buyer price applies ahead of the round, per-token and default prices
+    let mut commitment_deposit = Uint128::zero();
+    let (price, price_source) = match (&purchase_msg.salt, purchase_msg.quote_id) {
+        (Some(salt), _) => {
+            let commitment = reveal_commitment(deps.branch(), &env, &config, &buyer, &cw20_contract, sent_usd, salt)?;
+            commitment_deposit = commitment.deposit;
+            (commitment.price, "commitment")
+        }
+        (None, Some(quote_id)) => (use_quote(deps.branch(), &env, quote_id, &buyer, &cw20_contract)?, "quote"),
+        (None, None) => match BUYER_PRICES.may_load(deps.storage, &buyer)? {
+            Some(price) => (price, "buyer"),
+            None => price_with_source(deps.as_ref(), &env, &config, &cw20_contract)?,
+        },
+    };
+    if let Some(floor) = config.price_floor_usd {
+        if price < floor {
+            return Err(ContractError::PriceBelowFloor { price: price.u128(), floor: floor.u128() });
+        }
+    }
+
+    // Exact-out purchases spend only what `tokens_out` costs and refund the rest. An intent
+    // quoted in GNK is one, capped at the signed payment, whatever the relayer attached.
+    let exact_out = match purchase_msg.intent.as_ref().and_then(|intent| intent.tokens_out) {
+        Some(tokens_out) => Some(PurchaseExactOut { tokens_out, max_usd_in: sent_usd }),
+        None => purchase_msg.exact_out,
+    };
+    let (usd_amount, tokens_to_buy) = match &exact_out {
+        Some(exact_out) => {
+            let required = calculate_usd_for_tokens(exact_out.tokens_out, price, config.payout_decimals)
+                .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+            if required > exact_out.max_usd_in {
+                return Err(ContractError::MaxInputExceeded {
+                    required: required.u128(),
+                    max_usd_in: exact_out.max_usd_in.u128(),
+                });
+            }
+            if required > sent_usd {
+                return Err(ContractError::InsufficientPayment {
+                    sent: sent_usd.u128(),
+                    required: required.u128(),
+                });
+            }
+            (required, exact_out.tokens_out)
+        }
+        // Only the cost of the whole base units bought is charged; the dust is refunded
+        None => {
+            let (tokens, cost) = split_usd_payment(sent_usd, price, config.payout_decimals)
+                .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+            (cost, tokens)
+        }
+    };
+    let refund_usd = sent_usd - usd_amount;
+    let total_raised_usd = check_raise_cap(&config, usd_amount)?;
+
+    // Whitelisted buyers are bounded by their allocation; the designated buyer is not
+    if buyer != config.buyer {
+        if let Some(mut entry) = whitelist_entry {
+            let remaining = entry.allocation_usd.saturating_sub(entry.purchased_usd);
+            if usd_amount > remaining {
+                return Err(ContractError::AllocationExceeded {
+                    buyer: buyer.clone(),
+                    remaining: remaining.u128(),
+                });
+            }
+            entry.purchased_usd += usd_amount;
+            WHITELIST.save(deps.storage, &buyer, &entry)?;
+        }
+    }
+
+    if let Some(limits) = resolve_purchase_limits(deps.as_ref(), &config)? {
+        check_purchase_limits(&limits, usd_amount, price)?;
+    }
+
+    if tokens_to_buy.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    consume_purchase_approval(deps.storage, &env, &config, &buyer, usd_amount)?;
+    let epoch_raised = check_epoch_cap(deps.as_ref(), usd_amount)?;
+    let kickback_usd = validator_kickback_usd(&config, usd_amount);
+
+    // Check contract balance of the selected payout denom
+    let balance_u128 = available_payout(deps.as_ref(), &env, &config, &payout_denom)?;
+
+    if tokens_to_buy > balance_u128 {
+        return Err(ContractError::InsufficientBalance {
+            available: balance_u128.u128(),
+            needed: tokens_to_buy.u128(),
+        });
+    }
+    let pool = select_pool(deps.storage, &buyer, tokens_to_buy)?;
+
+    // Update total sold
+    let mut updated_config = config.clone();
+    updated_config.total_raised_usd = total_raised_usd;
+    updated_config.total_tokens_sold = updated_config
+        .total_tokens_sold
+        .checked_add(tokens_to_buy)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    let breaker_event = record_sale_volume(deps.storage, &env, &mut updated_config, usd_amount)?;
+    CONFIG.save(deps.storage, &updated_config)?;
+    TOKENS_SOLD_BY_DENOM.update(deps.storage, &payout_denom, |sold| -> Result<_, ContractError> {
+        sold.unwrap_or_default()
+            .checked_add(tokens_to_buy)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+    })?;
+    LAST_PURCHASE_HEIGHT.save(deps.storage, &env.block.height)?;
+    record_purchase_stats(deps.storage, &buyer, &cw20_contract, usd_amount)?;
+    record_refund(deps.storage, refund_usd)?;
+    if let Some((epoch, raised)) = epoch_raised {
+        EPOCH_RAISED.save(deps.storage, epoch, &raised)?;
+    }
+    if let Some(label) = &pool {
+        record_pool_draw(deps.storage, label, tokens_to_buy, usd_amount)?;
+    }
+    if let Some((round_id, round)) = &round {
+        let mut round = round.clone();
+        round.raised_usd = round
+            .raised_usd
+            .checked_add(usd_amount)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+        round.tokens_sold = round
+            .tokens_sold
+            .checked_add(tokens_to_buy)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+        ROUNDS.save(deps.storage, *round_id, &round)?;
+    }
+
+    let receipt = match &config.receipt_nft_contract {
+        Some(cw721_contract) => Some(create_receipt_mint_msg(
+            deps.storage,
+            cw721_contract,
+            &buyer,
+            ReceiptMetadata {
+                price_usd: price,
+                usd_amount,
+                tokens_purchased: tokens_to_buy,
+                payout_denom: payout_denom.clone(),
+                purchase_height: env.block.height,
+            },
+        )?),
+        None => None,
+    };
+
+    // Send GNK (or the selected alternative denom) to buyer, or lock it in a vesting contract
+    let payout = Coin {
+        denom: payout_denom.clone(),
+        amount: tokens_to_buy.into(),
+    };
+    let mut response = Response::new();
+    match &config.vesting {
+        Some(vesting) => {
+            let (vesting_msg, vesting_addr) =
+                create_vesting_instantiate_msg(deps.branch(), &env, &config, vesting, &buyer, payout)?;
+            response = response
+                .add_message(vesting_msg)
+                .add_attribute("vesting_contract", vesting_addr);
+        }
+        None => {
+            response = response.add_message(BankMsg::Send {
+                to_address: buyer.clone(),
+                amount: vec![payout],
+            });
+        }
+    }
+    if !commitment_deposit.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: buyer.clone(),
+            amount: vec![Coin {
+                denom: config.native_denom.clone(),
+                amount: commitment_deposit.into(),
+            }],
+        });
+    }
+
+    if let Some((mint_msg, token_id)) = receipt {
+        response = response
+            .add_message(mint_msg)
+            .add_attribute("receipt_token_id", token_id);
+    }
+
+    if let Some(kickback) = config.validator_kickback.as_ref().filter(|_| !kickback_usd.is_zero()) {
+        response = response
+            .add_message(create_kickback_msg(&env, kickback, cw20_contract.clone(), None, kickback_usd)?)
+            .add_attribute("validator_kickback_usd", kickback_usd);
+    }
+
+    // Forward the rest of the W(USDT) to the round's treasury, or the admin
+    let treasury = round
+        .as_ref()
+        .and_then(|(_, round)| round.treasury.clone())
+        .unwrap_or_else(|| config.proceeds_recipient());
+    let treasury_amount = usd_amount - kickback_usd;
+    if !treasury.is_empty() && !treasury_amount.is_zero() {
+        response = route_proceeds(deps.storage, &config, response, cw20_contract.clone(), treasury, treasury_amount)?;
+    }
+    if let Some((round_id, _)) = &round {
+        response = response.add_attribute("round_id", round_id.to_string());
+    }
+
+    if !refund_usd.is_zero() {
+        response = response
+            .add_message(create_cw20_transfer_msg(cw20_contract.clone(), payer.clone(), refund_usd)?)
+            .add_attribute("refund_usd", refund_usd);
+        if exact_out.is_none() {
+            response = response.add_attribute("dust_refund_usd", refund_usd);
+        }
+    }
+    if payer != buyer {
+        response = response.add_attribute("relayer", payer);
+    }
+    if let Some(label) = pool {
+        response = response.add_attribute("pool", label);
+    }
+    if let Some(event) = breaker_event {
+        response = response.add_event(event);
+    }
+
+    Ok(response
+        .add_attribute("method", "purchase")
+        .add_attribute("buyer", buyer)
+        .add_attribute("usdt_amount", usd_amount)
+        .add_attribute("gnk_purchased", tokens_to_buy)
+        .add_attribute("payout_denom", payout_denom)
+        .add_attribute("price_usd", price)
+        .add_attribute("price_source", price_source))
+}
+
+/// Checks a relayed purchase intent and consumes its nonce, returning the buyer
+fn verify_purchase_intent(
+    deps: DepsMut,
+    env: &Env,
+    cw20_contract: &str,
+    intent: &SignedPurchaseIntent,
+    purchase_msg: &PurchaseTokenMsg,
+    amount: Uint128,
+) -> Result<String, ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidIntent { reason: reason.to_string() };
+    if env.block.height > intent.deadline_height {
+        return Err(ContractError::IntentExpired { deadline_height: intent.deadline_height });
+    }
+    if intent.amount != amount {
+        return Err(invalid("amount differs from the payment"));
+    }
+    let buyer = deps.api.addr_validate(&intent.buyer)?.to_string();
+    let next_nonce = PURCHASE_NONCES.may_load(deps.storage, &buyer)?.unwrap_or_default();
+    if intent.nonce != next_nonce {
+        return Err(invalid(&format!("expected nonce {}", next_nonce)));
+    }
+    // Account addresses are ripemd160(sha256(pubkey)), tying the key to the buyer
+    let key_hash = Ripemd160::digest(Sha256::digest(intent.pubkey.as_slice()));
+    if deps.api.addr_canonicalize(&buyer)?.as_slice() != key_hash.as_slice() {
+        return Err(invalid("pubkey does not belong to buyer"));
+    }
+    let hash = purchase_intent_hash(&PurchaseIntentSignDoc {
+        chain_id: env.block.chain_id.clone(),
+        contract: env.contract.address.to_string(),
+        cw20_contract: cw20_contract.to_string(),
+        buyer: buyer.clone(),
+        amount,
+        nonce: intent.nonce,
+        deadline_height: intent.deadline_height,
+        tokens_out: intent.tokens_out,
+        payout_denom: purchase_msg.payout_denom.clone(),
+        exact_out: purchase_msg.exact_out.clone(),
+        salt: purchase_msg.salt.clone(),
+        sale_id: purchase_msg.sale_id,
+        reservation_id: purchase_msg.reservation_id,
+        quote_id: purchase_msg.quote_id,
+    })?;
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, intent.signature.as_slice(), intent.pubkey.as_slice())
+        .map_err(|e| invalid(&e.to_string()))?;
+    if !valid {
+        return Err(invalid("signature does not verify"));
+    }
+    PURCHASE_NONCES.save(deps.storage, &buyer, &(next_nonce + 1))?;
+    Ok(buyer)
+}
+
+/// The validator kickback's share of a `usd_amount` payment, in the payment's stablecoin
+fn validator_kickback_usd(config: &Config, usd_amount: Uint128) -> Uint128 {
+    config
+        .validator_kickback
+        .as_ref()
+        .map_or(Uint128::zero(), |kickback| usd_amount.multiply_ratio(kickback.bps, MAX_BPS))
+}
+
+/// Returns the raised total after collecting `usd_amount`, failing if it would pass the cap
+fn check_raise_cap(config: &Config, usd_amount: Uint128) -> Result<Uint128, ContractError> {
+    let total = config
+        .total_raised_usd
+        .checked_add(usd_amount)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    if let Some(max_raise_usd) = config.max_raise_usd {
+        if total > max_raise_usd {
+            return Err(ContractError::RaiseCapExceeded {
+                remaining_usd: max_raise_usd.saturating_sub(config.total_raised_usd).u128(),
+                requested_usd: usd_amount.u128(),
+            });
+        }
+    }
+    Ok(total)
+}
+
+/// Builds the cw721 mint of the next purchase receipt, returning the message and token id
+fn create_receipt_mint_msg(
+    storage: &mut dyn Storage,
+    cw721_contract: &str,
+    buyer: &str,
+    metadata: ReceiptMetadata,
+) -> Result<(WasmMsg, String), ContractError> {
+    let id = NEXT_RECEIPT_ID.may_load(storage)?.unwrap_or(1);
+    NEXT_RECEIPT_ID.save(storage, &(id + 1))?;
+    let token_id = id.to_string();
+    let msg = WasmMsg::Execute {
+        contract_addr: cw721_contract.to_string(),
+        msg: to_json_binary(&ReceiptNftExecuteMsg::Mint {
+            token_id: token_id.clone(),
+            owner: buyer.to_string(),
+            token_uri: None,
+            extension: metadata,
+        })?,
+        funds: vec![],
+    };
+    Ok((msg, token_id))
+}
+
+/// Consumes the buyer's commitment for (usd_amount, salt), releasing its deposit for the caller
+/// to return. The commitment carries the price locked at commit time.
+fn reveal_commitment(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    buyer: &str,
+    cw20_contract: &str,
+    usd_amount: Uint128,
+    salt: &Binary,
+) -> Result<PurchaseCommitment, ContractError> {
+    if config.commit_reveal_window_blocks == 0 {
+        return Err(ContractError::CommitRevealDisabled {});
+    }
+    let hash = purchase_commitment_hash(usd_amount, salt.as_slice());
+    let commitment = PURCHASE_COMMITMENTS
+        .may_load(deps.storage, (buyer, hash.as_slice()))?
+        .filter(|c| c.cw20_contract == cw20_contract)
+        .ok_or(ContractError::CommitmentNotFound {})?;
+    if env.block.height <= commitment.committed_at_height {
+        return Err(ContractError::RevealTooEarly {
+            committed_at_height: commitment.committed_at_height,
+        });
+    }
+    let expired_at_height = commitment
+        .expires_at_height
+        .unwrap_or(commitment.committed_at_height + config.commit_reveal_window_blocks);
+    if env.block.height > expired_at_height {
+        return Err(ContractError::CommitmentExpired { expired_at_height });
+    }
+    PURCHASE_COMMITMENTS.remove(deps.storage, (buyer, hash.as_slice()));
+    if let Some(expires_at_height) = commitment.expires_at_height {
+        PURCHASE_COMMITMENT_EXPIRIES.remove(deps.storage, (expires_at_height, buyer, hash.as_slice()));
+    }
+    update_reserve(deps.storage, RESERVE_COMMITMENTS, commitment.deposit, false)?;
+    Ok(commitment)
+}
+
+/// Consumes the buyer's quote, returning its price
+fn use_quote(deps: DepsMut, env: &Env, id: u64, buyer: &str, cw20_contract: &str) -> Result<Uint128, ContractError> {
+    let quote = QUOTES
+        .may_load(deps.storage, id)?
+        .filter(|quote| quote.buyer == buyer)
+        .ok_or(ContractError::QuoteNotFound { id })?;
+    if quote.cw20_contract.as_deref().is_some_and(|quoted| quoted != cw20_contract) {
+        return Err(ContractError::QuoteNotFound { id });
+    }
+    if env.block.height > quote.expires_at_height {
+        return Err(ContractError::QuoteExpired { expired_at_height: quote.expires_at_height });
+    }
+    QUOTES.remove(deps.storage, id);
+    QUOTE_EXPIRIES.remove(deps.storage, (quote.expires_at_height, id));
+    Ok(quote.price)
+}
+
+/// Price for a payment in `cw20_contract`: the active round's price, else the current epoch's
+/// scheduled price, else the per-token override, else the default price
+fn effective_price(deps: Deps, env: &Env, config: &Config, cw20_contract: &str) -> StdResult<Uint128> {
+    price_with_source(deps, env, config, cw20_contract).map(|(price, _)| price)
+}
+
+/// The price a payment in `cw20_contract` buys at, and which setting it came from
+fn price_with_source(deps: Deps, env: &Env, config: &Config, cw20_contract: &str) -> StdResult<(Uint128, &'static str)> {
+    let (price, source) = base_price(deps, env, config)?;
+    if source != "default" {
+        return Ok((price, source));
+    }
+    Ok(match TOKEN_PRICES.may_load(deps.storage, cw20_contract)? {
+        Some(price) => (price, "token"),
+        None => (price, source),
+    })
+}
+
+/// The price before per-token overrides: the active round's, else the current epoch's, else the default
+fn base_price(deps: Deps, env: &Env, config: &Config) -> StdResult<(Uint128, &'static str)> {
+    if let Some((_, round)) = active_round(deps.storage, env.block.height)? {
+        return Ok((round.price_usd, "round"));
+    }
+    if let Some(price) = current_epoch_sale(deps)?.and_then(|(_, sale)| sale.price_usd) {
+        return Ok((price, "epoch"));
+    }
+    Ok((config.price_usd, "default"))
+}
+
+/// Builds an Instantiate2 message for the buyer's next vesting contract, funded with the payout,
+/// and records the predicted address so it can be looked up before the contract exists.
+fn create_vesting_instantiate_msg(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    vesting: &VestingConfig,
+    buyer: &str,
+    payout: Coin,
+) -> Result<(CosmosMsg, String), ContractError> {
+    let index = VESTING_CONTRACTS
+        .prefix(buyer)
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?
+        .map_or(0, |last| last + 1);
+
+    // Salt commits to (buyer, index) so every purchase gets its own predictable address
+    let mut hasher = Sha256::new();
+    hasher.update(buyer.as_bytes());
+    hasher.update(index.to_be_bytes());
+    let salt = Binary::from(hasher.finalize().to_vec());
+
+    let checksum = deps.querier.query_wasm_code_info(vesting.code_id)?.checksum;
+    let creator = deps.api.addr_canonicalize(env.contract.address.as_str())?;
+    let canonical = instantiate2_address(checksum.as_slice(), &creator, &salt)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("instantiate2 address: {}", e))))?;
+    let vesting_addr = deps.api.addr_humanize(&canonical)?.to_string();
+    VESTING_CONTRACTS.save(deps.storage, (buyer, index), &vesting_addr)?;
+    let start = env.block.time.seconds();
+    VESTING_UNLOCKS.save(deps.storage, (start + vesting.cliff_seconds, &vesting_addr), &())?;
+    VESTING_UNLOCKS.save(deps.storage, (start + vesting.duration_seconds, &vesting_addr), &())?;
+
+    let init_msg = VestingInstantiateMsg {
+        beneficiary: buyer.to_string(),
+        start_time: env.block.time,
+        cliff_seconds: vesting.cliff_seconds,
+        duration_seconds: vesting.duration_seconds,
+    };
+    let msg = WasmMsg::Instantiate2 {
+        admin: Some(config.admin.clone()),
+        code_id: vesting.code_id,
+        label: format!("community-sale-vesting-{}-{}", buyer, index),
+        msg: to_json_binary(&init_msg)?,
+        funds: vec![payout],
+        salt,
+    };
+    Ok((msg.into(), vesting_addr))
+}
+
+fn validate_vesting_config(vesting: &VestingConfig) -> Result<(), ContractError> {
+    if vesting.duration_seconds == 0 {
+        return Err(ContractError::InvalidVestingConfig {
+            reason: "duration_seconds must be positive".to_string(),
+        });
+    }
+    if vesting.cliff_seconds > vesting.duration_seconds {
+        return Err(ContractError::InvalidVestingConfig {
+            reason: "cliff_seconds exceeds duration_seconds".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Resolves the payout denom requested in a purchase, defaulting to the native denom.
+fn resolve_payout_denom(config: &Config, requested: Option<String>) -> Result<String, ContractError> {
+    match requested {
+        None => Ok(config.native_denom.clone()),
+        Some(denom) if denom == config.native_denom => Ok(denom),
+        Some(denom) if config.alternative_payout_denoms.contains(&denom) => Ok(denom),
+        Some(denom) => Err(ContractError::PayoutDenomNotAccepted { denom }),
+    }
+}
+
+fn validate_payout_denoms(denoms: Vec<String>, native_denom: &str) -> Result<Vec<String>, ContractError> {
+    let mut validated: Vec<String> = Vec::with_capacity(denoms.len());
+    for denom in denoms {
+        let denom = denom.trim().to_string();
+        if denom.is_empty() || denom == native_denom || validated.contains(&denom) {
+            return Err(ContractError::PayoutDenomNotAccepted { denom });
+        }
+        validated.push(denom);
+    }
+    Ok(validated)
+}
+
+fn pause_contract(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = set_paused(&mut config.is_paused, true);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(deps.storage, &env, &info.sender, "pause", Some(old_value.to_string()), Some("true".to_string()))?;
+    Ok(Response::new().add_attribute("method", "pause"))
+}
+
+fn resume_contract(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    ensure_migration_complete(deps.storage)?;
+    if FINAL_REPORT.exists(deps.storage) {
+        return Err(ContractError::SaleFinalized {});
+    }
+    let old_value = set_paused(&mut config.is_paused, false);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(deps.storage, &env, &info.sender, "resume", Some(old_value.to_string()), Some("false".to_string()))?;
+    Ok(Response::new().add_attribute("method", "resume"))
+}
+
+fn update_buyer(deps: DepsMut, env: Env, info: MessageInfo, buyer: String) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    ensure_not_timelocked(&config, "update_buyer")?;
+    let validated_buyer = deps.api.addr_validate(&buyer)?.to_string();
+    let old_value = std::mem::replace(&mut config.buyer, validated_buyer.clone());
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(deps.storage, &env, &info.sender, "update_buyer", Some(old_value), Some(validated_buyer.clone()))?;
+    Ok(Response::new()
+        .add_attribute("method", "update_buyer")
+        .add_attribute("buyer", validated_buyer))
+}
+
+fn import_whitelist(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    entries: Vec<(String, Uint128)>,
+    batch: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    if entries.len() > MAX_WHITELIST_BATCH {
+        return Err(ContractError::WhitelistBatchTooLarge {
+            size: entries.len(),
+            max: MAX_WHITELIST_BATCH,
+        });
+    }
+
+    let mut progress = WHITELIST_IMPORT.may_load(deps.storage)?.unwrap_or_default();
+    if batch != progress.next_batch {
+        return Err(ContractError::WhitelistBatchOutOfOrder {
+            expected: progress.next_batch,
+            got: batch,
+        });
+    }
+
+    // Rejects duplicates both within the batch and against already-imported entries
+    for (address, allocation_usd) in &entries {
+        let address = deps.api.addr_validate(address)?.to_string();
+        if WHITELIST.has(deps.storage, &address) {
+            return Err(ContractError::DuplicateWhitelistEntry { address });
+        }
+        if allocation_usd.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+        WHITELIST.save(
+            deps.storage,
+            &address,
+            &WhitelistEntry {
+                allocation_usd: *allocation_usd,
+                purchased_usd: Uint128::zero(),
+            },
+        )?;
+        progress.last_address = Some(address);
+    }
+
+    progress.next_batch += 1;
+    progress.total_imported += entries.len() as u64;
+    WHITELIST_IMPORT.save(deps.storage, &progress)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "import_whitelist",
+        None,
+        Some(format!("batch {}: {} entries", batch, entries.len())),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "import_whitelist")
+        .add_attribute("batch", batch.to_string())
+        .add_attribute("imported", entries.len().to_string())
+        .add_attribute("total_imported", progress.total_imported.to_string()))
+}
+
+fn update_price(deps: DepsMut, env: Env, info: MessageInfo, price_usd: Uint128) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    ensure_not_timelocked(&config, "update_price")?;
+    if price_usd.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let old_value = config.price_usd;
+    config.price_usd = price_usd;
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_price",
+        Some(old_value.to_string()),
+        Some(price_usd.to_string()),
+    )?;
+    record_price_change(deps.storage, &env, &info.sender, None, Some(old_value), Some(price_usd))?;
+    Ok(Response::new()
+        .add_attribute("method", "update_price")
+        .add_attribute("price_usd", price_usd))
+}
+
+fn update_token_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_contract: String,
+    price: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let cw20_contract = deps.api.addr_validate(&cw20_contract)?.to_string();
+    let old_value = TOKEN_PRICES.may_load(deps.storage, &cw20_contract)?;
+    match price {
+        Some(price) => {
+            if price.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            TOKEN_PRICES.save(deps.storage, &cw20_contract, &price)?;
+        }
+        None => TOKEN_PRICES.remove(deps.storage, &cw20_contract),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_token_price",
+        old_value.map(|p| format!("{}: {}", cw20_contract, p)),
+        price.map(|p| format!("{}: {}", cw20_contract, p)),
+    )?;
+    record_price_change(deps.storage, &env, &info.sender, Some(cw20_contract.clone()), old_value, price)?;
+    Ok(Response::new()
+        .add_attribute("method", "update_token_price")
+        .add_attribute("cw20_contract", cw20_contract)
+        .add_attribute("price", price.map(|p| p.to_string()).unwrap_or_else(|| "default".to_string())))
+}
+
+fn update_buyer_price(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    buyer: String,
+    price: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let buyer = deps.api.addr_validate(&buyer)?.to_string();
+    let old_value = BUYER_PRICES.may_load(deps.storage, &buyer)?;
+    match price {
+        Some(price) => {
+            if price.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            BUYER_PRICES.save(deps.storage, &buyer, &price)?;
+        }
+        None => BUYER_PRICES.remove(deps.storage, &buyer),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_buyer_price",
+        old_value.map(|p| format!("{}: {}", buyer, p)),
+        price.map(|p| format!("{}: {}", buyer, p)),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_buyer_price")
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price.map(|p| p.to_string()).unwrap_or_else(|| "default".to_string())))
+}
+
+fn update_commit_reveal_window(deps: DepsMut, env: Env, info: MessageInfo, blocks: u64) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = std::mem::replace(&mut config.commit_reveal_window_blocks, blocks);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_commit_reveal_window",
+        Some(old_value.to_string()),
+        Some(blocks.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_commit_reveal_window")
+        .add_attribute("blocks", blocks.to_string()))
+}
+
+fn set_attestation_required(deps: DepsMut, env: Env, info: MessageInfo, required: bool) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = std::mem::replace(&mut config.require_attestation, required);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_attestation_required",
+        Some(old_value.to_string()),
+        Some(required.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_attestation_required")
+        .add_attribute("required", required.to_string()))
+}
+
+fn set_debug_grpc(deps: DepsMut, env: Env, info: MessageInfo, enabled: bool) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = std::mem::replace(&mut config.debug_grpc_enabled, enabled);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_debug_grpc",
+        Some(old_value.to_string()),
+        Some(enabled.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_debug_grpc")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+fn set_state_export(deps: DepsMut, env: Env, info: MessageInfo, enabled: bool) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = std::mem::replace(&mut config.state_export_enabled, enabled);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_state_export",
+        Some(old_value.to_string()),
+        Some(enabled.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_state_export")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+fn update_receipt_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw721_contract: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let cw721_contract = cw721_contract
+        .map(|addr| deps.api.addr_validate(&addr).map(|a| a.to_string()))
+        .transpose()?;
+    let old_value = std::mem::replace(&mut config.receipt_nft_contract, cw721_contract);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_receipt_nft",
+        old_value,
+        config.receipt_nft_contract.clone(),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_receipt_nft")
+        .add_attribute("cw721_contract", config.receipt_nft_contract.unwrap_or_default()))
+}
+
+fn start_lottery(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    commit_end_height: u64,
+    capacity_usd: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    if LOTTERY.exists(deps.storage) {
+        return Err(ContractError::LotteryActive {});
+    }
+    if commit_end_height <= env.block.height {
+        return Err(ContractError::LotteryClosed { commit_end_height });
+    }
+    if capacity_usd.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    LOTTERY.save(
+        deps.storage,
+        &Lottery {
+            commit_end_height,
+            capacity_usd,
+            price_usd: config.price_usd,
+            total_committed_usd: Uint128::zero(),
+            entries: 0,
+            unclaimed: 0,
+            seed: None,
+            claim_deadline_height: None,
+        },
+    )?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "start_lottery",
+        None,
+        Some(format!("capacity {} until height {}", capacity_usd, commit_end_height)),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "start_lottery")
+        .add_attribute("commit_end_height", commit_end_height.to_string())
+        .add_attribute("capacity_usd", capacity_usd)
+        .add_attribute("price_usd", config.price_usd))
+}
+
+fn commit_lottery(
+    deps: DepsMut,
+    env: &Env,
+    mut lottery: Lottery,
+    buyer: String,
+    cw20_contract: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    if env.block.height > lottery.commit_end_height {
+        return Err(ContractError::LotteryClosed {
+            commit_end_height: lottery.commit_end_height,
+        });
+    }
+    let mut entry = match LOTTERY_ENTRIES.may_load(deps.storage, &buyer)? {
+        Some(entry) => {
+            if entry.cw20_contract != cw20_contract {
+                return Err(ContractError::TokenNotAccepted {
+                    token: format!("lottery commitments of {} must use {}", buyer, entry.cw20_contract),
+                });
+            }
+            entry
+        }
+        None => {
+            if lottery.entries >= MAX_LOTTERY_ENTRIES {
+                return Err(ContractError::LotteryFull { max: MAX_LOTTERY_ENTRIES });
+            }
+            lottery.entries += 1;
+            lottery.unclaimed += 1;
+            LotteryEntry {
+                cw20_contract,
+                committed_usd: Uint128::zero(),
+                won_usd: Uint128::zero(),
+            }
+        }
+    };
+    entry.committed_usd = entry
+        .committed_usd
+        .checked_add(amount)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    lottery.total_committed_usd = lottery
+        .total_committed_usd
+        .checked_add(amount)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    LOTTERY_ENTRIES.save(deps.storage, &buyer, &entry)?;
+    LOTTERY.save(deps.storage, &lottery)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "lottery_commit")
+        .add_attribute("buyer", buyer)
+        .add_attribute("amount", amount)
+        .add_attribute("committed_usd", entry.committed_usd))
+}
+
+fn finalize_lottery(deps: DepsMut, env: &Env, seed: Vec<u8>) -> Result<Response, ContractError> {
+    let mut lottery = LOTTERY.may_load(deps.storage)?.ok_or(ContractError::LotteryNotActive {})?;
+    if lottery.seed.is_some() {
+        return Err(ContractError::LotteryNotActive {});
+    }
+    if env.block.height <= lottery.commit_end_height {
+        return Err(ContractError::LotteryStillOpen {
+            commit_end_height: lottery.commit_end_height,
+        });
+    }
+    if seed.is_empty() {
+        return Err(ContractError::Std(StdError::msg("empty lottery seed")));
+    }
+
+    let entries = LOTTERY_ENTRIES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let commitments: Vec<Uint128> = entries.iter().map(|(_, entry)| entry.committed_usd).collect();
+    // The wins count toward the raise cap now, so the draw sells no more than the cap has room for
+    let mut config = CONFIG.load(deps.storage)?;
+    let capacity_usd = match config.max_raise_usd {
+        Some(max_raise_usd) => lottery.capacity_usd.min(max_raise_usd.saturating_sub(config.total_raised_usd)),
+        None => lottery.capacity_usd,
+    };
+    let won = crate::lottery::select_winners(&commitments, capacity_usd, &seed);
+
+    let mut winners = 0u32;
+    let mut sold_usd = Uint128::zero();
+    let mut won_tokens = Uint128::zero();
+    for ((buyer, mut entry), won_usd) in entries.into_iter().zip(won) {
+        if !won_usd.is_zero() {
+            winners += 1;
+            sold_usd += won_usd;
+            // Per entry, so each claim releases exactly what was held for it
+            won_tokens += calculate_tokens_for_usd(won_usd, lottery.price_usd, config.payout_decimals);
+        }
+        entry.won_usd = won_usd;
+        LOTTERY_ENTRIES.save(deps.storage, &buyer, &entry)?;
+    }
+    update_reserve(deps.storage, RESERVE_LOTTERY, won_tokens, true)?;
+    config.total_raised_usd = check_raise_cap(&config, sold_usd)?;
+    CONFIG.save(deps.storage, &config)?;
+    lottery.seed = Some(Binary::from(seed));
+    let claim_deadline_height = env.block.height + LOTTERY_CLAIM_BLOCKS;
+    lottery.claim_deadline_height = Some(claim_deadline_height);
+    LOTTERY.save(deps.storage, &lottery)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "finalize_lottery")
+        .add_attribute("winners", winners.to_string())
+        .add_attribute("sold_usd", sold_usd)
+        .add_attribute("claim_deadline_height", claim_deadline_height.to_string()))
+}
+
+fn claim_lottery(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut lottery = LOTTERY.may_load(deps.storage)?.ok_or(ContractError::LotteryNotActive {})?;
+    if lottery.seed.is_none() {
+        return Err(ContractError::LotteryStillOpen {
+            commit_end_height: lottery.commit_end_height,
+        });
+    }
+    let buyer = info.sender.to_string();
+    let entry = LOTTERY_ENTRIES
+        .may_load(deps.storage, &buyer)?
+        .ok_or(ContractError::NothingToClaim {})?;
+    LOTTERY_ENTRIES.remove(deps.storage, &buyer);
+
+    let mut config = CONFIG.load(deps.storage)?;
+    let mut response = Response::new();
+    let mut tokens = Uint128::zero();
+    if !entry.won_usd.is_zero() {
+        tokens = calculate_tokens_for_usd(entry.won_usd, lottery.price_usd, config.payout_decimals);
+        let balance: Uint128 = deps
+            .querier
+            .query_balance(env.contract.address.to_string(), &config.native_denom)?
+            .amount
+            .try_into()
+            .map_err(|_| ContractError::Std(StdError::msg("balance exceeds Uint128")))?;
+        if tokens > balance {
+            return Err(ContractError::InsufficientBalance {
+                available: balance.u128(),
+                needed: tokens.u128(),
+            });
+        }
+        config.total_tokens_sold = config
+            .total_tokens_sold
+            .checked_add(tokens)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+        CONFIG.save(deps.storage, &config)?;
+        TOKENS_SOLD_BY_DENOM.update(deps.storage, &config.native_denom, |sold| -> Result<_, ContractError> {
+            sold.unwrap_or_default()
+                .checked_add(tokens)
+                .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+        })?;
+        record_purchase_stats(deps.storage, &buyer, &entry.cw20_contract, entry.won_usd)?;
+        update_reserve(deps.storage, RESERVE_LOTTERY, tokens, false)?;
+        response = response.add_message(BankMsg::Send {
+            to_address: buyer.clone(),
+            amount: vec![Coin {
+                denom: config.native_denom.clone(),
+                amount: tokens.into(),
+            }],
+        });
+        let treasury = config.proceeds_recipient();
+        if !treasury.is_empty() {
+            response = route_proceeds(
+                deps.storage,
+                &config,
+                response,
+                entry.cw20_contract.clone(),
+                treasury,
+                entry.won_usd,
+            )?;
+        }
+    }
+    let refund_usd = entry.committed_usd - entry.won_usd;
+    record_refund(deps.storage, refund_usd)?;
+    if !refund_usd.is_zero() {
+        response = response.add_message(create_cw20_transfer_msg(entry.cw20_contract, buyer.clone(), refund_usd)?);
+    }
+    close_lottery_entry(deps.storage, &mut lottery)?;
+
+    Ok(response
+        .add_attribute("method", "claim_lottery")
+        .add_attribute("buyer", buyer)
+        .add_attribute("won_usd", entry.won_usd)
+        .add_attribute("gnk_purchased", tokens)
+        .add_attribute("refund_usd", refund_usd))
+}
+
+/// Counts one entry as settled, removing the lottery with its last entry
+fn close_lottery_entry(storage: &mut dyn Storage, lottery: &mut Lottery) -> StdResult<()> {
+    lottery.unclaimed = lottery.unclaimed.saturating_sub(1);
+    if lottery.unclaimed == 0 {
+        LOTTERY.remove(storage);
+        Ok(())
+    } else {
+        LOTTERY.save(storage, lottery)
+    }
+}
+
+/// Gives lotteries finalized before claim deadlines existed a full claim window from the upgrade
+fn seed_lottery_claim_deadline(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    if let Some(mut lottery) = LOTTERY.may_load(storage)? {
+        if lottery.seed.is_some() && lottery.claim_deadline_height.is_none() {
+            lottery.claim_deadline_height = Some(env.block.height + LOTTERY_CLAIM_BLOCKS);
+            LOTTERY.save(storage, &lottery)?;
+        }
+    }
+    Ok(())
+}
+
+fn commit_purchase(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    commitment: Binary,
+    cw20_contract: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(config.is_paused)?;
+    if config.commit_reveal_window_blocks == 0 {
+        return Err(ContractError::CommitRevealDisabled {});
+    }
+    let buyer = info.sender.to_string();
+    if buyer != config.buyer && !WHITELIST.has(deps.storage, &buyer) {
+        return Err(ContractError::BuyerNotAllowed { buyer });
+    }
+    if commitment.len() != 32 {
+        return Err(ContractError::Std(StdError::msg("commitment must be a 32-byte sha256 hash")));
+    }
+    if info.funds != [Coin::new(PURCHASE_COMMITMENT_DEPOSIT, &config.native_denom)] {
+        return Err(ContractError::InvalidCommitmentDeposit {
+            amount: PURCHASE_COMMITMENT_DEPOSIT,
+            denom: config.native_denom,
+        });
+    }
+    if PURCHASE_COMMITMENTS.has(deps.storage, (&buyer, commitment.as_slice())) {
+        return Err(ContractError::CommitmentExists {});
+    }
+    let cw20_contract = deps.api.addr_validate(&cw20_contract)?.to_string();
+    let price = effective_price(deps.as_ref(), &env, &config, &cw20_contract)?;
+    let deposit = Uint128::new(PURCHASE_COMMITMENT_DEPOSIT);
+    let expires_at_height = env.block.height + config.commit_reveal_window_blocks;
+    PURCHASE_COMMITMENTS.save(
+        deps.storage,
+        (&buyer, commitment.as_slice()),
+        &PurchaseCommitment {
+            cw20_contract: cw20_contract.clone(),
+            price,
+            committed_at_height: env.block.height,
+            deposit,
+            expires_at_height: Some(expires_at_height),
+        },
+    )?;
+    PURCHASE_COMMITMENT_EXPIRIES.save(deps.storage, (expires_at_height, &buyer, commitment.as_slice()), &())?;
+    update_reserve(deps.storage, RESERVE_COMMITMENTS, deposit, true)?;
+    Ok(Response::new()
+        .add_attribute("method", "commit_purchase")
+        .add_attribute("buyer", buyer)
+        .add_attribute("cw20_contract", cw20_contract)
+        .add_attribute("price", price)
+        .add_attribute("deposit", deposit)
+        .add_attribute("reveal_deadline", expires_at_height.to_string()))
+}
+
+fn request_quote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_contract: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(config.is_paused)?;
+    let buyer = info.sender.to_string();
+    if buyer != config.buyer && !WHITELIST.has(deps.storage, &buyer) {
+        return Err(ContractError::BuyerNotAllowed { buyer });
+    }
+    let cw20_contract = cw20_contract
+        .map(|addr| deps.api.addr_validate(&addr).map(|a| a.to_string()))
+        .transpose()?;
+    // The same precedence a purchase applies: buyer price, then round, per-token and default
+    let price = match BUYER_PRICES.may_load(deps.storage, &buyer)? {
+        Some(price) => price,
+        None => effective_price(deps.as_ref(), &env, &config, cw20_contract.as_deref().unwrap_or_default())?,
+    };
+    let id = NEXT_QUOTE_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_QUOTE_ID.save(deps.storage, &(id + 1))?;
+    let expires_at_height = env.block.height + QUOTE_VALIDITY_BLOCKS;
+    QUOTES.save(
+        deps.storage,
+        id,
+        &Quote {
+            buyer: buyer.clone(),
+            cw20_contract,
+            price,
+            expires_at_height,
+        },
+    )?;
+    QUOTE_EXPIRIES.save(deps.storage, (expires_at_height, id), &())?;
+    Ok(Response::new()
+        .add_attribute("method", "request_quote")
+        .add_attribute("quote_id", id.to_string())
+        .add_attribute("buyer", buyer)
+        .add_attribute("price", price)
+        .add_attribute("expires_at_height", expires_at_height.to_string()))
+}
+
+fn update_vesting_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting: Option<VestingConfig>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    if let Some(vesting) = &vesting {
+        validate_vesting_config(vesting)?;
+    }
+    let code_id = vesting.as_ref().map_or("none".to_string(), |v| v.code_id.to_string());
+    let old_value = std::mem::replace(&mut config.vesting, vesting);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_vesting_config",
+        Some(to_json_string(&old_value)?),
+        Some(to_json_string(&config.vesting)?),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_vesting_config")
+        .add_attribute("vesting_code_id", code_id))
+}
+
+fn update_purchase_limits(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    purchase_limits: Option<PurchaseLimitsSource>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let source = match &purchase_limits {
+        None => "none",
+        Some(PurchaseLimitsSource::Config(_)) => "config",
+        Some(PurchaseLimitsSource::ChainParams {}) => "chain_params",
+    };
+    let old_value = std::mem::replace(&mut config.purchase_limits, purchase_limits);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_purchase_limits",
+        Some(to_json_string(&old_value)?),
+        Some(to_json_string(&config.purchase_limits)?),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_purchase_limits")
+        .add_attribute("source", source))
+}
+
+fn update_payout_denoms(deps: DepsMut, env: Env, info: MessageInfo, denoms: Vec<String>) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let denoms = validate_payout_denoms(denoms, &config.native_denom)?;
+    let old_value = std::mem::replace(&mut config.alternative_payout_denoms, denoms);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_payout_denoms",
+        Some(old_value.join(",")),
+        Some(config.alternative_payout_denoms.join(",")),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_payout_denoms")
+        .add_attribute("denoms", config.alternative_payout_denoms.join(",")))
+}
+
+fn withdraw_native_tokens(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    recipient: String,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    ensure_not_timelocked(&config, "withdraw")?;
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let denom = resolve_payout_denom(&config, denom)?;
+    if denom == config.native_denom {
+        let available = free_native(deps.as_ref(), &env, &config)?;
+        if amount > available {
+            return Err(ContractError::WithdrawalExceedsUnreserved {
+                available: available.u128(),
+                requested: amount.u128(),
+            });
+        }
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "withdraw",
+        None,
+        Some(format!("{}{} to {}", amount, denom, recipient_addr)),
+    )?;
+    let send_msg = BankMsg::Send {
+        to_address: recipient_addr.to_string(),
+        amount: vec![Coin {
+            denom: denom.clone(),
+            amount: amount.into(),
+        }],
+    };
+    Ok(Response::new()
+        .add_message(send_msg)
+        .add_attribute("method", "withdraw")
+        .add_attribute("amount", amount)
+        .add_attribute("denom", denom)
+        .add_attribute("recipient", recipient))
+}
+
+fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.funds.is_empty() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let depositor = info.sender.to_string();
+    let mut response = Response::new()
+        .add_attribute("method", "deposit")
+        .add_attribute("depositor", depositor.clone());
+    for coin in &info.funds {
+        // Only denoms the sale pays out in count as inventory
+        resolve_payout_denom(&config, Some(coin.denom.clone()))?;
+        let amount: Uint128 = coin
+            .amount
+            .try_into()
+            .map_err(|_| ContractError::Std(StdError::msg("deposit exceeds Uint128")))?;
+        DEPOSITS.update(deps.storage, (&depositor, &coin.denom), |total| -> Result<_, ContractError> {
+            total
+                .unwrap_or_default()
+                .checked_add(amount)
+                .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+        })?;
+        response = response.add_attribute("deposited", format!("{}{}", amount, coin.denom));
+    }
+    Ok(response)
+}
+
+fn emergency_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
+
+    // Sweep the native denom and every alternative payout denom
+    let mut balances = vec![];
+    for denom in std::iter::once(&config.native_denom).chain(config.alternative_payout_denoms.iter()) {
+        let balance = deps
+            .querier
+            .query_balance(env.contract.address.to_string(), denom)?;
+        if !balance.amount.is_zero() {
+            balances.push(balance);
+        }
+    }
+
+    if balances.is_empty() {
+        return Ok(Response::new()
+            .add_attribute("method", "emergency_withdraw")
+            .add_attribute("message", "no_funds"));
+    }
+
+    let amount = balances
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "emergency_withdraw",
+        None,
+        Some(format!("{} to {}", amount, recipient_addr)),
+    )?;
+    let send_msg = BankMsg::Send {
+        to_address: recipient_addr.to_string(),
+        amount: balances,
+    };
+    Ok(Response::new()
+        .add_message(send_msg)
+        .add_attribute("method", "emergency_withdraw")
+        .add_attribute("amount", amount)
+        .add_attribute("recipient", recipient))
+}
+
+/// Ends the sale: pauses it for good, sweeps the native tokens no buyer is owed and records the
+/// final report from the running totals
+fn finalize_sale(deps: DepsMut, env: Env, info: MessageInfo, recipient: String) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    if FINAL_REPORT.exists(deps.storage) {
+        return Err(ContractError::SaleFinalized {});
+    }
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
+
+    // Unclaimed lottery wins and open reservations stay behind for their buyers
+    let leftover = free_native(deps.as_ref(), &env, &config)?;
+
+    config.is_paused = true;
+    CONFIG.save(deps.storage, &config)?;
+    let raised_by_token = RAISED_BY_TOKEN
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(cw20_contract, raised_usd)| TokenRaised { cw20_contract, raised_usd }))
+        .collect::<StdResult<Vec<_>>>()?;
+    let report = FinalReport {
+        finalized_height: env.block.height,
+        raised_by_token,
+        total_raised_usd: config.total_raised_usd,
+        total_tokens_sold: config.total_tokens_sold,
+        unique_buyers: UNIQUE_BUYERS.may_load(deps.storage)?.unwrap_or_default(),
+        average_price_usd: average_price_usd(config.total_raised_usd, config.total_tokens_sold, config.payout_decimals),
+        refunded_usd: REFUNDED_USD.may_load(deps.storage)?.unwrap_or_default(),
+        leftover_swept: leftover,
+        swept_to: recipient_addr.to_string(),
+    };
+    FINAL_REPORT.save(deps.storage, &report)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "finalize_sale",
+        None,
+        Some(format!("{}{} to {}", leftover, config.native_denom, recipient_addr)),
+    )?;
+
+    let mut response = Response::new();
+    if !leftover.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: recipient_addr.to_string(),
+            amount: vec![Coin {
+                denom: config.native_denom.clone(),
+                amount: leftover.into(),
+            }],
+        });
+    }
+    Ok(response
+        .add_attribute("method", "finalize_sale")
+        .add_attribute("leftover_swept", leftover)
+        .add_attribute("recipient", recipient_addr)
+        .add_attribute("total_raised_usd", report.total_raised_usd))
+}
+
+/// Adds a completed purchase to the totals the final report is built from
+fn record_purchase_stats(storage: &mut dyn Storage, buyer: &str, cw20_contract: &str, usd_amount: Uint128) -> StdResult<()> {
+    RAISED_BY_TOKEN.update(storage, cw20_contract, |raised| -> StdResult<_> {
+        Ok(raised.unwrap_or_default().checked_add(usd_amount)?)
+    })?;
+    if !PURCHASERS.has(storage, buyer) {
+        PURCHASERS.save(storage, buyer, &())?;
+        let count = UNIQUE_BUYERS.may_load(storage)?.unwrap_or_default();
+        UNIQUE_BUYERS.save(storage, &(count + 1))?;
+    }
+    Ok(())
+}
+
+fn record_refund(storage: &mut dyn Storage, usd_amount: Uint128) -> StdResult<()> {
+    if usd_amount.is_zero() {
+        return Ok(());
+    }
+    let refunded = REFUNDED_USD.may_load(storage)?.unwrap_or_default();
+    REFUNDED_USD.save(storage, &refunded.checked_add(usd_amount)?)
+}
+
+/// The round whose [start_height, end_height) contains `height`
+fn active_round(storage: &dyn Storage, height: u64) -> StdResult<Option<(u32, SaleRound)>> {
+    for item in ROUNDS.range(storage, None, None, Order::Ascending) {
+        let (id, round) = item?;
+        if round.start_height <= height && height < round.end_height {
+            return Ok(Some((id, round)));
+        }
+    }
+    Ok(None)
+}
+
+fn add_round(deps: DepsMut, env: Env, info: MessageInfo, mut round: SaleRound) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let invalid = |reason: &str| ContractError::InvalidRound { reason: reason.to_string() };
+    if round.start_height >= round.end_height {
+        return Err(invalid("start_height must be before end_height"));
+    }
+    if round.price_usd.is_zero() {
+        return Err(invalid("price_usd must be non-zero"));
+    }
+    round.treasury = round
+        .treasury
+        .map(|t| deps.api.addr_validate(&t))
+        .transpose()?
+        .map(|t| t.to_string());
+    let rounds = ROUNDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if rounds.len() >= MAX_ROUNDS {
+        return Err(invalid(&format!("at most {} rounds", MAX_ROUNDS)));
+    }
+    if let Some((id, _)) = rounds
+        .iter()
+        .find(|(_, r)| round.start_height < r.end_height && r.start_height < round.end_height)
+    {
+        return Err(invalid(&format!("overlaps round {}", id)));
+    }
+
+    let id = NEXT_ROUND_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_ROUND_ID.save(deps.storage, &(id + 1))?;
+    ROUNDS.save(deps.storage, id, &round)?;
+    record_audit(deps.storage, &env, &info.sender, "add_round", None, Some(to_json_string(&round)?))?;
+    Ok(Response::new()
+        .add_attribute("method", "add_round")
+        .add_attribute("round_id", id.to_string())
+        .add_attribute("name", round.name))
+}
+
+/// Epochs already over cannot be scheduled; the current one can, so terms can be corrected
+fn set_epoch_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    epochs: Vec<EpochSaleParams>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let invalid = |reason: String| ContractError::InvalidEpochSchedule { reason };
+    if epochs.len() > MAX_EPOCH_SCHEDULE_BATCH {
+        return Err(invalid(format!("at most {} epochs per message", MAX_EPOCH_SCHEDULE_BATCH)));
+    }
+    let current_epoch = query_current_epoch(deps.as_ref())?;
+    for params in &epochs {
+        if params.epoch < current_epoch {
+            return Err(invalid(format!("epoch {} has passed", params.epoch)));
+        }
+        if params.price_usd.is_some_and(|price| price.is_zero()) {
+            return Err(invalid(format!("epoch {} price is zero", params.epoch)));
+        }
+        match (params.price_usd, params.cap_usd) {
+            (None, None) => EPOCH_SCHEDULE.remove(deps.storage, params.epoch),
+            (price_usd, cap_usd) => EPOCH_SCHEDULE.save(deps.storage, params.epoch, &EpochSale { price_usd, cap_usd })?,
+        }
+    }
+    record_audit(deps.storage, &env, &info.sender, "set_epoch_schedule", None, Some(to_json_string(&epochs)?))?;
+    Ok(Response::new()
+        .add_attribute("method", "set_epoch_schedule")
+        .add_attribute("epochs", epochs.len().to_string())
+        .add_attribute("current_epoch", current_epoch.to_string()))
+}
+
+fn remove_round(deps: DepsMut, env: Env, info: MessageInfo, id: u32) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let round = ROUNDS
+        .may_load(deps.storage, id)?
+        .ok_or_else(|| ContractError::InvalidRound { reason: format!("round {} not found", id) })?;
+    // Sold rounds stay for their raised totals
+    if !round.raised_usd.is_zero() {
+        return Err(ContractError::InvalidRound { reason: format!("round {} has sales", id) });
+    }
+    ROUNDS.remove(deps.storage, id);
+    record_audit(deps.storage, &env, &info.sender, "remove_round", Some(to_json_string(&round)?), None)?;
+    Ok(Response::new()
+        .add_attribute("method", "remove_round")
+        .add_attribute("round_id", id.to_string()))
+}
+
+fn update_max_raise(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    max_raise_usd: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = std::mem::replace(&mut config.max_raise_usd, max_raise_usd);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_max_raise",
+        old_value.map(|v| v.to_string()),
+        max_raise_usd.map(|v| v.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_max_raise")
+        .add_attribute("max_raise_usd", max_raise_usd.map_or("none".to_string(), |v| v.to_string())))
+}
+
+fn update_proceeds_bridge(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    destination: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let bridge = destination
+        .map(|destination| ExternalAddress::parse(&destination).map(|destination| ProceedsBridge { destination }))
+        .transpose()?;
+    let old_value = std::mem::replace(&mut config.proceeds_bridge, bridge.clone());
+    CONFIG.save(deps.storage, &config)?;
+    let new_value = bridge.map(|b| b.destination.to_string());
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_proceeds_bridge",
+        old_value.map(|b| b.destination.to_string()),
+        new_value.clone(),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_proceeds_bridge")
+        .add_attribute("destination", new_value.unwrap_or_else(|| "none".to_string())))
+}
+
+fn update_trusted_payment_token(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_contract: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = config.trusted_payment_token.take();
+    // The pin stands in for the full validation, so the token must pass it now
+    let cw20_contract = cw20_contract.map(|token| deps.api.addr_validate(&token)).transpose()?.map(String::from);
+    if let Some(token) = &cw20_contract {
+        validate_payment_token(deps.as_ref(), &config, token)?;
+    }
+    config.trusted_payment_token = cw20_contract.clone();
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(deps.storage, &env, &info.sender, "update_trusted_payment_token", old_value, cw20_contract.clone())?;
+    Ok(Response::new()
+        .add_attribute("method", "update_trusted_payment_token")
+        .add_attribute("cw20_contract", cw20_contract.unwrap_or_else(|| "none".to_string())))
+}
+
+fn update_proceeds_stream(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    interval_blocks: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let stream = interval_blocks
+        .map(|interval_blocks| {
+            if interval_blocks == 0 {
+                return Err(ContractError::InvalidProceedsStream { reason: "interval_blocks must be positive".to_string() });
+            }
+            Ok(ProceedsStream {
+                interval_blocks,
+                next_release_height: env.block.height + interval_blocks,
+            })
+        })
+        .transpose()?;
+    let old_value = std::mem::replace(&mut config.proceeds_stream, stream);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_proceeds_stream",
+        old_value.map(|s| s.interval_blocks.to_string()),
+        interval_blocks.map(|blocks| blocks.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_proceeds_stream")
+        .add_attribute("interval_blocks", interval_blocks.map_or("none".to_string(), |blocks| blocks.to_string())))
+}
+
+/// Unlike the per-execution refresh, binding names fails outright if they do not resolve, so
+/// a typo surfaces to the admin instead of silently keeping the old addresses
+fn update_name_bindings(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    bindings: Option<NameBindings>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = config.name_bindings.as_ref().map(to_json_string).transpose()?;
+    match &bindings {
+        Some(bindings) => {
+            let mut bindings = bindings.clone();
+            bindings.registry = deps.api.addr_validate(&bindings.registry)?.to_string();
+            if let Some(name) = &bindings.treasury {
+                config.treasury = Some(resolve_registry_name(deps.as_ref(), &bindings.registry, name)?);
+            }
+            if let Some(name) = &bindings.buyer {
+                config.buyer = resolve_registry_name(deps.as_ref(), &bindings.registry, name)?;
+            }
+            if bindings.treasury.is_none() {
+                config.treasury = None;
+            }
+            config.name_bindings = Some(bindings);
+            NAMES_RESOLVED_AT.save(deps.storage, &env.block.height)?;
+        }
+        None => {
+            config.name_bindings = None;
+            config.treasury = None;
+            NAMES_RESOLVED_AT.remove(deps.storage);
+        }
+    }
+    CONFIG.save(deps.storage, &config)?;
+    let new_value = config.name_bindings.as_ref().map(to_json_string).transpose()?;
+    record_audit(deps.storage, &env, &info.sender, "update_name_bindings", old_value, new_value)?;
+    Ok(Response::new()
+        .add_attribute("method", "update_name_bindings")
+        .add_attribute("treasury", config.proceeds_recipient())
+        .add_attribute("buyer", config.buyer))
+}
+
+fn update_co_approval(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    co_approval: Option<CoApproval>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let co_approval = co_approval
+        .map(|mut co_approval| -> Result<_, ContractError> {
+            co_approval.approver = deps.api.addr_validate(&co_approval.approver)?.to_string();
+            // The point is a second signer; the admin approving its own buyers adds nothing
+            if co_approval.approver == config.admin {
+                return Err(ContractError::InvalidCoApproval {
+                    reason: "approver must not be the admin".to_string(),
+                });
+            }
+            Ok(co_approval)
+        })
+        .transpose()?;
+    let old_value = config.co_approval.as_ref().map(to_json_string).transpose()?;
+    config.co_approval = co_approval;
+    CONFIG.save(deps.storage, &config)?;
+    let new_value = config.co_approval.as_ref().map(to_json_string).transpose()?;
+    record_audit(deps.storage, &env, &info.sender, "update_co_approval", old_value, new_value)?;
+    Ok(Response::new()
+        .add_attribute("method", "update_co_approval")
+        .add_attribute("approver", config.co_approval.map_or("none".to_string(), |c| c.approver)))
+}
+
+fn approve_purchase(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    buyer: String,
+    max_usd: Uint128,
+    expiry: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let co_approval = config.co_approval.ok_or(ContractError::Unauthorized {})?;
+    if info.sender.as_str() != co_approval.approver {
+        return Err(ContractError::Unauthorized {});
+    }
+    let buyer = deps.api.addr_validate(&buyer)?.to_string();
+    if max_usd.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    if expiry <= env.block.height {
+        return Err(ContractError::InvalidCoApproval {
+            reason: "expiry must be a future height".to_string(),
+        });
+    }
+    PURCHASE_APPROVALS.save(
+        deps.storage,
+        &buyer,
+        &PurchaseApproval {
+            max_usd,
+            expires_at_height: expiry,
+            approved_by: info.sender.to_string(),
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "approve_purchase")
+        .add_attribute("buyer", buyer)
+        .add_attribute("max_usd", max_usd)
+        .add_attribute("expiry", expiry.to_string()))
+}
+
+/// Whether a purchase of `usd_amount` by `buyer` needs co-approval, failing if it does and
+/// there is no unexpired approval covering it. The caller removes a used approval.
+fn check_purchase_approval(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    buyer: &str,
+    usd_amount: Uint128,
+) -> Result<bool, ContractError> {
+    let Some(co_approval) = &config.co_approval else {
+        return Ok(false);
+    };
+    if usd_amount <= co_approval.threshold_usd {
+        return Ok(false);
+    }
+    let approval = PURCHASE_APPROVALS
+        .may_load(storage, buyer)?
+        .filter(|approval| env.block.height <= approval.expires_at_height)
+        .ok_or(ContractError::PurchaseApprovalRequired {
+            usd_amount: usd_amount.u128(),
+            threshold_usd: co_approval.threshold_usd.u128(),
+        })?;
+    if usd_amount > approval.max_usd {
+        return Err(ContractError::PurchaseApprovalExceeded {
+            approved_usd: approval.max_usd.u128(),
+            requested_usd: usd_amount.u128(),
+        });
+    }
+    Ok(true)
+}
+
+fn consume_purchase_approval(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    buyer: &str,
+    usd_amount: Uint128,
+) -> Result<(), ContractError> {
+    if check_purchase_approval(storage, env, config, buyer, usd_amount)? {
+        PURCHASE_APPROVALS.remove(storage, buyer);
+    }
+    Ok(())
+}
+
+/// Retries proceeds whose bridge hop failed, or sends them to the treasury if bridging is now off
+fn flush_unbridged_proceeds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_contract: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let amount = UNBRIDGED_PROCEEDS.may_load(deps.storage, &cw20_contract)?.unwrap_or_default();
+    if amount.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    UNBRIDGED_PROCEEDS.remove(deps.storage, &cw20_contract);
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "flush_unbridged_proceeds",
+        Some(format!("{} {}", amount, cw20_contract)),
+        None,
+    )?;
+    Ok(Response::new()
+        .add_submessage(forward_proceeds(&config, cw20_contract.clone(), config.proceeds_recipient(), amount)?)
+        .add_attribute("method", "flush_unbridged_proceeds")
+        .add_attribute("cw20_contract", cw20_contract)
+        .add_attribute("amount", amount))
+}
+
+fn update_crank_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    crank_reward: Uint128,
+    timelock_delay_blocks: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let old_value = format!("reward {}, delay {}", config.crank_reward, config.timelock_delay_blocks);
+    config.crank_reward = crank_reward;
+    // Enabling the timelock takes effect at once; any later change waits behind the delay
+    if let Some(blocks) = timelock_delay_blocks {
+        ensure_not_timelocked(&config, "update_timelock_delay")?;
+        validate_timelock_delay(blocks)?;
+        config.timelock_delay_blocks = blocks;
+    }
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_crank_config",
+        Some(old_value),
+        Some(format!("reward {}, delay {}", crank_reward, config.timelock_delay_blocks)),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_crank_config")
+        .add_attribute("crank_reward", crank_reward)
+        .add_attribute("timelock_delay_blocks", config.timelock_delay_blocks.to_string()))
+}
+
+/// Rejects the direct form of a guarded action once the timelock is enabled
+fn ensure_not_timelocked(config: &Config, action: &str) -> Result<(), ContractError> {
+    if config.timelock_delay_blocks > 0 {
+        return Err(ContractError::TimelockRequired { action: action.to_string() });
+    }
+    Ok(())
+}
+
+fn validate_timelock_delay(blocks: u64) -> Result<(), ContractError> {
+    if blocks < MIN_TIMELOCK_DELAY_BLOCKS {
+        return Err(ContractError::InvalidTimelockDelay { blocks, min: MIN_TIMELOCK_DELAY_BLOCKS });
+    }
+    Ok(())
+}
+
+fn queue_action(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    action: TimelockedAction,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+
+    // Validate up front so the crank never stalls on a malformed action
+    let action = match action {
+        TimelockedAction::UpdatePrice { price_usd } => {
+            if price_usd.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            TimelockedAction::UpdatePrice { price_usd }
+        }
+        TimelockedAction::UpdateBuyer { buyer } => TimelockedAction::UpdateBuyer {
+            buyer: deps.api.addr_validate(&buyer)?.to_string(),
+        },
+        TimelockedAction::WithdrawNativeTokens { amount, recipient, denom } => {
+            if amount.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            TimelockedAction::WithdrawNativeTokens {
+                amount,
+                recipient: deps.api.addr_validate(&recipient)?.to_string(),
+                denom: Some(resolve_payout_denom(&config, denom)?),
+            }
+        }
+        TimelockedAction::CorrectAccounting { delta, reason } => {
+            if delta.is_zero() {
+                return Err(ContractError::ZeroAmount {});
+            }
+            let reason = reason.trim().to_string();
+            if reason.is_empty() || reason.len() > MAX_CORRECTION_REASON_LEN {
+                return Err(ContractError::InvalidAccountingCorrection {
+                    reason: format!("reason must be 1 to {} bytes", MAX_CORRECTION_REASON_LEN),
+                });
+            }
+            TimelockedAction::CorrectAccounting { delta, reason }
+        }
+        TimelockedAction::UpdateTimelockDelay { blocks } => {
+            validate_timelock_delay(blocks)?;
+            TimelockedAction::UpdateTimelockDelay { blocks }
+        }
+    };
+
+    let id = NEXT_TIMELOCK_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_TIMELOCK_ID.save(deps.storage, &(id + 1))?;
+    let eta_height = env.block.height + config.timelock_delay_blocks;
+    let queued = QueuedAction {
+        id,
+        action,
+        eta_height,
+        queued_at_height: env.block.height,
+    };
+    TIMELOCK_QUEUE.save(deps.storage, (eta_height, id), &queued)?;
+    TIMELOCK_ETA.save(deps.storage, id, &eta_height)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "queue_action",
+        None,
+        Some(to_json_string(&queued)?),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "queue_action")
+        .add_attribute("id", id.to_string())
+        .add_attribute("eta_height", eta_height.to_string()))
+}
+
+fn cancel_action(deps: DepsMut, env: Env, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let eta_height = TIMELOCK_ETA
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::QueuedActionNotFound { id })?;
+    let queued = TIMELOCK_QUEUE.load(deps.storage, (eta_height, id))?;
+    TIMELOCK_QUEUE.remove(deps.storage, (eta_height, id));
+    TIMELOCK_ETA.remove(deps.storage, id);
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "cancel_action",
+        Some(to_json_string(&queued)?),
+        None,
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "cancel_action")
+        .add_attribute("id", id.to_string()))
+}
+
+/// Appends an admin action to the audit log
+fn record_audit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    actor: &Addr,
+    action: &str,
+    old_value: Option<String>,
+    new_value: Option<String>,
+) -> StdResult<()> {
+    let id = NEXT_AUDIT_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_AUDIT_ID.save(storage, &(id + 1))?;
+    let prev_hash = match id.checked_sub(1) {
+        Some(prev) => AUDIT_LOG.load(storage, prev)?.hash,
+        None => Binary::default(),
+    };
+    let mut entry = AuditEntry {
+        id,
+        actor: actor.to_string(),
+        action: action.to_string(),
+        height: env.block.height,
+        old_value,
+        new_value,
+        hash: Binary::default(),
+    };
+    // Chain each entry to the previous one so rewriting history is detectable
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_slice());
+    hasher.update(to_json_vec(&entry)?);
+    entry.hash = Binary::from(hasher.finalize().to_vec());
+    AUDIT_LOG.save(storage, id, &entry)
+}
+
+/// Appends to the price history read by `PriceHistory`
+fn record_price_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    actor: &Addr,
+    cw20_contract: Option<String>,
+    old_price_usd: Option<Uint128>,
+    new_price_usd: Option<Uint128>,
+) -> StdResult<()> {
+    let id = NEXT_PRICE_CHANGE_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_PRICE_CHANGE_ID.save(storage, &(id + 1))?;
+    PRICE_HISTORY.save(
+        storage,
+        id,
+        &PriceChange {
+            id,
+            cw20_contract,
+            old_price_usd,
+            new_price_usd,
+            actor: actor.to_string(),
+            height: env.block.height,
+        },
+    )
+}
+
+/// `total` moved by `delta`, or None if it would leave the Uint128 range
+fn apply_accounting_delta(total: Uint128, delta: Int128) -> Option<Uint128> {
+    let magnitude = delta.unsigned_abs();
+    if delta.is_negative() {
+        total.checked_sub(magnitude).ok()
+    } else {
+        total.checked_add(magnitude).ok()
+    }
+}
+
+/// Applies a due timelocked action to the config, returning any messages it produces
+fn apply_timelocked_action(config: &mut Config, action: TimelockedAction) -> Vec<CosmosMsg> {
+    match action {
+        TimelockedAction::UpdatePrice { price_usd } => {
+            config.price_usd = price_usd;
+            vec![]
+        }
+        TimelockedAction::UpdateBuyer { buyer } => {
+            config.buyer = buyer;
+            vec![]
+        }
+        TimelockedAction::WithdrawNativeTokens { amount, recipient, denom } => {
+            vec![BankMsg::Send {
+                to_address: recipient,
+                amount: vec![Coin {
+                    denom: denom.unwrap_or_else(|| config.native_denom.clone()),
+                    amount: amount.into(),
+                }],
+            }
+            .into()]
+        }
+        TimelockedAction::CorrectAccounting { delta, .. } => {
+            // The crank drops corrections that do not fit before applying them
+            if let Some(total) = apply_accounting_delta(config.total_tokens_sold, delta) {
+                config.total_tokens_sold = total;
+            }
+            vec![]
+        }
+        TimelockedAction::UpdateTimelockDelay { blocks } => {
+            config.timelock_delay_blocks = blocks;
+            vec![]
+        }
+    }
+}
+
+/// Permissionless keeper entry point: processes due lifecycle items in bounded batches
+/// and pays the caller `crank_reward` per processed item.
+fn crank(deps: DepsMut, env: Env, info: MessageInfo, limit: Option<u32>) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_CRANK_LIMIT).min(MAX_CRANK_LIMIT) as usize;
+    let mut response = Response::new().add_attribute("method", "crank");
+
+    // Elapsed timelocks
+    let due = TIMELOCK_QUEUE
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive((env.block.height, u64::MAX))),
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut processed = 0u64;
+    let mut withdrawn_native = Uint128::zero();
+    for ((eta_height, id), queued) in due {
+        TIMELOCK_QUEUE.remove(deps.storage, (eta_height, id));
+        TIMELOCK_ETA.remove(deps.storage, id);
+        processed += 1;
+        // A queued withdrawal is checked against reserves when it runs, not when queued; one
+        // that would dip into them is dropped rather than blocking the queue
+        if let TimelockedAction::WithdrawNativeTokens { amount, denom, .. } = &queued.action {
+            if denom.as_deref().unwrap_or(&config.native_denom) == config.native_denom {
+                let available = free_native(deps.as_ref(), &env, &config)?.saturating_sub(withdrawn_native);
+                if *amount > available {
+                    response = response.add_attribute("skipped_action", id.to_string());
+                    continue;
+                }
+                withdrawn_native += *amount;
+            }
+        }
+        // A correction queued against an older total may no longer fit the counter
+        let correction = match &queued.action {
+            TimelockedAction::CorrectAccounting { delta, reason } => {
+                match apply_accounting_delta(config.total_tokens_sold, *delta) {
+                    Some(corrected) => Some((corrected, reason.clone())),
+                    None => {
+                        response = response.add_attribute("skipped_action", id.to_string());
+                        continue;
+                    }
+                }
+            }
+            _ => None,
+        };
+        // Executed on the admin's behalf; the keeper that triggered it is the actor
+        record_audit(
+            deps.storage,
+            &env,
+            &info.sender,
+            "execute_queued_action",
+            None,
+            Some(to_json_string(&queued)?),
+        )?;
+        if let TimelockedAction::UpdatePrice { price_usd } = &queued.action {
+            record_price_change(deps.storage, &env, &info.sender, None, Some(config.price_usd), Some(*price_usd))?;
+        }
+        if let Some((corrected, reason)) = correction {
+            record_audit(
+                deps.storage,
+                &env,
+                &info.sender,
+                "correct_accounting",
+                Some(config.total_tokens_sold.to_string()),
+                Some(format!("{} ({})", corrected, reason)),
+            )?;
+        }
+        response = response
+            .add_messages(apply_timelocked_action(&mut config, queued.action))
+            .add_attribute("executed_action", id.to_string());
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    // Expired quotes, with what is left of the limit
+    let expired = QUOTE_EXPIRIES
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((env.block.height, 0))),
+            Order::Ascending,
+        )
+        .take(limit.saturating_sub(processed as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (expires_at_height, id) in expired {
+        QUOTE_EXPIRIES.remove(deps.storage, (expires_at_height, id));
+        QUOTES.remove(deps.storage, id);
+        response = response.add_attribute("expired_quote", id.to_string());
+        processed += 1;
+    }
+
+    // Unrevealed purchase commitments past their deadline, whose deposits go to the admin
+    let expired = PURCHASE_COMMITMENT_EXPIRIES
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((env.block.height, "", &[][..]))),
+            Order::Ascending,
+        )
+        .take(limit.saturating_sub(processed as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut forfeited = Uint128::zero();
+    for (expires_at_height, buyer, commitment) in expired {
+        PURCHASE_COMMITMENT_EXPIRIES.remove(deps.storage, (expires_at_height, &buyer, &commitment));
+        if let Some(expired) = PURCHASE_COMMITMENTS.may_load(deps.storage, (&buyer, &commitment))? {
+            PURCHASE_COMMITMENTS.remove(deps.storage, (&buyer, &commitment));
+            update_reserve(deps.storage, RESERVE_COMMITMENTS, expired.deposit, false)?;
+            forfeited += expired.deposit;
+        }
+        response = response.add_attribute("expired_commitment", buyer);
+        processed += 1;
+    }
+    if !forfeited.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: config.admin.clone(),
+            amount: vec![Coin { denom: config.native_denom.clone(), amount: forfeited.into() }],
+        });
+    }
+
+    // Reservations past their deadline, refunded to their buyers with their reserve released
+    let expired = RESERVATION_EXPIRIES
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((env.block.height, 0))),
+            Order::Ascending,
+        )
+        .take(limit.saturating_sub(processed as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (deadline_height, id) in expired {
+        RESERVATION_EXPIRIES.remove(deps.storage, (deadline_height, id));
+        if let Some(reservation) = RESERVATIONS.may_load(deps.storage, id)? {
+            response = response.add_messages(close_reservation(deps.storage, id, reservation)?);
+        }
+        response = response.add_attribute("expired_reservation", id.to_string());
+        processed += 1;
+    }
+
+    // Rounds that have ended, reported once with their final totals
+    let finished = ROUNDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| item.as_ref().map_or(true, |(_, round)| !round.finished && round.end_height <= env.block.height))
+        .take(limit.saturating_sub(processed as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, mut round) in finished {
+        round.finished = true;
+        ROUNDS.save(deps.storage, id, &round)?;
+        response = response.add_event(
+            Event::new("round_finished")
+                .add_attribute("round_id", id.to_string())
+                .add_attribute("name", round.name)
+                .add_attribute("raised_usd", round.raised_usd)
+                .add_attribute("tokens_sold", round.tokens_sold),
+        );
+        processed += 1;
+    }
+
+    // Vesting contracts at their cliff or end release what has vested; a failed release is
+    // reported rather than failing the crank
+    let unlocked = VESTING_UNLOCKS
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::exclusive((env.block.time.seconds() + 1, ""))),
+            Order::Ascending,
+        )
+        .take(limit.saturating_sub(processed as usize))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (unlock_time, vesting_contract) in unlocked {
+        VESTING_UNLOCKS.remove(deps.storage, (unlock_time, &vesting_contract));
+        let release = WasmMsg::Execute {
+            contract_addr: vesting_contract.clone(),
+            msg: to_json_binary(&VestingExecuteMsg::Release {})?,
+            funds: vec![],
+        };
+        response = response
+            .add_submessage(SubMsg::reply_on_error(release, VESTING_RELEASE_REPLY_ID).with_payload(vesting_contract.as_bytes().to_vec()))
+            .add_attribute("vesting_release", vesting_contract);
+        processed += 1;
+    }
+
+    // Lottery entries left unclaimed past the deadline forfeit their win: the whole commitment
+    // is refunded and the tokens held for the win go back to inventory
+    let lottery = LOTTERY
+        .may_load(deps.storage)?
+        .filter(|lottery| lottery.claim_deadline_height.is_some_and(|deadline| env.block.height > deadline));
+    if let Some(mut lottery) = lottery {
+        let unclaimed = LOTTERY_ENTRIES
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(limit.saturating_sub(processed as usize))
+            .collect::<StdResult<Vec<_>>>()?;
+        for (buyer, entry) in unclaimed {
+            LOTTERY_ENTRIES.remove(deps.storage, &buyer);
+            if !entry.won_usd.is_zero() {
+                let tokens = calculate_tokens_for_usd(entry.won_usd, lottery.price_usd, config.payout_decimals);
+                update_reserve(deps.storage, RESERVE_LOTTERY, tokens, false)?;
+                // Counted toward the raise cap at finalize, but never collected
+                config.total_raised_usd = config.total_raised_usd.saturating_sub(entry.won_usd);
+                CONFIG.save(deps.storage, &config)?;
+            }
+            record_refund(deps.storage, entry.committed_usd)?;
+            if !entry.committed_usd.is_zero() {
+                response = response.add_message(create_cw20_transfer_msg(
+                    entry.cw20_contract,
+                    buyer.clone(),
+                    entry.committed_usd,
+                )?);
+            }
+            close_lottery_entry(deps.storage, &mut lottery)?;
+            response = response.add_attribute("swept_lottery_entry", buyer);
+            processed += 1;
+        }
+    }
+
+    // Held proceeds once their release is due, or at once after streaming was turned off.
+    // Nothing is released while paused, leaving governance time to react to an incident
+    let release_due = config
+        .proceeds_stream
+        .as_ref()
+        .is_none_or(|stream| env.block.height >= stream.next_release_height);
+    if release_due && !config.is_paused {
+        let remaining = limit.saturating_sub(processed as usize);
+        let held = STREAMED_PROCEEDS
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(remaining + 1)
+            .collect::<StdResult<Vec<_>>>()?;
+        let all_released = held.len() <= remaining;
+        for ((cw20_contract, recipient), amount) in held.into_iter().take(remaining) {
+            STREAMED_PROCEEDS.remove(deps.storage, (&cw20_contract, &recipient));
+            response = response
+                .add_submessage(forward_proceeds(&config, cw20_contract.clone(), recipient.clone(), amount)?)
+                .add_event(
+                    Event::new("proceeds_released")
+                        .add_attribute("cw20_contract", cw20_contract)
+                        .add_attribute("recipient", recipient)
+                        .add_attribute("amount", amount),
+                );
+            processed += 1;
+        }
+        // A release cut short by the limit stays due until a later crank finishes it
+        if let Some(stream) = config.proceeds_stream.as_mut().filter(|_| all_released) {
+            stream.next_release_height = env.block.height + stream.interval_blocks;
+            CONFIG.save(deps.storage, &config)?;
+        }
+    }
+
+    let response = response.add_attribute("processed", processed.to_string());
+    pay_crank_reward(response, &config, &info.sender, processed)
+}
+
+/// Pays `keeper` the crank reward for `rewarded` items
+fn pay_crank_reward(mut response: Response, config: &Config, keeper: &Addr, rewarded: u64) -> Result<Response, ContractError> {
+    let reward = config.crank_reward.checked_mul(Uint128::from(rewarded))
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    if !reward.is_zero() {
+        response = response
+            .add_message(BankMsg::Send {
+                to_address: keeper.to_string(),
+                amount: vec![Coin {
+                    denom: config.native_denom.clone(),
+                    amount: reward.into(),
+                }],
+            })
+            .add_attribute("reward", reward);
+    }
+
+    Ok(response)
+}
+
+fn subscribe(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_contract: String,
+    usd_amount: Uint128,
+    interval_blocks: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(config.is_paused)?;
+    let buyer = info.sender.to_string();
+    if buyer != config.buyer && !WHITELIST.has(deps.storage, &buyer) {
+        return Err(ContractError::BuyerNotAllowed { buyer });
+    }
+    if config.vesting.is_some() {
+        return Err(ContractError::InvalidSubscription {
+            reason: "purchases vest, and subscriptions pay out directly".to_string(),
+        });
+    }
+    if usd_amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    if interval_blocks < MIN_SUBSCRIPTION_INTERVAL_BLOCKS {
+        return Err(ContractError::InvalidSubscription {
+            reason: format!("interval_blocks below {}", MIN_SUBSCRIPTION_INTERVAL_BLOCKS),
+        });
+    }
+    let held = BUYER_SUBSCRIPTIONS.may_load(deps.storage, &buyer)?.unwrap_or_default();
+    if held >= MAX_SUBSCRIPTIONS_PER_BUYER {
+        return Err(ContractError::InvalidSubscription {
+            reason: format!("buyer already holds {} subscriptions", MAX_SUBSCRIPTIONS_PER_BUYER),
+        });
+    }
+    let cw20_contract = deps.api.addr_validate(&cw20_contract)?.to_string();
+    validate_payment_token(deps.as_ref(), &config, &cw20_contract)?;
+
+    BUYER_SUBSCRIPTIONS.save(deps.storage, &buyer, &(held + 1))?;
+    let id = NEXT_SUBSCRIPTION_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_SUBSCRIPTION_ID.save(deps.storage, &(id + 1))?;
+    let subscription = Subscription {
+        id,
+        buyer: buyer.clone(),
+        cw20_contract,
+        usd_amount,
+        interval_blocks,
+        next_due_height: env.block.height,
+        paused: false,
+        purchases: 0,
+        skips: 0,
+    };
+    SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+    SUBSCRIPTION_DUE.save(deps.storage, (subscription.next_due_height, id), &())?;
+    Ok(Response::new()
+        .add_attribute("method", "subscribe")
+        .add_attribute("subscription_id", id.to_string())
+        .add_attribute("buyer", buyer)
+        .add_attribute("usd_amount", usd_amount)
+        .add_attribute("interval_blocks", interval_blocks.to_string()))
+}
+
+fn load_own_subscription(storage: &dyn Storage, id: u64, sender: &Addr) -> Result<Subscription, ContractError> {
+    let subscription = SUBSCRIPTIONS
+        .may_load(storage, id)?
+        .ok_or(ContractError::SubscriptionNotFound { id })?;
+    if sender.as_str() != subscription.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(subscription)
+}
+
+fn pause_subscription(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let mut subscription = load_own_subscription(deps.storage, id, &info.sender)?;
+    if !subscription.paused {
+        SUBSCRIPTION_DUE.remove(deps.storage, (subscription.next_due_height, id));
+        subscription.paused = true;
+        SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+    }
+    Ok(Response::new()
+        .add_attribute("method", "pause_subscription")
+        .add_attribute("subscription_id", id.to_string()))
+}
+
+fn resume_subscription(deps: DepsMut, env: Env, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let mut subscription = load_own_subscription(deps.storage, id, &info.sender)?;
+    if subscription.paused {
+        // Periods missed while paused are not made up
+        subscription.next_due_height = subscription.next_due_height.max(env.block.height);
+        subscription.paused = false;
+        SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+        SUBSCRIPTION_DUE.save(deps.storage, (subscription.next_due_height, id), &())?;
+    }
+    Ok(Response::new()
+        .add_attribute("method", "resume_subscription")
+        .add_attribute("subscription_id", id.to_string())
+        .add_attribute("next_due_height", subscription.next_due_height.to_string()))
+}
+
+fn cancel_subscription(deps: DepsMut, info: MessageInfo, id: u64) -> Result<Response, ContractError> {
+    let subscription = load_own_subscription(deps.storage, id, &info.sender)?;
+    remove_subscription(deps.storage, &subscription)?;
+    Ok(Response::new()
+        .add_attribute("method", "cancel_subscription")
+        .add_attribute("subscription_id", id.to_string()))
+}
+
+/// Deletes a subscription and frees its place in the buyer's subscription limit
+fn remove_subscription(storage: &mut dyn Storage, subscription: &Subscription) -> StdResult<()> {
+    if !subscription.paused {
+        SUBSCRIPTION_DUE.remove(storage, (subscription.next_due_height, subscription.id));
+    }
+    SUBSCRIPTIONS.remove(storage, subscription.id);
+    match BUYER_SUBSCRIPTIONS.may_load(storage, &subscription.buyer)?.unwrap_or_default() {
+        0 | 1 => BUYER_SUBSCRIPTIONS.remove(storage, &subscription.buyer),
+        held => BUYER_SUBSCRIPTIONS.save(storage, &subscription.buyer, &(held - 1))?,
+    }
+    Ok(())
+}
+
+/// A due subscription purchase, checked against the same rules as a direct purchase before any
+/// state changes so a failing subscription can be skipped without partial writes
+struct SubscriptionPurchase {
+    price: Uint128,
+    tokens: Uint128,
+    /// Micro-USD pulled: the cost of `tokens`, without the dust `usd_amount` cannot buy
+    cost: Uint128,
+    /// Part of `cost` pulled to the validator kickback recipient rather than the treasury
+    kickback_usd: Uint128,
+    total_raised_usd: Uint128,
+    round: Option<(u32, SaleRound)>,
+    whitelist_entry: Option<WhitelistEntry>,
+    pool: Option<String>,
+    /// Whether the purchase uses the buyer's co-approval, which is then removed
+    co_approved: bool,
+    /// Scheduled epoch and its raised total including the purchase
+    epoch_raised: Option<(u64, Uint128)>,
+}
+
+/// `reserved_tokens` and `already_pulled` are what earlier purchases in the same batch will pay
+/// out and pull once their messages run, which the balance queries do not reflect yet
+fn plan_subscription_purchase(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    subscription: &Subscription,
+    reserved_tokens: Uint128,
+    already_pulled: Uint128,
+) -> Result<SubscriptionPurchase, ContractError> {
+    let buyer = &subscription.buyer;
+    if config.vesting.is_some() {
+        return Err(ContractError::InvalidSubscription {
+            reason: "purchases vest, and subscriptions pay out directly".to_string(),
+        });
+    }
+    let whitelist_entry = WHITELIST.may_load(deps.storage, buyer)?;
+    if *buyer != config.buyer && whitelist_entry.is_none() {
+        return Err(ContractError::BuyerNotAllowed { buyer: buyer.clone() });
+    }
+    if config.require_attestation {
+        ensure_attested(deps, buyer)?;
+    }
+    validate_payment_token(deps, config, &subscription.cw20_contract)?;
+
+    let round = active_round(deps.storage, env.block.height)?;
+    if round.is_none() && !ROUNDS.is_empty(deps.storage) {
+        return Err(ContractError::NoActiveRound {});
+    }
+    let price = match BUYER_PRICES.may_load(deps.storage, buyer)? {
+        Some(price) => price,
+        None => price_with_source(deps, env, config, &subscription.cw20_contract)?.0,
+    };
+    if let Some(floor) = config.price_floor_usd {
+        if price < floor {
+            return Err(ContractError::PriceBelowFloor { price: price.u128(), floor: floor.u128() });
+        }
+    }
+    let (tokens, cost) = split_usd_payment(subscription.usd_amount, price, config.payout_decimals)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    if tokens.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let total_raised_usd = check_raise_cap(config, cost)?;
+
+    let whitelist_entry = match whitelist_entry {
+        Some(mut entry) if *buyer != config.buyer => {
+            let remaining = entry.allocation_usd.saturating_sub(entry.purchased_usd);
+            if cost > remaining {
+                return Err(ContractError::AllocationExceeded {
+                    buyer: buyer.clone(),
+                    remaining: remaining.u128(),
+                });
+            }
+            entry.purchased_usd += cost;
+            Some(entry)
+        }
+        _ => None,
+    };
+    if let Some(limits) = resolve_purchase_limits(deps, config)? {
+        check_purchase_limits(&limits, cost, price)?;
+    }
+    let co_approved = check_purchase_approval(deps.storage, env, config, buyer, cost)?;
+    let epoch_raised = check_epoch_cap(deps, cost)?;
+    let kickback_usd = validator_kickback_usd(config, cost);
+
+    // Other obligations' holds and what earlier purchases in this batch will pay out are spoken for
+    let available = free_native(deps, env, config)?.saturating_sub(reserved_tokens);
+    if tokens > available {
+        return Err(ContractError::InsufficientBalance {
+            available: available.u128(),
+            needed: tokens.u128(),
+        });
+    }
+    let pool = select_pool(deps.storage, buyer, tokens)?;
+
+    let (balance, allowance) = query_cw20_funding(deps, env, &subscription.cw20_contract, buyer)?;
+    let allowance = allowance.saturating_sub(already_pulled);
+    if cost > allowance {
+        return Err(ContractError::InsufficientAllowance {
+            allowance: allowance.u128(),
+            required: cost.u128(),
+        });
+    }
+    let balance = balance.saturating_sub(already_pulled);
+    if cost > balance {
+        return Err(ContractError::InsufficientPayment {
+            sent: balance.u128(),
+            required: cost.u128(),
+        });
+    }
+
+    Ok(SubscriptionPurchase {
+        price,
+        tokens,
+        cost,
+        kickback_usd,
+        total_raised_usd,
+        round,
+        whitelist_entry,
+        pool,
+        co_approved,
+        epoch_raised,
+    })
+}
+
+/// Permissionless: runs due subscription purchases in due order and pays the crank reward per
+/// purchase made. Subscriptions that cannot purchase this period are skipped with a
+/// `subscription_skipped` event; either way they move on to their next period, except that
+/// one skipped `MAX_SUBSCRIPTION_SKIPS` times in a row is cancelled.
+fn process_subscriptions(deps: DepsMut, env: Env, info: MessageInfo, limit: Option<u32>) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(config.is_paused)?;
+    let limit = limit.unwrap_or(DEFAULT_CRANK_LIMIT).min(MAX_CRANK_LIMIT) as usize;
+    let due = SUBSCRIPTION_DUE
+        .keys(
+            deps.storage,
+            None,
+            Some(Bound::inclusive((env.block.height, u64::MAX))),
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut response = Response::new().add_attribute("method", "process_subscriptions");
+    let mut reserved_tokens = Uint128::zero();
+    let mut pulled: BTreeMap<(String, String), Uint128> = BTreeMap::new();
+    let mut processed = 0u64;
+    let mut purchased = 0u64;
+    for (due_height, id) in due {
+        // A purchase that tripped the circuit breaker stops the batch
+        if config.is_paused {
+            break;
+        }
+        SUBSCRIPTION_DUE.remove(deps.storage, (due_height, id));
+        let mut subscription = SUBSCRIPTIONS.load(deps.storage, id)?;
+        let funding_key = (subscription.buyer.clone(), subscription.cw20_contract.clone());
+        let already_pulled = pulled.get(&funding_key).copied().unwrap_or_default();
+
+        match plan_subscription_purchase(deps.as_ref(), &env, &config, &subscription, reserved_tokens, already_pulled) {
+            Ok(purchase) => {
+                if let Some(entry) = &purchase.whitelist_entry {
+                    WHITELIST.save(deps.storage, &subscription.buyer, entry)?;
+                }
+                if purchase.co_approved {
+                    PURCHASE_APPROVALS.remove(deps.storage, &subscription.buyer);
+                }
+                if let Some((epoch, raised)) = purchase.epoch_raised {
+                    EPOCH_RAISED.save(deps.storage, epoch, &raised)?;
+                }
+                config.total_raised_usd = purchase.total_raised_usd;
+                config.total_tokens_sold = config
+                    .total_tokens_sold
+                    .checked_add(purchase.tokens)
+                    .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+                response = response.add_events(record_sale_volume(deps.storage, &env, &mut config, purchase.cost)?);
+                TOKENS_SOLD_BY_DENOM.update(deps.storage, &config.native_denom, |sold| -> Result<_, ContractError> {
+                    sold.unwrap_or_default()
+                        .checked_add(purchase.tokens)
+                        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+                })?;
+                LAST_PURCHASE_HEIGHT.save(deps.storage, &env.block.height)?;
+                record_purchase_stats(deps.storage, &subscription.buyer, &subscription.cw20_contract, purchase.cost)?;
+                if let Some(label) = &purchase.pool {
+                    record_pool_draw(deps.storage, label, purchase.tokens, purchase.cost)?;
+                }
+                if let Some((round_id, mut round)) = purchase.round.clone() {
+                    round.raised_usd = round
+                        .raised_usd
+                        .checked_add(purchase.cost)
+                        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+                    round.tokens_sold = round
+                        .tokens_sold
+                        .checked_add(purchase.tokens)
+                        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+                    ROUNDS.save(deps.storage, round_id, &round)?;
+                }
+
+                if let Some(kickback) = config.validator_kickback.as_ref().filter(|_| !purchase.kickback_usd.is_zero()) {
+                    response = response.add_message(create_kickback_msg(
+                        &env,
+                        kickback,
+                        subscription.cw20_contract.clone(),
+                        Some(subscription.buyer.clone()),
+                        purchase.kickback_usd,
+                    )?);
+                }
+
+                // Pull the rest of the stablecoin straight to the round's treasury or the admin
+                let treasury_amount = purchase.cost - purchase.kickback_usd;
+                let treasury = purchase
+                    .round
+                    .as_ref()
+                    .and_then(|(_, round)| round.treasury.clone())
+                    .unwrap_or_else(|| config.proceeds_recipient());
+                // Bridged and streamed proceeds are pulled here first; the payment token
+                // withdraws from our balance, and streamed proceeds are released from it later
+                let streamed = config.proceeds_stream.is_some() && !treasury.is_empty();
+                let pull_to = if treasury.is_empty() || config.proceeds_bridge.is_some() || streamed {
+                    env.contract.address.to_string()
+                } else {
+                    treasury.clone()
+                };
+                if !treasury_amount.is_zero() {
+                    response = response.add_message(create_cw20_transfer_from_msg(
+                        subscription.cw20_contract.clone(),
+                        subscription.buyer.clone(),
+                        pull_to.clone(),
+                        treasury_amount,
+                    )?);
+                    if streamed || config.proceeds_bridge.is_some() {
+                        let recipient = if streamed { treasury } else { pull_to };
+                        response = route_proceeds(
+                            deps.storage,
+                            &config,
+                            response,
+                            subscription.cw20_contract.clone(),
+                            recipient,
+                            treasury_amount,
+                        )?;
+                    }
+                }
+                response = response
+                    .add_message(BankMsg::Send {
+                        to_address: subscription.buyer.clone(),
+                        amount: vec![Coin {
+                            denom: config.native_denom.clone(),
+                            amount: purchase.tokens.into(),
+                        }],
+                    })
+                    .add_event(
+                        Event::new("subscription_purchase")
+                            .add_attribute("subscription_id", id.to_string())
+                            .add_attribute("buyer", subscription.buyer.clone())
+                            .add_attribute("usdt_amount", purchase.cost)
+                            .add_attribute("gnk_purchased", purchase.tokens)
+                            .add_attribute("validator_kickback_usd", purchase.kickback_usd)
+                            .add_attribute("price_usd", purchase.price),
+                    );
+                reserved_tokens += purchase.tokens;
+                pulled.insert(funding_key, already_pulled + purchase.cost);
+                subscription.purchases += 1;
+                subscription.skips = 0;
+                purchased += 1;
+            }
+            Err(err) => {
+                response = response.add_event(
+                    Event::new("subscription_skipped")
+                        .add_attribute("subscription_id", id.to_string())
+                        .add_attribute("reason", err.to_string()),
+                );
+                // A subscription that keeps failing would otherwise stay due forever
+                subscription.skips += 1;
+                if subscription.skips >= MAX_SUBSCRIPTION_SKIPS {
+                    remove_subscription(deps.storage, &subscription)?;
+                    response = response.add_event(
+                        Event::new("subscription_cancelled")
+                            .add_attribute("subscription_id", id.to_string())
+                            .add_attribute("buyer", subscription.buyer.clone())
+                            .add_attribute("skips", subscription.skips.to_string()),
+                    );
+                    processed += 1;
+                    continue;
+                }
+            }
+        }
+
+        // Late processing does not bunch up purchases: an overdue schedule restarts from now
+        let next_due_height = subscription.next_due_height.saturating_add(subscription.interval_blocks);
+        subscription.next_due_height = if next_due_height > env.block.height {
+            next_due_height
+        } else {
+            env.block.height + subscription.interval_blocks
+        };
+        SUBSCRIPTIONS.save(deps.storage, id, &subscription)?;
+        SUBSCRIPTION_DUE.save(deps.storage, (subscription.next_due_height, id), &())?;
+        processed += 1;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    // Skipped subscriptions earn nothing, so a keeper cannot farm the reward from failing ones
+    let response = response.add_attribute("processed", processed.to_string());
+    pay_crank_reward(response, &config, &info.sender, purchased)
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Rounds { start_after, limit } => to_json_binary(&query_rounds(deps, env, start_after, limit)?),
+        QueryMsg::EpochSchedule { start_after, limit } => to_json_binary(&query_epoch_schedule(deps, start_after, limit)?),
+        QueryMsg::Sale { id } => to_json_binary(&SaleResponse {
+            id,
+            sale: SALES.load(deps.storage, id)?,
+        }),
+        QueryMsg::Reservation { id } => to_json_binary(&RESERVATIONS.load(deps.storage, id)?),
+        QueryMsg::CircuitBreaker {} => to_json_binary(&query_circuit_breaker(deps, env)?),
+        QueryMsg::Reserves {} => to_json_binary(&query_reserves(deps, env)?),
+        QueryMsg::UnbridgedProceeds {} => to_json_binary(&query_unbridged_proceeds(deps)?),
+        QueryMsg::StreamedProceeds {} => to_json_binary(&query_streamed_proceeds(deps)?),
+        QueryMsg::FinalReport {} => to_json_binary(&FINAL_REPORT.may_load(deps.storage)?),
+        QueryMsg::PurchaseApproval { buyer } => to_json_binary(&PURCHASE_APPROVALS.may_load(deps.storage, &buyer)?),
+        QueryMsg::Quote { id } => to_json_binary(&QUOTES.load(deps.storage, id)?),
+        QueryMsg::Subscription { id } => to_json_binary(&SUBSCRIPTIONS.load(deps.storage, id)?),
+        QueryMsg::Subscriptions { buyer, start_after, limit } => {
+            to_json_binary(&query_subscriptions(deps, buyer, start_after, limit)?)
+        }
+        QueryMsg::Sales { start_after, limit } => to_json_binary(&query_sales(deps, start_after, limit)?),
+        QueryMsg::Pool { label } => to_json_binary(&pool_response(label.clone(), POOLS.load(deps.storage, &label)?)),
+        QueryMsg::Pools { start_after, limit } => to_json_binary(&query_pools(deps, start_after, limit)?),
+        QueryMsg::BuyerPools { buyer } => to_json_binary(&query_buyer_pools(deps, buyer)?),
+        QueryMsg::SaleBuyer { id, buyer } => to_json_binary(&SALE_BUYERS.has(deps.storage, (id, buyer.as_str()))),
+        QueryMsg::RaiseCapacity {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(&RaiseCapacityResponse {
+                max_raise_usd: config.max_raise_usd,
+                total_raised_usd: config.total_raised_usd,
+                remaining_usd: config.max_raise_usd.map(|max| max.saturating_sub(config.total_raised_usd)),
+            })
+        }
+        QueryMsg::PurchaseNonce { buyer } => to_json_binary(&PurchaseNonceResponse {
+            next_nonce: PURCHASE_NONCES.may_load(deps.storage, &buyer)?.unwrap_or_default(),
+            buyer,
+        }),
+        QueryMsg::MigrationStatus {} => to_json_binary(&MigrationStatusResponse {
+            migration: MIGRATION.may_load(deps.storage)?,
+        }),
+        QueryMsg::Lottery {} => to_json_binary(&LotteryResponse {
+            lottery: LOTTERY.may_load(deps.storage)?,
+        }),
+        QueryMsg::LotteryEntry { buyer } => to_json_binary(&LotteryEntryResponse {
+            entry: LOTTERY_ENTRIES.may_load(deps.storage, &buyer)?,
+        }),
+        QueryMsg::CurrentPrice { cw20_contract } => {
+            let config = CONFIG.load(deps.storage)?;
+            let (price_usd, price_source) = match cw20_contract.or_else(|| config.trusted_payment_token.clone()) {
+                Some(cw20_contract) => price_with_source(deps, &env, &config, &cw20_contract)?,
+                None => base_price(deps, &env, &config)?,
+            };
+            to_json_binary(&CurrentPriceResponse {
+                price_usd,
+                price_source: price_source.to_string(),
+                floor_price_usd: config.price_floor_usd,
+            })
+        }
+        QueryMsg::Status {} => to_json_binary(&query_status(deps, env)?),
+        QueryMsg::AuditLog { start_after, limit } => to_json_binary(&query_audit_log(deps, start_after, limit)?),
+        QueryMsg::PriceHistory { start_after, limit } => to_json_binary(&query_price_history(deps, start_after, limit)?),
+        QueryMsg::ModuleVersion {} => to_json_binary(&query_module_version_status(deps)?),
+        QueryMsg::NativeBalance {} => to_json_binary(&query_native_balance(deps, env)?),
+        QueryMsg::Deposits { start_after, limit } => to_json_binary(&query_deposits(deps, start_after, limit)?),
+        QueryMsg::PayoutDenoms {} => to_json_binary(&query_payout_denoms(deps, env)?),
+        QueryMsg::ExportWhitelist { start_after, limit } => to_json_binary(&query_export_whitelist(deps, start_after, limit)?),
+        QueryMsg::WhitelistImportStatus {} => to_json_binary(&WHITELIST_IMPORT.may_load(deps.storage)?.unwrap_or_default()),
+        QueryMsg::QueuedActions { start_after, limit } => to_json_binary(&query_queued_actions(deps, start_after, limit)?),
+        QueryMsg::PurchaseLimits {} => to_json_binary(&query_purchase_limits(deps)?),
+        QueryMsg::VestingContracts { buyer } => to_json_binary(&query_vesting_contracts(deps, buyer)?),
+        QueryMsg::CalculateTokens { usd_amount } => to_json_binary(&query_calculate_tokens(deps, usd_amount)?),
+        QueryMsg::CanPurchase { buyer, usd_amount } => to_json_binary(&query_can_purchase(deps, env, buyer, usd_amount)?),
+        QueryMsg::CalculateUsdForTokens { token_amount } => to_json_binary(&query_calculate_usd_for_tokens(deps, token_amount)?),
+        QueryMsg::CalculateTokensForToken { cw20_contract, amount } => to_json_binary(&query_calculate_tokens_for_token(deps, env, cw20_contract, amount)?),
+        QueryMsg::TokenPrices {} => to_json_binary(&query_token_prices(deps)?),
+        QueryMsg::BuyerPrices { start_after, limit } => to_json_binary(&query_buyer_prices(deps, start_after, limit)?),
+        QueryMsg::ContractInfo {} => to_json_binary(&query_contract_info(deps)?),
+        #[cfg(feature = "debug")]
+        QueryMsg::TestBridgeValidation { cw20_contract } => to_json_binary(&query_test_bridge_validation(deps, cw20_contract)?),
+        #[cfg(feature = "debug")]
+        QueryMsg::BlockHeight {} => to_json_binary(&query_block_height(env)?),
+        #[cfg(feature = "debug")]
+        QueryMsg::TestApprovedTokens {} => to_json_binary(&query_test_approved_tokens(deps)?),
+        QueryMsg::DebugGrpc { path, data } => to_json_binary(&query_debug_grpc(deps, path, data)?),
+        QueryMsg::ExportState { section, start_after, limit } => {
+            to_json_binary(&query_export_state(deps, section, start_after, limit)?)
+        }
+    }
+}
+
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        PROCEEDS_BRIDGE_REPLY_ID => {
+            // Only failures are replied to; the reverted hop left the CW20 with us
+            let error = msg.result.into_result().err().unwrap_or_default();
+            let proceeds: BridgedProceeds = from_json(&msg.payload)?;
+            UNBRIDGED_PROCEEDS.update(deps.storage, &proceeds.cw20_contract, |held| -> Result<_, ContractError> {
+                held.unwrap_or_default()
+                    .checked_add(proceeds.amount)
+                    .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+            })?;
+            Ok(Response::new()
+                .add_attribute("method", "proceeds_bridge_failed")
+                .add_attribute("cw20_contract", proceeds.cw20_contract)
+                .add_attribute("amount", proceeds.amount)
+                .add_attribute("error", error))
+        }
+        VESTING_RELEASE_REPLY_ID => Ok(Response::new()
+            .add_attribute("method", "vesting_release_failed")
+            .add_attribute("vesting_contract", String::from_utf8_lossy(&msg.payload))
+            .add_attribute("error", msg.result.into_result().err().unwrap_or_default())),
+        id => Err(ContractError::Std(StdError::msg(format!("unknown reply id {}", id)))),
+    }
+}
+
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::SetValidatorKickback { kickback } => set_validator_kickback(deps, env, kickback),
+        SudoMsg::SetPriceFloor { floor_price_usd } => set_price_floor(deps, env, floor_price_usd),
+        SudoMsg::SetCircuitBreaker { breaker } => set_circuit_breaker(deps, env, breaker),
+        SudoMsg::FinalizeLottery { seed } => finalize_lottery(deps, &env, seed.to_vec()),
+    }
+}
+
+fn set_validator_kickback(
+    deps: DepsMut,
+    env: Env,
+    kickback: Option<ValidatorKickback>,
+) -> Result<Response, ContractError> {
+    if let Some(kickback) = &kickback {
+        if kickback.bps > MAX_BPS {
+            return Err(ContractError::InvalidKickbackBps { bps: kickback.bps, max: MAX_BPS });
+        }
+        deps.api.addr_validate(&kickback.recipient)?;
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    let old_value = std::mem::replace(&mut config.validator_kickback, kickback.clone());
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &Addr::unchecked("sudo"),
+        "set_validator_kickback",
+        old_value.map(|k| format!("{} bps to {}", k.bps, k.recipient)),
+        kickback.as_ref().map(|k| format!("{} bps to {}", k.bps, k.recipient)),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_validator_kickback")
+        .add_attribute("bps", kickback.map_or(0, |k| k.bps).to_string()))
+}
+
+fn set_price_floor(deps: DepsMut, env: Env, floor_price_usd: Option<Uint128>) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let old_value = std::mem::replace(&mut config.price_floor_usd, floor_price_usd);
+    CONFIG.save(deps.storage, &config)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &Addr::unchecked("sudo"),
+        "set_price_floor",
+        old_value.map(|p| p.to_string()),
+        floor_price_usd.map(|p| p.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_price_floor")
+        .add_attribute("floor_price_usd", floor_price_usd.map_or("none".to_string(), |p| p.to_string())))
+}
+
+fn set_circuit_breaker(deps: DepsMut, env: Env, breaker: Option<CircuitBreaker>) -> Result<Response, ContractError> {
+    if let Some(breaker) = &breaker {
+        let invalid = |reason: &str| ContractError::InvalidCircuitBreaker { reason: reason.to_string() };
+        if breaker.window_blocks == 0 {
+            return Err(invalid("window_blocks must be positive"));
+        }
+        if breaker.trailing_windows == 0 || breaker.trailing_windows > MAX_TRAILING_WINDOWS {
+            return Err(invalid(&format!("trailing_windows must be between 1 and {MAX_TRAILING_WINDOWS}")));
+        }
+        if breaker.max_multiple_bps < MAX_BPS as u32 {
+            return Err(invalid("max_multiple_bps below 10000 would trip on ordinary volume"));
+        }
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    let old_value = std::mem::replace(&mut config.circuit_breaker, breaker.clone());
+    CONFIG.save(deps.storage, &config)?;
+    // Windows recorded under the old parameters would skew the new baseline
+    let windows = WINDOW_VOLUME
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for window in windows {
+        WINDOW_VOLUME.remove(deps.storage, window);
+    }
+    let describe = |b: &CircuitBreaker| {
+        format!(
+            "{} bps over {}x{} blocks, min {}",
+            b.max_multiple_bps, b.trailing_windows, b.window_blocks, b.min_volume_usd
+        )
+    };
+    record_audit(
+        deps.storage,
+        &env,
+        &Addr::unchecked("sudo"),
+        "set_circuit_breaker",
+        old_value.as_ref().map(describe),
+        breaker.as_ref().map(describe),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_circuit_breaker")
+        .add_attribute("enabled", breaker.is_some().to_string()))
+}
+
+/// Current window volume, trailing average and the limit the window is held to: the average
+/// scaled by `max_multiple_bps`, but never below `min_volume_usd`
+fn circuit_breaker_volumes(
+    storage: &dyn Storage,
+    breaker: &CircuitBreaker,
+    height: u64,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let window = height / breaker.window_blocks;
+    let current = WINDOW_VOLUME.may_load(storage, window)?.unwrap_or_default();
+    let oldest = window.saturating_sub(breaker.trailing_windows as u64);
+    let trailing = WINDOW_VOLUME
+        .range(storage, Some(Bound::inclusive(oldest)), Some(Bound::exclusive(window)), Order::Ascending)
+        .try_fold(Uint128::zero(), |sum, item| -> StdResult<_> { Ok(sum.checked_add(item?.1)?) })?;
+    let average = trailing / Uint128::from(breaker.trailing_windows);
+    let limit = average
+        .multiply_ratio(breaker.max_multiple_bps, MAX_BPS)
+        .max(breaker.min_volume_usd);
+    Ok((current, average, limit))
+}
+
+/// Adds a purchase to the current window and pauses purchases if the window exceeds the
+/// breaker's limit. The tripping purchase itself completes, since failing it would also roll
+/// back the pause; the returned alert event marks it.
+fn record_sale_volume(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &mut Config,
+    usd_amount: Uint128,
+) -> Result<Option<Event>, ContractError> {
+    let Some(breaker) = config.circuit_breaker.clone() else {
+        return Ok(None);
+    };
+    let window = env.block.height / breaker.window_blocks;
+    WINDOW_VOLUME.update(storage, window, |volume| -> Result<_, ContractError> {
+        volume
+            .unwrap_or_default()
+            .checked_add(usd_amount)
+            .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))
+    })?;
+    let oldest = window.saturating_sub(breaker.trailing_windows as u64);
+    let stale = WINDOW_VOLUME
+        .keys(storage, None, Some(Bound::exclusive(oldest)), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for window in stale {
+        WINDOW_VOLUME.remove(storage, window);
+    }
+
+    let (volume, average, limit) = circuit_breaker_volumes(storage, &breaker, env.block.height)?;
+    if volume <= limit {
+        return Ok(None);
+    }
+    config.is_paused = true;
+    record_audit(
+        storage,
+        env,
+        &Addr::unchecked("circuit_breaker"),
+        "pause",
+        Some("false".to_string()),
+        Some("true".to_string()),
+    )?;
+    Ok(Some(
+        Event::new("circuit_breaker_tripped")
+            .add_attribute("window_volume_usd", volume)
+            .add_attribute("trailing_average_usd", average)
+            .add_attribute("limit_usd", limit),
+    ))
+}
+
+fn query_circuit_breaker(deps: Deps, env: Env) -> StdResult<CircuitBreakerResponse> {
+    let breaker = CONFIG.load(deps.storage)?.circuit_breaker;
+    let (window_volume_usd, trailing_average_usd, limit_usd) = match &breaker {
+        Some(breaker) => circuit_breaker_volumes(deps.storage, breaker, env.block.height)?,
+        None => Default::default(),
+    };
+    Ok(CircuitBreakerResponse {
+        breaker,
+        window_volume_usd,
+        trailing_average_usd,
+        limit_usd,
+    })
+}
+
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: Binary) -> Result<Response, ContractError> {
+    let old = get_contract_version(deps.storage)
+        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
+        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", old.version.clone())
+        .add_attribute("to_version", CONTRACT_VERSION);
+    if let Some(progress) = migrate_v1_config(deps.branch(), &env, old.version)? {
+        response = response.add_attribute("backfill_stage", format!("{:?}", progress.stage));
+    }
+    seed_reserves(deps.storage)?;
+    seed_total_raised(deps.storage)?;
+    seed_sale_reserves(deps.storage)?;
+    seed_buyer_subscriptions(deps.storage)?;
+    seed_lottery_claim_deadline(deps.storage, &env)?;
+    seed_reservation_expiries(deps.storage)?;
+    Ok(response)
+}
+
+/// Converts a v1 single-buyer config in place and pauses the sale until ContinueMigration has
+/// run every backfill step. Returns None when the stored config is not v1.
+fn migrate_v1_config(deps: DepsMut, env: &Env, from_version: String) -> Result<Option<MigrationProgress>, ContractError> {
+    let Some(raw) = deps.storage.get(CONFIG.as_slice()) else {
+        return Ok(None);
+    };
+    let Ok(v1) = from_json::<ConfigV1>(&raw) else {
+        return Ok(None);
+    };
+    // The single accepted token and buyer map onto the same fields of the current layout;
+    // every later addition starts disabled
+    let config = Config {
+        admin: v1.admin,
+        buyer: v1.buyer,
+        accepted_chain_id: v1.accepted_chain_id,
+        accepted_eth_contract: ExternalAddress::from(v1.accepted_eth_contract),
+        price_usd: v1.price_usd,
+        native_denom: v1.native_denom,
+        payout_decimals: DEFAULT_PAYOUT_DECIMALS,
+        alternative_payout_denoms: vec![],
+        vesting: None,
+        purchase_limits: None,
+        timelock_delay_blocks: 0,
+        crank_reward: Uint128::zero(),
+        commit_reveal_window_blocks: 0,
+        expected_module_version: None,
+        receipt_nft_contract: None,
+        require_attestation: false,
+        debug_grpc_enabled: false,
+        state_export_enabled: false,
+        validator_kickback: None,
+        price_floor_usd: None,
+        max_raise_usd: None,
+        total_raised_usd: Uint128::zero(),
+        circuit_breaker: None,
+        proceeds_bridge: None,
+        proceeds_stream: None,
+        treasury: None,
+        name_bindings: None,
+        co_approval: None,
+        trusted_payment_token: None,
+        is_paused: true,
+        total_tokens_sold: v1.total_tokens_sold,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    let progress = MigrationProgress {
+        from_version,
+        stage: MigrationStage::DenomAccounting,
+        was_paused: v1.is_paused,
+        started_at_height: env.block.height,
+    };
+    MIGRATION.save(deps.storage, &progress)?;
+    Ok(Some(progress))
+}
+
+/// Configs from before the raise cap start with nothing raised; values what was already sold at
+/// the default price, the price almost all of it sold at, so the cap counts it
+fn seed_total_raised(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let mut config = CONFIG.load(storage)?;
+    if !config.total_raised_usd.is_zero() || config.total_tokens_sold.is_zero() {
+        return Ok(());
+    }
+    config.total_raised_usd = calculate_usd_for_tokens(config.total_tokens_sold, config.price_usd, config.payout_decimals)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    CONFIG.save(storage, &config)?;
+    Ok(())
+}
+
+/// Runs one backfill step so each transaction stays within gas limits
+fn continue_migration(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    ensure_owner(&config.admin, info.sender.as_str())?;
+    let mut progress = MIGRATION.may_load(deps.storage)?.ok_or(ContractError::NoMigrationPending {})?;
+    let step = progress.stage.clone();
+    progress.stage = match step {
+        MigrationStage::DenomAccounting => {
+            // v1 only paid out the native denom, so its whole total belongs there
+            if !TOKENS_SOLD_BY_DENOM.has(deps.storage, &config.native_denom) {
+                TOKENS_SOLD_BY_DENOM.save(deps.storage, &config.native_denom, &config.total_tokens_sold)?;
+            }
+            MigrationStage::RestorePause
+        }
+        MigrationStage::RestorePause => {
+            config.is_paused = progress.was_paused;
+            CONFIG.save(deps.storage, &config)?;
+            MigrationStage::Complete
+        }
+        MigrationStage::Complete => return Err(ContractError::NoMigrationPending {}),
+    };
+    MIGRATION.save(deps.storage, &progress)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "continue_migration",
+        Some(format!("{:?}", step)),
+        Some(format!("{:?}", progress.stage)),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "continue_migration")
+        .add_attribute("completed_stage", format!("{:?}", step))
+        .add_attribute("next_stage", format!("{:?}", progress.stage)))
+}
+
+fn ensure_migration_complete(storage: &dyn Storage) -> Result<(), ContractError> {
+    match MIGRATION.may_load(storage)? {
+        Some(progress) if progress.stage != MigrationStage::Complete => Err(ContractError::MigrationInProgress {}),
+        _ => Ok(()),
+    }
+}
+
+fn query_unbridged_proceeds(deps: Deps) -> StdResult<UnbridgedProceedsResponse> {
+    let proceeds = UNBRIDGED_PROCEEDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(cw20_contract, amount)| UnbridgedProceedsEntry { cw20_contract, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(UnbridgedProceedsResponse { proceeds })
+}
+
+fn query_streamed_proceeds(deps: Deps) -> StdResult<StreamedProceedsResponse> {
+    let proceeds = STREAMED_PROCEEDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|((cw20_contract, recipient), amount)| StreamedProceedsEntry { cw20_contract, recipient, amount })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(StreamedProceedsResponse {
+        proceeds,
+        next_release_height: CONFIG.load(deps.storage)?.proceeds_stream.map(|s| s.next_release_height),
+    })
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        admin: config.admin,
+        buyer: config.buyer,
+        accepted_chain_id: config.accepted_chain_id,
+        accepted_eth_contract: config.accepted_eth_contract.into(),
+        price_usd: config.price_usd,
+        native_denom: config.native_denom,
+        payout_decimals: config.payout_decimals,
+        alternative_payout_denoms: config.alternative_payout_denoms,
+        vesting: config.vesting,
+        purchase_limits: config.purchase_limits,
+        timelock_delay_blocks: config.timelock_delay_blocks,
+        crank_reward: config.crank_reward,
+        commit_reveal_window_blocks: config.commit_reveal_window_blocks,
+        expected_module_version: config.expected_module_version,
+        receipt_nft_contract: config.receipt_nft_contract,
+        require_attestation: config.require_attestation,
+        debug_grpc_enabled: config.debug_grpc_enabled,
+        state_export_enabled: config.state_export_enabled,
+        validator_kickback: config.validator_kickback,
+        price_floor_usd: config.price_floor_usd,
+        max_raise_usd: config.max_raise_usd,
+        total_raised_usd: config.total_raised_usd,
+        proceeds_bridge: config.proceeds_bridge,
+        proceeds_stream: config.proceeds_stream,
+        treasury: config.treasury,
+        name_bindings: config.name_bindings,
+        co_approval: config.co_approval,
+        trusted_payment_token: config.trusted_payment_token,
+        is_paused: config.is_paused,
+        total_tokens_sold: config.total_tokens_sold,
+    })
+}
+
+fn query_status(deps: Deps, env: Env) -> StdResult<StatusResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.native_denom)?;
+    let reserved_inventory = reserved_native(deps.storage)?;
+    let remaining_inventory = Coin {
+        amount: balance.amount.saturating_sub(reserved_inventory.into()),
+        denom: balance.denom,
+    };
+    let (current_price_usd, price_source) = base_price(deps, &env, &config)?;
+    let epoch_sale = current_epoch_sale(deps)?;
+    let epoch_raised_usd = match &epoch_sale {
+        Some((epoch, _)) => EPOCH_RAISED.may_load(deps.storage, *epoch)?.unwrap_or_default(),
+        None => Uint128::zero(),
+    };
+    let timelock_queue_length = TIMELOCK_QUEUE
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count() as u64;
+    let next_timelock_eta = TIMELOCK_QUEUE
+        .keys(deps.storage, None, None, Order::Ascending)
+        .next()
+        .transpose()?
+        .map(|(eta_height, _)| eta_height);
+    Ok(StatusResponse {
+        is_paused: config.is_paused,
+        price_usd: config.price_usd,
+        current_price_usd,
+        price_source: price_source.to_string(),
+        active_round: active_round(deps.storage, env.block.height)?.map(|(id, _)| id),
+        remaining_inventory,
+        reserved_inventory,
+        total_tokens_sold: config.total_tokens_sold,
+        total_raised_usd: config.total_raised_usd,
+        max_raise_usd: config.max_raise_usd,
+        remaining_raise_usd: config.max_raise_usd.map(|max| max.saturating_sub(config.total_raised_usd)),
+        current_epoch: epoch_sale.as_ref().map(|(epoch, _)| *epoch),
+        epoch_cap_usd: epoch_sale.and_then(|(_, sale)| sale.cap_usd),
+        epoch_raised_usd,
+        timelock_queue_length,
+        next_timelock_eta,
+        last_purchase_height: LAST_PURCHASE_HEIGHT.may_load(deps.storage)?,
+    })
+}
+
+fn query_audit_log(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<AuditLogResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(start_after);
+    let entries = AUDIT_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_start_after(&entries, limit, |entry| entry.id);
+    Ok(AuditLogResponse { entries, next_start_after })
+}
+
+fn query_subscriptions(
+    deps: Deps,
+    buyer: Option<String>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SubscriptionsResponse> {
+    let limit = page_limit(limit);
+    let subscriptions = SUBSCRIPTIONS
+        .range(deps.storage, start_bound(start_after), None, Order::Ascending)
+        .map(|item| item.map(|(_, subscription)| subscription))
+        .filter(|item| match (item, &buyer) {
+            (Ok(subscription), Some(buyer)) => subscription.buyer == *buyer,
+            _ => true,
+        })
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_start_after(&subscriptions, limit, |subscription| subscription.id);
+    Ok(SubscriptionsResponse { subscriptions, next_start_after })
+}
+
+fn query_price_history(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<PriceHistoryResponse> {
+    let limit = page_limit(limit);
+    let changes = PRICE_HISTORY
+        .range(deps.storage, start_bound(start_after), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, change)| change))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_start_after(&changes, limit, |change| change.id);
+    Ok(PriceHistoryResponse { changes, next_start_after })
+}
+
+fn query_module_version_status(deps: Deps) -> StdResult<ModuleVersionResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ModuleVersionResponse {
+        module: proto::INFERENCE_MODULE_NAME.to_string(),
+        expected: config.expected_module_version,
+        actual: query_module_version(deps).ok().flatten(),
+    })
+}
+
+fn query_native_balance(deps: Deps, env: Env) -> StdResult<NativeBalanceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &config.native_denom)?;
+    Ok(NativeBalanceResponse { balance })
+}
+
+fn query_payout_denoms(deps: Deps, env: Env) -> StdResult<PayoutDenomsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let denoms = std::iter::once(config.native_denom)
+        .chain(config.alternative_payout_denoms)
+        .map(|denom| {
+            let tokens_sold = TOKENS_SOLD_BY_DENOM
+                .may_load(deps.storage, &denom)?
+                .unwrap_or_default();
+            let balance = deps.querier.query_balance(&env.contract.address, &denom)?;
+            Ok(PayoutDenomInfo { denom, tokens_sold, balance })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PayoutDenomsResponse { denoms })
+}
+
+fn query_export_whitelist(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<WhitelistResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(start_after.as_deref());
+    let entries = WHITELIST
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(address, entry)| WhitelistEntryResponse {
+                address,
+                allocation_usd: entry.allocation_usd,
+                purchased_usd: entry.purchased_usd,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = next_start_after(&entries, limit, |entry| entry.address.clone());
+    Ok(WhitelistResponse { entries, next_start_after })
+}
+
+fn query_deposits(deps: Deps, start_after: Option<(String, String)>, limit: Option<u32>) -> StdResult<DepositsResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(
+        start_after
+            .as_ref()
+            .map(|(depositor, denom)| (depositor.as_str(), denom.as_str())),
+    );
+    let deposits = DEPOSITS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|((depositor, denom), amount)| DepositEntry { depositor, denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(DepositsResponse {
+        next_start_after: next_start_after(&deposits, limit, |entry| (entry.depositor.clone(), entry.denom.clone())),
+        deposits,
+        reserved: reserved_native(deps.storage)?,
+    })
+}
+
+fn query_reserves(deps: Deps, env: Env) -> StdResult<ReservesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let balance: Uint128 = deps
+        .querier
+        .query_balance(env.contract.address.to_string(), &config.native_denom)?
+        .amount
+        .try_into()
+        .map_err(|_| StdError::msg("balance exceeds Uint128"))?;
+    let obligations = RESERVES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(obligation, amount)| ReserveEntry { obligation, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    let reserved = reserved_native(deps.storage)?;
+    Ok(ReservesResponse {
+        balance,
+        reserved,
+        free: balance.saturating_sub(reserved),
+        obligations,
+    })
+}
+
+fn query_rounds(deps: Deps, env: Env, start_after: Option<u32>, limit: Option<u32>) -> StdResult<RoundsResponse> {
+    let limit = page_limit(limit);
+    let rounds = ROUNDS
+        .range(deps.storage, start_bound(start_after), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, round)| RoundInfo { id, round }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(RoundsResponse {
+        next_start_after: next_start_after(&rounds, limit, |info| info.id),
+        rounds,
+        active_round: active_round(deps.storage, env.block.height)?.map(|(id, _)| id),
+    })
+}
+
+fn query_epoch_schedule(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<EpochScheduleResponse> {
+    let limit = page_limit(limit);
+    let epochs = EPOCH_SCHEDULE
+        .range(deps.storage, start_bound(start_after), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (epoch, sale) = item?;
+            let raised_usd = EPOCH_RAISED.may_load(deps.storage, epoch)?.unwrap_or_default();
+            Ok(EpochSaleInfo { epoch, sale, raised_usd })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(EpochScheduleResponse {
+        next_start_after: next_start_after(&epochs, limit, |info| info.epoch),
+        epochs,
+        current_epoch: query_current_epoch(deps).ok(),
+    })
+}
+
+fn query_sales(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<SalesResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(start_after);
+    let sales = SALES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, sale)| SaleResponse { id, sale }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SalesResponse {
+        next_start_after: next_start_after(&sales, limit, |sale| sale.id),
+        sales,
+    })
+}
+
+fn pool_response(label: String, pool: InventoryPool) -> PoolResponse {
+    PoolResponse {
+        remaining: pool.cap.saturating_sub(pool.sold),
+        label,
+        pool,
+    }
+}
+
+fn query_pools(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<PoolsResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(start_after.as_deref());
+    let pools = POOLS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(label, pool)| pool_response(label, pool)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(PoolsResponse {
+        next_start_after: next_start_after(&pools, limit, |info| info.label.clone()),
+        pools,
+    })
+}
+
+fn query_buyer_pools(deps: Deps, buyer: String) -> StdResult<BuyerPoolsResponse> {
+    let labels = POOLS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let pools = labels
+        .into_iter()
+        .filter(|label| POOL_BUYERS.has(deps.storage, (label.as_str(), buyer.as_str())))
+        .collect();
+    Ok(BuyerPoolsResponse { buyer, pools })
+}
+
+fn query_buyer_prices(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<BuyerPricesResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(start_after.as_deref());
+    let prices = BUYER_PRICES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(buyer, price)| BuyerPriceResponse { buyer, price }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(BuyerPricesResponse {
+        next_start_after: next_start_after(&prices, limit, |price| price.buyer.clone()),
+        prices,
+    })
+}
+
+fn query_queued_actions(
+    deps: Deps,
+    start_after: Option<(u64, u64)>,
+    limit: Option<u32>,
+) -> StdResult<QueuedActionsResponse> {
+    let limit = page_limit(limit);
+    let start = start_bound(start_after);
+    let actions = TIMELOCK_QUEUE
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, queued)| queued))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(QueuedActionsResponse {
+        next_start_after: next_start_after(&actions, limit, |queued| (queued.eta_height, queued.id)),
+        actions,
+    })
+}
+
+fn query_purchase_limits(deps: Deps) -> StdResult<PurchaseLimitsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limits = resolve_purchase_limits(deps, &config)
+        .map_err(|e| StdError::msg(e.to_string()))?;
+    Ok(PurchaseLimitsResponse {
+        source: config.purchase_limits,
+        limits,
+    })
+}
+
+fn query_vesting_contracts(deps: Deps, buyer: String) -> StdResult<VestingContractsResponse> {
+    let contracts = VESTING_CONTRACTS
+        .prefix(&buyer)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, addr)| addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(VestingContractsResponse { buyer, contracts })
+}
+
+fn query_calculate_tokens(deps: Deps, usd_amount: Uint128) -> StdResult<TokenCalculationResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let tokens = calculate_tokens_for_usd(usd_amount, config.price_usd, config.payout_decimals);
+    Ok(TokenCalculationResponse {
+        tokens,
+        price_usd: config.price_usd,
+    })
+}
+
+fn query_can_purchase(deps: Deps, env: Env, buyer: String, usd_amount: Uint128) -> StdResult<CanPurchaseResponse> {
+    Ok(match check_can_purchase(deps, &env, &buyer, usd_amount) {
+        Ok((price_usd, tokens)) => CanPurchaseResponse {
+            can_purchase: true,
+            error_code: None,
+            reason: None,
+            price_usd,
+            tokens,
+        },
+        Err(err) => CanPurchaseResponse {
+            can_purchase: false,
+            error_code: Some(err.code()),
+            reason: Some(err.to_string()),
+            price_usd: Uint128::zero(),
+            tokens: Uint128::zero(),
+        },
+    })
+}
+
+/// The checks a plain purchase of `usd_amount` by `buyer` runs, in the same order, without
+/// writing anything. Commitments, quotes and per-token prices are not considered. Returns the
+/// price and the tokens the purchase would pay out.
+fn check_can_purchase(deps: Deps, env: &Env, buyer: &str, usd_amount: Uint128) -> Result<(Uint128, Uint128), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    ensure_not_paused(config.is_paused)?;
+    let whitelist_entry = WHITELIST.may_load(deps.storage, buyer)?;
+    if buyer != config.buyer && whitelist_entry.is_none() {
+        return Err(ContractError::BuyerNotAllowed { buyer: buyer.to_string() });
+    }
+    // A payment during a lottery's commit window becomes an entry, not a purchase
+    if let Some(lottery) = LOTTERY.may_load(deps.storage)? {
+        if lottery.seed.is_none() {
+            return Err(ContractError::LotteryActive {});
+        }
+    }
+    let round = active_round(deps.storage, env.block.height)?;
+    if round.is_none() && !ROUNDS.is_empty(deps.storage) {
+        return Err(ContractError::NoActiveRound {});
+    }
+    if usd_amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    // Checked after the local state, in the order a purchase checks them
+    if config.require_attestation {
+        ensure_attested(deps, buyer)?;
+    }
+
+    let price = match BUYER_PRICES.may_load(deps.storage, buyer)? {
+        Some(price) => price,
+        None => round.map_or(config.price_usd, |(_, round)| round.price_usd),
+    };
+    if let Some(floor) = config.price_floor_usd {
+        if price < floor {
+            return Err(ContractError::PriceBelowFloor { price: price.u128(), floor: floor.u128() });
+        }
+    }
+    let (tokens, cost) = split_usd_payment(usd_amount, price, config.payout_decimals)
+        .map_err(|e| ContractError::Std(StdError::msg(format!("overflow: {}", e))))?;
+    check_raise_cap(&config, cost)?;
+    if buyer != config.buyer {
+        if let Some(entry) = whitelist_entry {
+            let remaining = entry.allocation_usd.saturating_sub(entry.purchased_usd);
+            if cost > remaining {
+                return Err(ContractError::AllocationExceeded {
+                    buyer: buyer.to_string(),
+                    remaining: remaining.u128(),
+                });
+            }
+        }
+    }
+    if let Some(limits) = resolve_purchase_limits(deps, &config)? {
+        check_purchase_limits(&limits, cost, price)?;
+    }
+    check_purchase_approval(deps.storage, env, &config, buyer, cost)?;
+    check_epoch_cap(deps, cost)?;
+    if tokens.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let balance = free_native(deps, env, &config)?;
+    if tokens > balance {
+        return Err(ContractError::InsufficientBalance {
+            available: balance.u128(),
+            needed: tokens.u128(),
+        });
+    }
+    select_pool(deps.storage, buyer, tokens)?;
+    Ok((price, tokens))
+}
+
+fn query_calculate_usd_for_tokens(deps: Deps, token_amount: Uint128) -> StdResult<UsdCalculationResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let usd_amount = calculate_usd_for_tokens(token_amount, config.price_usd, config.payout_decimals)
+        .map_err(|e| StdError::msg(format!("overflow: {}", e)))?;
+    Ok(UsdCalculationResponse {
+        usd_amount,
+        price_usd: config.price_usd,
+    })
+}
+
+fn query_calculate_tokens_for_token(
+    deps: Deps,
+    env: Env,
+    cw20_contract: String,
+    amount: Uint128,
+) -> StdResult<TokenCalculationResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let price = effective_price(deps, &env, &config, &cw20_contract)?;
+    Ok(TokenCalculationResponse {
+        tokens: calculate_tokens_for_usd(amount, price, config.payout_decimals),
+        price_usd: price,
+    })
+}
+
+fn query_token_prices(deps: Deps) -> StdResult<TokenPricesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let prices = TOKEN_PRICES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(cw20_contract, price)| TokenPriceResponse { cw20_contract, price }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TokenPricesResponse {
+        default_price_usd: config.price_usd,
+        prices,
+    })
+}
+
+fn query_contract_info(deps: Deps) -> StdResult<ContractInfoResponse> {
+    let version = get_contract_version(deps.storage)?;
+    Ok(ContractInfoResponse {
+        name: version.contract,
+        version: version.version,
+        schema_version: SCHEMA_VERSION,
+        purchase_msg_version: PURCHASE_MSG_VERSION,
+    })
+}
+
+#[cfg(feature = "debug")]
+fn query_test_bridge_validation(deps: Deps, cw20_contract: String) -> StdResult<TestBridgeValidationResponse> {
+    let denom = if cw20_contract.starts_with("cw20:") {
+        cw20_contract
+    } else {
+        format!("cw20:{}", cw20_contract)
+    };
+    let is_valid = validate_wrapped_token_for_trade(deps, &denom).unwrap_or(false);
+    Ok(TestBridgeValidationResponse { is_valid })
+}
+
+#[cfg(feature = "debug")]
+fn query_block_height(env: Env) -> StdResult<BlockHeightResponse> {
+    Ok(BlockHeightResponse { height: env.block.height })
+}
+
+#[cfg(feature = "debug")]
+fn query_test_approved_tokens(deps: Deps) -> StdResult<ApprovedTokensForTradeJson> {
+    let decoded: proto::QueryApprovedTokensForTradeResponseProto = query_proto(
+        deps,
+        proto::APPROVED_TOKENS_FOR_TRADE_PATH,
+        &proto::EmptyRequest::default(),
+    )?;
+    let approved_tokens = decoded
+        .approved_tokens
+        .into_iter()
+        .map(|t| ApprovedTokenJson {
+            chain_id: t.chain_id,
+            contract_address: ExternalAddress::from(t.contract_address).into(),
+        })
+        .collect();
+    Ok(ApprovedTokensForTradeJson { approved_tokens })
+}
+
+fn query_export_state(
+    deps: Deps,
+    section: StateSection,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ExportStateResponse> {
+    if !CONFIG.load(deps.storage)?.state_export_enabled {
+        return Err(StdError::msg("ExportState is disabled"));
+    }
+    export_state(deps.storage, section, start_after, limit)
+}
+
+fn query_debug_grpc(deps: Deps, path: String, data: Binary) -> StdResult<DebugGrpcResponse> {
+    if !CONFIG.load(deps.storage)?.debug_grpc_enabled {
+        return Err(StdError::msg("DebugGrpc is disabled"));
+    }
+    // Query failures are reported in the response rather than failing the debug query itself
+    let (response, error) = match query_grpc(deps, &path, data) {
+        Ok(bytes) => (Some(bytes), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let decode_attempts = match &response {
+        Some(bytes) => proto::decode_known_responses(bytes.as_slice())
+            .into_iter()
+            .map(|(type_name, result)| {
+                let (decoded, error) = match result {
+                    Ok(decoded) => (Some(decoded), None),
+                    Err(e) => (None, Some(e)),
+                };
+                DecodeAttempt {
+                    type_name: type_name.to_string(),
+                    decoded,
+                    error,
+                }
+            })
+            .collect(),
+        None => vec![],
+    };
+    Ok(DebugGrpcResponse {
+        path,
+        response,
+        error,
+        decode_attempts,
+    })
+}
+
+fn query_grpc(deps: Deps, path: &str, data: Binary) -> StdResult<Binary> {
+    let request = QueryRequest::Grpc(GrpcQuery {
+        path: path.to_string(),
+        data,
+    });
+    query_raw(deps, &request)
+}
+
+fn query_raw(deps: Deps, request: &QueryRequest<GrpcQuery>) -> StdResult<Binary> {
+    let raw = to_json_vec(request).map_err(|e| StdError::msg(format!("Serializing: {e}")))?;
+    match deps.querier.raw_query(&raw) {
+        SystemResult::Err(e) => Err(StdError::msg(format!("System error: {e}"))),
+        SystemResult::Ok(ContractResult::Err(e)) => Err(StdError::msg(format!("Contract error: {e}"))),
+        SystemResult::Ok(ContractResult::Ok(value)) => Ok(value),
+    }
+}
+
+fn query_proto<TRequest, TResponse>(deps: Deps, path: &str, request: &TRequest) -> StdResult<TResponse>
+where
+    TRequest: prost::Message,
+    TResponse: prost::Message + Default,
+{
+    let mut buf = Vec::new();
+    request.encode(&mut buf).map_err(|e| StdError::msg(format!("Encode: {}", e)))?;
+    let bytes = query_grpc(deps, path, Binary::from(buf))?;
+    TResponse::decode(bytes.as_slice()).map_err(|e| StdError::msg(format!("Decode: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::StructuredError;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::{from_json, Addr, MessageInfo};
+
+    fn mock_instantiate_msg(api: &MockApi) -> InstantiateMsg {
+        InstantiateMsg {
+            admin: api.addr_make("admin").to_string(),
+            buyer: api.addr_make("buyer").to_string(),
+            accepted_chain_id: "ethereum".to_string(),
+            accepted_eth_contract: "0xdac17f958d2ee523a2206206994597c13d831ec7".to_string(),
+            price_usd: Uint128::from(25000u128), // $0.025
+            alternative_payout_denoms: None,
+            vesting: None,
+            purchase_limits: None,
+            expected_module_version: None,
+            receipt_nft_contract: None,
+            payout_decimals: None,
+            max_raise_usd: None,
+        }
+    }
+
+    #[test]
+    fn proper_instantiation() {
+        let deps = mock_dependencies();
+        let api = MockApi::default();
+        let buyer_addr = api.addr_make("buyer").to_string();
+        
+        let mut deps = deps;
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+
+        let res = instantiate(deps.as_mut(), env, info, mock_instantiate_msg(&api)).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "buyer" && a.value == buyer_addr));
+        assert!(res.attributes.iter().any(|a| a.key == "accepted_chain_id" && a.value == "ethereum"));
+        assert!(res.attributes.iter().any(|a| a.key == "accepted_eth_contract" && a.value == "0xdac17f958d2ee523a2206206994597c13d831ec7"));
+    }
+
+    #[test]
+    fn test_pause_resume() {
+        let deps = mock_dependencies();
+        let api = MockApi::default();
+        let admin_addr = api.addr_make("admin");
+        
+        let mut deps = deps;
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let info = MessageInfo {
+            sender: admin_addr.clone(),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Pause {}).unwrap();
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert!(config.is_paused);
+
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Resume {}).unwrap();
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert!(!config.is_paused);
+    }
+
+    #[test]
+    fn test_update_buyer() {
+        let deps = mock_dependencies();
+        let api = MockApi::default();
+        let admin_addr = api.addr_make("admin");
+        let new_buyer = api.addr_make("newbuyer").to_string();
+        
+        let mut deps = deps;
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let info = MessageInfo {
+            sender: admin_addr,
+            funds: vec![],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::UpdateBuyer { buyer: new_buyer.clone() },
+        )
+        .unwrap();
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.buyer, new_buyer);
+    }
+
+    #[test]
+    fn test_update_price() {
+        let deps = mock_dependencies();
+        let api = MockApi::default();
+        let admin_addr = api.addr_make("admin");
+        
+        let mut deps = deps;
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let info = MessageInfo {
+            sender: admin_addr,
+            funds: vec![],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::UpdatePrice { price_usd: Uint128::from(50000u128) },
+        )
+        .unwrap();
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.price_usd, Uint128::from(50000u128));
+    }
+
+    #[test]
+    fn test_calculate_tokens() {
+        let deps = mock_dependencies();
+        let api = MockApi::default();
+        
+        let mut deps = deps;
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let usd_amount = Uint128::from(100_000_000u128); // $100
+        let response: TokenCalculationResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::CalculateTokens { usd_amount }).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(response.tokens, Uint128::from(4_000_000_000_000u128));
+        assert_eq!(response.price_usd, Uint128::from(25000u128));
+    }
+
+    #[test]
+    fn test_payout_decimals_are_validated_and_scale_payouts() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let mut msg = mock_instantiate_msg(&api);
+        msg.payout_decimals = Some(MAX_PAYOUT_DECIMALS + 1);
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPayoutDecimals { decimals: 19, max: MAX_PAYOUT_DECIMALS }));
+
+        msg.payout_decimals = Some(6);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let config: ConfigResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.payout_decimals, 6);
+
+        // $1 at $0.025 is 40 whole tokens, 40_000_000 base units of a 6-decimal denom
+        let tokens: TokenCalculationResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::CalculateTokens { usd_amount: Uint128::new(1_000_000) }).unwrap())
+                .unwrap();
+        assert_eq!(tokens.tokens, Uint128::new(40_000_000));
+        let usd: UsdCalculationResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::CalculateUsdForTokens { token_amount: Uint128::new(40_000_000) }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(usd.usd_amount, Uint128::new(1_000_000));
+
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000u128, config.native_denom.clone())]);
+        let pay = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: api.addr_make("buyer").to_string(),
+            amount: Uint128::new(1_000_000),
+            msg: to_json_binary(&PurchaseTokenMsg {
+                v: None,
+                payout_denom: None,
+                salt: None,
+                exact_out: None,
+                intent: None,
+                sale_id: None,
+                reservation_id: None,
+                quote_id: None,
+            })
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), env, wusdt, pay).unwrap();
+        let payout = CosmosMsg::Bank(BankMsg::Send {
+            to_address: api.addr_make("buyer").to_string(),
+            amount: vec![Coin::new(40_000_000u128, config.native_denom)],
+        });
+        assert!(res.messages.iter().any(|m| m.msg == payout));
+    }
+
+    #[test]
+    fn test_calculate_usd_for_tokens_rounds_up() {
+        let price = Uint128::from(25000u128); // $0.025
+        // 4000 GNK cost exactly $100
+        assert_eq!(
+            calculate_usd_for_tokens(Uint128::from(4_000_000_000_000u128), price, 9).unwrap(),
+            Uint128::from(100_000_000u128)
+        );
+        // 1 ngonka costs a fraction of a micro-USD, charged as a full one
+        assert_eq!(calculate_usd_for_tokens(Uint128::one(), price, 9).unwrap(), Uint128::one());
+        assert!(calculate_usd_for_tokens(Uint128::MAX, price, 9).is_err());
+
+        // The query quotes the same rounded-up cost an exact-out purchase is charged
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let quote: UsdCalculationResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::CalculateUsdForTokens { token_amount: Uint128::from(4_000_000_000_001u128) })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(quote.usd_amount, Uint128::from(100_000_001u128));
+        assert_eq!(quote.price_usd, price);
+        // With a 6-decimal payout denom, $100 buys the same 4000 whole tokens
+        assert_eq!(
+            calculate_tokens_for_usd(Uint128::from(100_000_000u128), price, 6),
+            Uint128::from(4_000_000_000u128)
+        );
+    }
+
+    #[test]
+    fn test_split_usd_payment_leaves_dust() {
+        let price = Uint128::from(25000u128); // $0.025
+        // With whole-token payouts, $100.000001 buys 4000 tokens for $100; 1 micro-USD is dust
+        assert_eq!(
+            split_usd_payment(Uint128::from(100_000_001u128), price, 0).unwrap(),
+            (Uint128::from(4000u128), Uint128::from(100_000_000u128))
+        );
+        // Below the price of one base unit everything is dust
+        assert_eq!(split_usd_payment(Uint128::from(24_999u128), price, 0).unwrap(), (Uint128::zero(), Uint128::zero()));
+        // Fine-grained denoms absorb the remainder into the rounded-up cost
+        assert_eq!(
+            split_usd_payment(Uint128::from(100_000_001u128), price, 9).unwrap(),
+            (Uint128::from(4_000_000_040_000u128), Uint128::from(100_000_001u128))
+        );
+    }
+
+    #[test]
+    fn test_update_payout_denoms() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let admin_addr = api.addr_make("admin");
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let info = MessageInfo {
+            sender: admin_addr,
+            funds: vec![],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            ExecuteMsg::UpdatePayoutDenoms { denoms: vec!["stgonka".to_string()] },
+        )
+        .unwrap();
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.alternative_payout_denoms, vec!["stgonka".to_string()]);
+
+        // The native denom cannot be listed as an alternative
+        let err = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::UpdatePayoutDenoms { denoms: vec![config.native_denom] },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::PayoutDenomNotAccepted { .. }));
+    }
+
+    #[test]
+    fn test_vesting_config_validation() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let mut msg = mock_instantiate_msg(&api);
+        msg.vesting = Some(VestingConfig {
+            code_id: 7,
+            cliff_seconds: 100,
+            duration_seconds: 50,
+        });
+        let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVestingConfig { .. }));
+
+        msg.vesting = Some(VestingConfig {
+            code_id: 7,
+            cliff_seconds: 50,
+            duration_seconds: 100,
+        });
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.vesting.unwrap().code_id, 7);
+    }
+
+    #[test]
+    fn test_check_purchase_limits() {
+        let limits = PurchaseLimits {
+            min_purchase_usd: Uint128::from(1_000_000u128),
+            max_purchase_usd: Uint128::from(10_000_000u128),
+            min_price_usd: Uint128::zero(),
+            max_price_usd: Uint128::from(30_000u128),
+        };
+        let price = Uint128::from(25_000u128);
+        check_purchase_limits(&limits, Uint128::from(5_000_000u128), price).unwrap();
+
+        let err = check_purchase_limits(&limits, Uint128::from(500_000u128), price).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseOutOfLimits { .. }));
+        let err = check_purchase_limits(&limits, Uint128::from(20_000_000u128), price).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseOutOfLimits { .. }));
+        let err = check_purchase_limits(&limits, Uint128::from(5_000_000u128), Uint128::from(40_000u128))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::PriceOutOfBounds { .. }));
+
+        // Zero bounds are unbounded
+        check_purchase_limits(&PurchaseLimits::default(), Uint128::from(1u128), price).unwrap();
+    }
+
+    #[test]
+    fn test_import_whitelist() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        let alice = api.addr_make("alice").to_string();
+        let bob = api.addr_make("bob").to_string();
+        let entries = vec![
+            (alice.clone(), Uint128::from(1_000_000u128)),
+            (bob.clone(), Uint128::from(2_000_000u128)),
+        ];
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::ImportWhitelist { entries: entries.clone(), batch: 0 },
+        )
+        .unwrap();
+
+        // Replaying the same batch index is rejected by the cursor
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::ImportWhitelist { entries: entries.clone(), batch: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::WhitelistBatchOutOfOrder { expected: 1, got: 0 }));
+
+        // Re-importing an existing address is a duplicate
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ExecuteMsg::ImportWhitelist { entries, batch: 1 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateWhitelistEntry { .. }));
+
+        let page: WhitelistResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ExportWhitelist { start_after: None, limit: Some(1) },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.next_start_after.as_ref(), Some(&page.entries[0].address));
+        let next: WhitelistResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::ExportWhitelist {
+                    start_after: page.next_start_after,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(next.entries.len(), 1);
+        assert_eq!(next.next_start_after, None);
+        assert_ne!(next.entries[0].address, page.entries[0].address);
+    }
+
+    #[test]
+    fn test_timelocked_action_executed_by_crank() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdateCrankConfig {
+                crank_reward: Uint128::zero(),
+                timelock_delay_blocks: Some(100),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ExecuteMsg::QueueAction {
+                action: TimelockedAction::UpdatePrice { price_usd: Uint128::from(50000u128) },
+            },
+        )
+        .unwrap();
+
+        let keeper = MessageInfo {
+            sender: api.addr_make("keeper"),
+            funds: vec![],
+        };
+        // Not due yet
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "0"));
+
+        env.block.height += 100;
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "1"));
+
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.price_usd, Uint128::from(50000u128));
+    }
+
+    #[test]
+    fn test_status_reports_timelock_queue() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdateCrankConfig {
+                crank_reward: Uint128::zero(),
+                timelock_delay_blocks: Some(100),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ExecuteMsg::QueueAction {
+                action: TimelockedAction::UpdatePrice { price_usd: Uint128::from(50000u128) },
+            },
+        )
+        .unwrap();
+
+        let status: StatusResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Status {}).unwrap()).unwrap();
+        assert!(!status.is_paused);
+        assert_eq!(status.price_usd, Uint128::from(25000u128));
+        assert_eq!(status.current_price_usd, Uint128::from(25000u128));
+        assert_eq!(status.price_source, "default");
+        assert_eq!(status.active_round, None);
+        assert_eq!(status.remaining_raise_usd, None);
+        assert_eq!(status.current_epoch, None);
+        assert_eq!(status.timelock_queue_length, 1);
+        assert_eq!(status.next_timelock_eta, Some(env.block.height + 100));
+        assert_eq!(status.last_purchase_height, None);
+
+        // Inventory is reported net of reserves, alongside the round and raise cap in force
+        let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(1_000_000u128, config.native_denom.clone())]);
+        RESERVES.save(deps.as_mut().storage, RESERVE_LOTTERY, &Uint128::from(300_000u128)).unwrap();
+        config.max_raise_usd = Some(Uint128::from(10_000_000u128));
+        config.total_raised_usd = Uint128::from(4_000_000u128);
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        let round = SaleRound {
+            name: "seed".to_string(),
+            start_height: env.block.height,
+            end_height: env.block.height + 10,
+            price_usd: Uint128::from(20000u128),
+            treasury: None,
+            raised_usd: Uint128::zero(),
+            tokens_sold: Uint128::zero(),
+            finished: false,
+        };
+        ROUNDS.save(deps.as_mut().storage, 3, &round).unwrap();
+
+        let status: StatusResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Status {}).unwrap()).unwrap();
+        assert_eq!(status.remaining_inventory, Coin::new(700_000u128, config.native_denom.clone()));
+        assert_eq!(status.reserved_inventory, Uint128::from(300_000u128));
+        assert_eq!(status.active_round, Some(3));
+        assert_eq!(status.current_price_usd, Uint128::from(20000u128));
+        assert_eq!(status.price_source, "round");
+        assert_eq!(status.price_usd, Uint128::from(25000u128));
+        assert_eq!(status.max_raise_usd, Some(Uint128::from(10_000_000u128)));
+        assert_eq!(status.remaining_raise_usd, Some(Uint128::from(6_000_000u128)));
+    }
+
+    #[test]
+    fn test_timelock_guards_direct_updates_and_its_own_delay() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let crank_config = |timelock_delay_blocks: Option<u64>| ExecuteMsg::UpdateCrankConfig {
+            crank_reward: Uint128::zero(),
+            timelock_delay_blocks,
+        };
+        let queue_delay = |blocks: u64| ExecuteMsg::QueueAction { action: TimelockedAction::UpdateTimelockDelay { blocks } };
+
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), crank_config(Some(0))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTimelockDelay { blocks: 0, min: MIN_TIMELOCK_DELAY_BLOCKS }));
+        execute(deps.as_mut(), env.clone(), admin.clone(), crank_config(Some(100))).unwrap();
+
+        // Guarded actions can no longer skip the queue
+        let direct = [
+            ExecuteMsg::UpdatePrice { price_usd: Uint128::from(50000u128) },
+            ExecuteMsg::UpdateBuyer { buyer: api.addr_make("other").to_string() },
+            ExecuteMsg::WithdrawNativeTokens { amount: Uint128::one(), recipient: admin.sender.to_string(), denom: None },
+        ];
+        for msg in direct {
+            let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+            assert!(matches!(err, ContractError::TimelockRequired { .. }));
+        }
+
+        // Nor can the delay: the reward still updates directly, the delay only through the queue
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), crank_config(Some(100))).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockRequired { .. }));
+        execute(deps.as_mut(), env.clone(), admin.clone(), crank_config(None)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), queue_delay(99)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTimelockDelay { blocks: 99, .. }));
+        execute(deps.as_mut(), env.clone(), admin, queue_delay(150)).unwrap();
+
+        env.block.height += 99;
+        execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().timelock_delay_blocks, 100);
+        env.block.height += 1;
+        execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().timelock_delay_blocks, 150);
+    }
+
+    #[test]
+    fn test_per_token_price() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let usdc = api.addr_make("wusdc").to_string();
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdateTokenPrice { cw20_contract: usdc.clone(), price: Some(Uint128::from(50000u128)) },
+        )
+        .unwrap();
+
+        let calc: TokenCalculationResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::CalculateTokensForToken { cw20_contract: usdc.clone(), amount: Uint128::from(1_000_000u128) },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(calc.price_usd, Uint128::from(50000u128));
+        assert_eq!(calc.tokens, Uint128::from(20_000_000_000u128));
+
+        // Clearing the override falls back to the default price
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ExecuteMsg::UpdateTokenPrice { cw20_contract: usdc.clone(), price: None },
+        )
+        .unwrap();
+        let prices: TokenPricesResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::TokenPrices {}).unwrap()).unwrap();
+        assert!(prices.prices.is_empty());
+        assert_eq!(prices.default_price_usd, Uint128::from(25000u128));
+    }
+
+    #[test]
+    fn test_commit_reveal_locks_price() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        let denom = CONFIG.load(&deps.storage).unwrap().native_denom;
+        let deposit = Coin::new(PURCHASE_COMMITMENT_DEPOSIT, &denom);
+        let buyer = MessageInfo {
+            sender: api.addr_make("buyer"),
+            funds: vec![deposit.clone()],
+        };
+        let usdt = api.addr_make("wusdt").to_string();
+        let amount = Uint128::from(1_000_000u128);
+        let salt = Binary::from(b"salt".to_vec());
+        let commitment = Binary::from(purchase_commitment_hash(amount, salt.as_slice()));
+        let commit = |commitment: &Binary| ExecuteMsg::CommitPurchase { commitment: commitment.clone(), cw20_contract: usdt.clone() };
+
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), commit(&commitment)).unwrap_err();
+        assert!(matches!(err, ContractError::CommitRevealDisabled {}));
+
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::UpdateCommitRevealWindow { blocks: 5 }).unwrap();
+        let unfunded = MessageInfo { sender: buyer.sender.clone(), funds: vec![] };
+        let err = execute(deps.as_mut(), env.clone(), unfunded, commit(&commitment)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCommitmentDeposit { .. }));
+        execute(deps.as_mut(), env.clone(), buyer.clone(), commit(&commitment)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), commit(&commitment)).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentExists {}));
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_COMMITMENTS).unwrap(), Uint128::new(PURCHASE_COMMITMENT_DEPOSIT));
+
+        // Price change after the commit does not affect the revealed purchase
+        execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::UpdatePrice { price_usd: Uint128::from(50000u128) }).unwrap();
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        let buyer_addr = buyer.sender.to_string();
+
+        let err = reveal_commitment(deps.as_mut(), &env, &config, &buyer_addr, &usdt, amount, &salt).unwrap_err();
+        assert!(matches!(err, ContractError::RevealTooEarly { .. }));
+
+        env.block.height += 1;
+        let revealed = reveal_commitment(deps.as_mut(), &env, &config, &buyer_addr, &usdt, amount, &salt).unwrap();
+        assert_eq!(revealed.price, Uint128::from(25000u128));
+        assert_eq!(revealed.deposit, Uint128::new(PURCHASE_COMMITMENT_DEPOSIT));
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_COMMITMENTS).unwrap(), Uint128::zero());
+
+        // Commitments are single use
+        let err = reveal_commitment(deps.as_mut(), &env, &config, &buyer_addr, &usdt, amount, &salt).unwrap_err();
+        assert!(matches!(err, ContractError::CommitmentNotFound {}));
+
+        // One never revealed is expired by the crank, and its deposit forfeited to the admin
+        let unrevealed = Binary::from(purchase_commitment_hash(amount, b"other"));
+        execute(deps.as_mut(), env.clone(), buyer.clone(), commit(&unrevealed)).unwrap();
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        env.block.height += 5;
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(!res.attributes.iter().any(|a| a.key == "expired_commitment"));
+        env.block.height += 1;
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "expired_commitment" && a.value == buyer_addr));
+        let forfeit = CosmosMsg::Bank(BankMsg::Send { to_address: config.admin.clone(), amount: vec![deposit] });
+        assert!(res.messages.iter().any(|m| m.msg == forfeit));
+        assert!(!PURCHASE_COMMITMENTS.has(&deps.storage, (&buyer_addr, unrevealed.as_slice())));
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_COMMITMENTS).unwrap(), Uint128::zero());
+    }
+
+    #[test]
+    fn test_instantiate_fails_on_module_version_mismatch() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let mut msg = mock_instantiate_msg(&api);
+        msg.expected_module_version = Some(3);
+        // The mock querier does not answer gRPC, so the live version is unknown
+        let err = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::ModuleVersionMismatch { expected: 3, actual: None, .. }
+        ));
+    }
+
+    #[test]
+    fn test_receipt_nft_mint_msg() {
+        let mut deps = mock_dependencies();
+        let metadata = ReceiptMetadata {
+            price_usd: Uint128::from(25000u128),
+            usd_amount: Uint128::from(1_000_000u128),
+            tokens_purchased: Uint128::from(40_000_000_000u128),
+            payout_denom: "ngonka".to_string(),
+            purchase_height: 12345,
+        };
+        let (msg, token_id) =
+            create_receipt_mint_msg(deps.as_mut().storage, "cw721", "buyer", metadata.clone()).unwrap();
+        assert_eq!(token_id, "1");
+        match msg {
+            WasmMsg::Execute { contract_addr, msg, .. } => {
+                assert_eq!(contract_addr, "cw721");
+                let ReceiptNftExecuteMsg::Mint { token_id, owner, extension, .. } = from_json(&msg).unwrap();
+                assert_eq!(token_id, "1");
+                assert_eq!(owner, "buyer");
+                assert_eq!(extension, metadata);
+            }
+            _ => panic!("expected wasm execute"),
+        }
+
+        let (_, token_id) = create_receipt_mint_msg(deps.as_mut().storage, "cw721", "buyer", metadata).unwrap();
+        assert_eq!(token_id, "2");
+    }
+
+    #[test]
+    fn test_attestation_gate_fails_closed() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetAttestationRequired { required: true }).unwrap();
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert!(config.require_attestation);
+
+        // The mock querier cannot answer the attestation query, so the buyer is rejected
+        let buyer = api.addr_make("buyer").to_string();
+        assert!(ensure_attested(deps.as_ref(), &buyer).is_err());
+    }
+
+    #[test]
+    fn test_sudo_sets_validator_kickback() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let fee_collector = api.addr_make("fee_collector").to_string();
+        let err = sudo(
+            deps.as_mut(),
+            env.clone(),
+            SudoMsg::SetValidatorKickback {
+                kickback: Some(ValidatorKickback { bps: 10_001, recipient: fee_collector.clone() }),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidKickbackBps { .. }));
+
+        let kickback = ValidatorKickback { bps: 250, recipient: fee_collector };
+        sudo(deps.as_mut(), env.clone(), SudoMsg::SetValidatorKickback { kickback: Some(kickback.clone()) }).unwrap();
+        let config: ConfigResponse = from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.validator_kickback, Some(kickback));
+    }
+
+    #[test]
+    fn test_sudo_sets_price_floor() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let floor = Uint128::from(20000u128);
+        sudo(deps.as_mut(), env.clone(), SudoMsg::SetPriceFloor { floor_price_usd: Some(floor) }).unwrap();
+        let price: CurrentPriceResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::CurrentPrice { cw20_contract: None }).unwrap()).unwrap();
+        assert_eq!(price.price_usd, Uint128::from(25000u128));
+        assert_eq!(price.floor_price_usd, Some(floor));
+    }
+
+    #[test]
+    fn test_current_price_reports_the_effective_price() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let wusdt = api.addr_make("wusdt").to_string();
+        TOKEN_PRICES.save(deps.as_mut().storage, &wusdt, &Uint128::from(30000u128)).unwrap();
+        let current_price = |deps: Deps, cw20_contract: Option<String>| -> CurrentPriceResponse {
+            from_json(query(deps, env.clone(), QueryMsg::CurrentPrice { cw20_contract }).unwrap()).unwrap()
+        };
+
+        let price = current_price(deps.as_ref(), None);
+        assert_eq!((price.price_usd, price.price_source.as_str()), (Uint128::from(25000u128), "default"));
+        let price = current_price(deps.as_ref(), Some(wusdt.clone()));
+        assert_eq!((price.price_usd, price.price_source.as_str()), (Uint128::from(30000u128), "token"));
+
+        // Without a token given, the trusted payment token's price is the one purchases pay
+        let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.clone());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        let price = current_price(deps.as_ref(), None);
+        assert_eq!((price.price_usd, price.price_source.as_str()), (Uint128::from(30000u128), "token"));
+
+        // An active round overrides the per-token price
+        let round = SaleRound {
+            name: "seed".to_string(),
+            start_height: env.block.height,
+            end_height: env.block.height + 10,
+            price_usd: Uint128::from(20000u128),
+            treasury: None,
+            raised_usd: Uint128::zero(),
+            tokens_sold: Uint128::zero(),
+            finished: false,
+        };
+        ROUNDS.save(deps.as_mut().storage, 0, &round).unwrap();
+        let price = current_price(deps.as_ref(), Some(wusdt));
+        assert_eq!((price.price_usd, price.price_source.as_str()), (Uint128::from(20000u128), "round"));
+    }
+
+    #[test]
+    fn test_lottery_start_and_finalize_window() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let commit_end_height = env.block.height + 10;
+        let start = ExecuteMsg::StartLottery {
+            commit_end_height,
+            capacity_usd: Uint128::from(1_000_000u128),
+        };
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), admin.clone(), start.clone()).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), admin, start).unwrap_err();
+        assert!(matches!(err, ContractError::LotteryActive {}));
+
+        let res: LotteryResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Lottery {}).unwrap()).unwrap();
+        let lottery = res.lottery.unwrap();
+        assert_eq!(lottery.price_usd, Uint128::from(25000u128));
+        assert_eq!(lottery.seed, None);
+
+        let seed = Binary::from(b"seed".to_vec());
+        let err = sudo(deps.as_mut(), env.clone(), SudoMsg::FinalizeLottery { seed: seed.clone() }).unwrap_err();
+        assert!(matches!(err, ContractError::LotteryStillOpen { .. }));
+        env.block.height = commit_end_height + 1;
+        sudo(deps.as_mut(), env.clone(), SudoMsg::FinalizeLottery { seed: seed.clone() }).unwrap();
+        let res: LotteryResponse = from_json(query(deps.as_ref(), env, QueryMsg::Lottery {}).unwrap()).unwrap();
+        assert_eq!(res.lottery.unwrap().seed, Some(seed));
+    }
+
+    #[test]
+    fn test_lottery_commit_claim_and_sweep() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let buyer = api.addr_make("buyer").to_string();
+        let late = api.addr_make("late").to_string();
+        let entry = WhitelistEntry { allocation_usd: Uint128::new(10_000_000), purchased_usd: Uint128::zero() };
+        WHITELIST.save(deps.as_mut().storage, &late, &entry).unwrap();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        config.max_raise_usd = Some(Uint128::new(3_500_000));
+        config.total_raised_usd = Uint128::new(1_000_000);
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(200_000_000_000u128, config.native_denom.clone())]);
+        let commit = |sender: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(2_000_000),
+                msg: to_json_binary(&PurchaseTokenMsg {
+                    v: None,
+                    payout_denom: None,
+                    salt: None,
+                    exact_out: None,
+                    intent: None,
+                    sale_id: None,
+                    reservation_id: None,
+                    quote_id: None,
+                })
+                .unwrap(),
+            })
+        };
+        let won_usd = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, buyer: &str| {
+            let res: LotteryEntryResponse = from_json(
+                query(deps.as_ref(), mock_env(), QueryMsg::LotteryEntry { buyer: buyer.to_string() }).unwrap(),
+            )
+            .unwrap();
+            res.entry.map(|entry| entry.won_usd)
+        };
+
+        // Payments in the window are commitments, not purchases
+        let commit_end_height = env.block.height + 10;
+        let start = ExecuteMsg::StartLottery { commit_end_height, capacity_usd: Uint128::new(3_000_000) };
+        execute(deps.as_mut(), env.clone(), admin, start).unwrap();
+        for sender in [&buyer, &late] {
+            let res = execute(deps.as_mut(), env.clone(), wusdt.clone(), commit(sender)).unwrap();
+            assert!(res.messages.is_empty());
+            assert!(res.attributes.iter().any(|a| a.key == "method" && a.value == "lottery_commit"));
+        }
+
+        // The mock cannot serve the chain's seed, so the draw is revealed through governance
+        env.block.height = commit_end_height + 1;
+        let err = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::FinalizeLottery {}).unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+        sudo(deps.as_mut(), env.clone(), SudoMsg::FinalizeLottery { seed: Binary::from(b"seed".to_vec()) }).unwrap();
+        let deadline = LOTTERY.load(&deps.storage).unwrap().claim_deadline_height.unwrap();
+        assert_eq!(deadline, env.block.height + LOTTERY_CLAIM_BLOCKS);
+
+        // The raise cap has room for $2.50 of the $3 capacity, across two $2 entrants: one is
+        // filled in full, the other for the remaining $0.50, and the wins count as raised
+        let buyer_won = won_usd(&deps, &buyer).unwrap();
+        let late_won = won_usd(&deps, &late).unwrap();
+        assert_eq!(buyer_won + late_won, Uint128::new(2_500_000));
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().total_raised_usd, Uint128::new(3_500_000));
+        let tokens = |usd: Uint128| calculate_tokens_for_usd(usd, Uint128::new(25_000), DEFAULT_PAYOUT_DECIMALS);
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_LOTTERY).unwrap(), tokens(Uint128::new(2_500_000)));
+
+        let claimer = MessageInfo { sender: Addr::unchecked(buyer.clone()), funds: vec![] };
+        let res = execute(deps.as_mut(), env.clone(), claimer.clone(), ExecuteMsg::ClaimLottery {}).unwrap();
+        let payout = CosmosMsg::Bank(BankMsg::Send {
+            to_address: buyer.clone(),
+            amount: vec![Coin::new(tokens(buyer_won).u128(), config.native_denom.clone())],
+        });
+        assert!(res.messages.iter().any(|m| m.msg == payout));
+        assert!(res.attributes.iter().any(|a| a.key == "refund_usd" && a.value == (Uint128::new(2_000_000) - buyer_won).to_string()));
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_LOTTERY).unwrap(), tokens(late_won));
+        let err = execute(deps.as_mut(), env.clone(), claimer, ExecuteMsg::ClaimLottery {}).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+
+        // Once the deadline passes the crank refunds the unclaimed entry and releases its tokens
+        env.block.height = deadline;
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(!res.attributes.iter().any(|a| a.key == "swept_lottery_entry"));
+        env.block.height = deadline + 1;
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        let refund = CosmosMsg::Wasm(create_cw20_transfer_msg(wusdt.sender.to_string(), late.clone(), Uint128::new(2_000_000)).unwrap());
+        assert_eq!(res.messages[0].msg, refund);
+        assert!(res.attributes.iter().any(|a| a.key == "swept_lottery_entry" && a.value == late));
+        assert_eq!(won_usd(&deps, &late), None);
+        assert_eq!(LOTTERY.may_load(&deps.storage).unwrap(), None);
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_LOTTERY).unwrap(), Uint128::zero());
+        // The swept win was never collected, so the cap gets its room back
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().total_raised_usd, Uint128::new(3_500_000) - late_won);
+    }
+
+    #[test]
+    fn test_migrate_from_v1_config() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let admin = api.addr_make("admin");
+        let v1 = ConfigV1 {
+            admin: admin.to_string(),
+            buyer: api.addr_make("buyer").to_string(),
+            accepted_chain_id: "ethereum".to_string(),
+            accepted_eth_contract: "0xdac17f958d2ee523a2206206994597c13d831ec7".to_string(),
+            price_usd: Uint128::from(25000u128),
+            native_denom: "ngonka".to_string(),
+            is_paused: false,
+            total_tokens_sold: Uint128::from(40_000_000_000u128),
+        };
+        deps.storage.set(CONFIG.as_slice(), &to_json_vec(&v1).unwrap());
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+
+        migrate(deps.as_mut(), env.clone(), Binary::default()).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert!(config.is_paused);
+        assert_eq!(config.total_tokens_sold, Uint128::from(40_000_000_000u128));
+        // 40 GNK sold at $0.025 count toward the raise cap
+        assert_eq!(config.total_raised_usd, Uint128::new(1_000_000));
+
+        let admin_info = MessageInfo { sender: admin, funds: vec![] };
+        let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), ExecuteMsg::Resume {}).unwrap_err();
+        assert!(matches!(err, ContractError::MigrationInProgress {}));
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), ExecuteMsg::ContinueMigration {}).unwrap();
+        execute(deps.as_mut(), env.clone(), admin_info.clone(), ExecuteMsg::ContinueMigration {}).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), admin_info, ExecuteMsg::ContinueMigration {}).unwrap_err();
+        assert!(matches!(err, ContractError::NoMigrationPending {}));
+
+        assert!(!CONFIG.load(&deps.storage).unwrap().is_paused);
+        assert_eq!(TOKENS_SOLD_BY_DENOM.load(&deps.storage, "ngonka").unwrap(), Uint128::from(40_000_000_000u128));
+        let status: MigrationStatusResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::MigrationStatus {}).unwrap()).unwrap();
+        assert_eq!(status.migration.unwrap().stage, MigrationStage::Complete);
+    }
+
+    #[test]
+    fn test_purchase_msg_versions() {
+        let parse = |json: &str| parse_purchase_msg(&Binary::from(json.as_bytes()));
+
+        // Integrators that predate versioning send an unversioned payload
+        assert_eq!(parse("{}").unwrap().v, None);
+        let msg = parse(r#"{"payout_denom":"uatom"}"#).unwrap();
+        assert_eq!(msg.payout_denom.as_deref(), Some("uatom"));
+
+        let msg = parse(r#"{"v":2,"sale_id":3}"#).unwrap();
+        assert_eq!((msg.v, msg.sale_id), (Some(2), Some(3)));
+
+        // A newer payload is rejected on its version, not on its unknown fields
+        let err = parse(r#"{"v":3,"referrer":"gonka1xyz"}"#).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPayloadVersion { version: 3, max: 2 }));
+        let err = parse(r#"{"v":0}"#).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPayloadVersion { version: 0, .. }));
+    }
+
+    #[test]
+    fn test_purchase_intent_rejects_expired_and_reused_nonce() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let intent = SignedPurchaseIntent {
+            buyer: api.addr_make("buyer").to_string(),
+            pubkey: Binary::from(vec![2u8; 33]),
+            amount: Uint128::from(1_000_000u128),
+            nonce: 0,
+            deadline_height: env.block.height,
+            signature: Binary::from(vec![0u8; 64]),
+            tokens_out: None,
+        };
+        let receive = |intent: SignedPurchaseIntent| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: api.addr_make("relayer").to_string(),
+                amount: Uint128::from(1_000_000u128),
+                msg: to_json_binary(&PurchaseTokenMsg {
+                    v: None,
+                    payout_denom: None,
+                    salt: None,
+                    exact_out: None,
+                    intent: Some(intent),
+                    sale_id: None,
+                    reservation_id: None,
+                    quote_id: None,
+                })
+                .unwrap(),
+            })
+        };
+        let cw20 = MessageInfo {
+            sender: api.addr_make("wusdt"),
+            funds: vec![],
+        };
+
+        PURCHASE_NONCES.save(deps.as_mut().storage, &intent.buyer, &1).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), cw20.clone(), receive(intent.clone())).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIntent { .. }));
+
+        env.block.height += 1;
+        let err = execute(deps.as_mut(), env.clone(), cw20, receive(intent)).unwrap_err();
+        assert!(matches!(err, ContractError::IntentExpired { .. }));
+
+        let res: PurchaseNonceResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::PurchaseNonce { buyer: api.addr_make("buyer").to_string() }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.next_nonce, 1);
+    }
+
+    #[test]
+    fn test_signed_purchase_intent_binds_the_payload() {
+        use cosmwasm_std::{Api, CanonicalAddr};
+        use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+        let key_hash = Ripemd160::digest(Sha256::digest(&pubkey));
+        let buyer = deps.api.addr_humanize(&CanonicalAddr::from(key_hash.as_slice())).unwrap().to_string();
+        let entry = WhitelistEntry { allocation_usd: Uint128::new(10_000_000), purchased_usd: Uint128::zero() };
+        WHITELIST.save(deps.as_mut().storage, &buyer, &entry).unwrap();
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let relayer = api.addr_make("relayer").to_string();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, config.native_denom.clone())]);
+
+        let payload = |exact_out: Option<PurchaseExactOut>| PurchaseTokenMsg {
+            v: None,
+            payout_denom: None,
+            salt: None,
+            exact_out,
+            intent: None,
+            sale_id: None,
+            reservation_id: None,
+            quote_id: None,
+        };
+        let sign = |payload: &PurchaseTokenMsg| {
+            let hash = purchase_intent_hash(&PurchaseIntentSignDoc {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                cw20_contract: wusdt.sender.to_string(),
+                buyer: buyer.clone(),
+                amount: Uint128::new(1_000_000),
+                nonce: 0,
+                deadline_height: env.block.height,
+                tokens_out: None,
+                payout_denom: payload.payout_denom.clone(),
+                exact_out: payload.exact_out.clone(),
+                salt: payload.salt.clone(),
+                sale_id: payload.sale_id,
+                reservation_id: payload.reservation_id,
+                quote_id: payload.quote_id,
+            })
+            .unwrap();
+            let signature: Signature = key.sign_prehash(&hash).unwrap();
+            SignedPurchaseIntent {
+                buyer: buyer.clone(),
+                pubkey: Binary::from(pubkey.clone()),
+                amount: Uint128::new(1_000_000),
+                nonce: 0,
+                deadline_height: env.block.height,
+                signature: Binary::from(signature.to_bytes().to_vec()),
+                tokens_out: None,
+            }
+        };
+        let relay = |mut payload: PurchaseTokenMsg, intent: SignedPurchaseIntent| {
+            payload.intent = Some(intent);
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: relayer.clone(),
+                amount: Uint128::new(1_000_000),
+                msg: to_json_binary(&payload).unwrap(),
+            })
+        };
+        // Half the payment buys 20 GNK; the other half is refunded to the relayer
+        let half = Some(PurchaseExactOut { tokens_out: Uint128::new(20_000_000_000), max_usd_in: Uint128::new(1_000_000) });
+
+        // A relayer cannot turn a spend-all purchase into an exact-out one that refunds to itself
+        let intent = sign(&payload(None));
+        let err = execute(deps.as_mut(), env.clone(), wusdt.clone(), relay(payload(half.clone()), intent)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIntent { .. }));
+
+        // The payload the buyer signed goes through, credited to the buyer
+        let intent = sign(&payload(half.clone()));
+        let res = execute(deps.as_mut(), env.clone(), wusdt.clone(), relay(payload(half), intent)).unwrap();
+        let payout = CosmosMsg::Bank(BankMsg::Send {
+            to_address: buyer.clone(),
+            amount: vec![Coin::new(20_000_000_000u128, config.native_denom.clone())],
+        });
+        assert!(res.messages.iter().any(|m| m.msg == payout));
+        assert!(res.attributes.iter().any(|a| a.key == "buyer" && a.value == buyer));
+        let res: PurchaseNonceResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::PurchaseNonce { buyer }).unwrap()).unwrap();
+        assert_eq!(res.next_nonce, 1);
+    }
+
+    #[test]
+    fn test_signed_intent_quoted_in_tokens_buys_exactly_that_amount() {
+        use cosmwasm_std::{Api, CanonicalAddr};
+        use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let key = SigningKey::from_slice(&[9u8; 32]).unwrap();
+        let pubkey = key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+        let key_hash = Ripemd160::digest(Sha256::digest(&pubkey));
+        let buyer = deps.api.addr_humanize(&CanonicalAddr::from(key_hash.as_slice())).unwrap().to_string();
+        let entry = WhitelistEntry { allocation_usd: Uint128::new(10_000_000), purchased_usd: Uint128::zero() };
+        WHITELIST.save(deps.as_mut().storage, &buyer, &entry).unwrap();
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let relayer = api.addr_make("relayer").to_string();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, config.native_denom.clone())]);
+
+        let sign = |nonce: u64, tokens_out: Uint128| {
+            let hash = purchase_intent_hash(&PurchaseIntentSignDoc {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                cw20_contract: wusdt.sender.to_string(),
+                buyer: buyer.clone(),
+                amount: Uint128::new(1_000_000),
+                nonce,
+                deadline_height: env.block.height,
+                tokens_out: Some(tokens_out),
+                payout_denom: None,
+                exact_out: None,
+                salt: None,
+                sale_id: None,
+                reservation_id: None,
+                quote_id: None,
+            })
+            .unwrap();
+            let signature: Signature = key.sign_prehash(&hash).unwrap();
+            SignedPurchaseIntent {
+                buyer: buyer.clone(),
+                pubkey: Binary::from(pubkey.clone()),
+                amount: Uint128::new(1_000_000),
+                nonce,
+                deadline_height: env.block.height,
+                signature: Binary::from(signature.to_bytes().to_vec()),
+                tokens_out: Some(tokens_out),
+            }
+        };
+        let relay = |intent: SignedPurchaseIntent| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: relayer.clone(),
+                amount: Uint128::new(1_000_000),
+                msg: to_json_binary(&PurchaseTokenMsg {
+                    v: None,
+                    payout_denom: None,
+                    salt: None,
+                    exact_out: None,
+                    intent: Some(intent),
+                    sale_id: None,
+                    reservation_id: None,
+                    quote_id: None,
+                })
+                .unwrap(),
+            })
+        };
+
+        // 10 GNK cost $0.25 of the signed $1; the rest goes back to the relayer that paid
+        let res = execute(deps.as_mut(), env.clone(), wusdt.clone(), relay(sign(0, Uint128::new(10_000_000_000)))).unwrap();
+        let payout = CosmosMsg::Bank(BankMsg::Send {
+            to_address: buyer.clone(),
+            amount: vec![Coin::new(10_000_000_000u128, config.native_denom.clone())],
+        });
+        let refund = CosmosMsg::Wasm(create_cw20_transfer_msg(wusdt.sender.to_string(), relayer.clone(), Uint128::new(750_000)).unwrap());
+        assert!(res.messages.iter().any(|m| m.msg == payout));
+        assert!(res.messages.iter().any(|m| m.msg == refund));
+        assert!(res.attributes.iter().any(|a| a.key == "usdt_amount" && a.value == "250000"));
+
+        // Tokens the signed payment cannot cover are refused rather than partially filled
+        let err = execute(deps.as_mut(), env.clone(), wusdt.clone(), relay(sign(1, Uint128::new(50_000_000_000)))).unwrap_err();
+        assert!(matches!(err, ContractError::MaxInputExceeded { required: 1_250_000, max_usd_in: 1_000_000 }));
+    }
+
+    #[test]
+    fn test_buyer_price_agreement() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let partner = api.addr_make("partner").to_string();
+        let update = ExecuteMsg::UpdateBuyerPrice { buyer: partner.clone(), price: Some(Uint128::from(20000u128)) };
+        let outsider = MessageInfo {
+            sender: api.addr_make("outsider"),
+            funds: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), outsider, update.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), admin, update).unwrap();
+        let prices: BuyerPricesResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::BuyerPrices { start_after: None, limit: None }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(prices.prices.len(), 1);
+        assert_eq!(prices.prices[0].buyer, partner);
+        assert_eq!(prices.prices[0].price, Uint128::from(20000u128));
+    }
+
+    #[test]
+    fn test_list_queries_return_next_start_after() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        for id in [1u64, 2] {
+            let sale = Sale {
+                name: format!("sale {}", id),
+                price_usd: Uint128::from(25000u128),
+                inventory: Uint128::from(1_000u128),
+                sold: Uint128::zero(),
+                raised_usd: Uint128::zero(),
+                closed: false,
+                created_at_height: env.block.height,
+            };
+            SALES.save(deps.as_mut().storage, id, &sale).unwrap();
+            let queued = QueuedAction {
+                id,
+                action: TimelockedAction::UpdatePrice { price_usd: Uint128::from(30000u128) },
+                eta_height: env.block.height + 100,
+                queued_at_height: env.block.height,
+            };
+            TIMELOCK_QUEUE.save(deps.as_mut().storage, (queued.eta_height, id), &queued).unwrap();
+            BUYER_PRICES.save(deps.as_mut().storage, &format!("buyer{}", id), &Uint128::from(20000u128)).unwrap();
+            DEPOSITS.save(deps.as_mut().storage, (&format!("depositor{}", id), "ngonka"), &Uint128::from(1u128)).unwrap();
+        }
+        let page = |msg: QueryMsg| query(deps.as_ref(), env.clone(), msg).unwrap();
+
+        let sales: SalesResponse = from_json(page(QueryMsg::Sales { start_after: None, limit: Some(1) })).unwrap();
+        assert_eq!(sales.next_start_after, Some(1));
+        let sales: SalesResponse = from_json(page(QueryMsg::Sales { start_after: Some(1), limit: Some(1) })).unwrap();
+        assert_eq!((sales.sales[0].id, sales.next_start_after), (2, Some(2)));
+        let sales: SalesResponse = from_json(page(QueryMsg::Sales { start_after: Some(2), limit: Some(1) })).unwrap();
+        assert!(sales.sales.is_empty() && sales.next_start_after.is_none());
+
+        let prices: BuyerPricesResponse = from_json(page(QueryMsg::BuyerPrices { start_after: None, limit: Some(1) })).unwrap();
+        assert_eq!(prices.next_start_after.as_deref(), Some("buyer1"));
+        let prices: BuyerPricesResponse = from_json(page(QueryMsg::BuyerPrices { start_after: None, limit: None })).unwrap();
+        assert!(prices.next_start_after.is_none());
+
+        let eta_height = env.block.height + 100;
+        let actions: QueuedActionsResponse = from_json(page(QueryMsg::QueuedActions { start_after: None, limit: Some(1) })).unwrap();
+        assert_eq!(actions.next_start_after, Some((eta_height, 1)));
+        let actions: QueuedActionsResponse =
+            from_json(page(QueryMsg::QueuedActions { start_after: actions.next_start_after, limit: Some(5) })).unwrap();
+        assert_eq!(actions.actions.len(), 1);
+        assert!(actions.next_start_after.is_none());
+
+        let deposits: DepositsResponse = from_json(page(QueryMsg::Deposits { start_after: None, limit: Some(1) })).unwrap();
+        assert_eq!(deposits.next_start_after, Some(("depositor1".to_string(), "ngonka".to_string())));
+    }
+
+    #[test]
+    fn test_reservation_partial_funding_refunds_on_cancel() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let msg = mock_instantiate_msg(&api);
+        let buyer = msg.buyer.clone();
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let buyer_info = MessageInfo {
+            sender: Addr::unchecked(buyer.clone()),
+            funds: vec![],
+        };
+        let too_late = ExecuteMsg::ReservePurchase {
+            usd_amount: Uint128::from(1_000u128),
+            deadline_height: env.block.height + MAX_RESERVATION_BLOCKS + 1,
+        };
+        let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), too_late).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidReservation { .. }));
+
+        let reserve = ExecuteMsg::ReservePurchase {
+            usd_amount: Uint128::from(1_000u128),
+            deadline_height: env.block.height + 10,
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info, reserve).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        fund_reservation(deps.as_mut(), &env, config.clone(), 1, buyer.clone(), "wusdt".to_string(), Uint128::from(600u128))
+            .unwrap();
+        let err = fund_reservation(deps.as_mut(), &env, config, 1, "stranger".to_string(), "wusdc".to_string(), Uint128::from(400u128))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+
+        // Anyone can cancel after the deadline; the partial funding goes back to the buyer
+        env.block.height += 11;
+        let keeper = MessageInfo {
+            sender: api.addr_make("keeper"),
+            funds: vec![],
+        };
+        let res = execute(deps.as_mut(), env, keeper, ExecuteMsg::CancelReservation { id: 1 }).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert!(RESERVATIONS.may_load(&deps.storage, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_crank_expires_reservations_finishes_rounds_and_releases_vesting() {
+        use cosmwasm_std::{Checksum, CodeInfoResponse, SubMsgResult};
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let msg = mock_instantiate_msg(&api);
+        let buyer = msg.buyer.clone();
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let wusdt = api.addr_make("wusdt").to_string();
+
+        let buyer_info = MessageInfo { sender: Addr::unchecked(buyer.clone()), funds: vec![] };
+        let reserve = ExecuteMsg::ReservePurchase {
+            usd_amount: Uint128::new(1_000_000),
+            deadline_height: env.block.height + 10,
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info, reserve).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        fund_reservation(deps.as_mut(), &env, config.clone(), 1, buyer.clone(), wusdt.clone(), Uint128::new(400_000)).unwrap();
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_RESERVATIONS).unwrap(), Uint128::new(40_000_000_000));
+
+        let round = SaleRound {
+            name: "seed".to_string(),
+            start_height: env.block.height,
+            end_height: env.block.height + 5,
+            price_usd: Uint128::from(20000u128),
+            treasury: None,
+            raised_usd: Uint128::zero(),
+            tokens_sold: Uint128::zero(),
+            finished: false,
+        };
+        ROUNDS.save(deps.as_mut().storage, 0, &round).unwrap();
+
+        deps.querier.update_wasm(|query: &WasmQuery| {
+            let WasmQuery::CodeInfo { code_id } = query else { panic!("unexpected query") };
+            let info = CodeInfoResponse::new(*code_id, Addr::unchecked("creator"), Checksum::generate(b"vesting"));
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+        });
+        let vesting = VestingConfig { code_id: 7, cliff_seconds: 100, duration_seconds: 1_000 };
+        let payout = Coin::new(1_000u128, config.native_denom.clone());
+        let (_, vesting_contract) =
+            create_vesting_instantiate_msg(deps.as_mut(), &env, &config, &vesting, &buyer, payout).unwrap();
+        let start = env.block.time.seconds();
+        assert!(VESTING_UNLOCKS.has(&deps.storage, (start + 100, &vesting_contract)));
+        assert!(VESTING_UNLOCKS.has(&deps.storage, (start + 1_000, &vesting_contract)));
+
+        // Nothing is due yet
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "0"));
+
+        // Past the round's end, the reservation's deadline and the vesting cliff
+        env.block.height += 11;
+        env.block.time = env.block.time.plus_seconds(100);
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "3"));
+        assert!(res.attributes.iter().any(|a| a.key == "expired_reservation" && a.value == "1"));
+        let refund = CosmosMsg::Wasm(create_cw20_transfer_msg(wusdt, buyer, Uint128::new(400_000)).unwrap());
+        assert!(res.messages.iter().any(|m| m.msg == refund));
+        assert!(!RESERVATIONS.has(&deps.storage, 1));
+        assert_eq!(RESERVES.load(&deps.storage, RESERVE_RESERVATIONS).unwrap(), Uint128::zero());
+        assert_eq!(REFUNDED_USD.load(&deps.storage).unwrap(), Uint128::new(400_000));
+
+        assert!(res.events.iter().any(|e| e.ty == "round_finished"));
+        assert!(ROUNDS.load(&deps.storage, 0).unwrap().finished);
+
+        let release = WasmMsg::Execute {
+            contract_addr: vesting_contract.clone(),
+            msg: to_json_binary(&VestingExecuteMsg::Release {}).unwrap(),
+            funds: vec![],
+        };
+        let release = res.messages.iter().find(|m| m.msg == CosmosMsg::Wasm(release.clone())).unwrap();
+        assert_eq!(release.id, VESTING_RELEASE_REPLY_ID);
+        assert!(!VESTING_UNLOCKS.has(&deps.storage, (start + 100, &vesting_contract)));
+
+        // Each item is handled once; the vesting end is still to come
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "0"));
+        env.block.time = env.block.time.plus_seconds(900);
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "vesting_release" && a.value == vesting_contract));
+        assert!(VESTING_UNLOCKS.is_empty(&deps.storage));
+
+        // A release the vesting contract rejects is reported, not fatal
+        let failed = Reply {
+            id: VESTING_RELEASE_REPLY_ID,
+            payload: Binary::from(vesting_contract.as_bytes()),
+            gas_used: 0,
+            result: SubMsgResult::Err("nothing to release".to_string()),
+        };
+        let res = reply(deps.as_mut(), env, failed).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "vesting_contract" && a.value == vesting_contract));
+    }
+
+    #[test]
+    fn test_reservation_settles_each_payment_at_its_token_price() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let msg = mock_instantiate_msg(&api);
+        let buyer = msg.buyer.clone();
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let wusdc = api.addr_make("wusdc").to_string();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, config.native_denom.clone())]);
+        // W(USDC) buys at $0.05, twice the default $0.025
+        let price = ExecuteMsg::UpdateTokenPrice { cw20_contract: wusdc.clone(), price: Some(Uint128::from(50000u128)) };
+        execute(deps.as_mut(), env.clone(), admin, price).unwrap();
+
+        let buyer_info = MessageInfo { sender: Addr::unchecked(buyer.clone()), funds: vec![] };
+        let reserve = ExecuteMsg::ReservePurchase {
+            usd_amount: Uint128::new(1_000_000),
+            deadline_height: env.block.height + 10,
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info, reserve).unwrap();
+
+        // $1 of W(USDC) buys 20 GNK, worth $0.50 at the default price
+        let config = CONFIG.load(&deps.storage).unwrap();
+        fund_reservation(deps.as_mut(), &env, config.clone(), 1, buyer.clone(), wusdc.clone(), Uint128::new(1_000_000)).unwrap();
+        assert_eq!(RESERVATIONS.load(&deps.storage, 1).unwrap().funded_usd, Uint128::new(500_000));
+
+        // W(USDT) covers the other $0.50 and settles; the rest of the payment is refunded
+        let pay = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: buyer.clone(),
+            amount: Uint128::new(700_000),
+            msg: to_json_binary(&PurchaseTokenMsg {
+                v: None,
+                payout_denom: None,
+                salt: None,
+                exact_out: None,
+                intent: None,
+                sale_id: None,
+                reservation_id: Some(1),
+                quote_id: None,
+            })
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay).unwrap();
+        let payout = CosmosMsg::Bank(BankMsg::Send {
+            to_address: buyer.clone(),
+            amount: vec![Coin::new(40_000_000_000u128, config.native_denom.clone())],
+        });
+        let refund = CosmosMsg::Wasm(create_cw20_transfer_msg(wusdt.sender.to_string(), buyer, Uint128::new(200_000)).unwrap());
+        assert!(res.messages.iter().any(|m| m.msg == payout));
+        assert!(res.messages.iter().any(|m| m.msg == refund));
+        assert!(RESERVATIONS.may_load(&deps.storage, 1).unwrap().is_none());
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.total_tokens_sold, Uint128::from(40_000_000_000u128));
+        assert_eq!(config.total_raised_usd, Uint128::new(1_000_000));
+    }
+
+    #[test]
+    fn test_deposits_and_reserved_inventory() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let msg = mock_instantiate_msg(&api);
+        let buyer = msg.buyer.clone();
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let denom = CONFIG.load(&deps.storage).unwrap().native_denom;
+
+        let treasury = MessageInfo {
+            sender: api.addr_make("treasury"),
+            funds: vec![Coin::new(100_000_000u128, denom.clone())],
+        };
+        execute(deps.as_mut(), env.clone(), treasury, ExecuteMsg::Deposit {}).unwrap();
+        deps.querier.bank.update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000u128, denom.clone())]);
+
+        // 1000 micro-USD at $0.025 reserves 40_000_000 tokens (9 payout decimals)
+        let buyer_info = MessageInfo {
+            sender: Addr::unchecked(buyer),
+            funds: vec![],
+        };
+        let reserve = ExecuteMsg::ReservePurchase {
+            usd_amount: Uint128::from(1_000u128),
+            deadline_height: env.block.height + 10,
+        };
+        execute(deps.as_mut(), env.clone(), buyer_info, reserve).unwrap();
+
+        let deposits: DepositsResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::Deposits { start_after: None, limit: None }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(deposits.deposits.len(), 1);
+        assert_eq!(deposits.deposits[0].amount, Uint128::from(100_000_000u128));
+        assert_eq!(deposits.reserved, Uint128::from(40_000_000u128));
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        let withdraw = |amount: u128| ExecuteMsg::WithdrawNativeTokens {
+            amount: Uint128::from(amount),
+            recipient: api.addr_make("admin").to_string(),
+            denom: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), withdraw(60_000_001)).unwrap_err();
+        assert!(matches!(err, ContractError::WithdrawalExceedsUnreserved { available: 60_000_000, .. }));
+        execute(deps.as_mut(), env, admin, withdraw(60_000_000)).unwrap();
+    }
+
+    #[test]
+    fn test_quote_locks_price_until_crank_expires_it() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("stranger"), funds: vec![] },
+            ExecuteMsg::RequestQuote { cw20_contract: None },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+
+        let res = execute(deps.as_mut(), env.clone(), buyer, ExecuteMsg::RequestQuote { cw20_contract: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "quote_id" && a.value == "0"));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin,
+            ExecuteMsg::UpdatePrice { price_usd: Uint128::from(50000u128) },
+        )
+        .unwrap();
+        let quote: Quote = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Quote { id: 0 }).unwrap()).unwrap();
+        assert_eq!(quote.price, Uint128::from(25000u128));
+        assert_eq!(quote.expires_at_height, env.block.height + QUOTE_VALIDITY_BLOCKS);
+
+        // Still valid at its expiry height, so the crank leaves it
+        env.block.height = quote.expires_at_height;
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(QUOTES.has(deps.as_ref().storage, 0));
+
+        env.block.height += 1;
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "expired_quote" && a.value == "0"));
+        assert!(query(deps.as_ref(), env, QueryMsg::Quote { id: 0 }).is_err());
+    }
+
+    #[test]
+    fn test_circuit_breaker_pauses_on_abnormal_volume() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let breaker = CircuitBreaker {
+            window_blocks: 10,
+            trailing_windows: 2,
+            max_multiple_bps: 30_000,
+            min_volume_usd: Uint128::from(500u128),
+        };
+        let err = sudo(
+            deps.as_mut(),
+            env.clone(),
+            SudoMsg::SetCircuitBreaker { breaker: Some(CircuitBreaker { max_multiple_bps: 5_000, ..breaker.clone() }) },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCircuitBreaker { .. }));
+        sudo(deps.as_mut(), env.clone(), SudoMsg::SetCircuitBreaker { breaker: Some(breaker) }).unwrap();
+
+        // Two quiet windows of 400 set a baseline of 400; 3x allows 1200 per window
+        env.block.height = 1_000;
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        for _ in 0..2 {
+            assert!(record_sale_volume(&mut deps.storage, &env, &mut config, Uint128::from(400u128)).unwrap().is_none());
+            env.block.height += 10;
+        }
+        assert!(record_sale_volume(&mut deps.storage, &env, &mut config, Uint128::from(1_200u128)).unwrap().is_none());
+        assert!(!config.is_paused);
+
+        let event = record_sale_volume(&mut deps.storage, &env, &mut config, Uint128::from(1u128)).unwrap().unwrap();
+        assert_eq!(event.ty, "circuit_breaker_tripped");
+        assert!(event.attributes.iter().any(|a| a.key == "limit_usd" && a.value == "1200"));
+        assert!(config.is_paused);
+
+        let status: CircuitBreakerResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::CircuitBreaker {}).unwrap()).unwrap();
+        assert_eq!(status.window_volume_usd, Uint128::from(1_201u128));
+        assert_eq!(status.trailing_average_usd, Uint128::from(400u128));
+
+        // Windows older than the trailing ones are pruned
+        env.block.height += 30;
+        config.is_paused = false;
+        record_sale_volume(&mut deps.storage, &env, &mut config, Uint128::from(1u128)).unwrap();
+        assert_eq!(WINDOW_VOLUME.keys(&deps.storage, None, None, Order::Ascending).count(), 1);
+    }
+
+    #[test]
+    fn test_price_history_records_changes() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let token = api.addr_make("wusdt").to_string();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdatePrice { price_usd: Uint128::from(30000u128) },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdateTokenPrice { cw20_contract: token.clone(), price: Some(Uint128::from(31000u128)) },
+        )
+        .unwrap();
+
+        let page: PriceHistoryResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::PriceHistory { start_after: None, limit: Some(2) }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(page.changes[0].old_price_usd, None);
+        assert_eq!(page.changes[0].new_price_usd, Some(Uint128::from(25000u128)));
+        assert_eq!(page.changes[1].old_price_usd, Some(Uint128::from(25000u128)));
+        assert_eq!(page.changes[1].actor, admin.sender.to_string());
+        assert_eq!(page.next_start_after, Some(1));
+
+        let page: PriceHistoryResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::PriceHistory { start_after: page.next_start_after, limit: Some(2) }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(page.changes.len(), 1);
+        assert_eq!(page.changes[0].cw20_contract, Some(token));
+        assert_eq!(page.changes[0].new_price_usd, Some(Uint128::from(31000u128)));
+        assert_eq!(page.next_start_after, None);
+    }
+
+    #[test]
+    fn test_subscriptions_skip_and_reschedule() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let other = MessageInfo { sender: api.addr_make("other"), funds: vec![] };
+        let token = api.addr_make("wusdt").to_string();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            other.clone(),
+            ExecuteMsg::Subscribe { cw20_contract: token.clone(), usd_amount: Uint128::from(1_000_000u128), interval_blocks: 100 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer.clone(),
+            ExecuteMsg::Subscribe { cw20_contract: token.clone(), usd_amount: Uint128::from(1_000_000u128), interval_blocks: 99 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSubscription { .. }));
+
+        // Subscribe validates the token over gRPC, which the mock querier lacks; seed one directly
+        let subscription = Subscription {
+            id: 0,
+            buyer: buyer.sender.to_string(),
+            cw20_contract: token,
+            usd_amount: Uint128::from(1_000_000u128),
+            interval_blocks: 100,
+            next_due_height: env.block.height,
+            paused: false,
+            purchases: 0,
+            skips: 0,
+        };
+        SUBSCRIPTIONS.save(&mut deps.storage, 0, &subscription).unwrap();
+        SUBSCRIPTION_DUE.save(&mut deps.storage, (env.block.height, 0), &()).unwrap();
+
+        let err = execute(deps.as_mut(), env.clone(), other.clone(), ExecuteMsg::PauseSubscription { id: 0 }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), buyer.clone(), ExecuteMsg::PauseSubscription { id: 0 }).unwrap();
+        assert!(SUBSCRIPTION_DUE.is_empty(&deps.storage));
+
+        // Resuming after the due height restarts the schedule from now
+        env.block.height += 250;
+        execute(deps.as_mut(), env.clone(), buyer.clone(), ExecuteMsg::ResumeSubscription { id: 0 }).unwrap();
+        assert!(SUBSCRIPTION_DUE.has(&deps.storage, (env.block.height, 0)));
+
+        // The payment token cannot be validated, so the purchase is skipped, not reverted
+        let res = execute(deps.as_mut(), env.clone(), other.clone(), ExecuteMsg::ProcessSubscriptions { limit: None }).unwrap();
+        assert!(res.messages.is_empty());
+        assert!(res.events.iter().any(|e| e.ty == "subscription_skipped"));
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "1"));
+        let stored: Subscription =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Subscription { id: 0 }).unwrap()).unwrap();
+        assert_eq!(stored.next_due_height, env.block.height + 100);
+        assert_eq!(stored.purchases, 0);
+
+        let page: SubscriptionsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Subscriptions { buyer: Some(other.sender.to_string()), start_after: None, limit: None },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(page.subscriptions.is_empty());
+
+        execute(deps.as_mut(), env.clone(), buyer, ExecuteMsg::CancelSubscription { id: 0 }).unwrap();
+        assert!(SUBSCRIPTION_DUE.is_empty(&deps.storage));
+        let err = execute(deps.as_mut(), env, other, ExecuteMsg::CancelSubscription { id: 0 }).unwrap_err();
+        assert!(matches!(err, ContractError::SubscriptionNotFound { id: 0 }));
+    }
+
+    #[test]
+    fn test_subscriptions_reward_purchases_and_cancel_after_skips() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let buyer = api.addr_make("buyer").to_string();
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let wusdt = api.addr_make("wusdt").to_string();
+        let wdai = api.addr_make("wdai").to_string();
+
+        BUYER_SUBSCRIPTIONS.save(&mut deps.storage, &buyer, &MAX_SUBSCRIPTIONS_PER_BUYER).unwrap();
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("buyer"), funds: vec![] },
+            ExecuteMsg::Subscribe { cw20_contract: wusdt.clone(), usd_amount: Uint128::from(1_000_000u128), interval_blocks: 100 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSubscription { .. }));
+
+        // wusdt is pinned and funded so its subscription purchases; wdai fails validation
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.clone());
+        config.crank_reward = Uint128::from(10u128);
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(200_000_000_000u128, config.native_denom.clone())]);
+        deps.querier.update_wasm(|query: &WasmQuery| {
+            let WasmQuery::Smart { msg, .. } = query else { panic!("unexpected query") };
+            let response = if msg.as_slice().starts_with(b"{\"balance\"") {
+                r#"{"balance":"5000000"}"#
+            } else {
+                r#"{"allowance":"1000000","expires":{"never":{}}}"#
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        });
+        BUYER_SUBSCRIPTIONS.save(&mut deps.storage, &buyer, &2).unwrap();
+        for (id, token) in [(0u64, wusdt), (1, wdai)] {
+            let subscription = Subscription {
+                id,
+                buyer: buyer.clone(),
+                cw20_contract: token,
+                usd_amount: Uint128::from(1_000_000u128),
+                interval_blocks: 100,
+                next_due_height: env.block.height,
+                paused: false,
+                purchases: 0,
+                skips: 0,
+            };
+            SUBSCRIPTIONS.save(&mut deps.storage, id, &subscription).unwrap();
+            SUBSCRIPTION_DUE.save(&mut deps.storage, (env.block.height, id), &()).unwrap();
+        }
+
+        let reward = BankMsg::Send { to_address: keeper.sender.to_string(), amount: vec![Coin::new(10u128, config.native_denom)] };
+        for period in 1..=MAX_SUBSCRIPTION_SKIPS {
+            let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::ProcessSubscriptions { limit: None }).unwrap();
+            assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "2"));
+            // Only the purchase is rewarded
+            let rewards = res.messages.iter().filter(|m| m.msg == CosmosMsg::Bank(reward.clone())).count();
+            assert_eq!(rewards, 1);
+            let cancelled = res.events.iter().any(|e| e.ty == "subscription_cancelled");
+            assert_eq!(cancelled, period == MAX_SUBSCRIPTION_SKIPS);
+            env.block.height += 100;
+        }
+
+        assert_eq!(SUBSCRIPTIONS.load(&deps.storage, 0).unwrap().purchases, u64::from(MAX_SUBSCRIPTION_SKIPS));
+        assert!(!SUBSCRIPTIONS.has(&deps.storage, 1));
+        assert!(!SUBSCRIPTION_DUE.keys(&deps.storage, None, None, Order::Ascending).any(|key| key.unwrap().1 == 1));
+        assert_eq!(BUYER_SUBSCRIPTIONS.load(&deps.storage, &buyer).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_subscriptions_cannot_spend_reserved_inventory() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let buyer = api.addr_make("buyer").to_string();
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let wusdt = api.addr_make("wusdt").to_string();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.clone());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(50_000_000_000u128, config.native_denom.clone())]);
+        deps.querier.update_wasm(|query: &WasmQuery| {
+            let WasmQuery::Smart { msg, .. } = query else { panic!("unexpected query") };
+            let response = if msg.as_slice().starts_with(b"{\"balance\"") {
+                r#"{"balance":"5000000"}"#
+            } else {
+                r#"{"allowance":"5000000","expires":{"never":{}}}"#
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        });
+        let subscription = Subscription {
+            id: 0,
+            buyer: buyer.clone(),
+            cw20_contract: wusdt,
+            usd_amount: Uint128::from(1_000_000u128),
+            interval_blocks: 100,
+            next_due_height: env.block.height,
+            paused: false,
+            purchases: 0,
+            skips: 0,
+        };
+        SUBSCRIPTIONS.save(&mut deps.storage, 0, &subscription).unwrap();
+        SUBSCRIPTION_DUE.save(&mut deps.storage, (env.block.height, 0), &()).unwrap();
+
+        // 30 of the 50 GNK are held for lottery winners, so the 40 GNK purchase is skipped
+        RESERVES.save(&mut deps.storage, RESERVE_LOTTERY, &Uint128::from(30_000_000_000u128)).unwrap();
+        let process = ExecuteMsg::ProcessSubscriptions { limit: None };
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), process.clone()).unwrap();
+        let skipped = res.events.iter().find(|e| e.ty == "subscription_skipped").unwrap();
+        assert!(skipped.attributes.iter().any(|a| a.key == "reason" && a.value.starts_with("Insufficient contract balance: 20000000000")));
+        assert_eq!(SUBSCRIPTIONS.load(&deps.storage, 0).unwrap().purchases, 0);
+
+        RESERVES.remove(&mut deps.storage, RESERVE_LOTTERY);
+        env.block.height += 100;
+        execute(deps.as_mut(), env, keeper, process).unwrap();
+        assert_eq!(SUBSCRIPTIONS.load(&deps.storage, 0).unwrap().purchases, 1);
+    }
+
+    #[test]
+    fn test_finalize_sale_records_report() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let treasury = api.addr_make("treasury").to_string();
+        let denom = CONFIG.load(&deps.storage).unwrap().native_denom;
+        deps.querier.bank.update_balance(env.contract.address.clone(), vec![Coin::new(500_000u128, denom)]);
+
+        let buyer = api.addr_make("buyer").to_string();
+        let usdt = api.addr_make("wusdt").to_string();
+        record_purchase_stats(&mut deps.storage, &buyer, &usdt, Uint128::from(1_000_000u128)).unwrap();
+        record_purchase_stats(&mut deps.storage, &buyer, &usdt, Uint128::from(500_000u128)).unwrap();
+        record_refund(&mut deps.storage, Uint128::from(7u128)).unwrap();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.total_raised_usd = Uint128::from(1_500_000u128);
+        config.total_tokens_sold = Uint128::from(60_000_000_000u128);
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+
+        let report: Option<FinalReport> = from_json(query(deps.as_ref(), env.clone(), QueryMsg::FinalReport {}).unwrap()).unwrap();
+        assert!(report.is_none());
+        let finalize = ExecuteMsg::FinalizeSale { recipient: treasury.clone() };
+        let buyer_info = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let err = execute(deps.as_mut(), env.clone(), buyer_info, finalize.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), finalize.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let report: FinalReport =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::FinalReport {}).unwrap()).unwrap();
+        assert_eq!(report.raised_by_token, vec![TokenRaised { cw20_contract: usdt, raised_usd: Uint128::from(1_500_000u128) }]);
+        assert_eq!(report.unique_buyers, 1);
+        assert_eq!(report.average_price_usd, Uint128::from(25_000u128));
+        assert_eq!(report.refunded_usd, Uint128::from(7u128));
+        assert_eq!(report.leftover_swept, Uint128::from(500_000u128));
+        assert_eq!(report.swept_to, treasury);
+        assert!(CONFIG.load(&deps.storage).unwrap().is_paused);
+
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Resume {}).unwrap_err();
+        assert!(matches!(err, ContractError::SaleFinalized {}));
+        let err = execute(deps.as_mut(), env, admin, finalize).unwrap_err();
+        assert!(matches!(err, ContractError::SaleFinalized {}));
+    }
+
+    #[test]
+    fn test_queued_withdrawal_respects_reserves() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let denom = CONFIG.load(&deps.storage).unwrap().native_denom;
+        deps.querier.bank.update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000u128, denom)]);
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdateCrankConfig { crank_reward: Uint128::zero(), timelock_delay_blocks: Some(100) },
+        )
+        .unwrap();
+
+        // 1000 micro-USD at $0.025 holds back 40_000_000 tokens (9 payout decimals)
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer,
+            ExecuteMsg::ReservePurchase { usd_amount: Uint128::from(1_000u128), deadline_height: env.block.height + 150 },
+        )
+        .unwrap();
+        let reserves: ReservesResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Reserves {}).unwrap()).unwrap();
+        assert_eq!(reserves.reserved, Uint128::from(40_000_000u128));
+        assert_eq!(reserves.free, Uint128::from(60_000_000u128));
+        assert_eq!(reserves.obligations[0].obligation, RESERVE_RESERVATIONS);
+
+        // Both fit when queued; together they would dip into the reservation
+        for amount in [50_000_000u128, 20_000_000u128] {
+            let action = TimelockedAction::WithdrawNativeTokens {
+                amount: Uint128::from(amount),
+                recipient: admin.sender.to_string(),
+                denom: None,
+            };
+            execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::QueueAction { action }).unwrap();
+        }
+        env.block.height += 100;
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert!(res.attributes.iter().any(|a| a.key == "executed_action" && a.value == "0"));
+        assert!(res.attributes.iter().any(|a| a.key == "skipped_action" && a.value == "1"));
+        assert!(res.attributes.iter().any(|a| a.key == "processed" && a.value == "2"));
+
+        execute(deps.as_mut(), env.clone(), MessageInfo { sender: api.addr_make("buyer"), funds: vec![] }, ExecuteMsg::CancelReservation { id: 1 }).unwrap();
+        let reserves: ReservesResponse = from_json(query(deps.as_ref(), env, QueryMsg::Reserves {}).unwrap()).unwrap();
+        assert!(reserves.reserved.is_zero());
+    }
+
+    #[test]
+    fn test_correct_accounting_behind_timelock() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdateCrankConfig { crank_reward: Uint128::zero(), timelock_delay_blocks: Some(100) },
+        )
+        .unwrap();
+
+        let correct = |delta: i128, reason: &str| ExecuteMsg::QueueAction {
+            action: TimelockedAction::CorrectAccounting { delta: Int128::new(delta), reason: reason.to_string() },
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), correct(1_000, "  ")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAccountingCorrection { .. }));
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let err = execute(deps.as_mut(), env.clone(), buyer, correct(1_000, "otc settlement")).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // The second correction would take the counter below zero once the first has run
+        execute(deps.as_mut(), env.clone(), admin.clone(), correct(1_000, "otc settlement")).unwrap();
+        execute(deps.as_mut(), env.clone(), admin, correct(-5_000, "double count")).unwrap();
+        env.block.height += 100;
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "executed_action" && a.value == "0"));
+        assert!(res.attributes.iter().any(|a| a.key == "skipped_action" && a.value == "1"));
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().total_tokens_sold, Uint128::from(1_000u128));
+
+        let log: AuditLogResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::AuditLog { start_after: None, limit: None }).unwrap(),
+        )
+        .unwrap();
+        let entry = log.entries.iter().find(|e| e.action == "correct_accounting").unwrap();
+        assert_eq!(entry.old_value.as_deref(), Some("0"));
+        assert_eq!(entry.new_value.as_deref(), Some("1000 (otc settlement)"));
+    }
+
+    #[test]
+    fn test_failed_proceeds_bridge_is_held_for_flush() {
+        use cosmwasm_std::{ReplyOn, SubMsgResult};
+
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let bridge_to = |destination: &str| ExecuteMsg::UpdateProceedsBridge { destination: Some(destination.to_string()) };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), bridge_to("treasury")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidExternalAddress { .. }));
+        let treasury = "0x00000000000000000000000000000000000000AA";
+        execute(deps.as_mut(), env.clone(), admin.clone(), bridge_to(treasury)).unwrap();
+
+        // The withdraw hop failed and was reverted, leaving the proceeds with the contract
+        let wusdt = api.addr_make("wusdt").to_string();
+        let payload = to_json_binary(&BridgedProceeds { cw20_contract: wusdt.clone(), amount: Uint128::from(1_000u128) }).unwrap();
+        let failed = Reply {
+            id: PROCEEDS_BRIDGE_REPLY_ID,
+            payload,
+            gas_used: 0,
+            result: SubMsgResult::Err("travel-rule memo required".to_string()),
+        };
+        reply(deps.as_mut(), env.clone(), failed).unwrap();
+        let held: UnbridgedProceedsResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::UnbridgedProceeds {}).unwrap()).unwrap();
+        assert_eq!(held.proceeds, vec![UnbridgedProceedsEntry { cw20_contract: wusdt.clone(), amount: Uint128::from(1_000u128) }]);
+
+        let flush = ExecuteMsg::FlushUnbridgedProceeds { cw20_contract: wusdt.clone() };
+        let res = execute(deps.as_mut(), env.clone(), admin.clone(), flush.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].reply_on, ReplyOn::Error);
+        match &res.messages[0].msg {
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+                assert_eq!(contract_addr, &wusdt);
+                let expected = format!(
+                    r#"{{"withdraw":{{"amount":"1000","destination_address":"{}"}}}}"#,
+                    treasury.to_lowercase()
+                );
+                assert_eq!(msg, &Binary::from(expected.as_bytes()));
+            }
+            other => panic!("unexpected message {:?}", other),
+        }
+        let err = execute(deps.as_mut(), env, admin, flush).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToClaim {}));
+    }
+
+    #[test]
+    fn test_local_checks_run_before_token_validation() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let mut msg = mock_instantiate_msg(&api);
+        msg.max_raise_usd = Some(Uint128::new(10_000_000));
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let pay = |sender: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: api.addr_make(sender).to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(&PurchaseTokenMsg {
+                    v: None,
+                    payout_denom: None,
+                    salt: None,
+                    exact_out: None,
+                    intent: None,
+                    sale_id: None,
+                    reservation_id: None,
+                    quote_id: None,
+                })
+                .unwrap(),
+            })
+        };
+
+        // The mock querier cannot answer the chain, so reaching the token validation fails
+        let err = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay("buyer", 1_000_000)).unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+        let err = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay("outsider", 1_000_000)).unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.total_raised_usd = Uint128::new(10_000_000);
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay("buyer", 1_000_000)).unwrap_err();
+        assert!(matches!(err, ContractError::RaiseCapExceeded { remaining_usd: 0, .. }));
+        config.total_raised_usd = Uint128::zero();
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+
+        // Pinning validates the token first
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let pin = ExecuteMsg::UpdateTrustedPaymentToken { cw20_contract: Some(wusdt.sender.to_string()) };
+        assert!(execute(deps.as_mut(), env.clone(), admin, pin).is_err());
+
+        // A pinned token pays without the chain queries
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, config.native_denom)]);
+        let res = execute(deps.as_mut(), env, wusdt, pay("buyer", 1_000_000)).unwrap();
+        assert!(res.attributes.iter().any(|a| a.key == "gnk_purchased" && a.value == "40000000000"));
+    }
+
+    #[test]
+    fn test_validator_kickback_is_a_share_of_the_stablecoin() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let fee_collector = api.addr_make("fee_collector").to_string();
+        let kickback = ValidatorKickback { bps: 250, recipient: fee_collector.clone() };
+        sudo(deps.as_mut(), env.clone(), SudoMsg::SetValidatorKickback { kickback: Some(kickback) }).unwrap();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        let pay = || {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: api.addr_make("buyer").to_string(),
+                amount: Uint128::new(1_000_000),
+                msg: to_json_binary(&PurchaseTokenMsg {
+                    v: None,
+                    payout_denom: None,
+                    salt: None,
+                    exact_out: None,
+                    intent: None,
+                    sale_id: None,
+                    reservation_id: None,
+                    quote_id: None,
+                })
+                .unwrap(),
+            })
+        };
+
+        // 2.5% of the $1 payment goes to the fee collector, with nothing extra from the inventory
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(40_000_000_000u128, config.native_denom.clone())]);
+        let res = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay()).unwrap();
+        let transfer = create_cw20_transfer_msg(wusdt.sender.to_string(), fee_collector.clone(), Uint128::new(25_000)).unwrap();
+        let WasmMsg::Execute { msg, .. } = transfer else { unreachable!() };
+        let kickback = CosmosMsg::Any(AnyMsg {
+            type_url: "/cosmwasm.wasm.v1.MsgExecuteContract".to_string(),
+            value: Binary::from(prost::Message::encode_to_vec(&MsgExecuteContract {
+                sender: env.contract.address.to_string(),
+                contract: wusdt.sender.to_string(),
+                msg: msg.to_vec(),
+                funds: vec![],
+            })),
+        });
+        let proceeds = CosmosMsg::Wasm(
+            create_cw20_transfer_msg(wusdt.sender.to_string(), config.proceeds_recipient(), Uint128::new(975_000)).unwrap(),
+        );
+        assert!(res.messages.iter().any(|m| m.msg == kickback));
+        assert!(res.messages.iter().any(|m| m.msg == proceeds));
+        assert!(!res.messages.iter().any(|m| matches!(&m.msg, CosmosMsg::Bank(BankMsg::Send { to_address, .. }) if *to_address == fee_collector)));
+        assert!(res.attributes.iter().any(|a| a.key == "validator_kickback_usd" && a.value == "25000"));
+        assert!(res.attributes.iter().any(|a| a.key == "gnk_purchased" && a.value == "40000000000"));
+
+        // Subscriptions pull the share straight from the buyer's allowance
+        let buyer = api.addr_make("buyer").to_string();
+        deps.querier.update_wasm(|query: &WasmQuery| {
+            let WasmQuery::Smart { msg, .. } = query else { panic!("unexpected query") };
+            let response = if msg.as_slice().starts_with(b"{\"balance\"") {
+                r#"{"balance":"5000000"}"#
+            } else {
+                r#"{"allowance":"1000000","expires":{"never":{}}}"#
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        });
+        let subscription = Subscription {
+            id: 0,
+            buyer: buyer.clone(),
+            cw20_contract: wusdt.sender.to_string(),
+            usd_amount: Uint128::from(1_000_000u128),
+            interval_blocks: 100,
+            next_due_height: env.block.height,
+            paused: false,
+            purchases: 0,
+            skips: 0,
+        };
+        SUBSCRIPTIONS.save(&mut deps.storage, 0, &subscription).unwrap();
+        SUBSCRIPTION_DUE.save(&mut deps.storage, (env.block.height, 0), &()).unwrap();
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::ProcessSubscriptions { limit: None }).unwrap();
+        let transfer_from =
+            create_cw20_transfer_from_msg(wusdt.sender.to_string(), buyer.clone(), fee_collector, Uint128::new(25_000)).unwrap();
+        let WasmMsg::Execute { msg, .. } = transfer_from else { unreachable!() };
+        let kickback = CosmosMsg::Any(AnyMsg {
+            type_url: "/cosmwasm.wasm.v1.MsgExecuteContract".to_string(),
+            value: Binary::from(prost::Message::encode_to_vec(&MsgExecuteContract {
+                sender: env.contract.address.to_string(),
+                contract: wusdt.sender.to_string(),
+                msg: msg.to_vec(),
+                funds: vec![],
+            })),
+        });
+        let proceeds = CosmosMsg::Wasm(
+            create_cw20_transfer_from_msg(wusdt.sender.to_string(), buyer, config.proceeds_recipient(), Uint128::new(975_000)).unwrap(),
+        );
+        assert!(res.messages.iter().any(|m| m.msg == kickback));
+        assert!(res.messages.iter().any(|m| m.msg == proceeds));
+    }
+
+    #[test]
+    fn test_purchase_via_allowance_pulls_payment_first() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let outsider = MessageInfo { sender: api.addr_make("outsider"), funds: vec![] };
+        let wusdt = api.addr_make("wusdt").to_string();
+        let purchase = |usd_amount: u128| ExecuteMsg::PurchaseViaAllowance {
+            token: wusdt.clone(),
+            usd_amount: Uint128::new(usd_amount),
+        };
+
+        // The purchase's own checks run ahead of the allowance
+        let err = execute(deps.as_mut(), env.clone(), outsider, purchase(1_000_000)).unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), purchase(0)).unwrap_err();
+        assert!(matches!(err, ContractError::ZeroAmount {}));
+
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.clone());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, config.native_denom)]);
+        deps.querier.update_wasm(|query: &WasmQuery| {
+            let WasmQuery::Smart { msg, .. } = query else { panic!("unexpected query") };
+            let response = if msg.as_slice().starts_with(b"{\"balance\"") {
+                r#"{"balance":"5000000"}"#
+            } else {
+                r#"{"allowance":"1000000","expires":{"never":{}}}"#
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        });
+
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), purchase(2_000_000)).unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientAllowance { allowance: 1_000_000, required: 2_000_000 }));
+
+        let res = execute(deps.as_mut(), env.clone(), buyer.clone(), purchase(1_000_000)).unwrap();
+        let pull = create_cw20_transfer_from_msg(
+            wusdt,
+            buyer.sender.to_string(),
+            env.contract.address.to_string(),
+            Uint128::new(1_000_000),
+        )
+        .unwrap();
+        assert_eq!(res.messages[0].msg, CosmosMsg::Wasm(pull));
+        assert!(res.attributes.iter().any(|a| a.key == "gnk_purchased" && a.value == "40000000000"));
+        assert!(res.attributes.iter().any(|a| a.key == "payment" && a.value == "allowance"));
+    }
+
+    #[test]
+    fn test_streamed_proceeds_released_by_crank() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let keeper = MessageInfo { sender: api.addr_make("keeper"), funds: vec![] };
+        let stream = |interval_blocks| ExecuteMsg::UpdateProceedsStream { interval_blocks };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), stream(Some(0))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidProceedsStream { .. }));
+        execute(deps.as_mut(), env.clone(), admin.clone(), stream(Some(100))).unwrap();
+
+        // Proceeds are held instead of forwarded
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let wusdt = api.addr_make("wusdt").to_string();
+        let treasury = config.proceeds_recipient();
+        for amount in [1_000u128, 500] {
+            let res = route_proceeds(
+                deps.as_mut().storage,
+                &config,
+                Response::new(),
+                wusdt.clone(),
+                treasury.clone(),
+                Uint128::new(amount),
+            )
+            .unwrap();
+            assert!(res.messages.is_empty());
+        }
+        let held: StreamedProceedsResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::StreamedProceeds {}).unwrap()).unwrap();
+        assert_eq!(
+            held.proceeds,
+            vec![StreamedProceedsEntry { cw20_contract: wusdt.clone(), recipient: treasury.clone(), amount: Uint128::new(1_500) }]
+        );
+        assert_eq!(held.next_release_height, Some(env.block.height + 100));
+
+        // Not due yet, then held while paused
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.messages.is_empty());
+        env.block.height += 100;
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Pause {}).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), keeper.clone(), ExecuteMsg::Crank { limit: None }).unwrap();
+        assert!(res.messages.is_empty());
+        execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::Resume {}).unwrap();
+
+        let res = execute(deps.as_mut(), env.clone(), keeper, ExecuteMsg::Crank { limit: None }).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(create_cw20_transfer_msg(wusdt, treasury, Uint128::new(1_500)).unwrap())
+        );
+        let held: StreamedProceedsResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::StreamedProceeds {}).unwrap()).unwrap();
+        assert!(held.proceeds.is_empty());
+        assert_eq!(held.next_release_height, Some(env.block.height + 100));
+    }
+
+    #[test]
+    fn test_can_purchase_reports_first_failing_check() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let can_purchase = |deps: Deps, buyer: &str| -> CanPurchaseResponse {
+            let msg = QueryMsg::CanPurchase { buyer: api.addr_make(buyer).to_string(), usd_amount: Uint128::from(1_000_000u128) };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        let res = can_purchase(deps.as_ref(), "outsider");
+        assert!(!res.can_purchase);
+        assert_eq!(res.error_code, Some(ContractError::BuyerNotAllowed { buyer: String::new() }.code()));
+        assert!(res.reason.unwrap().starts_with("Buyer not allowed"));
+
+        // $1 at $0.025 buys 40 GNK, which the empty contract cannot pay out
+        let res = can_purchase(deps.as_ref(), "buyer");
+        assert_eq!(res.error_code, Some(ContractError::InsufficientBalance { available: 0, needed: 0 }.code()));
+        assert!(res.reason.unwrap().starts_with("Insufficient contract balance"));
+        let denom = CONFIG.load(&deps.storage).unwrap().native_denom;
+        deps.querier.bank.update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, denom)]);
+        let res = can_purchase(deps.as_ref(), "buyer");
+        assert!(res.can_purchase);
+        assert_eq!(res.reason, None);
+        assert_eq!(res.tokens, Uint128::from(40_000_000_000u128));
+
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        execute(deps.as_mut(), env, admin, ExecuteMsg::Pause {}).unwrap();
+        let res = can_purchase(deps.as_ref(), "buyer");
+        assert_eq!(res.reason.as_deref(), Some("Contract is paused"));
+    }
+
+    #[test]
+    fn test_inventory_pools_round_robin() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        let buyer = api.addr_make("buyer").to_string();
+        let tokens = Uint128::from(100u128);
+
+        // No pools: purchases draw from the main inventory as before
+        assert_eq!(select_pool(&deps.storage, &buyer, tokens).unwrap(), None);
+
+        for (label, cap) in [("community", 250u128), ("strategic", 1_000u128)] {
+            let msg = ExecuteMsg::SetPool {
+                label: label.to_string(),
+                cap: Uint128::from(cap),
+                add_buyers: vec![buyer.clone()],
+                remove_buyers: vec![],
+            };
+            execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        }
+        let err = select_pool(&deps.storage, api.addr_make("outsider").as_ref(), tokens).unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+
+        // Eligible pools take turns
+        let mut drawn = vec![];
+        for _ in 0..4 {
+            let label = select_pool(&deps.storage, &buyer, tokens).unwrap().unwrap();
+            record_pool_draw(&mut deps.storage, &label, tokens, Uint128::from(2_500u128)).unwrap();
+            drawn.push(label);
+        }
+        assert_eq!(drawn, vec!["community", "strategic", "community", "strategic"]);
+        // community has 50 left, so the turn passes to strategic
+        assert_eq!(select_pool(&deps.storage, &buyer, tokens).unwrap().as_deref(), Some("strategic"));
+        let err = select_pool(&deps.storage, &buyer, Uint128::from(900u128)).unwrap_err();
+        assert!(matches!(err, ContractError::PoolCapacityExceeded { remaining: 800, requested: 900 }));
+
+        let res: PoolResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Pool { label: "community".to_string() }).unwrap()).unwrap();
+        assert_eq!(res.pool.sold, Uint128::from(200u128));
+        assert_eq!(res.pool.raised_usd, Uint128::from(5_000u128));
+        assert_eq!(res.remaining, Uint128::from(50u128));
+        let res: BuyerPoolsResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::BuyerPools { buyer: buyer.clone() }).unwrap()).unwrap();
+        assert_eq!(res.pools, vec!["community", "strategic"]);
+
+        let msg = ExecuteMsg::SetPool {
+            label: "community".to_string(),
+            cap: Uint128::from(150u128),
+            add_buyers: vec![],
+            remove_buyers: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPool { .. }));
+        let err = execute(deps.as_mut(), env, admin, ExecuteMsg::RemovePool { label: "community".to_string() }).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPool { .. }));
+    }
+
+    #[test]
+    fn test_epoch_schedule_needs_the_chain_epoch() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+
+        // Without a schedule purchases never ask the chain for the epoch
+        assert_eq!(check_epoch_cap(deps.as_ref(), Uint128::from(1_000_000u128)).unwrap(), None);
+
+        // The mock querier does not answer gRPC, so scheduling fails closed
+        let epochs = vec![EpochSaleParams {
+            epoch: 7,
+            price_usd: Some(Uint128::from(30_000u128)),
+            cap_usd: Some(Uint128::from(5_000_000u128)),
+        }];
+        let msg = ExecuteMsg::SetEpochSchedule { epochs };
+        assert!(execute(deps.as_mut(), env.clone(), admin, msg).is_err());
+
+        // and so does pricing once anything is scheduled
+        let sale = EpochSale {
+            price_usd: Some(Uint128::from(30_000u128)),
+            cap_usd: Some(Uint128::from(5_000_000u128)),
+        };
+        EPOCH_SCHEDULE.save(deps.as_mut().storage, 7, &sale).unwrap();
+        EPOCH_RAISED.save(deps.as_mut().storage, 7, &Uint128::from(1_000_000u128)).unwrap();
+        assert!(check_epoch_cap(deps.as_ref(), Uint128::from(1_000_000u128)).is_err());
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert!(effective_price(deps.as_ref(), &env, &config, "wusdt").is_err());
+
+        let schedule: EpochScheduleResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::EpochSchedule { start_after: None, limit: None }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(schedule.current_epoch, None);
+        assert_eq!(
+            schedule.epochs,
+            vec![EpochSaleInfo { epoch: 7, sale, raised_usd: Uint128::from(1_000_000u128) }]
+        );
+    }
+
+    #[test]
+    fn test_large_purchases_need_co_approval() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let approver = MessageInfo { sender: api.addr_make("risk_desk"), funds: vec![] };
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let reserve = |usd_amount: u128, height: u64| ExecuteMsg::ReservePurchase {
+            usd_amount: Uint128::from(usd_amount),
+            deadline_height: height + 100,
+        };
+
+        // The admin cannot be its own second signer
+        let co_approval = |approver: &Addr| CoApproval {
+            approver: approver.to_string(),
+            threshold_usd: Uint128::from(10_000_000u128),
+        };
+        let msg = ExecuteMsg::UpdateCoApproval { co_approval: Some(co_approval(&admin.sender)) };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCoApproval { .. }));
+        let msg = ExecuteMsg::UpdateCoApproval { co_approval: Some(co_approval(&approver.sender)) };
+        execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
 
-fn query_test_bridge_validation(deps: Deps, cw20_contract: String) -> StdResult<TestBridgeValidationResponse> {
-    let denom = if cw20_contract.starts_with("cw20:") {
-        cw20_contract
-    } else {
-        format!("cw20:{}", cw20_contract)
-    };
-    let is_valid = validate_wrapped_token_for_trade(deps, &denom).unwrap_or(false);
-    Ok(TestBridgeValidationResponse { is_valid })
-}
+        // At or below the threshold nothing changes
+        execute(deps.as_mut(), env.clone(), buyer.clone(), reserve(10_000_000, env.block.height)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), reserve(50_000_000, env.block.height)).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseApprovalRequired { usd_amount: 50_000_000, threshold_usd: 10_000_000 }));
 
-fn query_block_height(env: Env) -> StdResult<BlockHeightResponse> {
-    Ok(BlockHeightResponse { height: env.block.height })
-}
+        // Only the approver approves
+        let approve = |max_usd: u128, expiry: u64| ExecuteMsg::ApprovePurchase {
+            buyer: buyer.sender.to_string(),
+            max_usd: Uint128::from(max_usd),
+            expiry,
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), approve(50_000_000, env.block.height + 10)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), approver.clone(), approve(40_000_000, env.block.height + 10)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), reserve(50_000_000, env.block.height)).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseApprovalExceeded { approved_usd: 40_000_000, requested_usd: 50_000_000 }));
 
-fn query_test_approved_tokens(deps: Deps) -> StdResult<ApprovedTokensForTradeJson> {
-    let decoded: QueryApprovedTokensForTradeResponseProto = query_proto(
-        deps,
-        "/inference.inference.Query/ApprovedTokensForTrade",
-        &EmptyRequest::default(),
-    )?;
-    let approved_tokens = decoded
-        .approved_tokens
-        .into_iter()
-        .map(|t| ApprovedTokenJson {
-            chain_id: t.chain_id,
-            contract_address: t.contract_address,
-        })
-        .collect();
-    Ok(ApprovedTokensForTradeJson { approved_tokens })
-}
+        // An expired approval counts as none
+        env.block.height += 11;
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), reserve(30_000_000, env.block.height)).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseApprovalRequired { .. }));
 
-fn query_grpc(deps: Deps, path: &str, data: Binary) -> StdResult<Binary> {
-    let request = QueryRequest::Grpc(GrpcQuery {
-        path: path.to_string(),
-        data,
-    });
-    query_raw(deps, &request)
-}
+        // A used approval is gone
+        execute(deps.as_mut(), env.clone(), approver.clone(), approve(40_000_000, env.block.height + 10)).unwrap();
+        execute(deps.as_mut(), env.clone(), buyer.clone(), reserve(30_000_000, env.block.height)).unwrap();
+        let approval: Option<PurchaseApproval> = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::PurchaseApproval { buyer: buyer.sender.to_string() }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(approval, None);
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), reserve(30_000_000, env.block.height)).unwrap_err();
+        assert!(matches!(err, ContractError::PurchaseApprovalRequired { .. }));
 
-fn query_raw(deps: Deps, request: &QueryRequest<GrpcQuery>) -> StdResult<Binary> {
-    let raw = to_json_vec(request).map_err(|e| StdError::msg(format!("Serializing: {e}")))?;
-    match deps.querier.raw_query(&raw) {
-        SystemResult::Err(e) => Err(StdError::msg(format!("System error: {e}"))),
-        SystemResult::Ok(ContractResult::Err(e)) => Err(StdError::msg(format!("Contract error: {e}"))),
-        SystemResult::Ok(ContractResult::Ok(value)) => Ok(value),
+        // Dropping the requirement lets large purchases through unapproved
+        execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::UpdateCoApproval { co_approval: None }).unwrap();
+        execute(deps.as_mut(), env.clone(), buyer, reserve(30_000_000, env.block.height)).unwrap();
     }
-}
 
-fn query_proto<TRequest, TResponse>(deps: Deps, path: &str, request: &TRequest) -> StdResult<TResponse>
-where
-    TRequest: prost::Message,
-    TResponse: prost::Message + Default,
-{
-    let mut buf = Vec::new();
-    request.encode(&mut buf).map_err(|e| StdError::msg(format!("Encode: {}", e)))?;
-    let bytes = query_grpc(deps, path, Binary::from(buf))?;
-    TResponse::decode(bytes.as_slice()).map_err(|e| StdError::msg(format!("Decode: {}", e)))
-}
+    #[test]
+    fn test_name_bindings_follow_registry_rotation() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let registry = api.addr_make("registry").to_string();
+        fn point_treasury_at(address: String) -> impl Fn(&WasmQuery) -> cosmwasm_std::QuerierResult {
+            move |query| match query {
+                WasmQuery::Smart { msg, .. } if String::from_utf8_lossy(msg).contains("community-sale.treasury") => {
+                    let response = format!(
+                        r#"{{"name":"community-sale.treasury","address":"{}","updated_at_height":1}}"#,
+                        address
+                    );
+                    SystemResult::Ok(ContractResult::Ok(Binary::from(response.into_bytes())))
+                }
+                _ => SystemResult::Ok(ContractResult::Err("Name not found".to_string())),
+            }
+        }
+        let bindings = |buyer: Option<&str>| NameBindings {
+            registry: registry.clone(),
+            treasury: Some("community-sale.treasury".to_string()),
+            buyer: buyer.map(str::to_string),
+            cache_blocks: 10,
+        };
+        let first = api.addr_make("treasury_1").to_string();
+        deps.querier.update_wasm(point_treasury_at(first.clone()));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi};
-    use cosmwasm_std::{from_json, Addr, MessageInfo};
+        // Names that do not resolve are rejected when bound
+        let msg = ExecuteMsg::UpdateNameBindings { bindings: Some(bindings(Some("sale.buyer"))) };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+        let msg = ExecuteMsg::UpdateNameBindings { bindings: Some(bindings(None)) };
+        execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().proceeds_recipient(), first);
 
-    fn mock_instantiate_msg(api: &MockApi) -> InstantiateMsg {
-        InstantiateMsg {
-            admin: api.addr_make("admin").to_string(),
-            buyer: api.addr_make("buyer").to_string(),
-            accepted_chain_id: "ethereum".to_string(),
-            accepted_eth_contract: "0xdac17f958d2ee523a2206206994597c13d831ec7".to_string(),
-            price_usd: Uint128::from(25000u128), // $0.025
-        }
+        // A rotation in the registry is picked up once the cached resolution expires
+        let second = api.addr_make("treasury_2").to_string();
+        deps.querier.update_wasm(point_treasury_at(second.clone()));
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Pause {}).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().proceeds_recipient(), first);
+        env.block.height += 10;
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Resume {}).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().proceeds_recipient(), second);
+
+        // An unreachable registry keeps the last resolved address
+        deps.querier.update_wasm(|_: &WasmQuery| SystemResult::Ok(ContractResult::Err("registry gone".to_string())));
+        env.block.height += 10;
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Pause {}).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().proceeds_recipient(), second);
+
+        execute(deps.as_mut(), env, admin, ExecuteMsg::UpdateNameBindings { bindings: None }).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().proceeds_recipient(), api.addr_make("admin").to_string());
     }
 
     #[test]
-    fn proper_instantiation() {
-        let deps = mock_dependencies();
+    fn test_raise_cap() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let info = MessageInfo {
+            sender: Addr::unchecked("creator"),
+            funds: vec![],
+        };
+        let mut msg = mock_instantiate_msg(&api);
+        msg.max_raise_usd = Some(Uint128::from(1_000u128));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.total_raised_usd = Uint128::from(900u128);
+        assert_eq!(check_raise_cap(&config, Uint128::from(100u128)).unwrap(), Uint128::from(1_000u128));
+        let err = check_raise_cap(&config, Uint128::from(101u128)).unwrap_err();
+        assert!(matches!(err, ContractError::RaiseCapExceeded { remaining_usd: 100, requested_usd: 101 }));
+        config.max_raise_usd = None;
+        check_raise_cap(&config, Uint128::from(101u128)).unwrap();
+    }
+
+    #[test]
+    fn test_create_sale_and_buyer_gate() {
+        let mut deps = mock_dependencies();
         let api = MockApi::default();
-        let buyer_addr = api.addr_make("buyer").to_string();
-        
-        let mut deps = deps;
         let env = mock_env();
         let info = MessageInfo {
             sender: Addr::unchecked("creator"),
             funds: vec![],
         };
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
 
-        let res = instantiate(deps.as_mut(), env, info, mock_instantiate_msg(&api)).unwrap();
-        assert!(res.attributes.iter().any(|a| a.key == "buyer" && a.value == buyer_addr));
-        assert!(res.attributes.iter().any(|a| a.key == "accepted_chain_id" && a.value == "ethereum"));
-        assert!(res.attributes.iter().any(|a| a.key == "accepted_eth_contract" && a.value == "0xdac17f958d2ee523a2206206994597c13d831ec7"));
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        let buyer = api.addr_make("partner").to_string();
+        let create = ExecuteMsg::CreateSale {
+            params: SaleParams {
+                name: "partner".to_string(),
+                price_usd: Uint128::from(40000u128),
+                inventory: Uint128::from(1_000_000_000u128),
+                buyers: vec![buyer.clone()],
+            },
+        };
+        execute(deps.as_mut(), env.clone(), admin.clone(), create).unwrap();
+
+        let sale: SaleResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Sale { id: 1 }).unwrap()).unwrap();
+        assert_eq!(sale.sale.price_usd, Uint128::from(40000u128));
+        let allowed: bool = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::SaleBuyer { id: 1, buyer: buyer.clone() }).unwrap(),
+        )
+        .unwrap();
+        assert!(allowed);
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        let stranger = api.addr_make("stranger").to_string();
+        let err = purchase_from_sale(deps.as_mut(), &env, config.clone(), 1, stranger, "wusdt".to_string(), Uint128::from(100u128))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::BuyerNotAllowed { .. }));
+
+        let close = ExecuteMsg::UpdateSale { id: 1, add_buyers: vec![], remove_buyers: vec![], closed: Some(true) };
+        execute(deps.as_mut(), env.clone(), admin, close).unwrap();
+        let err = purchase_from_sale(deps.as_mut(), &env, config, 1, buyer, "wusdt".to_string(), Uint128::from(100u128))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::SaleClosed { id: 1 }));
     }
 
     #[test]
-    fn test_pause_resume() {
-        let deps = mock_dependencies();
+    fn test_sale_purchase_sells_from_its_reserve() {
+        let mut deps = mock_dependencies();
         let api = MockApi::default();
-        let admin_addr = api.addr_make("admin");
-        
-        let mut deps = deps;
         let env = mock_env();
         let info = MessageInfo {
             sender: Addr::unchecked("creator"),
             funds: vec![],
         };
         instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let partner = api.addr_make("partner").to_string();
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(100_000_000_000u128, config.native_denom.clone())]);
+        let create = ExecuteMsg::CreateSale {
+            params: SaleParams {
+                name: "partner".to_string(),
+                price_usd: Uint128::from(40000u128),
+                inventory: Uint128::from(50_000_000_000u128),
+                buyers: vec![partner.clone()],
+            },
+        };
+        execute(deps.as_mut(), env.clone(), admin.clone(), create).unwrap();
+        let reserves = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| -> ReservesResponse {
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Reserves {}).unwrap()).unwrap()
+        };
+        assert_eq!(reserves(&deps).free, Uint128::from(50_000_000_000u128));
+
+        // $1 at $0.04 buys 25 GNK out of the sale's reserve
+        let pay = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: partner.clone(),
+            amount: Uint128::new(1_000_000),
+            msg: to_json_binary(&PurchaseTokenMsg {
+                v: None,
+                payout_denom: None,
+                salt: None,
+                exact_out: None,
+                intent: None,
+                sale_id: Some(1),
+                reservation_id: None,
+                quote_id: None,
+            })
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay).unwrap();
+        let payout = CosmosMsg::Bank(BankMsg::Send {
+            to_address: partner,
+            amount: vec![Coin::new(25_000_000_000u128, config.native_denom.clone())],
+        });
+        let proceeds = CosmosMsg::Wasm(
+            create_cw20_transfer_msg(wusdt.sender.to_string(), config.proceeds_recipient(), Uint128::new(1_000_000)).unwrap(),
+        );
+        assert!(res.messages.iter().any(|m| m.msg == payout));
+        assert!(res.messages.iter().any(|m| m.msg == proceeds));
+        let sale = SALES.load(&deps.storage, 1).unwrap();
+        assert_eq!((sale.sold, sale.raised_usd), (Uint128::from(25_000_000_000u128), Uint128::new(1_000_000)));
+        assert_eq!(RESERVES.load(&deps.storage, &sale_reserve(1)).unwrap(), Uint128::from(25_000_000_000u128));
+
+        // The unsold rest cannot be withdrawn until the sale closes
+        let withdraw = ExecuteMsg::WithdrawNativeTokens {
+            amount: Uint128::from(80_000_000_000u128),
+            recipient: admin.sender.to_string(),
+            denom: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin.clone(), withdraw.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::WithdrawalExceedsUnreserved { .. }));
+        let close = ExecuteMsg::UpdateSale { id: 1, add_buyers: vec![], remove_buyers: vec![], closed: Some(true) };
+        execute(deps.as_mut(), env.clone(), admin.clone(), close).unwrap();
+        assert_eq!(reserves(&deps).free, Uint128::from(100_000_000_000u128));
+        execute(deps.as_mut(), env, admin, withdraw).unwrap();
+    }
 
+    #[test]
+    fn test_main_purchase_cannot_spend_reserved_inventory() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
         let info = MessageInfo {
-            sender: admin_addr.clone(),
+            sender: Addr::unchecked("creator"),
             funds: vec![],
         };
-        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Pause {}).unwrap();
+        let msg = mock_instantiate_msg(&api);
+        let buyer = msg.buyer.clone();
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let admin = MessageInfo { sender: api.addr_make("admin"), funds: vec![] };
+        let wusdt = MessageInfo { sender: api.addr_make("wusdt"), funds: vec![] };
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.trusted_payment_token = Some(wusdt.sender.to_string());
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        deps.querier
+            .bank
+            .update_balance(env.contract.address.clone(), vec![Coin::new(50_000_000_000u128, config.native_denom.clone())]);
+        let create = ExecuteMsg::CreateSale {
+            params: SaleParams {
+                name: "partner".to_string(),
+                price_usd: Uint128::from(40000u128),
+                inventory: Uint128::from(30_000_000_000u128),
+                buyers: vec![api.addr_make("partner").to_string()],
+            },
+        };
+        execute(deps.as_mut(), env.clone(), admin.clone(), create).unwrap();
 
-        let config: ConfigResponse =
-            from_json(&query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
-        assert!(config.is_paused);
+        // $1 at $0.025 needs 40 GNK, but only 20 of the 50 held are not the sale's
+        let pay = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: buyer.clone(),
+            amount: Uint128::new(1_000_000),
+            msg: to_json_binary(&PurchaseTokenMsg {
+                v: None,
+                payout_denom: None,
+                salt: None,
+                exact_out: None,
+                intent: None,
+                sale_id: None,
+                reservation_id: None,
+                quote_id: None,
+            })
+            .unwrap(),
+        });
+        let err = execute(deps.as_mut(), env.clone(), wusdt.clone(), pay.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ContractError::InsufficientBalance { available: 20_000_000_000, needed: 40_000_000_000 }
+        ));
+        let check = QueryMsg::CanPurchase { buyer: buyer.clone(), usd_amount: Uint128::new(1_000_000) };
+        let res: CanPurchaseResponse = from_json(query(deps.as_ref(), env.clone(), check.clone()).unwrap()).unwrap();
+        assert_eq!(res.error_code, Some(ContractError::InsufficientBalance { available: 0, needed: 0 }.code()));
 
-        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Resume {}).unwrap();
-        let config: ConfigResponse =
-            from_json(&query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
-        assert!(!config.is_paused);
+        // Closing the sale hands its inventory back to the main sale
+        let close = ExecuteMsg::UpdateSale { id: 1, add_buyers: vec![], remove_buyers: vec![], closed: Some(true) };
+        execute(deps.as_mut(), env.clone(), admin, close).unwrap();
+        let res: CanPurchaseResponse = from_json(query(deps.as_ref(), env.clone(), check).unwrap()).unwrap();
+        assert!(res.can_purchase);
+        execute(deps.as_mut(), env, wusdt, pay).unwrap();
     }
 
     #[test]
-    fn test_update_buyer() {
-        let deps = mock_dependencies();
+    fn test_rounds_set_price_and_reject_overlap() {
+        let mut deps = mock_dependencies();
         let api = MockApi::default();
-        let admin_addr = api.addr_make("admin");
-        let new_buyer = api.addr_make("newbuyer").to_string();
-        
-        let mut deps = deps;
         let env = mock_env();
         let info = MessageInfo {
             sender: Addr::unchecked("creator"),
@@ -615,30 +8438,57 @@ mod tests {
         };
         instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
 
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        let strategic = ExecuteMsg::AddRound {
+            name: "strategic".to_string(),
+            start_height: env.block.height,
+            end_height: env.block.height + 100,
+            price_usd: Uint128::from(20000u128),
+            treasury: Some(api.addr_make("strategic_treasury").to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), admin.clone(), strategic).unwrap();
+        let overlapping = ExecuteMsg::AddRound {
+            name: "public".to_string(),
+            start_height: env.block.height + 50,
+            end_height: env.block.height + 200,
+            price_usd: Uint128::from(25000u128),
+            treasury: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), admin, overlapping).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidRound { .. }));
+
+        let rounds: RoundsResponse = from_json(query(deps.as_ref(), env.clone(), QueryMsg::Rounds { start_after: None, limit: None }).unwrap()).unwrap();
+        assert_eq!(rounds.rounds.len(), 1);
+        assert_eq!(rounds.active_round, Some(0));
+        let price: CurrentPriceResponse =
+            from_json(query(deps.as_ref(), env, QueryMsg::CurrentPrice { cw20_contract: None }).unwrap()).unwrap();
+        assert_eq!(price.price_usd, Uint128::from(20000u128));
+    }
+
+    #[test]
+    fn test_contract_info() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
         let info = MessageInfo {
-            sender: admin_addr,
+            sender: Addr::unchecked("creator"),
             funds: vec![],
         };
-        execute(
-            deps.as_mut(),
-            env.clone(),
-            info,
-            ExecuteMsg::UpdateBuyer { buyer: new_buyer.clone() },
-        )
-        .unwrap();
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
 
-        let config: ConfigResponse =
-            from_json(&query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
-        assert_eq!(config.buyer, new_buyer);
+        let res: ContractInfoResponse = from_json(query(deps.as_ref(), env, QueryMsg::ContractInfo {}).unwrap()).unwrap();
+        assert_eq!(res.name, CONTRACT_NAME);
+        assert_eq!(res.version, CONTRACT_VERSION);
+        assert_eq!(res.schema_version, SCHEMA_VERSION);
     }
 
     #[test]
-    fn test_update_price() {
-        let deps = mock_dependencies();
+    fn test_debug_grpc_requires_admin_toggle() {
+        let mut deps = mock_dependencies();
         let api = MockApi::default();
-        let admin_addr = api.addr_make("admin");
-        
-        let mut deps = deps;
         let env = mock_env();
         let info = MessageInfo {
             sender: Addr::unchecked("creator"),
@@ -646,44 +8496,225 @@ mod tests {
         };
         instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
 
+        let debug = QueryMsg::DebugGrpc {
+            path: proto::MODULE_VERSIONS_PATH.to_string(),
+            data: Binary::default(),
+        };
+        assert!(query(deps.as_ref(), env.clone(), debug.clone()).is_err());
+
+        let buyer = MessageInfo {
+            sender: api.addr_make("buyer"),
+            funds: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), buyer, ExecuteMsg::SetDebugGrpc { enabled: true }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetDebugGrpc { enabled: true }).unwrap();
+
+        // The mock querier cannot answer gRPC, so the failure is reported instead of raised
+        let res: DebugGrpcResponse = from_json(query(deps.as_ref(), env, debug).unwrap()).unwrap();
+        assert!(res.response.is_none());
+        assert!(res.error.is_some());
+        assert!(res.decode_attempts.is_empty());
+    }
+
+    #[test]
+    fn test_export_state_pages_through_sections() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let env = mock_env();
         let info = MessageInfo {
-            sender: admin_addr,
+            sender: Addr::unchecked("creator"),
             funds: vec![],
         };
-        execute(
-            deps.as_mut(),
-            env.clone(),
-            info,
-            ExecuteMsg::UpdatePrice { price_usd: Uint128::from(50000u128) },
+        instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
+
+        let export = |section: StateSection, start_after: Option<String>| QueryMsg::ExportState {
+            section,
+            start_after,
+            limit: Some(2),
+        };
+        assert!(query(deps.as_ref(), env.clone(), export(StateSection::Config, None)).is_err());
+
+        let buyer = MessageInfo {
+            sender: api.addr_make("buyer"),
+            funds: vec![],
+        };
+        let err = execute(deps.as_mut(), env.clone(), buyer, ExecuteMsg::SetStateExport { enabled: true }).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), admin, ExecuteMsg::SetStateExport { enabled: true }).unwrap();
+
+        let entry = WhitelistEntry {
+            allocation_usd: Uint128::new(1_000),
+            purchased_usd: Uint128::zero(),
+        };
+        for address in ["a", "b", "c"] {
+            WHITELIST.save(deps.as_mut().storage, address, &entry).unwrap();
+        }
+        for (buyer, index) in [("a", 0), ("a", 1), ("b", 0)] {
+            VESTING_CONTRACTS
+                .save(deps.as_mut().storage, (buyer, index), &format!("vesting-{buyer}-{index}"))
+                .unwrap();
+        }
+
+        // Items come first, then maps; the config section starts with the stored config
+        let res: ExportStateResponse =
+            from_json(query(deps.as_ref(), env.clone(), export(StateSection::Config, None)).unwrap()).unwrap();
+        let keys: Vec<_> = res.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["config", "contract_version"]);
+        let config: Config = from_json(&res.entries[0].value).unwrap();
+        assert!(config.state_export_enabled);
+        assert_eq!(res.next_start_after.as_deref(), Some("contract_version"));
+
+        let res: ExportStateResponse =
+            from_json(query(deps.as_ref(), env.clone(), export(StateSection::Whitelist, None)).unwrap()).unwrap();
+        let keys: Vec<_> = res.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["whitelist/a", "whitelist/b"]);
+        let exported: WhitelistEntry = from_json(&res.entries[0].value).unwrap();
+        assert_eq!(exported, entry);
+        let res: ExportStateResponse =
+            from_json(query(deps.as_ref(), env.clone(), export(StateSection::Whitelist, res.next_start_after)).unwrap())
+                .unwrap();
+        let keys: Vec<_> = res.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["whitelist/c"]);
+        assert_eq!(res.next_start_after, None);
+
+        let res: ExportStateResponse = from_json(
+            query(deps.as_ref(), env.clone(), export(StateSection::Vesting, Some("vesting_contracts/a/0".to_string())))
+                .unwrap(),
         )
         .unwrap();
+        let keys: Vec<_> = res.entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, ["vesting_contracts/a/1", "vesting_contracts/b/0"]);
 
-        let config: ConfigResponse =
-            from_json(&query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
-        assert_eq!(config.price_usd, Uint128::from(50000u128));
+        // A cursor from another section is rejected rather than silently restarting
+        let cursor = Some("whitelist/a".to_string());
+        assert!(query(deps.as_ref(), env, export(StateSection::Vesting, cursor)).is_err());
     }
 
     #[test]
-    fn test_calculate_tokens() {
-        let deps = mock_dependencies();
+    fn test_export_sections_cover_every_store() {
+        let sections = [
+            StateSection::Config,
+            StateSection::Whitelist,
+            StateSection::Purchases,
+            StateSection::Reservations,
+            StateSection::Vesting,
+            StateSection::Sales,
+            StateSection::Pools,
+            StateSection::Lottery,
+            StateSection::Subscriptions,
+            StateSection::Quotes,
+            StateSection::Commitments,
+            StateSection::Timelock,
+            StateSection::History,
+            StateSection::Proceeds,
+        ];
+        let exported: Vec<&str> = sections.iter().flat_map(|section| crate::export::section_stores(section).iter().copied()).collect();
+
+        // Every Item and Map namespace declared in state.rs must be in a section
+        let source = include_str!("state.rs");
+        let mut stores = 0;
+        for marker in ["Item::new(\"", "Map::new(\""] {
+            for (at, _) in source.match_indices(marker) {
+                let rest = &source[at + marker.len()..];
+                let store = &rest[..rest.find('"').unwrap()];
+                assert!(exported.contains(&store), "store {store} is not exported by any section");
+                stores += 1;
+            }
+        }
+        assert!(stores > 50);
+
+        // And every listed store can be read, with composite keys paging through their cursor
+        let mut deps = mock_dependencies();
+        SALE_BUYERS.save(deps.as_mut().storage, (1, "a"), &()).unwrap();
+        SALE_BUYERS.save(deps.as_mut().storage, (1, "b"), &()).unwrap();
+        DEPOSITS.save(deps.as_mut().storage, ("a", "ibc/27394FB"), &Uint128::one()).unwrap();
+        DEPOSITS.save(deps.as_mut().storage, ("a", "ngonka"), &Uint128::one()).unwrap();
+        let commitment = PurchaseCommitment {
+            cw20_contract: "wusdt".to_string(),
+            price: Uint128::one(),
+            committed_at_height: 1,
+            deposit: Uint128::zero(),
+            expires_at_height: None,
+        };
+        PURCHASE_COMMITMENTS.save(deps.as_mut().storage, ("a", &[0xab, 0x01]), &commitment).unwrap();
+        PURCHASE_COMMITMENTS.save(deps.as_mut().storage, ("a", &[0xcd]), &commitment).unwrap();
+        for section in sections {
+            let mut keys = vec![];
+            let mut start_after = None;
+            loop {
+                let page = crate::export::export_state(&deps.storage, section.clone(), start_after, Some(1)).unwrap();
+                keys.extend(page.entries.into_iter().map(|entry| entry.key));
+                match page.next_start_after {
+                    Some(next) => start_after = Some(next),
+                    None => break,
+                }
+            }
+            match section {
+                StateSection::Sales => assert_eq!(keys, ["sale_buyers/1/a", "sale_buyers/1/b"]),
+                StateSection::Reservations => assert_eq!(keys, ["deposits/a/ibc/27394FB", "deposits/a/ngonka"]),
+                StateSection::Commitments => {
+                    assert_eq!(keys, ["purchase_commitments/a/ab01", "purchase_commitments/a/cd"])
+                }
+                _ => assert!(keys.is_empty()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_audit_log_records_admin_actions() {
+        let mut deps = mock_dependencies();
         let api = MockApi::default();
-        
-        let mut deps = deps;
-        let env = mock_env();
+        let mut env = mock_env();
         let info = MessageInfo {
             sender: Addr::unchecked("creator"),
             funds: vec![],
         };
         instantiate(deps.as_mut(), env.clone(), info, mock_instantiate_msg(&api)).unwrap();
 
-        let usd_amount = Uint128::from(100_000_000u128); // $100
-        let response: TokenCalculationResponse = from_json(
-            &query(deps.as_ref(), env, QueryMsg::CalculateTokens { usd_amount }).unwrap(),
+        let admin = MessageInfo {
+            sender: api.addr_make("admin"),
+            funds: vec![],
+        };
+        execute(deps.as_mut(), env.clone(), admin.clone(), ExecuteMsg::Pause {}).unwrap();
+        env.block.height += 1;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            admin.clone(),
+            ExecuteMsg::UpdatePrice { price_usd: Uint128::from(50000u128) },
         )
         .unwrap();
 
-        assert_eq!(response.tokens, Uint128::from(4_000_000_000_000u128));
-        assert_eq!(response.price_usd, Uint128::from(25000u128));
+        let log: AuditLogResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::AuditLog { start_after: None, limit: None }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(log.entries.len(), 2);
+        assert_eq!(log.entries[0].action, "pause");
+        assert_eq!(log.entries[0].actor, admin.sender.to_string());
+        assert_eq!(log.entries[1].action, "update_price");
+        assert_eq!(log.entries[1].height, env.block.height);
+        assert_eq!(log.entries[1].old_value.as_deref(), Some("25000"));
+        assert_eq!(log.entries[1].new_value.as_deref(), Some("50000"));
+        assert_ne!(log.entries[0].hash, log.entries[1].hash);
+
+        let page: AuditLogResponse = from_json(
+            query(deps.as_ref(), env, QueryMsg::AuditLog { start_after: Some(0), limit: None }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].id, 1);
+        assert_eq!(page.next_start_after, None);
     }
 
     #[test]
@@ -714,4 +8745,53 @@ mod tests {
         .unwrap_err();
         assert!(matches!(err, ContractError::Unauthorized {}));
     }
+
+    #[test]
+    fn test_error_codes_are_structured() {
+        let errors = vec![
+            ContractError::Std(StdError::msg("boom")),
+            ContractError::Unauthorized {},
+            ContractError::ContractPaused {},
+            ContractError::InvalidToken { token: "x".to_string() },
+            ContractError::ZeroAmount {},
+            ContractError::InsufficientBalance { available: 1, needed: 2 },
+            ContractError::TokenNotAccepted { token: "x".to_string() },
+            ContractError::BuyerNotAllowed { buyer: "x".to_string() },
+            ContractError::WrongToken {
+                expected_chain: "a".to_string(),
+                expected_contract: "b".to_string(),
+                got_chain: "c".to_string(),
+                got_contract: "d".to_string(),
+            },
+            ContractError::PayoutDenomNotAccepted { denom: "x".to_string() },
+            ContractError::InvalidVestingConfig { reason: "x".to_string() },
+            ContractError::PurchaseOutOfLimits { amount: 1, min: 2, max: 3 },
+            ContractError::PriceOutOfBounds { price: 1, min: 2, max: 3 },
+            ContractError::DuplicateWhitelistEntry { address: "x".to_string() },
+            ContractError::WhitelistBatchOutOfOrder { expected: 1, got: 2 },
+            ContractError::WhitelistBatchTooLarge { size: 1, max: 2 },
+            ContractError::AllocationExceeded { buyer: "x".to_string(), remaining: 1 },
+            ContractError::QueuedActionNotFound { id: 1 },
+            ContractError::TimelockRequired { action: "x".to_string() },
+            ContractError::InvalidTimelockDelay { blocks: 1, min: 2 },
+            ContractError::CommitmentExists {},
+            ContractError::InvalidCommitmentDeposit { amount: 1, denom: "x".to_string() },
+        ];
+        let mut codes: Vec<u32> = errors.iter().map(|e| e.code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+
+        let err = StructuredError::from(ContractError::InsufficientBalance { available: 1, needed: 2 });
+        #[derive(serde::Deserialize)]
+        struct Probe {
+            code: u32,
+            message: String,
+        }
+        let text = err.to_string();
+        let parsed: Probe = from_json(text.as_bytes()).unwrap();
+        assert_eq!(parsed.code, 6);
+        assert_eq!(parsed.message, "Insufficient contract balance: 1, needed: 2");
+        assert!(text.contains(r#""insufficient_balance":{"available":1,"needed":2}"#));
+    }
 }
@@ -0,0 +1,34 @@
+//! Wasm entry points. Each runs its handler in `contract` and returns the error as a
+//! `StructuredError`, so the chain reports the error code and fields with the message.
+//! Tests and library users call the handlers directly and match on `ContractError`.
+
+use cosmwasm_std::{entry_point, Binary, DepsMut, Env, MessageInfo, Reply, Response};
+
+use crate::contract;
+use crate::error::StructuredError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, SudoMsg};
+
+#[entry_point]
+pub fn instantiate(deps: DepsMut, env: Env, info: MessageInfo, msg: InstantiateMsg) -> Result<Response, StructuredError> {
+    Ok(contract::instantiate(deps, env, info, msg)?)
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, StructuredError> {
+    Ok(contract::execute(deps, env, info, msg)?)
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, StructuredError> {
+    Ok(contract::reply(deps, env, msg)?)
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, StructuredError> {
+    Ok(contract::sudo(deps, env, msg)?)
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, msg: Binary) -> Result<Response, StructuredError> {
+    Ok(contract::migrate(deps, env, msg)?)
+}
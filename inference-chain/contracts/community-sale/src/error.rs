@@ -1,10 +1,17 @@
-use cosmwasm_std::StdError;
+use std::fmt;
+
+use cosmwasm_std::{to_json_string, StdError};
+use serde::{Serialize, Serializer};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+/// Contract errors. Every variant has a stable numeric `code()`; codes are never reused or
+/// renumbered. The entry points return them as a `StructuredError`.
+// StdError has no std::error::Error impl on cosmwasm-std 3, so it cannot be a `#[from]` source
+#[derive(Error, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ContractError {
     #[error("{0}")]
-    Std(#[from] StdError),
+    Std(#[serde(serialize_with = "serialize_display")] StdError),
 
     #[error("Unauthorized")]
     Unauthorized {},
@@ -24,6 +31,198 @@ pub enum ContractError {
     #[error("Token not accepted: {token}")]
     TokenNotAccepted { token: String },
 
+    #[error("Payout denom not accepted: {denom}")]
+    PayoutDenomNotAccepted { denom: String },
+
+    #[error("Invalid vesting config: {reason}")]
+    InvalidVestingConfig { reason: String },
+
+    #[error("Purchase of {amount} micro-USD outside limits [{min}, {max}] (0 = unbounded)")]
+    PurchaseOutOfLimits { amount: u128, min: u128, max: u128 },
+
+    #[error("Price {price} outside bounds [{min}, {max}] (0 = unbounded)")]
+    PriceOutOfBounds { price: u128, min: u128, max: u128 },
+
+    #[error("Duplicate whitelist entry: {address}")]
+    DuplicateWhitelistEntry { address: String },
+
+    #[error("Whitelist batch {got} out of order, expected {expected}")]
+    WhitelistBatchOutOfOrder { expected: u64, got: u64 },
+
+    #[error("Whitelist batch too large: {size} entries, max {max}")]
+    WhitelistBatchTooLarge { size: usize, max: usize },
+
+    #[error("Allocation exceeded for {buyer}: remaining {remaining} micro-USD")]
+    AllocationExceeded { buyer: String, remaining: u128 },
+
+    #[error("Queued action not found: {id}")]
+    QueuedActionNotFound { id: u64 },
+
+    #[error("{action} is timelocked: queue it with QueueAction")]
+    TimelockRequired { action: String },
+
+    #[error("Timelock delay of {blocks} blocks is below the minimum of {min}")]
+    InvalidTimelockDelay { blocks: u64, min: u64 },
+
+    #[error("Commit-reveal purchases are disabled")]
+    CommitRevealDisabled {},
+
+    #[error("No matching purchase commitment")]
+    CommitmentNotFound {},
+
+    #[error("Purchase commitment already exists")]
+    CommitmentExists {},
+
+    #[error("A purchase commitment takes a deposit of exactly {amount}{denom}")]
+    InvalidCommitmentDeposit { amount: u128, denom: String },
+
+    #[error("Reveal must happen after the commit block {committed_at_height}")]
+    RevealTooEarly { committed_at_height: u64 },
+
+    #[error("Purchase commitment expired at height {expired_at_height}")]
+    CommitmentExpired { expired_at_height: u64 },
+
+    #[error("Chain module {module} is at version {actual:?}, contract expects {expected}")]
+    ModuleVersionMismatch { module: String, expected: u64, actual: Option<u64> },
+
+    #[error("No valid participant attestation for {buyer}")]
+    AttestationMissing { buyer: String },
+
+    #[error("Purchase requires {required} micro-USD, above max_usd_in {max_usd_in}")]
+    MaxInputExceeded { required: u128, max_usd_in: u128 },
+
+    #[error("Sent {sent} micro-USD, purchase requires {required}")]
+    InsufficientPayment { sent: u128, required: u128 },
+
+    #[error("Kickback of {bps} bps above maximum {max}")]
+    InvalidKickbackBps { bps: u16, max: u16 },
+
+    #[error("Price {price} below governance floor {floor}")]
+    PriceBelowFloor { price: u128, floor: u128 },
+
+    #[error("A lottery is already running")]
+    LotteryActive {},
+
+    #[error("No lottery in this phase")]
+    LotteryNotActive {},
+
+    #[error("Lottery commitments closed at height {commit_end_height}")]
+    LotteryClosed { commit_end_height: u64 },
+
+    #[error("Lottery accepts commitments until height {commit_end_height}")]
+    LotteryStillOpen { commit_end_height: u64 },
+
+    #[error("Lottery is full: max {max} entrants")]
+    LotteryFull { max: u32 },
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Migration backfill has not completed")]
+    MigrationInProgress {},
+
+    #[error("No migration step pending")]
+    NoMigrationPending {},
+
+    #[error("Invalid payout decimals {decimals}: max {max}")]
+    InvalidPayoutDecimals { decimals: u8, max: u8 },
+
+    #[error("Purchase intent expired at height {deadline_height}")]
+    IntentExpired { deadline_height: u64 },
+
+    #[error("Invalid purchase intent: {reason}")]
+    InvalidIntent { reason: String },
+
+    #[error("Raise cap exceeded: remaining {remaining_usd} micro-USD, requested {requested_usd}")]
+    RaiseCapExceeded { remaining_usd: u128, requested_usd: u128 },
+
+    #[error("Invalid round: {reason}")]
+    InvalidRound { reason: String },
+
+    #[error("No sale round is active")]
+    NoActiveRound {},
+
+    #[error("Sale {id} not found")]
+    SaleNotFound { id: u64 },
+
+    #[error("Sale {id} is closed")]
+    SaleClosed { id: u64 },
+
+    #[error("Sale inventory exceeded: {remaining} remaining, {requested} requested")]
+    SaleInventoryExceeded { remaining: u128, requested: u128 },
+
+    #[error("Reservation {id} not found")]
+    ReservationNotFound { id: u64 },
+
+    #[error("Reservation expired at height {deadline_height}")]
+    ReservationExpired { deadline_height: u64 },
+
+    #[error("Invalid reservation: {reason}")]
+    InvalidReservation { reason: String },
+
+    #[error("Withdrawal exceeds unreserved balance: available {available}, requested {requested}")]
+    WithdrawalExceedsUnreserved { available: u128, requested: u128 },
+
+    #[error("Invalid external address: {address}")]
+    InvalidExternalAddress { address: String },
+
+    #[error("Quote {id} not found")]
+    QuoteNotFound { id: u64 },
+
+    #[error("Quote expired at height {expired_at_height}")]
+    QuoteExpired { expired_at_height: u64 },
+
+    #[error("Invalid circuit breaker: {reason}")]
+    InvalidCircuitBreaker { reason: String },
+
+    #[error("Subscription {id} not found")]
+    SubscriptionNotFound { id: u64 },
+
+    #[error("Invalid subscription: {reason}")]
+    InvalidSubscription { reason: String },
+
+    #[error("Insufficient allowance: {allowance}, required: {required}")]
+    InsufficientAllowance { allowance: u128, required: u128 },
+
+    #[error("Sale already finalized")]
+    SaleFinalized {},
+
+    #[error("Invalid accounting correction: {reason}")]
+    InvalidAccountingCorrection { reason: String },
+
+    #[error("Pool {label} not found")]
+    PoolNotFound { label: String },
+
+    #[error("Invalid pool: {reason}")]
+    InvalidPool { reason: String },
+
+    #[error("Pool capacity exceeded: {remaining} remaining, {requested} requested")]
+    PoolCapacityExceeded { remaining: u128, requested: u128 },
+
+    #[error("{reason}")]
+    AccessControl { reason: String },
+
+    #[error("Unsupported payload version {version}: this contract accepts up to {max}")]
+    UnsupportedPayloadVersion { version: u32, max: u32 },
+
+    #[error("Purchase of {usd_amount} exceeds the co-approval threshold {threshold_usd} and is not approved")]
+    PurchaseApprovalRequired { usd_amount: u128, threshold_usd: u128 },
+
+    #[error("Purchase of {requested_usd} exceeds the approved {approved_usd}")]
+    PurchaseApprovalExceeded { approved_usd: u128, requested_usd: u128 },
+
+    #[error("Invalid co-approval: {reason}")]
+    InvalidCoApproval { reason: String },
+
+    #[error("Epoch {epoch} sale cap exceeded: {remaining_usd} remaining, {requested_usd} requested")]
+    EpochCapExceeded { epoch: u64, remaining_usd: u128, requested_usd: u128 },
+
+    #[error("Invalid epoch schedule: {reason}")]
+    InvalidEpochSchedule { reason: String },
+
+    #[error("Invalid proceeds stream: {reason}")]
+    InvalidProceedsStream { reason: String },
+
     #[error("Buyer not allowed: {buyer}")]
     BuyerNotAllowed { buyer: String },
 
@@ -35,3 +234,136 @@ pub enum ContractError {
         got_contract: String,
     },
 }
+
+impl ContractError {
+    /// Stable numeric error code
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::Std(_) => 1,
+            ContractError::Unauthorized {} => 2,
+            ContractError::ContractPaused {} => 3,
+            ContractError::InvalidToken { .. } => 4,
+            ContractError::ZeroAmount {} => 5,
+            ContractError::InsufficientBalance { .. } => 6,
+            ContractError::TokenNotAccepted { .. } => 7,
+            ContractError::BuyerNotAllowed { .. } => 8,
+            ContractError::WrongToken { .. } => 9,
+            ContractError::PayoutDenomNotAccepted { .. } => 10,
+            ContractError::InvalidVestingConfig { .. } => 11,
+            ContractError::PurchaseOutOfLimits { .. } => 12,
+            ContractError::PriceOutOfBounds { .. } => 13,
+            ContractError::DuplicateWhitelistEntry { .. } => 14,
+            ContractError::WhitelistBatchOutOfOrder { .. } => 15,
+            ContractError::WhitelistBatchTooLarge { .. } => 16,
+            ContractError::AllocationExceeded { .. } => 17,
+            ContractError::QueuedActionNotFound { .. } => 18,
+            ContractError::CommitRevealDisabled {} => 19,
+            ContractError::CommitmentNotFound {} => 20,
+            ContractError::RevealTooEarly { .. } => 21,
+            ContractError::CommitmentExpired { .. } => 22,
+            ContractError::ModuleVersionMismatch { .. } => 23,
+            ContractError::AttestationMissing { .. } => 24,
+            ContractError::MaxInputExceeded { .. } => 25,
+            ContractError::InsufficientPayment { .. } => 26,
+            ContractError::InvalidKickbackBps { .. } => 27,
+            ContractError::PriceBelowFloor { .. } => 28,
+            ContractError::LotteryActive {} => 29,
+            ContractError::LotteryNotActive {} => 30,
+            ContractError::LotteryClosed { .. } => 31,
+            ContractError::LotteryStillOpen { .. } => 32,
+            ContractError::LotteryFull { .. } => 33,
+            ContractError::NothingToClaim {} => 34,
+            ContractError::MigrationInProgress {} => 35,
+            ContractError::NoMigrationPending {} => 36,
+            ContractError::InvalidPayoutDecimals { .. } => 37,
+            ContractError::IntentExpired { .. } => 38,
+            ContractError::InvalidIntent { .. } => 39,
+            ContractError::RaiseCapExceeded { .. } => 40,
+            ContractError::InvalidRound { .. } => 41,
+            ContractError::NoActiveRound {} => 42,
+            ContractError::SaleNotFound { .. } => 43,
+            ContractError::SaleClosed { .. } => 44,
+            ContractError::SaleInventoryExceeded { .. } => 45,
+            ContractError::ReservationNotFound { .. } => 46,
+            ContractError::ReservationExpired { .. } => 47,
+            ContractError::InvalidReservation { .. } => 48,
+            ContractError::WithdrawalExceedsUnreserved { .. } => 49,
+            ContractError::InvalidExternalAddress { .. } => 50,
+            ContractError::QuoteNotFound { .. } => 51,
+            ContractError::QuoteExpired { .. } => 52,
+            ContractError::InvalidCircuitBreaker { .. } => 53,
+            ContractError::SubscriptionNotFound { .. } => 54,
+            ContractError::InvalidSubscription { .. } => 55,
+            ContractError::InsufficientAllowance { .. } => 56,
+            ContractError::SaleFinalized {} => 57,
+            ContractError::InvalidAccountingCorrection { .. } => 58,
+            ContractError::PoolNotFound { .. } => 59,
+            ContractError::InvalidPool { .. } => 60,
+            ContractError::PoolCapacityExceeded { .. } => 61,
+            ContractError::AccessControl { .. } => 62,
+            ContractError::UnsupportedPayloadVersion { .. } => 63,
+            ContractError::PurchaseApprovalRequired { .. } => 64,
+            ContractError::PurchaseApprovalExceeded { .. } => 65,
+            ContractError::InvalidCoApproval { .. } => 66,
+            ContractError::EpochCapExceeded { .. } => 67,
+            ContractError::InvalidEpochSchedule { .. } => 68,
+            ContractError::InvalidProceedsStream { .. } => 69,
+            ContractError::TimelockRequired { .. } => 70,
+            ContractError::InvalidTimelockDelay { .. } => 71,
+            ContractError::CommitmentExists {} => 72,
+            ContractError::InvalidCommitmentDeposit { .. } => 73,
+        }
+    }
+}
+
+fn serialize_display<S: Serializer>(err: &StdError, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(err)
+}
+
+/// The error the entry points return. Its string is
+/// `{"code":N,"message":"...","error":{"<variant>":{<fields>}}}`, so frontends can map
+/// failures to their own messages without parsing the text.
+#[derive(Debug, Serialize)]
+pub struct StructuredError {
+    pub code: u32,
+    pub message: String,
+    pub error: Box<ContractError>,
+}
+
+impl From<ContractError> for StructuredError {
+    fn from(error: ContractError) -> Self {
+        StructuredError { code: error.code(), message: error.to_string(), error: Box::new(error) }
+    }
+}
+
+impl fmt::Display for StructuredError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match to_json_string(self) {
+            Ok(json) => f.write_str(&json),
+            Err(_) => write!(f, "[{}] {}", self.code, self.message),
+        }
+    }
+}
+
+impl From<StdError> for ContractError {
+    fn from(e: StdError) -> Self {
+        ContractError::Std(e)
+    }
+}
+
+impl From<gonka_cw_controllers::ControllerError> for ContractError {
+    fn from(err: gonka_cw_controllers::ControllerError) -> Self {
+        use gonka_cw_controllers::ControllerError;
+        match err {
+            ControllerError::Unauthorized {} => ContractError::Unauthorized {},
+            ControllerError::Paused {} => ContractError::ContractPaused {},
+            err => ContractError::AccessControl { reason: err.to_string() },
+        }
+    }
+}
+
+impl From<external_address::InvalidExternalAddress> for ContractError {
+    fn from(err: external_address::InvalidExternalAddress) -> Self {
+        ContractError::InvalidExternalAddress { address: err.address }
+    }
+}
@@ -0,0 +1,256 @@
+//! Section-by-section export of contract state for rebuilding a sale after an emergency
+//! redeployment.
+//!
+//! A section is a fixed list of stores exported in order. Every entry is keyed
+//! `<store>/<key>` (just `<store>` for single items) with the stored value as JSON, so a page
+//! is deterministic for a given state and the last key is the cursor for the next page.
+
+use std::str::FromStr;
+
+use cosmwasm_std::{to_json_binary, HexBinary, Order, StdError, StdResult, Storage};
+use cw_storage_plus::{Bound, KeyDeserialize, Map, PrimaryKey};
+use serde::{de::DeserializeOwned, Serialize};
+use state_export::{read_chunk, Cursor};
+
+use crate::msg::{ExportStateResponse, StateEntry, StateSection};
+use crate::pagination::page_limit;
+use crate::state::{
+    AUDIT_LOG, BUYER_PRICES, BUYER_SUBSCRIPTIONS, CONFIG, DEPOSITS, EPOCH_RAISED, EPOCH_SCHEDULE, FINAL_REPORT,
+    LAST_PURCHASE_HEIGHT, LOTTERY, LOTTERY_ENTRIES, MIGRATION, NAMES_RESOLVED_AT, NEXT_AUDIT_ID, NEXT_PRICE_CHANGE_ID,
+    NEXT_QUOTE_ID, NEXT_RECEIPT_ID, NEXT_RESERVATION_ID, NEXT_ROUND_ID, NEXT_SALE_ID, NEXT_SUBSCRIPTION_ID,
+    NEXT_TIMELOCK_ID, POOLS, POOL_BUYERS, POOL_CURSOR, PRICE_HISTORY, PURCHASERS, PURCHASE_APPROVALS,
+    PURCHASE_COMMITMENTS, PURCHASE_COMMITMENT_EXPIRIES, PURCHASE_NONCES, QUOTES, QUOTE_EXPIRIES, RAISED_BY_TOKEN,
+    REFUNDED_USD, RESERVATIONS, RESERVATION_EXPIRIES, RESERVES, ROUNDS, SALES, SALE_BUYERS, STREAMED_PROCEEDS, SUBSCRIPTIONS,
+    SUBSCRIPTION_DUE, TIMELOCK_ETA, TIMELOCK_QUEUE, TOKENS_SOLD_BY_DENOM, TOKEN_PRICES, UNBRIDGED_PROCEEDS,
+    UNIQUE_BUYERS, VESTING_CONTRACTS, VESTING_UNLOCKS, WHITELIST, WHITELIST_IMPORT, WINDOW_VOLUME,
+};
+
+/// Stores of each section, in export order. Together the sections cover every store in
+/// `state.rs`, so a redeployment can be rebuilt from them.
+pub(crate) fn section_stores(section: &StateSection) -> &'static [&'static str] {
+    match section {
+        StateSection::Config => &[
+            "config",
+            "contract_version",
+            "names_resolved_at",
+            "migration",
+            "final_report",
+            "next_round_id",
+            "token_prices",
+            "buyer_prices",
+            "rounds",
+            "epoch_schedule",
+        ],
+        StateSection::Whitelist => &["whitelist_import", "whitelist"],
+        StateSection::Purchases => &[
+            "unique_buyers",
+            "refunded_usd",
+            "last_purchase_height",
+            "next_receipt_id",
+            "epoch_raised",
+            "raised_by_token",
+            "tokens_sold_by_denom",
+            "purchasers",
+            "purchase_nonces",
+            "purchase_approvals",
+            "window_volume",
+        ],
+        StateSection::Reservations => &["next_reservation_id", "reserves", "reservations", "reservation_expiries", "deposits"],
+        StateSection::Vesting => &["vesting_contracts", "vesting_unlocks"],
+        StateSection::Sales => &["next_sale_id", "sales", "sale_buyers"],
+        StateSection::Pools => &["pool_cursor", "pools", "pool_buyers"],
+        StateSection::Lottery => &["lottery", "lottery_entries"],
+        StateSection::Subscriptions => &["next_subscription_id", "subscriptions", "buyer_subscriptions", "subscription_due"],
+        StateSection::Quotes => &["next_quote_id", "quotes", "quote_expiries"],
+        StateSection::Commitments => &["purchase_commitments", "purchase_commitment_expiries"],
+        StateSection::Timelock => &["next_timelock_id", "timelock_queue", "timelock_eta"],
+        StateSection::History => &["next_audit_id", "audit_log", "next_price_change_id", "price_history"],
+        StateSection::Proceeds => &["unbridged_proceeds", "streamed_proceeds"],
+    }
+}
+
+pub fn export_state(
+    storage: &dyn Storage,
+    section: StateSection,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ExportStateResponse> {
+    let stores = section_stores(&section);
+    let cursor = Cursor::parse(stores, start_after.as_deref()).map_err(StdError::msg)?;
+    let chunk = read_chunk(
+        stores,
+        cursor,
+        page_limit(limit),
+        |store, start, limit| export_store(storage, store, start, limit),
+        |entry: &StateEntry| entry.key.as_str(),
+    )?;
+    Ok(ExportStateResponse {
+        section,
+        entries: chunk.entries,
+        next_start_after: chunk.next_start_after,
+    })
+}
+
+fn export_store(storage: &dyn Storage, store: &str, start: Option<&str>, limit: usize) -> StdResult<Vec<StateEntry>> {
+    match store {
+        "config" => item_entry(store, CONFIG.may_load(storage)?),
+        "contract_version" => item_entry(store, cw2::CONTRACT.may_load(storage)?),
+        "names_resolved_at" => item_entry(store, NAMES_RESOLVED_AT.may_load(storage)?),
+        "migration" => item_entry(store, MIGRATION.may_load(storage)?),
+        "final_report" => item_entry(store, FINAL_REPORT.may_load(storage)?),
+        "whitelist_import" => item_entry(store, WHITELIST_IMPORT.may_load(storage)?),
+        "unique_buyers" => item_entry(store, UNIQUE_BUYERS.may_load(storage)?),
+        "refunded_usd" => item_entry(store, REFUNDED_USD.may_load(storage)?),
+        "last_purchase_height" => item_entry(store, LAST_PURCHASE_HEIGHT.may_load(storage)?),
+        "next_reservation_id" => item_entry(store, NEXT_RESERVATION_ID.may_load(storage)?),
+        "next_round_id" => item_entry(store, NEXT_ROUND_ID.may_load(storage)?),
+        "next_receipt_id" => item_entry(store, NEXT_RECEIPT_ID.may_load(storage)?),
+        "next_sale_id" => item_entry(store, NEXT_SALE_ID.may_load(storage)?),
+        "pool_cursor" => item_entry(store, POOL_CURSOR.may_load(storage)?),
+        "lottery" => item_entry(store, LOTTERY.may_load(storage)?),
+        "next_subscription_id" => item_entry(store, NEXT_SUBSCRIPTION_ID.may_load(storage)?),
+        "next_quote_id" => item_entry(store, NEXT_QUOTE_ID.may_load(storage)?),
+        "next_timelock_id" => item_entry(store, NEXT_TIMELOCK_ID.may_load(storage)?),
+        "next_audit_id" => item_entry(store, NEXT_AUDIT_ID.may_load(storage)?),
+        "next_price_change_id" => item_entry(store, NEXT_PRICE_CHANGE_ID.may_load(storage)?),
+        "token_prices" => map_entries(store, TOKEN_PRICES, storage, start, limit, |key| key),
+        "buyer_prices" => map_entries(store, BUYER_PRICES, storage, start, limit, |key| key),
+        "rounds" => map_entries(store, ROUNDS, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "epoch_schedule" => map_entries(store, EPOCH_SCHEDULE, storage, parse_key(start)?, limit, |epoch| epoch.to_string()),
+        "whitelist" => map_entries(store, WHITELIST, storage, start, limit, |key| key),
+        "epoch_raised" => map_entries(store, EPOCH_RAISED, storage, parse_key(start)?, limit, |epoch| epoch.to_string()),
+        "raised_by_token" => map_entries(store, RAISED_BY_TOKEN, storage, start, limit, |key| key),
+        "tokens_sold_by_denom" => map_entries(store, TOKENS_SOLD_BY_DENOM, storage, start, limit, |key| key),
+        "purchasers" => map_entries(store, PURCHASERS, storage, start, limit, |key| key),
+        "purchase_nonces" => map_entries(store, PURCHASE_NONCES, storage, start, limit, |key| key),
+        "reserves" => map_entries(store, RESERVES, storage, start, limit, |key| key),
+        "reservations" => map_entries(store, RESERVATIONS, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "reservation_expiries" => map_entries(store, RESERVATION_EXPIRIES, storage, u64_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        "purchase_approvals" => map_entries(store, PURCHASE_APPROVALS, storage, start, limit, |key| key),
+        "window_volume" => map_entries(store, WINDOW_VOLUME, storage, parse_key(start)?, limit, |window| window.to_string()),
+        // Keyed `<depositor>/<denom>`; the denom may itself contain '/'
+        "deposits" => map_entries(store, DEPOSITS, storage, str_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        "sales" => map_entries(store, SALES, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "sale_buyers" => map_entries(store, SALE_BUYERS, storage, id_and_str(start)?, limit, |(id, buyer)| format!("{id}/{buyer}")),
+        "pools" => map_entries(store, POOLS, storage, start, limit, |key| key),
+        "pool_buyers" => map_entries(store, POOL_BUYERS, storage, str_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        "lottery_entries" => map_entries(store, LOTTERY_ENTRIES, storage, start, limit, |key| key),
+        "subscriptions" => map_entries(store, SUBSCRIPTIONS, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "buyer_subscriptions" => map_entries(store, BUYER_SUBSCRIPTIONS, storage, start, limit, |key| key),
+        "subscription_due" => map_entries(store, SUBSCRIPTION_DUE, storage, u64_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        "quotes" => map_entries(store, QUOTES, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "quote_expiries" => map_entries(store, QUOTE_EXPIRIES, storage, u64_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        // Keyed `<buyer>/<hex commitment>`
+        "purchase_commitments" => {
+            let start = start.map(|key| key_parts::<2>(key)).transpose()?;
+            let start = start.map(|[buyer, hash]| Ok::<_, StdError>((buyer, HexBinary::from_hex(hash)?))).transpose()?;
+            let start = start.as_ref().map(|(buyer, hash)| (*buyer, hash.as_slice()));
+            map_entries(store, PURCHASE_COMMITMENTS, storage, start, limit, |(buyer, hash)| {
+                format!("{buyer}/{}", HexBinary::from(hash).to_hex())
+            })
+        }
+        // Keyed `<expiry height>/<buyer>/<hex commitment>`
+        "purchase_commitment_expiries" => {
+            let start = start.map(|key| key_parts::<3>(key)).transpose()?;
+            let start = start
+                .map(|[height, buyer, hash]| Ok::<_, StdError>((parse_index::<u64>(height)?, buyer, HexBinary::from_hex(hash)?)))
+                .transpose()?;
+            let start = start.as_ref().map(|(height, buyer, hash)| (*height, *buyer, hash.as_slice()));
+            map_entries(store, PURCHASE_COMMITMENT_EXPIRIES, storage, start, limit, |(height, buyer, hash)| {
+                format!("{height}/{buyer}/{}", HexBinary::from(hash).to_hex())
+            })
+        }
+        "timelock_queue" => map_entries(store, TIMELOCK_QUEUE, storage, u64_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        "timelock_eta" => map_entries(store, TIMELOCK_ETA, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "audit_log" => map_entries(store, AUDIT_LOG, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "price_history" => map_entries(store, PRICE_HISTORY, storage, parse_key(start)?, limit, |id| id.to_string()),
+        "unbridged_proceeds" => map_entries(store, UNBRIDGED_PROCEEDS, storage, start, limit, |key| key),
+        "streamed_proceeds" => map_entries(store, STREAMED_PROCEEDS, storage, str_pair(start)?, limit, |(a, b)| format!("{a}/{b}")),
+        "vesting_contracts" => {
+            // Keyed `<buyer>/<index>`; buyer addresses contain no '/'
+            let start = start
+                .map(|key| {
+                    let (buyer, index) = key
+                        .rsplit_once('/')
+                        .ok_or_else(|| StdError::msg(format!("invalid vesting cursor {key}")))?;
+                    Ok::<_, StdError>((buyer, parse_index::<u64>(index)?))
+                })
+                .transpose()?;
+            map_entries(store, VESTING_CONTRACTS, storage, start, limit, |(buyer, index)| format!("{buyer}/{index}"))
+        }
+        // Keyed `<unlock time>/<vesting contract>`
+        "vesting_unlocks" => map_entries(store, VESTING_UNLOCKS, storage, id_and_str(start)?, limit, |(time, addr)| format!("{time}/{addr}")),
+        _ => Err(StdError::msg(format!("unknown store {store}"))),
+    }
+}
+
+fn item_entry<T: Serialize>(store: &str, value: Option<T>) -> StdResult<Vec<StateEntry>> {
+    value
+        .map(|value| {
+            Ok(StateEntry {
+                key: store.to_string(),
+                value: to_json_binary(&value)?,
+            })
+        })
+        .into_iter()
+        .collect()
+}
+
+fn map_entries<'a, K, T>(
+    store: &str,
+    map: Map<K, T>,
+    storage: &dyn Storage,
+    start_after: Option<K>,
+    limit: usize,
+    format_key: impl Fn(K::Output) -> String,
+) -> StdResult<Vec<StateEntry>>
+where
+    K: PrimaryKey<'a> + KeyDeserialize,
+    K::Output: 'static,
+    T: Serialize + DeserializeOwned,
+{
+    map.range(storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (key, value) = item?;
+            Ok(StateEntry {
+                key: format!("{store}/{}", format_key(key)),
+                value: to_json_binary(&value)?,
+            })
+        })
+        .collect()
+}
+
+fn parse_key<N: FromStr>(key: Option<&str>) -> StdResult<Option<N>> {
+    key.map(parse_index).transpose()
+}
+
+fn parse_index<N: FromStr>(key: &str) -> StdResult<N> {
+    key.parse().map_err(|_| StdError::msg(format!("invalid numeric cursor {key}")))
+}
+
+/// Splits a composite key into its `N` parts; only the last part may contain '/'
+fn key_parts<const N: usize>(key: &str) -> StdResult<[&str; N]> {
+    let parts: Vec<&str> = key.splitn(N, '/').collect();
+    parts.try_into().map_err(|_| StdError::msg(format!("invalid composite cursor {key}")))
+}
+
+fn str_pair(key: Option<&str>) -> StdResult<Option<(&str, &str)>> {
+    key.map(|key| key_parts::<2>(key).map(|[a, b]| (a, b))).transpose()
+}
+
+fn id_and_str(key: Option<&str>) -> StdResult<Option<(u64, &str)>> {
+    key.map(|key| {
+        let [id, rest] = key_parts::<2>(key)?;
+        Ok((parse_index(id)?, rest))
+    })
+    .transpose()
+}
+
+fn u64_pair(key: Option<&str>) -> StdResult<Option<(u64, u64)>> {
+    key.map(|key| {
+        let [a, b] = key_parts::<2>(key)?;
+        Ok((parse_index(a)?, parse_index(b)?))
+    })
+    .transpose()
+}
@@ -1,6 +1,11 @@
 pub mod contract;
+pub mod entry;
 pub mod error;
+pub mod export;
+pub mod lottery;
 pub mod msg;
+pub mod pagination;
+pub mod proto;
 pub mod state;
 
 pub use crate::error::ContractError; 
\ No newline at end of file
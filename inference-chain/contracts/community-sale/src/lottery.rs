@@ -0,0 +1,73 @@
+//! Weighted winner selection for lottery sales.
+
+use cosmwasm_std::Uint128;
+use sha2::{Digest, Sha256};
+
+/// Draws winners without replacement, each weighted by their commitment, until `capacity` is
+/// filled. Returns the amount won per commitment, in input order; the last winner drawn may be
+/// filled partially. The same seed and commitments always produce the same result.
+pub fn select_winners(commitments: &[Uint128], capacity: Uint128, seed: &[u8]) -> Vec<Uint128> {
+    let mut won = vec![Uint128::zero(); commitments.len()];
+    let mut drawn = vec![false; commitments.len()];
+    let mut remaining_weight: u128 = commitments.iter().map(|c| c.u128()).sum();
+    let mut remaining_capacity = capacity;
+    let mut round: u64 = 0;
+
+    while !remaining_capacity.is_zero() && remaining_weight > 0 {
+        let target = draw(seed, round) % remaining_weight;
+        round += 1;
+        let mut cumulative = 0u128;
+        for (i, commitment) in commitments.iter().enumerate() {
+            if drawn[i] {
+                continue;
+            }
+            cumulative += commitment.u128();
+            if target < cumulative {
+                let amount = (*commitment).min(remaining_capacity);
+                won[i] = amount;
+                drawn[i] = true;
+                remaining_capacity -= amount;
+                remaining_weight -= commitment.u128();
+                break;
+            }
+        }
+    }
+    won
+}
+
+/// Uniform 128-bit draw for `round`, derived from the seed
+fn draw(seed: &[u8], round: u64) -> u128 {
+    let hash = Sha256::new().chain_update(seed).chain_update(round.to_be_bytes()).finalize();
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    u128::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amounts(values: &[u128]) -> Vec<Uint128> {
+        values.iter().map(|v| Uint128::from(*v)).collect()
+    }
+
+    #[test]
+    fn fills_capacity_deterministically() {
+        let commitments = amounts(&[100, 300, 50, 250]);
+        let capacity = Uint128::from(400u128);
+
+        let won = select_winners(&commitments, capacity, b"seed");
+        assert_eq!(won.iter().map(|w| w.u128()).sum::<u128>(), 400);
+        for (w, c) in won.iter().zip(&commitments) {
+            assert!(w <= c);
+        }
+        assert_eq!(won, select_winners(&commitments, capacity, b"seed"));
+    }
+
+    #[test]
+    fn everyone_wins_when_undersubscribed() {
+        let commitments = amounts(&[100, 300, 50]);
+        let won = select_winners(&commitments, Uint128::from(1000u128), b"seed");
+        assert_eq!(won, commitments);
+    }
+}
@@ -1,5 +1,10 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, Coin, Uint128};
+use cosmwasm_std::{Binary, Coin, Timestamp, Uint128};
+
+use crate::state::{
+    AuditEntry, CircuitBreaker, CoApproval, EpochSale, FinalReport, InventoryPool, NameBindings, ProceedsBridge, ProceedsStream, Lottery, PriceChange, PurchaseApproval, Subscription, LotteryEntry, MigrationProgress, Quote, Reservation, Sale, SaleRound, PurchaseLimits, ValidatorKickback, PurchaseLimitsSource, QueuedAction, TimelockedAction, VestingConfig,
+    WhitelistImportProgress,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -13,6 +18,22 @@ pub struct InstantiateMsg {
     pub accepted_eth_contract: String,
     /// Fixed price per 1 GNK in micro-USD (6 decimals, e.g., 25000 = $0.025/GNK)
     pub price_usd: Uint128,
+    /// Alternative denoms buyers may choose as payout (e.g., a liquid-staked GNK denom)
+    pub alternative_payout_denoms: Option<Vec<String>>,
+    /// Optional vesting mode: purchased tokens are locked in a per-buyer vesting contract
+    pub vesting: Option<VestingConfig>,
+    /// Optional purchase size and price bounds, stored here or read from chain params
+    pub purchase_limits: Option<PurchaseLimitsSource>,
+    /// Expected inference module consensus version; instantiate fails if the chain reports another
+    pub expected_module_version: Option<u64>,
+    /// Optional cw721 contract to mint a purchase receipt NFT on
+    pub receipt_nft_contract: Option<String>,
+    /// Decimals of the payout denoms (default 9, as for ngonka)
+    #[serde(default)]
+    pub payout_decimals: Option<u8>,
+    /// Optional cap on total stablecoin collected, in micro-USD
+    #[serde(default)]
+    pub max_raise_usd: Option<Uint128>,
 }
 
 #[cw_serde]
@@ -25,12 +46,198 @@ pub enum ExecuteMsg {
     Resume {},
     /// Admin: Update buyer address
     UpdateBuyer { buyer: String },
+    /// Admin: Import a batch of (address, allocation in micro-USD) whitelist entries.
+    /// `batch` must equal the import cursor's `next_batch`, so a batch cannot be applied twice.
+    ImportWhitelist {
+        entries: Vec<(String, Uint128)>,
+        batch: u64,
+    },
     /// Admin: Update fixed price
     UpdatePrice { price_usd: Uint128 },
-    /// Admin: Withdraw native tokens from contract
-    WithdrawNativeTokens { amount: Uint128, recipient: String },
+    /// Admin: Set (or clear with None) the price quoted in units of a specific payment token
+    UpdateTokenPrice {
+        cw20_contract: String,
+        price: Option<Uint128>,
+    },
+    /// Admin: Set (or clear with None) a negotiated price for one buyer
+    UpdateBuyerPrice {
+        buyer: String,
+        price: Option<Uint128>,
+    },
+    /// Admin: Enable, change or disable (None) vesting mode
+    UpdateVestingConfig { vesting: Option<VestingConfig> },
+    /// Admin: Set or clear the purchase limits source
+    UpdatePurchaseLimits { purchase_limits: Option<PurchaseLimitsSource> },
+    /// Admin: Replace the set of alternative payout denoms
+    UpdatePayoutDenoms { denoms: Vec<String> },
+    /// Admin: Withdraw native tokens from contract (defaults to the base native denom)
+    WithdrawNativeTokens {
+        amount: Uint128,
+        recipient: String,
+        denom: Option<String>,
+    },
     /// Admin: Emergency withdraw all funds
     EmergencyWithdraw { recipient: String },
+    /// Admin: End the sale for good. Pauses it, sends unreserved native tokens to `recipient`
+    /// and records the FinalReport; Resume is rejected afterwards
+    FinalizeSale { recipient: String },
+    /// Fund the sale inventory with the attached native/payout denoms, recording the depositor
+    Deposit {},
+    /// Admin: Update the crank reward per item. `timelock_delay_blocks` enables the timelock;
+    /// once enabled, the delay only changes through a queued UpdateTimelockDelay
+    UpdateCrankConfig {
+        crank_reward: Uint128,
+        #[serde(default)]
+        timelock_delay_blocks: Option<u64>,
+    },
+    /// Admin: Set or clear the cap on total stablecoin collected (micro-USD)
+    UpdateMaxRaise { max_raise_usd: Option<Uint128> },
+    /// Admin: Bridge treasury proceeds to `destination` on the accepted chain through the
+    /// payment token's Withdraw, or forward them locally again (None)
+    UpdateProceedsBridge { destination: Option<String> },
+    /// Admin: Pin a payment token that has just passed validation so its purchases skip the
+    /// chain and bridge-info queries, or validate every payment again (None)
+    UpdateTrustedPaymentToken { cw20_contract: Option<String> },
+    /// Admin: Hold proceeds and have the crank release them every `interval_blocks`, or forward
+    /// them per purchase again (None). Proceeds already held go out on the next crank
+    UpdateProceedsStream { interval_blocks: Option<u64> },
+    /// Admin: Resolve the treasury and designated buyer from a name registry (None stops
+    /// resolving; the buyer keeps its last address and proceeds go to the admin again)
+    UpdateNameBindings { bindings: Option<NameBindings> },
+    /// Admin: Require purchases above `threshold_usd` to be pre-approved by `approver`, or drop
+    /// the requirement (None). Pending approvals stay but go unused while it is off
+    UpdateCoApproval { co_approval: Option<CoApproval> },
+    /// Co-approval approver: Allow `buyer` one purchase of up to `max_usd` above the threshold,
+    /// until block height `expiry`. Replaces the buyer's pending approval
+    ApprovePurchase {
+        buyer: String,
+        max_usd: Uint128,
+        expiry: u64,
+    },
+    /// Admin: Retry bridging proceeds held after a failed bridge hop, or send them to the admin
+    /// if bridging has been turned off
+    FlushUnbridgedProceeds { cw20_contract: String },
+    /// Admin: Schedule a sale round over [start_height, end_height) with its own price and
+    /// optional treasury for its proceeds
+    AddRound {
+        name: String,
+        start_height: u64,
+        end_height: u64,
+        price_usd: Uint128,
+        treasury: Option<String>,
+    },
+    /// Admin: Remove a round that has not sold anything
+    RemoveRound { id: u32 },
+    /// Admin: Set sale terms for upcoming inference epochs, replacing those already set for
+    /// the same epochs. An entry with neither price nor cap removes the epoch's terms.
+    SetEpochSchedule { epochs: Vec<EpochSaleParams> },
+    /// Admin: Open an independent sale with its own buyers, price and inventory. Purchases
+    /// address it by the returned `sale_id`.
+    CreateSale { params: SaleParams },
+    /// Admin: Add or remove a sale's buyers and optionally close or reopen it
+    UpdateSale {
+        id: u64,
+        #[serde(default)]
+        add_buyers: Vec<String>,
+        #[serde(default)]
+        remove_buyers: Vec<String>,
+        closed: Option<bool>,
+    },
+    /// Admin: Create a labeled inventory pool or update its cap and buyers. While any pool
+    /// exists, main-sale purchases draw from the buyer's eligible pools in turn.
+    SetPool {
+        label: String,
+        /// Payout tokens the pool may sell; not below what it has sold
+        cap: Uint128,
+        #[serde(default)]
+        add_buyers: Vec<String>,
+        #[serde(default)]
+        remove_buyers: Vec<String>,
+    },
+    /// Admin: Remove a pool that has not sold anything
+    RemovePool { label: String },
+    /// Buyer: open a purchase of `usd_amount` that several CW20 payments can fund by passing
+    /// the returned `reservation_id`. Settles once fully funded by `deadline_height`.
+    ReservePurchase { usd_amount: Uint128, deadline_height: u64 },
+    /// Refund a reservation's payments to its buyer. The buyer may cancel at any time; anyone
+    /// may once the deadline has passed.
+    CancelReservation { id: u64 },
+    /// Admin: Queue an action; the crank executes it once the timelock delay has elapsed
+    QueueAction { action: TimelockedAction },
+    /// Admin: Cancel a queued action
+    CancelAction { id: u64 },
+    /// Anyone: process up to `limit` due lifecycle items, earning the crank reward per item
+    Crank { limit: Option<u32> },
+    /// Buyer: a plain purchase paid with `usd_amount` of `token`, which the contract pulls
+    /// with TransferFrom from an allowance the buyer granted it beforehand. For wallets that
+    /// cannot send a CW20 Send with a purchase payload.
+    PurchaseViaAllowance { token: String, usd_amount: Uint128 },
+    /// Buyer: purchase `usd_amount` of GNK every `interval_blocks`, paid from an allowance the
+    /// buyer grants this contract on `cw20_contract`. The first purchase is due immediately
+    Subscribe {
+        cw20_contract: String,
+        usd_amount: Uint128,
+        interval_blocks: u64,
+    },
+    /// Subscriber: skip purchases until resumed
+    PauseSubscription { id: u64 },
+    /// Subscriber: resume a paused subscription; the next purchase is due immediately if overdue
+    ResumeSubscription { id: u64 },
+    /// Subscriber: delete a subscription
+    CancelSubscription { id: u64 },
+    /// Anyone: run up to `limit` due subscription purchases, earning the crank reward per item.
+    /// A purchase that cannot run (allowance, balance, limits) is skipped until its next period
+    ProcessSubscriptions { limit: Option<u32> },
+    /// Admin: Set the commit-reveal window in blocks (0 disables commit-reveal purchases)
+    UpdateCommitRevealWindow { blocks: u64 },
+    /// Admin: Set or clear (None) the cw721 contract purchase receipts are minted on
+    UpdateReceiptNft { cw721_contract: Option<String> },
+    /// Admin: Require (or stop requiring) a chain participant attestation for buyers
+    SetAttestationRequired { required: bool },
+    /// Admin: Enable or disable the DebugGrpc query
+    SetDebugGrpc { enabled: bool },
+    /// Admin: Enable or disable the ExportState query
+    SetStateExport { enabled: bool },
+    /// Admin: start a lottery; payments until `commit_end_height` become weighted entries
+    /// for `capacity_usd` worth of tokens at the current price
+    StartLottery {
+        commit_end_height: u64,
+        capacity_usd: Uint128,
+    },
+    /// Anyone: after the commit window, draw winners with the chain's random seed
+    FinalizeLottery {},
+    /// Entrant: receive tokens for the amount won and a refund of the rest. Entries still
+    /// unclaimed after the claim deadline are refunded in full by the crank, forfeiting the win
+    ClaimLottery {},
+    /// Admin: run the next backfill step of a v1 migration
+    ContinueMigration {},
+    /// Buyer: lock the current price of `cw20_contract` behind sha256(amount_be_bytes ‖ salt),
+    /// attaching `PURCHASE_COMMITMENT_DEPOSIT` of the native denom until the reveal
+    CommitPurchase {
+        commitment: Binary,
+        cw20_contract: String,
+    },
+    /// Buyer: lock the caller's current price for a short window. Pass the returned `quote_id`
+    /// with a purchase to buy at that price even if the price changes meanwhile
+    RequestQuote {
+        /// Quote the price of this payment token; defaults to the default price
+        #[serde(default)]
+        cw20_contract: Option<String>,
+    },
+}
+
+/// Privileged messages executed by the chain (governance `MsgSudoContract`)
+#[cw_serde]
+pub enum SudoMsg {
+    /// Route `bps` of every direct or subscription payment's stablecoin to `recipient` (fee
+    /// collector) instead of the treasury; None disables the kickback
+    SetValidatorKickback { kickback: Option<ValidatorKickback> },
+    /// Reject purchases priced below `floor_price_usd`; None removes the floor
+    SetPriceFloor { floor_price_usd: Option<Uint128> },
+    /// Pause purchases automatically on abnormal volume; None removes the breaker
+    SetCircuitBreaker { breaker: Option<CircuitBreaker> },
+    /// Finalize the lottery with a governance-provided seed (fallback when the chain query is unavailable)
+    FinalizeLottery { seed: Binary },
 }
 
 #[cw_serde]
@@ -40,8 +247,76 @@ pub struct Cw20ReceiveMsg {
     pub msg: Binary,
 }
 
+/// Highest `PurchaseTokenMsg` payload version this contract understands
+pub const PURCHASE_MSG_VERSION: u32 = 2;
+
+/// CW20 `Send` payload for a purchase. Payloads without `v` are version 1, the original
+/// shape, and are still accepted; `{}` is a plain purchase either way.
 #[cw_serde]
-pub struct PurchaseTokenMsg {}
+pub struct PurchaseTokenMsg {
+    /// Payload version, at most `PURCHASE_MSG_VERSION`
+    #[serde(default)]
+    pub v: Option<u32>,
+    /// Denom to receive the purchased tokens in; defaults to the base native denom
+    pub payout_denom: Option<String>,
+    /// Reveals an earlier CommitPurchase; the purchase executes at the committed price
+    #[serde(default)]
+    pub salt: Option<Binary>,
+    /// Buy exactly `tokens_out`; the unspent part of the payment is refunded
+    #[serde(default)]
+    pub exact_out: Option<PurchaseExactOut>,
+    /// Purchase signed off-chain by the buyer; the CW20 sender acts as a relayer paying for it
+    #[serde(default)]
+    pub intent: Option<SignedPurchaseIntent>,
+    /// Buy from a sale created with CreateSale instead of the main sale
+    #[serde(default)]
+    pub sale_id: Option<u64>,
+    /// Fund a reservation opened with ReservePurchase
+    #[serde(default)]
+    pub reservation_id: Option<u64>,
+    /// Buy at the price of a quote from RequestQuote
+    #[serde(default)]
+    pub quote_id: Option<u64>,
+}
+
+#[cw_serde]
+pub struct SaleParams {
+    pub name: String,
+    /// Price per GNK in micro-USD
+    pub price_usd: Uint128,
+    /// Payout tokens allotted to the sale
+    pub inventory: Uint128,
+    /// Initial buyers; bounded like a whitelist batch
+    pub buyers: Vec<String>,
+}
+
+/// A buyer's off-chain authorization of a purchase. `signature` is a 64-byte secp256k1
+/// signature over sha256 of the JSON `PurchaseIntentSignDoc`, which also covers the other
+/// fields of the purchase payload carrying it.
+#[cw_serde]
+pub struct SignedPurchaseIntent {
+    pub buyer: String,
+    /// 33-byte compressed secp256k1 key of the buyer's account
+    pub pubkey: Binary,
+    pub amount: Uint128,
+    /// Must equal the buyer's `PurchaseNonce`
+    pub nonce: u64,
+    /// Last block height the intent may execute at
+    pub deadline_height: u64,
+    pub signature: Binary,
+    /// Quote in GNK: buy exactly this many tokens (payout base units), spending at most
+    /// `amount` and refunding the rest
+    #[serde(default)]
+    pub tokens_out: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct PurchaseExactOut {
+    /// Tokens to receive, in base units of the payout denom
+    pub tokens_out: Uint128,
+    /// Fail if the purchase would cost more than this (micro-USD)
+    pub max_usd_in: Uint128,
+}
 
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -49,21 +324,196 @@ pub enum QueryMsg {
     /// Get contract configuration
     #[returns(ConfigResponse)]
     Config {},
+    /// Current lottery round, if any
+    #[returns(LotteryResponse)]
+    Lottery {},
+    /// A buyer's lottery commitment and winnings
+    #[returns(LotteryEntryResponse)]
+    LotteryEntry { buyer: String },
+    /// The price a purchase pays now, with the governance floor. Per-token prices apply when
+    /// `cw20_contract` is given, or when the trusted payment token is set.
+    #[returns(CurrentPriceResponse)]
+    CurrentPrice { cw20_contract: Option<String> },
+    /// Aggregated operational state for monitoring
+    #[returns(StatusResponse)]
+    Status {},
+    /// Page through the admin audit log in chronological order
+    #[returns(AuditLogResponse)]
+    AuditLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Page through default and per-token price changes in chronological order
+    #[returns(PriceHistoryResponse)]
+    PriceHistory {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Pinned and live inference module versions
+    #[returns(ModuleVersionResponse)]
+    ModuleVersion {},
     /// Get contract's native token balance
     #[returns(NativeBalanceResponse)]
     NativeBalance {},
+    /// Page through recorded inventory deposits ordered by depositor, and the native amount
+    /// held back for unclaimed lottery wins, open reservations and commitment deposits
+    #[returns(DepositsResponse)]
+    Deposits {
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// List payout denoms with per-denom sold amounts and inventory
+    #[returns(PayoutDenomsResponse)]
+    PayoutDenoms {},
+    /// Page through whitelist entries ordered by address
+    #[returns(WhitelistResponse)]
+    ExportWhitelist {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Progress cursor of the batched whitelist import
+    #[returns(WhitelistImportProgress)]
+    WhitelistImportStatus {},
+    /// Page through queued timelocked actions in execution order
+    #[returns(QueuedActionsResponse)]
+    QueuedActions {
+        start_after: Option<(u64, u64)>,
+        limit: Option<u32>,
+    },
+    /// Effective purchase limits (resolved from chain params when configured so)
+    #[returns(PurchaseLimitsResponse)]
+    PurchaseLimits {},
+    /// List vesting contracts instantiated for a buyer, in purchase order
+    #[returns(VestingContractsResponse)]
+    VestingContracts { buyer: String },
     /// Calculate how many tokens can be bought with given USD amount
     #[returns(TokenCalculationResponse)]
     CalculateTokens { usd_amount: Uint128 },
+    /// Wallet pre-flight: whether `buyer` could buy `usd_amount` at its default price right
+    /// now, and the first purchase check that would fail if not
+    #[returns(CanPurchaseResponse)]
+    CanPurchase { buyer: String, usd_amount: Uint128 },
+    /// Calculate the USD (micro-USD, rounded up) an exact-out purchase of `token_amount` costs
+    #[returns(UsdCalculationResponse)]
+    CalculateUsdForTokens { token_amount: Uint128 },
+    /// Calculate how many tokens a payment of `amount` in the given CW20 buys at its effective price
+    #[returns(TokenCalculationResponse)]
+    CalculateTokensForToken { cw20_contract: String, amount: Uint128 },
+    /// List per-token price overrides
+    #[returns(TokenPricesResponse)]
+    TokenPrices {},
+    /// Page through per-buyer price agreements ordered by buyer
+    #[returns(BuyerPricesResponse)]
+    BuyerPrices {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Scheduled rounds with their raised totals, and the round active at the current height
+    #[returns(RoundsResponse)]
+    Rounds {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    /// Page through scheduled epoch terms with what each epoch raised, and the current epoch
+    #[returns(EpochScheduleResponse)]
+    EpochSchedule {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// A sale created with CreateSale
+    #[returns(SaleResponse)]
+    Sale { id: u64 },
+    /// Page through sales by id
+    #[returns(SalesResponse)]
+    Sales {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// An inventory pool with its sales so far
+    #[returns(PoolResponse)]
+    Pool { label: String },
+    /// Page through inventory pools by label
+    #[returns(PoolsResponse)]
+    Pools {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Labels of the pools `buyer` is eligible for
+    #[returns(BuyerPoolsResponse)]
+    BuyerPools { buyer: String },
+    /// A split-payment reservation and the funding it has received
+    #[returns(Reservation)]
+    Reservation { id: u64 },
+    /// A buyer's pending large-purchase approval, if any
+    #[returns(Option<PurchaseApproval>)]
+    PurchaseApproval { buyer: String },
+    /// A price quote from RequestQuote; fails once used or cleaned up after expiry
+    #[returns(Quote)]
+    Quote { id: u64 },
+    /// A recurring-purchase subscription
+    #[returns(Subscription)]
+    Subscription { id: u64 },
+    /// Page through subscriptions by id, optionally only `buyer`'s
+    #[returns(SubscriptionsResponse)]
+    Subscriptions {
+        buyer: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Circuit-breaker parameters and the volumes it currently compares
+    #[returns(CircuitBreakerResponse)]
+    CircuitBreaker {},
+    /// Native tokens held back for unpaid obligations and what is free to withdraw
+    #[returns(ReservesResponse)]
+    Reserves {},
+    /// Proceeds held here after their bridge hop failed, per payment token
+    #[returns(UnbridgedProceedsResponse)]
+    UnbridgedProceeds {},
+    /// Proceeds held for the next scheduled release, per payment token and recipient
+    #[returns(StreamedProceedsResponse)]
+    StreamedProceeds {},
+    /// Sale outcome recorded by FinalizeSale; None before finalization
+    #[returns(Option<FinalReport>)]
+    FinalReport {},
+    /// Whether `buyer` may purchase from a sale
+    #[returns(bool)]
+    SaleBuyer { id: u64, buyer: String },
+    /// Stablecoin collected so far and what remains under `max_raise_usd`
+    #[returns(RaiseCapacityResponse)]
+    RaiseCapacity {},
+    /// Next nonce a signed purchase intent from `buyer` must carry
+    #[returns(PurchaseNonceResponse)]
+    PurchaseNonce { buyer: String },
+    /// Contract name, version and message schema version
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
+    /// Progress of a multi-stage migration from v1 state, if one ran
+    #[returns(MigrationStatusResponse)]
+    MigrationStatus {},
     /// Test bridge validation with a provided CW20 contract address
+    #[cfg(feature = "debug")]
     #[returns(TestBridgeValidationResponse)]
     TestBridgeValidation { cw20_contract: String },
     /// Return the current block height
+    #[cfg(feature = "debug")]
     #[returns(BlockHeightResponse)]
     BlockHeight {},
     /// Test gRPC call to fetch approved tokens for trade
+    #[cfg(feature = "debug")]
     #[returns(ApprovedTokensForTradeJson)]
     TestApprovedTokens {},
+    /// Forward an arbitrary gRPC query and return the raw response with decode attempts
+    /// for every known response type. Fails unless enabled by the admin via SetDebugGrpc
+    #[returns(DebugGrpcResponse)]
+    DebugGrpc { path: String, data: Binary },
+    /// Page through the raw stored state of one section, for rebuilding the sale after an
+    /// emergency redeployment. Fails unless enabled by the admin via SetStateExport
+    #[returns(ExportStateResponse)]
+    ExportState {
+        section: StateSection,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -74,26 +524,493 @@ pub struct ConfigResponse {
     pub accepted_eth_contract: String,
     pub price_usd: Uint128,
     pub native_denom: String,
+    pub payout_decimals: u8,
+    pub alternative_payout_denoms: Vec<String>,
+    pub vesting: Option<VestingConfig>,
+    pub purchase_limits: Option<PurchaseLimitsSource>,
+    pub timelock_delay_blocks: u64,
+    pub crank_reward: Uint128,
+    pub commit_reveal_window_blocks: u64,
+    pub expected_module_version: Option<u64>,
+    pub receipt_nft_contract: Option<String>,
+    pub require_attestation: bool,
+    pub debug_grpc_enabled: bool,
+    pub state_export_enabled: bool,
+    pub validator_kickback: Option<ValidatorKickback>,
+    pub price_floor_usd: Option<Uint128>,
+    pub max_raise_usd: Option<Uint128>,
+    pub total_raised_usd: Uint128,
+    pub proceeds_bridge: Option<ProceedsBridge>,
+    pub proceeds_stream: Option<ProceedsStream>,
+    /// Proceeds recipient resolved from `name_bindings`; the admin when None
+    pub treasury: Option<String>,
+    pub name_bindings: Option<NameBindings>,
+    pub co_approval: Option<CoApproval>,
+    pub trusted_payment_token: Option<String>,
     pub is_paused: bool,
     pub total_tokens_sold: Uint128,
 }
 
+#[cw_serde]
+pub struct RoundInfo {
+    pub id: u32,
+    pub round: SaleRound,
+}
+
+#[cw_serde]
+pub struct EpochSaleParams {
+    pub epoch: u64,
+    /// Price per GNK in micro-USD while no round is active; the usual price when None
+    pub price_usd: Option<Uint128>,
+    /// Most micro-USD the epoch may raise; unbounded when None
+    pub cap_usd: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct EpochSaleInfo {
+    pub epoch: u64,
+    pub sale: EpochSale,
+    pub raised_usd: Uint128,
+}
+
+#[cw_serde]
+pub struct EpochScheduleResponse {
+    pub epochs: Vec<EpochSaleInfo>,
+    /// The inference module's current epoch; None if the chain did not answer
+    pub current_epoch: Option<u64>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<u64>,
+}
+
+#[cw_serde]
+pub struct RoundsResponse {
+    pub rounds: Vec<RoundInfo>,
+    pub active_round: Option<u32>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<u32>,
+}
+
+#[cw_serde]
+pub struct SaleResponse {
+    pub id: u64,
+    pub sale: Sale,
+}
+
+#[cw_serde]
+pub struct SalesResponse {
+    pub sales: Vec<SaleResponse>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PoolResponse {
+    pub label: String,
+    pub pool: InventoryPool,
+    /// Payout tokens the pool can still sell
+    pub remaining: Uint128,
+}
+
+#[cw_serde]
+pub struct PoolsResponse {
+    pub pools: Vec<PoolResponse>,
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct BuyerPoolsResponse {
+    pub buyer: String,
+    pub pools: Vec<String>,
+}
+
+#[cw_serde]
+pub struct DepositsResponse {
+    pub deposits: Vec<DepositEntry>,
+    /// Native denom amount WithdrawNativeTokens cannot touch
+    pub reserved: Uint128,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<(String, String)>,
+}
+
+#[cw_serde]
+pub struct ReservesResponse {
+    /// Contract balance of the native denom
+    pub balance: Uint128,
+    /// Sum of `obligations`
+    pub reserved: Uint128,
+    /// What WithdrawNativeTokens may still take
+    pub free: Uint128,
+    pub obligations: Vec<ReserveEntry>,
+}
+
+#[cw_serde]
+pub struct UnbridgedProceedsResponse {
+    pub proceeds: Vec<UnbridgedProceedsEntry>,
+}
+
+#[cw_serde]
+pub struct UnbridgedProceedsEntry {
+    pub cw20_contract: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct StreamedProceedsResponse {
+    pub proceeds: Vec<StreamedProceedsEntry>,
+    /// None while streaming is off; anything still held goes out on the next crank
+    pub next_release_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct StreamedProceedsEntry {
+    pub cw20_contract: String,
+    pub recipient: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct ReserveEntry {
+    pub obligation: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct DepositEntry {
+    pub depositor: String,
+    pub denom: String,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct RaiseCapacityResponse {
+    pub max_raise_usd: Option<Uint128>,
+    pub total_raised_usd: Uint128,
+    /// None when the raise is uncapped
+    pub remaining_usd: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct LotteryResponse {
+    pub lottery: Option<Lottery>,
+}
+
+#[cw_serde]
+pub struct LotteryEntryResponse {
+    pub entry: Option<LotteryEntry>,
+}
+
+#[cw_serde]
+pub struct CircuitBreakerResponse {
+    pub breaker: Option<CircuitBreaker>,
+    /// Micro-USD sold in the current window
+    pub window_volume_usd: Uint128,
+    /// Average micro-USD per window over the trailing windows
+    pub trailing_average_usd: Uint128,
+    /// Window volume above which a purchase trips the breaker
+    pub limit_usd: Uint128,
+}
+
+#[cw_serde]
+pub struct CurrentPriceResponse {
+    pub price_usd: Uint128,
+    /// Where `price_usd` comes from: "round", "epoch", "token" or "default"
+    pub price_source: String,
+    /// Purchases below this price are rejected
+    pub floor_price_usd: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct PurchaseNonceResponse {
+    pub buyer: String,
+    pub next_nonce: u64,
+}
+
+#[cw_serde]
+pub struct MigrationStatusResponse {
+    pub migration: Option<MigrationProgress>,
+}
+
+#[cw_serde]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub version: String,
+    /// Bumped whenever the message or response JSON shape changes incompatibly
+    pub schema_version: u32,
+    /// Highest `PurchaseTokenMsg` payload version accepted
+    pub purchase_msg_version: u32,
+}
+
+#[cw_serde]
+pub struct StatusResponse {
+    pub is_paused: bool,
+    /// The default price, set by UpdatePrice
+    pub price_usd: Uint128,
+    /// The price purchases pay now, before per-token overrides
+    pub current_price_usd: Uint128,
+    /// Where `current_price_usd` comes from: "round", "epoch" or "default"
+    pub price_source: String,
+    pub active_round: Option<u32>,
+    /// Native tokens held by the contract and not owed to anyone
+    pub remaining_inventory: Coin,
+    /// Native tokens held back for lottery wins, reservations, commitments and sales
+    pub reserved_inventory: Uint128,
+    pub total_tokens_sold: Uint128,
+    pub total_raised_usd: Uint128,
+    pub max_raise_usd: Option<Uint128>,
+    /// None when the raise is uncapped
+    pub remaining_raise_usd: Option<Uint128>,
+    /// The current inference epoch, when it has scheduled terms
+    pub current_epoch: Option<u64>,
+    pub epoch_cap_usd: Option<Uint128>,
+    pub epoch_raised_usd: Uint128,
+    pub timelock_queue_length: u64,
+    /// Block height at which the next queued action becomes executable
+    pub next_timelock_eta: Option<u64>,
+    pub last_purchase_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditEntry>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<u64>,
+}
+
+#[cw_serde]
+pub struct SubscriptionsResponse {
+    pub subscriptions: Vec<Subscription>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<u64>,
+}
+
+#[cw_serde]
+pub struct PriceHistoryResponse {
+    pub changes: Vec<PriceChange>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<u64>,
+}
+
+#[cw_serde]
+pub struct ModuleVersionResponse {
+    pub module: String,
+    pub expected: Option<u64>,
+    /// None when the chain does not answer the ModuleVersions query
+    pub actual: Option<u64>,
+}
+
+#[cw_serde]
+pub struct QueuedActionsResponse {
+    pub actions: Vec<QueuedAction>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<(u64, u64)>,
+}
+
+#[cw_serde]
+pub struct WhitelistResponse {
+    pub entries: Vec<WhitelistEntryResponse>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct WhitelistEntryResponse {
+    pub address: String,
+    pub allocation_usd: Uint128,
+    pub purchased_usd: Uint128,
+}
+
+#[cw_serde]
+pub struct PurchaseLimitsResponse {
+    pub source: Option<PurchaseLimitsSource>,
+    /// None when limits are disabled
+    pub limits: Option<PurchaseLimits>,
+}
+
+#[cw_serde]
+pub struct VestingContractsResponse {
+    pub buyer: String,
+    pub contracts: Vec<String>,
+}
+
+/// Execute message sent to the receipt cw721 contract (cw721-base `Mint` shape)
+#[cw_serde]
+pub enum ReceiptNftExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: ReceiptMetadata,
+    },
+}
+
+/// On-chain metadata of a purchase receipt
+#[cw_serde]
+pub struct ReceiptMetadata {
+    /// Price per GNK the purchase executed at
+    pub price_usd: Uint128,
+    pub usd_amount: Uint128,
+    pub tokens_purchased: Uint128,
+    pub payout_denom: String,
+    pub purchase_height: u64,
+}
+
+/// Instantiate message sent to `VestingConfig::code_id`; the vesting contract must accept this shape
+#[cw_serde]
+pub struct VestingInstantiateMsg {
+    /// Address that can claim vested tokens
+    pub beneficiary: String,
+    /// Vesting start (purchase block time)
+    pub start_time: Timestamp,
+    /// Nothing is claimable before start_time + cliff_seconds
+    pub cliff_seconds: u64,
+    /// Everything is claimable at start_time + duration_seconds
+    pub duration_seconds: u64,
+}
+
+/// Sent by the crank to a vesting contract at its cliff and end; the vesting contract must
+/// accept this shape and pay the beneficiary whatever has vested
+#[cw_serde]
+pub enum VestingExecuteMsg {
+    Release {},
+}
+
+#[cw_serde]
+pub struct PayoutDenomsResponse {
+    pub denoms: Vec<PayoutDenomInfo>,
+}
+
+#[cw_serde]
+pub struct PayoutDenomInfo {
+    pub denom: String,
+    pub tokens_sold: Uint128,
+    pub balance: Coin,
+}
+
 #[cw_serde]
 pub struct NativeBalanceResponse {
     pub balance: Coin,
 }
 
+#[cw_serde]
+pub struct CanPurchaseResponse {
+    pub can_purchase: bool,
+    /// `ContractError::code` of the first failing check
+    pub error_code: Option<u32>,
+    /// Error message of the first failing check
+    pub reason: Option<String>,
+    /// Price and payout of the purchase; zero unless `can_purchase`
+    pub price_usd: Uint128,
+    pub tokens: Uint128,
+}
+
 #[cw_serde]
 pub struct TokenCalculationResponse {
     pub tokens: Uint128,
     pub price_usd: Uint128,
 }
 
+#[cw_serde]
+pub struct UsdCalculationResponse {
+    pub usd_amount: Uint128,
+    pub price_usd: Uint128,
+}
+
+#[cw_serde]
+pub struct TokenPricesResponse {
+    /// Price used for tokens without an override
+    pub default_price_usd: Uint128,
+    pub prices: Vec<TokenPriceResponse>,
+}
+
+#[cw_serde]
+pub struct TokenPriceResponse {
+    pub cw20_contract: String,
+    pub price: Uint128,
+}
+
+#[cw_serde]
+pub struct BuyerPricesResponse {
+    pub prices: Vec<BuyerPriceResponse>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct BuyerPriceResponse {
+    pub buyer: String,
+    pub price: Uint128,
+}
+
 #[cw_serde]
 pub struct TestBridgeValidationResponse {
     pub is_valid: bool,
 }
 
+#[cw_serde]
+pub enum StateSection {
+    /// Config, contract version, migration and final report, prices, rounds and epoch terms
+    Config,
+    /// Whitelist entries and any in-progress import
+    Whitelist,
+    /// Buyer, token and denom totals, purchase nonces and approvals, and breaker volume
+    Purchases,
+    /// Reservations, the reserves held back per obligation and tracked deposits
+    Reservations,
+    /// Vesting contracts instantiated per buyer
+    Vesting,
+    /// Concurrent sales and their buyer lists
+    Sales,
+    /// Inventory pools, their buyer lists and the round-robin cursor
+    Pools,
+    /// The running lottery and its entries
+    Lottery,
+    /// Recurring purchase subscriptions and their due index
+    Subscriptions,
+    /// Price-lock quotes and their expiry index
+    Quotes,
+    /// Commit-reveal purchase commitments and their expiry index
+    Commitments,
+    /// Queued timelocked actions
+    Timelock,
+    /// Audit log and price history
+    History,
+    /// Proceeds held for a failed bridge hop or a streaming release
+    Proceeds,
+}
+
+#[cw_serde]
+pub struct StateEntry {
+    /// `<store>/<key>`, or just `<store>` for a single item
+    pub key: String,
+    /// The stored value as JSON
+    pub value: Binary,
+}
+
+#[cw_serde]
+pub struct ExportStateResponse {
+    pub section: StateSection,
+    pub entries: Vec<StateEntry>,
+    /// Pass as `start_after` for the next page; None on the last page
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct DebugGrpcResponse {
+    pub path: String,
+    /// Raw response bytes; None when the query failed
+    pub response: Option<Binary>,
+    pub error: Option<String>,
+    pub decode_attempts: Vec<DecodeAttempt>,
+}
+
+#[cw_serde]
+pub struct DecodeAttempt {
+    pub type_name: String,
+    /// Debug rendering of the decoded message
+    pub decoded: Option<String>,
+    pub error: Option<String>,
+}
+
 #[cw_serde]
 pub struct BlockHeightResponse {
     pub height: u64,
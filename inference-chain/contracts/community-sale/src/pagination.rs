@@ -0,0 +1,128 @@
+//! Pagination shared by the list queries.
+//!
+//! Every list query takes `start_after` (the last key of the previous page, exclusive) and an
+//! optional `limit`. Responses that carry `next_start_after` set it to the last key of a full
+//! page and to `None` once the listing is exhausted, so clients page with a loop of
+//! `start_after = next_start_after` and never need to know the key layout.
+
+use cw_storage_plus::{Bound, PrimaryKey};
+
+/// Page size when the caller gives no limit
+pub const DEFAULT_LIMIT: u32 = 30;
+/// Largest page a single query returns
+pub const MAX_LIMIT: u32 = 100;
+
+/// Requested page size clamped to `1..=MAX_LIMIT`. A zero limit would return an empty page with
+/// no cursor, which clients read as the end of the listing.
+pub fn page_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize
+}
+
+/// Lower range bound for a page starting after `start_after`
+pub fn start_bound<'a, K: PrimaryKey<'a>>(start_after: Option<K>) -> Option<Bound<'a, K>> {
+    start_after.map(Bound::exclusive)
+}
+
+/// Cursor for the page after `page`: the key of its last item when the page is full
+pub fn next_start_after<T, K>(page: &[T], limit: usize, key: impl FnOnce(&T) -> K) -> Option<K> {
+    if page.len() < limit {
+        return None;
+    }
+    page.last().map(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::{Order, StdResult, Storage};
+    use cw_storage_plus::Map;
+
+    const NUMBERS: Map<u64, u64> = Map::new("numbers");
+    const NAMES: Map<&str, u64> = Map::new("names");
+
+    fn page_numbers(storage: &dyn Storage, start_after: Option<u64>, limit: Option<u32>) -> (Vec<u64>, Option<u64>) {
+        let limit = page_limit(limit);
+        let page = NUMBERS
+            .keys(storage, start_bound(start_after), None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        let next = next_start_after(&page, limit, |k| *k);
+        (page, next)
+    }
+
+    fn page_names(storage: &dyn Storage, start_after: Option<String>, limit: Option<u32>) -> (Vec<String>, Option<String>) {
+        let limit = page_limit(limit);
+        let page = NAMES
+            .keys(storage, start_bound(start_after.as_deref()), None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()
+            .unwrap();
+        let next = next_start_after(&page, limit, |k| k.clone());
+        (page, next)
+    }
+
+    #[test]
+    fn limits_are_clamped() {
+        assert_eq!(page_limit(None), DEFAULT_LIMIT as usize);
+        assert_eq!(page_limit(Some(0)), 1);
+        assert_eq!(page_limit(Some(7)), 7);
+        assert_eq!(page_limit(Some(u32::MAX)), MAX_LIMIT as usize);
+    }
+
+    #[test]
+    fn paging_visits_every_key_once_for_any_size_and_limit() {
+        for size in 0..40u64 {
+            let mut storage = MockStorage::new();
+            // Sparse keys so cursors never coincide with page boundaries by accident
+            let keys: Vec<u64> = (0..size).map(|i| i * 7 + 3).collect();
+            for k in &keys {
+                NUMBERS.save(&mut storage, *k, k).unwrap();
+                NAMES.save(&mut storage, &format!("addr{k}"), k).unwrap();
+            }
+            let mut names: Vec<String> = keys.iter().map(|k| format!("addr{k}")).collect();
+            names.sort();
+
+            for limit in [None, Some(0), Some(1), Some(2), Some(3), Some(5), Some(13), Some(500)] {
+                let (mut seen, mut cursor) = page_numbers(&storage, None, limit);
+                while cursor.is_some() {
+                    let (page, next) = page_numbers(&storage, cursor, limit);
+                    assert!(page.len() <= page_limit(limit));
+                    seen.extend(page);
+                    cursor = next;
+                }
+                assert_eq!(seen, keys, "size {size} limit {limit:?}");
+
+                let (mut seen, mut cursor) = page_names(&storage, None, limit);
+                while cursor.is_some() {
+                    let (page, next) = page_names(&storage, cursor, limit);
+                    seen.extend(page);
+                    cursor = next;
+                }
+                assert_eq!(seen, names, "size {size} limit {limit:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_is_stable_across_writes_between_pages() {
+        let mut storage = MockStorage::new();
+        for k in (10..60u64).step_by(10) {
+            NUMBERS.save(&mut storage, k, &k).unwrap();
+        }
+        let (first, cursor) = page_numbers(&storage, None, Some(2));
+        assert_eq!(first, vec![10, 20]);
+
+        // Writes behind the cursor are not revisited; writes ahead of it are picked up
+        NUMBERS.save(&mut storage, 15, &15).unwrap();
+        NUMBERS.save(&mut storage, 35, &35).unwrap();
+        NUMBERS.remove(&mut storage, 40);
+
+        let (second, cursor) = page_numbers(&storage, cursor, Some(2));
+        assert_eq!(second, vec![30, 35]);
+        let (third, cursor) = page_numbers(&storage, cursor, Some(2));
+        assert_eq!(third, vec![50]);
+        assert_eq!(cursor, None);
+    }
+}
@@ -0,0 +1,261 @@
+//! Protobuf types for chain gRPC queries and the messages sent as `CosmosMsg::Any`.
+//!
+//! The golden byte fixtures in the tests pin the wire encoding so a chain-side proto change
+//! can't silently break the contract. The inference module's consensus version is pinned
+//! separately through `Config::expected_module_version`.
+
+use prost::Message;
+
+pub const VALIDATE_WRAPPED_TOKEN_FOR_TRADE_PATH: &str = "/inference.inference.Query/ValidateWrappedTokenForTrade";
+pub const APPROVED_TOKENS_FOR_TRADE_PATH: &str = "/inference.inference.Query/ApprovedTokensForTrade";
+pub const COMMUNITY_SALE_PARAMS_PATH: &str = "/inference.inference.Query/CommunitySaleParams";
+pub const TOTAL_SUPPLY_PATH: &str = "/cosmos.bank.v1beta1.Query/TotalSupply";
+pub const PARTICIPANT_ATTESTATION_PATH: &str = "/inference.inference.Query/ParticipantAttestation";
+pub const MODULE_VERSIONS_PATH: &str = "/cosmos.upgrade.v1beta1.Query/ModuleVersions";
+pub const RANDOM_SEED_PATH: &str = "/inference.inference.Query/RandomSeed";
+pub const CURRENT_EPOCH_PATH: &str = "/inference.inference.Query/GetCurrentEpoch";
+pub const MSG_EXECUTE_CONTRACT_TYPE_URL: &str = "/cosmwasm.wasm.v1.MsgExecuteContract";
+
+/// Chain module whose gRPC interface this contract depends on
+pub const INFERENCE_MODULE_NAME: &str = "inference";
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryValidateWrappedTokenForTradeRequest {
+    #[prost(string, tag = "1")]
+    pub contract_address: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryValidateWrappedTokenForTradeResponse {
+    #[prost(bool, tag = "1")]
+    pub is_valid: bool,
+}
+
+#[derive(Clone, PartialEq, Message, serde::Serialize)]
+pub struct BridgeTradeApprovedToken {
+    #[prost(string, tag = "1")]
+    pub chain_id: String,
+    #[prost(string, tag = "2")]
+    pub contract_address: String,
+}
+
+#[derive(Clone, PartialEq, Message, serde::Serialize)]
+pub struct QueryApprovedTokensForTradeResponseProto {
+    #[prost(message, repeated, tag = "1")]
+    pub approved_tokens: ::prost::alloc::vec::Vec<BridgeTradeApprovedToken>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct EmptyRequest {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryTotalSupplyRequest {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryTotalSupplyResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub supply: ::prost::alloc::vec::Vec<CoinProto>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CoinProto {
+    #[prost(string, tag = "1")]
+    pub denom: String,
+    #[prost(string, tag = "2")]
+    pub amount: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryCommunitySaleParamsRequest {}
+
+/// Amounts are decimal strings (sdk math.Int); empty or "0" means unbounded
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryCommunitySaleParamsResponse {
+    #[prost(string, tag = "1")]
+    pub min_purchase_usd: String,
+    #[prost(string, tag = "2")]
+    pub max_purchase_usd: String,
+    #[prost(string, tag = "3")]
+    pub min_price_usd: String,
+    #[prost(string, tag = "4")]
+    pub max_price_usd: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryParticipantAttestationRequest {
+    #[prost(string, tag = "1")]
+    pub address: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryParticipantAttestationResponse {
+    #[prost(bool, tag = "1")]
+    pub valid: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryModuleVersionsRequest {
+    #[prost(string, tag = "1")]
+    pub module_name: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryModuleVersionsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub module_versions: ::prost::alloc::vec::Vec<ModuleVersion>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct ModuleVersion {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(uint64, tag = "2")]
+    pub version: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryRandomSeedRequest {
+    #[prost(uint64, tag = "1")]
+    pub block_height: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryRandomSeedResponse {
+    #[prost(bytes = "vec", tag = "1")]
+    pub seed: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryGetCurrentEpochRequest {}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct QueryGetCurrentEpochResponse {
+    #[prost(uint64, tag = "1")]
+    pub epoch: u64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct MsgExecuteContract {
+    #[prost(string, tag = "1")]
+    pub sender: String,
+    #[prost(string, tag = "2")]
+    pub contract: String,
+    #[prost(bytes = "vec", tag = "3")]
+    pub msg: Vec<u8>,
+    #[prost(message, repeated, tag = "5")]
+    pub funds: ::prost::alloc::vec::Vec<CoinProto>,
+}
+
+/// Decodes `bytes` as every known response type, returning the Debug rendering or decode error
+pub fn decode_known_responses(bytes: &[u8]) -> Vec<(&'static str, Result<String, String>)> {
+    fn attempt<T: Message + Default>(name: &'static str, bytes: &[u8]) -> (&'static str, Result<String, String>) {
+        (name, T::decode(bytes).map(|m| format!("{m:?}")).map_err(|e| e.to_string()))
+    }
+    vec![
+        attempt::<QueryValidateWrappedTokenForTradeResponse>("QueryValidateWrappedTokenForTradeResponse", bytes),
+        attempt::<QueryApprovedTokensForTradeResponseProto>("QueryApprovedTokensForTradeResponse", bytes),
+        attempt::<QueryTotalSupplyResponse>("QueryTotalSupplyResponse", bytes),
+        attempt::<QueryCommunitySaleParamsResponse>("QueryCommunitySaleParamsResponse", bytes),
+        attempt::<QueryParticipantAttestationResponse>("QueryParticipantAttestationResponse", bytes),
+        attempt::<QueryModuleVersionsResponse>("QueryModuleVersionsResponse", bytes),
+        attempt::<QueryRandomSeedResponse>("QueryRandomSeedResponse", bytes),
+        attempt::<QueryGetCurrentEpochResponse>("QueryGetCurrentEpochResponse", bytes),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_wrapped_token_golden_bytes() {
+        let request = QueryValidateWrappedTokenForTradeRequest {
+            contract_address: "abc".to_string(),
+        };
+        assert_eq!(request.encode_to_vec(), [0x0a, 0x03, b'a', b'b', b'c']);
+
+        let response = QueryValidateWrappedTokenForTradeResponse { is_valid: true };
+        assert_eq!(response.encode_to_vec(), [0x08, 0x01]);
+        assert_eq!(QueryValidateWrappedTokenForTradeResponse::decode(&[0x08u8, 0x01][..]).unwrap(), response);
+    }
+
+    #[test]
+    fn execute_contract_golden_bytes() {
+        let msg = MsgExecuteContract {
+            sender: "a".to_string(),
+            contract: "b".to_string(),
+            msg: b"{}".to_vec(),
+            funds: vec![],
+        };
+        assert_eq!(msg.encode_to_vec(), [0x0a, 0x01, b'a', 0x12, 0x01, b'b', 0x1a, 0x02, b'{', b'}']);
+    }
+
+    #[test]
+    fn community_sale_params_golden_bytes() {
+        let golden = [0x0a, 0x01, b'1', 0x12, 0x01, b'2', 0x22, 0x02, b'9', b'9'];
+        let response = QueryCommunitySaleParamsResponse::decode(&golden[..]).unwrap();
+        assert_eq!(response.min_purchase_usd, "1");
+        assert_eq!(response.max_purchase_usd, "2");
+        assert_eq!(response.min_price_usd, "");
+        assert_eq!(response.max_price_usd, "99");
+        assert_eq!(response.encode_to_vec(), golden);
+    }
+
+    #[test]
+    fn module_versions_golden_bytes() {
+        let request = QueryModuleVersionsRequest {
+            module_name: INFERENCE_MODULE_NAME.to_string(),
+        };
+        let mut golden = vec![0x0a, 0x09];
+        golden.extend_from_slice(b"inference");
+        assert_eq!(request.encode_to_vec(), golden);
+
+        let mut golden = vec![0x0a, 0x0d, 0x0a, 0x09];
+        golden.extend_from_slice(b"inference");
+        golden.extend_from_slice(&[0x10, 0x07]);
+        let response = QueryModuleVersionsResponse::decode(golden.as_slice()).unwrap();
+        assert_eq!(
+            response.module_versions,
+            vec![ModuleVersion { name: "inference".to_string(), version: 7 }]
+        );
+        assert_eq!(response.encode_to_vec(), golden);
+    }
+
+    #[test]
+    fn random_seed_golden_bytes() {
+        let request = QueryRandomSeedRequest { block_height: 300 };
+        assert_eq!(request.encode_to_vec(), [0x08, 0xac, 0x02]);
+
+        let response = QueryRandomSeedResponse::decode(&[0x0au8, 0x02, 0xbe, 0xef][..]).unwrap();
+        assert_eq!(response.seed, vec![0xbe, 0xef]);
+    }
+
+    #[test]
+    fn current_epoch_golden_bytes() {
+        assert!(QueryGetCurrentEpochRequest::default().encode_to_vec().is_empty());
+
+        let response = QueryGetCurrentEpochResponse::decode(&[0x08u8, 0xac, 0x02][..]).unwrap();
+        assert_eq!(response.epoch, 300);
+        assert_eq!(response.encode_to_vec(), [0x08, 0xac, 0x02]);
+    }
+
+    #[test]
+    fn participant_attestation_golden_bytes() {
+        let request = QueryParticipantAttestationRequest {
+            address: "gonka1".to_string(),
+        };
+        let mut golden = vec![0x0a, 0x06];
+        golden.extend_from_slice(b"gonka1");
+        assert_eq!(request.encode_to_vec(), golden);
+
+        let response = QueryParticipantAttestationResponse::decode(&[0x08u8, 0x01][..]).unwrap();
+        assert!(response.valid);
+        assert!(QueryParticipantAttestationResponse::decode(&[][..]).map(|r| !r.valid).unwrap());
+    }
+
+    #[test]
+    fn empty_request_encodes_to_nothing() {
+        assert!(EmptyRequest::default().encode_to_vec().is_empty());
+        assert!(QueryTotalSupplyRequest::default().encode_to_vec().is_empty());
+    }
+}
@@ -1,6 +1,11 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint128;
-use cw_storage_plus::Item;
+use cosmwasm_std::{to_json_vec, Binary, Int128, OverflowError, StdResult, Uint128};
+use cw_storage_plus::{Item, Map};
+use sha2::{Digest, Sha256};
+
+use external_address::ExternalAddress;
+
+use crate::msg::PurchaseExactOut;
 
 #[cw_serde]
 pub struct Config {
@@ -11,31 +16,768 @@ pub struct Config {
     /// Accepted chain ID (e.g., "ethereum")
     pub accepted_chain_id: String,
     /// Accepted contract address on external chain (e.g., "0xdac17f958d2ee523a2206206994597c13d831ec7" for USDT)
-    pub accepted_eth_contract: String,
+    pub accepted_eth_contract: ExternalAddress,
     /// Fixed price per 1 GNK in micro-USD (6 decimals, e.g., 25000 = $0.025/GNK)
     pub price_usd: Uint128,
     /// Native token denomination
     pub native_denom: String,
+    /// Decimals of the payout denoms; token amounts are scaled by 10^payout_decimals
+    #[serde(default = "default_payout_decimals")]
+    pub payout_decimals: u8,
+    /// Alternative payout denoms paid at the same price as the native denom
+    #[serde(default)]
+    pub alternative_payout_denoms: Vec<String>,
+    /// Vesting mode; when set, purchases fund a per-buyer vesting contract instead of the buyer
+    #[serde(default)]
+    pub vesting: Option<VestingConfig>,
+    /// Where purchase size and price bounds come from; None disables the checks
+    #[serde(default)]
+    pub purchase_limits: Option<PurchaseLimitsSource>,
+    /// Blocks a queued admin action waits before the crank can execute it. Zero until the
+    /// timelock is enabled; from then on the guarded actions can only be queued.
+    #[serde(default)]
+    pub timelock_delay_blocks: u64,
+    /// Native tokens paid to the Crank caller per processed item
+    #[serde(default)]
+    pub crank_reward: Uint128,
+    /// Blocks after a purchase commitment during which it can be revealed; 0 disables commit-reveal
+    #[serde(default)]
+    pub commit_reveal_window_blocks: u64,
+    /// Inference module consensus version the chain gRPC interface was pinned to at instantiate
+    #[serde(default)]
+    pub expected_module_version: Option<u64>,
+    /// cw721 contract minting a receipt NFT per purchase; this contract must be its minter
+    #[serde(default)]
+    pub receipt_nft_contract: Option<String>,
+    /// Require a valid inference-module participant attestation (KYC) for every buyer
+    #[serde(default)]
+    pub require_attestation: bool,
+    /// Enables the DebugGrpc query. Queries carry no sender, so admin control lives on this flag
+    #[serde(default)]
+    pub debug_grpc_enabled: bool,
+    /// Enables the ExportState query, gated the same way as DebugGrpc
+    #[serde(default)]
+    pub state_export_enabled: bool,
+    /// Share of each payment routed to validators; set by governance through sudo
+    #[serde(default)]
+    pub validator_kickback: Option<ValidatorKickback>,
+    /// Governance-set minimum price; purchases quoting a lower price are rejected
+    #[serde(default)]
+    pub price_floor_usd: Option<Uint128>,
+    /// Stop selling once this much stablecoin (micro-USD) has been collected; None is unlimited
+    #[serde(default)]
+    pub max_raise_usd: Option<Uint128>,
+    /// Stablecoin collected by completed purchases, in micro-USD
+    #[serde(default)]
+    pub total_raised_usd: Uint128,
+    /// Governance-set volume limit that pauses purchases when tripped; None disables it
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Bridge treasury proceeds out through the payment token's `Withdraw` instead of
+    /// forwarding the CW20 locally; None forwards locally
+    #[serde(default)]
+    pub proceeds_bridge: Option<ProceedsBridge>,
+    /// Hold proceeds and release them on a schedule through the crank; None forwards each
+    /// purchase's proceeds as it happens
+    #[serde(default)]
+    pub proceeds_stream: Option<ProceedsStream>,
+    /// Receives proceeds in place of the admin; resolved from `name_bindings`
+    #[serde(default)]
+    pub treasury: Option<String>,
+    /// Registry names the treasury and designated buyer are resolved from
+    #[serde(default)]
+    pub name_bindings: Option<NameBindings>,
+    /// Purchases above a USD threshold need a prior ApprovePurchase from a second role
+    #[serde(default)]
+    pub co_approval: Option<CoApproval>,
+    /// Payment token validated once when pinned; its payments skip the per-purchase chain and
+    /// bridge-info queries. None validates every payment token on every purchase
+    #[serde(default)]
+    pub trusted_payment_token: Option<String>,
     /// Whether contract is paused
     pub is_paused: bool,
     /// Total tokens sold
     pub total_tokens_sold: Uint128,
 }
 
+impl Config {
+    /// Default recipient of proceeds: the resolved treasury, else the admin. Rounds with their
+    /// own treasury keep it.
+    pub fn proceeds_recipient(&self) -> String {
+        self.treasury.clone().unwrap_or_else(|| self.admin.clone())
+    }
+}
+
+/// Config addresses kept in a name-registry contract. They are re-resolved at the start of an
+/// execution once `cache_blocks` have passed since the last resolution, so a rotation in the
+/// registry reaches the sale without an admin transaction.
+#[cw_serde]
+pub struct NameBindings {
+    pub registry: String,
+    /// Name of the proceeds treasury
+    pub treasury: Option<String>,
+    /// Name of the designated buyer
+    pub buyer: Option<String>,
+    /// Resolved addresses are reused for this many blocks (0 = every execution)
+    pub cache_blocks: u64,
+}
+
+/// Height the bound names were last resolved at
+pub const NAMES_RESOLVED_AT: Item<u64> = Item::new("names_resolved_at");
+
+/// Purchases of more than `threshold_usd` micro-USD only go through with an unexpired
+/// approval from `approver` covering them
+#[cw_serde]
+pub struct CoApproval {
+    pub approver: String,
+    pub threshold_usd: Uint128,
+}
+
+/// A buyer's approval for one large purchase of up to `max_usd`, usable through
+/// `expires_at_height`
+#[cw_serde]
+pub struct PurchaseApproval {
+    pub max_usd: Uint128,
+    pub expires_at_height: u64,
+    pub approved_by: String,
+}
+
+/// Buyer -> pending large-purchase approval; removed by the purchase it covers
+pub const PURCHASE_APPROVALS: Map<&str, PurchaseApproval> = Map::new("purchase_approvals");
+
+/// GNK uses 9 decimals (1 GNK = 1e9 ngonka)
+pub const DEFAULT_PAYOUT_DECIMALS: u8 = 9;
+/// Keeps 10^decimals times a micro-USD amount well inside Uint128
+pub const MAX_PAYOUT_DECIMALS: u8 = 18;
+
+fn default_payout_decimals() -> u8 {
+    DEFAULT_PAYOUT_DECIMALS
+}
+
+/// Basis points of every payment's stablecoin routed to `recipient` (the chain's fee collector
+/// module account, from which the distribution module pays validators)
+#[cw_serde]
+pub struct ValidatorKickback {
+    pub bps: u16,
+    pub recipient: String,
+}
+
+/// Upper bound for `ValidatorKickback::bps` (100%)
+pub const MAX_BPS: u16 = 10_000;
+
+/// Pauses purchases once the volume sold in the current window exceeds `max_multiple_bps` of
+/// the average of the preceding `trailing_windows` windows (and at least `min_volume_usd`)
+#[cw_serde]
+pub struct CircuitBreaker {
+    /// Window length in blocks
+    pub window_blocks: u64,
+    /// Completed windows averaged as the baseline
+    pub trailing_windows: u32,
+    /// Allowed window volume as basis points of the trailing average (30_000 = 3x)
+    pub max_multiple_bps: u32,
+    /// Window volume in micro-USD that never trips the breaker, so a quiet baseline does not
+    pub min_volume_usd: Uint128,
+}
+
+/// External-chain treasury that proceeds are bridged to. Payment tokens are wrapped-token
+/// contracts, whose `Withdraw` burns the CW20 and releases the original token there.
+#[cw_serde]
+pub struct ProceedsBridge {
+    /// Normalized address on the accepted chain
+    pub destination: ExternalAddress,
+}
+
+/// Payment-token contract -> proceeds held here after their bridge hop failed, awaiting
+/// FlushUnbridgedProceeds
+pub const UNBRIDGED_PROCEEDS: Map<&str, Uint128> = Map::new("unbridged_proceeds");
+
+/// Release schedule for held proceeds
+#[cw_serde]
+pub struct ProceedsStream {
+    pub interval_blocks: u64,
+    /// First height the crank releases held proceeds at; moves on by `interval_blocks` once
+    /// everything held has been released
+    pub next_release_height: u64,
+}
+
+/// (payment-token contract, recipient) -> proceeds held for the next scheduled release
+pub const STREAMED_PROCEEDS: Map<(&str, &str), Uint128> = Map::new("streamed_proceeds");
+
+/// Upper bound for `CircuitBreaker::trailing_windows`, keeping the per-purchase scan small
+pub const MAX_TRAILING_WINDOWS: u32 = 48;
+
+#[cw_serde]
+pub struct VestingConfig {
+    /// Code ID of the vesting contract instantiated per purchase
+    pub code_id: u64,
+    /// Seconds after purchase before anything unlocks
+    pub cliff_seconds: u64,
+    /// Seconds after purchase until everything is unlocked
+    pub duration_seconds: u64,
+}
+
+/// Purchase size and price bounds. Zero means unbounded.
+#[cw_serde]
+#[derive(Default)]
+pub struct PurchaseLimits {
+    /// Minimum purchase in micro-USD
+    pub min_purchase_usd: Uint128,
+    /// Maximum purchase in micro-USD
+    pub max_purchase_usd: Uint128,
+    /// Lowest price per GNK (micro-USD) purchases may execute at
+    pub min_price_usd: Uint128,
+    /// Highest price per GNK (micro-USD) purchases may execute at
+    pub max_price_usd: Uint128,
+}
+
+#[cw_serde]
+pub enum PurchaseLimitsSource {
+    /// Limits stored in contract config, managed by the admin
+    Config(PurchaseLimits),
+    /// Limits read from inference module params at execution time, managed by governance
+    ChainParams {},
+}
+
 /// Contract configuration
 pub const CONFIG: Item<Config> = Item::new("config");
 
+/// Config layout of the v1 single-buyer release. Its JSON only parses into this struct while
+/// no newer field has been written, which is how migrate detects v1 state.
+#[cw_serde]
+pub struct ConfigV1 {
+    pub admin: String,
+    pub buyer: String,
+    pub accepted_chain_id: String,
+    pub accepted_eth_contract: String,
+    pub price_usd: Uint128,
+    pub native_denom: String,
+    pub is_paused: bool,
+    pub total_tokens_sold: Uint128,
+}
+
+/// Backfill steps run by ContinueMigration after a v1 migration, in order
+#[cw_serde]
+pub enum MigrationStage {
+    /// Seed per-denom sales accounting from the v1 `total_tokens_sold`
+    DenomAccounting,
+    /// Restore the pause state the sale had before migrating
+    RestorePause,
+    Complete,
+}
+
+#[cw_serde]
+pub struct MigrationProgress {
+    pub from_version: String,
+    pub stage: MigrationStage,
+    /// Whether the sale was paused before migrating; it stays paused until the backfill completes
+    pub was_paused: bool,
+    pub started_at_height: u64,
+}
+
+pub const MIGRATION: Item<MigrationProgress> = Item::new("migration");
+
+/// Vesting contracts per buyer: (buyer, purchase index) -> vesting contract address
+pub const VESTING_CONTRACTS: Map<(&str, u64), String> = Map::new("vesting_contracts");
+/// (unlock time in seconds, vesting contract): the cliff and end of each vesting contract, when
+/// the crank releases what has vested to the beneficiary
+pub const VESTING_UNLOCKS: Map<(u64, &str), ()> = Map::new("vesting_unlocks");
+
+/// Whitelisted buyer allocation, in micro-USD
+#[cw_serde]
+pub struct WhitelistEntry {
+    pub allocation_usd: Uint128,
+    pub purchased_usd: Uint128,
+}
+
+/// Progress of a batched whitelist import
+#[cw_serde]
+#[derive(Default)]
+pub struct WhitelistImportProgress {
+    /// Index the next ImportWhitelist batch must carry
+    pub next_batch: u64,
+    pub total_imported: u64,
+    /// Last address imported, to resume an interrupted import
+    pub last_address: Option<String>,
+}
+
+/// Maximum entries accepted in a single ImportWhitelist message
+pub const MAX_WHITELIST_BATCH: usize = 500;
+
+/// Whitelisted buyers (in addition to the designated buyer), keyed by address
+pub const WHITELIST: Map<&str, WhitelistEntry> = Map::new("whitelist");
+
+pub const WHITELIST_IMPORT: Item<WhitelistImportProgress> = Item::new("whitelist_import");
+
+/// Admin actions that can be queued behind the timelock and executed by the crank
+#[cw_serde]
+pub enum TimelockedAction {
+    UpdatePrice { price_usd: Uint128 },
+    UpdateBuyer { buyer: String },
+    WithdrawNativeTokens {
+        amount: Uint128,
+        recipient: String,
+        denom: Option<String>,
+    },
+    /// Adjust `total_tokens_sold` by `delta` to reconcile settlements made outside the contract;
+    /// `reason` is kept in the audit log
+    CorrectAccounting { delta: Int128, reason: String },
+    /// Change the timelock delay itself, at least `MIN_TIMELOCK_DELAY_BLOCKS`
+    UpdateTimelockDelay { blocks: u64 },
+}
+
+/// Shortest timelock delay; once enabled, the timelock cannot be shortened below it or turned off
+pub const MIN_TIMELOCK_DELAY_BLOCKS: u64 = 100;
+
+/// Longest reason a CorrectAccounting action may carry
+pub const MAX_CORRECTION_REASON_LEN: usize = 256;
+
+#[cw_serde]
+pub struct QueuedAction {
+    pub id: u64,
+    pub action: TimelockedAction,
+    /// First block height at which the crank may execute the action
+    pub eta_height: u64,
+    pub queued_at_height: u64,
+}
+
+/// Timelock queue ordered by (eta_height, id) so the crank only scans due actions
+pub const TIMELOCK_QUEUE: Map<(u64, u64), QueuedAction> = Map::new("timelock_queue");
+/// Queued action id -> eta_height, for cancellation by id
+pub const TIMELOCK_ETA: Map<u64, u64> = Map::new("timelock_eta");
+pub const NEXT_TIMELOCK_ID: Item<u64> = Item::new("next_timelock_id");
+
+/// Tokens sold per payout denom (native denom included)
+pub const TOKENS_SOLD_BY_DENOM: Map<&str, Uint128> = Map::new("tokens_sold_by_denom");
+
+/// Micro-USD raised per CW20 payment token
+pub const RAISED_BY_TOKEN: Map<&str, Uint128> = Map::new("raised_by_token");
+/// Buyers with at least one completed purchase
+pub const PURCHASERS: Map<&str, ()> = Map::new("purchasers");
+pub const UNIQUE_BUYERS: Item<u64> = Item::new("unique_buyers");
+/// Micro-USD returned to payers: purchase change and dust, reservation and lottery refunds
+pub const REFUNDED_USD: Item<Uint128> = Item::new("refunded_usd");
+
+#[cw_serde]
+pub struct TokenRaised {
+    pub cw20_contract: String,
+    pub raised_usd: Uint128,
+}
+
+/// Outcome of the sale, written once by FinalizeSale
+#[cw_serde]
+pub struct FinalReport {
+    pub finalized_height: u64,
+    pub raised_by_token: Vec<TokenRaised>,
+    pub total_raised_usd: Uint128,
+    /// Base units sold across all payout denoms
+    pub total_tokens_sold: Uint128,
+    pub unique_buyers: u64,
+    /// Micro-USD per whole token, over everything sold
+    pub average_price_usd: Uint128,
+    pub refunded_usd: Uint128,
+    /// Unreserved native tokens sent to `swept_to` at finalization
+    pub leftover_swept: Uint128,
+    pub swept_to: String,
+}
+
+pub const FINAL_REPORT: Item<FinalReport> = Item::new("final_report");
+
+/// Per-token price overrides: CW20 contract -> price per GNK in micro-units of that token.
+/// Tokens without an entry use `Config::price_usd`.
+pub const TOKEN_PRICES: Map<&str, Uint128> = Map::new("token_prices");
+
+/// Negotiated per-buyer prices: buyer -> price per GNK in micro-USD. Applies ahead of round,
+/// per-token and default prices.
+pub const BUYER_PRICES: Map<&str, Uint128> = Map::new("buyer_prices");
+
+/// Price locked by a purchase commitment
+#[cw_serde]
+pub struct PurchaseCommitment {
+    pub cw20_contract: String,
+    pub price: Uint128,
+    pub committed_at_height: u64,
+    /// Native deposit held in `RESERVES` until the commitment is revealed or expires
+    #[serde(default)]
+    pub deposit: Uint128,
+    /// Last block the commitment can be revealed in; None for commitments made before it was
+    /// fixed at commit time, which expire with the current window
+    #[serde(default)]
+    pub expires_at_height: Option<u64>,
+}
+
+/// Native deposit CommitPurchase takes (1 GNK): returned on reveal, forfeited to the admin
+/// when the crank expires the commitment, so locking a price is not a free option
+pub const PURCHASE_COMMITMENT_DEPOSIT: u128 = 1_000_000_000;
+
+/// (buyer, sha256(amount_be_bytes ‖ salt)) -> commitment
+pub const PURCHASE_COMMITMENTS: Map<(&str, &[u8]), PurchaseCommitment> = Map::new("purchase_commitments");
+/// (expires_at_height, buyer, commitment) index the crank expires commitments by
+pub const PURCHASE_COMMITMENT_EXPIRIES: Map<(u64, &str, &[u8]), ()> = Map::new("purchase_commitment_expiries");
+
+/// Price locked for a buyer by RequestQuote. Single use; expired quotes are removed by the crank.
+#[cw_serde]
+pub struct Quote {
+    pub buyer: String,
+    /// Payment token the price is quoted in; None quotes the default price for any accepted token
+    pub cw20_contract: Option<String>,
+    pub price: Uint128,
+    /// Last block height a purchase may use the quote at
+    pub expires_at_height: u64,
+}
+
+/// How long a quote stays valid (about ten minutes at 6s blocks)
+pub const QUOTE_VALIDITY_BLOCKS: u64 = 100;
+
+pub const QUOTES: Map<u64, Quote> = Map::new("quotes");
+pub const NEXT_QUOTE_ID: Item<u64> = Item::new("next_quote_id");
+/// (expires_at_height, quote id), so the crank finds expired quotes without a scan
+pub const QUOTE_EXPIRIES: Map<(u64, u64), ()> = Map::new("quote_expiries");
+
+/// Commitment hash a buyer submits before revealing a purchase of `amount` with `salt`
+pub fn purchase_commitment_hash(amount: Uint128, salt: &[u8]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(amount.u128().to_be_bytes());
+    hasher.update(salt);
+    hasher.finalize().to_vec()
+}
+
+/// Message a buyer signs to authorize a relayed purchase. Binding the chain, sale contract and
+/// payment token keeps a signature from being replayed elsewhere; binding the rest of the
+/// purchase payload keeps the relayer from changing what the payment buys.
+#[cw_serde]
+pub struct PurchaseIntentSignDoc {
+    pub chain_id: String,
+    pub contract: String,
+    pub cw20_contract: String,
+    pub buyer: String,
+    pub amount: Uint128,
+    pub nonce: u64,
+    pub deadline_height: u64,
+    /// This and the fields below are omitted from the JSON when None, so intents that leave
+    /// them unset hash as before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_out: Option<Uint128>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payout_denom: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exact_out: Option<PurchaseExactOut>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt: Option<Binary>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sale_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reservation_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote_id: Option<u64>,
+}
+
+/// sha256 of the sign doc's JSON, the digest the buyer's signature covers
+pub fn purchase_intent_hash(doc: &PurchaseIntentSignDoc) -> StdResult<Vec<u8>> {
+    Ok(Sha256::digest(to_json_vec(doc)?).to_vec())
+}
+
+/// Next expected signed-intent nonce per buyer
+pub const PURCHASE_NONCES: Map<&str, u64> = Map::new("purchase_nonces");
+
+/// Sequence for receipt NFT token ids
+pub const NEXT_RECEIPT_ID: Item<u64> = Item::new("next_receipt_id");
+
+/// One administrative action. `hash` is sha256(previous hash ‖ JSON of this entry with an
+/// empty hash), chaining the log so any rewrite of earlier entries is detectable.
+#[cw_serde]
+pub struct AuditEntry {
+    pub id: u64,
+    pub actor: String,
+    pub action: String,
+    pub height: u64,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub hash: Binary,
+}
+
+/// Append-only audit log of admin actions
+pub const AUDIT_LOG: Map<u64, AuditEntry> = Map::new("audit_log");
+pub const NEXT_AUDIT_ID: Item<u64> = Item::new("next_audit_id");
+
+/// One change of the default price or a per-token price, in micro-USD per GNK
+#[cw_serde]
+pub struct PriceChange {
+    pub id: u64,
+    /// Payment token whose override changed; None for the default price
+    pub cw20_contract: Option<String>,
+    /// None when the token had no override (or, for the first entry, before instantiate)
+    pub old_price_usd: Option<Uint128>,
+    /// None when the token's override was removed and it falls back to the default price
+    pub new_price_usd: Option<Uint128>,
+    pub actor: String,
+    pub height: u64,
+}
+
+/// Append-only history of price changes, starting with the instantiate price
+pub const PRICE_HISTORY: Map<u64, PriceChange> = Map::new("price_history");
+pub const NEXT_PRICE_CHANGE_ID: Item<u64> = Item::new("next_price_change_id");
+
+/// Lottery sale round. While `seed` is None, payments are recorded as commitments instead of
+/// purchases; once finalized, each entrant claims tokens for the amount won and a refund of the rest.
+#[cw_serde]
+pub struct Lottery {
+    /// Last block accepting commitments
+    pub commit_end_height: u64,
+    /// Total micro-USD sold to winners
+    pub capacity_usd: Uint128,
+    /// Price snapshot taken when the lottery started
+    pub price_usd: Uint128,
+    pub total_committed_usd: Uint128,
+    pub entries: u32,
+    /// Entries that have not claimed yet; the lottery is removed when this reaches zero
+    pub unclaimed: u32,
+    /// Randomness the winners were drawn with; set at finalization
+    pub seed: Option<Binary>,
+    /// Last block entrants can claim in before the crank sweeps the rest; set at finalization
+    #[serde(default)]
+    pub claim_deadline_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct LotteryEntry {
+    /// CW20 the commitment was paid in; refunds and proceeds move in the same token
+    pub cw20_contract: String,
+    pub committed_usd: Uint128,
+    pub won_usd: Uint128,
+}
+
+/// Entrants are capped so finalization (quadratic in entries) fits in a block
+pub const MAX_LOTTERY_ENTRIES: u32 = 200;
+/// Blocks entrants have to claim after finalization (about a week at 6s blocks)
+pub const LOTTERY_CLAIM_BLOCKS: u64 = 100_800;
+
+pub const LOTTERY: Item<Lottery> = Item::new("lottery");
+pub const LOTTERY_ENTRIES: Map<&str, LotteryEntry> = Map::new("lottery_entries");
+
+/// A scheduled sale round with its own price and proceeds recipient. Once any round exists,
+/// purchases are only accepted while one is active.
+#[cw_serde]
+pub struct SaleRound {
+    pub name: String,
+    /// First block of the round
+    pub start_height: u64,
+    /// First block after the round
+    pub end_height: u64,
+    /// Price per GNK in micro-USD during the round
+    pub price_usd: Uint128,
+    /// Receives the round's proceeds; the admin when None
+    pub treasury: Option<String>,
+    pub raised_usd: Uint128,
+    pub tokens_sold: Uint128,
+    /// Set by the crank once the round has ended
+    #[serde(default)]
+    pub finished: bool,
+}
+
+/// Rounds are scanned on every purchase, so their number is bounded
+pub const MAX_ROUNDS: usize = 20;
+
+pub const ROUNDS: Map<u32, SaleRound> = Map::new("rounds");
+pub const NEXT_ROUND_ID: Item<u32> = Item::new("next_round_id");
+
+/// Sale terms for one inference epoch, set in advance. The price applies when no round is
+/// active; the cap bounds the micro-USD raised during the epoch.
+#[cw_serde]
+pub struct EpochSale {
+    pub price_usd: Option<Uint128>,
+    pub cap_usd: Option<Uint128>,
+}
+
+/// Entries accepted by one SetEpochSchedule
+pub const MAX_EPOCH_SCHEDULE_BATCH: usize = 100;
+
+/// Inference epoch -> its sale terms. While empty, purchases never query the epoch.
+pub const EPOCH_SCHEDULE: Map<u64, EpochSale> = Map::new("epoch_schedule");
+/// Inference epoch -> micro-USD raised under its scheduled terms
+pub const EPOCH_RAISED: Map<u64, Uint128> = Map::new("epoch_raised");
+
+/// An independent sale run from the same contract. Sales share the payment token, payout denom
+/// and pause switch with the main sale; buyers, price and inventory are their own.
+#[cw_serde]
+pub struct Sale {
+    pub name: String,
+    /// Price per GNK in micro-USD
+    pub price_usd: Uint128,
+    /// Payout tokens allotted to the sale
+    pub inventory: Uint128,
+    pub sold: Uint128,
+    pub raised_usd: Uint128,
+    pub closed: bool,
+    pub created_at_height: u64,
+}
+
+pub const SALES: Map<u64, Sale> = Map::new("sales");
+pub const NEXT_SALE_ID: Item<u64> = Item::new("next_sale_id");
+/// Buyers allowed in each sale, keyed by (sale id, buyer)
+pub const SALE_BUYERS: Map<(u64, &str), ()> = Map::new("sale_buyers");
+
+/// A labeled share of the main sale's inventory with its own buyers. Once any pool exists,
+/// main-sale purchases must draw from a pool the buyer is eligible for.
+#[cw_serde]
+pub struct InventoryPool {
+    /// Payout tokens the pool may sell
+    pub cap: Uint128,
+    pub sold: Uint128,
+    pub raised_usd: Uint128,
+    pub purchases: u64,
+}
+
+pub const MAX_POOLS: usize = 16;
+pub const MAX_POOL_LABEL_LEN: usize = 32;
+
+pub const POOLS: Map<&str, InventoryPool> = Map::new("pools");
+/// Buyers eligible for each pool, keyed by (label, buyer)
+pub const POOL_BUYERS: Map<(&str, &str), ()> = Map::new("pool_buyers");
+/// Label of the pool the last purchase drew from; the next purchase tries the pools after it
+pub const POOL_CURSOR: Item<String> = Item::new("pool_cursor");
+
+/// A purchase funded by several CW20 payments (e.g. W(USDT) and W(USDC), each counted 1:1 as
+/// micro-USD). It settles when `usd_amount` has arrived; before that the buyer, or anyone
+/// after `deadline_height`, can cancel it for a refund.
+#[cw_serde]
+pub struct Reservation {
+    pub buyer: String,
+    pub usd_amount: Uint128,
+    /// Micro-USD credited so far, payments in a priced token valued at the default price
+    pub funded_usd: Uint128,
+    pub deadline_height: u64,
+    /// Funding received so far, per CW20
+    pub payments: Vec<ReservationPayment>,
+    /// Native tokens held back in `RESERVES` for this reservation, released when it settles or
+    /// is cancelled
+    #[serde(default)]
+    pub reserved_tokens: Uint128,
+}
+
+#[cw_serde]
+pub struct ReservationPayment {
+    pub cw20_contract: String,
+    pub amount: Uint128,
+}
+
+/// Longest a reservation may stay open (about a day at 6s blocks)
+pub const MAX_RESERVATION_BLOCKS: u64 = 14_400;
+
+pub const RESERVATIONS: Map<u64, Reservation> = Map::new("reservations");
+pub const NEXT_RESERVATION_ID: Item<u64> = Item::new("next_reservation_id");
+/// (deadline_height, reservation id), so the crank finds expired reservations without a scan
+pub const RESERVATION_EXPIRIES: Map<(u64, u64), ()> = Map::new("reservation_expiries");
+
+/// Native tokens owed to buyers but not paid out yet, per obligation. Withdrawals cannot dip
+/// into their sum.
+pub const RESERVES: Map<&str, Uint128> = Map::new("reserves");
+/// Lottery wins awaiting ClaimLottery
+pub const RESERVE_LOTTERY: &str = "lottery";
+/// Open reservations, valued at the default price when reserved
+pub const RESERVE_RESERVATIONS: &str = "reservations";
+/// Deposits of unrevealed purchase commitments
+pub const RESERVE_COMMITMENTS: &str = "commitments";
+
+/// Obligation holding back the unsold inventory of an open CreateSale sale
+pub fn sale_reserve(id: u64) -> String {
+    format!("sale/{}", id)
+}
+
+/// Native funds deposited into the sale inventory: (depositor, denom) -> total amount
+pub const DEPOSITS: Map<(&str, &str), Uint128> = Map::new("deposits");
+
+/// Recurring purchase of `usd_amount` every `interval_blocks`, paid by pulling `cw20_contract`
+/// from the buyer's allowance to this contract
+#[cw_serde]
+pub struct Subscription {
+    pub id: u64,
+    pub buyer: String,
+    pub cw20_contract: String,
+    /// Micro-USD spent per purchase
+    pub usd_amount: Uint128,
+    pub interval_blocks: u64,
+    /// First height ProcessSubscriptions will purchase at
+    pub next_due_height: u64,
+    /// Paused subscriptions keep their settings but are skipped until resumed
+    pub paused: bool,
+    /// Purchases completed so far
+    pub purchases: u64,
+    /// Periods skipped in a row; a purchase resets it
+    #[serde(default)]
+    pub skips: u32,
+}
+
+/// Shortest interval a subscription may use
+pub const MIN_SUBSCRIPTION_INTERVAL_BLOCKS: u64 = 100;
+/// Most subscriptions one buyer may hold, paused ones included
+pub const MAX_SUBSCRIPTIONS_PER_BUYER: u32 = 5;
+/// Periods a subscription may skip in a row before ProcessSubscriptions cancels it
+pub const MAX_SUBSCRIPTION_SKIPS: u32 = 3;
+/// Buyer -> number of subscriptions they hold
+pub const BUYER_SUBSCRIPTIONS: Map<&str, u32> = Map::new("buyer_subscriptions");
+
+pub const SUBSCRIPTIONS: Map<u64, Subscription> = Map::new("subscriptions");
+pub const NEXT_SUBSCRIPTION_ID: Item<u64> = Item::new("next_subscription_id");
+/// (next due height, id) of active subscriptions, for ProcessSubscriptions
+pub const SUBSCRIPTION_DUE: Map<(u64, u64), ()> = Map::new("subscription_due");
+
+/// Block height of the most recent successful purchase
+pub const LAST_PURCHASE_HEIGHT: Item<u64> = Item::new("last_purchase_height");
+
+/// Micro-USD sold per circuit-breaker window (height / window_blocks); only kept while a
+/// breaker is configured, and pruned once older than its trailing windows
+pub const WINDOW_VOLUME: Map<u64, Uint128> = Map::new("window_volume");
+
+fn payout_scale(payout_decimals: u8) -> Uint128 {
+    Uint128::from(10u128.pow(payout_decimals as u32))
+}
+
+/// Micro-USD per whole token paid on average for `tokens_sold` base units
+pub fn average_price_usd(raised_usd: Uint128, tokens_sold: Uint128, payout_decimals: u8) -> Uint128 {
+    if tokens_sold.is_zero() {
+        return Uint128::zero();
+    }
+    raised_usd.multiply_ratio(payout_scale(payout_decimals), tokens_sold)
+}
+
 /// Calculate how many tokens can be bought with given USD amount at fixed price
-pub fn calculate_tokens_for_usd(usd_amount: Uint128, price_per_token: Uint128) -> Uint128 {
+pub fn calculate_tokens_for_usd(usd_amount: Uint128, price_per_token: Uint128, payout_decimals: u8) -> Uint128 {
     if price_per_token.is_zero() {
         return Uint128::zero();
     }
     // usd_amount has 6 decimals, price_per_token has 6 decimals
-    // Result should be in token units (9 decimals)
-    // Scale by 1e9 to get 9-decimal tokens
+    // Scale by 10^payout_decimals to get base units of the payout denom
     usd_amount
-        .checked_mul(Uint128::from(1_000_000_000u128))
+        .checked_mul(payout_scale(payout_decimals))
         .unwrap_or(Uint128::zero())
         .checked_div(price_per_token)
         .unwrap_or(Uint128::zero())
 }
+
+/// Split a payment into the tokens it buys and what they cost. The difference is dust: less
+/// than one base unit of the payout denom is worth, so it buys nothing.
+pub fn split_usd_payment(
+    usd_amount: Uint128,
+    price_per_token: Uint128,
+    payout_decimals: u8,
+) -> Result<(Uint128, Uint128), OverflowError> {
+    let tokens = calculate_tokens_for_usd(usd_amount, price_per_token, payout_decimals);
+    let cost = calculate_usd_for_tokens(tokens, price_per_token, payout_decimals)?;
+    Ok((tokens, cost.min(usd_amount)))
+}
+
+/// Calculate the USD amount needed to buy `tokens` at fixed price, rounded up
+pub fn calculate_usd_for_tokens(
+    tokens: Uint128,
+    price_per_token: Uint128,
+    payout_decimals: u8,
+) -> Result<Uint128, OverflowError> {
+    let scale = payout_scale(payout_decimals);
+    let numerator = tokens.checked_mul(price_per_token)?;
+    let usd = numerator / scale;
+    if (numerator % scale).is_zero() {
+        Ok(usd)
+    } else {
+        usd.checked_add(Uint128::one())
+    }
+}
@@ -0,0 +1,509 @@
+use cosmwasm_std::{
+    entry_point, to_json_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Uint128,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, LockedResponse, LockupInfo, LockupsResponse, QueryMsg,
+    UnlockedResponse,
+};
+use crate::state::{
+    Config, Lockup, UnlockSchedule, CONFIG, LOCKUPS, LOCKUP_COUNT, MAX_LOCKUPS_PER_ADDRESS, NEXT_LOCKUP_ID,
+    WRITERS,
+};
+
+const CONTRACT_NAME: &str = "lockup-registry";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
+        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
+
+    let admin = deps.api.addr_validate(&msg.admin)?;
+    CONFIG.save(deps.storage, &Config { admin: admin.clone() })?;
+    for writer in &msg.writers {
+        WRITERS.save(deps.storage, &deps.api.addr_validate(writer)?, &())?;
+    }
+    NEXT_LOCKUP_ID.save(deps.storage, &0)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", admin)
+        .add_attribute("writers", msg.writers.len().to_string()))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::AddLockup { token, address, amount, schedule } => {
+            add_lockup(deps, env, info, token, address, amount, schedule)
+        }
+        ExecuteMsg::RemoveLockup { token, address, id } => remove_lockup(deps, info, token, address, id),
+        ExecuteMsg::PruneUnlocked { token, address } => prune_unlocked(deps, env, token, address),
+        ExecuteMsg::UpdateWriters { add, remove } => update_writers(deps, info, add, remove),
+        ExecuteMsg::UpdateAdmin { admin } => update_admin(deps, info, admin),
+    }
+}
+
+fn ensure_admin(deps: Deps, sender: &Addr) -> Result<Config, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if *sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(config)
+}
+
+fn validate_schedule(env: &Env, schedule: &UnlockSchedule) -> Result<(), ContractError> {
+    if let UnlockSchedule::Linear { start_height, end_height } = *schedule {
+        if start_height >= end_height {
+            return Err(ContractError::InvalidSchedule {
+                reason: "start_height must be below end_height".to_string(),
+            });
+        }
+    }
+    if schedule.end_height() <= env.block.height {
+        return Err(ContractError::InvalidSchedule {
+            reason: "schedule is already fully unlocked".to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn add_lockup(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    address: String,
+    amount: Uint128,
+    schedule: UnlockSchedule,
+) -> Result<Response, ContractError> {
+    if !WRITERS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    validate_schedule(&env, &schedule)?;
+    let token = deps.api.addr_validate(&token)?;
+    let addr = deps.api.addr_validate(&address)?;
+
+    let count = LOCKUP_COUNT.may_load(deps.storage, (&token, &addr))?.unwrap_or_default();
+    if count >= MAX_LOCKUPS_PER_ADDRESS {
+        return Err(ContractError::TooManyLockups {
+            token: token.to_string(),
+            address: addr.to_string(),
+            max: MAX_LOCKUPS_PER_ADDRESS,
+        });
+    }
+    LOCKUP_COUNT.save(deps.storage, (&token, &addr), &(count + 1))?;
+
+    let id = NEXT_LOCKUP_ID.load(deps.storage)?;
+    NEXT_LOCKUP_ID.save(deps.storage, &(id + 1))?;
+    LOCKUPS.save(
+        deps.storage,
+        (&token, &addr, id),
+        &Lockup {
+            writer: info.sender.clone(),
+            amount,
+            schedule,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_lockup")
+        .add_attribute("writer", info.sender)
+        .add_attribute("token", token)
+        .add_attribute("address", addr)
+        .add_attribute("lockup_id", id.to_string())
+        .add_attribute("amount", amount))
+}
+
+fn remove_lockup(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: String,
+    address: String,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let token = deps.api.addr_validate(&token)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let lockup = LOCKUPS
+        .may_load(deps.storage, (&token, &addr, id))?
+        .ok_or_else(|| ContractError::LockupNotFound {
+            token: token.to_string(),
+            address: addr.to_string(),
+            id,
+        })?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != lockup.writer && info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    LOCKUPS.remove(deps.storage, (&token, &addr, id));
+    decrement_count(deps, &token, &addr, 1)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_lockup")
+        .add_attribute("sender", info.sender)
+        .add_attribute("token", token)
+        .add_attribute("address", addr)
+        .add_attribute("lockup_id", id.to_string()))
+}
+
+fn prune_unlocked(deps: DepsMut, env: Env, token: String, address: String) -> Result<Response, ContractError> {
+    let token = deps.api.addr_validate(&token)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let unlocked_ids = LOCKUPS
+        .prefix((&token, &addr))
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok((id, lockup)) if lockup.schedule.end_height() <= env.block.height => Some(Ok(id)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    for id in &unlocked_ids {
+        LOCKUPS.remove(deps.storage, (&token, &addr, *id));
+    }
+    decrement_count(deps, &token, &addr, unlocked_ids.len() as u32)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "prune_unlocked")
+        .add_attribute("token", token)
+        .add_attribute("address", addr)
+        .add_attribute("pruned", unlocked_ids.len().to_string()))
+}
+
+fn decrement_count(deps: DepsMut, token: &Addr, addr: &Addr, by: u32) -> StdResult<()> {
+    let count = LOCKUP_COUNT.may_load(deps.storage, (token, addr))?.unwrap_or_default().saturating_sub(by);
+    if count == 0 {
+        LOCKUP_COUNT.remove(deps.storage, (token, addr));
+        Ok(())
+    } else {
+        LOCKUP_COUNT.save(deps.storage, (token, addr), &count)
+    }
+}
+
+fn update_writers(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    ensure_admin(deps.as_ref(), &info.sender)?;
+    for writer in &add {
+        WRITERS.save(deps.storage, &deps.api.addr_validate(writer)?, &())?;
+    }
+    for writer in &remove {
+        WRITERS.remove(deps.storage, &deps.api.addr_validate(writer)?);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_writers")
+        .add_attribute("added", add.len().to_string())
+        .add_attribute("removed", remove.len().to_string()))
+}
+
+fn update_admin(deps: DepsMut, info: MessageInfo, admin: String) -> Result<Response, ContractError> {
+    let mut config = ensure_admin(deps.as_ref(), &info.sender)?;
+    config.admin = deps.api.addr_validate(&admin)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_admin")
+        .add_attribute("admin", config.admin))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Locked { token, address, at_height } => {
+            let at_height = at_height.unwrap_or(env.block.height);
+            let (_, locked) = lockup_totals(deps, &token, &address, at_height)?;
+            to_json_binary(&LockedResponse { token, address, locked, at_height })
+        }
+        QueryMsg::Unlocked { token, address, at_height } => {
+            let at_height = at_height.unwrap_or(env.block.height);
+            let (total, locked) = lockup_totals(deps, &token, &address, at_height)?;
+            to_json_binary(&UnlockedResponse {
+                token,
+                address,
+                unlocked: total - locked,
+                at_height,
+            })
+        }
+        QueryMsg::Lockups { token, address, start_after, limit } => {
+            to_json_binary(&query_lockups(deps, token, address, start_after, limit)?)
+        }
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let writers = WRITERS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|addr| addr.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ConfigResponse {
+        admin: config.admin.to_string(),
+        writers,
+    })
+}
+
+/// Returns (total registered, locked at `height`) over all `token` lockups of `address`
+fn lockup_totals(deps: Deps, token: &str, address: &str, height: u64) -> StdResult<(Uint128, Uint128)> {
+    let token = deps.api.addr_validate(token)?;
+    let addr = deps.api.addr_validate(address)?;
+    LOCKUPS
+        .prefix((&token, &addr))
+        .range(deps.storage, None, None, Order::Ascending)
+        .try_fold((Uint128::zero(), Uint128::zero()), |(total, locked), item| {
+            let (_, lockup) = item?;
+            Ok((
+                total.checked_add(lockup.amount)?,
+                locked.checked_add(lockup.schedule.locked_at(lockup.amount, height))?,
+            ))
+        })
+}
+
+fn query_lockups(
+    deps: Deps,
+    token: String,
+    address: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<LockupsResponse> {
+    let token = deps.api.addr_validate(&token)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let lockups = LOCKUPS
+        .prefix((&token, &addr))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|(id, lockup)| LockupInfo {
+                id,
+                writer: lockup.writer.to_string(),
+                amount: lockup.amount,
+                schedule: lockup.schedule,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(LockupsResponse { lockups })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::from_json;
+
+    fn setup(deps: DepsMut, api: &MockApi) {
+        instantiate(
+            deps,
+            mock_env(),
+            MessageInfo { sender: api.addr_make("creator"), funds: vec![] },
+            InstantiateMsg {
+                admin: api.addr_make("admin").to_string(),
+                writers: vec![api.addr_make("sale").to_string()],
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn linear_lockup_releases_over_time() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let env = mock_env();
+        let token = api.addr_make("wrapped-eth").to_string();
+        let holder = api.addr_make("holder").to_string();
+        let start = env.block.height + 100;
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("attacker"), funds: vec![] },
+            ExecuteMsg::AddLockup {
+                token: token.clone(),
+                address: holder.clone(),
+                amount: Uint128::from(1000u128),
+                schedule: UnlockSchedule::Cliff { at_height: start },
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("sale"), funds: vec![] },
+            ExecuteMsg::AddLockup {
+                token: token.clone(),
+                address: holder.clone(),
+                amount: Uint128::from(1000u128),
+                schedule: UnlockSchedule::Linear { start_height: start, end_height: start + 100 },
+            },
+        )
+        .unwrap();
+
+        let locked_at = |deps: Deps, height: Option<u64>| -> LockedResponse {
+            from_json(
+                query(
+                    deps,
+                    env.clone(),
+                    QueryMsg::Locked { token: token.clone(), address: holder.clone(), at_height: height },
+                )
+                .unwrap(),
+            )
+            .unwrap()
+        };
+        assert_eq!(locked_at(deps.as_ref(), None).locked, Uint128::from(1000u128));
+        assert_eq!(locked_at(deps.as_ref(), Some(start + 25)).locked, Uint128::from(750u128));
+        assert_eq!(locked_at(deps.as_ref(), Some(start + 100)).locked, Uint128::zero());
+
+        let unlocked: UnlockedResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Unlocked { token, address: holder, at_height: Some(start + 25) },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(unlocked.unlocked, Uint128::from(250u128));
+    }
+
+    #[test]
+    fn prune_removes_only_fully_unlocked() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let mut env = mock_env();
+        let token = api.addr_make("wrapped-eth").to_string();
+        let holder = api.addr_make("holder").to_string();
+        let sale = MessageInfo { sender: api.addr_make("sale"), funds: vec![] };
+
+        for at_height in [env.block.height + 10, env.block.height + 20] {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                sale.clone(),
+                ExecuteMsg::AddLockup {
+                    token: token.clone(),
+                    address: holder.clone(),
+                    amount: Uint128::from(500u128),
+                    schedule: UnlockSchedule::Cliff { at_height },
+                },
+            )
+            .unwrap();
+        }
+
+        env.block.height += 10;
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            sale,
+            ExecuteMsg::PruneUnlocked { token: token.clone(), address: holder.clone() },
+        )
+        .unwrap();
+
+        let lockups: LockupsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::Lockups { token, address: holder, start_after: None, limit: None },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(lockups.lockups.len(), 1);
+        assert_eq!(lockups.lockups[0].id, 1);
+    }
+
+    #[test]
+    fn lockups_only_restrict_their_own_token() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let env = mock_env();
+        let weth = api.addr_make("wrapped-eth").to_string();
+        let wusdc = api.addr_make("wrapped-usdc").to_string();
+        let holder = api.addr_make("holder").to_string();
+        let sale = MessageInfo { sender: api.addr_make("sale"), funds: vec![] };
+        let lock = |token: &str, amount: u128| ExecuteMsg::AddLockup {
+            token: token.to_string(),
+            address: holder.clone(),
+            amount: Uint128::from(amount),
+            schedule: UnlockSchedule::Cliff { at_height: env.block.height + 10 },
+        };
+
+        execute(deps.as_mut(), env.clone(), sale.clone(), lock(&weth, 1000)).unwrap();
+        let locked = |deps: Deps, token: &str| -> Uint128 {
+            let resp: LockedResponse = from_json(
+                query(
+                    deps,
+                    env.clone(),
+                    QueryMsg::Locked { token: token.to_string(), address: holder.clone(), at_height: None },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(resp.token, token);
+            resp.locked
+        };
+        assert_eq!(locked(deps.as_ref(), &weth), Uint128::from(1000u128));
+        assert_eq!(locked(deps.as_ref(), &wusdc), Uint128::zero());
+
+        execute(deps.as_mut(), env.clone(), sale.clone(), lock(&wusdc, 300)).unwrap();
+        assert_eq!(locked(deps.as_ref(), &weth), Uint128::from(1000u128));
+        assert_eq!(locked(deps.as_ref(), &wusdc), Uint128::from(300u128));
+
+        // Lockup ids are global, so a lockup is only found under the token it was registered for
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            sale.clone(),
+            ExecuteMsg::RemoveLockup { token: wusdc.clone(), address: holder.clone(), id: 0 },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::LockupNotFound { id: 0, .. }));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            sale.clone(),
+            ExecuteMsg::RemoveLockup { token: weth.clone(), address: holder.clone(), id: 0 },
+        )
+        .unwrap();
+        assert_eq!(locked(deps.as_ref(), &weth), Uint128::zero());
+        assert_eq!(locked(deps.as_ref(), &wusdc), Uint128::from(300u128));
+
+        // The per-address cap is counted per token
+        for _ in 0..MAX_LOCKUPS_PER_ADDRESS {
+            execute(deps.as_mut(), env.clone(), sale.clone(), lock(&weth, 1)).unwrap();
+        }
+        let err = execute(deps.as_mut(), env.clone(), sale.clone(), lock(&weth, 1)).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyLockups { .. }));
+        execute(deps.as_mut(), env.clone(), sale, lock(&wusdc, 1)).unwrap();
+    }
+}
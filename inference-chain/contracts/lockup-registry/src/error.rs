@@ -0,0 +1,23 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Zero amount not allowed")]
+    ZeroAmount {},
+
+    #[error("Invalid unlock schedule: {reason}")]
+    InvalidSchedule { reason: String },
+
+    #[error("Lockup not found: {address} #{id} on {token}")]
+    LockupNotFound { token: String, address: String, id: u64 },
+
+    #[error("Too many lockups for {address} on {token}: max {max}")]
+    TooManyLockups { token: String, address: String, max: u32 },
+}
@@ -0,0 +1,88 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
+
+use crate::state::UnlockSchedule;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Manages the set of writers (typically the governance module)
+    pub admin: String,
+    /// Contracts allowed to register lockups
+    pub writers: Vec<String>,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Writer only: lock `amount` of `address`'s balance of the `token` contract under `schedule`
+    AddLockup {
+        token: String,
+        address: String,
+        amount: Uint128,
+        schedule: UnlockSchedule,
+    },
+    /// Registering writer or admin: drop a lockup
+    RemoveLockup { token: String, address: String, id: u64 },
+    /// Anyone: drop lockups of `address` on `token` that have fully unlocked
+    PruneUnlocked { token: String, address: String },
+    /// Admin only: add and remove writers
+    UpdateWriters { add: Vec<String>, remove: Vec<String> },
+    /// Admin only: hand over administration
+    UpdateAdmin { admin: String },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Amount of `token` locked for `address` at `at_height` (current height when None).
+    /// This is the query the wrapped-token transfer restriction consults, passing itself as `token`.
+    #[returns(LockedResponse)]
+    Locked { token: String, address: String, at_height: Option<u64> },
+    /// Amount of registered `token` lockups already released at `at_height` (current height when None)
+    #[returns(UnlockedResponse)]
+    Unlocked { token: String, address: String, at_height: Option<u64> },
+    /// Lockups of `address` on `token` ordered by id
+    #[returns(LockupsResponse)]
+    Lockups {
+        token: String,
+        address: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub admin: String,
+    pub writers: Vec<String>,
+}
+
+#[cw_serde]
+pub struct LockedResponse {
+    pub token: String,
+    pub address: String,
+    pub locked: Uint128,
+    pub at_height: u64,
+}
+
+#[cw_serde]
+pub struct UnlockedResponse {
+    pub token: String,
+    pub address: String,
+    pub unlocked: Uint128,
+    pub at_height: u64,
+}
+
+#[cw_serde]
+pub struct LockupsResponse {
+    pub lockups: Vec<LockupInfo>,
+}
+
+#[cw_serde]
+pub struct LockupInfo {
+    pub id: u64,
+    pub writer: String,
+    pub amount: Uint128,
+    pub schedule: UnlockSchedule,
+}
@@ -0,0 +1,71 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+
+/// Lockups per (token, address) are capped so the `Locked` query consulted on every
+/// token transfer stays bounded in gas
+pub const MAX_LOCKUPS_PER_ADDRESS: u32 = 32;
+
+#[cw_serde]
+pub struct Config {
+    /// Manages the set of writers
+    pub admin: Addr,
+}
+
+#[cw_serde]
+pub enum UnlockSchedule {
+    /// Everything unlocks at `at_height`
+    Cliff { at_height: u64 },
+    /// Fully locked until `start_height`, then unlocks linearly until `end_height`
+    Linear { start_height: u64, end_height: u64 },
+}
+
+impl UnlockSchedule {
+    /// Portion of `amount` still locked at `height`
+    pub fn locked_at(&self, amount: Uint128, height: u64) -> Uint128 {
+        match *self {
+            UnlockSchedule::Cliff { at_height } => {
+                if height < at_height {
+                    amount
+                } else {
+                    Uint128::zero()
+                }
+            }
+            UnlockSchedule::Linear { start_height, end_height } => {
+                if height <= start_height {
+                    amount
+                } else if height >= end_height {
+                    Uint128::zero()
+                } else {
+                    amount.multiply_ratio(end_height - height, end_height - start_height)
+                }
+            }
+        }
+    }
+
+    /// Height from which nothing remains locked
+    pub fn end_height(&self) -> u64 {
+        match *self {
+            UnlockSchedule::Cliff { at_height } => at_height,
+            UnlockSchedule::Linear { end_height, .. } => end_height,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct Lockup {
+    /// Contract that registered the lockup; only it (or the admin) can remove it
+    pub writer: Addr,
+    pub amount: Uint128,
+    pub schedule: UnlockSchedule,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+/// Contracts (sale, vesting) allowed to register lockups
+pub const WRITERS: Map<&Addr, ()> = Map::new("writers");
+/// (token contract, address, lockup id) -> lockup. A lockup only restricts the token it
+/// was registered for, so tokens sharing the registry never see each other's lockups.
+pub const LOCKUPS: Map<(&Addr, &Addr, u64), Lockup> = Map::new("lockups");
+/// (token contract, address) -> number of lockups
+pub const LOCKUP_COUNT: Map<(&Addr, &Addr), u32> = Map::new("lockup_count");
+pub const NEXT_LOCKUP_ID: Item<u64> = Item::new("next_lockup_id");
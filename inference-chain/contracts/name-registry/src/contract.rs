@@ -0,0 +1,168 @@
+use cosmwasm_std::{
+    entry_point, to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{InstantiateMsg, NamesResponse, QueryMsg, ResolveResponse, SudoMsg};
+use crate::state::{NameEntry, MAX_NAME_LEN, NAMES};
+
+const CONTRACT_NAME: &str = "name-registry";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+
+#[entry_point]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
+        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
+
+    for (name, address) in &msg.entries {
+        set_name(deps.branch(), &env, name, address)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("entries", msg.entries.len().to_string()))
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::SetName { name, address } => {
+            let entry = set_name(deps, &env, &name, &address)?;
+            Ok(Response::new()
+                .add_attribute("action", "set_name")
+                .add_attribute("name", name)
+                .add_attribute("address", entry.address))
+        }
+        SudoMsg::RemoveName { name } => {
+            if !NAMES.has(deps.storage, &name) {
+                return Err(ContractError::NameNotFound { name });
+            }
+            NAMES.remove(deps.storage, &name);
+            Ok(Response::new()
+                .add_attribute("action", "remove_name")
+                .add_attribute("name", name))
+        }
+    }
+}
+
+/// Names are lowercase so lookups from config fields cannot miss on case
+fn validate_name(name: &str) -> Result<(), ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidName {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(invalid(&format!("must be 1-{} characters", MAX_NAME_LEN)));
+    }
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'_'))
+    {
+        return Err(invalid("only lowercase letters, digits, '.', '-' and '_' are allowed"));
+    }
+    Ok(())
+}
+
+fn set_name(deps: DepsMut, env: &Env, name: &str, address: &str) -> Result<NameEntry, ContractError> {
+    validate_name(name)?;
+    let entry = NameEntry {
+        address: deps.api.addr_validate(address)?,
+        updated_at_height: env.block.height,
+    };
+    NAMES.save(deps.storage, name, &entry)?;
+    Ok(entry)
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Resolve { name } => {
+            let entry = NAMES
+                .may_load(deps.storage, &name)?
+                .ok_or_else(|| StdError::msg(format!("Name not found: {}", name)))?;
+            to_json_binary(&resolve_response(name, entry))
+        }
+        QueryMsg::Names { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.as_deref().map(Bound::exclusive);
+            let names = NAMES
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(name, entry)| resolve_response(name, entry)))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&NamesResponse { names })
+        }
+    }
+}
+
+fn resolve_response(name: String, entry: NameEntry) -> ResolveResponse {
+    ResolveResponse {
+        name,
+        address: entry.address.to_string(),
+        updated_at_height: entry.updated_at_height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::from_json;
+
+    #[test]
+    fn governance_repoints_names() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let mut env = mock_env();
+        let treasury = api.addr_make("treasury").to_string();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("creator"), funds: vec![] },
+            InstantiateMsg { entries: vec![("community-sale.treasury".to_string(), treasury.clone())] },
+        )
+        .unwrap();
+
+        let resolve = |deps: Deps, name: &str| -> StdResult<ResolveResponse> {
+            query(deps, mock_env(), QueryMsg::Resolve { name: name.to_string() }).and_then(from_json)
+        };
+        assert_eq!(resolve(deps.as_ref(), "community-sale.treasury").unwrap().address, treasury);
+        assert!(resolve(deps.as_ref(), "governance").is_err());
+
+        let err = sudo(
+            deps.as_mut(),
+            env.clone(),
+            SudoMsg::SetName { name: "Governance".to_string(), address: treasury },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidName { .. }));
+
+        env.block.height += 10;
+        let new_treasury = api.addr_make("new_treasury").to_string();
+        sudo(
+            deps.as_mut(),
+            env.clone(),
+            SudoMsg::SetName { name: "community-sale.treasury".to_string(), address: new_treasury.clone() },
+        )
+        .unwrap();
+        let entry = resolve(deps.as_ref(), "community-sale.treasury").unwrap();
+        assert_eq!(entry.address, new_treasury);
+        assert_eq!(entry.updated_at_height, env.block.height);
+
+        sudo(deps.as_mut(), env, SudoMsg::RemoveName { name: "community-sale.treasury".to_string() }).unwrap();
+        let names: NamesResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Names { start_after: None, limit: None }).unwrap())
+                .unwrap();
+        assert!(names.names.is_empty());
+    }
+}
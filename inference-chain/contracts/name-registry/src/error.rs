@@ -0,0 +1,14 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Invalid name {name}: {reason}")]
+    InvalidName { name: String, reason: String },
+
+    #[error("Name not found: {name}")]
+    NameNotFound { name: String },
+}
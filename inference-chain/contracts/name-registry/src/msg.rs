@@ -0,0 +1,42 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Initial (name, address) entries
+    pub entries: Vec<(String, String)>,
+}
+
+/// Governance (MsgSudoContract) messages; the registry has no execute messages
+#[cw_serde]
+pub enum SudoMsg {
+    /// Point `name` at `address`, creating or replacing the entry
+    SetName { name: String, address: String },
+    RemoveName { name: String },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Address `name` points at; fails for unknown names. This is the query other contracts'
+    /// config fields resolve through.
+    #[returns(ResolveResponse)]
+    Resolve { name: String },
+    /// Names ordered alphabetically
+    #[returns(NamesResponse)]
+    Names {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ResolveResponse {
+    pub name: String,
+    pub address: String,
+    pub updated_at_height: u64,
+}
+
+#[cw_serde]
+pub struct NamesResponse {
+    pub names: Vec<ResolveResponse>,
+}
@@ -0,0 +1,15 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Addr;
+use cw_storage_plus::Map;
+
+pub const MAX_NAME_LEN: usize = 64;
+
+#[cw_serde]
+pub struct NameEntry {
+    pub address: Addr,
+    /// Height of the last governance write, so consumers can tell when a name moved
+    pub updated_at_height: u64,
+}
+
+/// Name (e.g. `community-sale.treasury`) -> entry
+pub const NAMES: Map<&str, NameEntry> = Map::new("names");
@@ -0,0 +1,101 @@
+//! Addresses on the external (EVM) chain, kept in one canonical form.
+//!
+//! Admins paste checksummed or unprefixed addresses and wrapped tokens report theirs however the
+//! bridge stored them, so every external address is normalized (trimmed, lowercased, `0x`
+//! prefixed) before it is stored or compared. Shared by community-sale and wrapped-token so both
+//! contracts format addresses identically.
+
+use std::fmt;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+#[error("Invalid external address: {address}")]
+pub struct InvalidExternalAddress {
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub struct ExternalAddress(String);
+
+impl ExternalAddress {
+    /// Normalizes and validates user input: 20 bytes of hex, with or without `0x`
+    pub fn parse(input: &str) -> Result<Self, InvalidExternalAddress> {
+        let address = Self::from(input.to_string());
+        let hex = &address.0[2..];
+        if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(InvalidExternalAddress {
+                address: input.to_string(),
+            });
+        }
+        Ok(address)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Normalizes without validating, so values stored before validation existed still load
+impl From<String> for ExternalAddress {
+    fn from(value: String) -> Self {
+        let lower = value.trim().to_lowercase();
+        match lower.strip_prefix("0x") {
+            Some(_) => ExternalAddress(lower),
+            None => ExternalAddress(format!("0x{}", lower)),
+        }
+    }
+}
+
+impl From<ExternalAddress> for String {
+    fn from(address: ExternalAddress) -> Self {
+        address.0
+    }
+}
+
+impl fmt::Display for ExternalAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl JsonSchema for ExternalAddress {
+    fn schema_name() -> String {
+        "ExternalAddress".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
+#[cfg(feature = "cw-schema")]
+impl cw_schema::Schemaifier for ExternalAddress {
+    fn visit_schema(visitor: &mut cw_schema::SchemaVisitor) -> cw_schema::DefinitionReference {
+        <String as cw_schema::Schemaifier>::visit_schema(visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_checksummed_and_unprefixed_input() {
+        let canonical = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+        for input in [
+            "0xdAC17F958D2ee523a2206206994597C13D831ec7",
+            "  0XDAC17F958D2EE523A2206206994597C13D831EC7 ",
+            "dac17f958d2ee523a2206206994597c13d831ec7",
+        ] {
+            assert_eq!(ExternalAddress::parse(input).unwrap().as_str(), canonical);
+        }
+        assert!(ExternalAddress::parse("0x1234").is_err());
+        assert!(ExternalAddress::parse("0xzz17f958d2ee523a2206206994597c13d831ec7").is_err());
+    }
+}
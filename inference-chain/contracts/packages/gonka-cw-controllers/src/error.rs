@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ControllerError {
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("No ownership transfer is pending")]
+    NoPendingOwner {},
+
+    #[error("Ownership transfer expired at height {expiry_height}")]
+    TransferExpired { expiry_height: u64 },
+
+    #[error("Missing role: {role}")]
+    MissingRole { role: String },
+}
@@ -0,0 +1,13 @@
+//! Access-control building blocks shared by the Gonka contracts: a pause switch, two-step
+//! ownership handoff and role checks.
+//!
+//! community-sale and wrapped-token are built against different cosmwasm-std majors, so nothing
+//! here depends on it. Addresses are plain strings the contract has already validated, and each
+//! contract keeps the state below in its own storage items.
+
+mod error;
+pub mod ownership;
+pub mod pause;
+pub mod roles;
+
+pub use error::ControllerError;
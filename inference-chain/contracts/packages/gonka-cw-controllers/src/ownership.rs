@@ -0,0 +1,91 @@
+//! Two-step ownership handoff. The owner proposes a successor, who must accept before the
+//! proposal expires, so control never moves to an address nobody can sign for.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::ControllerError;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingOwner {
+    pub new_owner: String,
+    /// Last height the proposal can be accepted at; None never expires
+    pub expiry_height: Option<u64>,
+}
+
+pub fn ensure_owner(owner: &str, sender: &str) -> Result<(), ControllerError> {
+    if sender != owner {
+        return Err(ControllerError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Owner: propose `new_owner` as successor, replacing any earlier proposal
+pub fn propose(
+    owner: &str,
+    sender: &str,
+    new_owner: String,
+    expiry_height: Option<u64>,
+    height: u64,
+) -> Result<PendingOwner, ControllerError> {
+    ensure_owner(owner, sender)?;
+    if let Some(expiry_height) = expiry_height {
+        if expiry_height < height {
+            return Err(ControllerError::TransferExpired { expiry_height });
+        }
+    }
+    Ok(PendingOwner { new_owner, expiry_height })
+}
+
+/// Proposed owner: take over, returning the new owner to store
+pub fn accept(pending: Option<&PendingOwner>, sender: &str, height: u64) -> Result<String, ControllerError> {
+    let pending = pending.ok_or(ControllerError::NoPendingOwner {})?;
+    if sender != pending.new_owner {
+        return Err(ControllerError::Unauthorized {});
+    }
+    if let Some(expiry_height) = pending.expiry_height {
+        if height > expiry_height {
+            return Err(ControllerError::TransferExpired { expiry_height });
+        }
+    }
+    Ok(pending.new_owner.clone())
+}
+
+/// Owner: withdraw a pending proposal
+pub fn cancel(owner: &str, sender: &str, pending: Option<&PendingOwner>) -> Result<(), ControllerError> {
+    ensure_owner(owner, sender)?;
+    if pending.is_none() {
+        return Err(ControllerError::NoPendingOwner {});
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handoff_needs_owner_proposal_and_successor_acceptance() {
+        assert_eq!(
+            propose("owner", "intruder", "next".to_string(), None, 10),
+            Err(ControllerError::Unauthorized {})
+        );
+        assert_eq!(
+            propose("owner", "owner", "next".to_string(), Some(9), 10),
+            Err(ControllerError::TransferExpired { expiry_height: 9 })
+        );
+        let pending = propose("owner", "owner", "next".to_string(), Some(20), 10).unwrap();
+
+        assert_eq!(accept(None, "next", 11), Err(ControllerError::NoPendingOwner {}));
+        assert_eq!(accept(Some(&pending), "owner", 11), Err(ControllerError::Unauthorized {}));
+        assert_eq!(
+            accept(Some(&pending), "next", 21),
+            Err(ControllerError::TransferExpired { expiry_height: 20 })
+        );
+        assert_eq!(accept(Some(&pending), "next", 20).unwrap(), "next");
+
+        assert_eq!(cancel("owner", "next", Some(&pending)), Err(ControllerError::Unauthorized {}));
+        assert_eq!(cancel("owner", "owner", None), Err(ControllerError::NoPendingOwner {}));
+        cancel("owner", "owner", Some(&pending)).unwrap();
+    }
+}
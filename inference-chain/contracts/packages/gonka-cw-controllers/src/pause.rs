@@ -0,0 +1,32 @@
+//! A single switch that stops a contract's user-facing flows. Admin flows stay available while
+//! paused so the contract can be repaired and resumed.
+
+use crate::ControllerError;
+
+/// Fails while the contract is paused
+pub fn ensure_not_paused(paused: bool) -> Result<(), ControllerError> {
+    if paused {
+        return Err(ControllerError::Paused {});
+    }
+    Ok(())
+}
+
+/// Flips the switch, returning its previous value for the audit record
+pub fn set_paused(switch: &mut bool, paused: bool) -> bool {
+    std::mem::replace(switch, paused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_switch_gates_flows() {
+        let mut paused = false;
+        ensure_not_paused(paused).unwrap();
+        assert!(!set_paused(&mut paused, true));
+        assert_eq!(ensure_not_paused(paused), Err(ControllerError::Paused {}));
+        assert!(set_paused(&mut paused, false));
+        ensure_not_paused(paused).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+//! Role-based access: named roles, each held by a set of addresses.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::ControllerError;
+
+/// Role name -> holders
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct Roles(BTreeMap<String, BTreeSet<String>>);
+
+impl Roles {
+    /// Returns whether `holder` did not already have the role
+    pub fn grant(&mut self, role: &str, holder: &str) -> bool {
+        self.0.entry(role.to_string()).or_default().insert(holder.to_string())
+    }
+
+    /// Returns whether `holder` had the role; roles left without holders are dropped
+    pub fn revoke(&mut self, role: &str, holder: &str) -> bool {
+        let Some(holders) = self.0.get_mut(role) else {
+            return false;
+        };
+        let removed = holders.remove(holder);
+        if holders.is_empty() {
+            self.0.remove(role);
+        }
+        removed
+    }
+
+    pub fn has_role(&self, role: &str, holder: &str) -> bool {
+        matches!(self.0.get(role), Some(holders) if holders.contains(holder))
+    }
+
+    pub fn ensure_role(&self, role: &str, holder: &str) -> Result<(), ControllerError> {
+        if !self.has_role(role, holder) {
+            return Err(ControllerError::MissingRole { role: role.to_string() });
+        }
+        Ok(())
+    }
+
+    pub fn holders(&self, role: &str) -> impl Iterator<Item = &str> {
+        self.0.get(role).into_iter().flatten().map(String::as_str)
+    }
+}
+
+/// Passes when `sender` is one of `holders`, for contracts that keep each privileged address
+/// in its own item rather than in `Roles`
+pub fn ensure_one_of(sender: &str, holders: &[&str]) -> Result<(), ControllerError> {
+    if !holders.contains(&sender) {
+        return Err(ControllerError::Unauthorized {});
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_grant_revoke_and_check() {
+        let mut roles = Roles::default();
+        assert!(roles.grant("minter", "alice"));
+        assert!(!roles.grant("minter", "alice"));
+        roles.grant("minter", "bob");
+        roles.ensure_role("minter", "alice").unwrap();
+        assert_eq!(
+            roles.ensure_role("pauser", "alice"),
+            Err(ControllerError::MissingRole { role: "pauser".to_string() })
+        );
+        assert_eq!(roles.holders("minter").collect::<Vec<_>>(), vec!["alice", "bob"]);
+
+        assert!(roles.revoke("minter", "alice"));
+        assert!(!roles.revoke("minter", "alice"));
+        assert!(!roles.has_role("minter", "alice"));
+        roles.revoke("minter", "bob");
+        assert_eq!(roles, Roles::default());
+
+        ensure_one_of("admin", &["creator", "admin"]).unwrap();
+        assert_eq!(ensure_one_of("user", &["creator", "admin"]), Err(ControllerError::Unauthorized {}));
+    }
+}
@@ -0,0 +1,137 @@
+//! Chunked export of contract state, shared by community-sale and wrapped-token.
+//!
+//! A section is a fixed list of stores exported in order. Every entry is keyed `<store>/<key>`
+//! (just `<store>` for single items), so the last key of a chunk is the cursor for the next one.
+//! Each contract reads its own stores and builds its own entries; this crate only walks the
+//! cursor across stores. Like gonka-cw-controllers it does not depend on cosmwasm-std, since the
+//! two contracts are built against different majors.
+
+/// Where a chunk resumes: the store named by `start_after` and the key within it, empty when
+/// the store is a single item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cursor<'a> {
+    pub store: &'a str,
+    pub key: &'a str,
+}
+
+impl<'a> Cursor<'a> {
+    /// Splits `start_after` into store and key, rejecting stores outside the section
+    pub fn parse(stores: &[&str], start_after: Option<&'a str>) -> Result<Option<Self>, String> {
+        let Some(cursor) = start_after else {
+            return Ok(None);
+        };
+        let (store, key) = cursor.split_once('/').unwrap_or((cursor, ""));
+        if !stores.contains(&store) {
+            return Err(format!("cursor store {store} is not in this section"));
+        }
+        Ok(Some(Cursor { store, key }))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Chunk<T> {
+    pub entries: Vec<T>,
+    /// Key of the last entry of a full chunk, `None` once the section is exhausted
+    pub next_start_after: Option<String>,
+}
+
+/// Reads up to `limit` entries of `stores` after `cursor`. `read_store(store, start_after, limit)`
+/// returns at most `limit` entries of one store, starting after the store-local key.
+pub fn read_chunk<T, E>(
+    stores: &[&str],
+    cursor: Option<Cursor>,
+    limit: usize,
+    mut read_store: impl FnMut(&str, Option<&str>, usize) -> Result<Vec<T>, E>,
+    entry_key: impl Fn(&T) -> &str,
+) -> Result<Chunk<T>, E> {
+    let mut entries = Vec::new();
+    let mut reached_cursor = cursor.is_none();
+    for store in stores {
+        if entries.len() >= limit {
+            break;
+        }
+        // Stores ahead of the cursor's went out in earlier chunks; within its store the chunk
+        // resumes after the cursor key, and a single item at the cursor is already done
+        let start = match cursor {
+            _ if reached_cursor => None,
+            Some(cursor) if cursor.store == *store => {
+                reached_cursor = true;
+                if cursor.key.is_empty() {
+                    continue;
+                }
+                Some(cursor.key)
+            }
+            _ => continue,
+        };
+        entries.extend(read_store(store, start, limit - entries.len())?);
+    }
+
+    let next_start_after = if entries.len() < limit {
+        None
+    } else {
+        entries.last().map(|entry| entry_key(entry).to_string())
+    };
+    Ok(Chunk { entries, next_start_after })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORES: [&str; 3] = ["config", "balance", "allowance"];
+
+    // config is a single item, balance has three keys, allowance two
+    fn read(store: &str, start: Option<&str>, limit: usize) -> Result<Vec<String>, String> {
+        let keys: &[&str] = match store {
+            "config" => return Ok(vec!["config".to_string()]),
+            "balance" => &["a", "b", "c"],
+            "allowance" => &["a/x", "b/y"],
+            _ => return Err(format!("unknown store {store}")),
+        };
+        Ok(keys
+            .iter()
+            .filter(|key| start.is_none_or(|start| **key > start))
+            .take(limit)
+            .map(|key| format!("{store}/{key}"))
+            .collect())
+    }
+
+    fn export_all(limit: usize) -> Vec<String> {
+        let mut all = Vec::new();
+        let mut start_after: Option<String> = None;
+        loop {
+            let cursor = Cursor::parse(&STORES, start_after.as_deref()).unwrap();
+            let chunk = read_chunk(&STORES, cursor, limit, read, |key: &String| key.as_str()).unwrap();
+            all.extend(chunk.entries);
+            match chunk.next_start_after {
+                Some(next) => start_after = Some(next),
+                None => return all,
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_cover_the_section_in_order() {
+        let expected = vec!["config", "balance/a", "balance/b", "balance/c", "allowance/a/x", "allowance/b/y"];
+        for limit in 1..=7 {
+            assert_eq!(export_all(limit), expected, "limit {limit}");
+        }
+    }
+
+    #[test]
+    fn cursor_resumes_within_its_store() {
+        let cursor = Cursor::parse(&STORES, Some("allowance/a/x")).unwrap();
+        assert_eq!(cursor, Some(Cursor { store: "allowance", key: "a/x" }));
+        let chunk = read_chunk(&STORES, cursor, 10, read, |key: &String| key.as_str()).unwrap();
+        assert_eq!(chunk.entries, vec!["allowance/b/y"]);
+        assert_eq!(chunk.next_start_after, None);
+    }
+
+    #[test]
+    fn cursor_outside_the_section_is_rejected() {
+        assert_eq!(
+            Cursor::parse(&STORES, Some("whitelist/a")),
+            Err("cursor store whitelist is not in this section".to_string())
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use cosmwasm_std::{
+    entry_point, to_json_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdError, StdResult, Uint128,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, IsBondedResponse, QueryMsg, RelayerResponse, RelayersResponse,
+    SudoMsg,
+};
+use crate::state::{Config, RelayerBond, BONDS, CONFIG};
+
+const CONTRACT_NAME: &str = "relayer-bond";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
+        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
+
+    let config = Config {
+        bond_denom: msg.bond_denom,
+        min_bond: msg.min_bond,
+        unbonding_blocks: msg.unbonding_blocks,
+        slash_recipient: deps.api.addr_validate(&msg.slash_recipient)?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("bond_denom", config.bond_denom)
+        .add_attribute("min_bond", config.min_bond))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Bond {} => bond(deps, info),
+        ExecuteMsg::Unbond { amount } => unbond(deps, env, info, amount),
+        ExecuteMsg::WithdrawUnbonded {} => withdraw_unbonded(deps, env, info),
+    }
+}
+
+fn bond(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let [coin] = info.funds.as_slice() else {
+        return Err(ContractError::InvalidFunds {
+            reason: format!("send exactly one coin of {}", config.bond_denom),
+        });
+    };
+    if coin.denom != config.bond_denom {
+        return Err(ContractError::InvalidFunds {
+            reason: format!("bonds are posted in {}, got {}", config.bond_denom, coin.denom),
+        });
+    }
+    let amount: Uint128 = coin
+        .amount
+        .try_into()
+        .map_err(|_| ContractError::Std(StdError::msg("amount exceeds Uint128")))?;
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let mut bond = BONDS.may_load(deps.storage, &info.sender)?.unwrap_or_default();
+    bond.bonded = bond.bonded.checked_add(amount).map_err(StdError::from)?;
+    BONDS.save(deps.storage, &info.sender, &bond)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "bond")
+        .add_attribute("relayer", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("bonded", bond.bonded)
+        .add_attribute("is_bonded", bond.is_bonded(&config).to_string()))
+}
+
+fn unbond(deps: DepsMut, env: Env, info: MessageInfo, amount: Uint128) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let mut bond = load_bond(deps.as_ref(), &info.sender)?;
+    if amount > bond.bonded {
+        return Err(ContractError::InsufficientBond {
+            bonded: bond.bonded.u128(),
+            requested: amount.u128(),
+        });
+    }
+    bond.bonded -= amount;
+    bond.unbonding += amount;
+    bond.release_height = env.block.height + config.unbonding_blocks;
+    BONDS.save(deps.storage, &info.sender, &bond)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "unbond")
+        .add_attribute("relayer", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("release_height", bond.release_height.to_string()))
+}
+
+fn withdraw_unbonded(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut bond = load_bond(deps.as_ref(), &info.sender)?;
+    if bond.unbonding.is_zero() || env.block.height < bond.release_height {
+        return Err(ContractError::NothingToWithdraw { release_height: bond.release_height });
+    }
+    let amount = std::mem::take(&mut bond.unbonding);
+    save_or_remove(deps, &info.sender, &bond)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin::new(amount, config.bond_denom)],
+        })
+        .add_attribute("action", "withdraw_unbonded")
+        .add_attribute("relayer", info.sender)
+        .add_attribute("amount", amount))
+}
+
+#[entry_point]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::Slash { relayer, amount, reason, jail } => slash(deps, relayer, amount, reason, jail),
+        SudoMsg::Unjail { relayer } => {
+            let relayer = deps.api.addr_validate(&relayer)?;
+            let mut bond = load_bond(deps.as_ref(), &relayer)?;
+            bond.jailed = false;
+            BONDS.save(deps.storage, &relayer, &bond)?;
+            Ok(Response::new()
+                .add_attribute("action", "unjail")
+                .add_attribute("relayer", relayer))
+        }
+        SudoMsg::UpdateConfig { min_bond, unbonding_blocks, slash_recipient } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            if let Some(min_bond) = min_bond {
+                config.min_bond = min_bond;
+            }
+            if let Some(unbonding_blocks) = unbonding_blocks {
+                config.unbonding_blocks = unbonding_blocks;
+            }
+            if let Some(slash_recipient) = slash_recipient {
+                config.slash_recipient = deps.api.addr_validate(&slash_recipient)?;
+            }
+            CONFIG.save(deps.storage, &config)?;
+            Ok(Response::new().add_attribute("action", "update_config"))
+        }
+    }
+}
+
+/// Unbonding funds stay slashable so a relayer cannot escape a pending slash by unbonding first
+fn slash(deps: DepsMut, relayer: String, amount: Uint128, reason: String, jail: bool) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let relayer = deps.api.addr_validate(&relayer)?;
+    let mut bond = load_bond(deps.as_ref(), &relayer)?;
+    let from_bonded = amount.min(bond.bonded);
+    let from_unbonding = (amount - from_bonded).min(bond.unbonding);
+    bond.bonded -= from_bonded;
+    bond.unbonding -= from_unbonding;
+    let slashed = from_bonded + from_unbonding;
+    bond.slashed += slashed;
+    bond.jailed |= jail;
+    BONDS.save(deps.storage, &relayer, &bond)?;
+
+    let mut response = Response::new();
+    if !slashed.is_zero() {
+        response = response.add_message(BankMsg::Send {
+            to_address: config.slash_recipient.to_string(),
+            amount: vec![Coin::new(slashed, config.bond_denom)],
+        });
+    }
+    Ok(response
+        .add_attribute("action", "slash")
+        .add_attribute("relayer", relayer)
+        .add_attribute("slashed", slashed)
+        .add_attribute("jailed", bond.jailed.to_string())
+        .add_attribute("reason", reason))
+}
+
+fn load_bond(deps: Deps, relayer: &Addr) -> Result<RelayerBond, ContractError> {
+    BONDS
+        .may_load(deps.storage, relayer)?
+        .ok_or_else(|| ContractError::RelayerNotFound { relayer: relayer.to_string() })
+}
+
+/// Relayers that withdrew everything are dropped unless their slash history or jail must stay
+fn save_or_remove(deps: DepsMut, relayer: &Addr, bond: &RelayerBond) -> StdResult<()> {
+    if bond.bonded.is_zero() && bond.unbonding.is_zero() && bond.slashed.is_zero() && !bond.jailed {
+        BONDS.remove(deps.storage, relayer);
+        Ok(())
+    } else {
+        BONDS.save(deps.storage, relayer, bond)
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(&ConfigResponse {
+                bond_denom: config.bond_denom,
+                min_bond: config.min_bond,
+                unbonding_blocks: config.unbonding_blocks,
+                slash_recipient: config.slash_recipient.to_string(),
+            })
+        }
+        QueryMsg::IsBonded { relayer } => {
+            let config = CONFIG.load(deps.storage)?;
+            let addr = deps.api.addr_validate(&relayer)?;
+            let bonded = matches!(BONDS.may_load(deps.storage, &addr)?, Some(bond) if bond.is_bonded(&config));
+            to_json_binary(&IsBondedResponse { relayer, bonded })
+        }
+        QueryMsg::Relayer { relayer } => {
+            let config = CONFIG.load(deps.storage)?;
+            let addr = deps.api.addr_validate(&relayer)?;
+            let bond = BONDS.may_load(deps.storage, &addr)?.unwrap_or_default();
+            to_json_binary(&relayer_response(&config, addr, bond))
+        }
+        QueryMsg::Relayers { start_after, limit } => to_json_binary(&query_relayers(deps, start_after, limit)?),
+    }
+}
+
+fn relayer_response(config: &Config, relayer: Addr, bond: RelayerBond) -> RelayerResponse {
+    RelayerResponse {
+        relayer: relayer.to_string(),
+        is_bonded: bond.is_bonded(config),
+        bonded: bond.bonded,
+        unbonding: bond.unbonding,
+        release_height: bond.release_height,
+        slashed: bond.slashed,
+        jailed: bond.jailed,
+    }
+}
+
+fn query_relayers(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<RelayersResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let relayers = BONDS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(relayer, bond)| relayer_response(&config, relayer, bond)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(RelayersResponse { relayers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::{coins, from_json, CosmosMsg};
+
+    fn setup(deps: DepsMut, api: &MockApi) {
+        instantiate(
+            deps,
+            mock_env(),
+            MessageInfo { sender: api.addr_make("creator"), funds: vec![] },
+            InstantiateMsg {
+                bond_denom: "ngonka".to_string(),
+                min_bond: Uint128::from(1000u128),
+                unbonding_blocks: 100,
+                slash_recipient: api.addr_make("gov").to_string(),
+            },
+        )
+        .unwrap();
+    }
+
+    fn is_bonded(deps: Deps, relayer: &Addr) -> bool {
+        let res: IsBondedResponse =
+            from_json(query(deps, mock_env(), QueryMsg::IsBonded { relayer: relayer.to_string() }).unwrap()).unwrap();
+        res.bonded
+    }
+
+    #[test]
+    fn unbonded_funds_wait_out_the_unbonding_period() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let mut env = mock_env();
+        let relayer = api.addr_make("relayer");
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: relayer.clone(), funds: coins(1000, "uatom") },
+            ExecuteMsg::Bond {},
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds { .. }));
+
+        let info = MessageInfo { sender: relayer.clone(), funds: coins(1000, "ngonka") };
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Bond {}).unwrap();
+        assert!(is_bonded(deps.as_ref(), &relayer));
+
+        let info = MessageInfo { sender: relayer.clone(), funds: vec![] };
+        execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Unbond { amount: Uint128::from(400u128) }).unwrap();
+        assert!(!is_bonded(deps.as_ref(), &relayer));
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::WithdrawUnbonded {}).unwrap_err();
+        assert!(matches!(err, ContractError::NothingToWithdraw { .. }));
+
+        env.block.height += 100;
+        let res = execute(deps.as_mut(), env, info, ExecuteMsg::WithdrawUnbonded {}).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address: relayer.to_string(), amount: coins(400, "ngonka") })
+        );
+    }
+
+    #[test]
+    fn slash_reaches_unbonding_funds_and_jails() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let env = mock_env();
+        let relayer = api.addr_make("relayer");
+
+        let info = MessageInfo { sender: relayer.clone(), funds: coins(1500, "ngonka") };
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Bond {}).unwrap();
+        let info = MessageInfo { sender: relayer.clone(), funds: vec![] };
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Unbond { amount: Uint128::from(1000u128) }).unwrap();
+
+        let res = sudo(
+            deps.as_mut(),
+            env.clone(),
+            SudoMsg::Slash {
+                relayer: relayer.to_string(),
+                amount: Uint128::from(800u128),
+                reason: "minted an unbacked deposit".to_string(),
+                jail: true,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send { to_address: api.addr_make("gov").to_string(), amount: coins(800, "ngonka") })
+        );
+
+        let bond = BONDS.load(&deps.storage, &relayer).unwrap();
+        assert_eq!(bond.bonded, Uint128::zero());
+        assert_eq!(bond.unbonding, Uint128::from(700u128));
+        assert_eq!(bond.slashed, Uint128::from(800u128));
+
+        let info = MessageInfo { sender: relayer.clone(), funds: coins(1000, "ngonka") };
+        execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Bond {}).unwrap();
+        assert!(!is_bonded(deps.as_ref(), &relayer));
+        sudo(deps.as_mut(), env, SudoMsg::Unjail { relayer: relayer.to_string() }).unwrap();
+        assert!(is_bonded(deps.as_ref(), &relayer));
+    }
+}
@@ -0,0 +1,23 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Zero amount not allowed")]
+    ZeroAmount {},
+
+    #[error("Invalid funds: {reason}")]
+    InvalidFunds { reason: String },
+
+    #[error("Relayer not found: {relayer}")]
+    RelayerNotFound { relayer: String },
+
+    #[error("Insufficient bond: {bonded} bonded, {requested} requested")]
+    InsufficientBond { bonded: u128, requested: u128 },
+
+    #[error("Nothing to withdraw until height {release_height}")]
+    NothingToWithdraw { release_height: u64 },
+}
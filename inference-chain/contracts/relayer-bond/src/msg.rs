@@ -0,0 +1,91 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Native denom bonds are posted in (GNK)
+    pub bond_denom: String,
+    pub min_bond: Uint128,
+    pub unbonding_blocks: u64,
+    pub slash_recipient: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Relayer: add the attached `bond_denom` funds to the sender's bond
+    Bond {},
+    /// Relayer: start unbonding `amount`. It stays slashable for `unbonding_blocks`; unbonding
+    /// again adds to the pending amount and restarts the wait.
+    Unbond { amount: Uint128 },
+    /// Relayer: withdraw unbonded funds once their wait is over
+    WithdrawUnbonded {},
+}
+
+/// Governance (MsgSudoContract) messages
+#[cw_serde]
+pub enum SudoMsg {
+    /// Take up to `amount` from `relayer`, bonded funds first and then unbonding ones, and send
+    /// it to the slash recipient. `jail` also stops the relayer counting as bonded.
+    Slash {
+        relayer: String,
+        amount: Uint128,
+        reason: String,
+        jail: bool,
+    },
+    /// Let a jailed relayer count as bonded again
+    Unjail { relayer: String },
+    UpdateConfig {
+        min_bond: Option<Uint128>,
+        unbonding_blocks: Option<u64>,
+        slash_recipient: Option<String>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Whether `relayer` holds at least `min_bond` and is not jailed. This is the query the
+    /// wrapped-token mint path consults.
+    #[returns(IsBondedResponse)]
+    IsBonded { relayer: String },
+    #[returns(RelayerResponse)]
+    Relayer { relayer: String },
+    /// Relayers ordered by address
+    #[returns(RelayersResponse)]
+    Relayers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub bond_denom: String,
+    pub min_bond: Uint128,
+    pub unbonding_blocks: u64,
+    pub slash_recipient: String,
+}
+
+#[cw_serde]
+pub struct IsBondedResponse {
+    pub relayer: String,
+    pub bonded: bool,
+}
+
+#[cw_serde]
+pub struct RelayerResponse {
+    pub relayer: String,
+    pub bonded: Uint128,
+    pub unbonding: Uint128,
+    pub release_height: u64,
+    pub slashed: Uint128,
+    pub jailed: bool,
+    pub is_bonded: bool,
+}
+
+#[cw_serde]
+pub struct RelayersResponse {
+    pub relayers: Vec<RelayerResponse>,
+}
@@ -0,0 +1,37 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+
+#[cw_serde]
+pub struct Config {
+    /// Native denom bonds are posted in
+    pub bond_denom: String,
+    /// Bond a relayer must hold to count as bonded
+    pub min_bond: Uint128,
+    /// Blocks unbonded funds stay slashable before they can be withdrawn
+    pub unbonding_blocks: u64,
+    /// Receives slashed funds (typically the community pool or governance module)
+    pub slash_recipient: Addr,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct RelayerBond {
+    pub bonded: Uint128,
+    /// Unbonded but still slashable until `release_height`
+    pub unbonding: Uint128,
+    pub release_height: u64,
+    /// Total slashed over the relayer's lifetime
+    pub slashed: Uint128,
+    /// Set by governance when slashing; a jailed relayer is never bonded until unjailed
+    pub jailed: bool,
+}
+
+impl RelayerBond {
+    pub fn is_bonded(&self, config: &Config) -> bool {
+        !self.jailed && !self.bonded.is_zero() && self.bonded >= config.min_bond
+    }
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const BONDS: Map<&Addr, RelayerBond> = Map::new("bonds");
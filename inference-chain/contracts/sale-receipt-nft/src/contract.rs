@@ -0,0 +1,818 @@
+use cosmwasm_std::{
+    entry_point, to_json_binary, Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdError, StdResult,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use cw_utils::Expiration;
+
+use crate::error::ContractError;
+use crate::msg::{
+    AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, ContractInfoResponse, Cw721ReceiveMsg, ExecuteMsg,
+    InstantiateMsg, MinterResponse, NftInfoResponse, NumTokensResponse, OperatorResponse, OperatorsResponse,
+    OwnerOfResponse, QueryMsg, TokensResponse,
+};
+use crate::state::{
+    Approval, CollectionInfo, ReceiptMetadata, TokenInfo, COLLECTION, OPERATORS, OWNER_TOKENS, TOKENS, TOKEN_COUNT,
+};
+
+const CONTRACT_NAME: &str = "sale-receipt-nft";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 30;
+const MAX_LIMIT: u32 = 100;
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
+        .map_err(|e| ContractError::Std(StdError::msg(e.to_string())))?;
+
+    let minter = deps.api.addr_validate(&msg.minter)?;
+    COLLECTION.save(
+        deps.storage,
+        &CollectionInfo {
+            name: msg.name.clone(),
+            symbol: msg.symbol.clone(),
+            minter: minter.clone(),
+        },
+    )?;
+    TOKEN_COUNT.save(deps.storage, &0)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("name", msg.name)
+        .add_attribute("symbol", msg.symbol)
+        .add_attribute("minter", minter))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Mint { token_id, owner, token_uri, extension } => {
+            mint(deps, info, token_id, owner, token_uri, extension)
+        }
+        ExecuteMsg::TransferNft { recipient, token_id } => transfer_nft(deps, env, info, recipient, token_id),
+        ExecuteMsg::SendNft { contract, token_id, msg } => send_nft(deps, env, info, contract, token_id, msg),
+        ExecuteMsg::Approve { spender, token_id, expires } => {
+            approve(deps, env, info, spender, token_id, expires)
+        }
+        ExecuteMsg::Revoke { spender, token_id } => revoke(deps, env, info, spender, token_id),
+        ExecuteMsg::ApproveAll { operator, expires } => approve_all(deps, env, info, operator, expires),
+        ExecuteMsg::RevokeAll { operator } => revoke_all(deps, info, operator),
+        ExecuteMsg::Burn { token_id } => burn(deps, env, info, token_id),
+    }
+}
+
+fn mint(
+    deps: DepsMut,
+    info: MessageInfo,
+    token_id: String,
+    owner: String,
+    token_uri: Option<String>,
+    extension: ReceiptMetadata,
+) -> Result<Response, ContractError> {
+    let collection = COLLECTION.load(deps.storage)?;
+    if info.sender != collection.minter {
+        return Err(ContractError::Unauthorized {});
+    }
+    if TOKENS.has(deps.storage, &token_id) {
+        return Err(ContractError::Claimed { token_id });
+    }
+    let owner = deps.api.addr_validate(&owner)?;
+
+    TOKENS.save(
+        deps.storage,
+        &token_id,
+        &TokenInfo {
+            owner: owner.clone(),
+            approvals: vec![],
+            token_uri,
+            extension,
+        },
+    )?;
+    OWNER_TOKENS.save(deps.storage, (&owner, token_id.as_str()), &())?;
+    TOKEN_COUNT.update(deps.storage, |count| -> StdResult<_> { Ok(count + 1) })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "mint")
+        .add_attribute("minter", info.sender)
+        .add_attribute("owner", owner)
+        .add_attribute("token_id", token_id))
+}
+
+fn load_token(deps: Deps, token_id: &str) -> Result<TokenInfo, ContractError> {
+    TOKENS
+        .may_load(deps.storage, token_id)?
+        .ok_or_else(|| ContractError::TokenNotFound { token_id: token_id.to_string() })
+}
+
+fn is_operator(deps: Deps, block: &BlockInfo, owner: &Addr, operator: &Addr) -> StdResult<bool> {
+    Ok(OPERATORS
+        .may_load(deps.storage, (owner, operator))?
+        .is_some_and(|expires| !expires.is_expired(block)))
+}
+
+/// The owner and the owner's operators may change a token's approvals
+fn check_can_approve(deps: Deps, env: &Env, sender: &Addr, token: &TokenInfo) -> Result<(), ContractError> {
+    if token.owner == *sender || is_operator(deps, &env.block, &token.owner, sender)? {
+        return Ok(());
+    }
+    Err(ContractError::Unauthorized {})
+}
+
+/// The owner, an approved spender and the owner's operators may move or burn a token
+fn check_can_send(deps: Deps, env: &Env, sender: &Addr, token: &TokenInfo) -> Result<(), ContractError> {
+    let approved = token
+        .approvals
+        .iter()
+        .any(|approval| approval.spender == *sender && !approval.is_expired(&env.block));
+    if approved {
+        return Ok(());
+    }
+    check_can_approve(deps, env, sender, token)
+}
+
+/// Moves `token_id` to `recipient` on behalf of `sender`, dropping its approvals
+fn move_token(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    recipient: &Addr,
+    token_id: &str,
+) -> Result<(), ContractError> {
+    let mut token = load_token(deps.as_ref(), token_id)?;
+    check_can_send(deps.as_ref(), env, sender, &token)?;
+
+    OWNER_TOKENS.remove(deps.storage, (&token.owner, token_id));
+    OWNER_TOKENS.save(deps.storage, (recipient, token_id), &())?;
+    token.owner = recipient.clone();
+    token.approvals.clear();
+    TOKENS.save(deps.storage, token_id, &token)?;
+    Ok(())
+}
+
+fn transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    move_token(deps, &env, &info.sender, &recipient, &token_id)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("token_id", token_id))
+}
+
+fn send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_id: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+    move_token(deps, &env, &info.sender, &contract, &token_id)?;
+
+    let receive = Cw721ReceiveMsg {
+        sender: info.sender.to_string(),
+        token_id: token_id.clone(),
+        msg,
+    };
+    Ok(Response::new()
+        .add_message(receive.into_cosmos_msg(contract.to_string())?)
+        .add_attribute("action", "send_nft")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", contract)
+        .add_attribute("token_id", token_id))
+}
+
+fn approve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let mut token = load_token(deps.as_ref(), &token_id)?;
+    check_can_approve(deps.as_ref(), &env, &info.sender, &token)?;
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    let spender = deps.api.addr_validate(&spender)?;
+
+    token.approvals.retain(|approval| approval.spender != spender);
+    token.approvals.push(Approval { spender: spender.clone(), expires });
+    TOKENS.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+fn revoke(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut token = load_token(deps.as_ref(), &token_id)?;
+    check_can_approve(deps.as_ref(), &env, &info.sender, &token)?;
+    let spender = deps.api.addr_validate(&spender)?;
+
+    token.approvals.retain(|approval| approval.spender != spender);
+    TOKENS.save(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke")
+        .add_attribute("sender", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("token_id", token_id))
+}
+
+fn approve_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+    let operator = deps.api.addr_validate(&operator)?;
+    OPERATORS.save(deps.storage, (&info.sender, &operator), &expires)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_all")
+        .add_attribute("sender", info.sender)
+        .add_attribute("operator", operator))
+}
+
+fn revoke_all(deps: DepsMut, info: MessageInfo, operator: String) -> Result<Response, ContractError> {
+    let operator = deps.api.addr_validate(&operator)?;
+    OPERATORS.remove(deps.storage, (&info.sender, &operator));
+
+    Ok(Response::new()
+        .add_attribute("action", "revoke_all")
+        .add_attribute("sender", info.sender)
+        .add_attribute("operator", operator))
+}
+
+fn burn(deps: DepsMut, env: Env, info: MessageInfo, token_id: String) -> Result<Response, ContractError> {
+    let token = load_token(deps.as_ref(), &token_id)?;
+    check_can_send(deps.as_ref(), &env, &info.sender, &token)?;
+
+    OWNER_TOKENS.remove(deps.storage, (&token.owner, token_id.as_str()));
+    TOKENS.remove(deps.storage, &token_id);
+    TOKEN_COUNT.update(deps.storage, |count| -> StdResult<_> { Ok(count.saturating_sub(1)) })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "burn")
+        .add_attribute("sender", info.sender)
+        .add_attribute("token_id", token_id))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::OwnerOf { token_id, include_expired } => {
+            to_json_binary(&query_owner_of(deps, &env, token_id, include_expired.unwrap_or(false))?)
+        }
+        QueryMsg::Approval { token_id, spender, include_expired } => {
+            to_json_binary(&query_approval(deps, &env, token_id, spender, include_expired.unwrap_or(false))?)
+        }
+        QueryMsg::Approvals { token_id, include_expired } => {
+            let access = query_owner_of(deps, &env, token_id, include_expired.unwrap_or(false))?;
+            to_json_binary(&ApprovalsResponse { approvals: access.approvals })
+        }
+        QueryMsg::Operator { owner, operator, include_expired } => {
+            to_json_binary(&query_operator(deps, &env, owner, operator, include_expired.unwrap_or(false))?)
+        }
+        QueryMsg::AllOperators { owner, include_expired, start_after, limit } => to_json_binary(
+            &query_all_operators(deps, &env, owner, include_expired.unwrap_or(false), start_after, limit)?,
+        ),
+        QueryMsg::NftInfo { token_id } => to_json_binary(&query_nft_info(deps, token_id)?),
+        QueryMsg::AllNftInfo { token_id, include_expired } => to_json_binary(&AllNftInfoResponse {
+            access: query_owner_of(deps, &env, token_id.clone(), include_expired.unwrap_or(false))?,
+            info: query_nft_info(deps, token_id)?,
+        }),
+        QueryMsg::Tokens { owner, start_after, limit } => to_json_binary(&query_tokens(deps, owner, start_after, limit)?),
+        QueryMsg::AllTokens { start_after, limit } => to_json_binary(&query_all_tokens(deps, start_after, limit)?),
+        QueryMsg::NumTokens {} => to_json_binary(&NumTokensResponse {
+            count: TOKEN_COUNT.load(deps.storage)?,
+        }),
+        QueryMsg::ContractInfo {} => {
+            let collection = COLLECTION.load(deps.storage)?;
+            to_json_binary(&ContractInfoResponse {
+                name: collection.name,
+                symbol: collection.symbol,
+            })
+        }
+        QueryMsg::Minter {} => to_json_binary(&MinterResponse {
+            minter: Some(COLLECTION.load(deps.storage)?.minter.to_string()),
+        }),
+    }
+}
+
+fn query_owner_of(deps: Deps, env: &Env, token_id: String, include_expired: bool) -> StdResult<OwnerOfResponse> {
+    let token = TOKENS.load(deps.storage, &token_id)?;
+    Ok(OwnerOfResponse {
+        owner: token.owner.to_string(),
+        approvals: token
+            .approvals
+            .into_iter()
+            .filter(|approval| include_expired || !approval.is_expired(&env.block))
+            .collect(),
+    })
+}
+
+fn query_approval(
+    deps: Deps,
+    env: &Env,
+    token_id: String,
+    spender: String,
+    include_expired: bool,
+) -> StdResult<ApprovalResponse> {
+    let token = TOKENS.load(deps.storage, &token_id)?;
+    let spender = deps.api.addr_validate(&spender)?;
+    // The owner implicitly holds a permanent approval
+    if token.owner == spender {
+        return Ok(ApprovalResponse {
+            approval: Approval { spender, expires: Expiration::Never {} },
+        });
+    }
+    token
+        .approvals
+        .into_iter()
+        .find(|approval| approval.spender == spender && (include_expired || !approval.is_expired(&env.block)))
+        .map(|approval| ApprovalResponse { approval })
+        .ok_or_else(|| StdError::msg(format!("Approval not found for {spender} on token {token_id}")))
+}
+
+fn query_operator(
+    deps: Deps,
+    env: &Env,
+    owner: String,
+    operator: String,
+    include_expired: bool,
+) -> StdResult<OperatorResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let operator = deps.api.addr_validate(&operator)?;
+    OPERATORS
+        .may_load(deps.storage, (&owner, &operator))?
+        .filter(|expires| include_expired || !expires.is_expired(&env.block))
+        .map(|expires| OperatorResponse {
+            approval: Approval { spender: operator.clone(), expires },
+        })
+        .ok_or_else(|| StdError::msg(format!("Operator not found: {operator} for {owner}")))
+}
+
+fn query_all_operators(
+    deps: Deps,
+    env: &Env,
+    owner: String,
+    include_expired: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OperatorsResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|addr| deps.api.addr_validate(&addr)).transpose()?;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let operators = OPERATORS
+        .prefix(&owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, expires)) => include_expired || !expires.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| item.map(|(spender, expires)| Approval { spender, expires }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(OperatorsResponse { operators })
+}
+
+fn query_nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse> {
+    let token = TOKENS.load(deps.storage, &token_id)?;
+    Ok(NftInfoResponse {
+        token_uri: token.token_uri,
+        extension: token.extension,
+    })
+}
+
+fn query_tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let tokens = OWNER_TOKENS
+        .prefix(&owner)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+fn query_all_tokens(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let tokens = TOKENS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::{from_json, Uint128};
+
+    fn metadata() -> ReceiptMetadata {
+        ReceiptMetadata {
+            price_usd: Uint128::from(25000u128),
+            usd_amount: Uint128::from(1_000_000u128),
+            tokens_purchased: Uint128::from(40_000_000_000u128),
+            payout_denom: "ngonka".to_string(),
+            purchase_height: 100,
+        }
+    }
+
+    /// Instantiates the collection with `sale` as minter and mints receipts "1" and "2" to `buyer`
+    fn setup(mut deps: DepsMut, api: &MockApi) {
+        instantiate(
+            deps.branch(),
+            mock_env(),
+            MessageInfo { sender: api.addr_make("creator"), funds: vec![] },
+            InstantiateMsg {
+                name: "Sale Receipts".to_string(),
+                symbol: "RCPT".to_string(),
+                minter: api.addr_make("sale").to_string(),
+            },
+        )
+        .unwrap();
+        for token_id in ["1", "2"] {
+            execute(
+                deps.branch(),
+                mock_env(),
+                MessageInfo { sender: api.addr_make("sale"), funds: vec![] },
+                mint_msg(token_id, api.addr_make("buyer").as_str()),
+            )
+            .unwrap();
+        }
+    }
+
+    fn mint_msg(token_id: &str, owner: &str) -> ExecuteMsg {
+        ExecuteMsg::Mint {
+            token_id: token_id.to_string(),
+            owner: owner.to_string(),
+            token_uri: None,
+            extension: metadata(),
+        }
+    }
+
+    #[test]
+    fn only_sale_contract_mints_and_tokens_enumerate() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let sale = api.addr_make("sale");
+        let buyer = api.addr_make("buyer").to_string();
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("creator"), funds: vec![] },
+            InstantiateMsg {
+                name: "Sale Receipts".to_string(),
+                symbol: "RCPT".to_string(),
+                minter: sale.to_string(),
+            },
+        )
+        .unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("attacker"), funds: vec![] },
+            mint_msg("1", &buyer),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let sale_info = MessageInfo { sender: sale, funds: vec![] };
+        execute(deps.as_mut(), env.clone(), sale_info.clone(), mint_msg("1", &buyer)).unwrap();
+        execute(deps.as_mut(), env.clone(), sale_info.clone(), mint_msg("2", &buyer)).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), sale_info, mint_msg("1", &buyer)).unwrap_err();
+        assert!(matches!(err, ContractError::Claimed { .. }));
+
+        let tokens: TokensResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::Tokens { owner: buyer, start_after: None, limit: None })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(tokens.tokens, vec!["1".to_string(), "2".to_string()]);
+
+        let info: NftInfoResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::NftInfo { token_id: "2".to_string() }).unwrap())
+                .unwrap();
+        assert_eq!(info.extension, metadata());
+
+        let count: NumTokensResponse = from_json(query(deps.as_ref(), env, QueryMsg::NumTokens {}).unwrap()).unwrap();
+        assert_eq!(count.count, 2);
+    }
+
+    #[test]
+    fn owner_transfers_receipt() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let sale = api.addr_make("sale");
+        let buyer = api.addr_make("buyer");
+        let other = api.addr_make("other");
+        let env = mock_env();
+
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("creator"), funds: vec![] },
+            InstantiateMsg {
+                name: "Sale Receipts".to_string(),
+                symbol: "RCPT".to_string(),
+                minter: sale.to_string(),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: sale, funds: vec![] },
+            mint_msg("1", buyer.as_str()),
+        )
+        .unwrap();
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: buyer.clone(), funds: vec![] },
+            ExecuteMsg::TransferNft { recipient: other.to_string(), token_id: "1".to_string() },
+        )
+        .unwrap();
+
+        let owner: OwnerOfResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::OwnerOf { token_id: "1".to_string(), include_expired: None })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(owner.owner, other.to_string());
+
+        let err = execute(
+            deps.as_mut(),
+            env,
+            MessageInfo { sender: buyer, funds: vec![] },
+            ExecuteMsg::Burn { token_id: "1".to_string() },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn approved_spender_moves_a_receipt_until_it_changes_hands() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let mut env = mock_env();
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let spender = MessageInfo { sender: api.addr_make("spender"), funds: vec![] };
+        let other = api.addr_make("other");
+        let approve = |expires: Option<Expiration>| ExecuteMsg::Approve {
+            spender: spender.sender.to_string(),
+            token_id: "1".to_string(),
+            expires,
+        };
+        let transfer = |recipient: &Addr| ExecuteMsg::TransferNft {
+            recipient: recipient.to_string(),
+            token_id: "1".to_string(),
+        };
+
+        // Only the owner (or an operator) approves, and only into the future
+        let err = execute(deps.as_mut(), env.clone(), spender.clone(), approve(None)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let past = Expiration::AtHeight(env.block.height);
+        let err = execute(deps.as_mut(), env.clone(), buyer.clone(), approve(Some(past))).unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+        let err = execute(deps.as_mut(), env.clone(), spender.clone(), transfer(&other)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        execute(deps.as_mut(), env.clone(), buyer.clone(), approve(Some(expires))).unwrap();
+        let approval = |deps: Deps, env: &Env, include_expired: bool| {
+            query(
+                deps,
+                env.clone(),
+                QueryMsg::Approval {
+                    token_id: "1".to_string(),
+                    spender: spender.sender.to_string(),
+                    include_expired: Some(include_expired),
+                },
+            )
+            .map(|bin| from_json::<ApprovalResponse>(bin).unwrap().approval)
+        };
+        assert_eq!(approval(deps.as_ref(), &env, false).unwrap().expires, expires);
+
+        // Expired approvals neither authorize nor show up unless asked for
+        env.block.height += 10;
+        let err = execute(deps.as_mut(), env.clone(), spender.clone(), transfer(&other)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        assert!(approval(deps.as_ref(), &env, false).is_err());
+        assert!(approval(deps.as_ref(), &env, true).is_ok());
+        let owner_of = |deps: Deps, env: &Env, include_expired: Option<bool>| -> OwnerOfResponse {
+            let msg = QueryMsg::OwnerOf { token_id: "1".to_string(), include_expired };
+            from_json(query(deps, env.clone(), msg).unwrap()).unwrap()
+        };
+        assert!(owner_of(deps.as_ref(), &env, None).approvals.is_empty());
+        assert_eq!(owner_of(deps.as_ref(), &env, Some(true)).approvals.len(), 1);
+
+        // Revoke drops the approval; re-approving replaces rather than duplicates it
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer.clone(),
+            ExecuteMsg::Revoke { spender: spender.sender.to_string(), token_id: "1".to_string() },
+        )
+        .unwrap();
+        assert!(approval(deps.as_ref(), &env, true).is_err());
+        let later = Expiration::AtHeight(env.block.height + 5);
+        execute(deps.as_mut(), env.clone(), buyer.clone(), approve(Some(later))).unwrap();
+        execute(deps.as_mut(), env.clone(), buyer.clone(), approve(None)).unwrap();
+        let approvals: ApprovalsResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::Approvals { token_id: "1".to_string(), include_expired: None })
+                .unwrap(),
+        )
+        .unwrap();
+        let never = Approval { spender: spender.sender.clone(), expires: Expiration::Never {} };
+        assert_eq!(approvals.approvals, vec![never]);
+
+        // The spender moves the receipt, which clears its approvals
+        execute(deps.as_mut(), env.clone(), spender.clone(), transfer(&other)).unwrap();
+        let access = owner_of(deps.as_ref(), &env, Some(true));
+        assert_eq!(access.owner, other.to_string());
+        assert!(access.approvals.is_empty());
+        let err = execute(deps.as_mut(), env.clone(), spender, transfer(&api.addr_make("buyer"))).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        // The owner's own approval is implicit
+        let own: ApprovalResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::Approval { token_id: "1".to_string(), spender: other.to_string(), include_expired: None },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(own.approval.expires, Expiration::Never {});
+    }
+
+    #[test]
+    fn operators_act_on_all_receipts_and_send_calls_the_receiver() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let env = mock_env();
+        let buyer = MessageInfo { sender: api.addr_make("buyer"), funds: vec![] };
+        let operator = MessageInfo { sender: api.addr_make("operator"), funds: vec![] };
+        let marketplace = api.addr_make("marketplace");
+
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer.clone(),
+            ExecuteMsg::ApproveAll {
+                operator: operator.sender.to_string(),
+                expires: Some(Expiration::AtHeight(env.block.height)),
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Expired {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer.clone(),
+            ExecuteMsg::ApproveAll { operator: operator.sender.to_string(), expires: None },
+        )
+        .unwrap();
+
+        let op: OperatorResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Operator {
+                    owner: buyer.sender.to_string(),
+                    operator: operator.sender.to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(op.approval.spender, operator.sender);
+        let all: OperatorsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AllOperators {
+                    owner: buyer.sender.to_string(),
+                    include_expired: None,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(all.operators, vec![Approval { spender: operator.sender.clone(), expires: Expiration::Never {} }]);
+
+        // The operator approves on the owner's behalf and sends a receipt to a contract,
+        // which is told who sent it
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            ExecuteMsg::Approve {
+                spender: api.addr_make("friend").to_string(),
+                token_id: "2".to_string(),
+                expires: None,
+            },
+        )
+        .unwrap();
+        let payload = to_json_binary(&"list").unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            operator.clone(),
+            ExecuteMsg::SendNft { contract: marketplace.to_string(), token_id: "1".to_string(), msg: payload.clone() },
+        )
+        .unwrap();
+        let receive = Cw721ReceiveMsg { sender: operator.sender.to_string(), token_id: "1".to_string(), msg: payload };
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, receive.into_cosmos_msg(marketplace.to_string()).unwrap());
+        let cosmwasm_std::CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute { msg, .. }) = &res.messages[0].msg else {
+            panic!("expected a wasm execute");
+        };
+        assert!(String::from_utf8(msg.to_vec()).unwrap().starts_with(r#"{"receive_nft":{"#));
+        let owner: OwnerOfResponse = from_json(
+            query(deps.as_ref(), env.clone(), QueryMsg::OwnerOf { token_id: "1".to_string(), include_expired: None })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(owner.owner, marketplace.to_string());
+
+        // Once revoked the operator can no longer burn the remaining receipt
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            buyer.clone(),
+            ExecuteMsg::RevokeAll { operator: operator.sender.to_string() },
+        )
+        .unwrap();
+        let err = execute(deps.as_mut(), env.clone(), operator, ExecuteMsg::Burn { token_id: "2".to_string() })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            MessageInfo { sender: api.addr_make("friend"), funds: vec![] },
+            ExecuteMsg::Burn { token_id: "2".to_string() },
+        )
+        .unwrap();
+        let count: NumTokensResponse = from_json(query(deps.as_ref(), env, QueryMsg::NumTokens {}).unwrap()).unwrap();
+        assert_eq!(count.count, 1);
+    }
+}
@@ -0,0 +1,20 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Token id already minted: {token_id}")]
+    Claimed { token_id: String },
+
+    #[error("Token not found: {token_id}")]
+    TokenNotFound { token_id: String },
+
+    #[error("Approval expiry is already in the past")]
+    Expired {},
+}
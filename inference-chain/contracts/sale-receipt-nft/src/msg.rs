@@ -0,0 +1,202 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{to_json_binary, Binary, CosmosMsg, StdResult, WasmMsg};
+use cw_utils::Expiration;
+
+use crate::state::{Approval, ReceiptMetadata};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    /// Sale contract allowed to mint receipts
+    pub minter: String,
+}
+
+/// The cw721 execute interface
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Minter only: mint a receipt to `owner`
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: ReceiptMetadata,
+    },
+    /// Owner, approved spender or operator: transfer a receipt
+    TransferNft { recipient: String, token_id: String },
+    /// Owner, approved spender or operator: transfer a receipt to `contract` and call its
+    /// `ReceiveNft` hook with `msg`
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// Owner or operator: allow `spender` to transfer, send or burn one receipt until `expires`
+    /// (never when None). Approvals are cleared when the receipt changes hands.
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Owner or operator: drop `spender`'s approval on one receipt
+    Revoke { spender: String, token_id: String },
+    /// Allow `operator` to act on all of the sender's receipts until `expires` (never when None)
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    /// Drop an operator of the sender
+    RevokeAll { operator: String },
+    /// Owner, approved spender or operator: burn a receipt
+    Burn { token_id: String },
+}
+
+/// Payload of the `ReceiveNft` hook called on the recipient contract of `SendNft`
+#[cw_serde]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721ReceiveMsg {
+    /// Wraps the payload in the recipient's `ReceiveNft` execute message
+    pub fn into_cosmos_msg(self, contract: String) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract,
+            msg: to_json_binary(&ReceiverExecuteMsg::ReceiveNft(self))?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+#[cw_serde]
+enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+/// The cw721 query interface
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// Owner of a token and its approvals; expired ones only with `include_expired`
+    #[returns(OwnerOfResponse)]
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// Approval of `spender` on a token; fails when there is none (or it expired)
+    #[returns(ApprovalResponse)]
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    /// All approvals on a token
+    #[returns(ApprovalsResponse)]
+    Approvals {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// Whether `operator` may act on all of `owner`'s tokens; fails when it may not
+    #[returns(OperatorResponse)]
+    Operator {
+        owner: String,
+        operator: String,
+        include_expired: Option<bool>,
+    },
+    /// Operators of `owner`, ordered by address
+    #[returns(OperatorsResponse)]
+    AllOperators {
+        owner: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(NftInfoResponse)]
+    NftInfo { token_id: String },
+    #[returns(AllNftInfoResponse)]
+    AllNftInfo {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// Tokens owned by `owner`, ordered by token id
+    #[returns(TokensResponse)]
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// All tokens, ordered by token id
+    #[returns(TokensResponse)]
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(NumTokensResponse)]
+    NumTokens {},
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
+    #[returns(MinterResponse)]
+    Minter {},
+}
+
+#[cw_serde]
+pub struct OwnerOfResponse {
+    pub owner: String,
+    pub approvals: Vec<Approval>,
+}
+
+#[cw_serde]
+pub struct ApprovalResponse {
+    pub approval: Approval,
+}
+
+#[cw_serde]
+pub struct ApprovalsResponse {
+    pub approvals: Vec<Approval>,
+}
+
+#[cw_serde]
+pub struct OperatorResponse {
+    pub approval: Approval,
+}
+
+#[cw_serde]
+pub struct OperatorsResponse {
+    pub operators: Vec<Approval>,
+}
+
+#[cw_serde]
+pub struct NftInfoResponse {
+    pub token_uri: Option<String>,
+    pub extension: ReceiptMetadata,
+}
+
+#[cw_serde]
+pub struct AllNftInfoResponse {
+    pub access: OwnerOfResponse,
+    pub info: NftInfoResponse,
+}
+
+#[cw_serde]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+#[cw_serde]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
+}
+
+#[cw_serde]
+pub struct MinterResponse {
+    pub minter: Option<String>,
+}
@@ -0,0 +1,53 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, BlockInfo, Uint128};
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
+
+#[cw_serde]
+pub struct CollectionInfo {
+    pub name: String,
+    pub symbol: String,
+    /// Sale contract allowed to mint receipts
+    pub minter: Addr,
+}
+
+/// On-chain metadata of a purchase receipt, as sent by the community-sale contract
+#[cw_serde]
+pub struct ReceiptMetadata {
+    /// Price per GNK the purchase executed at
+    pub price_usd: Uint128,
+    pub usd_amount: Uint128,
+    pub tokens_purchased: Uint128,
+    pub payout_denom: String,
+    pub purchase_height: u64,
+}
+
+/// Permission for `spender` to transfer, send or burn one token until `expires`
+#[cw_serde]
+pub struct Approval {
+    pub spender: Addr,
+    pub expires: Expiration,
+}
+
+impl Approval {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.expires.is_expired(block)
+    }
+}
+
+#[cw_serde]
+pub struct TokenInfo {
+    pub owner: Addr,
+    /// Per-token approvals, cleared whenever the token changes hands
+    pub approvals: Vec<Approval>,
+    pub token_uri: Option<String>,
+    pub extension: ReceiptMetadata,
+}
+
+pub const COLLECTION: Item<CollectionInfo> = Item::new("collection");
+pub const TOKENS: Map<&str, TokenInfo> = Map::new("tokens");
+/// (owner, token_id) index for per-owner enumeration
+pub const OWNER_TOKENS: Map<(&Addr, &str), ()> = Map::new("owner_tokens");
+pub const TOKEN_COUNT: Item<u64> = Item::new("token_count");
+/// (owner, operator) -> expiry of an operator allowed to act on all of the owner's tokens
+pub const OPERATORS: Map<(&Addr, &Addr), Expiration> = Map::new("operators");
@@ -12,6 +12,7 @@ rm -rf artifacts/ && mkdir -p artifacts/
 # Build optimized WASM using cosmwasm rust-optimizer
 docker run --rm \
     -v "$SCRIPT_DIR":/code \
+    -v "$SCRIPT_DIR/../packages":/packages \
     --mount type=volume,source="${PROJECT_NAME}_cache",target=/code/target \
     --mount type=volume,source=registry_cache,target=/usr/local/cargo/registry \
     cosmwasm/rust-optimizer:0.17.0 > /dev/null 2>&1
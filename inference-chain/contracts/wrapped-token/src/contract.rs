@@ -1,21 +1,56 @@
 use cosmwasm_std::{
-    entry_point, to_json_binary, to_json_vec, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, QueryRequest, GrpcQuery, StdError, ContractResult, SystemResult, Uint128, CosmosMsg,
+    entry_point, instantiate2_address, to_json_binary, to_json_string, to_json_vec, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, StdError, Int128, Uint128, Uint256, CosmosMsg,
+    Order, Event, Attribute, Storage,
 };
 use cw20_base::contract as cw20_base_contract;
 use cw20_base::msg as cw20_base_msg;
+use cw20_base::state as cw20_base_state;
 use cw_utils::Expiration as CwExpiration;
-use cw20::{EmbeddedLogo as CwEmbeddedLogo, Logo as CwLogo};
+use cw20::{EmbeddedLogo as CwEmbeddedLogo, Logo as CwLogo, LogoInfo};
 use cw2::{get_contract_version, set_contract_version};
 use cw_storage_plus::Item;
 use prost::Message as ProstMessage;
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+use external_address::ExternalAddress;
 
 use crate::error::ContractError;
+use crate::grpc::{query_proto, record_failure};
+use crate::export;
+use crate::proto::{
+    self, MsgAckBridgeDeposit, MsgRequestBridgeWithdrawal, QueryBridgeLedgerBalanceRequest, QueryBridgeLedgerBalanceResponse,
+    QueryBridgeTokenMetadataRequest, QueryBridgeTokenMetadataResponse, QuerySanctionedAddressRequest,
+    QuerySanctionedAddressResponse, QueryGetCurrentEpochRequest, QueryGetCurrentEpochResponse,
+    QueryExternalTokenMetadataRequest, QueryExternalTokenMetadataResponse,
+};
 use crate::msg::{
-    BridgeInfoResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
-    ApprovedTokensForTradeJson, ApprovedTokenJson,
+    BridgeInfoResponse, BridgeInfoHistoryResponse, BridgeRouteEntry, BridgeSaltResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    SupplyBreakdownResponse, ReconcileResponse, SpenderActivityResponse,
+    TransferGuardResponse, SessionResponse, SessionsResponse, StatusResponse, MinterResponse,
+    DepositStatusResponse, AuditLogResponse, SudoMsg, ForwardAllowlistResponse, BlockedDestinationsResponse, LockupRegistryResponse,
+    LockupRegistryQueryMsg, LockedResponse, RelayerBondQueryMsg, IsBondedResponse, RelayerBondResponse, Cw20Coin, ExportBalancesResponse, VersionResponse,
+    TransferSignDoc, TransferNonceResponse, PendingWithdrawal, PendingWithdrawalsResponse, ExemptRelayersResponse, HoldersAtResponse,
+    EpochWithdrawalsResponse,
+    TravelRuleMemo, MemoRuleEntry, MemoRulesResponse, SpendAlertEntry, SpendAlertsResponse, CircuitBreakerResponse, FlowStatus,
+    ManifestResponse, ManifestRoles, ManifestLimits, SelfTransfersResponse, CanWithdrawResponse,
+    InterfaceVersionResponse, EXECUTE_INTERFACE_VERSION, QUERY_INTERFACE_VERSION, SUDO_INTERFACE_VERSION,
+};
+use crate::state::{
+    BridgeInfo, BRIDGE_INFO, BRIDGE_INFO_HISTORY, TOKEN_METADATA, TokenMetadataOverride, FROZEN_ACCOUNTS,
+    PENDING_WITHDRAWAL_SUPPLY, SPENDER_ACTIVITY, INSTANTIATE_SALT, TransferGuard, DailyOutflow,
+    TRANSFER_GUARDS, DAILY_OUTFLOW, Session, SESSIONS, PROCESSED_DEPOSITS, AuditEntry, AUDIT_LOG,
+    NEXT_AUDIT_ID, LockupRegistryConfig, LOCKUP_REGISTRY, CachedLockup, LOCKUP_CACHE,
+    FORWARD_ALLOWLIST, BALANCE_IMPORT_OPEN, DEPOSIT_ACKS_ENABLED, CW20_BASE_VERSION, TRANSFER_NONCES, WithdrawalBatching,
+    WITHDRAWAL_BATCHING, EXEMPT_RELAYERS, PENDING_WITHDRAWALS, PENDING_WITHDRAWAL_SENDERS, BALANCE_CHECKPOINTS, CHECKPOINT_HEIGHTS, SNAPSHOTS_SINCE, TravelRule, TRAVEL_RULE,
+    SpendAlert, SPEND_ALERTS, SPEND_ALERT_THRESHOLDS, NEXT_SPEND_ALERT, CircuitBreaker, VolumeLimit, CIRCUIT_BREAKER,
+    FLOW_VOLUME, HALTED_FLOWS, BLOCKED_DESTINATIONS, SelfTransferPolicy, SELF_TRANSFER_POLICY, PENDING_ADMIN,
+    RELAYER_BOND, SanctionsSync, SANCTIONS_SYNC, CachedSanction, SANCTIONS_CACHE, PriorityFees, PRIORITY_FEES,
+    PENDING_PRIORITY_FEES, WITHDRAWAL_PRIORITY, WithdrawalTotals, EPOCH_WITHDRAWALS, UNATTRIBUTED_WITHDRAWALS,
+    MemoEncoding, MemoRule, MEMO_RULES, STATE_ROOT_PROGRESS, FROZEN_ACCOUNT_COUNT, GUARDED_ACCOUNT_COUNT, PENDING_WITHDRAWAL_COUNT,
 };
-use crate::state::{ BridgeInfo, BRIDGE_INFO, TOKEN_METADATA, TokenMetadataOverride };
+use gonka_cw_controllers::{ownership, roles};
+use cw_storage_plus::{Bound, PrefixBound};
 
 // Admin storage: stores the address of the contract admin (governance module)
 pub const ADMIN: Item<Addr> = Item::new("admin");
@@ -25,18 +60,50 @@ pub const CREATOR: Item<Addr> = Item::new("creator");
 
 const CONTRACT_NAME: &str = "wrapped-token";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const CW20_BASE_CONTRACT_NAME: &str = "crates.io:cw20-base";
+
+// Largest MintBatch and TransferFromBatch
+const MAX_MINT_BATCH: usize = 100;
+const MAX_TRANSFER_BATCH: usize = 100;
+// Largest balance chunk ExportBalances returns and ImportBalances accepts
+const MAX_BALANCE_CHUNK: u32 = 100;
+// Bounds on travel-rule memos: each text field, and the encoded memo as a whole
+const MAX_TRAVEL_RULE_FIELD_LEN: usize = 128;
+const MAX_TRAVEL_RULE_MEMO_LEN: usize = 1024;
+// Conventional EVM burn addresses, in normalized form; withdrawals to them need `force`
+const BURN_ADDRESSES: [&str; 2] = [
+    "0x0000000000000000000000000000000000000000",
+    "0x000000000000000000000000000000000000dead",
+];
+
+// Longest logo URI SyncExternalMetadata takes from the chain
+const MAX_LOGO_URI_LEN: usize = 512;
+// Metadata used when neither the instantiate message nor the chain provides a valid value
+const FALLBACK_NAME: &str = "Wrapped Token";
+const FALLBACK_SYMBOL: &str = "WTKN";
+const FALLBACK_DECIMALS: u8 = 6;
+
+// Pagination settings, matching cw20-base
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// HoldersAt reaches back this many blocks (about a week at 6s blocks); older balance checkpoints
+// are pruned, at most MAX_CHECKPOINT_PRUNE per call so no single call pays for a backlog
+const SNAPSHOT_RETENTION_BLOCKS: u64 = 100_800;
+const MAX_CHECKPOINT_PRUNE: usize = 20;
+
+// Flows the circuit breaker watches
+const FLOW_MINT: &str = "mint";
+const FLOW_WITHDRAW: &str = "withdraw";
+const MAX_BPS: u16 = 10_000;
 
 #[entry_point]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    // Note: We don't set_contract_version here because cw20_base_contract::instantiate
-    // will set it to "crates.io:cw20-base". Our migrate function handles this by
-    // allowing migration from both "wrapped-token" and "crates.io:cw20-base".
-    
     // Save creator (instantiator = inference module) - controls operations
     CREATOR.save(deps.storage, &info.sender)?;
     
@@ -56,16 +123,23 @@ pub fn instantiate(
         }
     };
     ADMIN.save(deps.storage, &admin_addr)?;
+
+    // Deterministic deployment: the salt must commit to the bridge info being persisted
+    if let Some(salt) = &msg.salt {
+        verify_instantiate_salt(deps.as_ref(), &env, &info, &msg.chain_id, &msg.contract_address, salt)?;
+        INSTANTIATE_SALT.save(deps.storage, salt)?;
+    }
     
     // Persist bridge info (extra state)
-    BRIDGE_INFO.save(deps.storage, &BridgeInfo { chain_id: msg.chain_id.clone(), contract_address: msg.contract_address.clone() })?;
+    let contract_address = ExternalAddress::parse(&msg.contract_address)?;
+    save_bridge_info(deps.storage, &env, &BridgeInfo { chain_id: msg.chain_id.clone(), contract_address })?;
 
-    // Map our instantiate to cw20-base InstantiateMsg (use placeholders if needed)
+    // Map our instantiate to cw20-base InstantiateMsg
+    let (name, symbol, decimals) = resolve_token_metadata(deps.branch(), &env, &msg)?;
     let cw20_init = cw20_base_msg::InstantiateMsg {
-        name: "Wrapped Token".to_string(),
-        // cw20-base enforces ticker format [a-zA-Z\-]{3,12}
-        symbol: "WTKN".to_string(),
-        decimals: 6,
+        name,
+        symbol,
+        decimals,
         initial_balances: msg.initial_balances.into_iter().map(|c| cw20::Cw20Coin { address: c.address, amount: c.amount }).collect(),
         mint: msg.mint.map(|m| cw20::MinterResponse { minter: m.minter, cap: m.cap }),
         // Set marketing account to admin (governance module)
@@ -77,308 +151,4928 @@ pub fn instantiate(
             logo: None,
         }),
     };
-    let resp = cw20_base_contract::instantiate(deps, env, info, cw20_init)
-        .map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
+    BALANCE_IMPORT_OPEN.save(deps.storage, &())?;
+    SNAPSHOTS_SINCE.save(deps.storage, &env.block.height)?;
+    let resp = cw20_base_contract::instantiate(deps.branch(), env, info, cw20_init)
+        .map_err(ContractError::from)?;
+
+    // cw20-base stamps its own name into cw2; keep that as the embedded base version and
+    // claim cw2 for wrapped-token so migrations only need to recognise one name
+    let base_version = get_contract_version(deps.storage)?;
+    CW20_BASE_VERSION.save(deps.storage, &base_version)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     Ok(resp)
 }
 
+/// Name, symbol and decimals for a new instance. Explicit values must be valid; omitted ones are
+/// taken from the chain's metadata for the external token when valid there, else the fallbacks.
+fn resolve_token_metadata(deps: DepsMut, env: &Env, msg: &InstantiateMsg) -> Result<(String, String, u8), ContractError> {
+    if let Some(name) = &msg.name {
+        validate_name(name)?;
+    }
+    if let Some(symbol) = &msg.symbol {
+        validate_symbol(symbol)?;
+    }
+    if let Some(decimals) = msg.decimals {
+        validate_decimals(decimals)?;
+    }
+    let chain = if msg.name.is_none() || msg.symbol.is_none() || msg.decimals.is_none() {
+        // The lookup is best effort: an unknown token or unavailable endpoint means fallbacks
+        query_token_metadata(deps, env, &msg.chain_id, ExternalAddress::from(msg.contract_address.clone()))?
+    } else {
+        None
+    };
+    let name = msg.name.clone().unwrap_or_else(|| {
+        chain
+            .as_ref()
+            .map(|m| m.name.clone())
+            .filter(|name| validate_name(name).is_ok())
+            .unwrap_or_else(|| FALLBACK_NAME.to_string())
+    });
+    let symbol = msg.symbol.clone().unwrap_or_else(|| {
+        chain
+            .as_ref()
+            .map(|m| m.symbol.clone())
+            .filter(|symbol| validate_symbol(symbol).is_ok())
+            .unwrap_or_else(|| FALLBACK_SYMBOL.to_string())
+    });
+    let decimals = msg.decimals.unwrap_or_else(|| {
+        chain
+            .as_ref()
+            .and_then(|m| u8::try_from(m.decimals).ok())
+            .filter(|decimals| validate_decimals(*decimals).is_ok())
+            .unwrap_or(FALLBACK_DECIMALS)
+    });
+    Ok((name, symbol, decimals))
+}
+
+// Same rules cw20-base applies at instantiate, also enforced on UpdateMetadata
+fn validate_name(name: &str) -> Result<(), ContractError> {
+    if name.len() < 3 || name.len() > 50 {
+        return Err(ContractError::InvalidTokenMetadata { reason: "name must be 3-50 characters".to_string() });
+    }
+    Ok(())
+}
+
+fn validate_symbol(symbol: &str) -> Result<(), ContractError> {
+    if symbol.len() < 3 || symbol.len() > 12 || !symbol.bytes().all(|b| b.is_ascii_alphabetic() || b == b'-') {
+        return Err(ContractError::InvalidTokenMetadata { reason: "symbol must match [a-zA-Z-]{3,12}".to_string() });
+    }
+    Ok(())
+}
+
+fn validate_decimals(decimals: u8) -> Result<(), ContractError> {
+    if decimals > 18 {
+        return Err(ContractError::InvalidTokenMetadata { reason: "decimals must be at most 18".to_string() });
+    }
+    Ok(())
+}
+
+/// Salt the chain module uses with Instantiate2 for a bridge route; external addresses are
+/// compared case-insensitively, so the address is lowercased before hashing.
+pub fn bridge_salt(chain_id: &str, contract_address: &str) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update(chain_id.as_bytes());
+    hasher.update(b":");
+    hasher.update(contract_address.to_lowercase().as_bytes());
+    Binary::from(hasher.finalize().to_vec())
+}
+
+fn verify_instantiate_salt(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    chain_id: &str,
+    contract_address: &str,
+    salt: &Binary,
+) -> Result<(), ContractError> {
+    let mismatch = || ContractError::SaltMismatch {
+        chain_id: chain_id.to_string(),
+        contract_address: contract_address.to_string(),
+    };
+    if *salt != bridge_salt(chain_id, contract_address) {
+        return Err(mismatch());
+    }
+
+    // When contract info is already queryable, also check our address was derived from this salt
+    if let Ok(contract_info) = deps.querier.query_wasm_contract_info(&env.contract.address) {
+        let checksum = deps.querier.query_wasm_code_info(contract_info.code_id)?.checksum;
+        let creator = deps.api.addr_canonicalize(info.sender.as_str())?;
+        let expected = instantiate2_address(checksum.as_slice(), &creator, salt)
+            .map_err(|e| ContractError::Std(StdError::generic_err(format!("instantiate2 address: {}", e))))?;
+        if deps.api.addr_canonicalize(env.contract.address.as_str())? != expected {
+            return Err(mismatch());
+        }
+    }
+    Ok(())
+}
+
 // (Removed: legacy local cw20 state and queries — delegated to cw20-base)
 
 #[entry_point]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // Any other activity makes balances authoritative, closing the redeployment import
+    if !matches!(msg, ExecuteMsg::ImportBalances { .. }) && BALANCE_IMPORT_OPEN.exists(deps.storage) {
+        BALANCE_IMPORT_OPEN.remove(deps.storage);
+    }
+    // A state root commitment spanning several calls only holds while nothing else writes
+    if !matches!(msg, ExecuteMsg::CommitStateRoot { .. }) && STATE_ROOT_PROGRESS.exists(deps.storage) {
+        STATE_ROOT_PROGRESS.remove(deps.storage);
+    }
+
+    // Tokens sent to the contract's own address are stranded, so catch them before anything moves
+    let to_self = transfer_recipients(&msg)
+        .into_iter()
+        .filter(|(recipient, _)| *recipient == env.contract.address.as_str())
+        .fold(Uint128::zero(), |total, (_, amount)| total.saturating_add(amount));
+    let returned = match (to_self.is_zero(), SELF_TRANSFER_POLICY.may_load(deps.storage)?.unwrap_or_default()) {
+        (true, _) => None,
+        (false, SelfTransferPolicy::Reject) => return Err(ContractError::TransferToSelf {}),
+        (false, SelfTransferPolicy::Return) => debited_account(&info, &msg).map(|(account, _)| (account, to_self)),
+    };
+
+    // Frozen accounts cannot move their balance out, directly or through an allowance,
+    // and guarded accounts cannot exceed their own limits. Exempt relayers' withdrawals are
+    // rebalancing rather than outflow, so they are left out of the daily limits.
+    if let Some((account, amount)) = debited_account(&info, &msg) {
+        ensure_not_frozen(deps.as_ref(), &account)?;
+        if !is_exempt_withdrawal(deps.as_ref(), &info.sender, &msg) {
+            apply_transfer_guard(deps.branch(), &env, &account, amount)?;
+        }
+        enforce_lockup(deps.branch(), &env, &account, amount)?;
+    }
+
+    // Record opening balances before anything below can change them, for HoldersAt
+    checkpoint_balances(deps.branch(), &env, &info, &msg)?;
+
+    // Supply-changing events carry the bridge route so indexers watching many wrapped tokens
+    // can attribute them without querying each contract
+    let bridge_info = if changes_supply(&msg) {
+        Some(BRIDGE_INFO.load(deps.storage)?)
+    } else {
+        None
+    };
+
+    let resp = dispatch(deps.branch(), env.clone(), info, msg)?;
+    let resp = match returned {
+        Some((account, amount)) => return_self_transfer(deps.storage, &env, resp, account, amount)?,
+        None => resp,
+    };
+
+    Ok(match bridge_info {
+        Some(bridge_info) => tag_bridge_route(resp, &bridge_info),
+        None => resp,
+    })
+}
+
+fn dispatch(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> Result<Response, ContractError> {
     match msg {
         // Custom extras
-        ExecuteMsg::Withdraw { amount, destination_address } => withdraw(deps, env, info, amount, destination_address),
-        ExecuteMsg::UpdateMetadata { name, symbol, decimals } => update_metadata(deps, info, name, symbol, decimals),
+        ExecuteMsg::FreezeAccount { address } => freeze_account(deps, env, info, address),
+        ExecuteMsg::UnfreezeAccount { address } => unfreeze_account(deps, env, info, address),
+        ExecuteMsg::UpdateBridgeInfo { chain_id, contract_address } => update_bridge_info(deps, env, info, chain_id, contract_address),
+        ExecuteMsg::GrantSession { session_key, max_amount, expires_at_height } => grant_session(deps, env, info, session_key, max_amount, expires_at_height),
+        ExecuteMsg::RevokeSession { session_key } => revoke_session(deps, info, session_key),
+        ExecuteMsg::SubscribeSpendAlerts { threshold } => subscribe_spend_alerts(deps, info, threshold),
+        ExecuteMsg::SessionTransfer { owner, recipient, amount } => session_transfer(deps, env, info, owner, recipient, amount),
+        ExecuteMsg::TransferBySig { owner, recipient, amount, nonce, deadline, pubkey, signature } => {
+            let doc = TransferSignDoc {
+                chain_id: env.block.chain_id.clone(),
+                contract: env.contract.address.to_string(),
+                owner,
+                recipient,
+                amount,
+                nonce,
+                deadline,
+            };
+            transfer_by_sig(deps, env, info, doc, pubkey, signature)
+        }
+        ExecuteMsg::SetTransferGuard { max_per_tx, daily_max } => set_transfer_guard(deps, info, max_per_tx, daily_max),
+        ExecuteMsg::SetLockupRegistry { contract, cache_blocks } => set_lockup_registry(deps, env, info, contract, cache_blocks),
+        ExecuteMsg::SetSanctionsSync { cache_blocks } => set_sanctions_sync(deps, env, info, cache_blocks),
+        ExecuteMsg::SetRelayerBond { contract } => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            set_relayer_bond(deps, env, info.sender, contract)
+        }
+        ExecuteMsg::ForwardModuleMsg { type_url, value } => {
+            if info.sender != CREATOR.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            forward_module_msg(deps, env, info.sender, type_url, value)
+        }
+        ExecuteMsg::MintBatch { mints } => {
+            if info.sender != CREATOR.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            ensure_bonded_relayer(deps.as_ref(), &info.sender)?;
+            mint_batch(deps, env, mints)
+        }
+        ExecuteMsg::SetDepositAcks { enabled } => set_deposit_acks(deps, env, info, enabled),
+        ExecuteMsg::SetWithdrawalBatching { threshold } => set_withdrawal_batching(deps, env, info, threshold),
+        ExecuteMsg::SetPriorityFees { fee_collector } => set_priority_fees(deps, env, info, fee_collector),
+        ExecuteMsg::SetTravelRule { threshold } => set_travel_rule(deps, env, info, threshold),
+        ExecuteMsg::SetMemoRule { chain_id, rule } => set_memo_rule(deps, env, info, chain_id, rule),
+        ExecuteMsg::SetSelfTransferPolicy { policy } => set_self_transfer_policy(deps, env, info, policy),
+        ExecuteMsg::RecoverStuckBalance { recipient, amount } => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            recover_stuck_balance(deps, env, info.sender, recipient, amount)
+        }
+        ExecuteMsg::ProposeAdmin { admin, expiry_height } => propose_admin(deps, env, info, admin, expiry_height),
+        ExecuteMsg::AcceptAdmin {} => accept_admin(deps, env, info),
+        ExecuteMsg::CancelAdminProposal {} => cancel_admin_proposal(deps, env, info),
+        ExecuteMsg::SetRelayerExemption { relayer, exempt } => set_relayer_exemption(deps, env, info, relayer, exempt),
+        ExecuteMsg::FlushWithdrawals { limit } => flush_withdrawals(deps, env, limit),
+        ExecuteMsg::CommitStateRoot { limit } => export::commit_state_root(deps, env, limit).map_err(ContractError::from),
+        ExecuteMsg::UpdateMinterCap { new_cap } => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            update_minter_cap(deps, env, info.sender, new_cap)
+        }
+        ExecuteMsg::SetCircuitBreaker { breaker } => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            set_circuit_breaker(deps, env, info.sender, breaker)
+        }
+        ExecuteMsg::ResumeCircuitBreaker {} => {
+            if info.sender != ADMIN.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            resume_circuit_breaker(deps, env, info.sender)
+        }
+        ExecuteMsg::TransferMinter { new_minter } => {
+            if info.sender != CREATOR.load(deps.storage)? {
+                return Err(ContractError::Unauthorized {});
+            }
+            transfer_minter(deps, env, info.sender, new_minter)
+        }
+        ExecuteMsg::ExportBalances { start_after, limit } => export_balances(deps, info, start_after, limit),
+        ExecuteMsg::ImportBalances { balances } => import_balances(deps, env, info, balances),
+        ExecuteMsg::Withdraw { amount, destination_address, travel_rule, force, priority_fee, payload } => {
+            withdraw(deps, env, info, amount, destination_address, travel_rule, force, priority_fee, payload)
+        }
+        ExecuteMsg::UpdateMetadata { name, symbol, decimals } => update_metadata(deps, env, info, name, symbol, decimals),
         // Delegate all standard cw20 ops
-        ExecuteMsg::Transfer { recipient, amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Transfer { recipient, amount }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::Burn { amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Burn { amount }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::Send { contract, amount, msg } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Send { contract, amount, msg }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::Mint { recipient, amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Mint { recipient, amount }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::IncreaseAllowance { spender, amount, expires } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::IncreaseAllowance { spender, amount, expires: map_expiration(expires) }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::DecreaseAllowance { spender, amount, expires } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::DecreaseAllowance { spender, amount, expires: map_expiration(expires) }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::TransferFrom { owner, recipient, amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::TransferFrom { owner, recipient, amount }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::SendFrom { owner, contract, amount, msg } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::SendFrom { owner, contract, amount, msg }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::BurnFrom { owner, amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::BurnFrom { owner, amount }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::UpdateMarketing { project, description, marketing } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::UpdateMarketing { project, description, marketing }).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
-        ExecuteMsg::UploadLogo(logo) => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::UploadLogo(map_logo(logo))).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string()))),
+        ExecuteMsg::Transfer { recipient, amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Transfer { recipient, amount }).map_err(ContractError::from),
+        ExecuteMsg::Burn { amount } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Burn { amount }).map_err(ContractError::from),
+        ExecuteMsg::Send { contract, amount, msg } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::Send { contract, amount, msg }).map_err(ContractError::from),
+        ExecuteMsg::Mint { recipient, amount } => mint(deps, env, info, recipient, amount),
+        ExecuteMsg::IncreaseAllowance { spender, amount, expires } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::IncreaseAllowance { spender, amount, expires: map_expiration(expires) }).map_err(ContractError::from),
+        ExecuteMsg::DecreaseAllowance { spender, amount, expires } => cw20_base_contract::execute(deps, env, info, cw20_base_msg::ExecuteMsg::DecreaseAllowance { spender, amount, expires: map_expiration(expires) }).map_err(ContractError::from),
+        ExecuteMsg::TransferFrom { owner, recipient, amount } => execute_spend_from(deps, env, info, owner.clone(), amount, "transfer_from", cw20_base_msg::ExecuteMsg::TransferFrom { owner, recipient, amount }),
+        ExecuteMsg::TransferFromBatch { owner, recipients } => transfer_from_batch(deps, env, info, owner, recipients),
+        ExecuteMsg::SendFrom { owner, contract, amount, msg } => execute_spend_from(deps, env, info, owner.clone(), amount, "send_from", cw20_base_msg::ExecuteMsg::SendFrom { owner, contract, amount, msg }),
+        ExecuteMsg::PullAndNotify { owner, amount, contract, payload } => pull_and_notify(deps, env, info, owner, amount, contract, payload),
+        ExecuteMsg::BurnFrom { owner, amount } => execute_spend_from(deps, env, info, owner.clone(), amount, "burn_from", cw20_base_msg::ExecuteMsg::BurnFrom { owner, amount }),
+        ExecuteMsg::UpdateMarketing { project, description, marketing } => update_marketing(deps, env, info, project, description, marketing),
+        ExecuteMsg::UploadLogo(logo) => upload_logo(deps, env, info, logo),
+        ExecuteMsg::SyncExternalMetadata {} => sync_external_metadata(deps, env, info),
     }
 }
 
-fn map_logo(logo: crate::msg::Logo) -> CwLogo {
-    match logo {
-        crate::msg::Logo::Url(u) => CwLogo::Url(u),
-        crate::msg::Logo::Embedded(embed) => match embed {
-            crate::msg::EmbeddedLogo::Svg(b) => CwLogo::Embedded(CwEmbeddedLogo::Svg(b)),
-            crate::msg::EmbeddedLogo::Png(b) => CwLogo::Embedded(CwEmbeddedLogo::Png(b)),
-        },
-    }
+/// Appends a privileged action to the hash-chained audit log
+fn record_audit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    actor: &Addr,
+    action: &str,
+    old_value: Option<String>,
+    new_value: Option<String>,
+) -> StdResult<()> {
+    let id = NEXT_AUDIT_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_AUDIT_ID.save(storage, &(id + 1))?;
+    let prev_hash = match id.checked_sub(1) {
+        Some(prev) => AUDIT_LOG.load(storage, prev)?.hash,
+        None => Binary::default(),
+    };
+    let mut entry = AuditEntry {
+        id,
+        actor: actor.to_string(),
+        action: action.to_string(),
+        height: env.block.height,
+        old_value,
+        new_value,
+        hash: Binary::default(),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_slice());
+    hasher.update(to_json_vec(&entry)?);
+    entry.hash = Binary::from(hasher.finalize().to_vec());
+    AUDIT_LOG.save(storage, id, &entry)
 }
 
-fn map_expiration(exp: Option<crate::msg::Expiration>) -> Option<CwExpiration> {
-    exp.map(|e| match e {
-        crate::msg::Expiration::AtHeight(h) => CwExpiration::AtHeight(h),
-        crate::msg::Expiration::AtTime(t) => CwExpiration::AtTime(t),
-        crate::msg::Expiration::Never {} => CwExpiration::Never {},
-    })
+fn changes_supply(msg: &ExecuteMsg) -> bool {
+    matches!(
+        msg,
+        ExecuteMsg::Mint { .. }
+            | ExecuteMsg::MintBatch { .. }
+            | ExecuteMsg::ImportBalances { .. }
+            | ExecuteMsg::FlushWithdrawals { .. }
+            | ExecuteMsg::Burn { .. }
+            | ExecuteMsg::BurnFrom { .. }
+            | ExecuteMsg::Withdraw { .. }
+    )
 }
 
-/// Allows both creator (inference module) and admin (governance module) to update token metadata.
-fn update_metadata(
-    deps: DepsMut,
+/// Adds `chain_id` and `contract_address` to the wasm event and every custom event
+fn tag_bridge_route(mut resp: Response, bridge_info: &BridgeInfo) -> Response {
+    for event in resp.events.iter_mut() {
+        event.attributes.push(Attribute::new("chain_id", bridge_info.chain_id.clone()));
+        event.attributes.push(Attribute::new("contract_address", bridge_info.contract_address.to_string()));
+    }
+    resp.add_attribute("chain_id", bridge_info.chain_id.clone())
+        .add_attribute("contract_address", bridge_info.contract_address.to_string())
+}
+
+/// Delegates an allowance-based spend to cw20-base, then records the spend against the
+/// (owner, spender) pair and emits an event attributing it to both.
+fn execute_spend_from(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    name: String,
-    symbol: String,
-    decimals: u8,
+    owner: String,
+    amount: Uint128,
+    action: &str,
+    base_msg: cw20_base_msg::ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    // Load both creator and admin addresses
-    let creator = CREATOR.load(deps.storage)?;
-    let admin = ADMIN.load(deps.storage)?;
-    
-    // Allow both creator (inference module) and admin (governance module) to update metadata
-    let is_creator = info.sender == creator;
-    let is_admin = info.sender == admin;
-    
-    if !is_creator && !is_admin {
-        return Err(ContractError::Unauthorized {});
-    }
+    let resp = cw20_base_contract::execute(deps.branch(), env.clone(), info.clone(), base_msg)
+        .map_err(ContractError::from)?;
 
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let event = record_allowance_spend(deps.storage, &env, &owner_addr, &info.sender, amount, action)?;
+    Ok(resp.add_event(event))
+}
 
-    TOKEN_METADATA.save(
-        deps.storage,
-        &TokenMetadataOverride { name: name.clone(), symbol: symbol.clone(), decimals },
-    )?;
+/// Records a spend already deducted from the (owner, spender) allowance and returns the event
+/// attributing it to both
+fn record_allowance_spend(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    spender: &Addr,
+    amount: Uint128,
+    action: &str,
+) -> StdResult<Event> {
+    let remaining_allowance = cw20_base_state::ALLOWANCES
+        .may_load(storage, (owner, spender))?
+        .map(|a| a.allowance)
+        .unwrap_or_default();
+    SPENDER_ACTIVITY.update(storage, (owner, spender), |activity| -> StdResult<_> {
+        let mut activity = activity.unwrap_or_default();
+        activity.total_spent = activity.total_spent.checked_add(amount)?;
+        activity.spend_count += 1;
+        activity.last_spend_height = env.block.height;
+        Ok(activity)
+    })?;
+    record_spend_alert(storage, env, owner, spender, amount, action)?;
 
-    Ok(Response::new()
-        .add_attribute("method", "update_metadata")
-        .add_attribute("name", name)
-        .add_attribute("symbol", symbol)
-        .add_attribute("decimals", decimals.to_string()))
+    Ok(Event::new("allowance_spend")
+        .add_attribute("action", action)
+        .add_attribute("owner", owner)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount)
+        .add_attribute("remaining_allowance", remaining_allowance))
 }
 
-// Special bridge withdraw function
-fn withdraw(
+/// Pays every recipient from `owner`'s balance against a single deduction of the total from the
+/// caller's allowance, so the whole batch counts as one spend. Fails as a whole if any item does.
+fn transfer_from_batch(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    amount: Uint128,
-    destination_address: String,
+    owner: String,
+    recipients: Vec<Cw20Coin>,
 ) -> Result<Response, ContractError> {
-    if amount.is_zero() {
-        return Err(ContractError::InsufficientFunds {
-            balance: 0,
-            required: 1,
-        });
+    if recipients.len() > MAX_TRANSFER_BATCH {
+        return Err(ContractError::BatchTooLarge { size: recipients.len(), max: MAX_TRANSFER_BATCH });
+    }
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let mut total = Uint128::zero();
+    let mut payments = Vec::with_capacity(recipients.len());
+    for Cw20Coin { address, amount } in recipients {
+        if amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        let recipient = deps.api.addr_validate(&address)?;
+        total = total.checked_add(amount).map_err(StdError::from)?;
+        payments.push((recipient, amount));
+    }
+    if total.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
     }
 
-    // Validate destination address is not empty
-    if destination_address.trim().is_empty() {
-        return Err(ContractError::Std(StdError::generic_err("destination_address cannot be empty")));
+    cw20_base::allowances::deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, total)?;
+    cw20_base_state::BALANCES.update(deps.storage, &owner_addr, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_sub(total)?)
+    })?;
+    let mut resp = Response::new()
+        .add_attribute("method", "transfer_from_batch")
+        .add_attribute("owner", owner_addr.to_string())
+        .add_attribute("spender", info.sender.to_string())
+        .add_attribute("recipients", payments.len().to_string())
+        .add_attribute("total", total);
+    for (recipient, amount) in payments {
+        cw20_base_state::BALANCES.update(deps.storage, &recipient, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_add(amount)?)
+        })?;
+        resp = resp.add_event(
+            Event::new("transfer_from_batch_item")
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount),
+        );
     }
 
-    // Delegate to cw20-base burn
-    let mut resp = cw20_base_contract::execute(
-        deps,
-        env.clone(),
-        info.clone(),
-        cw20_base_msg::ExecuteMsg::Burn { amount },
-    ).map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
+    let event = record_allowance_spend(deps.storage, &env, &owner_addr, &info.sender, total, "transfer_from_batch")?;
+    Ok(resp.add_event(event))
+}
 
-    // Create the bridge withdrawal message
-    let bridge_msg = create_bridge_withdrawal_msg(
-        env.contract.address.to_string(), // creator (this contract - will be the transaction signer)
-        info.sender.to_string(),          // user_address (the caller)
-        amount.to_string(),               // amount
-        destination_address.clone(),      // destination_address
+/// `TransferFrom` to `contract`, then `Receive` on it. As with `SendFrom`, the notification names
+/// the spender: the target and payload are the spender's choice, so naming the owner would let
+/// any allowance holder act on Receive-hook contracts as the owner.
+fn pull_and_notify(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    amount: Uint128,
+    contract: String,
+    payload: Binary,
+) -> Result<Response, ContractError> {
+    let contract_addr = deps.api.addr_validate(&contract)?;
+    let spender = info.sender.clone();
+    let resp = execute_spend_from(
+        deps,
+        env,
+        info,
+        owner.clone(),
+        amount,
+        "pull_and_notify",
+        cw20_base_msg::ExecuteMsg::TransferFrom { owner: owner.clone(), recipient: contract, amount },
     )?;
-
-    resp = resp
-        .add_message(bridge_msg)
-        .add_attribute("method", "withdraw")
-        .add_attribute("burn_amount", amount)
-        .add_attribute("destination_address", destination_address);
-
-    Ok(resp)
+    let notify: CosmosMsg =
+        cw20::Cw20ReceiveMsg { sender: spender.to_string(), amount, msg: payload }.into_cosmos_msg(contract_addr.clone())?;
+    Ok(resp
+        .add_message(notify)
+        .add_attribute("method", "pull_and_notify")
+        .add_attribute("spender", spender)
+        .add_attribute("contract", contract_addr))
 }
 
-// Proto message for MsgRequestBridgeWithdrawal
-#[derive(Clone, PartialEq, ProstMessage)]
-pub struct MsgRequestBridgeWithdrawal {
-    #[prost(string, tag = "1")]
-    pub creator: String,
-    #[prost(string, tag = "2")]
-    pub user_address: String,
-    #[prost(string, tag = "3")]
-    pub amount: String,
-    #[prost(string, tag = "4")]
-    pub destination_address: String,
+fn subscribe_spend_alerts(deps: DepsMut, info: MessageInfo, threshold: Option<Uint128>) -> Result<Response, ContractError> {
+    match threshold {
+        Some(threshold) => SPEND_ALERT_THRESHOLDS.save(deps.storage, &info.sender, &threshold)?,
+        None => SPEND_ALERT_THRESHOLDS.remove(deps.storage, &info.sender),
+    }
+    Ok(Response::new()
+        .add_attribute("method", "subscribe_spend_alerts")
+        .add_attribute("owner", info.sender)
+        .add_attribute("threshold", threshold.map_or("none".to_string(), |t| t.to_string())))
 }
 
-// Helper function to create the bridge withdrawal message
-fn create_bridge_withdrawal_msg(
-    creator: String,
-    user_address: String,
-    amount: String,
-    destination_address: String,
-) -> Result<CosmosMsg, ContractError> {
-    // Create the protobuf message
-    let msg = MsgRequestBridgeWithdrawal {
-        creator,
-        user_address,
-        amount,
-        destination_address,
-    };
-
-    // Encode the message as protobuf
-    let mut buf = Vec::new();
-    msg.encode(&mut buf)
-        .map_err(|e| ContractError::Std(StdError::generic_err(format!("Failed to encode withdrawal message: {}", e))))?;
+/// Records a delegated spend from `owner` if it exceeds the owner's alert threshold
+fn record_spend_alert(
+    storage: &mut dyn Storage,
+    env: &Env,
+    owner: &Addr,
+    spender: &Addr,
+    amount: Uint128,
+    action: &str,
+) -> StdResult<()> {
+    match SPEND_ALERT_THRESHOLDS.may_load(storage, owner)? {
+        Some(threshold) if amount > threshold => {}
+        _ => return Ok(()),
+    }
+    let id = NEXT_SPEND_ALERT.may_load(storage, owner)?.unwrap_or_default();
+    NEXT_SPEND_ALERT.save(storage, owner, &(id + 1))?;
+    SPEND_ALERTS.save(
+        storage,
+        (owner, id),
+        &SpendAlert {
+            spender: spender.clone(),
+            amount,
+            action: action.to_string(),
+            height: env.block.height,
+        },
+    )
+}
 
-    let stargate_msg = CosmosMsg::Any(cosmwasm_std::AnyMsg {
-        type_url: "/inference.inference.MsgRequestBridgeWithdrawal".to_string(),
-        value: Binary::from(buf),
-    });
+/// Returns the account whose balance the message would debit and the amount, if any.
+fn debited_account(info: &MessageInfo, msg: &ExecuteMsg) -> Option<(String, Uint128)> {
+    match msg {
+        ExecuteMsg::Transfer { amount, .. }
+        | ExecuteMsg::Burn { amount }
+        | ExecuteMsg::Send { amount, .. } => Some((info.sender.to_string(), *amount)),
+        // The priority fee may not be charged, but limits hold for the most it can debit
+        ExecuteMsg::Withdraw { amount, priority_fee, .. } => {
+            Some((info.sender.to_string(), amount.saturating_add(priority_fee.unwrap_or_default())))
+        }
+        ExecuteMsg::TransferFrom { owner, amount, .. }
+        | ExecuteMsg::SendFrom { owner, amount, .. }
+        | ExecuteMsg::PullAndNotify { owner, amount, .. }
+        | ExecuteMsg::BurnFrom { owner, amount }
+        | ExecuteMsg::SessionTransfer { owner, amount, .. }
+        | ExecuteMsg::TransferBySig { owner, amount, .. } => Some((owner.clone(), *amount)),
+        ExecuteMsg::TransferFromBatch { owner, recipients } => {
+            let total = recipients.iter().fold(Uint128::zero(), |total, coin| total.saturating_add(coin.amount));
+            Some((owner.clone(), total))
+        }
+        _ => None,
+    }
+}
 
-    Ok(stargate_msg)
+fn is_exempt_withdrawal(deps: Deps, sender: &Addr, msg: &ExecuteMsg) -> bool {
+    matches!(msg, ExecuteMsg::Withdraw { .. }) && EXEMPT_RELAYERS.has(deps.storage, sender)
 }
 
-#[entry_point]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+/// Recipients of a plain transfer and their amounts, checked against the contract's own
+/// address. A batch paying the contract anywhere is rejected as a whole.
+fn transfer_recipients(msg: &ExecuteMsg) -> Vec<(&str, Uint128)> {
     match msg {
-        QueryMsg::BridgeInfo {} => to_json_binary(&query_bridge_info(deps)?),
-        QueryMsg::Balance { address } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::Balance { address }),
-        QueryMsg::TokenInfo {} => {
-            let base_bin = cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::TokenInfo {})?;
-            let mut base: cw20::TokenInfoResponse = cosmwasm_std::from_json(base_bin.clone())?;
-            if let Some(override_md) = TOKEN_METADATA.may_load(deps.storage)? {
-                base.name = override_md.name;
-                base.symbol = override_md.symbol;
-                base.decimals = override_md.decimals;
-            }
-            let resp = crate::msg::TokenInfoResponse {
-                name: base.name,
-                symbol: base.symbol,
-                decimals: base.decimals,
-                total_supply: base.total_supply,
-            };
-            to_json_binary(&resp)
-        },
-        QueryMsg::Allowance { owner, spender } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::Allowance { owner, spender }),
-        QueryMsg::AllAllowances { owner, start_after, limit } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::AllAllowances { owner, start_after, limit }),
-        QueryMsg::AllAccounts { start_after, limit } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::AllAccounts { start_after, limit }),
-        QueryMsg::MarketingInfo {} => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::MarketingInfo {}),
-        QueryMsg::DownloadLogo {} => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::DownloadLogo {}),
-        QueryMsg::Minter {} => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::Minter {}),
-        QueryMsg::TestApprovedTokens {} => to_json_binary(&query_test_approved_tokens(deps)?),
+        ExecuteMsg::Transfer { recipient, amount }
+        | ExecuteMsg::TransferFrom { recipient, amount, .. }
+        | ExecuteMsg::SessionTransfer { recipient, amount, .. }
+        | ExecuteMsg::TransferBySig { recipient, amount, .. } => vec![(recipient, *amount)],
+        ExecuteMsg::TransferFromBatch { recipients, .. } => {
+            recipients.iter().map(|coin| (coin.address.as_str(), coin.amount)).collect()
+        }
+        _ => vec![],
     }
 }
 
-#[entry_point]
-pub fn migrate(
-    deps: DepsMut,
-    _env: Env,
-    _msg: Binary,
+/// Sends what a transfer just paid the contract's own address back to the account it came from
+fn return_self_transfer(
+    storage: &mut dyn Storage,
+    env: &Env,
+    resp: Response,
+    account: String,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let old = get_contract_version(deps.storage)
-        .map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
-    
-    // Allow migration from both cw20-base (legacy) and wrapped-token contracts
-    if old.contract != CONTRACT_NAME && old.contract != "crates.io:cw20-base" {
-        return Err(ContractError::Std(StdError::generic_err(format!(
-            "wrong contract: expected {} or crates.io:cw20-base, got {}",
-            CONTRACT_NAME, old.contract
-        ))));
-    }
-
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
-        .map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
-
-    Ok(Response::new()
-        .add_attribute("action", "migrate")
-        .add_attribute("from_contract", old.contract)
-        .add_attribute("from_version", old.version)
-        .add_attribute("to_version", CONTRACT_VERSION))
+    let account = Addr::unchecked(account);
+    cw20_base_state::BALANCES.update(storage, &env.contract.address, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    cw20_base_state::BALANCES.update(storage, &account, |balance| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_add(amount)?)
+    })?;
+    Ok(resp.add_event(
+        Event::new("self_transfer_returned")
+            .add_attribute("account", account)
+            .add_attribute("amount", amount),
+    ))
 }
 
-// Generic helpers for gRPC queries using raw_query serialization pattern
-fn query_grpc(deps: Deps, path: &str, data: Binary) -> StdResult<Binary> {
-    let request = QueryRequest::Grpc(GrpcQuery {
-        path: path.to_string(),
-        data,
-    });
-    query_raw(deps, &request)
+/// Accounts whose balance the message may change
+fn balance_accounts(env: &Env, info: &MessageInfo, msg: &ExecuteMsg) -> Vec<String> {
+    let mut accounts: Vec<String> = debited_account(info, msg).map(|(account, _)| account).into_iter().collect();
+    match msg {
+        ExecuteMsg::Transfer { recipient, .. }
+        | ExecuteMsg::Mint { recipient, .. }
+        | ExecuteMsg::TransferFrom { recipient, .. }
+        | ExecuteMsg::SessionTransfer { recipient, .. }
+        | ExecuteMsg::TransferBySig { recipient, .. } => accounts.push(recipient.clone()),
+        ExecuteMsg::Send { contract, .. }
+        | ExecuteMsg::SendFrom { contract, .. }
+        | ExecuteMsg::PullAndNotify { contract, .. } => accounts.push(contract.clone()),
+        ExecuteMsg::MintBatch { mints } => accounts.extend(mints.iter().map(|(recipient, _, _)| recipient.clone())),
+        ExecuteMsg::TransferFromBatch { recipients, .. } => accounts.extend(recipients.iter().map(|coin| coin.address.clone())),
+        ExecuteMsg::ImportBalances { balances } => accounts.extend(balances.iter().map(|coin| coin.address.clone())),
+        ExecuteMsg::RecoverStuckBalance { recipient, .. } => {
+            accounts.push(env.contract.address.to_string());
+            accounts.push(recipient.clone());
+        }
+        _ => {}
+    }
+    accounts
 }
 
-fn query_raw(deps: Deps, request: &QueryRequest<GrpcQuery>) -> StdResult<Binary> {
-    let raw = to_json_vec(request)
-        .map_err(|e| StdError::generic_err(format!("Serializing QueryRequest: {e}")))?;
-    match deps.querier.raw_query(&raw) {
-        SystemResult::Err(system_err) => Err(StdError::generic_err(format!(
-            "Querier system error: {system_err}"
-        ))),
-        SystemResult::Ok(ContractResult::Err(contract_err)) => Err(StdError::generic_err(
-            format!("Querier contract error: {contract_err}")
-        )),
-        SystemResult::Ok(ContractResult::Ok(value)) => Ok(value),
+/// Saves each affected account's balance as its opening balance for this block, once per block
+fn checkpoint_balances(deps: DepsMut, env: &Env, info: &MessageInfo, msg: &ExecuteMsg) -> StdResult<()> {
+    let mut accounts = balance_accounts(env, info, msg);
+    // The fee collector comes from storage rather than the message
+    if let ExecuteMsg::Withdraw { priority_fee: Some(_), .. } = msg {
+        if let Some(fees) = PRIORITY_FEES.may_load(deps.storage)? {
+            accounts.push(fees.fee_collector.to_string());
+        }
     }
+    checkpoint_accounts(deps, env, accounts)
 }
 
-fn query_bridge_info(deps: Deps) -> StdResult<BridgeInfoResponse> {
-    let info = BRIDGE_INFO.load(deps.storage)?;
-    Ok(BridgeInfoResponse {
-        chain_id: info.chain_id,
-        contract_address: info.contract_address,
-    })
+fn checkpoint_accounts(deps: DepsMut, env: &Env, accounts: Vec<String>) -> StdResult<()> {
+    for account in accounts {
+        // Invalid addresses fail in the handler itself
+        let Ok(addr) = deps.api.addr_validate(&account) else {
+            continue;
+        };
+        if BALANCE_CHECKPOINTS.has(deps.storage, (&addr, env.block.height)) {
+            continue;
+        }
+        let balance = cw20_base_state::BALANCES.may_load(deps.storage, &addr)?.unwrap_or_default();
+        BALANCE_CHECKPOINTS.save(deps.storage, (&addr, env.block.height), &balance)?;
+        CHECKPOINT_HEIGHTS.save(deps.storage, (env.block.height, &addr), &())?;
+    }
+    prune_checkpoints(deps.storage, env)
 }
 
-fn query_test_approved_tokens(deps: Deps) -> StdResult<ApprovedTokensForTradeJson> {
-    let decoded: QueryApprovedTokensForTradeResponseProto = query_proto(
-        deps,
-        "/inference.inference.Query/ApprovedTokensForTrade",
-        &EmptyRequest {},
-    )?;
-    let approved_tokens = decoded
-        .approved_tokens
-        .into_iter()
-        .map(|t| ApprovedTokenJson { chain_id: t.chain_id, contract_address: t.contract_address })
-        .collect();
-    Ok(ApprovedTokensForTradeJson { approved_tokens })
+/// Removes the oldest checkpoints that fell out of the retention window. Queries at heights
+/// still inside the window only read checkpoints at or after them, so pruning cannot change
+/// their answers.
+fn prune_checkpoints(storage: &mut dyn Storage, env: &Env) -> StdResult<()> {
+    let cutoff = env.block.height.saturating_sub(SNAPSHOT_RETENTION_BLOCKS);
+    let expired = CHECKPOINT_HEIGHTS
+        .prefix_range(storage, None, Some(PrefixBound::exclusive(cutoff)), Order::Ascending)
+        .take(MAX_CHECKPOINT_PRUNE)
+        .map(|item| item.map(|(key, _)| key))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (height, addr) in expired {
+        CHECKPOINT_HEIGHTS.remove(storage, (height, &addr));
+        BALANCE_CHECKPOINTS.remove(storage, (&addr, height));
+    }
+    Ok(())
 }
 
-// Proto message types for ApprovedTokensForTrade response
-#[derive(Clone, PartialEq, ProstMessage)]
-pub struct BridgeTradeApprovedToken {
-    #[prost(string, tag = "1")]
-    pub chain_id: String,
-    #[prost(string, tag = "2")]
-    pub contract_address: String,
+fn grant_session(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    session_key: String,
+    max_amount: Uint128,
+    expires_at_height: u64,
+) -> Result<Response, ContractError> {
+    let key_addr = deps.api.addr_validate(&session_key)?;
+    if key_addr == info.sender {
+        return Err(ContractError::Std(StdError::generic_err("cannot grant a session to own account")));
+    }
+    if expires_at_height <= env.block.height {
+        return Err(ContractError::Expired {});
+    }
+    SESSIONS.save(
+        deps.storage,
+        (&info.sender, &key_addr),
+        &Session { remaining: max_amount, expires_at_height },
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "grant_session")
+        .add_attribute("owner", info.sender)
+        .add_attribute("session_key", key_addr)
+        .add_attribute("max_amount", max_amount)
+        .add_attribute("expires_at_height", expires_at_height.to_string()))
 }
 
-#[derive(Clone, PartialEq, ProstMessage)]
-pub struct QueryApprovedTokensForTradeResponseProto {
-    #[prost(message, repeated, tag = "1")]
-    pub approved_tokens: ::prost::alloc::vec::Vec<BridgeTradeApprovedToken>,
+fn revoke_session(deps: DepsMut, info: MessageInfo, session_key: String) -> Result<Response, ContractError> {
+    let key_addr = deps.api.addr_validate(&session_key)?;
+    if !SESSIONS.has(deps.storage, (&info.sender, &key_addr)) {
+        return Err(ContractError::SessionNotFound {});
+    }
+    SESSIONS.remove(deps.storage, (&info.sender, &key_addr));
+    Ok(Response::new()
+        .add_attribute("method", "revoke_session")
+        .add_attribute("owner", info.sender)
+        .add_attribute("session_key", key_addr))
 }
 
-#[derive(Clone, PartialEq, ProstMessage)]
-pub struct EmptyRequest {}
-
-// Generic helper: encode request proto and decode response proto
-fn query_proto<TRequest, TResponse>(deps: Deps, path: &str, request: &TRequest) -> StdResult<TResponse>
-where
-    TRequest: ProstMessage,
-    TResponse: ProstMessage + Default,
-{
+/// Moves tokens from owner to recipient on behalf of a session key, consuming its budget.
+/// Expired sessions are removed when touched.
+fn session_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let mut session = SESSIONS
+        .may_load(deps.storage, (&owner_addr, &info.sender))?
+        .ok_or(ContractError::SessionNotFound {})?;
+    if env.block.height >= session.expires_at_height {
+        return Err(ContractError::SessionExpired { expires_at_height: session.expires_at_height });
+    }
+    if amount > session.remaining {
+        return Err(ContractError::SessionLimitExceeded {
+            remaining: session.remaining.u128(),
+            requested: amount.u128(),
+        });
+    }
+    session.remaining -= amount;
+    SESSIONS.save(deps.storage, (&owner_addr, &info.sender), &session)?;
+    record_spend_alert(deps.storage, &env, &owner_addr, &info.sender, amount, "session_transfer")?;
+
+    // Execute the transfer as the owner so cw20-base balance rules apply unchanged
+    let owner_info = MessageInfo { sender: owner_addr, funds: vec![] };
+    let resp = cw20_base_contract::execute_transfer(deps, env, owner_info, recipient, amount)
+        .map_err(ContractError::from)?;
+    Ok(resp
+        .add_attribute("session_key", info.sender)
+        .add_attribute("session_remaining", session.remaining))
+}
+
+/// Moves tokens for an owner without native gas: a relayer submits the owner's signature and
+/// pays the fee. Nonces are sequential per owner, so each signature executes at most once.
+fn transfer_by_sig(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    doc: TransferSignDoc,
+    pubkey: Binary,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidSignature { reason: reason.to_string() };
+    if env.block.height > doc.deadline {
+        return Err(ContractError::SignatureExpired { deadline: doc.deadline });
+    }
+    let owner_addr = deps.api.addr_validate(&doc.owner)?;
+    let next_nonce = TRANSFER_NONCES.may_load(deps.storage, &owner_addr)?.unwrap_or_default();
+    if doc.nonce != next_nonce {
+        return Err(invalid(&format!("expected nonce {}", next_nonce)));
+    }
+    // Account addresses are ripemd160(sha256(pubkey)), tying the key to the owner
+    let key_hash = Ripemd160::digest(Sha256::digest(pubkey.as_slice()));
+    if deps.api.addr_canonicalize(owner_addr.as_str())?.as_slice() != key_hash.as_slice() {
+        return Err(invalid("pubkey does not belong to owner"));
+    }
+    let hash = Sha256::digest(to_json_vec(&doc)?);
+    let valid = deps
+        .api
+        .secp256k1_verify(&hash, signature.as_slice(), pubkey.as_slice())
+        .map_err(|e| invalid(&e.to_string()))?;
+    if !valid {
+        return Err(invalid("signature does not verify"));
+    }
+    TRANSFER_NONCES.save(deps.storage, &owner_addr, &(next_nonce + 1))?;
+
+    // Execute the transfer as the owner so cw20-base balance rules apply unchanged
+    let owner_info = MessageInfo { sender: owner_addr, funds: vec![] };
+    let resp = cw20_base_contract::execute_transfer(deps, env, owner_info, doc.recipient, doc.amount)
+        .map_err(ContractError::from)?;
+    Ok(resp
+        .add_attribute("relayer", info.sender)
+        .add_attribute("nonce", doc.nonce.to_string()))
+}
+
+fn current_day(env: &Env) -> u64 {
+    env.block.time.seconds() / 86_400
+}
+
+/// Enforces the account's transfer guard (if any) and records the outflow against today's total.
+fn apply_transfer_guard(deps: DepsMut, env: &Env, account: &str, amount: Uint128) -> Result<(), ContractError> {
+    let addr = deps.api.addr_validate(account)?;
+    if let Some(outflow) = check_transfer_guard(deps.as_ref(), env, &addr, amount)? {
+        DAILY_OUTFLOW.save(deps.storage, &addr, &outflow)?;
+    }
+    Ok(())
+}
+
+/// Checks `amount` against the account's transfer guard, returning the daily outflow to record
+/// when a daily limit applies
+fn check_transfer_guard(deps: Deps, env: &Env, addr: &Addr, amount: Uint128) -> Result<Option<DailyOutflow>, ContractError> {
+    let Some(guard) = TRANSFER_GUARDS.may_load(deps.storage, addr)? else {
+        return Ok(None);
+    };
+    if let Some(max_per_tx) = guard.max_per_tx {
+        if amount > max_per_tx {
+            return Err(ContractError::TransferGuardExceeded {
+                limit: max_per_tx.u128(),
+                requested: amount.u128(),
+            });
+        }
+    }
+    let Some(daily_max) = guard.daily_max else {
+        return Ok(None);
+    };
+    let day = current_day(env);
+    let mut outflow = DAILY_OUTFLOW.may_load(deps.storage, addr)?.unwrap_or_default();
+    if outflow.day != day {
+        outflow = DailyOutflow { day, amount: Uint128::zero() };
+    }
+    let total = outflow.amount.checked_add(amount).map_err(StdError::from)?;
+    if total > daily_max {
+        return Err(ContractError::TransferGuardExceeded {
+            limit: daily_max.u128(),
+            requested: total.u128(),
+        });
+    }
+    outflow.amount = total;
+    Ok(Some(outflow))
+}
+
+fn set_transfer_guard(
+    deps: DepsMut,
+    info: MessageInfo,
+    max_per_tx: Option<Uint128>,
+    daily_max: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let was_guarded = TRANSFER_GUARDS.has(deps.storage, &info.sender);
+    let guarded = max_per_tx.is_some() || daily_max.is_some();
+    if guarded {
+        TRANSFER_GUARDS.save(deps.storage, &info.sender, &TransferGuard { max_per_tx, daily_max })?;
+    } else {
+        TRANSFER_GUARDS.remove(deps.storage, &info.sender);
+        DAILY_OUTFLOW.remove(deps.storage, &info.sender);
+    }
+    update_count(deps.storage, &GUARDED_ACCOUNT_COUNT, was_guarded, guarded)?;
+    Ok(Response::new()
+        .add_attribute("method", "set_transfer_guard")
+        .add_attribute("account", info.sender)
+        .add_attribute("max_per_tx", max_per_tx.map_or("none".to_string(), |m| m.to_string()))
+        .add_attribute("daily_max", daily_max.map_or("none".to_string(), |m| m.to_string())))
+}
+
+fn set_lockup_registry(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: Option<String>,
+    cache_blocks: u64,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let old = LOCKUP_REGISTRY.may_load(deps.storage)?;
+    let new = match contract {
+        Some(contract) => {
+            let config = LockupRegistryConfig { contract: deps.api.addr_validate(&contract)?, cache_blocks };
+            LOCKUP_REGISTRY.save(deps.storage, &config)?;
+            Some(config)
+        }
+        None => {
+            LOCKUP_REGISTRY.remove(deps.storage);
+            None
+        }
+    };
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_lockup_registry",
+        old.map(|c| to_json_string(&c)).transpose()?,
+        new.as_ref().map(to_json_string).transpose()?,
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_lockup_registry")
+        .add_attribute("contract", new.map_or("none".to_string(), |c| c.contract.to_string()))
+        .add_attribute("cache_blocks", cache_blocks.to_string()))
+}
+
+fn set_sanctions_sync(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cache_blocks: Option<u64>,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let old = SANCTIONS_SYNC.may_load(deps.storage)?;
+    match cache_blocks {
+        Some(cache_blocks) => SANCTIONS_SYNC.save(deps.storage, &SanctionsSync { cache_blocks })?,
+        None => SANCTIONS_SYNC.remove(deps.storage),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_sanctions_sync",
+        old.map(|s| s.cache_blocks.to_string()),
+        cache_blocks.map(|c| c.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_sanctions_sync")
+        .add_attribute("cache_blocks", cache_blocks.map_or("none".to_string(), |c| c.to_string())))
+}
+
+/// Rejects a withdrawal destination on the chain's sanctions list, caching a fresh answer
+fn ensure_not_sanctioned(deps: DepsMut, env: &Env, destination: &ExternalAddress) -> Result<(), ContractError> {
+    let (sanctioned, fresh) = check_sanctioned(deps.as_ref(), env, destination)?;
+    if let Some(fresh) = fresh {
+        SANCTIONS_CACHE.save(deps.storage, destination.as_str(), &fresh)?;
+    }
+    if sanctioned {
+        return Err(ContractError::SanctionedDestination { address: destination.to_string() });
+    }
+    Ok(())
+}
+
+/// Whether the chain's sanctions list names `destination`, when sanctions sync is on, along
+/// with a freshly queried answer to cache. A cached answer may lag the list by up to
+/// `cache_blocks` blocks. A failed lookup fails the check rather than letting funds through.
+fn check_sanctioned(deps: Deps, env: &Env, destination: &ExternalAddress) -> Result<(bool, Option<CachedSanction>), ContractError> {
+    let Some(sync) = SANCTIONS_SYNC.may_load(deps.storage)? else {
+        return Ok((false, None));
+    };
+    let cached = SANCTIONS_CACHE
+        .may_load(deps.storage, destination.as_str())?
+        .filter(|c| env.block.height < c.queried_at_height + sync.cache_blocks);
+    if let Some(cached) = cached {
+        return Ok((cached.sanctioned, None));
+    }
+    let request = QuerySanctionedAddressRequest {
+        chain_id: BRIDGE_INFO.load(deps.storage)?.chain_id,
+        address: destination.to_string(),
+    };
+    let resp: QuerySanctionedAddressResponse = query_proto(deps, proto::SANCTIONED_ADDRESS_PATH, &request)?;
+    let fresh = (sync.cache_blocks > 0).then_some(CachedSanction {
+        sanctioned: resp.sanctioned,
+        queried_at_height: env.block.height,
+    });
+    Ok((resp.sanctioned, fresh))
+}
+
+fn set_relayer_bond(deps: DepsMut, env: Env, actor: Addr, contract: Option<String>) -> Result<Response, ContractError> {
+    let old = RELAYER_BOND.may_load(deps.storage)?;
+    let new = contract.map(|contract| deps.api.addr_validate(&contract)).transpose()?;
+    match &new {
+        Some(contract) => RELAYER_BOND.save(deps.storage, contract)?,
+        None => RELAYER_BOND.remove(deps.storage),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &actor,
+        "set_relayer_bond",
+        old.map(|c| c.to_string()),
+        new.as_ref().map(|c| c.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_relayer_bond")
+        .add_attribute("contract", new.map_or("none".to_string(), |c| c.to_string())))
+}
+
+/// Mints are only honored from relayers with a bond at stake, when a bond contract is set
+fn ensure_bonded_relayer(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let Some(bond_contract) = RELAYER_BOND.may_load(deps.storage)? else {
+        return Ok(());
+    };
+    let resp: IsBondedResponse = deps.querier.query_wasm_smart(
+        bond_contract,
+        &RelayerBondQueryMsg::IsBonded { relayer: sender.to_string() },
+    )?;
+    if !resp.bonded {
+        return Err(ContractError::RelayerNotBonded { relayer: sender.to_string() });
+    }
+    Ok(())
+}
+
+/// Rejects movements that would dip into the amount the lockup registry reports as locked.
+/// A cached amount may lag the registry by up to `cache_blocks` blocks.
+fn enforce_lockup(deps: DepsMut, env: &Env, account: &str, amount: Uint128) -> Result<(), ContractError> {
+    let addr = deps.api.addr_validate(account)?;
+    if let Some(fresh) = check_lockup(deps.as_ref(), env, &addr, amount)? {
+        LOCKUP_CACHE.save(deps.storage, &addr, &fresh)?;
+    }
+    Ok(())
+}
+
+/// Checks `amount` against the account's unlocked balance, returning a freshly queried lockup
+/// to cache when caching is on
+fn check_lockup(deps: Deps, env: &Env, addr: &Addr, amount: Uint128) -> Result<Option<CachedLockup>, ContractError> {
+    let Some(registry) = LOCKUP_REGISTRY.may_load(deps.storage)? else {
+        return Ok(None);
+    };
+    let cached = LOCKUP_CACHE
+        .may_load(deps.storage, addr)?
+        .filter(|c| c.registry == registry.contract && env.block.height < c.queried_at_height + registry.cache_blocks);
+    let (locked, fresh) = match cached {
+        Some(cached) => (cached.locked, None),
+        None => {
+            let resp: LockedResponse = deps.querier.query_wasm_smart(
+                registry.contract.to_string(),
+                &LockupRegistryQueryMsg::Locked {
+                    token: env.contract.address.to_string(),
+                    address: addr.to_string(),
+                    at_height: None,
+                },
+            )?;
+            let fresh = (registry.cache_blocks > 0).then(|| CachedLockup {
+                registry: registry.contract.clone(),
+                locked: resp.locked,
+                queried_at_height: env.block.height,
+            });
+            (resp.locked, fresh)
+        }
+    };
+    if locked.is_zero() {
+        return Ok(fresh);
+    }
+    let balance = cw20_base_state::BALANCES.may_load(deps.storage, addr)?.unwrap_or_default();
+    let available = balance.saturating_sub(locked);
+    if amount > available {
+        return Err(ContractError::BalanceLocked {
+            locked: locked.u128(),
+            available: available.u128(),
+            requested: amount.u128(),
+        });
+    }
+    Ok(fresh)
+}
+
+fn ensure_not_frozen(deps: Deps, account: &str) -> Result<(), ContractError> {
+    let addr = deps.api.addr_validate(account)?;
+    if FROZEN_ACCOUNTS.has(deps.storage, &addr) {
+        return Err(ContractError::AccountFrozen { address: addr.to_string() });
+    }
+    Ok(())
+}
+
+/// Creator (inference module) and admin (governance module) share operational privileges.
+fn ensure_creator_or_admin(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    let creator = CREATOR.load(deps.storage)?;
+    let admin = ADMIN.load(deps.storage)?;
+    roles::ensure_one_of(sender.as_str(), &[creator.as_str(), admin.as_str()])?;
+    Ok(())
+}
+
+/// cw20-base mint, held to the mint circuit breaker
+fn mint(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    ensure_flow_open(deps.storage, FLOW_MINT)?;
+    ensure_bonded_relayer(deps.as_ref(), &info.sender)?;
+    let resp = cw20_base_contract::execute(
+        deps.branch(),
+        env.clone(),
+        info,
+        cw20_base_msg::ExecuteMsg::Mint { recipient, amount },
+    )
+    .map_err(ContractError::from)?;
+    Ok(resp.add_events(record_flow_volume(deps.storage, &env, FLOW_MINT, amount)?))
+}
+
+/// Mints each deposit independently: duplicates and failures are skipped and reported in a
+/// `mint_batch_item` event so one bad item does not revert the whole relayer batch.
+/// The creator or sudo caller is authorized before this runs; items mint as the cw20 minter, so
+/// the minter cap still applies.
+fn mint_batch(mut deps: DepsMut, env: Env, mints: Vec<(String, Uint128, String)>) -> Result<Response, ContractError> {
+    let minter = cw20_base_state::TOKEN_INFO
+        .load(deps.storage)?
+        .mint
+        .map(|m| m.minter)
+        .ok_or(ContractError::OnlyModuleCanMint {})?;
+    let minter_info = MessageInfo { sender: minter, funds: vec![] };
+    if mints.len() > MAX_MINT_BATCH {
+        return Err(ContractError::BatchTooLarge { size: mints.len(), max: MAX_MINT_BATCH });
+    }
+
+    let ack = DEPOSIT_ACKS_ENABLED.exists(deps.storage);
+    let mut resp = Response::new().add_attribute("method", "mint_batch");
+    let mut minted: u64 = 0;
+    let mut skipped: u64 = 0;
+    for (recipient, amount, deposit_id) in mints {
+        let event = Event::new("mint_batch_item")
+            .add_attribute("deposit_id", deposit_id.clone())
+            .add_attribute("recipient", recipient.clone())
+            .add_attribute("amount", amount);
+
+        if PROCESSED_DEPOSITS.has(deps.storage, &deposit_id) {
+            skipped += 1;
+            resp = resp.add_event(event.add_attribute("status", "duplicate"));
+            continue;
+        }
+        // Halted items stay unprocessed so the module can resubmit them after a resume
+        if HALTED_FLOWS.has(deps.storage, FLOW_MINT) {
+            skipped += 1;
+            resp = resp.add_event(event.add_attribute("status", "halted"));
+            continue;
+        }
+        // cw20-base saves the new supply before validating the recipient, so validate first to
+        // make sure a failed item leaves no partial state
+        let result = deps
+            .api
+            .addr_validate(&recipient)
+            .map_err(ContractError::Std)
+            .and_then(|_| {
+                cw20_base_contract::execute_mint(deps.branch(), env.clone(), minter_info.clone(), recipient, amount)
+                    .map_err(ContractError::from)
+            });
+        match result {
+            Ok(_) => {
+                PROCESSED_DEPOSITS.save(deps.storage, &deposit_id, &env.block.height)?;
+                minted += 1;
+                resp = resp.add_event(event.add_attribute("status", "minted"));
+                resp = resp.add_events(record_flow_volume(deps.storage, &env, FLOW_MINT, amount)?);
+                if ack {
+                    resp = resp.add_message(create_ack_deposit_msg(env.contract.address.to_string(), deposit_id, amount)?);
+                }
+            }
+            Err(e) => {
+                skipped += 1;
+                resp = resp.add_event(
+                    event
+                        .add_attribute("status", "failed")
+                        .add_attribute("error", e.to_string()),
+                );
+            }
+        }
+    }
+
+    Ok(resp
+        .add_attribute("minted", minted.to_string())
+        .add_attribute("skipped", skipped.to_string()))
+}
+
+fn set_deposit_acks(deps: DepsMut, env: Env, info: MessageInfo, enabled: bool) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let was_enabled = DEPOSIT_ACKS_ENABLED.exists(deps.storage);
+    if enabled {
+        DEPOSIT_ACKS_ENABLED.save(deps.storage, &())?;
+    } else {
+        DEPOSIT_ACKS_ENABLED.remove(deps.storage);
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_deposit_acks",
+        Some(was_enabled.to_string()),
+        Some(enabled.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_deposit_acks")
+        .add_attribute("enabled", enabled.to_string()))
+}
+
+// Acknowledges a minted deposit to the inference module, signed by this contract
+fn create_ack_deposit_msg(creator: String, deposit_id: String, amount: Uint128) -> Result<CosmosMsg, ContractError> {
     let mut buf = Vec::new();
-    request
-        .encode(&mut buf)
-        .map_err(|e| StdError::generic_err(format!("Encode request: {}", e)))?;
-    let bytes = query_grpc(deps, path, Binary::from(buf))?;
-    TResponse::decode(bytes.as_slice())
-        .map_err(|e| StdError::generic_err(format!("Decode response: {}", e)))
-}
\ No newline at end of file
+    MsgAckBridgeDeposit {
+        creator,
+        deposit_id,
+        amount: amount.to_string(),
+    }
+    .encode(&mut buf)
+    .map_err(|e| ContractError::Std(StdError::generic_err(format!("Failed to encode deposit ack: {}", e))))?;
+
+    Ok(CosmosMsg::Any(cosmwasm_std::AnyMsg {
+        type_url: proto::MSG_ACK_BRIDGE_DEPOSIT_TYPE_URL.to_string(),
+        value: Binary::from(buf),
+    }))
+}
+
+/// Execute rather than query so only the creator drives a redeployment export
+fn export_balances(
+    deps: DepsMut,
+    info: MessageInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    if info.sender != CREATOR.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    let limit = limit.unwrap_or(MAX_BALANCE_CHUNK).min(MAX_BALANCE_CHUNK) as usize;
+    let start_addr = start_after.map(|s| deps.api.addr_validate(&s)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+    // Fetch one extra entry to tell whether another chunk follows
+    let mut balances = cw20_base_state::BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .map(|item| item.map(|(address, amount)| Cw20Coin { address: address.to_string(), amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if balances.len() > limit {
+        balances.truncate(limit);
+        balances.last().map(|c| c.address.clone())
+    } else {
+        None
+    };
+    let export = ExportBalancesResponse {
+        balances,
+        next_start_after,
+        total_supply: cw20_base_state::TOKEN_INFO.load(deps.storage)?.total_supply,
+    };
+    Ok(Response::new()
+        .set_data(to_json_binary(&export)?)
+        .add_attribute("method", "export_balances")
+        .add_attribute("count", export.balances.len().to_string()))
+}
+
+fn import_balances(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    balances: Vec<Cw20Coin>,
+) -> Result<Response, ContractError> {
+    if info.sender != CREATOR.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !BALANCE_IMPORT_OPEN.exists(deps.storage) {
+        return Err(ContractError::ImportClosed {});
+    }
+    if balances.len() > MAX_BALANCE_CHUNK as usize {
+        return Err(ContractError::BatchTooLarge { size: balances.len(), max: MAX_BALANCE_CHUNK as usize });
+    }
+
+    let mut token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+    let mut imported = Uint128::zero();
+    for coin in &balances {
+        let addr = deps.api.addr_validate(&coin.address)?;
+        // Chunks are disjoint, so an existing balance means a chunk was replayed
+        if cw20_base_state::BALANCES.has(deps.storage, &addr) {
+            return Err(ContractError::DuplicateInitialBalanceAddresses {});
+        }
+        cw20_base_state::BALANCES.save(deps.storage, &addr, &coin.amount)?;
+        imported = imported.checked_add(coin.amount).map_err(StdError::from)?;
+    }
+    token_info.total_supply = token_info.total_supply.checked_add(imported).map_err(StdError::from)?;
+    if let Some(cap) = token_info.get_cap() {
+        if token_info.total_supply > cap {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    cw20_base_state::TOKEN_INFO.save(deps.storage, &token_info)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "import_balances",
+        None,
+        Some(format!("{} accounts, {}", balances.len(), imported)),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "import_balances")
+        .add_attribute("accounts", balances.len().to_string())
+        .add_attribute("amount", imported)
+        .add_attribute("total_supply", token_info.total_supply))
+}
+
+fn freeze_account(deps: DepsMut, env: Env, info: MessageInfo, address: String) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let frozen = FROZEN_ACCOUNTS.has(deps.storage, &addr);
+    update_count(deps.storage, &FROZEN_ACCOUNT_COUNT, frozen, true)?;
+    FROZEN_ACCOUNTS.save(deps.storage, &addr, &true)?;
+    record_audit(deps.storage, &env, &info.sender, "freeze_account", None, Some(addr.to_string()))?;
+    Ok(Response::new()
+        .add_attribute("method", "freeze_account")
+        .add_attribute("address", addr))
+}
+
+fn unfreeze_account(deps: DepsMut, env: Env, info: MessageInfo, address: String) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let addr = deps.api.addr_validate(&address)?;
+    let frozen = FROZEN_ACCOUNTS.has(deps.storage, &addr);
+    update_count(deps.storage, &FROZEN_ACCOUNT_COUNT, frozen, false)?;
+    FROZEN_ACCOUNTS.remove(deps.storage, &addr);
+    record_audit(deps.storage, &env, &info.sender, "unfreeze_account", Some(addr.to_string()), None)?;
+    Ok(Response::new()
+        .add_attribute("method", "unfreeze_account")
+        .add_attribute("address", addr))
+}
+
+fn update_bridge_info(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    chain_id: String,
+    contract_address: String,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let contract_address = ExternalAddress::parse(&contract_address)?;
+    let old = BRIDGE_INFO.load(deps.storage)?;
+    save_bridge_info(deps.storage, &env, &BridgeInfo { chain_id: chain_id.clone(), contract_address: contract_address.clone() })?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "update_bridge_info",
+        Some(format!("{}:{}", old.chain_id, old.contract_address)),
+        Some(format!("{}:{}", chain_id, contract_address)),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_bridge_info")
+        .add_attribute("chain_id", chain_id)
+        .add_attribute("contract_address", contract_address.to_string()))
+}
+
+/// Moves a map's entry counter when a key is added (`was` false, `is` true) or removed
+fn update_count(storage: &mut dyn Storage, count: &Item<u64>, was: bool, is: bool) -> StdResult<()> {
+    if was == is {
+        return Ok(());
+    }
+    let current = count.may_load(storage)?.unwrap_or_default();
+    count.save(storage, &if is { current + 1 } else { current.saturating_sub(1) })
+}
+
+// Marketing and logo updates are authorized by cw20-base (marketing address) and recorded once they succeed
+fn update_marketing(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project: Option<String>,
+    description: Option<String>,
+    marketing: Option<String>,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.clone();
+    let old = marketing_info_json(deps.storage)?;
+    let resp = cw20_base_contract::execute(
+        deps.branch(),
+        env.clone(),
+        info,
+        cw20_base_msg::ExecuteMsg::UpdateMarketing { project, description, marketing },
+    )
+    .map_err(ContractError::from)?;
+    let new = marketing_info_json(deps.storage)?;
+    record_audit(deps.storage, &env, &sender, "update_marketing", old, new)?;
+    Ok(resp)
+}
+
+fn upload_logo(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    logo: crate::msg::Logo,
+) -> Result<Response, ContractError> {
+    let sender = info.sender.clone();
+    let old = marketing_info_json(deps.storage)?;
+    let resp = cw20_base_contract::execute(
+        deps.branch(),
+        env.clone(),
+        info,
+        cw20_base_msg::ExecuteMsg::UploadLogo(map_logo(logo)),
+    )
+    .map_err(ContractError::from)?;
+    // Embedded logos are only referenced through the marketing info, not copied into the log
+    let new = marketing_info_json(deps.storage)?;
+    record_audit(deps.storage, &env, &sender, "upload_logo", old, new)?;
+    Ok(resp)
+}
+
+fn marketing_info_json(storage: &dyn Storage) -> StdResult<Option<String>> {
+    cw20_base_state::MARKETING_INFO
+        .may_load(storage)?
+        .map(|info| to_json_string(&info))
+        .transpose()
+}
+
+fn map_logo(logo: crate::msg::Logo) -> CwLogo {
+    match logo {
+        crate::msg::Logo::Url(u) => CwLogo::Url(u),
+        crate::msg::Logo::Embedded(embed) => match embed {
+            crate::msg::EmbeddedLogo::Svg(b) => CwLogo::Embedded(CwEmbeddedLogo::Svg(b)),
+            crate::msg::EmbeddedLogo::Png(b) => CwLogo::Embedded(CwEmbeddedLogo::Png(b)),
+        },
+    }
+}
+
+fn map_expiration(exp: Option<crate::msg::Expiration>) -> Option<CwExpiration> {
+    exp.map(|e| match e {
+        crate::msg::Expiration::AtHeight(h) => CwExpiration::AtHeight(h),
+        crate::msg::Expiration::AtTime(t) => CwExpiration::AtTime(t),
+        crate::msg::Expiration::Never {} => CwExpiration::Never {},
+    })
+}
+
+/// Allows both creator (inference module) and admin (governance module) to update token metadata.
+fn update_metadata(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    symbol: String,
+    decimals: u8,
+) -> Result<Response, ContractError> {
+    // Load both creator and admin addresses
+    let creator = CREATOR.load(deps.storage)?;
+    let admin = ADMIN.load(deps.storage)?;
+    
+    // Allow both creator (inference module) and admin (governance module) to update metadata
+    let is_creator = info.sender == creator;
+    let is_admin = info.sender == admin;
+    
+    if !is_creator && !is_admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    validate_name(&name)?;
+    validate_symbol(&symbol)?;
+    validate_decimals(decimals)?;
+
+    let mut token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+    let old = match TOKEN_METADATA.may_load(deps.storage)? {
+        Some(meta) => to_json_string(&meta)?,
+        None => to_json_string(&TokenMetadataOverride {
+            name: token_info.name.clone(),
+            symbol: token_info.symbol.clone(),
+            decimals: token_info.decimals,
+        })?,
+    };
+    let new_meta = TokenMetadataOverride { name: name.clone(), symbol: symbol.clone(), decimals };
+    // Write through to cw20-base so every reader sees the same metadata; the override item
+    // only remains meaningful for instances that have not been updated since
+    token_info.name = name.clone();
+    token_info.symbol = symbol.clone();
+    token_info.decimals = decimals;
+    cw20_base_state::TOKEN_INFO.save(deps.storage, &token_info)?;
+    TOKEN_METADATA.remove(deps.storage);
+    record_audit(deps.storage, &env, &info.sender, "update_metadata", Some(old), Some(to_json_string(&new_meta)?))?;
+
+    Ok(Response::new()
+        .add_attribute("method", "update_metadata")
+        .add_attribute("name", name)
+        .add_attribute("symbol", symbol)
+        .add_attribute("decimals", decimals.to_string()))
+}
+
+/// Mirrors the external token's metadata from the inference module, so wrapped tokens need no
+/// manual UpdateMetadata. Unlike the tolerated lookups this fails if the chain does not answer.
+fn sync_external_metadata(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let bridge_info = BRIDGE_INFO.load(deps.storage)?;
+    let request = QueryExternalTokenMetadataRequest {
+        chain_id: bridge_info.chain_id.clone(),
+        contract_address: bridge_info.contract_address.to_string(),
+    };
+    let metadata: QueryExternalTokenMetadataResponse =
+        query_proto(deps.as_ref(), proto::EXTERNAL_TOKEN_METADATA_PATH, &request)?;
+    if !metadata.found {
+        return Err(ContractError::ExternalMetadataNotFound {
+            chain_id: bridge_info.chain_id,
+            contract_address: bridge_info.contract_address.into(),
+        });
+    }
+    validate_name(&metadata.name)?;
+    validate_symbol(&metadata.symbol)?;
+    let external_decimals = u8::try_from(metadata.decimals)
+        .map_err(|_| ContractError::InvalidTokenMetadata { reason: "decimals must be at most 18".to_string() })?;
+    validate_decimals(external_decimals)?;
+    if metadata.logo_uri.len() > MAX_LOGO_URI_LEN {
+        return Err(ContractError::InvalidTokenMetadata {
+            reason: format!("logo URI must be at most {MAX_LOGO_URI_LEN} bytes"),
+        });
+    }
+
+    let mut token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+    let old = to_json_string(&TokenMetadataOverride {
+        name: token_info.name.clone(),
+        symbol: token_info.symbol.clone(),
+        decimals: token_info.decimals,
+    })?;
+    token_info.name = metadata.name.clone();
+    token_info.symbol = metadata.symbol.clone();
+    // Existing balances are denominated in the local decimals, and withdrawals already scale
+    // between them and the external token's
+    if token_info.total_supply.is_zero() {
+        token_info.decimals = external_decimals;
+    }
+    cw20_base_state::TOKEN_INFO.save(deps.storage, &token_info)?;
+    TOKEN_METADATA.remove(deps.storage);
+    let new_meta = TokenMetadataOverride {
+        name: token_info.name.clone(),
+        symbol: token_info.symbol.clone(),
+        decimals: token_info.decimals,
+    };
+    record_audit(deps.storage, &env, &info.sender, "sync_external_metadata", Some(old), Some(to_json_string(&new_meta)?))?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "sync_external_metadata")
+        .add_attribute("name", token_info.name)
+        .add_attribute("symbol", token_info.symbol)
+        .add_attribute("decimals", token_info.decimals.to_string());
+    // An empty URI means the bridge found no logo; any logo set here stays
+    if !metadata.logo_uri.is_empty() {
+        let old_marketing = marketing_info_json(deps.storage)?;
+        let mut marketing_info = cw20_base_state::MARKETING_INFO.may_load(deps.storage)?.unwrap_or_default();
+        marketing_info.logo = Some(LogoInfo::Url(metadata.logo_uri.clone()));
+        cw20_base_state::MARKETING_INFO.save(deps.storage, &marketing_info)?;
+        cw20_base_state::LOGO.save(deps.storage, &CwLogo::Url(metadata.logo_uri.clone()))?;
+        let new_marketing = marketing_info_json(deps.storage)?;
+        record_audit(deps.storage, &env, &info.sender, "sync_external_logo", old_marketing, new_marketing)?;
+        response = response.add_attribute("logo_uri", metadata.logo_uri);
+    }
+    Ok(response)
+}
+
+// Special bridge withdraw function
+#[allow(clippy::too_many_arguments)]
+fn withdraw(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    destination_address: String,
+    travel_rule: Option<TravelRuleMemo>,
+    force: bool,
+    priority_fee: Option<Uint128>,
+    payload: Option<String>,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InsufficientFunds {
+            balance: 0,
+            required: 1,
+        });
+    }
+
+    ensure_flow_open(deps.storage, FLOW_WITHDRAW)?;
+
+    // Validate and normalize the destination so queued and bridged withdrawals agree on its form
+    let destination_address = ExternalAddress::parse(&destination_address)?;
+    let irreversible = irreversible_destination(deps.storage, &destination_address)?;
+    if let (Some(reason), false) = (&irreversible, force) {
+        return Err(ContractError::IrreversibleDestination {
+            address: destination_address.into(),
+            reason: reason.clone(),
+        });
+    }
+    // Unlike the blocked destinations, sanctioned ones are not a matter of lost funds, so
+    // `force` does not apply
+    ensure_not_sanctioned(deps.branch(), &env, &destination_address)?;
+    check_withdrawal_payload(deps.storage, &BRIDGE_INFO.load(deps.storage)?.chain_id, payload.as_deref())?;
+
+    if let Some(rule) = TRAVEL_RULE.may_load(deps.storage)? {
+        if amount >= rule.threshold && travel_rule.is_none() {
+            return Err(ContractError::TravelRuleMemoRequired { threshold: rule.threshold.u128() });
+        }
+    }
+    let travel_rule_memo = travel_rule.as_ref().map(encode_travel_rule_memo).transpose()?;
+    let memo = match (&travel_rule_memo, &payload) {
+        (Some(travel_rule), Some(payload)) => Some(to_json_string(&CombinedMemo { travel_rule, payload })?),
+        (travel_rule, payload) => travel_rule.clone().or_else(|| payload.clone()),
+    };
+    // Exempt relayers pay no withdrawal fees, are left out of the breaker's window volume and
+    // take the priority lane
+    let exempt = EXEMPT_RELAYERS.has(deps.storage, &info.sender);
+    let priority_fee = match priority_fee.filter(|fee| !fee.is_zero() && !exempt) {
+        Some(fee) => {
+            let fees = PRIORITY_FEES.may_load(deps.storage)?.ok_or(ContractError::PriorityFeesDisabled {})?;
+            Some((fee, fees.fee_collector))
+        }
+        None => None,
+    };
+    ensure_bridgeable(deps.branch(), &env, amount)?;
+    let breaker_event = if exempt {
+        None
+    } else {
+        record_flow_volume(deps.storage, &env, FLOW_WITHDRAW, amount)?
+    };
+
+    // Batched withdrawals are aggregated per destination, which would drop the memo. Exempt
+    // relayers are always bridged immediately.
+    if let Some(batching) = WITHDRAWAL_BATCHING.may_load(deps.storage)? {
+        if amount < batching.threshold && memo.is_none() && !exempt {
+            return queue_withdrawal(deps, env, info, amount, destination_address, priority_fee)
+                .map(|resp| resp.add_events(breaker_event));
+        }
+    }
+
+    let epoch = record_epoch_withdrawal(deps.branch(), &env, amount, 1)?;
+
+    // Delegate to cw20-base burn
+    let mut resp = cw20_base_contract::execute(
+        deps,
+        env.clone(),
+        info.clone(),
+        cw20_base_msg::ExecuteMsg::Burn { amount },
+    ).map_err(ContractError::from)?;
+
+    // Create the bridge withdrawal message
+    let bridge_msg = create_bridge_withdrawal_msg(
+        env.contract.address.to_string(), // creator (this contract - will be the transaction signer)
+        info.sender.to_string(),          // user_address (the caller)
+        amount.to_string(),               // amount
+        destination_address.clone(),      // destination_address
+        memo,
+    )?;
+
+    resp = resp
+        .add_message(bridge_msg)
+        .add_events(breaker_event)
+        .add_attribute("method", "withdraw")
+        .add_attribute("burn_amount", amount)
+        .add_attribute("destination_address", destination_address);
+    if let Some(epoch) = epoch {
+        resp = resp.add_attribute("epoch", epoch.to_string());
+    }
+    if exempt {
+        resp = resp.add_attribute("priority", "true");
+    }
+    if let Some(memo) = travel_rule_memo {
+        resp = resp.add_attribute("travel_rule_memo", memo);
+    }
+    if let Some(payload) = payload {
+        resp = resp.add_attribute("payload", payload);
+    }
+    if let Some(reason) = irreversible {
+        resp = resp.add_attribute("forced_destination", reason);
+    }
+
+    Ok(resp)
+}
+
+/// Adds withdrawals requested on the chain to the current inference epoch's totals. They do
+/// not wait on the epoch: if the chain cannot report it, they are counted as unattributed.
+fn record_epoch_withdrawal(deps: DepsMut, env: &Env, amount: Uint128, count: u64) -> Result<Option<u64>, ContractError> {
+    let add = |totals: Option<WithdrawalTotals>| -> StdResult<_> {
+        let totals = totals.unwrap_or_default();
+        Ok(WithdrawalTotals {
+            amount: totals.amount.checked_add(amount)?,
+            count: totals.count + count,
+        })
+    };
+    match query_proto::<_, QueryGetCurrentEpochResponse>(deps.as_ref(), proto::CURRENT_EPOCH_PATH, &QueryGetCurrentEpochRequest {}) {
+        Ok(response) => {
+            EPOCH_WITHDRAWALS.update(deps.storage, response.epoch, add)?;
+            Ok(Some(response.epoch))
+        }
+        Err(err) => {
+            record_failure(deps.storage, env, proto::CURRENT_EPOCH_PATH, &err)?;
+            let totals = add(UNATTRIBUTED_WITHDRAWALS.may_load(deps.storage)?)?;
+            UNATTRIBUTED_WITHDRAWALS.save(deps.storage, &totals)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Why funds sent to `destination` could never be recovered, if they could not
+fn irreversible_destination(storage: &dyn Storage, destination: &ExternalAddress) -> StdResult<Option<String>> {
+    if BURN_ADDRESSES.contains(&destination.as_str()) {
+        return Ok(Some("burn address".to_string()));
+    }
+    if BRIDGE_INFO.load(storage)?.contract_address == *destination {
+        return Ok(Some("external token contract".to_string()));
+    }
+    if BLOCKED_DESTINATIONS.has(storage, destination.as_str()) {
+        return Ok(Some("blocked destination".to_string()));
+    }
+    Ok(None)
+}
+
+/// Validates a travel-rule memo's shape and size and returns its JSON encoding
+fn encode_travel_rule_memo(memo: &TravelRuleMemo) -> Result<String, ContractError> {
+    let invalid = |reason: String| ContractError::InvalidTravelRuleMemo { reason };
+    let fields = [
+        ("originator_name", Some(&memo.originator_name)),
+        ("originator_account", Some(&memo.originator_account)),
+        ("beneficiary_name", Some(&memo.beneficiary_name)),
+        ("beneficiary_account", Some(&memo.beneficiary_account)),
+        ("beneficiary_vasp", memo.beneficiary_vasp.as_ref()),
+    ];
+    for (name, value) in fields {
+        let Some(value) = value else { continue };
+        if value.trim().is_empty() {
+            return Err(invalid(format!("{} is empty", name)));
+        }
+        if value.len() > MAX_TRAVEL_RULE_FIELD_LEN {
+            return Err(invalid(format!("{} longer than {} bytes", name, MAX_TRAVEL_RULE_FIELD_LEN)));
+        }
+    }
+    let encoded = to_json_string(memo)?;
+    if encoded.len() > MAX_TRAVEL_RULE_MEMO_LEN {
+        return Err(invalid(format!("memo longer than {} bytes", MAX_TRAVEL_RULE_MEMO_LEN)));
+    }
+    Ok(encoded)
+}
+
+/// Bridge memo of a withdrawal carrying both a travel-rule memo and a payload
+#[derive(serde::Serialize)]
+struct CombinedMemo<'a> {
+    /// JSON-encoded travel-rule memo
+    travel_rule: &'a str,
+    payload: &'a str,
+}
+
+/// Checks a Withdraw payload against the destination chain's memo rule
+fn check_withdrawal_payload(storage: &dyn Storage, chain_id: &str, payload: Option<&str>) -> Result<(), ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidWithdrawalPayload {
+        chain_id: chain_id.to_string(),
+        reason: reason.to_string(),
+    };
+    let rule = MEMO_RULES.may_load(storage, chain_id)?;
+    let (rule, payload) = match (rule, payload) {
+        (None, None) => return Ok(()),
+        (None, Some(_)) => return Err(invalid("the chain has no memo rule")),
+        (Some(rule), None) if rule.required => return Err(invalid("the chain requires a payload")),
+        (Some(_), None) => return Ok(()),
+        (Some(rule), Some(payload)) => (rule, payload),
+    };
+    if payload.is_empty() {
+        return Err(invalid("payload is empty"));
+    }
+    if payload.len() > rule.max_len as usize {
+        return Err(invalid(&format!("payload longer than {} bytes", rule.max_len)));
+    }
+    match rule.encoding {
+        MemoEncoding::Text if !payload.bytes().all(|b| b.is_ascii_graphic() || b == b' ') => {
+            Err(invalid("payload is not printable ASCII"))
+        }
+        MemoEncoding::Hex => {
+            let digits = payload.strip_prefix("0x").unwrap_or(payload);
+            if digits.is_empty() || digits.len() % 2 != 0 || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid("payload is not hex bytes"));
+            }
+            Ok(())
+        }
+        MemoEncoding::Text => Ok(()),
+    }
+}
+
+fn set_memo_rule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    chain_id: String,
+    rule: Option<MemoRule>,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    if chain_id.trim().is_empty() {
+        return Err(ContractError::Std(StdError::generic_err("chain_id is empty")));
+    }
+    if let Some(rule) = &rule {
+        // The whole payload has to fit in a travel-rule-sized memo
+        if rule.max_len == 0 || rule.max_len as usize > MAX_TRAVEL_RULE_MEMO_LEN {
+            return Err(ContractError::Std(StdError::generic_err(format!(
+                "max_len must be between 1 and {}",
+                MAX_TRAVEL_RULE_MEMO_LEN
+            ))));
+        }
+    }
+    let old = MEMO_RULES.may_load(deps.storage, &chain_id)?;
+    match &rule {
+        Some(rule) => MEMO_RULES.save(deps.storage, &chain_id, rule)?,
+        None => MEMO_RULES.remove(deps.storage, &chain_id),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_memo_rule",
+        old.map(|r| to_json_string(&r)).transpose()?,
+        rule.as_ref().map(to_json_string).transpose()?,
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_memo_rule")
+        .add_attribute("chain_id", chain_id)
+        .add_attribute("rule", rule.map_or(Ok("none".to_string()), |r| to_json_string(&r))?))
+}
+
+fn set_travel_rule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    threshold: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let old = TRAVEL_RULE.may_load(deps.storage)?.map(|r| r.threshold);
+    match threshold {
+        Some(threshold) => TRAVEL_RULE.save(deps.storage, &TravelRule { threshold })?,
+        None => TRAVEL_RULE.remove(deps.storage),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_travel_rule",
+        old.map(|t| t.to_string()),
+        threshold.map(|t| t.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_travel_rule")
+        .add_attribute("threshold", threshold.map_or("none".to_string(), |t| t.to_string())))
+}
+
+fn set_self_transfer_policy(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    policy: SelfTransferPolicy,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let old = SELF_TRANSFER_POLICY.may_load(deps.storage)?.unwrap_or_default();
+    SELF_TRANSFER_POLICY.save(deps.storage, &policy)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_self_transfer_policy",
+        Some(to_json_string(&old)?),
+        Some(to_json_string(&policy)?),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_self_transfer_policy")
+        .add_attribute("policy", to_json_string(&policy)?))
+}
+
+/// Moves tokens out of the contract's own balance, where transfers made before the self-transfer
+/// check left them. Pending withdrawals are not held there, so this cannot touch escrow.
+fn recover_stuck_balance(
+    deps: DepsMut,
+    env: Env,
+    admin: Addr,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let recipient_addr = deps.api.addr_validate(&recipient)?;
+    if recipient_addr == env.contract.address {
+        return Err(ContractError::TransferToSelf {});
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &admin,
+        "recover_stuck_balance",
+        None,
+        Some(format!("{} to {}", amount, recipient_addr)),
+    )?;
+    let contract_info = MessageInfo { sender: env.contract.address.clone(), funds: vec![] };
+    let resp = cw20_base_contract::execute_transfer(deps, env, contract_info, recipient, amount)
+        .map_err(ContractError::from)?;
+    Ok(resp.add_attribute("method", "recover_stuck_balance"))
+}
+
+/// Chain metadata for an external token, `None` when the chain does not know it. A failed
+/// lookup also yields `None` and is recorded for the `GrpcFailures` debug query.
+fn query_token_metadata(
+    deps: DepsMut,
+    env: &Env,
+    chain_id: &str,
+    contract_address: ExternalAddress,
+) -> StdResult<Option<QueryBridgeTokenMetadataResponse>> {
+    let request = QueryBridgeTokenMetadataRequest {
+        chain_id: chain_id.to_string(),
+        contract_address: contract_address.into(),
+    };
+    match query_proto::<_, QueryBridgeTokenMetadataResponse>(deps.as_ref(), proto::BRIDGE_TOKEN_METADATA_PATH, &request) {
+        Ok(metadata) => Ok(Some(metadata).filter(|metadata| metadata.found)),
+        Err(err) => {
+            record_failure(deps.storage, env, proto::BRIDGE_TOKEN_METADATA_PATH, &err)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Checks that `amount` converts to a whole amount of the external token that fits in a uint256,
+/// before any tokens are burned. Without chain metadata for the external token the decimals are
+/// unknown and the amount is passed through unchecked.
+fn ensure_bridgeable(mut deps: DepsMut, env: &Env, amount: Uint128) -> Result<(), ContractError> {
+    let bridge_info = BRIDGE_INFO.load(deps.storage)?;
+    let Some(metadata) = query_token_metadata(deps.branch(), env, &bridge_info.chain_id, bridge_info.contract_address)? else {
+        return Ok(());
+    };
+    let local_decimals = cw20_base_state::TOKEN_INFO.load(deps.storage)?.decimals;
+    check_bridgeable_amount(amount, local_decimals, metadata.decimals)
+}
+
+fn check_bridgeable_amount(amount: Uint128, local_decimals: u8, external_decimals: u32) -> Result<(), ContractError> {
+    let unbridgeable = |reason: &str| ContractError::UnbridgeableAmount {
+        amount: amount.u128(),
+        local_decimals,
+        external_decimals,
+        reason: reason.to_string(),
+    };
+
+    match external_decimals.checked_sub(local_decimals as u32) {
+        // Scaling up: the external amount must stay within uint256
+        Some(shift) => {
+            Uint256::from(10u8)
+                .checked_pow(shift)
+                .and_then(|scale| Uint256::from(amount).checked_mul(scale))
+                .map_err(|_| unbridgeable("external amount overflows uint256"))?;
+        }
+        // Scaling down: anything below the external token's precision would be lost
+        None => {
+            let shift = local_decimals as u32 - external_decimals;
+            let exact = match Uint128::from(10u8).checked_pow(shift) {
+                Ok(scale) => (amount % scale).is_zero(),
+                Err(_) => amount.is_zero(),
+            };
+            if !exact {
+                return Err(unbridgeable("amount has precision the external token cannot represent"));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_relayer_exemption(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    relayer: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let relayer = deps.api.addr_validate(&relayer)?;
+    let was_exempt = EXEMPT_RELAYERS.has(deps.storage, &relayer);
+    let mut resp = Response::new()
+        .add_attribute("method", "set_relayer_exemption")
+        .add_attribute("relayer", relayer.as_str())
+        .add_attribute("exempt", exempt.to_string());
+    if was_exempt == exempt {
+        return Ok(resp);
+    }
+    if exempt {
+        EXEMPT_RELAYERS.save(deps.storage, &relayer, &())?;
+    } else {
+        EXEMPT_RELAYERS.remove(deps.storage, &relayer);
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_relayer_exemption",
+        Some(format!("{}:{}", relayer, was_exempt)),
+        Some(format!("{}:{}", relayer, exempt)),
+    )?;
+    resp = resp.add_event(
+        Event::new("relayer_exemption_changed")
+            .add_attribute("relayer", relayer)
+            .add_attribute("exempt", exempt.to_string()),
+    );
+    Ok(resp)
+}
+
+/// Debits a small withdrawal into the per-destination queue. The amount stays in total supply
+/// (as pending-withdrawal escrow) until FlushWithdrawals burns and bridges it. A priority fee
+/// moves to the fee collector right away and raises the destination's place in the flush order.
+fn queue_withdrawal(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    destination: ExternalAddress,
+    priority_fee: Option<(Uint128, Addr)>,
+) -> Result<Response, ContractError> {
+    let fee = priority_fee.as_ref().map(|(fee, _)| *fee).unwrap_or_default();
+    let debit = amount.checked_add(fee).map_err(StdError::from)?;
+    cw20_base_state::BALANCES.update(deps.storage, &info.sender, |balance| -> Result<_, ContractError> {
+        let balance = balance.unwrap_or_default();
+        balance.checked_sub(debit).map_err(|_| ContractError::InsufficientFunds {
+            balance: balance.u128(),
+            required: debit.u128(),
+        })
+    })?;
+    let was_queued = PENDING_WITHDRAWALS.has(deps.storage, destination.as_str());
+    update_count(deps.storage, &PENDING_WITHDRAWAL_COUNT, was_queued, true)?;
+    let queued = PENDING_WITHDRAWALS.update(deps.storage, destination.as_str(), |queued| -> StdResult<_> {
+        Ok(queued.unwrap_or_default().checked_add(amount)?)
+    })?;
+    PENDING_WITHDRAWAL_SENDERS.update(deps.storage, (destination.as_str(), &info.sender), |queued| -> StdResult<_> {
+        Ok(queued.unwrap_or_default().checked_add(amount)?)
+    })?;
+    // The aggregate is what FlushWithdrawals bridges, so it must convert too
+    ensure_bridgeable(deps.branch(), &env, queued)?;
+    let pending = PENDING_WITHDRAWAL_SUPPLY.may_load(deps.storage)?.unwrap_or_default();
+    PENDING_WITHDRAWAL_SUPPLY.save(deps.storage, &pending.checked_add(amount).map_err(StdError::from)?)?;
+
+    let mut resp = Response::new()
+        .add_attribute("method", "withdraw")
+        .add_attribute("batched", "true")
+        .add_attribute("amount", amount)
+        .add_attribute("destination_address", destination.as_str())
+        .add_attribute("queued_for_destination", queued);
+    if let Some((fee, fee_collector)) = priority_fee {
+        cw20_base_state::BALANCES.update(deps.storage, &fee_collector, |balance| -> StdResult<_> {
+            Ok(balance.unwrap_or_default().checked_add(fee)?)
+        })?;
+        let old = PENDING_PRIORITY_FEES.may_load(deps.storage, destination.as_str())?;
+        if let Some(old) = old {
+            WITHDRAWAL_PRIORITY.remove(deps.storage, (old.u128(), destination.as_str()));
+        }
+        let total = old.unwrap_or_default().checked_add(fee).map_err(StdError::from)?;
+        PENDING_PRIORITY_FEES.save(deps.storage, destination.as_str(), &total)?;
+        WITHDRAWAL_PRIORITY.save(deps.storage, (total.u128(), destination.as_str()), &())?;
+        resp = resp
+            .add_attribute("priority_fee", fee)
+            .add_attribute("fee_collector", fee_collector)
+            .add_attribute("priority_fee_for_destination", total);
+    }
+    Ok(resp)
+}
+
+/// Burns and bridges up to `limit` queued destinations, those that paid priority fees first
+/// (highest total fee first), then the rest in address order. Permissionless: the queue can
+/// only move toward the destinations users chose.
+///
+/// Each destination goes out as one withdrawal. A destination queued by a single account names
+/// it as the user; one queued by several names the contract, which escrowed the tokens, and
+/// lists every sender's share in the memo.
+fn flush_withdrawals(deps: DepsMut, env: Env, limit: Option<u32>) -> Result<Response, ContractError> {
+    ensure_flow_open(deps.storage, FLOW_WITHDRAW)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut destinations = WITHDRAWAL_PRIORITY
+        .keys(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|key| key.map(|(_, destination)| destination))
+        .collect::<StdResult<Vec<_>>>()?;
+    if destinations.len() < limit {
+        let rest = PENDING_WITHDRAWALS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .filter(|destination| match destination {
+                Ok(destination) => !PENDING_PRIORITY_FEES.has(deps.storage, destination),
+                Err(_) => true,
+            })
+            .take(limit - destinations.len())
+            .collect::<StdResult<Vec<_>>>()?;
+        destinations.extend(rest);
+    }
+    let queued = destinations
+        .into_iter()
+        .map(|destination| {
+            let amount = PENDING_WITHDRAWALS.load(deps.storage, &destination)?;
+            Ok((destination, amount))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut resp = Response::new().add_attribute("method", "flush_withdrawals");
+    let mut flushed = Uint128::zero();
+    for (destination, amount) in &queued {
+        let senders = take_withdrawal_senders(deps.storage, &env, destination, *amount)?;
+        PENDING_WITHDRAWALS.remove(deps.storage, destination);
+        update_count(deps.storage, &PENDING_WITHDRAWAL_COUNT, true, false)?;
+        if let Some(fee) = PENDING_PRIORITY_FEES.may_load(deps.storage, destination)? {
+            PENDING_PRIORITY_FEES.remove(deps.storage, destination);
+            WITHDRAWAL_PRIORITY.remove(deps.storage, (fee.u128(), destination.as_str()));
+        }
+        flushed += *amount;
+        let (user_address, memo) = match senders.as_slice() {
+            [sender] => (sender.address.clone(), None),
+            _ => (env.contract.address.to_string(), Some(to_json_string(&FlushedSenders { senders: &senders })?)),
+        };
+        resp = resp
+            .add_message(create_bridge_withdrawal_msg(
+                env.contract.address.to_string(),
+                user_address.clone(),
+                amount.to_string(),
+                ExternalAddress::from(destination.clone()),
+                memo,
+            )?)
+            .add_event(
+                Event::new("withdrawal_flushed")
+                    .add_attribute("destination_address", destination.clone())
+                    .add_attribute("amount", *amount)
+                    .add_attribute("user_address", user_address),
+            )
+            .add_events(senders.iter().map(|sender| {
+                Event::new("withdrawal_flushed_sender")
+                    .add_attribute("destination_address", destination.clone())
+                    .add_attribute("sender", sender.address.clone())
+                    .add_attribute("amount", sender.amount)
+            }));
+    }
+    if !flushed.is_zero() {
+        let mut token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+        token_info.total_supply = token_info.total_supply.checked_sub(flushed).map_err(StdError::from)?;
+        cw20_base_state::TOKEN_INFO.save(deps.storage, &token_info)?;
+        let pending = PENDING_WITHDRAWAL_SUPPLY.may_load(deps.storage)?.unwrap_or_default();
+        PENDING_WITHDRAWAL_SUPPLY.save(deps.storage, &pending.saturating_sub(flushed))?;
+        if let Some(epoch) = record_epoch_withdrawal(deps, &env, flushed, queued.len() as u64)? {
+            resp = resp.add_attribute("epoch", epoch.to_string());
+        }
+    }
+    Ok(resp
+        .add_attribute("destinations", queued.len().to_string())
+        .add_attribute("burn_amount", flushed))
+}
+
+/// Bridge memo of a flushed destination queued by several accounts
+#[derive(serde::Serialize)]
+struct FlushedSenders<'a> {
+    senders: &'a [Cw20Coin],
+}
+
+/// Clears a destination's per-sender shares and returns them. A share queued before senders
+/// were tracked has no owner on record and is attributed to the contract.
+fn take_withdrawal_senders(storage: &mut dyn Storage, env: &Env, destination: &str, amount: Uint128) -> StdResult<Vec<Cw20Coin>> {
+    let shares = PENDING_WITHDRAWAL_SENDERS
+        .prefix(destination)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut senders = Vec::with_capacity(shares.len() + 1);
+    let mut attributed = Uint128::zero();
+    for (sender, share) in shares {
+        PENDING_WITHDRAWAL_SENDERS.remove(storage, (destination, &sender));
+        attributed += share;
+        senders.push(Cw20Coin { address: sender.to_string(), amount: share });
+    }
+    if amount > attributed {
+        senders.push(Cw20Coin { address: env.contract.address.to_string(), amount: amount - attributed });
+    }
+    Ok(senders)
+}
+
+fn set_withdrawal_batching(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    threshold: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let old = WITHDRAWAL_BATCHING.may_load(deps.storage)?.map(|b| b.threshold);
+    match threshold {
+        Some(threshold) => WITHDRAWAL_BATCHING.save(deps.storage, &WithdrawalBatching { threshold })?,
+        None => WITHDRAWAL_BATCHING.remove(deps.storage),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_withdrawal_batching",
+        old.map(|t| t.to_string()),
+        threshold.map(|t| t.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_withdrawal_batching")
+        .add_attribute("threshold", threshold.map_or("none".to_string(), |t| t.to_string())))
+}
+
+fn set_priority_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    fee_collector: Option<String>,
+) -> Result<Response, ContractError> {
+    ensure_creator_or_admin(deps.as_ref(), &info.sender)?;
+    let fee_collector = fee_collector.map(|c| deps.api.addr_validate(&c)).transpose()?;
+    let old = PRIORITY_FEES.may_load(deps.storage)?.map(|f| f.fee_collector);
+    match &fee_collector {
+        Some(fee_collector) => PRIORITY_FEES.save(deps.storage, &PriorityFees { fee_collector: fee_collector.clone() })?,
+        None => PRIORITY_FEES.remove(deps.storage),
+    }
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "set_priority_fees",
+        old.map(|c| c.to_string()),
+        fee_collector.as_ref().map(|c| c.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_priority_fees")
+        .add_attribute("fee_collector", fee_collector.map_or("none".to_string(), |c| c.to_string())))
+}
+
+// Helper function to create the bridge withdrawal message
+fn create_bridge_withdrawal_msg(
+    creator: String,
+    user_address: String,
+    amount: String,
+    destination_address: ExternalAddress,
+    memo: Option<String>,
+) -> Result<CosmosMsg, ContractError> {
+    // Encode the message as protobuf; only memo-carrying withdrawals need the v2 shape
+    let mut buf = Vec::new();
+    let encoded = match memo {
+        Some(memo) => proto::v2::MsgRequestBridgeWithdrawal {
+            creator,
+            user_address,
+            amount,
+            destination_address: destination_address.to_string(),
+            memo,
+        }
+        .encode(&mut buf),
+        None => MsgRequestBridgeWithdrawal {
+            creator,
+            user_address,
+            amount,
+            destination_address: destination_address.into(),
+        }
+        .encode(&mut buf),
+    };
+    encoded.map_err(|e| ContractError::Std(StdError::generic_err(format!("Failed to encode withdrawal message: {}", e))))?;
+
+    let stargate_msg = CosmosMsg::Any(cosmwasm_std::AnyMsg {
+        type_url: proto::MSG_REQUEST_BRIDGE_WITHDRAWAL_TYPE_URL.to_string(),
+        value: Binary::from(buf),
+    });
+
+    Ok(stargate_msg)
+}
+
+#[entry_point]
+pub fn sudo(mut deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let governance = Addr::unchecked("sudo");
+    STATE_ROOT_PROGRESS.remove(deps.storage);
+    match msg {
+        SudoMsg::ForwardModuleMsg { type_url, value } => forward_module_msg(deps, env, governance, type_url, value),
+        SudoMsg::UpdateMinterCap { new_cap } => update_minter_cap(deps, env, governance, new_cap),
+        SudoMsg::TransferMinter { new_minter } => transfer_minter(deps, env, governance, new_minter),
+        SudoMsg::SetCircuitBreaker { breaker } => set_circuit_breaker(deps, env, governance, breaker),
+        SudoMsg::ResumeCircuitBreaker {} => resume_circuit_breaker(deps, env, governance),
+        SudoMsg::MintBatch { mints } => {
+            // The bookkeeping execute does around supply changes
+            let recipients = mints.iter().map(|(recipient, _, _)| recipient.clone()).collect();
+            checkpoint_accounts(deps.branch(), &env, recipients)?;
+            let bridge_info = BRIDGE_INFO.load(deps.storage)?;
+            Ok(tag_bridge_route(mint_batch(deps, env, mints)?, &bridge_info))
+        }
+        SudoMsg::UpdateBlockedDestinations { add, remove } => {
+            let add = add.iter().map(|address| ExternalAddress::parse(address)).collect::<Result<Vec<_>, _>>()?;
+            let remove = remove.iter().map(|address| ExternalAddress::parse(address)).collect::<Result<Vec<_>, _>>()?;
+            for address in &add {
+                BLOCKED_DESTINATIONS.save(deps.storage, address.as_str(), &())?;
+            }
+            for address in &remove {
+                BLOCKED_DESTINATIONS.remove(deps.storage, address.as_str());
+            }
+            let join = |addresses: &[ExternalAddress]| addresses.iter().map(ExternalAddress::as_str).collect::<Vec<_>>().join(",");
+            record_audit(
+                deps.storage,
+                &env,
+                &governance,
+                "update_blocked_destinations",
+                (!remove.is_empty()).then(|| join(&remove)),
+                (!add.is_empty()).then(|| join(&add)),
+            )?;
+            Ok(Response::new()
+                .add_attribute("method", "update_blocked_destinations")
+                .add_attribute("added", add.len().to_string())
+                .add_attribute("removed", remove.len().to_string()))
+        }
+        SudoMsg::UpdateForwardAllowlist { add, remove } => {
+            for type_url in &add {
+                FORWARD_ALLOWLIST.save(deps.storage, type_url, &())?;
+            }
+            for type_url in &remove {
+                FORWARD_ALLOWLIST.remove(deps.storage, type_url);
+            }
+            record_audit(
+                deps.storage,
+                &env,
+                &governance,
+                "update_forward_allowlist",
+                (!remove.is_empty()).then(|| remove.join(",")),
+                (!add.is_empty()).then(|| add.join(",")),
+            )?;
+            Ok(Response::new()
+                .add_attribute("method", "update_forward_allowlist")
+                .add_attribute("added", add.len().to_string())
+                .add_attribute("removed", remove.len().to_string()))
+        }
+    }
+}
+
+/// Smaller of the limit's absolute and supply-relative bounds
+fn volume_ceiling(limit: &VolumeLimit, supply: Uint128) -> Option<Uint128> {
+    let relative = limit.max_supply_bps.map(|bps| supply.multiply_ratio(bps, MAX_BPS));
+    match (limit.max_amount, relative) {
+        (Some(absolute), Some(relative)) => Some(absolute.min(relative)),
+        (absolute, relative) => absolute.or(relative),
+    }
+}
+
+fn ensure_flow_open(storage: &dyn Storage, flow: &str) -> Result<(), ContractError> {
+    match HALTED_FLOWS.may_load(storage, flow)? {
+        Some(since_height) => Err(ContractError::FlowHalted { flow: flow.to_string(), since_height }),
+        None => Ok(()),
+    }
+}
+
+/// Adds `amount` to the flow's volume for the current window and halts the flow once the volume
+/// exceeds its limit. The operation that trips the breaker completes, since failing it would roll
+/// the halt back as well; the returned event marks it.
+fn record_flow_volume(storage: &mut dyn Storage, env: &Env, flow: &str, amount: Uint128) -> Result<Option<Event>, ContractError> {
+    let Some(breaker) = CIRCUIT_BREAKER.may_load(storage)? else {
+        return Ok(None);
+    };
+    let limit = if flow == FLOW_MINT { breaker.mint } else { breaker.withdraw };
+    let Some(limit) = limit else {
+        return Ok(None);
+    };
+    let window = env.block.height / breaker.window_blocks;
+    let stale = FLOW_VOLUME
+        .prefix(flow)
+        .keys(storage, None, Some(Bound::exclusive(window)), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for old in stale {
+        FLOW_VOLUME.remove(storage, (flow, old));
+    }
+    let volume = FLOW_VOLUME.update(storage, (flow, window), |volume| -> StdResult<_> {
+        Ok(volume.unwrap_or_default().checked_add(amount)?)
+    })?;
+
+    let supply = cw20_base_state::TOKEN_INFO.load(storage)?.total_supply;
+    match volume_ceiling(&limit, supply) {
+        Some(ceiling) if volume > ceiling => {
+            HALTED_FLOWS.save(storage, flow, &env.block.height)?;
+            record_audit(
+                storage,
+                env,
+                &Addr::unchecked("circuit_breaker"),
+                "circuit_breaker_tripped",
+                None,
+                Some(flow.to_string()),
+            )?;
+            Ok(Some(
+                Event::new("circuit_breaker_tripped")
+                    .add_attribute("flow", flow)
+                    .add_attribute("volume", volume)
+                    .add_attribute("limit", ceiling),
+            ))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Whether adding `amount` to the flow's current window would trip the circuit breaker
+fn flow_would_trip(storage: &dyn Storage, env: &Env, flow: &str, amount: Uint128) -> StdResult<bool> {
+    let Some(breaker) = CIRCUIT_BREAKER.may_load(storage)? else {
+        return Ok(false);
+    };
+    let limit = if flow == FLOW_MINT { breaker.mint } else { breaker.withdraw };
+    let Some(limit) = limit else {
+        return Ok(false);
+    };
+    let window = env.block.height / breaker.window_blocks;
+    let volume = FLOW_VOLUME.may_load(storage, (flow, window))?.unwrap_or_default().checked_add(amount)?;
+    let supply = cw20_base_state::TOKEN_INFO.load(storage)?.total_supply;
+    Ok(matches!(volume_ceiling(&limit, supply), Some(ceiling) if volume > ceiling))
+}
+
+fn clear_flow_volume(storage: &mut dyn Storage) -> StdResult<()> {
+    let keys = FLOW_VOLUME
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (flow, window) in keys {
+        FLOW_VOLUME.remove(storage, (flow.as_str(), window));
+    }
+    Ok(())
+}
+
+fn set_circuit_breaker(deps: DepsMut, env: Env, actor: Addr, breaker: Option<CircuitBreaker>) -> Result<Response, ContractError> {
+    if let Some(breaker) = &breaker {
+        let invalid = |reason: &str| ContractError::InvalidCircuitBreaker { reason: reason.to_string() };
+        if breaker.window_blocks == 0 {
+            return Err(invalid("window_blocks must be positive"));
+        }
+        for limit in [&breaker.mint, &breaker.withdraw].into_iter().flatten() {
+            if limit.max_amount.is_none() && limit.max_supply_bps.is_none() {
+                return Err(invalid("a volume limit needs max_amount or max_supply_bps"));
+            }
+            if limit.max_supply_bps.is_some_and(|bps| bps == 0 || bps > MAX_BPS) {
+                return Err(invalid("max_supply_bps must be between 1 and 10000"));
+            }
+        }
+    }
+    let old = CIRCUIT_BREAKER.may_load(deps.storage)?;
+    match &breaker {
+        Some(breaker) => CIRCUIT_BREAKER.save(deps.storage, breaker)?,
+        None => CIRCUIT_BREAKER.remove(deps.storage),
+    }
+    // Volume counted under the old limits would not line up with the new windows
+    clear_flow_volume(deps.storage)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &actor,
+        "set_circuit_breaker",
+        old.as_ref().map(to_json_string).transpose()?,
+        breaker.as_ref().map(to_json_string).transpose()?,
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "set_circuit_breaker")
+        .add_attribute("enabled", breaker.is_some().to_string()))
+}
+
+fn resume_circuit_breaker(deps: DepsMut, env: Env, actor: Addr) -> Result<Response, ContractError> {
+    let halted = HALTED_FLOWS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for flow in &halted {
+        HALTED_FLOWS.remove(deps.storage, flow);
+    }
+    // Start fresh windows so the volume that tripped the breaker does not trip it again
+    clear_flow_volume(deps.storage)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &actor,
+        "resume_circuit_breaker",
+        (!halted.is_empty()).then(|| halted.join(",")),
+        None,
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "resume_circuit_breaker")
+        .add_attribute("resumed", halted.join(",")))
+}
+
+/// cw20-base fixes the cap at instantiate; this rewrites it in place, never below current supply
+fn update_minter_cap(deps: DepsMut, env: Env, actor: Addr, new_cap: Option<Uint128>) -> Result<Response, ContractError> {
+    let mut token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+    let Some(mint) = token_info.mint.as_mut() else {
+        return Err(ContractError::Std(StdError::generic_err("token has no minter")));
+    };
+    if let Some(cap) = new_cap {
+        if cap < token_info.total_supply {
+            return Err(ContractError::CapBelowSupply {
+                cap: cap.u128(),
+                supply: token_info.total_supply.u128(),
+            });
+        }
+    }
+    let old_cap = mint.cap;
+    mint.cap = new_cap;
+    cw20_base_state::TOKEN_INFO.save(deps.storage, &token_info)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &actor,
+        "update_minter_cap",
+        old_cap.map(|c| c.to_string()),
+        new_cap.map(|c| c.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "update_minter_cap")
+        .add_attribute("old_cap", old_cap.map_or("none".to_string(), |c| c.to_string()))
+        .add_attribute("new_cap", new_cap.map_or("none".to_string(), |c| c.to_string())))
+}
+
+/// Rotates the minter, e.g. when the chain moves bridge minting to a new module account.
+/// The cap is kept.
+fn transfer_minter(deps: DepsMut, env: Env, actor: Addr, new_minter: String) -> Result<Response, ContractError> {
+    let new_minter = deps.api.addr_validate(&new_minter)?;
+    let mut token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+    let Some(mint) = token_info.mint.as_mut() else {
+        return Err(ContractError::Std(StdError::generic_err("token has no minter")));
+    };
+    let old_minter = std::mem::replace(&mut mint.minter, new_minter.clone());
+    cw20_base_state::TOKEN_INFO.save(deps.storage, &token_info)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &actor,
+        "transfer_minter",
+        Some(old_minter.to_string()),
+        Some(new_minter.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "transfer_minter")
+        .add_event(
+            Event::new("minter_transferred")
+                .add_attribute("old_minter", old_minter)
+                .add_attribute("new_minter", new_minter),
+        ))
+}
+
+fn propose_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    admin: String,
+    expiry_height: Option<u64>,
+) -> Result<Response, ContractError> {
+    let current = ADMIN.load(deps.storage)?;
+    let admin = deps.api.addr_validate(&admin)?;
+    let pending = ownership::propose(
+        current.as_str(),
+        info.sender.as_str(),
+        admin.to_string(),
+        expiry_height,
+        env.block.height,
+    )?;
+    PENDING_ADMIN.save(deps.storage, &pending)?;
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "propose_admin",
+        Some(current.to_string()),
+        Some(admin.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "propose_admin")
+        .add_attribute("pending_admin", admin))
+}
+
+fn accept_admin(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN.may_load(deps.storage)?;
+    ownership::accept(pending.as_ref(), info.sender.as_str(), env.block.height)?;
+    let old_admin = ADMIN.load(deps.storage)?;
+    ADMIN.save(deps.storage, &info.sender)?;
+    PENDING_ADMIN.remove(deps.storage);
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "accept_admin",
+        Some(old_admin.to_string()),
+        Some(info.sender.to_string()),
+    )?;
+    Ok(Response::new()
+        .add_attribute("method", "accept_admin")
+        .add_event(
+            Event::new("admin_transferred")
+                .add_attribute("old_admin", old_admin)
+                .add_attribute("new_admin", info.sender),
+        ))
+}
+
+fn cancel_admin_proposal(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN.may_load(deps.storage)?;
+    ownership::cancel(ADMIN.load(deps.storage)?.as_str(), info.sender.as_str(), pending.as_ref())?;
+    PENDING_ADMIN.remove(deps.storage);
+    record_audit(
+        deps.storage,
+        &env,
+        &info.sender,
+        "cancel_admin_proposal",
+        pending.map(|pending| pending.new_owner),
+        None,
+    )?;
+    Ok(Response::new().add_attribute("method", "cancel_admin_proposal"))
+}
+
+/// Lets the inference module drive new chain messages through deployed tokens without a migration
+fn forward_module_msg(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    type_url: String,
+    value: Binary,
+) -> Result<Response, ContractError> {
+    if !FORWARD_ALLOWLIST.has(deps.storage, &type_url) {
+        return Err(ContractError::TypeUrlNotAllowed { type_url });
+    }
+    record_audit(deps.storage, &env, &sender, "forward_module_msg", None, Some(type_url.clone()))?;
+    Ok(Response::new()
+        .add_message(CosmosMsg::Any(cosmwasm_std::AnyMsg { type_url: type_url.clone(), value }))
+        .add_attribute("method", "forward_module_msg")
+        .add_attribute("type_url", type_url))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Version {} => to_json_binary(&VersionResponse {
+            contract: get_contract_version(deps.storage)?,
+            cw20_base: CW20_BASE_VERSION.may_load(deps.storage)?,
+        }),
+        QueryMsg::InterfaceVersion {} => to_json_binary(&InterfaceVersionResponse {
+            execute: EXECUTE_INTERFACE_VERSION,
+            query: QUERY_INTERFACE_VERSION,
+            sudo: SUDO_INTERFACE_VERSION,
+            debug_queries: cfg!(feature = "debug"),
+        }),
+        QueryMsg::BridgeInfo {} => to_json_binary(&query_bridge_info(deps)?),
+        QueryMsg::BridgeInfoHistory {} => to_json_binary(&query_bridge_info_history(deps)?),
+        QueryMsg::BridgeSalt { chain_id, contract_address } => to_json_binary(&BridgeSaltResponse {
+            salt: bridge_salt(&chain_id, &contract_address),
+            instance_salt: INSTANTIATE_SALT.may_load(deps.storage)?,
+        }),
+        QueryMsg::Balance { address } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::Balance { address }),
+        QueryMsg::TokenInfo {} => to_json_binary(&query_token_info(deps, env)?),
+        QueryMsg::Allowance { owner, spender } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::Allowance { owner, spender }),
+        QueryMsg::AllAllowances { owner, start_after, limit } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::AllAllowances { owner, start_after, limit }),
+        QueryMsg::AllAccounts { start_after, limit } => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::AllAccounts { start_after, limit }),
+        QueryMsg::MarketingInfo {} => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::MarketingInfo {}),
+        QueryMsg::DownloadLogo {} => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::DownloadLogo {}),
+        QueryMsg::Minter {} => cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::Minter {}),
+        QueryMsg::SpenderActivity { owner, spender } => to_json_binary(&query_spender_activity(deps, owner, spender)?),
+        QueryMsg::SpendAlerts { owner, start_after, limit } => to_json_binary(&query_spend_alerts(deps, owner, start_after, limit)?),
+        QueryMsg::Session { owner, session_key } => to_json_binary(&query_session(deps, env, owner, session_key)?),
+        QueryMsg::Sessions { owner, start_after, limit } => to_json_binary(&query_sessions(deps, env, owner, start_after, limit)?),
+        QueryMsg::TransferNonce { address } => {
+            let addr = deps.api.addr_validate(&address)?;
+            to_json_binary(&TransferNonceResponse {
+                next_nonce: TRANSFER_NONCES.may_load(deps.storage, &addr)?.unwrap_or_default(),
+                address,
+            })
+        }
+        QueryMsg::TransferGuard { address } => to_json_binary(&query_transfer_guard(deps, env, address)?),
+        QueryMsg::ForwardAllowlist {} => to_json_binary(&ForwardAllowlistResponse {
+            type_urls: FORWARD_ALLOWLIST
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?,
+        }),
+        QueryMsg::BlockedDestinations {} => to_json_binary(&BlockedDestinationsResponse {
+            burn_addresses: BURN_ADDRESSES.iter().map(|address| address.to_string()).collect(),
+            token_contract: BRIDGE_INFO.load(deps.storage)?.contract_address.into(),
+            blocked: BLOCKED_DESTINATIONS
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?,
+            sanctions_cache_blocks: SANCTIONS_SYNC.may_load(deps.storage)?.map(|s| s.cache_blocks),
+        }),
+        QueryMsg::PendingAdmin {} => to_json_binary(&PENDING_ADMIN.may_load(deps.storage)?),
+        QueryMsg::SelfTransfers {} => to_json_binary(&SelfTransfersResponse {
+            policy: SELF_TRANSFER_POLICY.may_load(deps.storage)?.unwrap_or_default(),
+            stranded_balance: cw20_base_state::BALANCES
+                .may_load(deps.storage, &env.contract.address)?
+                .unwrap_or_default(),
+        }),
+        QueryMsg::LockupRegistry {} => {
+            let config = LOCKUP_REGISTRY.may_load(deps.storage)?;
+            to_json_binary(&LockupRegistryResponse {
+                contract: config.as_ref().map(|c| c.contract.to_string()),
+                cache_blocks: config.map_or(0, |c| c.cache_blocks),
+            })
+        }
+        QueryMsg::RelayerBond {} => to_json_binary(&RelayerBondResponse {
+            contract: RELAYER_BOND.may_load(deps.storage)?.map(|c| c.to_string()),
+        }),
+        QueryMsg::SupplyBreakdown {} => to_json_binary(&query_supply_breakdown(deps)?),
+        QueryMsg::Reconcile {} => to_json_binary(&query_reconcile(deps)?),
+        QueryMsg::Status {} => to_json_binary(&query_status(deps, env)?),
+        QueryMsg::CanWithdraw { address, amount, chain_id, destination_address, payload } => {
+            to_json_binary(&query_can_withdraw(deps, env, address, amount, chain_id, destination_address, payload)?)
+        }
+        QueryMsg::MemoRules { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.as_deref().map(Bound::exclusive);
+            let rules = MEMO_RULES
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let (chain_id, rule) = item?;
+                    Ok(MemoRuleEntry { chain_id, rule })
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&MemoRulesResponse { rules })
+        }
+        QueryMsg::Manifest {} => to_json_binary(&query_manifest(deps, env)?),
+        QueryMsg::CircuitBreaker {} => to_json_binary(&query_circuit_breaker(deps, env)?),
+        QueryMsg::AuditLog { start_after, limit } => to_json_binary(&query_audit_log(deps, start_after, limit)?),
+        QueryMsg::PendingWithdrawals { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start = start_after.as_deref().map(Bound::exclusive);
+            let withdrawals = PENDING_WITHDRAWALS
+                .range(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let (destination_address, amount) = item?;
+                    let priority_fee = PENDING_PRIORITY_FEES
+                        .may_load(deps.storage, &destination_address)?
+                        .unwrap_or_default();
+                    Ok(PendingWithdrawal { destination_address, amount, priority_fee })
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&PendingWithdrawalsResponse {
+                threshold: WITHDRAWAL_BATCHING.may_load(deps.storage)?.map(|b| b.threshold),
+                priority_fee_collector: PRIORITY_FEES.may_load(deps.storage)?.map(|f| f.fee_collector.to_string()),
+                withdrawals,
+            })
+        }
+        QueryMsg::EpochWithdrawals { epoch } => {
+            let totals = EPOCH_WITHDRAWALS.may_load(deps.storage, epoch)?.unwrap_or_default();
+            to_json_binary(&EpochWithdrawalsResponse {
+                epoch,
+                amount: totals.amount,
+                count: totals.count,
+                unattributed_amount: UNATTRIBUTED_WITHDRAWALS.may_load(deps.storage)?.unwrap_or_default().amount,
+            })
+        }
+        QueryMsg::ExemptRelayers { start_after, limit } => {
+            let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+            let start_addr = start_after.map(|s| deps.api.addr_validate(&s)).transpose()?;
+            let start = start_addr.as_ref().map(Bound::exclusive);
+            let relayers = EXEMPT_RELAYERS
+                .keys(deps.storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|relayer| relayer.to_string()))
+                .collect::<StdResult<Vec<_>>>()?;
+            to_json_binary(&ExemptRelayersResponse { relayers })
+        }
+        QueryMsg::HoldersAt { height, start_after, limit } => {
+            to_json_binary(&query_holders_at(deps, env, height, start_after, limit)?)
+        }
+        QueryMsg::DepositStatus { deposit_id } => to_json_binary(&DepositStatusResponse {
+            processed_at_height: PROCESSED_DEPOSITS.may_load(deps.storage, &deposit_id)?,
+            deposit_id,
+        }),
+        QueryMsg::ExportState { section, start_after, limit } => {
+            to_json_binary(&export::query_export_state(deps, section, start_after, limit)?)
+        }
+        QueryMsg::StateRoot {} => to_json_binary(&export::query_state_root(deps)?),
+        #[cfg(feature = "debug")]
+        QueryMsg::Debug(msg) => crate::debug::query(deps, msg),
+    }
+}
+
+fn query_holders_at(
+    deps: Deps,
+    env: Env,
+    height: u64,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<HoldersAtResponse> {
+    let since = SNAPSHOTS_SINCE
+        .may_load(deps.storage)?
+        .map_or(u64::MAX, |since| since.max(env.block.height.saturating_sub(SNAPSHOT_RETENTION_BLOCKS)));
+    if height < since || height > env.block.height {
+        return Err(StdError::generic_err(format!(
+            "height {} outside snapshot range {}..={}",
+            height, since, env.block.height
+        )));
+    }
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_addr = start_after.map(|s| deps.api.addr_validate(&s)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+    // Fetch one extra account to tell whether another page follows
+    let mut accounts = cw20_base_state::BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit + 1)
+        .collect::<StdResult<Vec<_>>>()?;
+    let next_start_after = if accounts.len() > limit {
+        accounts.truncate(limit);
+        accounts.last().map(|(address, _)| address.to_string())
+    } else {
+        None
+    };
+    let mut holders = vec![];
+    for (address, current) in accounts {
+        // The first checkpoint at or after `height` holds the balance the account opened that block with
+        let amount = BALANCE_CHECKPOINTS
+            .prefix(&address)
+            .range(deps.storage, Some(Bound::inclusive(height)), None, Order::Ascending)
+            .next()
+            .transpose()?
+            .map_or(current, |(_, balance)| balance);
+        if !amount.is_zero() {
+            holders.push(Cw20Coin { address: address.to_string(), amount });
+        }
+    }
+    Ok(HoldersAtResponse { height, holders, next_start_after })
+}
+
+#[entry_point]
+pub fn migrate(
+    deps: DepsMut,
+    env: Env,
+    _msg: Binary,
+) -> Result<Response, ContractError> {
+    let old = get_contract_version(deps.storage)
+        .map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
+    
+    // Instances created before instantiate claimed cw2 still carry cw20-base's name; record it
+    // as the embedded base version on the way through
+    if old.contract == CW20_BASE_CONTRACT_NAME {
+        CW20_BASE_VERSION.save(deps.storage, &old)?;
+    } else if old.contract != CONTRACT_NAME {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "wrong contract: expected {} or {}, got {}",
+            CONTRACT_NAME, CW20_BASE_CONTRACT_NAME, old.contract
+        ))));
+    }
+
+    // Balance history starts with the first upgrade that tracks it
+    if !SNAPSHOTS_SINCE.exists(deps.storage) {
+        SNAPSHOTS_SINCE.save(deps.storage, &env.block.height)?;
+    }
+    // Checkpoints written before the height index existed are indexed so they can be pruned
+    if CHECKPOINT_HEIGHTS.is_empty(deps.storage) {
+        let checkpoints = BALANCE_CHECKPOINTS
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (addr, height) in checkpoints {
+            CHECKPOINT_HEIGHTS.save(deps.storage, (height, &addr), &())?;
+        }
+    }
+    // Status counters start from the maps as they stand; saves and removes keep them after this
+    if !FROZEN_ACCOUNT_COUNT.exists(deps.storage) {
+        let frozen = FROZEN_ACCOUNTS.keys(deps.storage, None, None, Order::Ascending).count() as u64;
+        FROZEN_ACCOUNT_COUNT.save(deps.storage, &frozen)?;
+    }
+    if !GUARDED_ACCOUNT_COUNT.exists(deps.storage) {
+        let guarded = TRANSFER_GUARDS.keys(deps.storage, None, None, Order::Ascending).count() as u64;
+        GUARDED_ACCOUNT_COUNT.save(deps.storage, &guarded)?;
+    }
+    if !PENDING_WITHDRAWAL_COUNT.exists(deps.storage) {
+        let pending = PENDING_WITHDRAWALS.keys(deps.storage, None, None, Order::Ascending).count() as u64;
+        PENDING_WITHDRAWAL_COUNT.save(deps.storage, &pending)?;
+    }
+    // Route history likewise, from the upgrade height: an existing instance cannot recover when
+    // its stored route took effect, so history does not claim anything earlier
+    if BRIDGE_INFO_HISTORY.is_empty(deps.storage) {
+        let route = BRIDGE_INFO.load(deps.storage)?;
+        BRIDGE_INFO_HISTORY.save(deps.storage, env.block.height, &route)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)
+        .map_err(|e| ContractError::Std(StdError::generic_err(e.to_string())))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_contract", old.contract)
+        .add_attribute("from_version", old.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+/// Stores the bridge route and appends it to the history read by `BridgeInfoHistory`. Every
+/// write of `BRIDGE_INFO` goes through here.
+fn save_bridge_info(storage: &mut dyn Storage, env: &Env, bridge_info: &BridgeInfo) -> StdResult<()> {
+    BRIDGE_INFO.save(storage, bridge_info)?;
+    BRIDGE_INFO_HISTORY.save(storage, env.block.height, bridge_info)
+}
+
+fn query_token_info(deps: Deps, env: Env) -> StdResult<crate::msg::TokenInfoResponse> {
+    let base_bin = cw20_base_contract::query(deps, env, cw20_base_msg::QueryMsg::TokenInfo {})?;
+    let mut base: cw20::TokenInfoResponse = cosmwasm_std::from_json(base_bin)?;
+    if let Some(override_md) = TOKEN_METADATA.may_load(deps.storage)? {
+        base.name = override_md.name;
+        base.symbol = override_md.symbol;
+        base.decimals = override_md.decimals;
+    }
+    Ok(crate::msg::TokenInfoResponse {
+        name: base.name,
+        symbol: base.symbol,
+        decimals: base.decimals,
+        total_supply: base.total_supply,
+    })
+}
+
+fn query_bridge_info_history(deps: Deps) -> StdResult<BridgeInfoHistoryResponse> {
+    let entries = BRIDGE_INFO_HISTORY
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let routes = entries
+        .iter()
+        .enumerate()
+        .map(|(i, (from_height, route))| BridgeRouteEntry {
+            chain_id: route.chain_id.clone(),
+            contract_address: route.contract_address.to_string(),
+            from_height: *from_height,
+            until_height: entries.get(i + 1).map(|(next, _)| *next),
+        })
+        .collect();
+    Ok(BridgeInfoHistoryResponse { routes })
+}
+
+fn query_bridge_info(deps: Deps) -> StdResult<BridgeInfoResponse> {
+    let info = BRIDGE_INFO.load(deps.storage)?;
+    Ok(BridgeInfoResponse {
+        chain_id: info.chain_id,
+        contract_address: info.contract_address.into(),
+    })
+}
+
+fn query_spender_activity(deps: Deps, owner: String, spender: String) -> StdResult<SpenderActivityResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let activity = SPENDER_ACTIVITY
+        .may_load(deps.storage, (&owner_addr, &spender_addr))?
+        .unwrap_or_default();
+    let remaining_allowance = cw20_base_state::ALLOWANCES
+        .may_load(deps.storage, (&owner_addr, &spender_addr))?
+        .map(|a| a.allowance)
+        .unwrap_or_default();
+    Ok(SpenderActivityResponse {
+        owner,
+        spender,
+        total_spent: activity.total_spent,
+        spend_count: activity.spend_count,
+        last_spend_height: activity.last_spend_height,
+        remaining_allowance,
+    })
+}
+
+fn session_response(env: &Env, session_key: String, session: Session) -> SessionResponse {
+    SessionResponse {
+        session_key,
+        remaining: session.remaining,
+        expires_at_height: session.expires_at_height,
+        expired: env.block.height >= session.expires_at_height,
+    }
+}
+
+fn query_session(deps: Deps, env: Env, owner: String, session_key: String) -> StdResult<SessionResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let key_addr = deps.api.addr_validate(&session_key)?;
+    let session = SESSIONS
+        .may_load(deps.storage, (&owner_addr, &key_addr))?
+        .unwrap_or(Session { remaining: Uint128::zero(), expires_at_height: 0 });
+    Ok(session_response(&env, session_key, session))
+}
+
+fn query_spend_alerts(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SpendAlertsResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let alerts = SPEND_ALERTS
+        .prefix(&owner_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, alert)| SpendAlertEntry { id, alert }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SpendAlertsResponse {
+        threshold: SPEND_ALERT_THRESHOLDS.may_load(deps.storage, &owner_addr)?,
+        alerts,
+    })
+}
+
+fn query_sessions(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SessionsResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_addr = start_after.map(|s| deps.api.addr_validate(&s)).transpose()?;
+    let start = start_addr.as_ref().map(Bound::exclusive);
+    let sessions = SESSIONS
+        .prefix(&owner_addr)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(key, session)| session_response(&env, key.to_string(), session)))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SessionsResponse { sessions })
+}
+
+fn query_transfer_guard(deps: Deps, env: Env, address: String) -> StdResult<TransferGuardResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let guard = TRANSFER_GUARDS.may_load(deps.storage, &addr)?;
+    let spent_today = DAILY_OUTFLOW
+        .may_load(deps.storage, &addr)?
+        .filter(|outflow| outflow.day == current_day(&env))
+        .map(|outflow| outflow.amount)
+        .unwrap_or_default();
+    Ok(TransferGuardResponse {
+        max_per_tx: guard.as_ref().and_then(|g| g.max_per_tx),
+        daily_max: guard.as_ref().and_then(|g| g.daily_max),
+        spent_today,
+    })
+}
+
+fn query_supply_breakdown(deps: Deps) -> StdResult<SupplyBreakdownResponse> {
+    let total_supply = cw20_base_state::TOKEN_INFO.load(deps.storage)?.total_supply;
+    let escrowed_pending_withdrawal = PENDING_WITHDRAWAL_SUPPLY
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    // Frozen accounts are an admin-curated set, so iterating it is bounded
+    let mut frozen = Uint128::zero();
+    for addr in FROZEN_ACCOUNTS.keys(deps.storage, None, None, Order::Ascending) {
+        let addr = addr?;
+        frozen += cw20_base_state::BALANCES
+            .may_load(deps.storage, &addr)?
+            .unwrap_or_default();
+    }
+
+    let circulating = total_supply
+        .saturating_sub(escrowed_pending_withdrawal)
+        .saturating_sub(frozen);
+    Ok(SupplyBreakdownResponse {
+        total_supply,
+        circulating,
+        escrowed_pending_withdrawal,
+        frozen,
+    })
+}
+
+fn query_reconcile(deps: Deps) -> StdResult<ReconcileResponse> {
+    let total_supply = cw20_base_state::TOKEN_INFO.load(deps.storage)?.total_supply;
+    let escrowed_pending_withdrawal = PENDING_WITHDRAWAL_SUPPLY
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+
+    let bridge_info = BRIDGE_INFO.load(deps.storage)?;
+    let request = QueryBridgeLedgerBalanceRequest {
+        chain_id: bridge_info.chain_id,
+        contract_address: bridge_info.contract_address.into(),
+    };
+    let (chain_bridged_balance, chain_error) =
+        match query_proto::<_, QueryBridgeLedgerBalanceResponse>(deps, proto::BRIDGE_LEDGER_BALANCE_PATH, &request) {
+            Ok(ledger) if ledger.found => (Some(ledger.amount.parse::<Uint128>()?), None),
+            Ok(_) => (None, Some("token not found in the bridge ledger".to_string())),
+            Err(err) => (None, Some(err.to_string())),
+        };
+    let delta = chain_bridged_balance
+        .map(|chain| -> StdResult<Int128> {
+            Ok(Int128::try_from(total_supply)?.checked_sub(Int128::try_from(chain)?)?)
+        })
+        .transpose()?;
+
+    Ok(ReconcileResponse {
+        total_supply,
+        chain_bridged_balance,
+        delta,
+        escrowed_pending_withdrawal,
+        chain_error,
+    })
+}
+
+fn query_audit_log(deps: Deps, start_after: Option<u64>, limit: Option<u32>) -> StdResult<AuditLogResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let entries = AUDIT_LOG
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AuditLogResponse { entries })
+}
+
+fn query_circuit_breaker(deps: Deps, env: Env) -> StdResult<CircuitBreakerResponse> {
+    let breaker = CIRCUIT_BREAKER.may_load(deps.storage)?;
+    let window = breaker.as_ref().map(|breaker| env.block.height / breaker.window_blocks);
+    let flows = [FLOW_MINT, FLOW_WITHDRAW]
+        .into_iter()
+        .map(|flow| {
+            let volume = match window {
+                Some(window) => FLOW_VOLUME.may_load(deps.storage, (flow, window))?.unwrap_or_default(),
+                None => Uint128::zero(),
+            };
+            Ok(FlowStatus {
+                flow: flow.to_string(),
+                volume,
+                halted_at_height: HALTED_FLOWS.may_load(deps.storage, flow)?,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(CircuitBreakerResponse { breaker, flows })
+}
+
+fn query_can_withdraw(
+    deps: Deps,
+    env: Env,
+    address: String,
+    amount: Uint128,
+    chain_id: String,
+    destination_address: Option<String>,
+    payload: Option<String>,
+) -> StdResult<CanWithdrawResponse> {
+    let addr = deps.api.addr_validate(&address)?;
+    let travel_rule_required = matches!(TRAVEL_RULE.may_load(deps.storage)?, Some(rule) if amount >= rule.threshold);
+    // Memo-carrying and exempt-relayer withdrawals are never batched
+    let batched = match WITHDRAWAL_BATCHING.may_load(deps.storage)? {
+        Some(batching) => {
+            amount < batching.threshold
+                && !travel_rule_required
+                && payload.is_none()
+                && !EXEMPT_RELAYERS.has(deps.storage, &addr)
+        }
+        None => false,
+    };
+    let checked = check_withdraw(deps, &env, &addr, amount, &chain_id, destination_address.as_deref(), payload.as_deref());
+    let (failed_check, reason) = match checked {
+        Ok(()) => (None, None),
+        Err((check, err)) => (Some(check.to_string()), Some(err.to_string())),
+    };
+    Ok(CanWithdrawResponse {
+        can_withdraw: failed_check.is_none(),
+        failed_check,
+        reason,
+        travel_rule_required,
+        batched,
+        trips_circuit_breaker: flow_would_trip(deps.storage, &env, FLOW_WITHDRAW, amount)?,
+    })
+}
+
+/// Withdraw's checks in execution order, without writing anything; an error carries the label
+/// of the check that failed. Without chain metadata the precision check passes, as in Withdraw.
+fn check_withdraw(
+    deps: Deps,
+    env: &Env,
+    addr: &Addr,
+    amount: Uint128,
+    chain_id: &str,
+    destination_address: Option<&str>,
+    payload: Option<&str>,
+) -> Result<(), (&'static str, ContractError)> {
+    let at = |check: &'static str| move |err: ContractError| (check, err);
+    ensure_not_frozen(deps, addr.as_str()).map_err(at("frozen"))?;
+    if !EXEMPT_RELAYERS.has(deps.storage, addr) {
+        check_transfer_guard(deps, env, addr, amount).map_err(at("transfer_guard"))?;
+    }
+    check_lockup(deps, env, addr, amount).map_err(at("lockup"))?;
+    if amount.is_zero() {
+        return Err(("amount", ContractError::InsufficientFunds { balance: 0, required: 1 }));
+    }
+    ensure_flow_open(deps.storage, FLOW_WITHDRAW).map_err(at("halted"))?;
+
+    let bridge_info = BRIDGE_INFO.load(deps.storage).map_err(ContractError::from).map_err(at("destination_chain"))?;
+    if chain_id != bridge_info.chain_id {
+        return Err((
+            "destination_chain",
+            ContractError::UnsupportedChain { chain_id: chain_id.to_string(), supported: bridge_info.chain_id },
+        ));
+    }
+    if let Some(destination) = destination_address {
+        let destination = ExternalAddress::parse(destination).map_err(ContractError::from).map_err(at("destination"))?;
+        let irreversible = irreversible_destination(deps.storage, &destination)
+            .map_err(ContractError::from)
+            .map_err(at("blocked_destination"))?;
+        if let Some(reason) = irreversible {
+            return Err(("blocked_destination", ContractError::IrreversibleDestination { address: destination.into(), reason }));
+        }
+        let (sanctioned, _) = check_sanctioned(deps, env, &destination).map_err(at("sanctioned_destination"))?;
+        if sanctioned {
+            return Err(("sanctioned_destination", ContractError::SanctionedDestination { address: destination.into() }));
+        }
+    }
+    check_withdrawal_payload(deps.storage, &bridge_info.chain_id, payload).map_err(at("payload"))?;
+
+    let request = QueryBridgeTokenMetadataRequest {
+        chain_id: bridge_info.chain_id,
+        contract_address: bridge_info.contract_address.into(),
+    };
+    let metadata = query_proto::<_, QueryBridgeTokenMetadataResponse>(deps, proto::BRIDGE_TOKEN_METADATA_PATH, &request)
+        .ok()
+        .filter(|metadata| metadata.found);
+    if let Some(metadata) = metadata {
+        let local_decimals = cw20_base_state::TOKEN_INFO
+            .load(deps.storage)
+            .map_err(ContractError::from)
+            .map_err(at("precision"))?
+            .decimals;
+        check_bridgeable_amount(amount, local_decimals, metadata.decimals).map_err(at("precision"))?;
+    }
+
+    let balance = cw20_base_state::BALANCES
+        .may_load(deps.storage, addr)
+        .map_err(ContractError::from)
+        .map_err(at("balance"))?
+        .unwrap_or_default();
+    if amount > balance {
+        return Err(("balance", ContractError::InsufficientFunds { balance: balance.u128(), required: amount.u128() }));
+    }
+    Ok(())
+}
+
+fn query_status(deps: Deps, env: Env) -> StdResult<StatusResponse> {
+    let token_info = cw20_base_state::TOKEN_INFO.load(deps.storage)?;
+    let minter = token_info.mint.map(|m| MinterResponse {
+        minter: m.minter.to_string(),
+        cap: m.cap,
+    });
+    Ok(StatusResponse {
+        bridge_info: query_bridge_info(deps)?,
+        creator: CREATOR.load(deps.storage)?.to_string(),
+        admin: ADMIN.load(deps.storage)?.to_string(),
+        minter,
+        total_supply: token_info.total_supply,
+        pending_withdrawal_supply: PENDING_WITHDRAWAL_SUPPLY
+            .may_load(deps.storage)?
+            .unwrap_or_default(),
+        frozen_accounts: FROZEN_ACCOUNT_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        guarded_accounts: GUARDED_ACCOUNT_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        travel_rule_threshold: TRAVEL_RULE.may_load(deps.storage)?.map(|r| r.threshold),
+        withdrawal_batching_threshold: WITHDRAWAL_BATCHING.may_load(deps.storage)?.map(|b| b.threshold),
+        priority_fee_collector: PRIORITY_FEES.may_load(deps.storage)?.map(|f| f.fee_collector.to_string()),
+        pending_withdrawals: PENDING_WITHDRAWAL_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        circuit_breaker: query_circuit_breaker(deps, env)?,
+    })
+}
+
+fn query_manifest(deps: Deps, env: Env) -> StdResult<ManifestResponse> {
+    let address = env.contract.address.to_string();
+    let token = query_token_info(deps, env)?;
+    let mint = cw20_base_state::TOKEN_INFO.load(deps.storage)?.mint;
+    Ok(ManifestResponse {
+        address,
+        name: token.name,
+        symbol: token.symbol,
+        decimals: token.decimals,
+        version: VersionResponse {
+            contract: get_contract_version(deps.storage)?,
+            cw20_base: CW20_BASE_VERSION.may_load(deps.storage)?,
+        },
+        bridge: query_bridge_info(deps)?,
+        bridge_routes: query_bridge_info_history(deps)?.routes,
+        roles: ManifestRoles {
+            admin: ADMIN.load(deps.storage)?.to_string(),
+            creator: CREATOR.load(deps.storage)?.to_string(),
+            minter: mint.as_ref().map(|m| m.minter.to_string()),
+        },
+        limits: ManifestLimits {
+            mint_cap: mint.and_then(|m| m.cap),
+            circuit_breaker: CIRCUIT_BREAKER.may_load(deps.storage)?,
+            withdrawal_batch_threshold: WITHDRAWAL_BATCHING.may_load(deps.storage)?.map(|b| b.threshold),
+            travel_rule_threshold: TRAVEL_RULE.may_load(deps.storage)?.map(|r| r.threshold),
+            lockup_registry: LOCKUP_REGISTRY.may_load(deps.storage)?.map(|r| r.contract.to_string()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env, MockApi};
+
+    use crate::msg::{StateEntry, StateRootResponse, StateSection};
+
+    const EXTERNAL_CONTRACT: &str = "0x1111111111111111111111111111111111111111";
+
+    /// Explicit metadata so no chain query is needed; `owner` starts with 1000
+    fn instantiate_msg(api: &MockApi) -> InstantiateMsg {
+        InstantiateMsg {
+            chain_id: "ethereum".to_string(),
+            contract_address: EXTERNAL_CONTRACT.to_string(),
+            initial_balances: vec![Cw20Coin { address: api.addr_make("owner").to_string(), amount: Uint128::new(1_000) }],
+            mint: Some(MinterResponse { minter: api.addr_make("creator").to_string(), cap: None }),
+            marketing: None,
+            admin: Some(api.addr_make("admin").to_string()),
+            salt: None,
+            name: Some("Wrapped Token".to_string()),
+            symbol: Some("WTKN".to_string()),
+            decimals: Some(6),
+        }
+    }
+
+    fn setup(deps: DepsMut, api: &MockApi) {
+        instantiate(deps, mock_env(), message_info(&api.addr_make("creator"), &[]), instantiate_msg(api)).unwrap();
+    }
+
+    fn balance(deps: Deps, address: &Addr) -> Uint128 {
+        cw20_base_state::BALANCES.may_load(deps.storage, address).unwrap().unwrap_or_default()
+    }
+
+    /// Answers the configured gRPC paths (a module error when the result is `Err`) and reports
+    /// any other path as unsupported, like wasmd; everything else goes to the mock querier
+    struct GrpcQuerier {
+        base: cosmwasm_std::testing::MockQuerier,
+        routes: std::collections::HashMap<String, Result<Vec<u8>, String>>,
+    }
+
+    impl cosmwasm_std::Querier for GrpcQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> cosmwasm_std::QuerierResult {
+            use cosmwasm_std::{ContractResult, SystemError, SystemResult};
+            match cosmwasm_std::from_json::<cosmwasm_std::QueryRequest<cosmwasm_std::Empty>>(bin_request) {
+                Ok(cosmwasm_std::QueryRequest::Grpc(query)) => match self.routes.get(&query.path) {
+                    Some(Ok(bytes)) => SystemResult::Ok(ContractResult::Ok(Binary::from(bytes.as_slice()))),
+                    Some(Err(message)) => SystemResult::Ok(ContractResult::Err(message.clone())),
+                    None => SystemResult::Err(SystemError::UnsupportedRequest { kind: query.path }),
+                },
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    fn grpc_dependencies(
+        routes: &[(&str, Result<Vec<u8>, String>)],
+    ) -> cosmwasm_std::OwnedDeps<cosmwasm_std::testing::MockStorage, MockApi, GrpcQuerier> {
+        let mock = mock_dependencies();
+        cosmwasm_std::OwnedDeps {
+            storage: mock.storage,
+            api: mock.api,
+            querier: GrpcQuerier {
+                base: mock.querier,
+                routes: routes.iter().map(|(path, result)| (path.to_string(), result.clone())).collect(),
+            },
+            custom_query_type: std::marker::PhantomData,
+        }
+    }
+
+    fn token_metadata(decimals: u32) -> Vec<u8> {
+        let metadata = proto::QueryBridgeTokenMetadataResponse {
+            name: "Tether".to_string(),
+            symbol: "USDT".to_string(),
+            decimals,
+            found: true,
+        };
+        metadata.encode_to_vec()
+    }
+
+    #[test]
+    fn test_pull_and_notify_names_the_spender() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let spender = api.addr_make("spender");
+        let target = api.addr_make("target");
+
+        let approve = ExecuteMsg::IncreaseAllowance { spender: spender.to_string(), amount: Uint128::new(500), expires: None };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), approve).unwrap();
+
+        let pull = |amount: u128| ExecuteMsg::PullAndNotify {
+            owner: owner.to_string(),
+            amount: Uint128::new(amount),
+            contract: target.to_string(),
+            payload: Binary::from(b"{\"buy\":{}}".as_slice()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), pull(300)).unwrap();
+        let expected = cw20::Cw20ReceiveMsg {
+            sender: spender.to_string(),
+            amount: Uint128::new(300),
+            msg: Binary::from(b"{\"buy\":{}}".as_slice()),
+        }
+        .into_cosmos_msg(target.clone())
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, expected);
+        assert_eq!(balance(deps.as_ref(), &owner), Uint128::new(700));
+        assert_eq!(balance(deps.as_ref(), &target), Uint128::new(300));
+
+        // Nothing beyond the remaining allowance can be pulled, and nothing without one
+        let err = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), pull(201)).unwrap_err();
+        assert!(matches!(err, ContractError::Std(StdError::Overflow { .. })));
+        let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("stranger"), &[]), pull(1)).unwrap_err();
+        assert!(matches!(err, ContractError::NoAllowance {}));
+        assert_eq!(balance(deps.as_ref(), &target), Uint128::new(300));
+    }
+
+    #[test]
+    fn test_transfer_from_batch_deducts_the_allowance_once() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let spender = api.addr_make("spender");
+        let (alice, bob) = (api.addr_make("alice"), api.addr_make("bob"));
+        let approve = ExecuteMsg::IncreaseAllowance { spender: spender.to_string(), amount: Uint128::new(500), expires: None };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), approve).unwrap();
+        let batch = |amounts: &[(&Addr, u128)]| ExecuteMsg::TransferFromBatch {
+            owner: owner.to_string(),
+            recipients: amounts.iter().map(|(a, n)| Cw20Coin { address: a.to_string(), amount: Uint128::new(*n) }).collect(),
+        };
+
+        execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch(&[(&alice, 100), (&bob, 250)])).unwrap();
+        assert_eq!(balance(deps.as_ref(), &owner), Uint128::new(650));
+        assert_eq!(balance(deps.as_ref(), &alice), Uint128::new(100));
+        assert_eq!(balance(deps.as_ref(), &bob), Uint128::new(250));
+        let allowance = cw20_base::allowances::query_allowance(deps.as_ref(), owner.to_string(), spender.to_string()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(150));
+
+        // A zero item, an overdrawn allowance or an oversized batch fails as a whole
+        let err = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch(&[(&alice, 10), (&bob, 0)])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidZeroAmount {}));
+        let err = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch(&[])).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidZeroAmount {}));
+        execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch(&[(&alice, 100), (&bob, 51)])).unwrap_err();
+        let oversized = vec![(&alice, 1); MAX_TRANSFER_BATCH + 1];
+        let err = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch(&oversized)).unwrap_err();
+        assert!(matches!(err, ContractError::BatchTooLarge { .. }));
+        assert_eq!(balance(deps.as_ref(), &alice), Uint128::new(100));
+        assert_eq!(balance(deps.as_ref(), &owner), Uint128::new(650));
+    }
+
+    #[test]
+    fn test_self_transfers_are_rejected_or_returned() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let spender = api.addr_make("spender");
+        let contract = mock_env().contract.address;
+        let to_contract = |amount: u128| ExecuteMsg::Transfer { recipient: contract.to_string(), amount: Uint128::new(amount) };
+
+        // Rejected by default, batches included
+        let err = execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), to_contract(100)).unwrap_err();
+        assert!(matches!(err, ContractError::TransferToSelf {}));
+        let approve = ExecuteMsg::IncreaseAllowance { spender: spender.to_string(), amount: Uint128::new(300), expires: None };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), approve).unwrap();
+        let batch = ExecuteMsg::TransferFromBatch {
+            owner: owner.to_string(),
+            recipients: vec![
+                Cw20Coin { address: api.addr_make("alice").to_string(), amount: Uint128::new(10) },
+                Cw20Coin { address: contract.to_string(), amount: Uint128::new(20) },
+            ],
+        };
+        let err = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TransferToSelf {}));
+
+        // Under Return the tokens land back with the account they came from
+        let policy = ExecuteMsg::SetSelfTransferPolicy { policy: SelfTransferPolicy::Return };
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("admin"), &[]), policy).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), to_contract(100)).unwrap();
+        let returned = res.events.iter().find(|event| event.ty == "self_transfer_returned").unwrap();
+        assert!(returned.attributes.iter().any(|a| a.key == "account" && a.value == owner.as_str()));
+        assert!(returned.attributes.iter().any(|a| a.key == "amount" && a.value == "100"));
+        assert_eq!(balance(deps.as_ref(), &owner), Uint128::new(1_000));
+        assert_eq!(balance(deps.as_ref(), &contract), Uint128::zero());
+
+        // A spender's batch pays the other recipients and returns the rest to the owner
+        execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), batch).unwrap();
+        assert_eq!(balance(deps.as_ref(), &owner), Uint128::new(990));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("alice")), Uint128::new(10));
+        assert_eq!(balance(deps.as_ref(), &contract), Uint128::zero());
+    }
+
+    #[test]
+    fn test_admin_recovers_stuck_balance() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let contract = mock_env().contract.address;
+        let alice = api.addr_make("alice");
+        // Stranded by a transfer made before the self-transfer check existed
+        cw20_base_state::BALANCES.save(&mut deps.storage, &contract, &Uint128::new(40)).unwrap();
+        let recover = |recipient: &Addr, amount: u128| ExecuteMsg::RecoverStuckBalance { recipient: recipient.to_string(), amount: Uint128::new(amount) };
+
+        let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), recover(&alice, 40)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), recover(&contract, 40)).unwrap_err();
+        assert!(matches!(err, ContractError::TransferToSelf {}));
+        execute(deps.as_mut(), mock_env(), admin.clone(), recover(&alice, 41)).unwrap_err();
+
+        execute(deps.as_mut(), mock_env(), admin, recover(&alice, 40)).unwrap();
+        assert_eq!(balance(deps.as_ref(), &alice), Uint128::new(40));
+        assert_eq!(balance(deps.as_ref(), &contract), Uint128::zero());
+    }
+
+    fn committed_root(deps: Deps) -> Option<StateRootResponse> {
+        crate::export::query_state_root(deps).unwrap()
+    }
+
+    #[test]
+    fn test_state_root_commits_across_calls() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let anyone = message_info(&api.addr_make("anyone"), &[]);
+        let commit = |limit| ExecuteMsg::CommitStateRoot { limit: Some(limit) };
+        assert_eq!(committed_root(deps.as_ref()), None);
+
+        execute(deps.as_mut(), mock_env(), anyone.clone(), commit(100)).unwrap();
+        let whole = committed_root(deps.as_ref()).unwrap();
+        assert!(whole.entries > 2);
+
+        // One entry per call gives the same root, and nothing is stored until the last call
+        let mut calls = 0;
+        while STATE_ROOT_PROGRESS.exists(&deps.storage) || calls == 0 {
+            execute(deps.as_mut(), mock_env(), anyone.clone(), commit(1)).unwrap();
+            calls += 1;
+        }
+        assert_eq!(committed_root(deps.as_ref()), Some(whole.clone()));
+        assert!(calls as u64 >= whole.entries);
+
+        // A transfer in the middle drops the partial commitment
+        execute(deps.as_mut(), mock_env(), anyone.clone(), commit(1)).unwrap();
+        assert!(STATE_ROOT_PROGRESS.exists(&deps.storage));
+        let transfer = ExecuteMsg::Transfer { recipient: api.addr_make("anyone").to_string(), amount: Uint128::new(10) };
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("owner"), &[]), transfer).unwrap();
+        assert!(!STATE_ROOT_PROGRESS.exists(&deps.storage));
+
+        execute(deps.as_mut(), mock_env(), anyone, commit(100)).unwrap();
+        let after = committed_root(deps.as_ref()).unwrap();
+        assert_eq!(after.entries, whole.entries + 1);
+        assert_ne!(after.root, whole.root);
+    }
+
+    #[test]
+    fn test_status_counts_follow_freezes_and_guards() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let freeze = |name: &str| ExecuteMsg::FreezeAccount { address: api.addr_make(name).to_string() };
+        let unfreeze = |name: &str| ExecuteMsg::UnfreezeAccount { address: api.addr_make(name).to_string() };
+
+        // Freezing twice or unfreezing an account that is not frozen leaves the count alone
+        execute(deps.as_mut(), mock_env(), admin.clone(), freeze("alice")).unwrap();
+        execute(deps.as_mut(), mock_env(), admin.clone(), freeze("alice")).unwrap();
+        execute(deps.as_mut(), mock_env(), admin.clone(), freeze("bob")).unwrap();
+        execute(deps.as_mut(), mock_env(), admin.clone(), unfreeze("carol")).unwrap();
+        execute(deps.as_mut(), mock_env(), admin.clone(), unfreeze("bob")).unwrap();
+
+        let guard = |max: Option<u128>| ExecuteMsg::SetTransferGuard { max_per_tx: max.map(Uint128::new), daily_max: None };
+        execute(deps.as_mut(), mock_env(), owner.clone(), guard(Some(10))).unwrap();
+        execute(deps.as_mut(), mock_env(), owner.clone(), guard(Some(20))).unwrap();
+        let status = query_status(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!((status.frozen_accounts, status.guarded_accounts), (1, 1));
+
+        execute(deps.as_mut(), mock_env(), owner, guard(None)).unwrap();
+        execute(deps.as_mut(), mock_env(), admin, unfreeze("alice")).unwrap();
+        let status = query_status(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!((status.frozen_accounts, status.guarded_accounts), (0, 0));
+    }
+
+    #[test]
+    fn test_status_reports_fees_limits_and_queued_withdrawals() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let status = query_status(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!((status.withdrawal_batching_threshold, status.priority_fee_collector, status.pending_withdrawals), (None, None, 0));
+        assert_eq!(status.circuit_breaker.breaker, None);
+
+        let breaker = CircuitBreaker {
+            window_blocks: 100,
+            mint: None,
+            withdraw: Some(VolumeLimit { max_amount: Some(Uint128::new(150)), max_supply_bps: None }),
+        };
+        for msg in [
+            ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(500)) },
+            ExecuteMsg::SetPriorityFees { fee_collector: Some(api.addr_make("collector").to_string()) },
+            ExecuteMsg::SetCircuitBreaker { breaker: Some(breaker.clone()) },
+        ] {
+            execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+        }
+        let withdraw = |amount: u128, digit: &str| ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+            destination_address: EXTERNAL_CONTRACT.replace('1', digit),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        // Two withdrawals to one destination queue as one entry; the third trips the breaker
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(50, "2")).unwrap();
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(50, "2")).unwrap();
+        execute(deps.as_mut(), mock_env(), owner, withdraw(60, "3")).unwrap();
+
+        let status = query_status(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(status.withdrawal_batching_threshold, Some(Uint128::new(500)));
+        assert_eq!(status.priority_fee_collector, Some(api.addr_make("collector").to_string()));
+        assert_eq!(status.pending_withdrawals, 2);
+        assert_eq!(status.circuit_breaker.breaker, Some(breaker));
+        let withdrawals = status.circuit_breaker.flows.iter().find(|flow| flow.flow == FLOW_WITHDRAW).unwrap();
+        assert_eq!(withdrawals.volume, Uint128::new(160));
+        assert_eq!(withdrawals.halted_at_height, Some(mock_env().block.height));
+
+        execute(deps.as_mut(), mock_env(), admin.clone(), ExecuteMsg::ResumeCircuitBreaker {}).unwrap();
+        execute(deps.as_mut(), mock_env(), admin, ExecuteMsg::FlushWithdrawals { limit: Some(1) }).unwrap();
+        assert_eq!(query_status(deps.as_ref(), mock_env()).unwrap().pending_withdrawals, 1);
+    }
+
+    #[test]
+    fn test_session_transfer_spends_the_budget_until_expiry() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let session_key = api.addr_make("session");
+        let recipient = api.addr_make("recipient");
+        let env = mock_env();
+        let grant = ExecuteMsg::GrantSession {
+            session_key: session_key.to_string(),
+            max_amount: Uint128::new(100),
+            expires_at_height: env.block.height + 10,
+        };
+        execute(deps.as_mut(), env.clone(), message_info(&owner, &[]), grant).unwrap();
+        let transfer = |amount: u128| ExecuteMsg::SessionTransfer {
+            owner: owner.to_string(),
+            recipient: recipient.to_string(),
+            amount: Uint128::new(amount),
+        };
+
+        execute(deps.as_mut(), env.clone(), message_info(&session_key, &[]), transfer(60)).unwrap();
+        assert_eq!(balance(deps.as_ref(), &recipient), Uint128::new(60));
+        let err = execute(deps.as_mut(), env.clone(), message_info(&session_key, &[]), transfer(41)).unwrap_err();
+        assert!(matches!(err, ContractError::SessionLimitExceeded { remaining: 40, requested: 41 }));
+        let err = execute(deps.as_mut(), env.clone(), message_info(&api.addr_make("stranger"), &[]), transfer(1)).unwrap_err();
+        assert!(matches!(err, ContractError::SessionNotFound {}));
+
+        let mut later = env.clone();
+        later.block.height += 10;
+        let err = execute(deps.as_mut(), later.clone(), message_info(&session_key, &[]), transfer(10)).unwrap_err();
+        assert!(matches!(err, ContractError::SessionExpired { expires_at_height } if expires_at_height == later.block.height));
+        assert_eq!(balance(deps.as_ref(), &owner), Uint128::new(940));
+        let session = query_session(deps.as_ref(), later.clone(), owner.to_string(), session_key.to_string()).unwrap();
+        assert!(session.expired);
+        assert_eq!(session.remaining, Uint128::new(40));
+
+        // The owner clears the expired session by revoking it
+        let revoke = ExecuteMsg::RevokeSession { session_key: session_key.to_string() };
+        execute(deps.as_mut(), later.clone(), message_info(&owner, &[]), revoke).unwrap();
+        let err = execute(deps.as_mut(), later, message_info(&session_key, &[]), transfer(10)).unwrap_err();
+        assert!(matches!(err, ContractError::SessionNotFound {}));
+    }
+
+    #[test]
+    fn test_holders_at_reads_past_heights_within_retention() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let (owner, alice, bob) = (api.addr_make("owner"), api.addr_make("alice"), api.addr_make("bob"));
+        let start = mock_env().block.height;
+        let at = |offset: u64| {
+            let mut env = mock_env();
+            env.block.height = start + offset;
+            env
+        };
+        let transfer = |recipient: &Addr, amount: u128| ExecuteMsg::Transfer { recipient: recipient.to_string(), amount: Uint128::new(amount) };
+        execute(deps.as_mut(), at(10), message_info(&owner, &[]), transfer(&alice, 300)).unwrap();
+        execute(deps.as_mut(), at(20), message_info(&alice, &[]), transfer(&bob, 100)).unwrap();
+
+        let holders = |deps: Deps, now: u64, height: u64| {
+            query_holders_at(deps, at(now), start + height, None, None).map(|res| {
+                res.holders.into_iter().map(|coin| (coin.address, coin.amount.u128())).collect::<std::collections::BTreeMap<_, _>>()
+            })
+        };
+        let expect = |pairs: &[(&Addr, u128)]| pairs.iter().map(|(addr, amount)| (addr.to_string(), *amount)).collect();
+        assert_eq!(holders(deps.as_ref(), 25, 5).unwrap(), expect(&[(&owner, 1_000)]));
+        assert_eq!(holders(deps.as_ref(), 25, 15).unwrap(), expect(&[(&owner, 700), (&alice, 300)]));
+        assert_eq!(holders(deps.as_ref(), 25, 25).unwrap(), expect(&[(&owner, 700), (&alice, 200), (&bob, 100)]));
+        holders(deps.as_ref(), 25, 26).unwrap_err();
+        query_holders_at(deps.as_ref(), at(25), start - 1, None, None).unwrap_err();
+
+        // Past the retention window the old checkpoints are pruned and their heights refused
+        let later = SNAPSHOT_RETENTION_BLOCKS + 30;
+        execute(deps.as_mut(), at(later), message_info(&bob, &[]), transfer(&alice, 50)).unwrap();
+        assert!(!BALANCE_CHECKPOINTS.has(&deps.storage, (&owner, start + 10)));
+        assert!(!BALANCE_CHECKPOINTS.has(&deps.storage, (&alice, start + 20)));
+        assert!(BALANCE_CHECKPOINTS.has(&deps.storage, (&alice, start + later)));
+        holders(deps.as_ref(), later, 15).unwrap_err();
+        assert_eq!(holders(deps.as_ref(), later, 30).unwrap(), expect(&[(&owner, 700), (&alice, 200), (&bob, 100)]));
+    }
+
+    #[test]
+    fn test_migration_records_the_existing_route_from_the_upgrade() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        // An instance from before route history was tracked
+        BRIDGE_INFO_HISTORY.clear(&mut deps.storage);
+
+        let mut env = mock_env();
+        env.block.height += 1_000;
+        migrate(deps.as_mut(), env.clone(), Binary::default()).unwrap();
+        let routes = query_bridge_info_history(deps.as_ref()).unwrap().routes;
+        assert_eq!(routes.len(), 1);
+        assert_eq!((routes[0].from_height, routes[0].until_height), (env.block.height, None));
+        assert_eq!(routes[0].contract_address, EXTERNAL_CONTRACT);
+    }
+
+    #[test]
+    fn test_update_bridge_info_closes_the_previous_route() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let start = mock_env().block.height;
+        let new_contract = "0x2222222222222222222222222222222222222222";
+        let update = ExecuteMsg::UpdateBridgeInfo { chain_id: "ethereum".to_string(), contract_address: new_contract.to_string() };
+
+        let mut env = mock_env();
+        env.block.height += 50;
+        let err = execute(deps.as_mut(), env.clone(), message_info(&api.addr_make("owner"), &[]), update.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let bad = ExecuteMsg::UpdateBridgeInfo { chain_id: "ethereum".to_string(), contract_address: "not-an-address".to_string() };
+        execute(deps.as_mut(), env.clone(), message_info(&api.addr_make("admin"), &[]), bad).unwrap_err();
+
+        execute(deps.as_mut(), env.clone(), message_info(&api.addr_make("admin"), &[]), update).unwrap();
+        assert_eq!(query_bridge_info(deps.as_ref()).unwrap().contract_address, new_contract);
+        let routes = query_bridge_info_history(deps.as_ref()).unwrap().routes;
+        assert_eq!(routes.len(), 2);
+        assert_eq!((routes[0].from_height, routes[0].until_height), (start, Some(env.block.height)));
+        assert_eq!(routes[0].contract_address, EXTERNAL_CONTRACT);
+        assert_eq!((routes[1].from_height, routes[1].until_height), (env.block.height, None));
+        assert_eq!(routes[1].contract_address, new_contract);
+    }
+
+    #[test]
+    fn test_exempt_relayer_skips_withdrawal_fees_and_limits() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let owner = api.addr_make("owner");
+        let relayer = api.addr_make("relayer");
+        let collector = api.addr_make("collector");
+        let transfer = ExecuteMsg::Transfer { recipient: relayer.to_string(), amount: Uint128::new(500) };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), transfer).unwrap();
+        let breaker = CircuitBreaker {
+            window_blocks: 100,
+            mint: None,
+            withdraw: Some(VolumeLimit { max_amount: Some(Uint128::new(150)), max_supply_bps: None }),
+        };
+        for msg in [
+            ExecuteMsg::SetRelayerExemption { relayer: relayer.to_string(), exempt: true },
+            ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(500)) },
+            ExecuteMsg::SetPriorityFees { fee_collector: Some(collector.to_string()) },
+            ExecuteMsg::SetCircuitBreaker { breaker: Some(breaker) },
+        ] {
+            execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+        }
+        let guard = ExecuteMsg::SetTransferGuard { max_per_tx: None, daily_max: Some(Uint128::new(200)) };
+        execute(deps.as_mut(), mock_env(), message_info(&relayer, &[]), guard.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), guard).unwrap();
+        let withdraw = |amount: u128, priority_fee: Option<u128>| ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: priority_fee.map(Uint128::new),
+            payload: None,
+        };
+
+        // Above its guard and the breaker window, with a fee offered: bridged at once, fee-free
+        let res = execute(deps.as_mut(), mock_env(), message_info(&relayer, &[]), withdraw(250, Some(10))).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert!(res.attributes.iter().any(|attr| attr.key == "priority" && attr.value == "true"));
+        assert_eq!(balance(deps.as_ref(), &relayer), Uint128::new(250));
+        assert_eq!(balance(deps.as_ref(), &collector), Uint128::zero());
+        assert!(!HALTED_FLOWS.has(&deps.storage, FLOW_WITHDRAW));
+
+        // Anyone else is held to their guard, pays the fee and counts toward the breaker
+        let err = execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), withdraw(250, None)).unwrap_err();
+        assert!(matches!(err, ContractError::TransferGuardExceeded { limit: 200, requested: 250 }));
+        let res = execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), withdraw(100, Some(5))).unwrap();
+        assert!(res.attributes.iter().any(|attr| attr.key == "batched"));
+        assert_eq!(balance(deps.as_ref(), &collector), Uint128::new(5));
+        let res = execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), withdraw(60, None)).unwrap();
+        assert!(res.events.iter().any(|event| event.ty == "circuit_breaker_tripped"));
+
+        // A halt still stops the relayer too
+        let err = execute(deps.as_mut(), mock_env(), message_info(&relayer, &[]), withdraw(10, None)).unwrap_err();
+        assert!(matches!(err, ContractError::FlowHalted { .. }));
+    }
+
+    fn decode_withdrawal(msg: &CosmosMsg) -> proto::v2::MsgRequestBridgeWithdrawal {
+        let CosmosMsg::Any(any) = msg else { panic!("not a bridge withdrawal: {msg:?}") };
+        assert_eq!(any.type_url, proto::MSG_REQUEST_BRIDGE_WITHDRAWAL_TYPE_URL);
+        proto::v2::MsgRequestBridgeWithdrawal::decode(any.value.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_flushed_withdrawals_keep_their_senders() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let alice = api.addr_make("alice");
+        let batching = ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(500)) };
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("admin"), &[]), batching).unwrap();
+        let transfer = ExecuteMsg::Transfer { recipient: alice.to_string(), amount: Uint128::new(200) };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), transfer).unwrap();
+        let (shared, own) = (EXTERNAL_CONTRACT.replace('1', "2"), EXTERNAL_CONTRACT.replace('1', "3"));
+        let withdraw = |amount: u128, destination: &str| ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+            destination_address: destination.to_string(),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), withdraw(50, &shared)).unwrap();
+        execute(deps.as_mut(), mock_env(), message_info(&alice, &[]), withdraw(30, &shared)).unwrap();
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), withdraw(20, &own)).unwrap();
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), withdraw(20, &own)).unwrap();
+
+        let flush = ExecuteMsg::FlushWithdrawals { limit: None };
+        let res = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("anyone"), &[]), flush).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        // Several senders: the contract is the user and the memo carries each share
+        let contract = mock_env().contract.address;
+        let first = decode_withdrawal(&res.messages[0].msg);
+        assert_eq!((first.destination_address.as_str(), first.amount.as_str()), (shared.as_str(), "80"));
+        assert_eq!(first.user_address, contract.to_string());
+        #[derive(serde::Deserialize)]
+        struct Memo {
+            senders: Vec<Cw20Coin>,
+        }
+        let mut shares = cosmwasm_std::from_json::<Memo>(&first.memo).unwrap().senders;
+        shares.sort_by(|a, b| a.address.cmp(&b.address));
+        let mut expected = vec![
+            Cw20Coin { address: owner.to_string(), amount: Uint128::new(50) },
+            Cw20Coin { address: alice.to_string(), amount: Uint128::new(30) },
+        ];
+        expected.sort_by(|a, b| a.address.cmp(&b.address));
+        assert_eq!(shares, expected);
+
+        // A single sender is the user, with no memo
+        let second = decode_withdrawal(&res.messages[1].msg);
+        assert_eq!((second.destination_address.as_str(), second.amount.as_str()), (own.as_str(), "40"));
+        assert_eq!((second.user_address.as_str(), second.memo.as_str()), (owner.as_str(), ""));
+
+        let sender_events = res.events.iter().filter(|event| event.ty == "withdrawal_flushed_sender").count();
+        assert_eq!(sender_events, 3);
+        assert!(PENDING_WITHDRAWAL_SENDERS.is_empty(&deps.storage));
+    }
+
+    #[test]
+    fn test_priority_fees_order_the_queue_and_pay_the_collector() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let collector = api.addr_make("collector");
+        let withdraw = |digit: &str, priority_fee: Option<u128>| ExecuteMsg::Withdraw {
+            amount: Uint128::new(10),
+            destination_address: EXTERNAL_CONTRACT.replace('1', digit),
+            travel_rule: None,
+            force: false,
+            priority_fee: priority_fee.map(Uint128::new),
+            payload: None,
+        };
+        let batching = ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(500)) };
+        execute(deps.as_mut(), mock_env(), admin.clone(), batching).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw("2", Some(1))).unwrap_err();
+        assert!(matches!(err, ContractError::PriorityFeesDisabled {}));
+        let fees = ExecuteMsg::SetPriorityFees { fee_collector: Some(collector.to_string()) };
+        execute(deps.as_mut(), mock_env(), admin, fees).unwrap();
+
+        // Fee-less "2" and "3", then "4" paying 3 and "5" paying 2 + 2 in two withdrawals
+        for (digit, fee) in [("2", None), ("3", None), ("4", Some(3)), ("5", Some(2)), ("5", Some(2))] {
+            execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(digit, fee)).unwrap();
+        }
+        assert_eq!(balance(deps.as_ref(), &collector), Uint128::new(7));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("owner")), Uint128::new(1_000 - 50 - 7));
+
+        let flush = |limit: u32| ExecuteMsg::FlushWithdrawals { limit: Some(limit) };
+        let flushed = |res: &Response| -> Vec<String> {
+            res.messages.iter().map(|msg| decode_withdrawal(&msg.msg).destination_address).collect()
+        };
+        let anyone = message_info(&api.addr_make("anyone"), &[]);
+        let res = execute(deps.as_mut(), mock_env(), anyone.clone(), flush(3)).unwrap();
+        let expected: Vec<String> = ["5", "4", "2"].iter().map(|digit| EXTERNAL_CONTRACT.replace('1', digit)).collect();
+        assert_eq!(flushed(&res), expected);
+        // Paying for priority keeps the withdrawal attributed to its sender
+        assert_eq!(decode_withdrawal(&res.messages[0].msg).user_address, api.addr_make("owner").to_string());
+        let res = execute(deps.as_mut(), mock_env(), anyone, flush(3)).unwrap();
+        assert_eq!(flushed(&res), vec![EXTERNAL_CONTRACT.replace('1', "3")]);
+        assert!(WITHDRAWAL_PRIORITY.is_empty(&deps.storage));
+        assert!(PENDING_PRIORITY_FEES.is_empty(&deps.storage));
+        // Fees are not refunded by the flush
+        assert_eq!(balance(deps.as_ref(), &collector), Uint128::new(7));
+    }
+
+    #[test]
+    fn test_supply_breakdown_tracks_queued_withdrawals() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let batching = ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(500)) };
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("admin"), &[]), batching).unwrap();
+
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Uint128::new(100),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("owner"), &[]), withdraw).unwrap();
+        assert!(res.attributes.iter().any(|attr| attr.key == "batched"));
+        let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+        assert_eq!(breakdown.total_supply, Uint128::new(1_000));
+        assert_eq!(breakdown.escrowed_pending_withdrawal, Uint128::new(100));
+        assert_eq!(breakdown.circulating, Uint128::new(900));
+
+        let flush = ExecuteMsg::FlushWithdrawals { limit: None };
+        let res = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("anyone"), &[]), flush).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let breakdown = query_supply_breakdown(deps.as_ref()).unwrap();
+        assert_eq!(breakdown.total_supply, Uint128::new(900));
+        assert_eq!(breakdown.escrowed_pending_withdrawal, Uint128::zero());
+        assert_eq!(breakdown.circulating, Uint128::new(900));
+    }
+
+    fn batch_item_status(res: &Response, deposit: &str) -> String {
+        res.events
+            .iter()
+            .find(|event| event.ty == "mint_batch_item" && event.attributes.iter().any(|a| a.key == "deposit_id" && a.value == deposit))
+            .and_then(|event| event.attributes.iter().find(|a| a.key == "status"))
+            .map(|a| a.value.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_mint_batch_isolates_failing_deposits() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let item = |name: &str, amount: u128, deposit: &str| (api.addr_make(name).to_string(), Uint128::new(amount), deposit.to_string());
+        // Supply is 1000 from setup; the cap leaves room for 500 more
+        sudo(deps.as_mut(), mock_env(), SudoMsg::UpdateMinterCap { new_cap: Some(Uint128::new(1_500)) }).unwrap();
+
+        let mints = vec![
+            item("alice", 100, "d1"),
+            ("not an address".to_string(), Uint128::new(50), "d2".to_string()),
+            item("bob", 450, "d3"),
+            item("carol", 200, "d4"),
+            item("dave", 100, "d1"),
+        ];
+        for sender in ["owner", "admin"] {
+            let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make(sender), &[]), ExecuteMsg::MintBatch { mints: mints.clone() }).unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        let res = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), ExecuteMsg::MintBatch { mints }).unwrap();
+        let statuses: Vec<_> = ["d2", "d3", "d4"].iter().map(|deposit| batch_item_status(&res, deposit)).collect();
+        assert_eq!(batch_item_status(&res, "d1"), "minted");
+        assert_eq!(statuses, ["failed", "failed", "minted"]);
+        // The repeated d1 is reported as a duplicate rather than minted twice
+        assert!(res.events.iter().any(|event| event.ty == "mint_batch_item" && event.attributes.iter().any(|a| a.value == "duplicate")));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("alice")), Uint128::new(100));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("bob")), Uint128::zero());
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("carol")), Uint128::new(200));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("dave")), Uint128::zero());
+        assert_eq!(cw20_base_state::TOKEN_INFO.load(&deps.storage).unwrap().total_supply, Uint128::new(1_300));
+        assert!(!PROCESSED_DEPOSITS.has(&deps.storage, "d3"));
+
+        // Governance can push the same batch through sudo; the failed deposit is retried
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::MintBatch { mints: vec![item("bob", 200, "d3"), item("carol", 200, "d4")] }).unwrap();
+        assert_eq!((batch_item_status(&res, "d3"), batch_item_status(&res, "d4")), ("minted".into(), "duplicate".into()));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("bob")), Uint128::new(200));
+    }
+
+    #[test]
+    fn test_circuit_breaker_halts_withdrawals_relative_to_supply() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let set = |window_blocks: u64, max_supply_bps: Option<u16>| ExecuteMsg::SetCircuitBreaker {
+            breaker: Some(CircuitBreaker {
+                window_blocks,
+                mint: None,
+                withdraw: Some(VolumeLimit { max_amount: None, max_supply_bps }),
+            }),
+        };
+        for invalid in [set(0, Some(1_000)), set(100, None), set(100, Some(0)), set(100, Some(10_001))] {
+            let err = execute(deps.as_mut(), mock_env(), admin.clone(), invalid).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidCircuitBreaker { .. }));
+        }
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), set(100, Some(1_000))).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        // 10% of the 1000 supply per 100 blocks
+        execute(deps.as_mut(), mock_env(), admin.clone(), set(100, Some(1_000))).unwrap();
+
+        let withdraw = |amount: u128| ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(80)).unwrap();
+        assert!(!res.events.iter().any(|event| event.ty == "circuit_breaker_tripped"));
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(30)).unwrap();
+        assert!(res.events.iter().any(|event| event.ty == "circuit_breaker_tripped"));
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(1)).unwrap_err();
+        assert!(matches!(err, ContractError::FlowHalted { .. }));
+
+        // Only the admin or sudo resumes
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), ExecuteMsg::ResumeCircuitBreaker {}).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        sudo(deps.as_mut(), mock_env(), SudoMsg::ResumeCircuitBreaker {}).unwrap();
+        assert!(!HALTED_FLOWS.has(&deps.storage, FLOW_WITHDRAW));
+        let mut next_window = mock_env();
+        next_window.block.height += 100;
+        execute(deps.as_mut(), next_window, owner, withdraw(50)).unwrap();
+    }
+
+    #[test]
+    fn test_circuit_breaker_trips_halts_and_resumes_mints() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let minter = message_info(&api.addr_make("creator"), &[]);
+        let breaker = CircuitBreaker {
+            window_blocks: 100,
+            mint: Some(VolumeLimit { max_amount: Some(Uint128::new(500)), max_supply_bps: None }),
+            withdraw: None,
+        };
+        execute(deps.as_mut(), mock_env(), admin.clone(), ExecuteMsg::SetCircuitBreaker { breaker: Some(breaker) }).unwrap();
+
+        let item = |name: &str, amount: u128, deposit: &str| (api.addr_make(name).to_string(), Uint128::new(amount), deposit.to_string());
+
+        // The item that pushes the window past 500 is minted and trips the breaker; the rest wait
+        let mints = vec![item("alice", 300, "d1"), item("bob", 300, "d2"), item("carol", 100, "d3")];
+        let res = execute(deps.as_mut(), mock_env(), minter.clone(), ExecuteMsg::MintBatch { mints }).unwrap();
+        assert_eq!((batch_item_status(&res, "d1"), batch_item_status(&res, "d2"), batch_item_status(&res, "d3")), ("minted".into(), "minted".into(), "halted".into()));
+        assert!(res.events.iter().any(|event| event.ty == "circuit_breaker_tripped"));
+        assert!(HALTED_FLOWS.has(&deps.storage, FLOW_MINT));
+
+        let mint = ExecuteMsg::Mint { recipient: api.addr_make("carol").to_string(), amount: Uint128::new(1) };
+        let err = execute(deps.as_mut(), mock_env(), minter.clone(), mint).unwrap_err();
+        assert!(matches!(err, ContractError::FlowHalted { .. }));
+
+        // After a resume the halted deposit goes through and the minted ones stay deduplicated
+        execute(deps.as_mut(), mock_env(), admin, ExecuteMsg::ResumeCircuitBreaker {}).unwrap();
+        let mints = vec![item("alice", 300, "d1"), item("carol", 100, "d3")];
+        let res = execute(deps.as_mut(), mock_env(), minter, ExecuteMsg::MintBatch { mints }).unwrap();
+        assert_eq!((batch_item_status(&res, "d1"), batch_item_status(&res, "d3")), ("duplicate".into(), "minted".into()));
+        assert!(!HALTED_FLOWS.has(&deps.storage, FLOW_MINT));
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("carol")), Uint128::new(100));
+    }
+
+    #[test]
+    fn test_allowance_spends_are_attributed_to_the_spender() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let spender = api.addr_make("spender");
+        let approve = ExecuteMsg::IncreaseAllowance { spender: spender.to_string(), amount: Uint128::new(300), expires: None };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), approve).unwrap();
+
+        let transfer_from = |amount: u128| ExecuteMsg::TransferFrom {
+            owner: owner.to_string(),
+            recipient: api.addr_make("alice").to_string(),
+            amount: Uint128::new(amount),
+        };
+        let res = execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), transfer_from(100)).unwrap();
+        let event = res.events.iter().find(|event| event.ty == "allowance_spend").unwrap();
+        let attr = |key: &str| event.attributes.iter().find(|a| a.key == key).map(|a| a.value.clone()).unwrap();
+        assert_eq!((attr("owner"), attr("spender")), (owner.to_string(), spender.to_string()));
+        assert_eq!((attr("amount"), attr("remaining_allowance")), ("100".to_string(), "200".to_string()));
+
+        let mut later = mock_env();
+        later.block.height += 5;
+        let burn_from = ExecuteMsg::BurnFrom { owner: owner.to_string(), amount: Uint128::new(50) };
+        execute(deps.as_mut(), later.clone(), message_info(&spender, &[]), burn_from).unwrap();
+
+        // A spend beyond the allowance fails without counting
+        execute(deps.as_mut(), later.clone(), message_info(&spender, &[]), transfer_from(151)).unwrap_err();
+        let activity = query_spender_activity(deps.as_ref(), owner.to_string(), spender.to_string()).unwrap();
+        assert_eq!((activity.total_spent, activity.spend_count), (Uint128::new(150), 2));
+        assert_eq!((activity.last_spend_height, activity.remaining_allowance), (later.block.height, Uint128::new(150)));
+
+        // A spender without an allowance has no activity
+        let stranger = api.addr_make("stranger");
+        let err = execute(deps.as_mut(), mock_env(), message_info(&stranger, &[]), transfer_from(1)).unwrap_err();
+        assert!(matches!(err, ContractError::NoAllowance {}));
+        let activity = query_spender_activity(deps.as_ref(), owner.to_string(), stranger.to_string()).unwrap();
+        assert_eq!((activity.total_spent, activity.spend_count), (Uint128::zero(), 0));
+    }
+
+    #[test]
+    fn test_instantiate_salt_must_commit_to_the_bridge_info() {
+        let api = MockApi::default();
+        let creator = message_info(&api.addr_make("creator"), &[]);
+        let salted = |salt: Binary| InstantiateMsg { salt: Some(salt), ..instantiate_msg(&api) };
+
+        let mut deps = mock_dependencies();
+        let other_route = bridge_salt("ethereum", &EXTERNAL_CONTRACT.replace('1', "2"));
+        let err = instantiate(deps.as_mut(), mock_env(), creator.clone(), salted(other_route)).unwrap_err();
+        assert!(matches!(err, ContractError::SaltMismatch { .. }));
+        let other_chain = bridge_salt("polygon", EXTERNAL_CONTRACT);
+        let err = instantiate(deps.as_mut(), mock_env(), creator.clone(), salted(other_chain)).unwrap_err();
+        assert!(matches!(err, ContractError::SaltMismatch { .. }));
+
+        let salt = bridge_salt("ethereum", EXTERNAL_CONTRACT);
+        instantiate(deps.as_mut(), mock_env(), creator, salted(salt.clone())).unwrap();
+        let salt_query = QueryMsg::BridgeSalt { chain_id: "ethereum".to_string(), contract_address: EXTERNAL_CONTRACT.to_string() };
+        let response: BridgeSaltResponse = cosmwasm_std::from_json(query(deps.as_ref(), mock_env(), salt_query).unwrap()).unwrap();
+        assert_eq!((response.salt, response.instance_salt), (salt.clone(), Some(salt)));
+    }
+
+    #[test]
+    fn test_transfer_guard_caps_outgoing_transfers() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let alice = api.addr_make("alice");
+        let transfer = |amount: u128| ExecuteMsg::Transfer { recipient: alice.to_string(), amount: Uint128::new(amount) };
+        let guard = ExecuteMsg::SetTransferGuard { max_per_tx: Some(Uint128::new(100)), daily_max: Some(Uint128::new(150)) };
+        execute(deps.as_mut(), mock_env(), owner.clone(), guard).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), transfer(101)).unwrap_err();
+        assert!(matches!(err, ContractError::TransferGuardExceeded { limit: 100, requested: 101 }));
+        execute(deps.as_mut(), mock_env(), owner.clone(), transfer(100)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), transfer(60)).unwrap_err();
+        assert!(matches!(err, ContractError::TransferGuardExceeded { limit: 150, requested: 160 }));
+        // Withdrawals draw from the same daily allowance
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Uint128::new(60),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw).unwrap_err();
+        assert!(matches!(err, ContractError::TransferGuardExceeded { limit: 150, .. }));
+        execute(deps.as_mut(), mock_env(), owner.clone(), transfer(50)).unwrap();
+
+        // The daily total resets the next day
+        let mut tomorrow = mock_env();
+        tomorrow.block.time = tomorrow.block.time.plus_days(1);
+        execute(deps.as_mut(), tomorrow.clone(), owner.clone(), transfer(100)).unwrap();
+
+        // Only the account itself is guarded, and clearing the guard lifts it
+        let alice_info = message_info(&alice, &[]);
+        let back = ExecuteMsg::Transfer { recipient: api.addr_make("owner").to_string(), amount: Uint128::new(250) };
+        execute(deps.as_mut(), tomorrow.clone(), alice_info, back).unwrap();
+        let clear = ExecuteMsg::SetTransferGuard { max_per_tx: None, daily_max: None };
+        execute(deps.as_mut(), tomorrow.clone(), owner.clone(), clear).unwrap();
+        execute(deps.as_mut(), tomorrow, owner, transfer(500)).unwrap();
+        assert_eq!(balance(deps.as_ref(), &alice), Uint128::new(500));
+    }
+
+    #[test]
+    fn test_cw20_base_failures_keep_their_error_type() {
+        let api = MockApi::default();
+        let mut deps = mock_dependencies();
+        let owner = Cw20Coin { address: api.addr_make("owner").to_string(), amount: Uint128::new(1) };
+        let duplicated = InstantiateMsg { initial_balances: vec![owner.clone(), owner], ..instantiate_msg(&api) };
+        let err = instantiate(deps.as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), duplicated).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateInitialBalanceAddresses {}));
+
+        setup(deps.as_mut(), &api);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        sudo(deps.as_mut(), mock_env(), SudoMsg::UpdateMinterCap { new_cap: Some(Uint128::new(1_100)) }).unwrap();
+        let mint = |amount: u128| ExecuteMsg::Mint { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(amount) };
+        let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), mint(101)).unwrap_err();
+        assert!(matches!(err, ContractError::CannotExceedCap {}));
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), mint(100)).unwrap();
+
+        // Failures without a dedicated variant still carry the cw20-base error
+        let expired = ExecuteMsg::IncreaseAllowance {
+            spender: api.addr_make("spender").to_string(),
+            amount: Uint128::new(1),
+            expires: Some(crate::msg::Expiration::AtHeight(1)),
+        };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), expired).unwrap_err();
+        assert!(matches!(err, ContractError::Base(cw20_base::ContractError::InvalidExpiration {})));
+        let own = ExecuteMsg::IncreaseAllowance { spender: owner.sender.to_string(), amount: Uint128::new(1), expires: None };
+        let err = execute(deps.as_mut(), mock_env(), owner, own).unwrap_err();
+        assert!(matches!(err, ContractError::Base(cw20_base::ContractError::CannotSetOwnAccount {})));
+    }
+
+    #[test]
+    fn test_supply_changes_carry_the_bridge_route() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let tagged = |attributes: &[Attribute]| {
+            attributes.iter().any(|a| a.key == "chain_id" && a.value == "ethereum")
+                && attributes.iter().any(|a| a.key == "contract_address" && a.value == EXTERNAL_CONTRACT)
+        };
+
+        let mint = ExecuteMsg::Mint { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(10) };
+        let burn = ExecuteMsg::Burn { amount: Uint128::new(10) };
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Uint128::new(10),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        let creator = message_info(&api.addr_make("creator"), &[]);
+        for (info, msg) in [(creator, mint), (owner.clone(), burn), (owner.clone(), withdraw)] {
+            let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            assert!(tagged(&res.attributes));
+            assert!(res.events.iter().all(|event| tagged(&event.attributes)));
+        }
+
+        // Plain transfers don't change supply and stay untagged
+        let transfer = ExecuteMsg::Transfer { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(10) };
+        let res = execute(deps.as_mut(), mock_env(), owner, transfer).unwrap();
+        assert!(!res.attributes.iter().any(|a| a.key == "chain_id"));
+    }
+
+    #[test]
+    fn test_audit_log_records_privileged_actions_in_order() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let mallory = api.addr_make("mallory").to_string();
+        let freeze = ExecuteMsg::FreezeAccount { address: mallory.clone() };
+
+        // Rejected actions leave no entry
+        let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("owner"), &[]), freeze.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        assert!(query_audit_log(deps.as_ref(), None, None).unwrap().entries.is_empty());
+
+        let mut env = mock_env();
+        for msg in [
+            freeze,
+            ExecuteMsg::UnfreezeAccount { address: mallory.clone() },
+            ExecuteMsg::UpdateMetadata { name: "Bridged Token".to_string(), symbol: "BTKN".to_string(), decimals: 6 },
+        ] {
+            env.block.height += 1;
+            execute(deps.as_mut(), env.clone(), admin.clone(), msg).unwrap();
+        }
+        let new_minter = api.addr_make("module").to_string();
+        sudo(deps.as_mut(), env.clone(), SudoMsg::TransferMinter { new_minter: new_minter.clone() }).unwrap();
+
+        let entries = query_audit_log(deps.as_ref(), None, None).unwrap().entries;
+        let actions: Vec<_> = entries.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, ["freeze_account", "unfreeze_account", "update_metadata", "transfer_minter"]);
+        assert_eq!((entries[0].actor.as_str(), entries[0].new_value.as_deref()), (admin.sender.as_str(), Some(mallory.as_str())));
+        assert_eq!((entries[1].old_value.as_deref(), entries[1].new_value.as_deref()), (Some(mallory.as_str()), None));
+        assert_eq!(entries[3].new_value.as_deref(), Some(new_minter.as_str()));
+        assert_eq!(entries.iter().map(|entry| entry.id).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!(entries[2].height, mock_env().block.height + 3);
+
+        // Each hash chains onto the previous entry
+        let mut prev_hash = Binary::default();
+        for entry in &entries {
+            let mut hasher = Sha256::new();
+            hasher.update(prev_hash.as_slice());
+            hasher.update(to_json_vec(&AuditEntry { hash: Binary::default(), ..entry.clone() }).unwrap());
+            assert_eq!(entry.hash.as_slice(), hasher.finalize().as_slice());
+            prev_hash = entry.hash.clone();
+        }
+
+        let page = query_audit_log(deps.as_ref(), Some(1), Some(2)).unwrap().entries;
+        assert_eq!(page.iter().map(|entry| entry.id).collect::<Vec<_>>(), [2, 3]);
+    }
+
+    #[test]
+    fn test_debug_queries_follow_the_build_feature() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let version: InterfaceVersionResponse = cosmwasm_std::from_json(query(deps.as_ref(), mock_env(), QueryMsg::InterfaceVersion {}).unwrap()).unwrap();
+        assert_eq!(version.debug_queries, cfg!(feature = "debug"));
+        // Production builds don't even parse the wrapper
+        let parsed = cosmwasm_std::from_json::<QueryMsg>(br#"{"debug":{"roles":{}}}"#);
+        assert_eq!(parsed.is_ok(), cfg!(feature = "debug"));
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn test_debug_queries_expose_roles_and_raw_state() {
+        use crate::debug::{DebugQuery, RawBridgeInfoResponse, RolesResponse};
+
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let debug = |msg: DebugQuery| query(deps.as_ref(), mock_env(), QueryMsg::Debug(msg)).unwrap();
+
+        let roles: RolesResponse = cosmwasm_std::from_json(debug(DebugQuery::Roles {})).unwrap();
+        assert_eq!((roles.admin, roles.creator), (api.addr_make("admin").to_string(), api.addr_make("creator").to_string()));
+        let raw: RawBridgeInfoResponse = cosmwasm_std::from_json(debug(DebugQuery::RawBridgeInfo {})).unwrap();
+        let stored: BridgeInfo = cosmwasm_std::from_json(raw.value.unwrap()).unwrap();
+        assert_eq!(stored.contract_address.to_string(), EXTERNAL_CONTRACT);
+        let version: cw2::ContractVersion = cosmwasm_std::from_json(debug(DebugQuery::ContractVersion {})).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+    }
+
+    #[test]
+    fn test_lockup_registry_holds_back_locked_balances() {
+        use std::{cell::Cell, rc::Rc};
+
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let registry = api.addr_make("registry");
+        let queries = Rc::new(Cell::new(0));
+        let counted = queries.clone();
+        let registry_addr = registry.to_string();
+        deps.querier.update_wasm(move |query| match query {
+            cosmwasm_std::WasmQuery::Smart { contract_addr, msg } if *contract_addr == registry_addr => {
+                counted.set(counted.get() + 1);
+                // Only lockups registered for this token count against it
+                let LockupRegistryQueryMsg::Locked { token, address, .. } = cosmwasm_std::from_json(msg).unwrap();
+                let locked = if token == mock_env().contract.address.to_string() { 600 } else { 0 };
+                let locked = LockedResponse { token, address, locked: Uint128::new(locked), at_height: 0 };
+                cosmwasm_std::SystemResult::Ok(cosmwasm_std::ContractResult::Ok(to_json_binary(&locked).unwrap()))
+            }
+            _ => cosmwasm_std::SystemResult::Err(cosmwasm_std::SystemError::UnsupportedRequest { kind: "wasm".to_string() }),
+        });
+        let transfer = |amount: u128| ExecuteMsg::Transfer { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(amount) };
+        let set = ExecuteMsg::SetLockupRegistry { contract: Some(registry.to_string()), cache_blocks: 10 };
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), set.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("admin"), &[]), set).unwrap();
+
+        // 600 of the 1000 are locked
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), transfer(401)).unwrap_err();
+        assert!(matches!(err, ContractError::BalanceLocked { locked: 600, available: 400, requested: 401 }));
+        execute(deps.as_mut(), mock_env(), owner.clone(), transfer(300)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), transfer(101)).unwrap_err();
+        assert!(matches!(err, ContractError::BalanceLocked { available: 100, .. }));
+        // The rejected transfer cached nothing; after that the lockup is queried once per window
+        assert_eq!(queries.get(), 2);
+        let mut later = mock_env();
+        later.block.height += 10;
+        execute(deps.as_mut(), later.clone(), owner.clone(), transfer(100)).unwrap();
+        assert_eq!(queries.get(), 3);
+
+        // Turning the registry off lifts the restriction
+        let unset = ExecuteMsg::SetLockupRegistry { contract: None, cache_blocks: 0 };
+        execute(deps.as_mut(), later.clone(), message_info(&api.addr_make("admin"), &[]), unset).unwrap();
+        execute(deps.as_mut(), later, owner, transfer(600)).unwrap();
+        assert_eq!(queries.get(), 3);
+        assert_eq!(balance(deps.as_ref(), &api.addr_make("owner")), Uint128::zero());
+    }
+
+    #[test]
+    fn test_forward_module_msg_emits_allowlisted_type_urls_only() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let type_url = "/inference.inference.MsgFutureAction".to_string();
+        let value = Binary::from(b"\x0a\x03abc".as_slice());
+        let forward = ExecuteMsg::ForwardModuleMsg { type_url: type_url.clone(), value: value.clone() };
+        let creator = message_info(&api.addr_make("creator"), &[]);
+
+        let err = execute(deps.as_mut(), mock_env(), creator.clone(), forward.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TypeUrlNotAllowed { .. }));
+        let allow = SudoMsg::UpdateForwardAllowlist { add: vec![type_url.clone()], remove: vec![] };
+        sudo(deps.as_mut(), mock_env(), allow).unwrap();
+        let allowlist: ForwardAllowlistResponse = cosmwasm_std::from_json(query(deps.as_ref(), mock_env(), QueryMsg::ForwardAllowlist {}).unwrap()).unwrap();
+        assert_eq!(allowlist.type_urls, vec![type_url.clone()]);
+
+        // Only the creator or governance may forward, even an allowlisted type
+        for sender in ["owner", "admin"] {
+            let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make(sender), &[]), forward.clone()).unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        let expected = CosmosMsg::Any(cosmwasm_std::AnyMsg { type_url: type_url.clone(), value: value.clone() });
+        let res = execute(deps.as_mut(), mock_env(), creator.clone(), forward.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, expected);
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::ForwardModuleMsg { type_url: type_url.clone(), value }).unwrap();
+        assert_eq!(res.messages[0].msg, expected);
+
+        let disallow = SudoMsg::UpdateForwardAllowlist { add: vec![], remove: vec![type_url] };
+        sudo(deps.as_mut(), mock_env(), disallow).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), creator, forward).unwrap_err();
+        assert!(matches!(err, ContractError::TypeUrlNotAllowed { .. }));
+    }
+
+    #[test]
+    fn test_balances_export_in_chunks_and_import_once() {
+        let api = MockApi::default();
+        let creator = message_info(&api.addr_make("creator"), &[]);
+        let mut source = mock_dependencies();
+        setup(source.as_mut(), &api);
+        for name in ["alice", "bob"] {
+            let transfer = ExecuteMsg::Transfer { recipient: api.addr_make(name).to_string(), amount: Uint128::new(100) };
+            execute(source.as_mut(), mock_env(), message_info(&api.addr_make("owner"), &[]), transfer).unwrap();
+        }
+        let export = |deps: DepsMut, info: MessageInfo, start_after: Option<String>| {
+            execute(deps, mock_env(), info, ExecuteMsg::ExportBalances { start_after, limit: Some(2) })
+        };
+        let err = export(source.as_mut(), message_info(&api.addr_make("admin"), &[]), None).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let mut target = mock_dependencies();
+        let fresh = InstantiateMsg { initial_balances: vec![], ..instantiate_msg(&api) };
+        instantiate(target.as_mut(), mock_env(), creator.clone(), fresh).unwrap();
+        let mut start_after = None;
+        let mut chunks = 0;
+        loop {
+            let res = export(source.as_mut(), creator.clone(), start_after).unwrap();
+            let chunk: ExportBalancesResponse = cosmwasm_std::from_json(res.data.unwrap()).unwrap();
+            assert_eq!(chunk.total_supply, Uint128::new(1_000));
+            let import = ExecuteMsg::ImportBalances { balances: chunk.balances.clone() };
+            let err = execute(target.as_mut(), mock_env(), message_info(&api.addr_make("admin"), &[]), import.clone()).unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+            execute(target.as_mut(), mock_env(), creator.clone(), import.clone()).unwrap();
+            // Replaying a chunk is refused
+            let err = execute(target.as_mut(), mock_env(), creator.clone(), import).unwrap_err();
+            assert!(matches!(err, ContractError::DuplicateInitialBalanceAddresses {}));
+            chunks += 1;
+            start_after = chunk.next_start_after;
+            if start_after.is_none() {
+                break;
+            }
+        }
+        assert_eq!(chunks, 2);
+        for name in ["owner", "alice", "bob"] {
+            let addr = api.addr_make(name);
+            assert_eq!(balance(target.as_ref(), &addr), balance(source.as_ref(), &addr));
+        }
+        assert_eq!(cw20_base_state::TOKEN_INFO.load(&target.storage).unwrap().total_supply, Uint128::new(1_000));
+
+        // Any other activity closes the import for good
+        let transfer = ExecuteMsg::Transfer { recipient: api.addr_make("carol").to_string(), amount: Uint128::new(1) };
+        execute(target.as_mut(), mock_env(), message_info(&api.addr_make("owner"), &[]), transfer).unwrap();
+        let late = ExecuteMsg::ImportBalances { balances: vec![Cw20Coin { address: api.addr_make("dave").to_string(), amount: Uint128::new(1) }] };
+        let err = execute(target.as_mut(), mock_env(), creator, late).unwrap_err();
+        assert!(matches!(err, ContractError::ImportClosed {}));
+    }
+
+    #[test]
+    fn test_version_names_the_contract_and_the_embedded_cw20_base() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let version = |deps: Deps| -> VersionResponse { cosmwasm_std::from_json(query(deps, mock_env(), QueryMsg::Version {}).unwrap()).unwrap() };
+        let fresh = version(deps.as_ref());
+        assert_eq!((fresh.contract.contract.as_str(), fresh.contract.version.as_str()), (CONTRACT_NAME, CONTRACT_VERSION));
+        let base = fresh.cw20_base.unwrap();
+        assert_eq!(base.contract, CW20_BASE_CONTRACT_NAME);
+
+        // An instance from before instantiate claimed cw2 migrates under either name
+        CW20_BASE_VERSION.remove(&mut deps.storage);
+        set_contract_version(&mut deps.storage, CW20_BASE_CONTRACT_NAME, &base.version).unwrap();
+        migrate(deps.as_mut(), mock_env(), Binary::default()).unwrap();
+        let migrated = version(deps.as_ref());
+        assert_eq!(migrated.contract.contract, CONTRACT_NAME);
+        assert_eq!(migrated.cw20_base, Some(base));
+
+        set_contract_version(&mut deps.storage, "crates.io:other", "1.0.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), Binary::default()).unwrap_err();
+    }
+
+    #[test]
+    fn test_minter_cap_follows_governance_but_never_below_supply() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let minter = message_info(&api.addr_make("creator"), &[]);
+        let cap = |deps: Deps| cw20_base_state::TOKEN_INFO.load(deps.storage).unwrap().get_cap();
+        let update = |new_cap: Option<u128>| ExecuteMsg::UpdateMinterCap { new_cap: new_cap.map(Uint128::new) };
+        let mint = |amount: u128| ExecuteMsg::Mint { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(amount) };
+
+        for sender in [&minter, &message_info(&api.addr_make("owner"), &[])] {
+            let err = execute(deps.as_mut(), mock_env(), sender.clone(), update(Some(2_000))).unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        let err = execute(deps.as_mut(), mock_env(), admin.clone(), update(Some(999))).unwrap_err();
+        assert!(matches!(err, ContractError::CapBelowSupply { cap: 999, supply: 1_000 }));
+        let err = sudo(deps.as_mut(), mock_env(), SudoMsg::UpdateMinterCap { new_cap: Some(Uint128::new(999)) }).unwrap_err();
+        assert!(matches!(err, ContractError::CapBelowSupply { .. }));
+
+        // A cap equal to the supply stops minting; raising it lets more through
+        execute(deps.as_mut(), mock_env(), admin.clone(), update(Some(1_000))).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), minter.clone(), mint(1)).unwrap_err();
+        assert!(matches!(err, ContractError::CannotExceedCap {}));
+        sudo(deps.as_mut(), mock_env(), SudoMsg::UpdateMinterCap { new_cap: Some(Uint128::new(1_200)) }).unwrap();
+        assert_eq!(cap(deps.as_ref()), Some(Uint128::new(1_200)));
+        execute(deps.as_mut(), mock_env(), minter.clone(), mint(200)).unwrap();
+
+        // Removing the cap keeps the minter
+        execute(deps.as_mut(), mock_env(), admin, update(None)).unwrap();
+        assert_eq!(cap(deps.as_ref()), None);
+        execute(deps.as_mut(), mock_env(), minter, mint(5_000)).unwrap();
+    }
+
+    #[test]
+    fn test_minter_role_rotates_to_the_new_module_account() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let creator = message_info(&api.addr_make("creator"), &[]);
+        let module = api.addr_make("module");
+        let mint = ExecuteMsg::Mint { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(10) };
+        sudo(deps.as_mut(), mock_env(), SudoMsg::UpdateMinterCap { new_cap: Some(Uint128::new(2_000)) }).unwrap();
+
+        let rotate = |new_minter: &str| ExecuteMsg::TransferMinter { new_minter: new_minter.to_string() };
+        for sender in ["admin", "owner"] {
+            let err = execute(deps.as_mut(), mock_env(), message_info(&api.addr_make(sender), &[]), rotate(module.as_str())).unwrap_err();
+            assert!(matches!(err, ContractError::Unauthorized {}));
+        }
+        execute(deps.as_mut(), mock_env(), creator.clone(), rotate("not an address")).unwrap_err();
+
+        let res = execute(deps.as_mut(), mock_env(), creator.clone(), rotate(module.as_str())).unwrap();
+        let event = res.events.iter().find(|event| event.ty == "minter_transferred").unwrap();
+        let attr = |key: &str| event.attributes.iter().find(|a| a.key == key).map(|a| a.value.clone()).unwrap();
+        assert_eq!((attr("old_minter"), attr("new_minter")), (creator.sender.to_string(), module.to_string()));
+        // The cap is kept, and only the new minter can mint
+        let token_info = cw20_base_state::TOKEN_INFO.load(&deps.storage).unwrap();
+        assert_eq!((token_info.mint.as_ref().unwrap().minter.clone(), token_info.get_cap()), (module.clone(), Some(Uint128::new(2_000))));
+        let err = execute(deps.as_mut(), mock_env(), creator, mint.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), message_info(&module, &[]), mint).unwrap();
+
+        // Governance can rotate it as well
+        let next = api.addr_make("next_module");
+        sudo(deps.as_mut(), mock_env(), SudoMsg::TransferMinter { new_minter: next.to_string() }).unwrap();
+        assert_eq!(cw20_base_state::TOKEN_INFO.load(&deps.storage).unwrap().mint.unwrap().minter, next);
+    }
+
+    #[test]
+    fn test_transfer_by_sig_moves_signed_amounts_once() {
+        use cosmwasm_std::{Api, CanonicalAddr};
+        use k256::ecdsa::{signature::hazmat::PrehashSigner, Signature, SigningKey};
+
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let pubkey = Binary::from(key.verifying_key().to_encoded_point(true).as_bytes());
+        let signer = api.addr_humanize(&CanonicalAddr::from(Ripemd160::digest(Sha256::digest(pubkey.as_slice())).as_slice())).unwrap();
+        let fund = ExecuteMsg::Transfer { recipient: signer.to_string(), amount: Uint128::new(300) };
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("owner"), &[]), fund).unwrap();
+        let alice = api.addr_make("alice");
+        let doc = |nonce: u64, amount: u128| TransferSignDoc {
+            chain_id: mock_env().block.chain_id,
+            contract: mock_env().contract.address.to_string(),
+            owner: signer.to_string(),
+            recipient: alice.to_string(),
+            amount: Uint128::new(amount),
+            nonce,
+            deadline: mock_env().block.height + 10,
+        };
+        let sign = |doc: &TransferSignDoc| {
+            let signature: Signature = key.sign_prehash(&Sha256::digest(to_json_vec(doc).unwrap())).unwrap();
+            Binary::from(signature.to_bytes().as_slice())
+        };
+        let relay = |doc: &TransferSignDoc, signature: Binary| ExecuteMsg::TransferBySig {
+            owner: doc.owner.clone(),
+            recipient: doc.recipient.clone(),
+            amount: doc.amount,
+            nonce: doc.nonce,
+            deadline: doc.deadline,
+            pubkey: pubkey.clone(),
+            signature,
+        };
+        let relayer = message_info(&api.addr_make("relayer"), &[]);
+
+        let first = doc(0, 100);
+        execute(deps.as_mut(), mock_env(), relayer.clone(), relay(&first, sign(&first))).unwrap();
+        assert_eq!(balance(deps.as_ref(), &alice), Uint128::new(100));
+        let nonce: TransferNonceResponse = cosmwasm_std::from_json(query(deps.as_ref(), mock_env(), QueryMsg::TransferNonce { address: signer.to_string() }).unwrap()).unwrap();
+        assert_eq!(nonce.next_nonce, 1);
+
+        // A replay, a tampered amount, someone else's key and a late relay are all refused
+        let err = execute(deps.as_mut(), mock_env(), relayer.clone(), relay(&first, sign(&first))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature { .. }));
+        let second = doc(1, 100);
+        let err = execute(deps.as_mut(), mock_env(), relayer.clone(), relay(&doc(1, 200), sign(&second))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature { .. }));
+        let stolen = TransferSignDoc { owner: api.addr_make("owner").to_string(), ..doc(0, 100) };
+        let err = execute(deps.as_mut(), mock_env(), relayer.clone(), relay(&stolen, sign(&stolen))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature { .. }));
+        let mut late = mock_env();
+        late.block.height += 11;
+        let err = execute(deps.as_mut(), late, relayer.clone(), relay(&second, sign(&second))).unwrap_err();
+        assert!(matches!(err, ContractError::SignatureExpired { .. }));
+
+        execute(deps.as_mut(), mock_env(), relayer, relay(&second, sign(&second))).unwrap();
+        assert_eq!(balance(deps.as_ref(), &signer), Uint128::new(100));
+    }
+
+    #[test]
+    fn test_spend_alerts_record_delegated_spends_above_the_threshold() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let spender = api.addr_make("spender");
+        let approve = ExecuteMsg::IncreaseAllowance { spender: spender.to_string(), amount: Uint128::new(1_000), expires: None };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), approve).unwrap();
+        let transfer_from = |amount: u128| ExecuteMsg::TransferFrom {
+            owner: owner.to_string(),
+            recipient: api.addr_make("alice").to_string(),
+            amount: Uint128::new(amount),
+        };
+        let alerts = |deps: Deps, start_after: Option<u64>| query_spend_alerts(deps, owner.to_string(), start_after, None).unwrap();
+
+        // Nothing is recorded before subscribing
+        execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), transfer_from(200)).unwrap();
+        assert!(alerts(deps.as_ref(), None).alerts.is_empty());
+
+        let subscribe = |threshold: Option<u128>| ExecuteMsg::SubscribeSpendAlerts { threshold: threshold.map(Uint128::new) };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), subscribe(Some(100))).unwrap();
+        for amount in [100, 101, 50, 150] {
+            execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), transfer_from(amount)).unwrap();
+        }
+        let burn_from = ExecuteMsg::BurnFrom { owner: owner.to_string(), amount: Uint128::new(120) };
+        execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), burn_from).unwrap();
+        // The owner's own transfers are not delegated spends
+        let own = ExecuteMsg::Transfer { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(150) };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), own).unwrap();
+
+        let response = alerts(deps.as_ref(), None);
+        assert_eq!(response.threshold, Some(Uint128::new(100)));
+        let recorded: Vec<_> = response.alerts.iter().map(|entry| (entry.id, entry.alert.amount.u128(), entry.alert.action.as_str())).collect();
+        assert_eq!(recorded, [(0, 101, "transfer_from"), (1, 150, "transfer_from"), (2, 120, "burn_from")]);
+        assert!(response.alerts.iter().all(|entry| entry.alert.spender == spender));
+        assert_eq!(alerts(deps.as_ref(), Some(1)).alerts.len(), 1);
+
+        // Unsubscribing stops new entries but keeps the history
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), subscribe(None)).unwrap();
+        execute(deps.as_mut(), mock_env(), message_info(&spender, &[]), transfer_from(120)).unwrap();
+        let response = alerts(deps.as_ref(), None);
+        assert_eq!((response.threshold, response.alerts.len()), (None, 3));
+    }
+
+    #[test]
+    fn test_external_addresses_are_normalized_everywhere() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        let checksummed = " 0xAbCdEf0123456789aBcDeF0123456789AbCdEf01 ";
+        let normalized = "0xabcdef0123456789abcdef0123456789abcdef01";
+        let msg = InstantiateMsg { contract_address: checksummed.to_string(), ..instantiate_msg(&api) };
+        instantiate(deps.as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), msg).unwrap();
+        assert_eq!(query_bridge_info(deps.as_ref()).unwrap().contract_address, normalized);
+
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let withdraw = |destination: &str| ExecuteMsg::Withdraw {
+            amount: Uint128::new(10),
+            destination_address: destination.to_string(),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        // Unprefixed, upper-case input reaches the bridge in canonical form
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(&"2222222222AAAAAAAAAA".repeat(2))).unwrap();
+        assert_eq!(decode_withdrawal(&res.messages[0].msg).destination_address, format!("0x{}", "2222222222aaaaaaaaaa".repeat(2)));
+
+        for invalid in ["0x1234", "not an address", &format!("0x{}", "g".repeat(40))] {
+            let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(invalid)).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidExternalAddress(_)));
+        }
+        let bad = InstantiateMsg { contract_address: "0x1234".to_string(), ..instantiate_msg(&api) };
+        let err = instantiate(mock_dependencies().as_mut(), mock_env(), message_info(&api.addr_make("creator"), &[]), bad).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidExternalAddress(_)));
+    }
+
+    #[test]
+    fn test_withdrawals_the_external_token_cannot_represent_are_refused() {
+        let api = MockApi::default();
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let withdraw = |amount: u128| ExecuteMsg::Withdraw {
+            amount: Uint128::new(amount),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+
+        // 6 local decimals against 4 external ones: only multiples of 100 survive the conversion
+        let mut deps = grpc_dependencies(&[(proto::BRIDGE_TOKEN_METADATA_PATH, Ok(token_metadata(4)))]);
+        setup(deps.as_mut(), &api);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(150)).unwrap_err();
+        assert!(matches!(err, ContractError::UnbridgeableAmount { amount: 150, local_decimals: 6, external_decimals: 4, .. }));
+        assert_eq!(balance(deps.as_ref(), &owner.sender), Uint128::new(1_000));
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(200)).unwrap();
+        assert_eq!(balance(deps.as_ref(), &owner.sender), Uint128::new(800));
+
+        // 81 external decimals scale by 10^75, so 1000 no longer fits in a uint256
+        let mut deps = grpc_dependencies(&[(proto::BRIDGE_TOKEN_METADATA_PATH, Ok(token_metadata(81)))]);
+        setup(deps.as_mut(), &api);
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(1_000)).unwrap_err();
+        assert!(matches!(err, ContractError::UnbridgeableAmount { external_decimals: 81, .. }));
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(100)).unwrap();
+
+        // Without chain metadata the amount passes through unchecked
+        let mut deps = mock_dependencies();
+        setup(deps.as_mut(), &api);
+        execute(deps.as_mut(), mock_env(), owner, withdraw(150)).unwrap();
+    }
+
+    #[test]
+    fn test_grpc_failures_are_typed_and_tolerated_ones_recorded() {
+        use crate::grpc::GrpcQueryError;
+        use crate::state::GRPC_FAILURES;
+
+        let api = MockApi::default();
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let sync = |routes: &[(&str, Result<Vec<u8>, String>)]| {
+            let mut deps = grpc_dependencies(routes);
+            setup(deps.as_mut(), &api);
+            execute(deps.as_mut(), mock_env(), admin.clone(), ExecuteMsg::SyncExternalMetadata {}).unwrap_err()
+        };
+        let path = proto::EXTERNAL_TOKEN_METADATA_PATH;
+        assert!(matches!(sync(&[]), ContractError::Grpc(GrpcQueryError::PathNotFound { .. })));
+        let err = sync(&[(path, Err("token registry unavailable".to_string()))]);
+        assert!(matches!(err, ContractError::Grpc(GrpcQueryError::ChainModule { ref message, .. }) if message == "token registry unavailable"));
+        assert!(matches!(sync(&[(path, Ok(vec![0xff]))]), ContractError::Grpc(GrpcQueryError::Decode { .. })));
+
+        // A failed metadata lookup doesn't stop a withdrawal, but is kept per path
+        let mut deps = grpc_dependencies(&[(proto::BRIDGE_TOKEN_METADATA_PATH, Err("module panicked".to_string()))]);
+        setup(deps.as_mut(), &api);
+        let withdraw = ExecuteMsg::Withdraw {
+            amount: Uint128::new(10),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: None,
+        };
+        let mut env = mock_env();
+        env.block.height += 7;
+        execute(deps.as_mut(), env.clone(), message_info(&api.addr_make("owner"), &[]), withdraw).unwrap();
+        let failure = GRPC_FAILURES.load(&deps.storage, proto::BRIDGE_TOKEN_METADATA_PATH).unwrap();
+        assert_eq!((failure.kind.as_str(), failure.height), ("chain_module_error", env.block.height));
+        assert!(failure.message.contains("module panicked"));
+    }
+
+    #[test]
+    fn test_irreversible_destinations_need_force() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let withdraw = |destination: &str, force: bool| ExecuteMsg::Withdraw {
+            amount: Uint128::new(10),
+            destination_address: destination.to_string(),
+            travel_rule: None,
+            force,
+            priority_fee: None,
+            payload: None,
+        };
+        let blocked = EXTERNAL_CONTRACT.replace('1', "b");
+        let block = SudoMsg::UpdateBlockedDestinations { add: vec![blocked.to_uppercase().replace("0X", "0x")], remove: vec![] };
+        sudo(deps.as_mut(), mock_env(), block).unwrap();
+
+        let zero = format!("0x{}", "0".repeat(40));
+        for (destination, reason) in [(zero.as_str(), "burn address"), (EXTERNAL_CONTRACT, "external token contract"), (blocked.as_str(), "blocked destination")] {
+            let err = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(destination, false)).unwrap_err();
+            assert!(matches!(err, ContractError::IrreversibleDestination { reason: ref r, .. } if r == reason), "{destination}: {err}");
+        }
+        assert_eq!(balance(deps.as_ref(), &owner.sender), Uint128::new(1_000));
+
+        // Forcing goes through, and unblocking lifts governance's entry
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(&zero, true)).unwrap();
+        sudo(deps.as_mut(), mock_env(), SudoMsg::UpdateBlockedDestinations { add: vec![], remove: vec![blocked.clone()] }).unwrap();
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(&blocked, false)).unwrap();
+        assert_eq!(balance(deps.as_ref(), &owner.sender), Uint128::new(980));
+    }
+
+    #[test]
+    fn test_manifest_describes_the_token_in_one_document() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let manifest = |deps: Deps| -> ManifestResponse { cosmwasm_std::from_json(query(deps, mock_env(), QueryMsg::Manifest {}).unwrap()).unwrap() };
+
+        let fresh = manifest(deps.as_ref());
+        assert_eq!((fresh.name.as_str(), fresh.symbol.as_str(), fresh.decimals), ("Wrapped Token", "WTKN", 6));
+        assert_eq!(fresh.address, mock_env().contract.address.to_string());
+        assert_eq!(fresh.version.contract.contract, CONTRACT_NAME);
+        assert_eq!((fresh.bridge.chain_id.as_str(), fresh.bridge.contract_address.as_str()), ("ethereum", EXTERNAL_CONTRACT));
+        assert_eq!(fresh.roles.minter, Some(api.addr_make("creator").to_string()));
+        assert_eq!(fresh.limits, ManifestLimits {
+            mint_cap: None,
+            circuit_breaker: None,
+            withdrawal_batch_threshold: None,
+            travel_rule_threshold: None,
+            lockup_registry: None,
+        });
+
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        for msg in [
+            ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(50)) },
+            ExecuteMsg::SetTravelRule { threshold: Some(Uint128::new(500)) },
+            ExecuteMsg::UpdateMinterCap { new_cap: Some(Uint128::new(5_000)) },
+            ExecuteMsg::UpdateBridgeInfo { chain_id: "ethereum".to_string(), contract_address: EXTERNAL_CONTRACT.replace('1', "2") },
+        ] {
+            let mut env = mock_env();
+            env.block.height += 1;
+            execute(deps.as_mut(), env, admin.clone(), msg).unwrap();
+        }
+        let configured = manifest(deps.as_ref());
+        assert_eq!(configured.limits.withdrawal_batch_threshold, Some(Uint128::new(50)));
+        assert_eq!(configured.limits.travel_rule_threshold, Some(Uint128::new(500)));
+        assert_eq!(configured.limits.mint_cap, Some(Uint128::new(5_000)));
+        assert_eq!(configured.bridge.contract_address, EXTERNAL_CONTRACT.replace('1', "2"));
+        assert_eq!(configured.bridge_routes.len(), 2);
+    }
+
+    #[test]
+    fn test_can_withdraw_names_the_first_failing_check() {
+        let api = MockApi::default();
+        let mut deps = grpc_dependencies(&[(proto::BRIDGE_TOKEN_METADATA_PATH, Ok(token_metadata(4)))]);
+        setup(deps.as_mut(), &api);
+        let owner = api.addr_make("owner");
+        let destination = EXTERNAL_CONTRACT.replace('1', "2");
+        let zero = format!("0x{}", "0".repeat(40));
+        let verdict = |deps: Deps, amount: u128, chain_id: &str, destination: &str| {
+            query_can_withdraw(deps, mock_env(), owner.to_string(), Uint128::new(amount), chain_id.to_string(), Some(destination.to_string()), None).unwrap()
+        };
+        let failed = |deps: Deps, amount: u128, chain_id: &str, destination: &str| verdict(deps, amount, chain_id, destination).failed_check;
+
+        let ok = verdict(deps.as_ref(), 200, "ethereum", &destination);
+        assert!(ok.can_withdraw && ok.reason.is_none());
+        assert!(!ok.batched && !ok.travel_rule_required && !ok.trips_circuit_breaker);
+        for (amount, chain_id, target, check) in [
+            (0, "ethereum", destination.as_str(), "amount"),
+            (200, "polygon", destination.as_str(), "destination_chain"),
+            (200, "ethereum", "0x12", "destination"),
+            (200, "ethereum", zero.as_str(), "blocked_destination"),
+            (150, "ethereum", destination.as_str(), "precision"),
+            (2_000, "ethereum", destination.as_str(), "balance"),
+        ] {
+            assert_eq!(failed(deps.as_ref(), amount, chain_id, target).as_deref(), Some(check), "{check}");
+        }
+
+        // The verdict follows the account's and the contract's settings
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        for msg in [
+            ExecuteMsg::SetWithdrawalBatching { threshold: Some(Uint128::new(500)) },
+            ExecuteMsg::SetTravelRule { threshold: Some(Uint128::new(300)) },
+        ] {
+            execute(deps.as_mut(), mock_env(), admin.clone(), msg).unwrap();
+        }
+        let small = verdict(deps.as_ref(), 200, "ethereum", &destination);
+        assert!(small.can_withdraw && small.batched && !small.travel_rule_required);
+        let large = verdict(deps.as_ref(), 400, "ethereum", &destination);
+        assert!(large.travel_rule_required && !large.batched);
+        let guard = ExecuteMsg::SetTransferGuard { max_per_tx: Some(Uint128::new(100)), daily_max: None };
+        execute(deps.as_mut(), mock_env(), message_info(&owner, &[]), guard).unwrap();
+        assert_eq!(failed(deps.as_ref(), 200, "ethereum", &destination).as_deref(), Some("transfer_guard"));
+        execute(deps.as_mut(), mock_env(), admin, ExecuteMsg::FreezeAccount { address: owner.to_string() }).unwrap();
+        let frozen = verdict(deps.as_ref(), 200, "ethereum", &destination);
+        assert_eq!((frozen.can_withdraw, frozen.failed_check.as_deref()), (false, Some("frozen")));
+        assert!(frozen.reason.unwrap().contains(owner.as_str()));
+    }
+
+    /// Base64 SHA-256 of a message's JSON schema plus, for queries, every response schema
+    fn interface_fingerprint<T: schemars::JsonSchema>(responses: Option<std::collections::BTreeMap<String, schemars::schema::RootSchema>>) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(serde_json::to_vec(&schemars::schema_for!(T)).unwrap());
+        if let Some(responses) = responses {
+            hasher.update(serde_json::to_vec(&responses).unwrap());
+        }
+        Binary::from(hasher.finalize().to_vec()).to_base64()
+    }
+
+    #[test]
+    fn test_interface_versions_are_bumped_with_message_shapes() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let version: InterfaceVersionResponse = cosmwasm_std::from_json(query(deps.as_ref(), mock_env(), QueryMsg::InterfaceVersion {}).unwrap()).unwrap();
+        assert_eq!((version.execute, version.query, version.sudo), (EXECUTE_INTERFACE_VERSION, QUERY_INTERFACE_VERSION, SUDO_INTERFACE_VERSION));
+
+        // When one of these fails, bump the matching version and pin the new fingerprint with it
+        assert_eq!(
+            (EXECUTE_INTERFACE_VERSION, interface_fingerprint::<ExecuteMsg>(None).as_str()),
+            (8, "wkWNEEdaIdMPyN8JVN1Dzj/Hpz/Py/UTLsQo4tL+SfQ="),
+        );
+        assert_eq!(
+            (SUDO_INTERFACE_VERSION, interface_fingerprint::<SudoMsg>(None).as_str()),
+            (2, "X2mIXVyM7GHNvr6p1ZCelnPR4ByC8fSbaQVPiyUkwfI="),
+        );
+        // The debug wrapper is outside the versioned interface
+        #[cfg(not(feature = "debug"))]
+        assert_eq!(
+            (QUERY_INTERFACE_VERSION, interface_fingerprint::<QueryMsg>(Some(<QueryMsg as cosmwasm_schema::QueryResponses>::response_schemas().unwrap())).as_str()),
+            (8, "XIhGLkFJ2R43w+RxHV16TvIUHMkW0kAAj7ZjcgfhHO8="),
+        );
+    }
+
+    #[test]
+    fn test_exported_chunks_fold_into_the_committed_root() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        for msg in [
+            ExecuteMsg::Transfer { recipient: api.addr_make("alice").to_string(), amount: Uint128::new(100) },
+            ExecuteMsg::IncreaseAllowance { spender: api.addr_make("spender").to_string(), amount: Uint128::new(50), expires: None },
+        ] {
+            execute(deps.as_mut(), mock_env(), owner.clone(), msg).unwrap();
+        }
+        let fold = |mut hash: Vec<u8>, entries: &[StateEntry]| {
+            for entry in entries {
+                let mut hasher = Sha256::new();
+                hasher.update(&hash);
+                hasher.update(to_json_vec(entry).unwrap());
+                hash = hasher.finalize().to_vec();
+            }
+            hash
+        };
+
+        // Two entries at a time, each chunk hashing its own entries and the sections chaining
+        // into the root
+        let mut root = Vec::new();
+        let mut keys = Vec::new();
+        for section in StateSection::ALL {
+            let mut start_after = None;
+            loop {
+                let chunk = crate::export::query_export_state(deps.as_ref(), section.clone(), start_after, Some(2)).unwrap();
+                assert!(chunk.entries.len() <= 2);
+                assert_eq!(chunk.hash.as_slice(), fold(Vec::new(), &chunk.entries).as_slice());
+                root = fold(root, &chunk.entries);
+                keys.extend(chunk.entries.iter().map(|entry| entry.key.clone()));
+                start_after = chunk.next_start_after;
+                if start_after.is_none() {
+                    break;
+                }
+            }
+        }
+        let unique: std::collections::BTreeSet<_> = keys.iter().collect();
+        assert_eq!(unique.len(), keys.len());
+        assert!(keys.contains(&format!("balance/{}", api.addr_make("alice"))));
+        assert!(keys.iter().any(|key| key.starts_with("allowance/")));
+
+        execute(deps.as_mut(), mock_env(), message_info(&api.addr_make("anyone"), &[]), ExecuteMsg::CommitStateRoot { limit: None }).unwrap();
+        let committed = committed_root(deps.as_ref()).unwrap();
+        assert_eq!((committed.root.as_slice(), committed.entries), (root.as_slice(), keys.len() as u64));
+        assert_eq!(committed.height, mock_env().block.height);
+    }
+
+    #[test]
+    fn test_withdrawal_payloads_follow_the_chain_memo_rule() {
+        let mut deps = mock_dependencies();
+        let api = MockApi::default();
+        setup(deps.as_mut(), &api);
+        let admin = message_info(&api.addr_make("admin"), &[]);
+        let owner = message_info(&api.addr_make("owner"), &[]);
+        let withdraw = |payload: Option<&str>| ExecuteMsg::Withdraw {
+            amount: Uint128::new(10),
+            destination_address: EXTERNAL_CONTRACT.replace('1', "2"),
+            travel_rule: None,
+            force: false,
+            priority_fee: None,
+            payload: payload.map(str::to_string),
+        };
+        let set = |max_len: u32, encoding: MemoEncoding| ExecuteMsg::SetMemoRule {
+            chain_id: "ethereum".to_string(),
+            rule: Some(MemoRule { required: true, max_len, encoding }),
+        };
+        let rejected = |err: ContractError| matches!(err, ContractError::InvalidWithdrawalPayload { .. });
+
+        // Without a rule the chain takes no payload
+        assert!(rejected(execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(Some("ref"))).unwrap_err()));
+        let err = execute(deps.as_mut(), mock_env(), owner.clone(), set(10, MemoEncoding::Hex)).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), admin.clone(), set(0, MemoEncoding::Hex)).unwrap_err();
+        execute(deps.as_mut(), mock_env(), admin.clone(), set(10, MemoEncoding::Hex)).unwrap();
+
+        for payload in [None, Some(""), Some("0xabc"), Some("0xzz"), Some("0xabcdef01234")] {
+            assert!(rejected(execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(payload)).unwrap_err()), "{payload:?}");
+        }
+        let res = execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(Some("0xabcd"))).unwrap();
+        assert_eq!(decode_withdrawal(&res.messages[0].msg).memo, "0xabcd");
+
+        execute(deps.as_mut(), mock_env(), admin.clone(), set(10, MemoEncoding::Text)).unwrap();
+        assert!(rejected(execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(Some("tag\u{e9}"))).unwrap_err()));
+        execute(deps.as_mut(), mock_env(), owner.clone(), withdraw(Some("tag 42"))).unwrap();
+
+        // Removing the rule drops the requirement
+        execute(deps.as_mut(), mock_env(), admin, ExecuteMsg::SetMemoRule { chain_id: "ethereum".to_string(), rule: None }).unwrap();
+        execute(deps.as_mut(), mock_env(), owner, withdraw(None)).unwrap();
+    }
+}
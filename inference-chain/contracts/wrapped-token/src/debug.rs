@@ -0,0 +1,118 @@
+//! Diagnostic queries for devnets.
+//!
+//! Compiled only with the `debug` cargo feature so production builds keep a clean query
+//! surface. New diagnostics go into `DebugQuery` rather than the top-level `QueryMsg`.
+
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{to_json_binary, Binary, Deps, Order, StdResult};
+use cw2::{get_contract_version, ContractVersion};
+use external_address::ExternalAddress;
+
+use crate::contract::{ADMIN, CREATOR};
+use crate::grpc::query_proto;
+use crate::proto::{self, EmptyRequest, QueryApprovedTokensForTradeResponse};
+use crate::state::{BRIDGE_INFO, GRPC_FAILURES};
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum DebugQuery {
+    /// gRPC call to fetch approved tokens for trade; returns JSON-normalized data
+    #[returns(ApprovedTokensForTradeJson)]
+    ApprovedTokens {},
+    /// Raw storage bytes of the bridge info item
+    #[returns(RawBridgeInfoResponse)]
+    RawBridgeInfo {},
+    /// Stored admin and creator addresses
+    #[returns(RolesResponse)]
+    Roles {},
+    /// cw2 contract name and version
+    #[returns(ContractVersion)]
+    ContractVersion {},
+    /// Latest tolerated gRPC failure per query path
+    #[returns(GrpcFailuresResponse)]
+    GrpcFailures {},
+}
+
+// JSON-normalized response for ApprovedTokensForTrade
+#[cw_serde]
+pub struct ApprovedTokensForTradeJson {
+    pub approved_tokens: Vec<ApprovedTokenJson>,
+}
+
+#[cw_serde]
+pub struct ApprovedTokenJson {
+    pub chain_id: String,
+    pub contract_address: String,
+}
+
+#[cw_serde]
+pub struct RawBridgeInfoResponse {
+    pub key: Binary,
+    pub value: Option<Binary>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub admin: String,
+    pub creator: String,
+}
+
+#[cw_serde]
+pub struct GrpcFailureJson {
+    pub path: String,
+    /// `path_not_found`, `chain_module_error`, `decode_error` or `encode_error`
+    pub kind: String,
+    pub message: String,
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct GrpcFailuresResponse {
+    pub failures: Vec<GrpcFailureJson>,
+}
+
+pub fn query(deps: Deps, msg: DebugQuery) -> StdResult<Binary> {
+    match msg {
+        DebugQuery::ApprovedTokens {} => to_json_binary(&query_approved_tokens(deps)?),
+        DebugQuery::RawBridgeInfo {} => {
+            let item = BRIDGE_INFO;
+            let key = item.as_slice();
+            to_json_binary(&RawBridgeInfoResponse {
+                key: Binary::from(key),
+                value: deps.storage.get(key).map(Binary::from),
+            })
+        }
+        DebugQuery::Roles {} => to_json_binary(&RolesResponse {
+            admin: ADMIN.load(deps.storage)?.to_string(),
+            creator: CREATOR.load(deps.storage)?.to_string(),
+        }),
+        DebugQuery::ContractVersion {} => to_json_binary(&get_contract_version(deps.storage)?),
+        DebugQuery::GrpcFailures {} => to_json_binary(&query_grpc_failures(deps)?),
+    }
+}
+
+fn query_approved_tokens(deps: Deps) -> StdResult<ApprovedTokensForTradeJson> {
+    let decoded: QueryApprovedTokensForTradeResponse = query_proto(
+        deps,
+        proto::APPROVED_TOKENS_FOR_TRADE_PATH,
+        &EmptyRequest {},
+    )?;
+    let approved_tokens = decoded
+        .approved_tokens
+        .into_iter()
+        .map(|t| ApprovedTokenJson { chain_id: t.chain_id, contract_address: ExternalAddress::from(t.contract_address).into() })
+        .collect();
+    Ok(ApprovedTokensForTradeJson { approved_tokens })
+}
+
+// One entry per gRPC path, so the listing stays small enough to return unpaginated
+fn query_grpc_failures(deps: Deps) -> StdResult<GrpcFailuresResponse> {
+    let failures = GRPC_FAILURES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (path, failure) = item?;
+            Ok(GrpcFailureJson { path, kind: failure.kind, message: failure.message, height: failure.height })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(GrpcFailuresResponse { failures })
+}
@@ -6,6 +6,10 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    /// cw20-base failures without a dedicated variant here
+    #[error("{0}")]
+    Base(cw20_base::ContractError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -39,6 +43,126 @@ pub enum ContractError {
     #[error("Only the module can mint tokens")]
     OnlyModuleCanMint {},
 
+    #[error("Relayer {relayer} is not bonded")]
+    RelayerNotBonded { relayer: String },
+
     #[error("Only the module or authorized accounts can burn tokens")]
     OnlyAuthorizedCanBurn {},
-}
\ No newline at end of file
+
+    #[error("Instantiate2 salt does not commit to bridge info {chain_id}:{contract_address}")]
+    SaltMismatch { chain_id: String, contract_address: String },
+
+    #[error("Transfer guard exceeded: limit {limit}, requested {requested}")]
+    TransferGuardExceeded { limit: u128, requested: u128 },
+
+    #[error("Signature expired at height {deadline}")]
+    SignatureExpired { deadline: u64 },
+
+    #[error("Invalid signature: {reason}")]
+    InvalidSignature { reason: String },
+
+    #[error("No session for this key")]
+    SessionNotFound {},
+
+    #[error("Session expired at height {expires_at_height}")]
+    SessionExpired { expires_at_height: u64 },
+
+    #[error("Session budget exceeded: remaining {remaining}, requested {requested}")]
+    SessionLimitExceeded { remaining: u128, requested: u128 },
+
+    #[error("Batch too large: {size} items, max {max}")]
+    BatchTooLarge { size: usize, max: usize },
+
+    #[error("Invalid zero amount")]
+    InvalidZeroAmount {},
+
+    #[error("Account is frozen: {address}")]
+    AccountFrozen { address: String },
+
+    #[error("Message type not on the forward allowlist: {type_url}")]
+    TypeUrlNotAllowed { type_url: String },
+
+    #[error("Minter cap {cap} is below total supply {supply}")]
+    CapBelowSupply { cap: u128, supply: u128 },
+
+    #[error("Balance import is closed once the contract has seen other activity")]
+    ImportClosed {},
+
+    #[error("Balance is locked: locked {locked}, available {available}, requested {requested}")]
+    BalanceLocked { locked: u128, available: u128, requested: u128 },
+
+    #[error("Withdrawals of {threshold} or more require a travel-rule memo")]
+    TravelRuleMemoRequired { threshold: u128 },
+
+    #[error("Withdrawal to {address} would be lost ({reason}); set force to send anyway")]
+    IrreversibleDestination { address: String, reason: String },
+
+    #[error("Withdrawal destination {address} is on the chain sanctions list")]
+    SanctionedDestination { address: String },
+
+    #[error("Withdrawal priority fees are not accepted")]
+    PriorityFeesDisabled {},
+
+    #[error("Invalid travel-rule memo: {reason}")]
+    InvalidTravelRuleMemo { reason: String },
+
+    #[error("Invalid withdrawal payload for {chain_id}: {reason}")]
+    InvalidWithdrawalPayload { chain_id: String, reason: String },
+
+    #[error("Invalid token metadata: {reason}")]
+    InvalidTokenMetadata { reason: String },
+
+    #[error("The inference module has no metadata for {chain_id} token {contract_address}")]
+    ExternalMetadataNotFound { chain_id: String, contract_address: String },
+
+    #[error("{0}")]
+    Grpc(#[from] crate::grpc::GrpcQueryError),
+
+    #[error("{0}")]
+    InvalidExternalAddress(#[from] external_address::InvalidExternalAddress),
+
+    #[error("{flow} halted by the circuit breaker at height {since_height}")]
+    FlowHalted { flow: String, since_height: u64 },
+
+    #[error("Withdrawals to chain {chain_id} are not supported; this token bridges to {supported}")]
+    UnsupportedChain { chain_id: String, supported: String },
+
+    #[error("Tokens sent to the token contract itself cannot be recovered by the sender")]
+    TransferToSelf {},
+
+    #[error("Invalid circuit breaker: {reason}")]
+    InvalidCircuitBreaker { reason: String },
+
+    #[error("{0}")]
+    AccessControl(gonka_cw_controllers::ControllerError),
+
+    #[error("Amount {amount} cannot be bridged ({local_decimals} local decimals, {external_decimals} external): {reason}")]
+    UnbridgeableAmount { amount: u128, local_decimals: u8, external_decimals: u32, reason: String },
+}
+
+impl From<gonka_cw_controllers::ControllerError> for ContractError {
+    fn from(err: gonka_cw_controllers::ControllerError) -> Self {
+        match err {
+            gonka_cw_controllers::ControllerError::Unauthorized {} => ContractError::Unauthorized {},
+            other => ContractError::AccessControl(other),
+        }
+    }
+}
+
+impl From<cw20_base::ContractError> for ContractError {
+    fn from(err: cw20_base::ContractError) -> Self {
+        use cw20_base::ContractError as Base;
+        match err {
+            Base::Std(e) => ContractError::Std(e),
+            Base::Unauthorized {} => ContractError::Unauthorized {},
+            Base::Expired {} => ContractError::Expired {},
+            Base::NoAllowance {} => ContractError::NoAllowance {},
+            Base::CannotExceedCap {} => ContractError::CannotExceedCap {},
+            Base::DuplicateInitialBalanceAddresses {} => ContractError::DuplicateInitialBalanceAddresses {},
+            Base::LogoTooBig {} => ContractError::LogoTooBig {},
+            Base::InvalidXmlPreamble {} => ContractError::InvalidXmlPreamble {},
+            Base::InvalidPngHeader {} => ContractError::InvalidPngHeader {},
+            other => ContractError::Base(other),
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Deterministic state export for audits.
+//!
+//! Each section is a fixed list of stores read in order. Entries are keyed `<store>/<key>`
+//! (just `<store>` for single items) and carry the stored value as JSON. Entries are folded
+//! into a hash as `sha256(previous || json(entry))`, starting from an empty hash: a chunk's
+//! `hash` folds its own entries, and the state root folds every entry of every section in
+//! `StateSection::ALL` order, so it does not depend on how the export was chunked.
+//!
+//! Folding everything in one query would outgrow the query gas limit with the holder set, so
+//! CommitStateRoot builds the root a chunk per call and stores it for StateRoot. Any other
+//! execute drops a commitment in progress, so a stored root always matches a single state.
+
+use cosmwasm_std::{to_json_binary, to_json_vec, Addr, Binary, Deps, DepsMut, Env, Order, Response, StdError, StdResult};
+use cw20_base::state as cw20_base_state;
+use cw_storage_plus::Bound;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use state_export::{read_chunk, Chunk, Cursor};
+
+use crate::contract::{ADMIN, CREATOR};
+use crate::msg::{ExportStateResponse, StateEntry, StateRootResponse, StateSection};
+use crate::state::{
+    StateRootCommit, StateRootProgress, EXEMPT_RELAYERS, PENDING_ADMIN, PENDING_PRIORITY_FEES, PENDING_WITHDRAWALS,
+    PENDING_WITHDRAWAL_SENDERS, PENDING_WITHDRAWAL_SUPPLY, PRIORITY_FEES, STATE_ROOT, STATE_ROOT_PROGRESS,
+    WITHDRAWAL_BATCHING,
+};
+
+// Largest chunk ExportState returns
+const MAX_EXPORT_CHUNK: u32 = 100;
+
+impl StateSection {
+    pub const ALL: [StateSection; 4] = [
+        StateSection::Balances,
+        StateSection::Allowances,
+        StateSection::PendingWithdrawals,
+        StateSection::Roles,
+    ];
+
+    /// Stores of the section, in export order
+    fn stores(&self) -> &'static [&'static str] {
+        match self {
+            StateSection::Balances => &["token_info", "balance"],
+            StateSection::Allowances => &["allowance"],
+            StateSection::PendingWithdrawals => &[
+                "pending_withdrawal_supply",
+                "withdrawal_batching",
+                "priority_fees",
+                "pending_withdrawals",
+                "pending_withdrawal_senders",
+                "pending_priority_fees",
+            ],
+            StateSection::Roles => &["admin", "creator", "pending_admin", "minter", "marketing", "exempt_relayers"],
+        }
+    }
+}
+
+pub fn query_export_state(
+    deps: Deps,
+    section: StateSection,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ExportStateResponse> {
+    let chunk = read_section(deps, &section, start_after.as_deref(), export_limit(limit))?;
+    Ok(ExportStateResponse {
+        hash: Binary::from(fold_entries(Vec::new(), &chunk.entries)?),
+        section,
+        entries: chunk.entries,
+        next_start_after: chunk.next_start_after,
+    })
+}
+
+/// Folds up to `limit` more entries into the state root commitment, starting one if none is in
+/// progress. The commitment is stored once the last section is folded.
+pub fn commit_state_root(deps: DepsMut, env: Env, limit: Option<u32>) -> StdResult<Response> {
+    let mut budget = export_limit(limit);
+    let mut progress = STATE_ROOT_PROGRESS.may_load(deps.storage)?.unwrap_or(StateRootProgress {
+        section: 0,
+        start_after: None,
+        hash: Binary::default(),
+        entries: 0,
+    });
+    let mut resp = Response::new().add_attribute("method", "commit_state_root");
+    while budget > 0 {
+        let Some(section) = StateSection::ALL.get(progress.section as usize) else {
+            break;
+        };
+        let chunk = read_section(deps.as_ref(), section, progress.start_after.as_deref(), budget)?;
+        budget -= chunk.entries.len();
+        progress.entries += chunk.entries.len() as u64;
+        progress.hash = Binary::from(fold_entries(progress.hash.to_vec(), &chunk.entries)?);
+        progress.start_after = chunk.next_start_after;
+        if progress.start_after.is_none() {
+            progress.section += 1;
+        }
+    }
+
+    if (progress.section as usize) < StateSection::ALL.len() {
+        STATE_ROOT_PROGRESS.save(deps.storage, &progress)?;
+        return Ok(resp.add_attribute("done", "false").add_attribute("entries", progress.entries.to_string()));
+    }
+    STATE_ROOT_PROGRESS.remove(deps.storage);
+    let commit = StateRootCommit { root: progress.hash, entries: progress.entries, height: env.block.height };
+    resp = resp
+        .add_attribute("done", "true")
+        .add_attribute("root", commit.root.to_base64())
+        .add_attribute("entries", commit.entries.to_string());
+    STATE_ROOT.save(deps.storage, &commit)?;
+    Ok(resp)
+}
+
+/// Latest finished commitment; None until CommitStateRoot has completed once
+pub fn query_state_root(deps: Deps) -> StdResult<Option<StateRootResponse>> {
+    Ok(STATE_ROOT.may_load(deps.storage)?.map(|commit| StateRootResponse {
+        root: commit.root,
+        entries: commit.entries,
+        height: commit.height,
+    }))
+}
+
+fn export_limit(limit: Option<u32>) -> usize {
+    limit.unwrap_or(MAX_EXPORT_CHUNK).clamp(1, MAX_EXPORT_CHUNK) as usize
+}
+
+fn read_section(deps: Deps, section: &StateSection, start_after: Option<&str>, limit: usize) -> StdResult<Chunk<StateEntry>> {
+    let stores = section.stores();
+    let cursor = Cursor::parse(stores, start_after).map_err(StdError::generic_err)?;
+    read_chunk(
+        stores,
+        cursor,
+        limit,
+        |store, start, limit| export_store(deps, store, start, limit),
+        |entry: &StateEntry| entry.key.as_str(),
+    )
+}
+
+fn fold_entries(mut hash: Vec<u8>, entries: &[StateEntry]) -> StdResult<Vec<u8>> {
+    for entry in entries {
+        let mut hasher = Sha256::new();
+        hasher.update(&hash);
+        hasher.update(to_json_vec(entry)?);
+        hash = hasher.finalize().to_vec();
+    }
+    Ok(hash)
+}
+
+fn export_store(deps: Deps, store: &str, start: Option<&str>, limit: usize) -> StdResult<Vec<StateEntry>> {
+    let storage = deps.storage;
+    match store {
+        "token_info" => item_entry(store, cw20_base_state::TOKEN_INFO.may_load(storage)?),
+        "pending_withdrawal_supply" => item_entry(store, PENDING_WITHDRAWAL_SUPPLY.may_load(storage)?),
+        "withdrawal_batching" => item_entry(store, WITHDRAWAL_BATCHING.may_load(storage)?),
+        "priority_fees" => item_entry(store, PRIORITY_FEES.may_load(storage)?),
+        "admin" => item_entry(store, ADMIN.may_load(storage)?),
+        "creator" => item_entry(store, CREATOR.may_load(storage)?),
+        "pending_admin" => item_entry(store, PENDING_ADMIN.may_load(storage)?),
+        "minter" => item_entry(store, cw20_base_state::TOKEN_INFO.may_load(storage)?.and_then(|info| info.mint)),
+        "marketing" => item_entry(
+            store,
+            cw20_base_state::MARKETING_INFO.may_load(storage)?.and_then(|info| info.marketing),
+        ),
+        "balance" => {
+            let start = start.map(|key| deps.api.addr_validate(key)).transpose()?;
+            cw20_base_state::BALANCES
+                .range(storage, start.as_ref().map(Bound::exclusive), None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let (address, amount) = item?;
+                    map_entry(store, &address, &amount)
+                })
+                .collect()
+        }
+        "allowance" => {
+            // Keyed `<owner>/<spender>`; addresses contain no '/'
+            let start = start
+                .map(|key| {
+                    let (owner, spender) = key
+                        .split_once('/')
+                        .ok_or_else(|| StdError::generic_err(format!("invalid allowance cursor {key}")))?;
+                    Ok::<_, StdError>((deps.api.addr_validate(owner)?, deps.api.addr_validate(spender)?))
+                })
+                .transpose()?;
+            let start = start.as_ref().map(|(owner, spender)| Bound::exclusive((owner, spender)));
+            cw20_base_state::ALLOWANCES
+                .range(storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let ((owner, spender), allowance) = item?;
+                    map_entry(store, &format!("{owner}/{spender}"), &allowance)
+                })
+                .collect()
+        }
+        "pending_withdrawals" | "pending_priority_fees" => {
+            let map = if store == "pending_withdrawals" { PENDING_WITHDRAWALS } else { PENDING_PRIORITY_FEES };
+            map.range(storage, start.map(Bound::exclusive), None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let (destination, amount) = item?;
+                    map_entry(store, &destination, &amount)
+                })
+                .collect()
+        }
+        "pending_withdrawal_senders" => {
+            // Keyed `<destination>/<sender>`; neither contains a '/'
+            let start = start
+                .map(|key| {
+                    let (destination, sender) = key
+                        .split_once('/')
+                        .ok_or_else(|| StdError::generic_err(format!("invalid sender cursor {key}")))?;
+                    Ok::<_, StdError>((destination, deps.api.addr_validate(sender)?))
+                })
+                .transpose()?;
+            let start = start.as_ref().map(|(destination, sender)| Bound::exclusive((*destination, sender)));
+            PENDING_WITHDRAWAL_SENDERS
+                .range(storage, start, None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let ((destination, sender), amount) = item?;
+                    map_entry(store, &format!("{destination}/{sender}"), &amount)
+                })
+                .collect()
+        }
+        "exempt_relayers" => {
+            let start = start.map(|key| deps.api.addr_validate(key)).transpose()?;
+            EXEMPT_RELAYERS
+                .keys(storage, start.as_ref().map(Bound::exclusive), None, Order::Ascending)
+                .take(limit)
+                .map(|item| {
+                    let relayer: Addr = item?;
+                    map_entry(store, &relayer, &())
+                })
+                .collect()
+        }
+        _ => Err(StdError::generic_err(format!("unknown store {store}"))),
+    }
+}
+
+fn item_entry<T: Serialize>(store: &str, value: Option<T>) -> StdResult<Vec<StateEntry>> {
+    value
+        .map(|value| {
+            Ok(StateEntry {
+                key: store.to_string(),
+                value: to_json_binary(&value)?,
+            })
+        })
+        .into_iter()
+        .collect()
+}
+
+fn map_entry<T: Serialize>(store: &str, key: &impl std::fmt::Display, value: &T) -> StdResult<StateEntry> {
+    Ok(StateEntry {
+        key: format!("{store}/{key}"),
+        value: to_json_binary(value)?,
+    })
+}
@@ -0,0 +1,103 @@
+//! gRPC queries to the inference chain, encoded with the types in `proto`.
+
+use cosmwasm_std::{to_json_vec, Binary, ContractResult, Deps, Env, GrpcQuery, QueryRequest, StdError, StdResult, Storage, SystemError, SystemResult};
+use prost::Message as ProstMessage;
+use thiserror::Error;
+
+use crate::state::{GrpcFailure, GRPC_FAILURES};
+
+/// Why a gRPC query to the chain failed
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum GrpcQueryError {
+    /// The chain has no route for the path, e.g. the module predates the query
+    #[error("gRPC path not found: {path}")]
+    PathNotFound { path: String },
+
+    /// The route exists but the module (or the querier) rejected the request
+    #[error("gRPC query {path} failed in chain module: {message}")]
+    ChainModule { path: String, message: String },
+
+    /// The module answered with bytes that do not decode as the expected response
+    #[error("gRPC query {path} returned an undecodable response: {message}")]
+    Decode { path: String, message: String },
+
+    /// The request could not be serialized for the querier
+    #[error("gRPC query {path} could not be encoded: {message}")]
+    Encode { path: String, message: String },
+}
+
+impl GrpcQueryError {
+    /// Stable label for the failure class, as stored by `record_failure`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GrpcQueryError::PathNotFound { .. } => "path_not_found",
+            GrpcQueryError::ChainModule { .. } => "chain_module_error",
+            GrpcQueryError::Decode { .. } => "decode_error",
+            GrpcQueryError::Encode { .. } => "encode_error",
+        }
+    }
+}
+
+impl From<GrpcQueryError> for StdError {
+    fn from(err: GrpcQueryError) -> Self {
+        StdError::generic_err(err.to_string())
+    }
+}
+
+// Generic helpers for gRPC queries using raw_query serialization pattern
+fn query_grpc(deps: Deps, path: &str, data: Binary) -> Result<Binary, GrpcQueryError> {
+    let request = QueryRequest::Grpc(GrpcQuery {
+        path: path.to_string(),
+        data,
+    });
+    query_raw(deps, path, &request)
+}
+
+fn query_raw(deps: Deps, path: &str, request: &QueryRequest<GrpcQuery>) -> Result<Binary, GrpcQueryError> {
+    let raw = to_json_vec(request).map_err(|e| GrpcQueryError::Encode {
+        path: path.to_string(),
+        message: format!("Serializing QueryRequest: {e}"),
+    })?;
+    match deps.querier.raw_query(&raw) {
+        // wasmd answers unknown gRPC routes with an unsupported request
+        SystemResult::Err(SystemError::UnsupportedRequest { .. }) => Err(GrpcQueryError::PathNotFound {
+            path: path.to_string(),
+        }),
+        SystemResult::Err(system_err) => Err(GrpcQueryError::ChainModule {
+            path: path.to_string(),
+            message: format!("Querier system error: {system_err}"),
+        }),
+        SystemResult::Ok(ContractResult::Err(contract_err)) => Err(GrpcQueryError::ChainModule {
+            path: path.to_string(),
+            message: contract_err,
+        }),
+        SystemResult::Ok(ContractResult::Ok(value)) => Ok(value),
+    }
+}
+
+// Generic helper: encode request proto and decode response proto
+pub(crate) fn query_proto<TRequest, TResponse>(deps: Deps, path: &str, request: &TRequest) -> Result<TResponse, GrpcQueryError>
+where
+    TRequest: ProstMessage,
+    TResponse: ProstMessage + Default,
+{
+    let bytes = query_grpc(deps, path, Binary::from(request.encode_to_vec()))?;
+    TResponse::decode(bytes.as_slice()).map_err(|e| GrpcQueryError::Decode {
+        path: path.to_string(),
+        message: e.to_string(),
+    })
+}
+
+/// Keeps the latest failure per path for the `GrpcFailures` debug query. Only failures that a
+/// transaction tolerates are recorded; the rest revert with the error in the tx log.
+pub(crate) fn record_failure(storage: &mut dyn Storage, env: &Env, path: &str, err: &GrpcQueryError) -> StdResult<()> {
+    GRPC_FAILURES.save(
+        storage,
+        path,
+        &GrpcFailure {
+            kind: err.kind().to_string(),
+            message: err.to_string(),
+            height: env.block.height,
+        },
+    )
+}
@@ -1,6 +1,11 @@
 pub mod contract;
+#[cfg(feature = "debug")]
+pub mod debug;
 pub mod error;
+pub mod export;
+pub mod grpc;
 pub mod msg;
+pub mod proto;
 pub mod state;
 
 pub use crate::error::ContractError;
\ No newline at end of file
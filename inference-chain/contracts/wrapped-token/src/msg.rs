@@ -1,5 +1,15 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, Uint128};
+use cosmwasm_std::{Binary, Int128, Uint128};
+use cw2::ContractVersion;
+use gonka_cw_controllers::ownership::PendingOwner;
+
+use crate::state::{AuditEntry, CircuitBreaker, MemoRule, SelfTransferPolicy, SpendAlert};
+
+// Interface versions reported by QueryMsg::InterfaceVersion. Bump the matching one whenever a
+// message or response in it gains, loses or reshapes a variant or field.
+pub const EXECUTE_INTERFACE_VERSION: u32 = 8;
+pub const QUERY_INTERFACE_VERSION: u32 = 8;
+pub const SUDO_INTERFACE_VERSION: u32 = 2;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -15,6 +25,28 @@ pub struct InstantiateMsg {
     pub marketing: Option<InstantiateMarketingInfo>,
     /// Optional admin address (WASM admin = governance module). If not provided, will try to query from contract info.
     pub admin: Option<String>,
+    /// Salt used when the contract is created with Instantiate2. Must equal
+    /// sha256("{chain_id}:{lowercase contract_address}") so the precomputed address commits to the bridge info.
+    pub salt: Option<Binary>,
+    /// Token name (3-50 chars). Omitted fields come from the chain's metadata for the external token.
+    pub name: Option<String>,
+    /// Token symbol ([a-zA-Z-]{3,12})
+    pub symbol: Option<String>,
+    /// Decimals (at most 18)
+    pub decimals: Option<u8>,
+}
+
+/// Travel-rule data for a bridge withdrawal, carried JSON-encoded in the bridge message memo
+#[cw_serde]
+pub struct TravelRuleMemo {
+    pub originator_name: String,
+    /// Originator's account at its VASP; the withdrawing address when self-hosted
+    pub originator_account: String,
+    pub beneficiary_name: String,
+    /// Beneficiary's account; normally the destination address
+    pub beneficiary_account: String,
+    /// Identifier (e.g. LEI) of the beneficiary's VASP, when custodial
+    pub beneficiary_vasp: Option<String>,
 }
 
 #[cw_serde]
@@ -86,6 +118,10 @@ pub enum ExecuteMsg {
         recipient: String,
         amount: Uint128,
     },
+    /// Pay many recipients from `owner` using allowance, with one deduction of their total
+    /// (at most 100 recipients). A recipient at this contract's address applies the self-transfer
+    /// policy to the whole batch.
+    TransferFromBatch { owner: String, recipients: Vec<Cw20Coin> },
     /// Send tokens from owner to contract using allowance
     SendFrom {
         owner: String,
@@ -96,12 +132,36 @@ pub enum ExecuteMsg {
     
     /// Burn tokens from account using allowance
     BurnFrom { owner: String, amount: Uint128 },
+    /// Transfer tokens from owner to `contract` using allowance, then call the contract's `Receive`
+    /// with `payload`. `sender` is the spender, as with SendFrom, since the owner approved neither
+    /// the target nor the payload
+    PullAndNotify {
+        owner: String,
+        amount: Uint128,
+        contract: String,
+        payload: Binary,
+    },
     /// Only with "mintable" extension. Mint new tokens
     Mint { recipient: String, amount: Uint128 },
     /// Special bridge withdraw function that burns tokens and triggers bridge withdrawal
     Withdraw { 
         amount: Uint128,
-        destination_address: String, // Ethereum address to receive tokens
+        destination_address: String, // Ethereum address to receive tokens; normalized to lowercase 0x form
+        /// Originator/beneficiary data; required at or above the travel-rule threshold
+        #[serde(default)]
+        travel_rule: Option<TravelRuleMemo>,
+        /// Withdraw even to a destination listed by BlockedDestinations, where funds are lost
+        #[serde(default)]
+        force: bool,
+        /// Paid on top of `amount` to the fee collector when the withdrawal is queued, so its
+        /// destination flushes ahead of lower-fee and fee-less ones. Not charged when the
+        /// withdrawal is bridged immediately.
+        #[serde(default)]
+        priority_fee: Option<Uint128>,
+        /// Memo for the destination chain (e.g. an exchange deposit tag), checked against the
+        /// chain's memo rule. Alongside a travel-rule memo, both go out in one JSON memo.
+        #[serde(default)]
+        payload: Option<String>,
     },
     UpdateMetadata {
         name: String,
@@ -116,6 +176,125 @@ pub enum ExecuteMsg {
     },
     /// Upload a logo for the token
     UploadLogo(Logo),
+    /// Creator/admin: copy the external token's name, symbol, decimals and logo URI, as imported
+    /// by the bridge, from the inference module. Decimals are only taken while no tokens exist.
+    SyncExternalMetadata {},
+    /// Creator/admin: freeze an account, blocking all outgoing movements of its balance
+    FreezeAccount { address: String },
+    /// Creator/admin: lift a previous freeze
+    UnfreezeAccount { address: String },
+    /// Creator/admin: remap the token to another external contract. The previous route stays in
+    /// `BridgeInfoHistory` with the block range it applied to.
+    UpdateBridgeInfo { chain_id: String, contract_address: String },
+    /// Authorize a session key to transfer up to `max_amount` from the sender's account until `expires_at_height`.
+    /// Replaces any existing session for that key.
+    GrantSession {
+        session_key: String,
+        max_amount: Uint128,
+        expires_at_height: u64,
+    },
+    /// Revoke a session key before it expires
+    RevokeSession { session_key: String },
+    /// Record an alert whenever a spender or session key moves more than `threshold` from the
+    /// sender's account in one call (None stops recording; past alerts are kept)
+    SubscribeSpendAlerts { threshold: Option<Uint128> },
+    /// Called by a session key: transfer from the granting owner's account within the session budget.
+    /// Fails with `SessionExpired` once the session has expired; the owner can revoke or re-grant it.
+    SessionTransfer {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+    },
+    /// Relayer-submitted transfer authorized by the owner's secp256k1 signature over sha256 of
+    /// the JSON `TransferSignDoc`. `pubkey` is the owner's 33-byte compressed account key.
+    TransferBySig {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+        /// Must equal the owner's `TransferNonce`
+        nonce: u64,
+        /// Last block height the signature is valid at
+        deadline: u64,
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Set (or clear, with both limits None) a self-imposed limit on outgoing movements from the sender's account
+    SetTransferGuard {
+        max_per_tx: Option<Uint128>,
+        daily_max: Option<Uint128>,
+    },
+    /// Creator/admin: consult a lockup registry before outgoing transfers (None disables).
+    /// Locked amounts are cached for `cache_blocks` blocks.
+    SetLockupRegistry {
+        contract: Option<String>,
+        cache_blocks: u64,
+    },
+    /// Creator/admin: also reject withdrawals to destinations on the chain-maintained sanctions
+    /// list, which `force` cannot override (None turns the check off). The chain's answer per
+    /// destination is cached for `cache_blocks` blocks.
+    SetSanctionsSync { cache_blocks: Option<u64> },
+    /// Admin only: require Mint and MintBatch senders to be bonded in a relayer bond contract
+    /// (None disables)
+    SetRelayerBond { contract: Option<String> },
+    /// Creator/admin: queue withdrawals below `threshold` per destination instead of bridging
+    /// each one (None turns batching off; already queued amounts still flush)
+    SetWithdrawalBatching { threshold: Option<Uint128> },
+    /// Creator/admin: accept Withdraw priority fees, paid to `fee_collector` (None stops
+    /// accepting them; fees already paid keep their place in the queue)
+    SetPriorityFees { fee_collector: Option<String> },
+    /// Creator/admin: require a travel-rule memo on withdrawals of `threshold` or more
+    /// (None turns the requirement off)
+    SetTravelRule { threshold: Option<Uint128> },
+    /// Creator/admin: set how Withdraw payloads to `chain_id` must be formatted (None removes
+    /// the rule, after which payloads to the chain are rejected)
+    SetMemoRule { chain_id: String, rule: Option<MemoRule> },
+    /// Creator/admin: reject plain transfers to this contract's address, or return the tokens to
+    /// the account they came from
+    SetSelfTransferPolicy { policy: SelfTransferPolicy },
+    /// Admin only: send tokens stranded at this contract's address by earlier transfers
+    RecoverStuckBalance { recipient: String, amount: Uint128 },
+    /// Admin only: propose a successor admin, who takes over by sending AcceptAdmin at or before
+    /// `expiry_height`. Replaces any earlier proposal.
+    ProposeAdmin { admin: String, expiry_height: Option<u64> },
+    /// Proposed admin: take over the admin role
+    AcceptAdmin {},
+    /// Admin only: withdraw a pending ProposeAdmin
+    CancelAdminProposal {},
+    /// Creator/admin: add or remove an official bridge relayer. Exempt relayers' withdrawals
+    /// bypass batching and withdrawal fees/rate limits, so rebalancing is not taxed.
+    SetRelayerExemption { relayer: String, exempt: bool },
+    /// Anyone: bridge up to `limit` queued destinations, one aggregated withdrawal each
+    FlushWithdrawals { limit: Option<u32> },
+    /// Anyone: fold up to `limit` more ExportState entries into the state root, storing it for
+    /// the StateRoot query once every section is folded. Any other execute restarts it.
+    CommitStateRoot { limit: Option<u32> },
+    /// Creator only: emit an arbitrary Any-encoded chain message. `type_url` must be on the
+    /// forward allowlist
+    ForwardModuleMsg { type_url: String, value: Binary },
+    /// Creator only: mint many bridge deposits as (recipient, amount, deposit_id). Deposit ids
+    /// already processed and failing items are skipped and reported in events.
+    MintBatch { mints: Vec<(String, Uint128, String)> },
+    /// Creator/admin: emit a MsgAckBridgeDeposit for every deposit MintBatch mints, once the
+    /// chain module handles it
+    SetDepositAcks { enabled: bool },
+    /// Admin only: change the minter cap (None removes it). Must not be below total supply.
+    UpdateMinterCap { new_cap: Option<Uint128> },
+    /// Admin only: halt mints and/or withdrawals automatically when their volume in a window
+    /// exceeds the limits (None removes the breaker)
+    SetCircuitBreaker { breaker: Option<CircuitBreaker> },
+    /// Admin only: resume flows halted by the circuit breaker and restart their windows
+    ResumeCircuitBreaker {},
+    /// Creator only: hand the cw20 minter role to another (module account) address
+    TransferMinter { new_minter: String },
+    /// Creator only: return a chunk of balances in address order as response data, for moving
+    /// holders to a redeployed instance. Continue from `next_start_after` until it is None.
+    ExportBalances {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Creator only: credit exported balances on a fresh instance. Only accepted before any
+    /// other execute message has run on this instance.
+    ImportBalances { balances: Vec<Cw20Coin> },
 }
 
 #[cw_serde]
@@ -138,6 +317,28 @@ impl Expiration {
     }
 }
 
+/// Privileged messages executed by the chain (governance `MsgSudoContract`)
+#[cw_serde]
+pub enum SudoMsg {
+    /// Emit an allowlisted Any-encoded chain message
+    ForwardModuleMsg { type_url: String, value: Binary },
+    /// Add and remove type URLs ForwardModuleMsg may emit
+    UpdateForwardAllowlist { add: Vec<String>, remove: Vec<String> },
+    /// Change the minter cap as bridge collateral grows (None removes it)
+    UpdateMinterCap { new_cap: Option<Uint128> },
+    /// Hand the cw20 minter role to another (module account) address
+    TransferMinter { new_minter: String },
+    /// Halt mints and/or withdrawals on abnormal volume (None removes the breaker)
+    SetCircuitBreaker { breaker: Option<CircuitBreaker> },
+    /// Resume flows halted by the circuit breaker
+    ResumeCircuitBreaker {},
+    /// Mint many bridge deposits as (recipient, amount, deposit_id), as MintBatch does
+    MintBatch { mints: Vec<(String, Uint128, String)> },
+    /// Add and remove external addresses withdrawals may not target without `force`, such as
+    /// the bridge contract
+    UpdateBlockedDestinations { add: Vec<String>, remove: Vec<String> },
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
@@ -147,9 +348,22 @@ pub enum QueryMsg {
     /// Returns metadata on the contract - name, symbol, decimals, etc.
     #[returns(TokenInfoResponse)]
     TokenInfo {},
+    /// Returns the wrapped-token cw2 version and the embedded cw20-base version
+    #[returns(VersionResponse)]
+    Version {},
+    /// Versions of the execute, query and sudo message interfaces, so frontends can branch on
+    /// capability instead of probing for messages
+    #[returns(InterfaceVersionResponse)]
+    InterfaceVersion {},
     /// Returns bridge information - chain ID and original contract address
     #[returns(BridgeInfoResponse)]
     BridgeInfo {},
+    /// Every bridge route the token has mapped to, with the block range it applied to
+    #[returns(BridgeInfoHistoryResponse)]
+    BridgeInfoHistory {},
+    /// Returns the Instantiate2 salt for a bridge route, for precomputing wrapped-token addresses
+    #[returns(BridgeSaltResponse)]
+    BridgeSalt { chain_id: String, contract_address: String },
     /// Returns how much spender can use from owner account, 0 if unset.
     #[returns(AllowanceResponse)]
     Allowance { owner: String, spender: String },
@@ -175,10 +389,138 @@ pub enum QueryMsg {
     /// Only with "mintable" extension. Returns who can mint and the hard cap on total tokens after minting.
     #[returns(MinterResponse)]
     Minter {},
-
-    /// Test gRPC call to fetch approved tokens for trade; returns JSON-normalized data
-    #[returns(ApprovedTokensForTradeJson)]
-    TestApprovedTokens {},
+    /// Returns how much a spender has moved from owner's account through the allowance
+    #[returns(SpenderActivityResponse)]
+    SpenderActivity { owner: String, spender: String },
+    /// Returns the owner's alert threshold and recorded spend alerts, oldest first. Supports pagination.
+    #[returns(SpendAlertsResponse)]
+    SpendAlerts {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns a session granted by owner to session_key
+    #[returns(SessionResponse)]
+    Session { owner: String, session_key: String },
+    /// Returns all sessions granted by owner. Supports pagination.
+    #[returns(SessionsResponse)]
+    Sessions {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Next nonce a TransferBySig from `address` must carry
+    #[returns(TransferNonceResponse)]
+    TransferNonce { address: String },
+    /// Returns the account's transfer guard and today's guarded outflow
+    #[returns(TransferGuardResponse)]
+    TransferGuard { address: String },
+    /// Type URLs ForwardModuleMsg may emit
+    #[returns(ForwardAllowlistResponse)]
+    ForwardAllowlist {},
+    /// Destinations Withdraw rejects unless forced: burn addresses, the external token contract
+    /// and those blocked by governance. Also reports whether the chain's sanctions list is
+    /// consulted.
+    #[returns(BlockedDestinationsResponse)]
+    BlockedDestinations {},
+    /// How transfers to this contract's address are handled and the balance stranded there
+    #[returns(SelfTransfersResponse)]
+    SelfTransfers {},
+    /// Successor proposed by ProposeAdmin, if any
+    #[returns(Option<PendingOwner>)]
+    PendingAdmin {},
+    /// Returns the configured lockup registry, if transfer restrictions are enabled
+    #[returns(LockupRegistryResponse)]
+    LockupRegistry {},
+    /// Relayer bond contract minters must be bonded in, if required
+    #[returns(RelayerBondResponse)]
+    RelayerBond {},
+    /// Returns total supply split into circulating, pending-withdrawal escrow and frozen amounts
+    #[returns(SupplyBreakdownResponse)]
+    SupplyBreakdown {},
+    /// Compares total supply with the bridged-in balance the inference module records for this
+    /// token, for solvency monitoring
+    #[returns(ReconcileResponse)]
+    Reconcile {},
+    /// Returns the batching threshold and queued withdrawals per destination, in address order
+    /// (FlushWithdrawals goes by priority fee first). Supports pagination.
+    #[returns(PendingWithdrawalsResponse)]
+    PendingWithdrawals {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the withdrawals requested on the chain during an inference epoch
+    #[returns(EpochWithdrawalsResponse)]
+    EpochWithdrawals { epoch: u64 },
+    /// Returns the withdrawal memo rules by chain id. Supports pagination.
+    #[returns(MemoRulesResponse)]
+    MemoRules {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns relayers registered with SetRelayerExemption. Supports pagination.
+    #[returns(ExemptRelayersResponse)]
+    ExemptRelayers {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns non-zero balances as they stood at the start of block `height`, ordered by
+    /// address. Heights from roughly the last week of blocks are available. Supports pagination.
+    #[returns(HoldersAtResponse)]
+    HoldersAt {
+        height: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns whether a bridge deposit id has been minted through MintBatch
+    #[returns(DepositStatusResponse)]
+    DepositStatus { deposit_id: String },
+    /// Withdraw pre-flight for frontends: runs Withdraw's checks for `address` without burning
+    /// anything and reports the first that fails. A given `destination_address` is also checked
+    /// against the blocked destinations, and `payload` against the chain's memo rule.
+    #[returns(CanWithdrawResponse)]
+    CanWithdraw {
+        address: String,
+        amount: Uint128,
+        chain_id: String,
+        #[serde(default)]
+        destination_address: Option<String>,
+        #[serde(default)]
+        payload: Option<String>,
+    },
+    /// Aggregated configuration and operational state for wallets and relayer dashboards
+    #[returns(StatusResponse)]
+    Status {},
+    /// Everything a wallet token list needs in one document: metadata, version, bridge
+    /// routes, roles and limits
+    #[returns(ManifestResponse)]
+    Manifest {},
+    /// Circuit-breaker limits, current window volumes and halted flows
+    #[returns(CircuitBreakerResponse)]
+    CircuitBreaker {},
+    /// Page through the audit log of privileged actions in chronological order
+    #[returns(AuditLogResponse)]
+    AuditLog {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns a chunk of one state section in a fixed order, with the hash of the chunk.
+    /// Continue from `next_start_after` until it is None.
+    #[returns(ExportStateResponse)]
+    ExportState {
+        section: StateSection,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Latest root committed by CommitStateRoot: every ExportState entry of every section
+    /// folded into one hash, for checking a full snapshot. None until one has been committed.
+    #[returns(Option<StateRootResponse>)]
+    StateRoot {},
+    /// Diagnostic queries, only available in builds with the `debug` feature. The response
+    /// type depends on the inner query; `DebugQuery` derives its own `QueryResponses`.
+    #[cfg(feature = "debug")]
+    #[returns(Binary)]
+    Debug(crate::debug::DebugQuery),
 }
 
 #[cw_serde]
@@ -194,12 +536,403 @@ pub struct TokenInfoResponse {
     pub total_supply: Uint128,
 }
 
+#[cw_serde]
+pub struct SpenderActivityResponse {
+    pub owner: String,
+    pub spender: String,
+    /// Cumulative amount moved via TransferFrom/SendFrom/BurnFrom/PullAndNotify
+    pub total_spent: Uint128,
+    pub spend_count: u64,
+    /// Block height of the most recent spend, 0 if none
+    pub last_spend_height: u64,
+    pub remaining_allowance: Uint128,
+}
+
+#[cw_serde]
+pub struct SpendAlertsResponse {
+    pub threshold: Option<Uint128>,
+    pub alerts: Vec<SpendAlertEntry>,
+}
+
+#[cw_serde]
+pub struct SpendAlertEntry {
+    pub id: u64,
+    pub alert: SpendAlert,
+}
+
+#[cw_serde]
+pub struct SessionResponse {
+    pub session_key: String,
+    pub remaining: Uint128,
+    pub expires_at_height: u64,
+    pub expired: bool,
+}
+
+#[cw_serde]
+pub struct SessionsResponse {
+    pub sessions: Vec<SessionResponse>,
+}
+
+#[cw_serde]
+pub struct TransferGuardResponse {
+    pub max_per_tx: Option<Uint128>,
+    pub daily_max: Option<Uint128>,
+    /// Amount moved out of the account during the current UTC day
+    pub spent_today: Uint128,
+}
+
+// Message an owner signs for TransferBySig; chain id and contract prevent cross-deployment replay
+#[cw_serde]
+pub struct TransferSignDoc {
+    pub chain_id: String,
+    pub contract: String,
+    pub owner: String,
+    pub recipient: String,
+    pub amount: Uint128,
+    pub nonce: u64,
+    pub deadline: u64,
+}
+
+#[cw_serde]
+pub struct TransferNonceResponse {
+    pub address: String,
+    pub next_nonce: u64,
+}
+
+#[cw_serde]
+pub struct ForwardAllowlistResponse {
+    pub type_urls: Vec<String>,
+}
+
+#[cw_serde]
+pub struct BlockedDestinationsResponse {
+    pub burn_addresses: Vec<String>,
+    pub token_contract: String,
+    /// Added with UpdateBlockedDestinations
+    pub blocked: Vec<String>,
+    /// Set while destinations are also checked against the chain's sanctions list: how long
+    /// an answer is cached, in blocks
+    pub sanctions_cache_blocks: Option<u64>,
+}
+
+#[cw_serde]
+pub struct SelfTransfersResponse {
+    pub policy: SelfTransferPolicy,
+    /// Tokens held at the contract's own address, recoverable with RecoverStuckBalance
+    pub stranded_balance: Uint128,
+}
+
+#[cw_serde]
+pub struct LockupRegistryResponse {
+    pub contract: Option<String>,
+    pub cache_blocks: u64,
+}
+
+// Query interface a lockup registry must implement
+#[cw_serde]
+pub enum LockupRegistryQueryMsg {
+    // Amount of `token` locked for `address` at `at_height` (current height when None)
+    Locked { token: String, address: String, at_height: Option<u64> },
+}
+
+#[cw_serde]
+pub struct LockedResponse {
+    pub token: String,
+    pub address: String,
+    pub locked: Uint128,
+    pub at_height: u64,
+}
+
+#[cw_serde]
+pub struct RelayerBondResponse {
+    pub contract: Option<String>,
+}
+
+// Query interface a relayer bond contract must implement
+#[cw_serde]
+pub enum RelayerBondQueryMsg {
+    IsBonded { relayer: String },
+}
+
+#[cw_serde]
+pub struct IsBondedResponse {
+    pub relayer: String,
+    pub bonded: bool,
+}
+
+#[cw_serde]
+pub struct SupplyBreakdownResponse {
+    pub total_supply: Uint128,
+    /// total_supply minus escrowed and frozen amounts
+    pub circulating: Uint128,
+    /// Tokens debited from holders for bridge withdrawals that have not been burned yet
+    pub escrowed_pending_withdrawal: Uint128,
+    /// Sum of balances held by frozen accounts
+    pub frozen: Uint128,
+}
+
+#[cw_serde]
+pub struct ReconcileResponse {
+    pub total_supply: Uint128,
+    /// Net bridged-in balance recorded by the inference module; `None` if the chain could not
+    /// answer, with the reason in `chain_error`
+    pub chain_bridged_balance: Option<Uint128>,
+    /// total_supply minus chain_bridged_balance; zero when fully reconciled, positive when
+    /// more tokens circulate than the chain has bridged in
+    pub delta: Option<Int128>,
+    /// Debited from holders and still in total supply, but not yet requested on the chain, so
+    /// part of both sides until flushed
+    pub escrowed_pending_withdrawal: Uint128,
+    pub chain_error: Option<String>,
+}
+
+#[cw_serde]
+pub struct PendingWithdrawal {
+    pub destination_address: String,
+    pub amount: Uint128,
+    /// Priority fees paid by the queued withdrawals to this destination
+    pub priority_fee: Uint128,
+}
+
+#[cw_serde]
+pub struct EpochWithdrawalsResponse {
+    pub epoch: u64,
+    pub amount: Uint128,
+    pub count: u64,
+    /// Withdrawn while the chain could not report the epoch, across all epochs
+    pub unattributed_amount: Uint128,
+}
+
+#[cw_serde]
+pub struct ExemptRelayersResponse {
+    pub relayers: Vec<String>,
+}
+
+#[cw_serde]
+pub struct MemoRuleEntry {
+    pub chain_id: String,
+    pub rule: MemoRule,
+}
+
+#[cw_serde]
+pub struct MemoRulesResponse {
+    pub rules: Vec<MemoRuleEntry>,
+}
+
+#[cw_serde]
+pub struct FlowStatus {
+    /// `mint` or `withdraw`
+    pub flow: String,
+    /// Volume in the current window
+    pub volume: Uint128,
+    /// Height the flow was halted at, if it is halted
+    pub halted_at_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct CircuitBreakerResponse {
+    pub breaker: Option<CircuitBreaker>,
+    pub flows: Vec<FlowStatus>,
+}
+
+#[cw_serde]
+pub struct PendingWithdrawalsResponse {
+    /// None when batching is off
+    pub threshold: Option<Uint128>,
+    /// Receives Withdraw priority fees; None when they are not accepted
+    pub priority_fee_collector: Option<String>,
+    pub withdrawals: Vec<PendingWithdrawal>,
+}
+
+#[cw_serde]
+pub struct AuditLogResponse {
+    pub entries: Vec<AuditEntry>,
+}
+
+#[cw_serde]
+pub enum StateSection {
+    /// Token info (with total supply), then balances by address
+    Balances,
+    /// Allowances by owner, then spender
+    Allowances,
+    /// Withdrawal queue settings, then queued amounts, their senders and priority fees by destination
+    PendingWithdrawals,
+    /// Admin, creator, pending admin, minter, marketing and exempt relayers
+    Roles,
+}
+
+#[cw_serde]
+pub struct StateEntry {
+    /// `<store>/<key>`, or just `<store>` for a single item
+    pub key: String,
+    /// The stored value as JSON
+    pub value: Binary,
+}
+
+#[cw_serde]
+pub struct ExportStateResponse {
+    pub section: StateSection,
+    pub entries: Vec<StateEntry>,
+    /// Entries folded as `sha256(previous || json(entry))` from an empty hash
+    pub hash: Binary,
+    /// Pass as `start_after` for the next chunk; None once the section is exported
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct StateRootResponse {
+    /// Every section's entries folded like an ExportState chunk hash, sections in declaration order
+    pub root: Binary,
+    pub entries: u64,
+    /// Height the commitment was finished at; the root describes the state as of that block
+    pub height: u64,
+}
+
+#[cw_serde]
+pub struct ExportBalancesResponse {
+    pub balances: Vec<Cw20Coin>,
+    /// Pass as `start_after` for the next chunk; None once all balances are exported
+    pub next_start_after: Option<String>,
+    pub total_supply: Uint128,
+}
+
+#[cw_serde]
+pub struct HoldersAtResponse {
+    pub height: u64,
+    pub holders: Vec<Cw20Coin>,
+    /// Pass as `start_after` for the next page; None once all accounts are scanned. Pages may
+    /// hold fewer than `limit` holders because zero balances are skipped.
+    pub next_start_after: Option<String>,
+}
+
+#[cw_serde]
+pub struct DepositStatusResponse {
+    pub deposit_id: String,
+    pub processed_at_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct CanWithdrawResponse {
+    pub can_withdraw: bool,
+    /// First failing check: `frozen`, `transfer_guard`, `lockup`, `amount`, `halted`,
+    /// `destination_chain`, `destination`, `blocked_destination`, `sanctioned_destination`,
+    /// `payload`, `precision` or `balance`
+    pub failed_check: Option<String>,
+    pub reason: Option<String>,
+    /// The withdrawal must carry a travel-rule memo
+    pub travel_rule_required: bool,
+    /// The withdrawal would be queued for the next batch instead of bridged immediately
+    pub batched: bool,
+    /// The withdrawal would trip the circuit breaker; it still completes, but halts later ones
+    pub trips_circuit_breaker: bool,
+}
+
+#[cw_serde]
+pub struct StatusResponse {
+    pub bridge_info: BridgeInfoResponse,
+    /// Inference module that created the contract
+    pub creator: String,
+    /// Governance admin
+    pub admin: String,
+    pub minter: Option<MinterResponse>,
+    pub total_supply: Uint128,
+    /// Tokens debited for bridge withdrawals that have not been burned yet
+    pub pending_withdrawal_supply: Uint128,
+    pub frozen_accounts: u64,
+    /// Accounts with an owner-configured transfer guard (daily windows reset at UTC midnight)
+    pub guarded_accounts: u64,
+    /// Withdrawals of this amount or more must carry a travel-rule memo
+    pub travel_rule_threshold: Option<Uint128>,
+    /// Withdrawals below this amount are queued for FlushWithdrawals; None when batching is off
+    pub withdrawal_batching_threshold: Option<Uint128>,
+    /// Receives Withdraw priority fees; None when they are not accepted
+    pub priority_fee_collector: Option<String>,
+    /// Destinations with queued withdrawals awaiting FlushWithdrawals
+    pub pending_withdrawals: u64,
+    /// Rate-limit windows, current volumes and halted flows
+    pub circuit_breaker: CircuitBreakerResponse,
+}
+
+#[cw_serde]
+pub struct ManifestResponse {
+    pub address: String,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub version: VersionResponse,
+    /// Current bridge route
+    pub bridge: BridgeInfoResponse,
+    /// Every route the token has mapped to, oldest first
+    pub bridge_routes: Vec<BridgeRouteEntry>,
+    pub roles: ManifestRoles,
+    pub limits: ManifestLimits,
+}
+
+#[cw_serde]
+pub struct ManifestRoles {
+    /// Governance admin
+    pub admin: String,
+    /// Inference module that created the contract
+    pub creator: String,
+    pub minter: Option<String>,
+}
+
+#[cw_serde]
+pub struct ManifestLimits {
+    pub mint_cap: Option<Uint128>,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Withdrawals below this amount are batched; None when batching is off
+    pub withdrawal_batch_threshold: Option<Uint128>,
+    /// Withdrawals of this amount or more must carry a travel-rule memo
+    pub travel_rule_threshold: Option<Uint128>,
+    /// Registry outgoing transfers are checked against, if restrictions are on
+    pub lockup_registry: Option<String>,
+}
+
+#[cw_serde]
+pub struct VersionResponse {
+    pub contract: ContractVersion,
+    /// None for instances created before the base version was recorded separately
+    pub cw20_base: Option<ContractVersion>,
+}
+
+#[cw_serde]
+pub struct InterfaceVersionResponse {
+    pub execute: u32,
+    pub query: u32,
+    pub sudo: u32,
+    /// Built with the `debug` feature, so QueryMsg::Debug is available
+    pub debug_queries: bool,
+}
+
 #[cw_serde]
 pub struct BridgeInfoResponse {
     pub chain_id: String,
     pub contract_address: String,
 }
 
+#[cw_serde]
+pub struct BridgeRouteEntry {
+    pub chain_id: String,
+    pub contract_address: String,
+    /// First height the route applied to
+    pub from_height: u64,
+    /// Height the next route took effect; None for the current route
+    pub until_height: Option<u64>,
+}
+
+#[cw_serde]
+pub struct BridgeInfoHistoryResponse {
+    pub routes: Vec<BridgeRouteEntry>,
+}
+
+#[cw_serde]
+pub struct BridgeSaltResponse {
+    pub salt: Binary,
+    /// Salt this instance was created with, if it was instantiated deterministically
+    pub instance_salt: Option<Binary>,
+}
+
 #[cw_serde]
 pub struct AllowanceResponse {
     pub allowance: Uint128,
@@ -245,18 +978,6 @@ pub struct DownloadLogoResponse {
     pub data: Binary,
 }
 
-// JSON-normalized response for ApprovedTokensForTrade
-#[cw_serde]
-pub struct ApprovedTokensForTradeJson {
-    pub approved_tokens: Vec<ApprovedTokenJson>,
-}
-
-#[cw_serde]
-pub struct ApprovedTokenJson {
-    pub chain_id: String,
-    pub contract_address: String,
-}
-
 #[cw_serde]
 pub struct Cw20ReceiveMsg {
     pub sender: String,
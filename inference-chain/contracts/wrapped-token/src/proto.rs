@@ -0,0 +1,321 @@
+//! Protobuf types shared with the inference chain module.
+//!
+//! Types live in a versioned module so a chain-side proto change can be introduced as `v2`
+//! alongside `v1` while deployed contracts keep encoding the old shape. The golden byte
+//! fixtures in the tests pin the wire encoding.
+
+pub mod v1 {
+    use prost::Message;
+
+    pub const MSG_REQUEST_BRIDGE_WITHDRAWAL_TYPE_URL: &str = "/inference.inference.MsgRequestBridgeWithdrawal";
+    pub const APPROVED_TOKENS_FOR_TRADE_PATH: &str = "/inference.inference.Query/ApprovedTokensForTrade";
+    pub const BRIDGE_TOKEN_METADATA_PATH: &str = "/inference.inference.Query/BridgeTokenMetadata";
+    pub const MSG_ACK_BRIDGE_DEPOSIT_TYPE_URL: &str = "/inference.inference.MsgAckBridgeDeposit";
+    pub const BRIDGE_LEDGER_BALANCE_PATH: &str = "/inference.inference.Query/BridgeLedgerBalance";
+    pub const SANCTIONED_ADDRESS_PATH: &str = "/inference.inference.Query/SanctionedAddress";
+    pub const CURRENT_EPOCH_PATH: &str = "/inference.inference.Query/GetCurrentEpoch";
+    pub const EXTERNAL_TOKEN_METADATA_PATH: &str = "/inference.inference.Query/ExternalTokenMetadata";
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct MsgRequestBridgeWithdrawal {
+        #[prost(string, tag = "1")]
+        pub creator: String,
+        #[prost(string, tag = "2")]
+        pub user_address: String,
+        #[prost(string, tag = "3")]
+        pub amount: String,
+        #[prost(string, tag = "4")]
+        pub destination_address: String,
+    }
+
+    /// Tells the inference module a bridge deposit was minted, so it can mark it completed
+    #[derive(Clone, PartialEq, Message)]
+    pub struct MsgAckBridgeDeposit {
+        #[prost(string, tag = "1")]
+        pub creator: String,
+        #[prost(string, tag = "2")]
+        pub deposit_id: String,
+        #[prost(string, tag = "3")]
+        pub amount: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct BridgeTradeApprovedToken {
+        #[prost(string, tag = "1")]
+        pub chain_id: String,
+        #[prost(string, tag = "2")]
+        pub contract_address: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryApprovedTokensForTradeResponse {
+        #[prost(message, repeated, tag = "1")]
+        pub approved_tokens: ::prost::alloc::vec::Vec<BridgeTradeApprovedToken>,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryBridgeTokenMetadataRequest {
+        #[prost(string, tag = "1")]
+        pub chain_id: String,
+        #[prost(string, tag = "2")]
+        pub contract_address: String,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryBridgeTokenMetadataResponse {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub symbol: String,
+        #[prost(uint32, tag = "3")]
+        pub decimals: u32,
+        #[prost(bool, tag = "4")]
+        pub found: bool,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryExternalTokenMetadataRequest {
+        #[prost(string, tag = "1")]
+        pub chain_id: String,
+        #[prost(string, tag = "2")]
+        pub contract_address: String,
+    }
+
+    /// Metadata the bridge imported from the external token's own contract, logo included
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryExternalTokenMetadataResponse {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub symbol: String,
+        #[prost(uint32, tag = "3")]
+        pub decimals: u32,
+        #[prost(string, tag = "4")]
+        pub logo_uri: String,
+        #[prost(bool, tag = "5")]
+        pub found: bool,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryBridgeLedgerBalanceRequest {
+        #[prost(string, tag = "1")]
+        pub chain_id: String,
+        #[prost(string, tag = "2")]
+        pub contract_address: String,
+    }
+
+    /// Net amount the inference module has recorded as bridged in for an external token:
+    /// completed deposits minus requested withdrawals
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryBridgeLedgerBalanceResponse {
+        #[prost(string, tag = "1")]
+        pub amount: String,
+        #[prost(bool, tag = "2")]
+        pub found: bool,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QuerySanctionedAddressRequest {
+        #[prost(string, tag = "1")]
+        pub chain_id: String,
+        #[prost(string, tag = "2")]
+        pub address: String,
+    }
+
+    /// Whether the chain-maintained sanctions list names an external address
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QuerySanctionedAddressResponse {
+        #[prost(bool, tag = "1")]
+        pub sanctioned: bool,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryGetCurrentEpochRequest {}
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct QueryGetCurrentEpochResponse {
+        #[prost(uint64, tag = "1")]
+        pub epoch: u64,
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct EmptyRequest {}
+}
+
+/// Adds the travel-rule memo to withdrawals. Encodes identically to v1 when `memo` is empty,
+/// so v1 stays in use for withdrawals without one.
+pub mod v2 {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct MsgRequestBridgeWithdrawal {
+        #[prost(string, tag = "1")]
+        pub creator: String,
+        #[prost(string, tag = "2")]
+        pub user_address: String,
+        #[prost(string, tag = "3")]
+        pub amount: String,
+        #[prost(string, tag = "4")]
+        pub destination_address: String,
+        #[prost(string, tag = "5")]
+        pub memo: String,
+    }
+}
+
+pub use v1::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn msg_request_bridge_withdrawal_golden_bytes() {
+        let msg = MsgRequestBridgeWithdrawal {
+            creator: "a".to_string(),
+            user_address: "b".to_string(),
+            amount: "1".to_string(),
+            destination_address: "0x".to_string(),
+        };
+        let golden: &[u8] = &[
+            0x0a, 0x01, b'a', //
+            0x12, 0x01, b'b', //
+            0x1a, 0x01, b'1', //
+            0x22, 0x02, b'0', b'x',
+        ];
+        assert_eq!(msg.encode_to_vec(), golden);
+        assert_eq!(MsgRequestBridgeWithdrawal::decode(golden).unwrap(), msg);
+    }
+
+    #[test]
+    fn msg_request_bridge_withdrawal_v2_golden_bytes() {
+        let msg = v2::MsgRequestBridgeWithdrawal {
+            creator: "a".to_string(),
+            user_address: "b".to_string(),
+            amount: "1".to_string(),
+            destination_address: "0x".to_string(),
+            memo: "{}".to_string(),
+        };
+        let golden: &[u8] = &[
+            0x0a, 0x01, b'a', //
+            0x12, 0x01, b'b', //
+            0x1a, 0x01, b'1', //
+            0x22, 0x02, b'0', b'x', //
+            0x2a, 0x02, b'{', b'}',
+        ];
+        assert_eq!(msg.encode_to_vec(), golden);
+        assert_eq!(v2::MsgRequestBridgeWithdrawal::decode(golden).unwrap(), msg);
+    }
+
+    #[test]
+    fn msg_ack_bridge_deposit_golden_bytes() {
+        let msg = MsgAckBridgeDeposit {
+            creator: "a".to_string(),
+            deposit_id: "d1".to_string(),
+            amount: "5".to_string(),
+        };
+        let golden: &[u8] = &[
+            0x0a, 0x01, b'a', //
+            0x12, 0x02, b'd', b'1', //
+            0x1a, 0x01, b'5',
+        ];
+        assert_eq!(msg.encode_to_vec(), golden);
+        assert_eq!(MsgAckBridgeDeposit::decode(golden).unwrap(), msg);
+    }
+
+    #[test]
+    fn approved_tokens_response_golden_bytes() {
+        let response = QueryApprovedTokensForTradeResponse {
+            approved_tokens: vec![BridgeTradeApprovedToken {
+                chain_id: "ethereum".to_string(),
+                contract_address: "0xab".to_string(),
+            }],
+        };
+        let mut golden = vec![0x0a, 0x10, 0x0a, 0x08];
+        golden.extend_from_slice(b"ethereum");
+        golden.extend_from_slice(&[0x12, 0x04]);
+        golden.extend_from_slice(b"0xab");
+        assert_eq!(response.encode_to_vec(), golden);
+        assert_eq!(QueryApprovedTokensForTradeResponse::decode(golden.as_slice()).unwrap(), response);
+    }
+
+    #[test]
+    fn bridge_token_metadata_response_golden_bytes() {
+        let response = QueryBridgeTokenMetadataResponse {
+            name: "Tether".to_string(),
+            symbol: "USDT".to_string(),
+            decimals: 6,
+            found: true,
+        };
+        let mut golden = vec![0x0a, 0x06];
+        golden.extend_from_slice(b"Tether");
+        golden.extend_from_slice(&[0x12, 0x04]);
+        golden.extend_from_slice(b"USDT");
+        golden.extend_from_slice(&[0x18, 0x06, 0x20, 0x01]);
+        assert_eq!(response.encode_to_vec(), golden);
+        assert_eq!(QueryBridgeTokenMetadataResponse::decode(golden.as_slice()).unwrap(), response);
+    }
+
+    #[test]
+    fn external_token_metadata_response_golden_bytes() {
+        let response = QueryExternalTokenMetadataResponse {
+            name: "Tether".to_string(),
+            symbol: "USDT".to_string(),
+            decimals: 6,
+            logo_uri: "ipfs://x".to_string(),
+            found: true,
+        };
+        let mut golden = vec![0x0a, 0x06];
+        golden.extend_from_slice(b"Tether");
+        golden.extend_from_slice(&[0x12, 0x04]);
+        golden.extend_from_slice(b"USDT");
+        golden.extend_from_slice(&[0x18, 0x06, 0x22, 0x08]);
+        golden.extend_from_slice(b"ipfs://x");
+        golden.extend_from_slice(&[0x28, 0x01]);
+        assert_eq!(response.encode_to_vec(), golden);
+        assert_eq!(QueryExternalTokenMetadataResponse::decode(golden.as_slice()).unwrap(), response);
+    }
+
+    #[test]
+    fn bridge_ledger_balance_response_golden_bytes() {
+        let response = QueryBridgeLedgerBalanceResponse {
+            amount: "1000".to_string(),
+            found: true,
+        };
+        let mut golden = vec![0x0a, 0x04];
+        golden.extend_from_slice(b"1000");
+        golden.extend_from_slice(&[0x10, 0x01]);
+        assert_eq!(response.encode_to_vec(), golden);
+        assert_eq!(QueryBridgeLedgerBalanceResponse::decode(golden.as_slice()).unwrap(), response);
+    }
+
+    #[test]
+    fn sanctioned_address_golden_bytes() {
+        let request = QuerySanctionedAddressRequest {
+            chain_id: "eth".to_string(),
+            address: "0xab".to_string(),
+        };
+        let mut golden = vec![0x0a, 0x03];
+        golden.extend_from_slice(b"eth");
+        golden.extend_from_slice(&[0x12, 0x04]);
+        golden.extend_from_slice(b"0xab");
+        assert_eq!(request.encode_to_vec(), golden);
+
+        let response = QuerySanctionedAddressResponse { sanctioned: true };
+        assert_eq!(response.encode_to_vec(), [0x08, 0x01]);
+        assert_eq!(QuerySanctionedAddressResponse::decode([0x08u8, 0x01].as_slice()).unwrap(), response);
+    }
+
+    #[test]
+    fn current_epoch_golden_bytes() {
+        let response = QueryGetCurrentEpochResponse { epoch: 300 };
+        assert_eq!(response.encode_to_vec(), [0x08, 0xac, 0x02]);
+        assert_eq!(QueryGetCurrentEpochResponse::decode([0x08u8, 0xac, 0x02].as_slice()).unwrap(), response);
+    }
+
+    #[test]
+    fn empty_request_encodes_to_nothing() {
+        assert!(EmptyRequest {}.encode_to_vec().is_empty());
+        assert!(QueryGetCurrentEpochRequest {}.encode_to_vec().is_empty());
+    }
+}
@@ -1,6 +1,9 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Binary, Uint128};
+use cw2::ContractVersion;
 use cw_storage_plus::{Item, Map};
+use external_address::ExternalAddress;
+use gonka_cw_controllers::ownership::PendingOwner;
 
 use crate::msg::{Expiration, Logo, MinterResponse};
 
@@ -17,8 +20,8 @@ pub struct TokenInfo {
 pub struct BridgeInfo {
     /// Original chain ID where the token exists
     pub chain_id: String,
-    /// Original contract address on the external chain
-    pub contract_address: String,
+    /// Original contract address on the external chain, normalized
+    pub contract_address: ExternalAddress,
 }
 
 #[cw_serde]
@@ -31,12 +34,269 @@ pub struct MarketingInfo {
 
 pub const TOKEN_INFO: Item<TokenInfo> = Item::new("token_info");
 pub const BRIDGE_INFO: Item<BridgeInfo> = Item::new("bridge_info");
+// Height a bridge route took effect -> the route; each entry holds until the next one
+pub const BRIDGE_INFO_HISTORY: Map<u64, BridgeInfo> = Map::new("bridge_info_history");
+// cw2 version cw20-base stamped when this instance was created; cw2 itself holds wrapped-token's
+pub const CW20_BASE_VERSION: Item<ContractVersion> = Item::new("cw20_base_version");
+// Instantiate2 salt the contract was created with, if any
+pub const INSTANTIATE_SALT: Item<Binary> = Item::new("instantiate_salt");
 pub const MARKETING_INFO: Item<MarketingInfo> = Item::new("marketing_info");
 pub const LOGO: Item<Logo> = Item::new("logo");
 pub const BALANCES: Map<&Addr, Uint128> = Map::new("balance");
 pub const ALLOWANCES: Map<(&Addr, &Addr), AllowanceResponse> = Map::new("allowance");
 
-// Optional metadata override that can be updated post-instantiate by admin
+// Accounts whose balances are frozen (no outgoing transfers, sends, burns or withdrawals)
+pub const FROZEN_ACCOUNTS: Map<&Addr, bool> = Map::new("frozen_accounts");
+// Number of FROZEN_ACCOUNTS, TRANSFER_GUARDS and PENDING_WITHDRAWALS entries, kept on every save
+// and remove so Status does not have to walk the maps
+pub const FROZEN_ACCOUNT_COUNT: Item<u64> = Item::new("frozen_account_count");
+pub const GUARDED_ACCOUNT_COUNT: Item<u64> = Item::new("guarded_account_count");
+pub const PENDING_WITHDRAWAL_COUNT: Item<u64> = Item::new("pending_withdrawal_count");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct SpenderActivity {
+    pub total_spent: Uint128,
+    pub spend_count: u64,
+    pub last_spend_height: u64,
+}
+
+// Delegated spend counters: (owner, spender) -> activity
+pub const SPENDER_ACTIVITY: Map<(&Addr, &Addr), SpenderActivity> = Map::new("spender_activity");
+
+// Owners opted into spend alerts -> per-call amount above which a delegated spend is recorded
+pub const SPEND_ALERT_THRESHOLDS: Map<&Addr, Uint128> = Map::new("spend_alert_thresholds");
+
+// A delegated spend (allowance or session key) above the owner's alert threshold
+#[cw_serde]
+pub struct SpendAlert {
+    pub spender: Addr,
+    pub amount: Uint128,
+    pub action: String,
+    pub height: u64,
+}
+
+// (owner, sequence) -> alert, in the order they were raised
+pub const SPEND_ALERTS: Map<(&Addr, u64), SpendAlert> = Map::new("spend_alerts");
+// Next alert sequence per owner
+pub const NEXT_SPEND_ALERT: Map<&Addr, u64> = Map::new("next_spend_alert");
+
+// Time-boxed transfer authority granted by an owner to a session key
+#[cw_serde]
+pub struct Session {
+    pub remaining: Uint128,
+    pub expires_at_height: u64,
+}
+
+// (owner, session_key) -> session
+pub const SESSIONS: Map<(&Addr, &Addr), Session> = Map::new("sessions");
+
+// Owner-configured limits on outgoing movements (transfers, sends, burns, withdrawals)
+#[cw_serde]
+pub struct TransferGuard {
+    pub max_per_tx: Option<Uint128>,
+    pub daily_max: Option<Uint128>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct DailyOutflow {
+    /// Days since unix epoch (block time / 86400)
+    pub day: u64,
+    pub amount: Uint128,
+}
+
+// Next expected TransferBySig nonce per owner
+pub const TRANSFER_NONCES: Map<&Addr, u64> = Map::new("transfer_nonces");
+
+pub const TRANSFER_GUARDS: Map<&Addr, TransferGuard> = Map::new("transfer_guards");
+pub const DAILY_OUTFLOW: Map<&Addr, DailyOutflow> = Map::new("daily_outflow");
+
+// Bridge deposit ids already minted through MintBatch -> block height they were minted at
+pub const PROCESSED_DEPOSITS: Map<&str, u64> = Map::new("processed_deposits");
+
+// One privileged action. `hash` is sha256(previous hash ‖ JSON of this entry with an empty
+// hash), chaining the log so any rewrite of earlier entries is detectable.
+#[cw_serde]
+pub struct AuditEntry {
+    pub id: u64,
+    pub actor: String,
+    pub action: String,
+    pub height: u64,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub hash: Binary,
+}
+
+// Append-only audit log of privileged actions
+pub const AUDIT_LOG: Map<u64, AuditEntry> = Map::new("audit_log");
+pub const NEXT_AUDIT_ID: Item<u64> = Item::new("next_audit_id");
+
+// Lockup registry consulted before outgoing transfers; absent when restrictions are off
+#[cw_serde]
+pub struct LockupRegistryConfig {
+    pub contract: Addr,
+    // Locked amounts are reused for this many blocks to bound cross-contract query gas (0 = no cache)
+    pub cache_blocks: u64,
+}
+
+pub const LOCKUP_REGISTRY: Item<LockupRegistryConfig> = Item::new("lockup_registry");
+
+// Relayer bond contract Mint and MintBatch senders must be bonded in; absent when not required
+pub const RELAYER_BOND: Item<Addr> = Item::new("relayer_bond");
+
+#[cw_serde]
+pub struct CachedLockup {
+    // Registry the amount came from; entries from a replaced registry are ignored
+    pub registry: Addr,
+    pub locked: Uint128,
+    pub queried_at_height: u64,
+}
+
+pub const LOCKUP_CACHE: Map<&Addr, CachedLockup> = Map::new("lockup_cache");
+
+// Type URLs ForwardModuleMsg may emit; managed by governance through sudo
+pub const FORWARD_ALLOWLIST: Map<&str, ()> = Map::new("forward_allowlist");
+
+// Present from instantiate until the first execute message other than ImportBalances
+pub const BALANCE_IMPORT_OPEN: Item<()> = Item::new("balance_import_open");
+
+// Present while MintBatch acknowledges each minted deposit back to the inference module
+pub const DEPOSIT_ACKS_ENABLED: Item<()> = Item::new("deposit_acks_enabled");
+
+// Supply debited from holders for bridge withdrawals but not burned yet
+pub const PENDING_WITHDRAWAL_SUPPLY: Item<Uint128> = Item::new("pending_withdrawal_supply");
+
+// Withdrawals below `threshold` are queued per destination and bridged in aggregate by
+// FlushWithdrawals; absent when batching is off
+#[cw_serde]
+pub struct WithdrawalBatching {
+    pub threshold: Uint128,
+}
+
+pub const WITHDRAWAL_BATCHING: Item<WithdrawalBatching> = Item::new("withdrawal_batching");
+// Official bridge relayers. Their withdrawals take the priority lane (never batched) and are
+// exempt from withdrawal fees and rate limits.
+pub const EXEMPT_RELAYERS: Map<&Addr, ()> = Map::new("exempt_relayers");
+// Normalized external addresses Withdraw rejects unless forced, set by governance
+pub const BLOCKED_DESTINATIONS: Map<&str, ()> = Map::new("blocked_destinations");
+
+// Withdrawal destinations are also checked against the chain's sanctions list; absent when off
+#[cw_serde]
+pub struct SanctionsSync {
+    // Answers are reused for this many blocks to bound gRPC gas (0 = no cache)
+    pub cache_blocks: u64,
+}
+
+pub const SANCTIONS_SYNC: Item<SanctionsSync> = Item::new("sanctions_sync");
+
+#[cw_serde]
+pub struct CachedSanction {
+    pub sanctioned: bool,
+    pub queried_at_height: u64,
+}
+
+// Normalized external address -> the chain's last answer for it
+pub const SANCTIONS_CACHE: Map<&str, CachedSanction> = Map::new("sanctions_cache");
+// What a plain transfer to the contract's own address does. Tokens held by the contract cannot
+// be moved on by anyone but governance, so by default such transfers are rejected.
+#[cw_serde]
+#[derive(Default)]
+pub enum SelfTransferPolicy {
+    #[default]
+    Reject,
+    /// Let the transfer through, then send the tokens straight back to the account they came
+    /// from with a `self_transfer_returned` event
+    Return,
+}
+
+pub const SELF_TRANSFER_POLICY: Item<SelfTransferPolicy> = Item::new("self_transfer_policy");
+// Successor proposed by ProposeAdmin; the admin changes once it sends AcceptAdmin
+pub const PENDING_ADMIN: Item<PendingOwner> = Item::new("pending_admin");
+// Lowercased destination address -> queued amount awaiting a flush
+pub const PENDING_WITHDRAWALS: Map<&str, Uint128> = Map::new("pending_withdrawals");
+// (destination, sender) -> the part of the destination's queued amount the sender requested, so
+// the flushed withdrawal stays attributed to the accounts behind it
+pub const PENDING_WITHDRAWAL_SENDERS: Map<(&str, &Addr), Uint128> = Map::new("pending_withdrawal_senders");
+
+// Queued withdrawals may pay a priority fee, in this token, to `fee_collector`; absent when off
+#[cw_serde]
+pub struct PriorityFees {
+    pub fee_collector: Addr,
+}
+
+pub const PRIORITY_FEES: Item<PriorityFees> = Item::new("priority_fees");
+// Lowercased destination address -> priority fees paid by its queued withdrawals
+pub const PENDING_PRIORITY_FEES: Map<&str, Uint128> = Map::new("pending_priority_fees");
+// (total priority fee, destination) of queued destinations that paid one; FlushWithdrawals
+// takes these highest fee first, before the fee-less destinations
+pub const WITHDRAWAL_PRIORITY: Map<(u128, &str), ()> = Map::new("withdrawal_priority");
+
+// Opening balance of an account in each block it changed in: (account, height) -> balance
+// at the start of that block. Accounts without a checkpoint at or after a height still hold
+// their current balance.
+pub const BALANCE_CHECKPOINTS: Map<(&Addr, u64), Uint128> = Map::new("balance_checkpoints");
+// The same checkpoints by (height, account), so those past the retention window can be pruned
+// oldest first
+pub const CHECKPOINT_HEIGHTS: Map<(u64, &Addr), ()> = Map::new("checkpoint_heights");
+// First height whose balances checkpoints can reconstruct; HoldersAt also stops at the
+// retention window once it is further back than that
+pub const SNAPSHOTS_SINCE: Item<u64> = Item::new("snapshots_since");
+
+#[cw_serde]
+pub struct TravelRule {
+    pub threshold: Uint128,
+}
+
+pub const TRAVEL_RULE: Item<TravelRule> = Item::new("travel_rule");
+
+// How a destination chain reads the bridge memo
+#[cw_serde]
+pub enum MemoEncoding {
+    /// Printable ASCII
+    Text,
+    /// Hex digits, with or without a 0x prefix
+    Hex,
+}
+
+// Formatting rules for the Withdraw payload on one destination chain
+#[cw_serde]
+pub struct MemoRule {
+    /// Withdrawals to the chain must carry a payload
+    pub required: bool,
+    /// Longest payload the chain accepts, in bytes
+    pub max_len: u32,
+    pub encoding: MemoEncoding,
+}
+
+// Chain id -> memo rule. Withdrawals to a chain without a rule cannot carry a payload.
+pub const MEMO_RULES: Map<&str, MemoRule> = Map::new("memo_rules");
+
+// Volume ceiling for one flow per window; exceeding either bound trips the breaker
+#[cw_serde]
+pub struct VolumeLimit {
+    /// Absolute amount per window
+    pub max_amount: Option<Uint128>,
+    /// Share of total supply per window, in basis points
+    pub max_supply_bps: Option<u16>,
+}
+
+#[cw_serde]
+pub struct CircuitBreaker {
+    /// Volume is summed over fixed windows of this many blocks
+    pub window_blocks: u64,
+    pub mint: Option<VolumeLimit>,
+    pub withdraw: Option<VolumeLimit>,
+}
+
+pub const CIRCUIT_BREAKER: Item<CircuitBreaker> = Item::new("circuit_breaker");
+// (flow, window) -> volume in that window; only the current window of each flow is kept
+pub const FLOW_VOLUME: Map<(&str, u64), Uint128> = Map::new("flow_volume");
+// Halted flow -> height it tripped at; stays halted until the admin or governance resumes it
+pub const HALTED_FLOWS: Map<&str, u64> = Map::new("halted_flows");
+
+// Metadata override written by UpdateMetadata before it wrote through to cw20-base; cleared
+// on the next update
 #[cw_serde]
 pub struct TokenMetadataOverride {
     pub name: String,
@@ -46,6 +306,54 @@ pub struct TokenMetadataOverride {
 
 pub const TOKEN_METADATA: Item<TokenMetadataOverride> = Item::new("token_metadata");
 
+// Latest tolerated gRPC failure per query path, for diagnosing bridge integration
+#[cw_serde]
+pub struct GrpcFailure {
+    pub kind: String,
+    pub message: String,
+    pub height: u64,
+}
+
+pub const GRPC_FAILURES: Map<&str, GrpcFailure> = Map::new("grpc_failures");
+
+// Withdrawals requested on the chain, summed per inference epoch
+#[cw_serde]
+#[derive(Default)]
+pub struct WithdrawalTotals {
+    pub amount: Uint128,
+    /// Bridge withdrawal requests; a flushed destination is one request
+    pub count: u64,
+}
+
+// Inference epoch -> withdrawals requested during it
+pub const EPOCH_WITHDRAWALS: Map<u64, WithdrawalTotals> = Map::new("epoch_withdrawals");
+// Withdrawals requested while the chain could not report the epoch
+pub const UNATTRIBUTED_WITHDRAWALS: Item<WithdrawalTotals> = Item::new("unattributed_withdrawals");
+
+// StateRoot commitment built by CommitStateRoot, one chunk of entries per call
+#[cw_serde]
+pub struct StateRootProgress {
+    /// Index into `StateSection::ALL` of the section being folded
+    pub section: u8,
+    pub start_after: Option<String>,
+    pub hash: Binary,
+    pub entries: u64,
+}
+
+// A commitment in progress; any other execute changes state under it, so it is dropped
+pub const STATE_ROOT_PROGRESS: Item<StateRootProgress> = Item::new("state_root_progress");
+
+#[cw_serde]
+pub struct StateRootCommit {
+    pub root: Binary,
+    pub entries: u64,
+    /// Height of the call that finished the commitment
+    pub height: u64,
+}
+
+// Latest finished commitment, served by the StateRoot query
+pub const STATE_ROOT: Item<StateRootCommit> = Item::new("state_root");
+
 #[cw_serde]
 pub struct AllowanceResponse {
     pub allowance: Uint128,